@@ -0,0 +1,176 @@
+//! Measures the heap allocations and time spent decoding and validating a
+//! large map-heavy CBOR data item
+//!
+//! ```sh
+//! cargo bench --bench cbor_validation
+//! ```
+
+use std::{
+  alloc::{GlobalAlloc, Layout, System},
+  sync::atomic::{AtomicUsize, Ordering},
+  time::{Duration, Instant},
+};
+
+use cddl::{
+  cddl_from_str,
+  validator::{cbor::CBORValidator, Validator},
+};
+use ciborium::value::Value;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+    System.alloc(layout)
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    System.dealloc(ptr, layout)
+  }
+
+  unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+    System.realloc(ptr, layout, new_size)
+  }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const CDDL: &str = r#"
+ledger = {
+  accounts: [* account],
+  ? metadata: { * tstr => tstr },
+}
+
+account = {
+  id: tstr,
+  owner: bstr .size 32,
+  balance: uint,
+  ? tags: [* tstr],
+  history: [* entry],
+}
+
+entry = {
+  kind: "credit" / "debit",
+  amount: uint,
+  memo: tstr,
+}
+"#;
+
+const ACCOUNTS: usize = 500;
+const ENTRIES_PER_ACCOUNT: usize = 20;
+const ITERATIONS: usize = 10;
+
+fn text(s: &str) -> Value {
+  Value::Text(s.to_string())
+}
+
+fn ledger() -> Value {
+  let accounts = (0..ACCOUNTS)
+    .map(|i| {
+      let history = (0..ENTRIES_PER_ACCOUNT)
+        .map(|j| {
+          Value::Map(vec![
+            (
+              text("kind"),
+              text(if j % 2 == 0 { "credit" } else { "debit" }),
+            ),
+            (text("amount"), Value::Integer((i * j).into())),
+            (
+              text("memo"),
+              text(&format!("transfer {} of account {}", j, i)),
+            ),
+          ])
+        })
+        .collect();
+
+      Value::Map(vec![
+        (text("id"), text(&format!("account-{:05}", i))),
+        (text("owner"), Value::Bytes(vec![(i % 256) as u8; 32])),
+        (text("balance"), Value::Integer((i * 1000).into())),
+        (
+          text("tags"),
+          Value::Array(vec![text("retail"), text(&format!("region-{}", i % 8))]),
+        ),
+        (text("history"), Value::Array(history)),
+      ])
+    })
+    .collect();
+
+  let metadata = (0..50)
+    .map(|i| (text(&format!("key-{}", i)), text(&format!("value-{}", i))))
+    .collect();
+
+  Value::Map(vec![
+    (text("accounts"), Value::Array(accounts)),
+    (text("metadata"), Value::Map(metadata)),
+  ])
+}
+
+// Run the given phase, returning the number of allocations, bytes allocated
+// and time spent per iteration
+fn measure<F: FnMut()>(mut f: F) -> (usize, usize, Duration) {
+  let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+  let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+  let start = Instant::now();
+
+  for _ in 0..ITERATIONS {
+    f();
+  }
+
+  let elapsed = start.elapsed();
+
+  (
+    (ALLOCATIONS.load(Ordering::Relaxed) - allocations) / ITERATIONS,
+    (ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes) / ITERATIONS,
+    elapsed / ITERATIONS as u32,
+  )
+}
+
+fn report(phase: &str, (allocations, bytes, elapsed): (usize, usize, Duration)) {
+  println!(
+    "{:<10} {:>12} allocations {:>14} bytes {:>12.3?}",
+    phase, allocations, bytes, elapsed
+  );
+}
+
+fn main() {
+  let cddl = cddl_from_str(CDDL, true).unwrap();
+
+  let mut cbor = Vec::new();
+  ciborium::ser::into_writer(&ledger(), &mut cbor).unwrap();
+  println!(
+    "{} accounts with {} history entries each, {} bytes of CBOR",
+    ACCOUNTS,
+    ENTRIES_PER_ACCOUNT,
+    cbor.len()
+  );
+
+  report(
+    "decode",
+    measure(|| {
+      let value: Value = ciborium::de::from_reader(cbor.as_slice()).unwrap();
+      std::hint::black_box(value);
+    }),
+  );
+
+  // The validator takes ownership of the decoded data item, so copies are
+  // made up front to leave them out of the measurement
+  let value: Value = ciborium::de::from_reader(cbor.as_slice()).unwrap();
+  let mut values = vec![value; ITERATIONS];
+  report(
+    "validate",
+    measure(|| {
+      let mut cv = CBORValidator::new(&cddl, values.pop().unwrap(), None);
+      cv.set_cbor_slice(&cbor);
+      cv.validate().unwrap();
+    }),
+  );
+}
@@ -0,0 +1,1356 @@
+#![cfg(feature = "std")]
+
+use crate::{
+  ast::*,
+  token::{ByteValue, Value},
+};
+
+use displaydoc::Display;
+use itertools::Itertools;
+use std::convert::TryFrom;
+
+/// Alias for `Result` with an error of type `analysis::Error`
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Analysis error
+#[derive(Debug, Display, Clone, PartialEq)]
+pub enum Error {
+  /// rule {0} is not defined
+  UndefinedRule(String),
+  /// rule {0} is recursive
+  Recursive(String),
+  /// {0} is not supported
+  Unsupported(String),
+  /// subtyping can't be decided: {0}
+  Undecidable(String),
+}
+
+impl std::error::Error for Error {}
+
+// Maximum number of alternatives a group is expanded into
+const MAX_ALTERNATIVES: usize = 256;
+
+/// Whether every data item matching rule `a` of the given CDDL document also
+/// matches rule `b`, e.g. to verify that messages of a new revision of a
+/// protocol remain acceptable to consumers of the previous revision
+///
+/// Subtyping is decided for documents without recursive rules, generic rules
+/// or unwrapped groups. Control operators other than `.default`, `.eq`, `.lt`,
+/// `.le`, `.gt`, `.ge` and `.size` (on unsigned integers) are only compared by
+/// their text. An error is returned when subtyping can't be decided, e.g.
+/// when a type is only covered by several choices together, or by arrays
+/// whose optional or repeated entries are laid out differently
+///
+/// # Example
+///
+/// ```
+/// use cddl::{analysis::is_subtype, cddl_from_str};
+///
+/// let cddl = cddl_from_str(
+///   r#"
+///     v1 = { id: uint, ? name: tstr, * tstr => any }
+///     v2 = { id: 0..1000, name: tstr, tags: [* tstr] }
+///   "#,
+///   true,
+/// )
+/// .unwrap();
+///
+/// assert_eq!(is_subtype(&cddl, "v2", "v1"), Ok(true));
+/// assert_eq!(is_subtype(&cddl, "v1", "v2"), Ok(false));
+/// ```
+pub fn is_subtype(cddl: &CDDL, a: &str, b: &str) -> Result<bool> {
+  is_subtype_across(cddl, a, cddl, b)
+}
+
+/// Whether every data item matching rule `a` of the CDDL document `a_cddl`
+/// also matches rule `b` of the CDDL document `b_cddl`, e.g. of two revisions
+/// of the same document. See [`is_subtype`]
+pub fn is_subtype_across(a_cddl: &CDDL, a: &str, b_cddl: &CDDL, b: &str) -> Result<bool> {
+  let a = Resolver::new(a_cddl).rule_type(a)?;
+  let b = Resolver::new(b_cddl).rule_type(b)?;
+
+  choices_subtype(&a, &b)
+}
+
+// Whether every data item matching one of the type choices `a` of the CDDL
+// document `a_cddl` also matches one of the type choices `b` of `b_cddl`, e.g.
+// of a member of a map in two revisions of the same document
+pub(crate) fn type_choices_subtype_across(
+  a_cddl: &CDDL,
+  a: &[&Type1],
+  b_cddl: &CDDL,
+  b: &[&Type1],
+) -> Result<bool> {
+  let a = Resolver::new(a_cddl).type_choices(a)?;
+  let b = Resolver::new(b_cddl).type_choices(b)?;
+
+  choices_subtype(&a, &b)
+}
+
+/// Map type of a rule whose entries are keyed by integers, as in COSE and CWT
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntKeyedMap {
+  /// Name of the rule
+  pub rule: String,
+  /// Integer keys of the entries with a single key, in the order they're
+  /// defined
+  pub keys: Vec<i128>,
+  /// Inclusive ranges of the further integer keys accepted by entries keyed
+  /// by a type, e.g. `* int => any` or `* (-65536..-257) => any`
+  pub extensions: Vec<(i128, i128)>,
+}
+
+impl IntKeyedMap {
+  /// Whether integer keys other than those of [`IntKeyedMap::keys`] are
+  /// rejected. Maps are closed unless an entry keyed by a type admitting
+  /// integers, such as `* int => any`, explicitly makes them extensible
+  pub fn is_closed(&self) -> bool {
+    self
+      .extensions
+      .iter()
+      .all(|(lower, upper)| (*lower..=*upper).all(|k| self.keys.contains(&k)))
+  }
+}
+
+/// Infer the key domain of each rule of the given CDDL document that defines
+/// a map keyed by integers. A map is keyed by integers if at least one of its
+/// entries has an integer value as its key and none has a text value as its
+/// key. Rules that can't be analyzed, e.g. recursive or generic rules, are
+/// skipped
+///
+/// # Example
+///
+/// ```
+/// use cddl::{analysis::int_keyed_maps, cddl_from_str};
+///
+/// let cddl = cddl_from_str(
+///   r#"
+///     header = { ? 1 => int / tstr, ? 4 => bstr }
+///     claims = { ? 1 => tstr, ? 6 => int, * label => any }
+///     label = int / tstr
+///   "#,
+///   true,
+/// )
+/// .unwrap();
+///
+/// let maps = int_keyed_maps(&cddl);
+/// assert_eq!(maps[0].keys, vec![1, 4]);
+/// assert!(maps[0].is_closed());
+/// assert!(!maps[1].is_closed());
+/// ```
+pub fn int_keyed_maps(cddl: &CDDL) -> Vec<IntKeyedMap> {
+  let mut maps: Vec<IntKeyedMap> = Vec::new();
+
+  for rule in cddl.rules.iter() {
+    let name = match rule {
+      Rule::Type { rule, .. } if rule.generic_params.is_none() => rule.name.to_string(),
+      _ => continue,
+    };
+    if maps.iter().any(|m| m.rule == name) {
+      continue;
+    }
+
+    let shapes = match Resolver::new(cddl).rule_type(&name) {
+      Ok(shapes) => shapes,
+      Err(_) => continue,
+    };
+
+    let mut map = IntKeyedMap {
+      rule: name,
+      keys: Vec::new(),
+      extensions: Vec::new(),
+    };
+    let mut has_text_key = false;
+    let entries = shapes.iter().flat_map(|s| match s {
+      Shape::Map(alternatives) => alternatives.iter().flatten().collect(),
+      _ => Vec::new(),
+    });
+    for entry in entries {
+      for key in entry.key.iter().flatten() {
+        match key {
+          Shape::Int(lower, upper) if lower == upper && entry.max == 1 => map.keys.push(*lower),
+          Shape::Int(lower, upper) => map.extensions.push((*lower, *upper)),
+          Shape::Any => map.extensions.push((i128::MIN, i128::MAX)),
+          Shape::Text(Some(_)) => has_text_key = true,
+          _ => (),
+        }
+      }
+    }
+
+    if !map.keys.is_empty() && !has_text_key {
+      map.keys = map.keys.into_iter().unique().collect();
+      maps.push(map);
+    }
+  }
+
+  maps
+}
+
+// Reason no value satisfies a type restricted by a range or control operator,
+// if it can be decided. Named types are resolved, so constraints contradicting
+// those of the rules they restrict are caught
+pub(crate) fn unsatisfiable_constraint(cddl: &CDDL, t1: &Type1) -> Option<String> {
+  let operator = t1.operator.as_ref()?;
+  let mut resolver = Resolver::new(cddl);
+
+  match &operator.operator {
+    RangeCtlOp::RangeOp { is_inclusive, .. } => {
+      let lower = resolver.literal(&t1.type2).ok()?;
+      let upper = resolver.literal(&operator.type2).ok()?;
+      let is_empty = match (&lower, &upper) {
+        (Shape::Int(l, _), Shape::Int(u, _)) => l > u || (l == u && !is_inclusive),
+        (Shape::Float(l, _), Shape::Float(u, _)) => l > u || (l == u && !is_inclusive),
+        _ => false,
+      };
+
+      is_empty.then(|| {
+        format!(
+          "its lower bound {} exceeds its upper bound {}",
+          domain(&[lower]),
+          domain(&[upper])
+        )
+      })
+    }
+    RangeCtlOp::CtlOp { ctrl, .. } => {
+      let target = resolver.type2(&t1.type2).ok()?;
+      if target.is_empty() {
+        return None;
+      }
+
+      match ctrl.as_ref() {
+        ".default" | ".eq" => {
+          let value = match resolver.type2(&operator.type2).ok()?.as_slice() {
+            [s @ Shape::Int(lower, upper)] if lower == upper => s.clone(),
+            [s @ Shape::Float(lower, upper)] if lower == upper => s.clone(),
+            [s @ Shape::Text(Some(_))] | [s @ Shape::Bool(Some(_))] => s.clone(),
+            _ => return None,
+          };
+          let is_default = *ctrl == ".default";
+          (shape_subtype(&value, &target) == Ok(false)).then(|| {
+            format!(
+              "{}{} is not a value of {}, which ranges over {}",
+              if is_default { "its default value " } else { "" },
+              domain(&[value]),
+              t1.type2,
+              domain(&target)
+            )
+          })
+        }
+        ".lt" | ".le" | ".gt" | ".ge" | ".size" => {
+          let shapes = resolver.type1(t1).ok()?;
+          let is_int = target.iter().all(|s| matches!(s, Shape::Int(..)));
+
+          (is_int && shapes.is_empty())
+            .then(|| format!("{} only ranges over {}", t1.type2, domain(&target)))
+        }
+        _ => None,
+      }
+    }
+  }
+}
+
+// Text of the numeric ranges and values of a type
+fn domain(shapes: &[Shape]) -> String {
+  shapes
+    .iter()
+    .map(|s| match s {
+      Shape::Int(lower, upper) if lower == upper => lower.to_string(),
+      Shape::Int(lower, upper) => format!("{}..{}", lower, upper),
+      Shape::Float(lower, upper) if lower == upper => format!("{:?}", lower),
+      Shape::Float(lower, upper) => format!("{:?}..{:?}", lower, upper),
+      Shape::Bool(Some(b)) => b.to_string(),
+      Shape::Text(Some(t)) => format!("\"{}\"", t),
+      Shape::Text(None) => "text strings".to_string(),
+      Shape::Bytes(_) => "byte strings".to_string(),
+      _ => "other values".to_string(),
+    })
+    .unique()
+    .join(" / ")
+}
+
+// Normalized choice of a type
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+  Any,
+  Bool(Option<bool>),
+  Null,
+  Undefined,
+  // Inclusive bounds
+  Int(i128, i128),
+  Float(f64, f64),
+  Text(Option<String>),
+  Bytes(Option<Vec<u8>>),
+  // Alternatives of the group of the array
+  Array(Vec<Vec<Entry>>),
+  // Alternatives of the group of the map
+  Map(Vec<Vec<Entry>>),
+  // Tag number, if not any, and tagged type
+  Tag(Option<u64>, Vec<Shape>),
+  // Text or byte strings whose size in bytes is within the inclusive bounds
+  Sized(Box<Shape>, usize, usize),
+  // Type restricted by a control operator that isn't analyzed, identified by
+  // its text
+  Constrained(String, Vec<Shape>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+  min: usize,
+  // usize::MAX if unbounded
+  max: usize,
+  // Only set for map entries
+  key: Option<Vec<Shape>>,
+  value: Vec<Shape>,
+}
+
+const UINT: Shape = Shape::Int(0, u64::MAX as i128);
+const NINT: Shape = Shape::Int(-(u64::MAX as i128) - 1, -1);
+const INT: Shape = Shape::Int(-(u64::MAX as i128) - 1, u64::MAX as i128);
+const FLOAT: Shape = Shape::Float(f64::NEG_INFINITY, f64::INFINITY);
+
+fn prelude_type(name: &str) -> Option<Vec<Shape>> {
+  let tag = |tag, t: Vec<Shape>| Shape::Tag(Some(tag), t);
+  let biguint = tag(2, vec![Shape::Bytes(None)]);
+  let bignint = tag(3, vec![Shape::Bytes(None)]);
+  let fraction = Shape::Array(vec![vec![
+    entry(vec![INT]),
+    entry(vec![INT, biguint.clone(), bignint.clone()]),
+  ]]);
+
+  let shapes = match name {
+    "any" => vec![Shape::Any],
+    "uint" => vec![UINT],
+    "nint" => vec![NINT],
+    "int" => vec![INT],
+    "bool" => vec![Shape::Bool(None)],
+    "true" => vec![Shape::Bool(Some(true))],
+    "false" => vec![Shape::Bool(Some(false))],
+    "nil" | "null" => vec![Shape::Null],
+    "undefined" => vec![Shape::Undefined],
+    "float" | "float16" | "float32" | "float64" | "float16-32" | "float32-64" => vec![FLOAT],
+    "number" => vec![INT, FLOAT],
+    "tstr" | "text" => vec![Shape::Text(None)],
+    "bstr" | "bytes" => vec![Shape::Bytes(None)],
+    "tdate" => vec![tag(0, vec![Shape::Text(None)])],
+    "time" => vec![tag(1, vec![INT, FLOAT])],
+    "biguint" => vec![biguint],
+    "bignint" => vec![bignint],
+    "bigint" => vec![biguint, bignint],
+    "integer" => vec![INT, biguint, bignint],
+    "unsigned" => vec![UINT, biguint],
+    "decfrac" => vec![tag(4, vec![fraction])],
+    "bigfloat" => vec![tag(5, vec![fraction])],
+    "eb64url" => vec![tag(21, vec![Shape::Any])],
+    "eb64legacy" => vec![tag(22, vec![Shape::Any])],
+    "eb16" => vec![tag(23, vec![Shape::Any])],
+    "encoded-cbor" => vec![tag(24, vec![Shape::Bytes(None)])],
+    "uri" => vec![tag(32, vec![Shape::Text(None)])],
+    "b64url" => vec![tag(33, vec![Shape::Text(None)])],
+    "b64legacy" => vec![tag(34, vec![Shape::Text(None)])],
+    "regexp" => vec![tag(35, vec![Shape::Text(None)])],
+    "mime-message" => vec![tag(36, vec![Shape::Text(None)])],
+    "cbor-any" => vec![tag(55799, vec![Shape::Any])],
+    _ => return None,
+  };
+
+  Some(shapes)
+}
+
+fn entry(value: Vec<Shape>) -> Entry {
+  Entry {
+    min: 1,
+    max: 1,
+    key: None,
+    value,
+  }
+}
+
+// Resolves the rules of a document into shapes
+struct Resolver<'c, 'a> {
+  cddl: &'c CDDL<'a>,
+  // Rules being resolved
+  stack: Vec<String>,
+}
+
+impl<'c, 'a> Resolver<'c, 'a> {
+  fn new(cddl: &'c CDDL<'a>) -> Self {
+    Resolver {
+      cddl,
+      stack: Vec::new(),
+    }
+  }
+
+  fn enter(&mut self, name: &str, is_generic: bool) -> Result<()> {
+    if is_generic {
+      return Err(Error::Unsupported(format!("generic rule {}", name)));
+    }
+    if self.stack.iter().any(|r| r == name) {
+      return Err(Error::Recursive(name.to_string()));
+    }
+
+    self.stack.push(name.to_string());
+
+    Ok(())
+  }
+
+  fn rule_type(&mut self, name: &str) -> Result<Vec<Shape>> {
+    let rules = self
+      .cddl
+      .rules
+      .iter()
+      .filter_map(|r| match r {
+        Rule::Type { rule, .. } if rule.name.to_string() == name => Some(rule),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+
+    if rules.is_empty() {
+      let is_group = self
+        .cddl
+        .rules
+        .iter()
+        .any(|r| matches!(r, Rule::Group { rule, .. } if rule.name.to_string() == name));
+      if is_group {
+        return Err(Error::Unsupported(format!(
+          "group rule {} used as a type",
+          name
+        )));
+      }
+
+      return prelude_type(name).ok_or_else(|| Error::UndefinedRule(name.to_string()));
+    }
+
+    self.enter(name, rules.iter().any(|r| r.generic_params.is_some()))?;
+    let mut shapes = Vec::new();
+    for rule in rules {
+      shapes.extend(self.type_(&rule.value)?);
+    }
+    self.stack.pop();
+
+    Ok(shapes)
+  }
+
+  // Alternatives of the named group, if defined
+  fn group_rule(&mut self, name: &str, is_map: bool) -> Result<Option<Vec<Vec<Entry>>>> {
+    let rules = self
+      .cddl
+      .rules
+      .iter()
+      .filter_map(|r| match r {
+        Rule::Group { rule, .. } if rule.name.to_string() == name => Some(rule),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+
+    if rules.is_empty() {
+      return Ok(None);
+    }
+
+    self.enter(name, rules.iter().any(|r| r.generic_params.is_some()))?;
+    let mut alternatives = Vec::new();
+    for rule in rules {
+      alternatives.extend(self.group_entry(&rule.entry, is_map)?);
+    }
+    self.stack.pop();
+
+    Ok(Some(alternatives))
+  }
+
+  fn type_(&mut self, t: &Type) -> Result<Vec<Shape>> {
+    let mut shapes = Vec::new();
+    for tc in t.type_choices.iter() {
+      shapes.extend(self.type1(&tc.type1)?);
+    }
+
+    Ok(shapes)
+  }
+
+  fn type_choices(&mut self, choices: &[&Type1]) -> Result<Vec<Shape>> {
+    let mut shapes = Vec::new();
+    for t1 in choices.iter() {
+      shapes.extend(self.type1(t1)?);
+    }
+
+    Ok(shapes)
+  }
+
+  fn type1(&mut self, t1: &Type1) -> Result<Vec<Shape>> {
+    let operator = match &t1.operator {
+      Some(operator) => operator,
+      None => return self.type2(&t1.type2),
+    };
+
+    match &operator.operator {
+      RangeCtlOp::RangeOp { is_inclusive, .. } => {
+        match (self.literal(&t1.type2)?, self.literal(&operator.type2)?) {
+          (Shape::Int(lower, _), Shape::Int(upper, _)) => {
+            let upper = if *is_inclusive { upper } else { upper - 1 };
+            Ok(vec![Shape::Int(lower, upper)])
+          }
+          (Shape::Float(lower, _), Shape::Float(upper, _)) if *is_inclusive => {
+            Ok(vec![Shape::Float(lower, upper)])
+          }
+          _ => Err(Error::Unsupported(format!("range {}", t1))),
+        }
+      }
+      RangeCtlOp::CtlOp { ctrl, .. } => {
+        let target = self.type2(&t1.type2)?;
+        let controller = self.type2(&operator.type2)?;
+        let bound = match controller.as_slice() {
+          [Shape::Int(lower, upper)] if lower == upper => Some(*lower),
+          _ => None,
+        };
+        let is_int = target.iter().all(|s| matches!(s, Shape::Int(..)));
+        let is_string = target
+          .iter()
+          .all(|s| matches!(s, Shape::Text(None) | Shape::Bytes(None)));
+
+        // The size of a text or byte string is its exact length in bytes
+        if let (".size", [Shape::Int(lower, upper)]) = (ctrl.as_ref(), controller.as_slice()) {
+          if is_string && *lower >= 0 {
+            let size = |n: i128| usize::try_from(n).unwrap_or(usize::MAX);
+            return Ok(
+              target
+                .into_iter()
+                .map(|s| Shape::Sized(Box::new(s), size(*lower), size(*upper)))
+                .collect(),
+            );
+          }
+        }
+
+        let bounds = match (ctrl.as_ref(), bound) {
+          (".default", _) => return Ok(target),
+          (".eq", _) if controller.len() == 1 => return Ok(controller),
+          (".lt", Some(n)) if is_int => (i128::MIN, n - 1),
+          (".le", Some(n)) if is_int => (i128::MIN, n),
+          (".gt", Some(n)) if is_int => (n + 1, i128::MAX),
+          (".ge", Some(n)) if is_int => (n, i128::MAX),
+          (".size", Some(n)) if is_int && (0..16).contains(&n) => {
+            (i128::MIN, 256i128.pow(n as u32) - 1)
+          }
+          _ => {
+            let text = t1.to_string();
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            return Ok(vec![Shape::Constrained(text, target)]);
+          }
+        };
+
+        Ok(
+          target
+            .into_iter()
+            .filter_map(|s| match s {
+              Shape::Int(lower, upper) => {
+                let (lower, upper) = (lower.max(bounds.0), upper.min(bounds.1));
+                (lower <= upper).then_some(Shape::Int(lower, upper))
+              }
+              s => Some(s),
+            })
+            .collect(),
+        )
+      }
+    }
+  }
+
+  // Single literal value of a range bound
+  fn literal(&mut self, t2: &Type2) -> Result<Shape> {
+    match self.type2(t2)?.as_slice() {
+      [s @ Shape::Int(lower, upper)] if lower == upper => Ok(s.clone()),
+      [s @ Shape::Float(lower, upper)] if lower == upper => Ok(s.clone()),
+      _ => Err(Error::Unsupported(format!("range bound {}", t2))),
+    }
+  }
+
+  fn type2(&mut self, t2: &Type2) -> Result<Vec<Shape>> {
+    let shape = match t2 {
+      Type2::IntValue { value, .. } => Shape::Int(*value as i128, *value as i128),
+      Type2::UintValue { value, .. } => Shape::Int(*value as i128, *value as i128),
+      Type2::FloatValue { value, .. } => Shape::Float(*value, *value),
+      Type2::TextValue { value, .. } => Shape::Text(Some(value.to_string())),
+      Type2::UTF8ByteString { value, .. } => Shape::Bytes(Some(value.to_vec())),
+      Type2::B16ByteString { .. } | Type2::B64ByteString { .. } => {
+        Shape::Constrained(t2.to_string(), vec![Shape::Bytes(None)])
+      }
+      Type2::Typename {
+        ident,
+        generic_args: None,
+        ..
+      } => return self.rule_type(&ident.to_string()),
+      Type2::ParenthesizedType { pt, .. } => return self.type_(pt),
+      Type2::Map { group, .. } => Shape::Map(self.group(group, true)?),
+      Type2::Array { group, .. } => Shape::Array(self.group(group, false)?),
+      Type2::ChoiceFromInlineGroup { group, .. } => {
+        return Ok(enumeration(self.group(group, true)?))
+      }
+      Type2::ChoiceFromGroup {
+        ident,
+        generic_args: None,
+        ..
+      } => match self.group_rule(&ident.to_string(), true)? {
+        Some(alternatives) => return Ok(enumeration(alternatives)),
+        None => return Err(Error::UndefinedRule(ident.to_string())),
+      },
+      Type2::TaggedData {
+        tag,
+        tag_type: None,
+        t,
+        ..
+      } => Shape::Tag(tag.map(|t| t as u64), self.type_(t)?),
+      Type2::TaggedData {
+        tag_type: Some(tag_type),
+        t,
+        ..
+      } => match self.type_(tag_type)?.as_slice() {
+        [Shape::Int(lower, upper)] if lower == upper && *lower >= 0 => {
+          Shape::Tag(Some(*lower as u64), self.type_(t)?)
+        }
+        _ => return Err(Error::Unsupported(format!("tag number {}", tag_type))),
+      },
+      Type2::DataMajorType { mt, constraint, .. } => {
+        let constraint = constraint.map(|c| c as i128);
+        match (mt, constraint) {
+          (0, Some(c)) => Shape::Int(c, c),
+          (0, None) => UINT,
+          (1, Some(c)) => Shape::Int(-1 - c, -1 - c),
+          (1, None) => NINT,
+          (2, None) => Shape::Bytes(None),
+          (3, None) => Shape::Text(None),
+          (6, tag) => Shape::Tag(tag.map(|t| t as u64), vec![Shape::Any]),
+          (7, Some(20)) => Shape::Bool(Some(false)),
+          (7, Some(21)) => Shape::Bool(Some(true)),
+          (7, Some(22)) => Shape::Null,
+          (7, Some(23)) => Shape::Undefined,
+          _ => return Err(Error::Unsupported(format!("data item {}", t2))),
+        }
+      }
+      Type2::Any { .. } => Shape::Any,
+      Type2::Typename { .. } | Type2::ChoiceFromGroup { .. } => {
+        return Err(Error::Unsupported(format!("generic arguments of {}", t2)))
+      }
+      Type2::Unwrap { .. } => return Err(Error::Unsupported(format!("unwrapped group {}", t2))),
+    };
+
+    Ok(vec![shape])
+  }
+
+  fn group(&mut self, group: &Group, is_map: bool) -> Result<Vec<Vec<Entry>>> {
+    let mut alternatives = Vec::new();
+    for gc in group.group_choices.iter() {
+      let mut choice_alternatives = vec![Vec::new()];
+      for (ge, _) in gc.group_entries.iter() {
+        let entry_alternatives = self.group_entry(ge, is_map)?;
+
+        choice_alternatives = choice_alternatives
+          .iter()
+          .flat_map(|a| {
+            entry_alternatives.iter().map(move |e| {
+              let mut a = a.clone();
+              a.extend(e.iter().cloned());
+              a
+            })
+          })
+          .collect();
+
+        if choice_alternatives.len() > MAX_ALTERNATIVES {
+          return Err(Error::Unsupported(format!(
+            "group with more than {} alternatives",
+            MAX_ALTERNATIVES
+          )));
+        }
+      }
+
+      alternatives.append(&mut choice_alternatives);
+    }
+
+    Ok(alternatives)
+  }
+
+  fn group_entry(&mut self, ge: &GroupEntry, is_map: bool) -> Result<Vec<Vec<Entry>>> {
+    let (min, max) = occurrence(ge);
+
+    match ge {
+      GroupEntry::ValueMemberKey { ge: vmke, .. } => {
+        let key = match &vmke.member_key {
+          _ if !is_map => None,
+          Some(MemberKey::Bareword { ident, .. }) => {
+            Some(vec![Shape::Text(Some(ident.to_string()))])
+          }
+          Some(MemberKey::Value { value, .. }) => Some(vec![value_shape(value)]),
+          Some(MemberKey::Type1 { t1, .. }) => Some(self.type1(t1)?),
+          _ => return Err(Error::Unsupported(format!("map entry {}", vmke))),
+        };
+
+        Ok(vec![vec![Entry {
+          min,
+          max,
+          key,
+          value: self.type_(&vmke.entry_type)?,
+        }]])
+      }
+      GroupEntry::TypeGroupname { ge: tge, .. } => {
+        if tge.generic_args.is_some() {
+          return Err(Error::Unsupported(format!("generic arguments of {}", tge)));
+        }
+
+        match self.group_rule(&tge.name.to_string(), is_map)? {
+          Some(alternatives) => repeat(alternatives, min, max, ge),
+          None if is_map => Err(Error::Unsupported(format!("map entry {}", tge))),
+          None => Ok(vec![vec![Entry {
+            min,
+            max,
+            key: None,
+            value: self.rule_type(&tge.name.to_string())?,
+          }]]),
+        }
+      }
+      GroupEntry::InlineGroup { group, .. } => repeat(self.group(group, is_map)?, min, max, ge),
+    }
+  }
+}
+
+// Alternatives of a group with the given occurrence. Groups with several
+// entries can only occur once
+fn repeat(
+  alternatives: Vec<Vec<Entry>>,
+  min: usize,
+  max: usize,
+  ge: &GroupEntry,
+) -> Result<Vec<Vec<Entry>>> {
+  if (min, max) == (1, 1) {
+    return Ok(alternatives);
+  }
+
+  alternatives
+    .into_iter()
+    .map(|mut entries| match entries.as_mut_slice() {
+      [entry] if (entry.min, entry.max) == (1, 1) => {
+        entry.min = min;
+        entry.max = max;
+        Ok(entries)
+      }
+      _ => Err(Error::Unsupported(format!(
+        "occurrence indicator of group {}",
+        ge
+      ))),
+    })
+    .collect()
+}
+
+// Choices of an enumeration of the values of a group
+fn enumeration(alternatives: Vec<Vec<Entry>>) -> Vec<Shape> {
+  alternatives
+    .into_iter()
+    .flatten()
+    .flat_map(|e| e.value)
+    .collect()
+}
+
+fn value_shape(value: &Value) -> Shape {
+  match value {
+    Value::INT(i) => Shape::Int(*i as i128, *i as i128),
+    Value::UINT(u) => Shape::Int(*u as i128, *u as i128),
+    Value::FLOAT(f) => Shape::Float(*f, *f),
+    Value::TEXT(t) => Shape::Text(Some(t.to_string())),
+    Value::BYTE(ByteValue::UTF8(b)) => Shape::Bytes(Some(b.to_vec())),
+    Value::BYTE(_) => Shape::Constrained(value.to_string(), vec![Shape::Bytes(None)]),
+  }
+}
+
+fn occurrence(ge: &GroupEntry) -> (usize, usize) {
+  let occur = match ge {
+    GroupEntry::ValueMemberKey { ge, .. } => ge.occur.as_ref(),
+    GroupEntry::TypeGroupname { ge, .. } => ge.occur.as_ref(),
+    GroupEntry::InlineGroup { occur, .. } => occur.as_ref(),
+  };
+
+  match occur.map(|o| &o.occur) {
+    None => (1, 1),
+    Some(Occur::Optional { .. }) => (0, 1),
+    Some(Occur::ZeroOrMore { .. }) => (0, usize::MAX),
+    Some(Occur::OneOrMore { .. }) => (1, usize::MAX),
+    Some(Occur::Exact { lower, upper, .. }) => (lower.unwrap_or(0), upper.unwrap_or(usize::MAX)),
+  }
+}
+
+// Conjunction of verdicts, where a definite `false` takes precedence over an
+// undecidable verdict
+fn all(verdicts: impl IntoIterator<Item = Result<bool>>) -> Result<bool> {
+  let mut result = Ok(true);
+  for verdict in verdicts {
+    match verdict {
+      Ok(true) => (),
+      Ok(false) => return Ok(false),
+      Err(e) => {
+        if result.is_ok() {
+          result = Err(e);
+        }
+      }
+    }
+  }
+
+  result
+}
+
+// Verdict of a value of one of several candidates, where candidates that
+// definitely exclude the value are left out
+fn any_candidate(verdicts: Vec<(Result<bool>, bool)>) -> Result<bool> {
+  if verdicts.iter().any(|(v, _)| *v == Ok(true)) {
+    return Ok(true);
+  }
+
+  let mut overlapping = verdicts.into_iter().filter(|(_, excludes)| !excludes);
+  match (overlapping.next(), overlapping.next()) {
+    (None, _) => Ok(false),
+    (Some((verdict, _)), None) => verdict,
+    _ => Err(Error::Undecidable(
+      "values may only be covered by several choices together".to_string(),
+    )),
+  }
+}
+
+fn choices_subtype(a: &[Shape], b: &[Shape]) -> Result<bool> {
+  all(a.iter().map(|s| shape_subtype(s, b)))
+}
+
+fn shape_subtype(a: &Shape, b: &[Shape]) -> Result<bool> {
+  if b.contains(&Shape::Any) {
+    return Ok(true);
+  }
+
+  let verdict = match a {
+    Shape::Any => Ok(false),
+    Shape::Bool(value) => Ok(
+      b.contains(&Shape::Bool(None))
+        || match value {
+          Some(value) => b.contains(&Shape::Bool(Some(*value))),
+          None => b.contains(&Shape::Bool(Some(true))) && b.contains(&Shape::Bool(Some(false))),
+        },
+    ),
+    Shape::Null | Shape::Undefined => Ok(b.contains(a)),
+    Shape::Int(lower, upper) => {
+      let mut ranges = b
+        .iter()
+        .filter_map(|s| match s {
+          Shape::Int(lower, upper) => Some((*lower, *upper)),
+          _ => None,
+        })
+        .collect::<Vec<_>>();
+      ranges.sort_unstable();
+
+      // Ranges are merged until the upper bound is covered
+      let mut covered = *lower;
+      for (l, u) in ranges {
+        if l <= covered && u >= covered {
+          covered = u.saturating_add(1);
+        }
+      }
+
+      Ok(covered > *upper)
+    }
+    Shape::Float(lower, upper) => {
+      let mut ranges = b
+        .iter()
+        .filter_map(|s| match s {
+          Shape::Float(lower, upper) => Some((*lower, *upper)),
+          _ => None,
+        })
+        .collect::<Vec<_>>();
+      ranges.sort_by(|x, y| x.partial_cmp(y).unwrap_or(std::cmp::Ordering::Equal));
+
+      let mut covered = None;
+      for (l, u) in ranges {
+        match covered {
+          None if l <= *lower && u >= *lower => covered = Some(u),
+          Some(c) if l <= c && u > c => covered = Some(u),
+          _ => (),
+        }
+      }
+
+      Ok(covered.is_some_and(|c| c >= *upper))
+    }
+    Shape::Text(value) => Ok(
+      b.contains(&Shape::Text(None))
+        || (value.is_some() && b.contains(a))
+        || value
+          .as_ref()
+          .is_some_and(|v| size_matches(b, &Shape::Text(None), v.len())),
+    ),
+    Shape::Bytes(value) => Ok(
+      b.contains(&Shape::Bytes(None))
+        || (value.is_some() && b.contains(a))
+        || value
+          .as_ref()
+          .is_some_and(|v| size_matches(b, &Shape::Bytes(None), v.len())),
+    ),
+    Shape::Sized(base, lower, upper) => Ok(
+      b.contains(base) || {
+        b.iter().any(|s| {
+          matches!(s, Shape::Sized(b_base, b_lower, b_upper)
+          if b_base == base && b_lower <= lower && upper <= b_upper)
+        })
+      },
+    ),
+    Shape::Tag(tag, t) => any_candidate(
+      b.iter()
+        .filter_map(|s| match s {
+          Shape::Tag(b_tag, b_t) if b_tag.is_none() || b_tag == tag => {
+            Some((choices_subtype(t, b_t), choices_exclude(t, b_t)))
+          }
+          _ => None,
+        })
+        .collect(),
+    ),
+    Shape::Array(alternatives) => all(alternatives.iter().map(|a_entries| {
+      any_candidate(
+        b.iter()
+          .filter_map(|s| match s {
+            Shape::Array(b_alternatives) => Some(b_alternatives),
+            _ => None,
+          })
+          .flatten()
+          .map(|b_entries| {
+            (
+              array_subtype(a_entries, b_entries),
+              array_excludes(a_entries, b_entries),
+            )
+          })
+          .collect(),
+      )
+    })),
+    Shape::Map(alternatives) => all(alternatives.iter().map(|a_entries| {
+      any_candidate(
+        b.iter()
+          .filter_map(|s| match s {
+            Shape::Map(b_alternatives) => Some(b_alternatives),
+            _ => None,
+          })
+          .flatten()
+          .map(|b_entries| {
+            (
+              map_subtype(a_entries, b_entries),
+              map_excludes(a_entries, b_entries),
+            )
+          })
+          .collect(),
+      )
+    })),
+    Shape::Constrained(text, base) => {
+      // Types restricted by the same control are assumed to be equal
+      let is_same = b
+        .iter()
+        .any(|s| matches!(s, Shape::Constrained(b_text, _) if b_text == text));
+
+      if is_same || choices_subtype(base, b) == Ok(true) {
+        Ok(true)
+      } else {
+        Err(Error::Undecidable(format!("control in {}", text)))
+      }
+    }
+  };
+
+  // Values not covered otherwise may still be covered by a choice restricted
+  // by a control operator
+  match (verdict, constrained_overlap(a, b)) {
+    (Ok(false), Some(text)) => Err(Error::Undecidable(format!("control in {}", text))),
+    (verdict, _) => verdict,
+  }
+}
+
+// Whether a text or byte string of the given size matches a choice of b
+// restricted by `.size`
+fn size_matches(b: &[Shape], base: &Shape, size: usize) -> bool {
+  b.iter().any(|s| {
+    matches!(s, Shape::Sized(b_base, lower, upper)
+      if **b_base == *base && (*lower..=*upper).contains(&size))
+  })
+}
+
+fn constrained_overlap<'b>(a: &Shape, b: &'b [Shape]) -> Option<&'b str> {
+  b.iter().find_map(|s| match s {
+    Shape::Constrained(text, base) if !base.iter().all(|b| shape_excludes(a, b)) => {
+      Some(text.as_str())
+    }
+    _ => None,
+  })
+}
+
+// Whether no value of a can match b. Only definite exclusions are reported
+fn shape_excludes(a: &Shape, b: &Shape) -> bool {
+  match (a, b) {
+    (Shape::Any, _) | (_, Shape::Any) => false,
+    (_, Shape::Constrained(_, base)) => base.iter().all(|b| shape_excludes(a, b)),
+    (Shape::Constrained(_, base), b) => base.iter().all(|a| shape_excludes(a, b)),
+    (Shape::Sized(a_base, a_lower, a_upper), Shape::Sized(b_base, b_lower, b_upper)) => {
+      a_upper < b_lower || b_upper < a_lower || shape_excludes(a_base, b_base)
+    }
+    (Shape::Sized(base, ..), b) => shape_excludes(base, b),
+    (a, Shape::Sized(base, ..)) => shape_excludes(a, base),
+    (Shape::Bool(Some(x)), Shape::Bool(Some(y))) => x != y,
+    (Shape::Int(a_lower, a_upper), Shape::Int(b_lower, b_upper)) => {
+      a_upper < b_lower || b_upper < a_lower
+    }
+    (Shape::Float(a_lower, a_upper), Shape::Float(b_lower, b_upper)) => {
+      a_upper < b_lower || b_upper < a_lower
+    }
+    (Shape::Text(Some(x)), Shape::Text(Some(y))) => x != y,
+    (Shape::Bytes(Some(x)), Shape::Bytes(Some(y))) => x != y,
+    (Shape::Tag(Some(x), _), Shape::Tag(Some(y), _)) if x != y => true,
+    (Shape::Tag(_, a_t), Shape::Tag(_, b_t)) => choices_exclude(a_t, b_t),
+    (Shape::Array(a_alternatives), Shape::Array(b_alternatives)) => a_alternatives
+      .iter()
+      .all(|a| b_alternatives.iter().all(|b| array_excludes(a, b))),
+    (Shape::Map(a_alternatives), Shape::Map(b_alternatives)) => a_alternatives
+      .iter()
+      .all(|a| b_alternatives.iter().all(|b| map_excludes(a, b))),
+    (a, b) => std::mem::discriminant(a) != std::mem::discriminant(b),
+  }
+}
+
+fn choices_exclude(a: &[Shape], b: &[Shape]) -> bool {
+  a.iter().all(|a| b.iter().all(|b| shape_excludes(a, b)))
+}
+
+// Total number of items of an array
+fn length(entries: &[Entry]) -> (usize, usize) {
+  entries.iter().fold((0, 0), |(min, max), e| {
+    (min.saturating_add(e.min), max.saturating_add(e.max))
+  })
+}
+
+fn is_exactly_once(entries: &[Entry]) -> bool {
+  entries.iter().all(|e| (e.min, e.max) == (1, 1))
+}
+
+fn array_subtype(a: &[Entry], b: &[Entry]) -> Result<bool> {
+  let a = a.iter().filter(|e| e.max > 0).collect::<Vec<_>>();
+  let (a_min, a_max) = a.iter().fold((0usize, 0usize), |(min, max), e| {
+    (min.saturating_add(e.min), max.saturating_add(e.max))
+  });
+  let (b_min, b_max) = length(b);
+
+  // Arrays of a single repeated type, e.g. `[* tstr]`, match any items of the
+  // type
+  if let [b_entry] = b {
+    if (b_entry.min, b_entry.max) != (1, 1) {
+      if a_min < b_min || a_max > b_max {
+        return Ok(false);
+      }
+
+      return all(a.iter().map(|e| choices_subtype(&e.value, &b_entry.value)));
+    }
+  }
+
+  if a_max < b_min || b_max < a_min {
+    return Ok(false);
+  }
+
+  let is_fixed = a.iter().all(|e| (e.min, e.max) == (1, 1)) && is_exactly_once(b);
+  if a.len() == b.len()
+    && a
+      .iter()
+      .zip(b.iter())
+      .all(|(a, b)| a.min >= b.min && a.max <= b.max)
+  {
+    let verdict = all(
+      a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| choices_subtype(&a.value, &b.value)),
+    );
+
+    // Without optional or repeated entries, items are matched by position
+    if verdict != Ok(false) || is_fixed {
+      return verdict;
+    }
+  }
+
+  if is_fixed {
+    return Ok(false);
+  }
+
+  Err(Error::Undecidable(
+    "arrays with optional or repeated entries laid out differently".to_string(),
+  ))
+}
+
+fn array_excludes(a: &[Entry], b: &[Entry]) -> bool {
+  let (a_min, a_max) = length(a);
+  let (b_min, b_max) = length(b);
+  if a_max < b_min || b_max < a_min {
+    return true;
+  }
+
+  is_exactly_once(a)
+    && is_exactly_once(b)
+    && a.len() == b.len()
+    && a
+      .iter()
+      .zip(b.iter())
+      .any(|(a, b)| choices_exclude(&a.value, &b.value))
+}
+
+// Key of a map entry with a single literal key
+fn literal_key(entry: &Entry) -> Option<&Shape> {
+  match entry.key.as_deref() {
+    Some([key]) => match key {
+      Shape::Bool(Some(_)) | Shape::Text(Some(_)) | Shape::Bytes(Some(_)) => Some(key),
+      Shape::Int(lower, upper) if lower == upper => Some(key),
+      Shape::Float(lower, upper) if lower == upper => Some(key),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+fn map_subtype(a: &[Entry], b: &[Entry]) -> Result<bool> {
+  let mut verdicts = Vec::new();
+
+  for a_entry in a.iter().filter(|e| e.max > 0) {
+    let a_key = a_entry.key.as_deref().unwrap_or_default();
+
+    // Entries with the same literal key are matched
+    if let Some(key) = literal_key(a_entry) {
+      if let Some(b_entry) = b.iter().find(|e| literal_key(e) == Some(key)) {
+        verdicts.push(Ok(a_entry.min >= b_entry.min && a_entry.max <= b_entry.max));
+        verdicts.push(choices_subtype(&a_entry.value, &b_entry.value));
+        continue;
+      }
+    }
+
+    // Otherwise the keys have to be matched by a key type of any number of
+    // entries, e.g. `* tstr => any`
+    let candidates = b
+      .iter()
+      .filter(|e| literal_key(e).is_none())
+      .map(|b_entry| {
+        let b_key = b_entry.key.as_deref().unwrap_or_default();
+        let verdict = all(vec![
+          choices_subtype(a_key, b_key),
+          choices_subtype(&a_entry.value, &b_entry.value),
+        ]);
+        let verdict = match verdict {
+          Ok(true) if b_entry.max != usize::MAX || a_entry.min < b_entry.min => {
+            Err(Error::Undecidable(
+              "map entries matched by key types with bounded occurrences".to_string(),
+            ))
+          }
+          verdict => verdict,
+        };
+
+        (
+          verdict,
+          choices_exclude(a_key, b_key) || choices_exclude(&a_entry.value, &b_entry.value),
+        )
+      })
+      .collect::<Vec<_>>();
+
+    verdicts.push(any_candidate(candidates));
+  }
+
+  for b_entry in b.iter().filter(|e| e.min > 0) {
+    let is_required = match literal_key(b_entry) {
+      Some(key) => a.iter().any(|e| e.min > 0 && literal_key(e) == Some(key)),
+      None => a.iter().any(|e| {
+        e.min >= b_entry.min
+          && choices_subtype(
+            e.key.as_deref().unwrap_or_default(),
+            b_entry.key.as_deref().unwrap_or_default(),
+          ) == Ok(true)
+      }),
+    };
+
+    verdicts.push(Ok(is_required));
+  }
+
+  all(verdicts)
+}
+
+fn map_excludes(a: &[Entry], b: &[Entry]) -> bool {
+  let key_excludes =
+    |key: &[Shape], entry: &Entry| choices_exclude(key, entry.key.as_deref().unwrap_or_default());
+
+  // A required key of a isn't allowed in b, or its values are
+  let excluded_key = a.iter().filter(|e| e.min > 0).any(|a_entry| {
+    let a_key = a_entry.key.as_deref().unwrap_or_default();
+    b.iter().all(|b_entry| {
+      key_excludes(a_key, b_entry) || choices_exclude(&a_entry.value, &b_entry.value)
+    })
+  });
+
+  // A required key of b can't occur in a
+  let missing_key = b.iter().filter(|e| e.min > 0).any(|b_entry| {
+    let b_key = b_entry.key.as_deref().unwrap_or_default();
+    a.iter().all(|a_entry| key_excludes(b_key, a_entry))
+  });
+
+  excluded_key || missing_key
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cddl_from_str;
+
+  use indoc::indoc;
+
+  #[test]
+  fn verify_is_subtype() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = indoc!(
+      r#"
+        small = 0..10
+        byte = uint .size 1
+        code = tstr .size 3
+        short-text = tstr .size (1..5)
+        id = bstr .size 16
+        signed = uint / nint
+        port = uint .le 65535
+        color = "red" / "green" / "blue"
+        primary = &( red: "red", green: "green", blue: "blue" )
+        point = [x: int, y: int]
+        point3d = [x: int, y: int, z: int]
+        coords = [* int]
+        list = [1*3 small]
+        email = tstr .regexp "[a-z]+@[a-z]+"
+        contact = email / uri
+
+        base = { id: uint, ? name: tstr }
+        extended = { id: small, name: tstr, ? note: tstr }
+        open = { id: int, * tstr => any }
+        header-fields = ( id: uint, kind: "a" / "b" )
+        header = { header-fields }
+        header-a = { id: uint, kind: "a" }
+
+        ping = { type: "ping", seq: uint }
+        pong = { type: "pong", seq: uint }
+        message = ping / pong
+        any-number = { type: "ping" / "pong", seq: uint }
+
+        tree = [* tree]
+      "#
+    );
+    let cddl = cddl_from_str(cddl, true)?;
+    let check = |a: &str, b: &str| is_subtype(&cddl, a, b);
+
+    assert_eq!(check("small", "uint"), Ok(true));
+    assert_eq!(check("uint", "small"), Ok(false));
+    assert_eq!(check("byte", "small"), Ok(false));
+    assert_eq!(check("byte", "port"), Ok(true));
+    assert_eq!(check("int", "signed"), Ok(true));
+    assert_eq!(check("primary", "color"), Ok(true));
+    assert_eq!(check("color", "tstr"), Ok(true));
+    assert_eq!(check("tstr", "color"), Ok(false));
+    assert_eq!(check("code", "short-text"), Ok(true));
+    assert_eq!(check("short-text", "code"), Ok(false));
+    assert_eq!(check("code", "tstr"), Ok(true));
+    assert_eq!(check("tstr", "code"), Ok(false));
+    assert_eq!(check("id", "code"), Ok(false));
+
+    assert_eq!(check("point", "coords"), Ok(true));
+    assert_eq!(check("point", "point3d"), Ok(false));
+    assert_eq!(check("list", "coords"), Ok(true));
+    assert_eq!(check("coords", "list"), Ok(false));
+
+    assert_eq!(check("email", "contact"), Ok(true));
+    assert_eq!(check("email", "tstr"), Ok(true));
+    assert!(matches!(check("tstr", "email"), Err(Error::Undecidable(_))));
+
+    assert_eq!(check("extended", "base"), Ok(false));
+    assert_eq!(check("header-a", "header"), Ok(true));
+    assert_eq!(check("header", "header-a"), Ok(false));
+    assert_eq!(check("header", "open"), Ok(true));
+    assert_eq!(check("base", "open"), Ok(true));
+    assert_eq!(check("open", "base"), Ok(false));
+    assert_eq!(check("extended", "header"), Ok(false));
+
+    assert_eq!(check("ping", "message"), Ok(true));
+    assert_eq!(check("message", "any-number"), Ok(true));
+    assert!(matches!(
+      check("any-number", "message"),
+      Err(Error::Undecidable(_))
+    ));
+
+    assert_eq!(
+      check("tree", "any"),
+      Err(Error::Recursive("tree".to_string()))
+    );
+    assert_eq!(
+      check("small", "undefined-rule"),
+      Err(Error::UndefinedRule("undefined-rule".to_string()))
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_int_keyed_maps() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = cddl_from_str(
+      indoc!(
+        r#"
+          header = { ? alg => int / tstr, ? 4 => bstr, * (-65536..-1) => any }
+          alg = 1
+          claims = { ? 1 => tstr, ? 2 => tstr, * label => any }
+          label = int / tstr
+          extensible = { ? 1 => tstr, * tstr => any }
+          enumerated = { ? 1 => tstr, ? (1 / 2) => int }
+          named = { ? 1 => tstr, name: tstr }
+          list = [1, 2]
+        "#
+      ),
+      true,
+    )?;
+
+    assert_eq!(
+      int_keyed_maps(&cddl),
+      vec![
+        IntKeyedMap {
+          rule: "header".to_string(),
+          keys: vec![1, 4],
+          extensions: vec![(-65536, -1)],
+        },
+        IntKeyedMap {
+          rule: "claims".to_string(),
+          keys: vec![1, 2],
+          extensions: vec![(-(u64::MAX as i128) - 1, u64::MAX as i128)],
+        },
+        IntKeyedMap {
+          rule: "extensible".to_string(),
+          keys: vec![1],
+          extensions: Vec::new(),
+        },
+        IntKeyedMap {
+          rule: "enumerated".to_string(),
+          keys: vec![1, 2],
+          extensions: Vec::new(),
+        },
+      ]
+    );
+    assert!(int_keyed_maps(&cddl)[2].is_closed());
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_is_subtype_across() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let v1 = cddl_from_str(
+      indoc!(
+        r#"
+          message = { version: 1 / 2, body: body, ? extensions: { * tstr => any } }
+          body = { id: uint, text: tstr }
+        "#
+      ),
+      true,
+    )?;
+    let v2 = cddl_from_str(
+      indoc!(
+        r#"
+          message = { version: 2, body: body, extensions: { priority: 0..3 } }
+          body = { id: uint .size 4, text: tstr .size (1..280) }
+        "#
+      ),
+      true,
+    )?;
+
+    assert_eq!(is_subtype_across(&v2, "message", &v1, "message"), Ok(true));
+    assert_eq!(is_subtype_across(&v1, "message", &v2, "message"), Ok(false));
+
+    Ok(())
+  }
+}
@@ -0,0 +1,232 @@
+use crate::{
+  lexer::{self, Lexer},
+  token::Token,
+};
+
+use std::fmt;
+
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+  collections::BTreeMap,
+  string::{String, ToString},
+  vec::Vec,
+};
+
+/// Annotation embedded in a comment in the form `;@name args`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+  /// Annotation name (the identifier following `@`)
+  pub name: String,
+  /// Remaining text following the annotation name, trimmed
+  pub args: String,
+}
+
+impl fmt::Display for Annotation {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.args.is_empty() {
+      return write!(f, "@{}", self.name);
+    }
+
+    write!(f, "@{} {}", self.name, self.args)
+  }
+}
+
+impl Annotation {
+  /// Parse an annotation from the text of a comment (excluding the leading
+  /// `;`). Returns `None` if the comment is not an annotation
+  pub fn from_comment(comment: &str) -> Option<Self> {
+    let comment = comment.trim().strip_prefix('@')?;
+
+    let name_len = comment
+      .find(|c: char| c.is_whitespace())
+      .unwrap_or(comment.len());
+    let (name, args) = comment.split_at(name_len);
+
+    if name.is_empty() {
+      return None;
+    }
+
+    Some(Annotation {
+      name: name.to_string(),
+      args: args.trim().to_string(),
+    })
+  }
+
+  /// Comma separated list of values given as the annotation arguments
+  pub fn values(&self) -> Vec<&str> {
+    self
+      .args
+      .split(',')
+      .map(|v| v.trim())
+      .filter(|v| !v.is_empty())
+      .collect()
+  }
+}
+
+/// Comments attached to rules, keyed by rule name. Comments on the lines
+/// immediately preceding a rule, as well as comments trailing the first line of
+/// a rule, are attached to that rule
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleComments(BTreeMap<String, Vec<String>>);
+
+impl RuleComments {
+  /// Collect rule comments from the given CDDL input
+  pub fn from_cddl(input: &str) -> Result<Self, lexer::Error> {
+    let mut tokens = Vec::new();
+    let mut lexer = Lexer::new(input);
+    loop {
+      let (_, token) = lexer.next_token()?;
+      if let Token::EOF = token {
+        break;
+      }
+
+      tokens.push(token);
+    }
+
+    let mut rule_comments = RuleComments::default();
+    let mut pending: Vec<String> = Vec::new();
+    let mut at_line_start = true;
+    let mut current_rule: Option<String> = None;
+
+    for (idx, token) in tokens.iter().enumerate() {
+      match token {
+        Token::COMMENT(comment) => {
+          if let Some(rule) = &current_rule {
+            rule_comments.push(rule, comment);
+          } else {
+            pending.push(comment.to_string());
+          }
+
+          // Comments are terminated by (and consume) the end of the line
+          current_rule = None;
+          at_line_start = true;
+          continue;
+        }
+        Token::NEWLINE => {
+          // A blank line detaches preceding comments from the next rule
+          if at_line_start && current_rule.is_none() {
+            pending.clear();
+          }
+
+          current_rule = None;
+          at_line_start = true;
+          continue;
+        }
+        Token::IDENT(ident, socket) if at_line_start && is_rule_start(&tokens[idx + 1..]) => {
+          let name = match socket {
+            Some(sp) => format!("{}{}", sp, ident),
+            None => ident.to_string(),
+          };
+
+          for comment in pending.drain(..) {
+            rule_comments.push(&name, &comment);
+          }
+
+          current_rule = Some(name);
+        }
+        _ => pending.clear(),
+      }
+
+      at_line_start = false;
+    }
+
+    Ok(rule_comments)
+  }
+
+  fn push(&mut self, rule: &str, comment: &str) {
+    self
+      .0
+      .entry(rule.to_string())
+      .or_default()
+      .push(comment.to_string());
+  }
+
+  /// Comments attached to the given rule
+  pub fn comments(&self, rule: &str) -> &[String] {
+    self.0.get(rule).map(|c| c.as_slice()).unwrap_or(&[])
+  }
+
+  /// Annotations attached to the given rule
+  pub fn annotations(&self, rule: &str) -> Vec<Annotation> {
+    self
+      .comments(rule)
+      .iter()
+      .filter_map(|c| Annotation::from_comment(c))
+      .collect()
+  }
+
+  /// Iterator over all rule names and their comments
+  pub fn iter(&self) -> impl Iterator<Item = (&str, &[String])> {
+    self.0.iter().map(|(k, v)| (k.as_str(), v.as_slice()))
+  }
+}
+
+/// Whether the tokens following an identifier at the start of a line denote a
+/// rule definition
+fn is_rule_start(tokens: &[Token]) -> bool {
+  let mut iter = tokens.iter();
+
+  match iter.next() {
+    Some(Token::ASSIGN) | Some(Token::TCHOICEALT) | Some(Token::GCHOICEALT) => true,
+    Some(Token::LANGLEBRACKET) => {
+      for token in iter.by_ref() {
+        if let Token::RANGLEBRACKET = token {
+          break;
+        }
+      }
+
+      matches!(
+        iter.next(),
+        Some(Token::ASSIGN) | Some(Token::TCHOICEALT) | Some(Token::GCHOICEALT)
+      )
+    }
+    _ => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use indoc::indoc;
+
+  #[cfg(not(feature = "std"))]
+  use alloc::string::ToString;
+
+  #[test]
+  fn verify_rule_annotations() -> lexer::Result<()> {
+    let cddl = indoc!(
+      r#"
+        ; A person
+        ;@label pii, personal
+        person = {
+          name: tstr, ; not a rule comment
+          ssn: ssn,
+        }
+
+        ; detached comment
+
+        ssn = tstr ;@label secret
+        generic<T> = [* T] ;@bound T: uint
+      "#
+    );
+
+    let rc = RuleComments::from_cddl(cddl)?;
+
+    assert_eq!(
+      rc.comments("person"),
+      &[" A person".to_string(), "@label pii, personal".to_string()]
+    );
+    assert_eq!(
+      rc.annotations("person")[0].values(),
+      vec!["pii", "personal"]
+    );
+    assert_eq!(rc.annotations("ssn")[0].to_string(), "@label secret");
+    assert_eq!(rc.annotations("generic")[0].name, "bound");
+    assert_eq!(rc.annotations("generic")[0].args, "T: uint");
+
+    Ok(())
+  }
+}
@@ -0,0 +1,547 @@
+#![cfg(feature = "std")]
+#![cfg(feature = "json")]
+#![cfg(feature = "cbor")]
+#![cfg(not(feature = "lsp"))]
+#![cfg(not(target_arch = "wasm32"))]
+
+use crate::{
+  ast::*,
+  generator::{self, from_json, to_json, Generator, Target},
+  token,
+  validator::{cbor::CBORValidator, json::JSONValidator, Validator},
+  visitor::{self, Visitor},
+};
+
+use ciborium::value::{Integer, Value};
+use displaydoc::Display;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::convert::{Infallible, TryFrom};
+
+/// Alias for `Result` with an error of type `anonymize::Error`
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Anonymization error
+#[derive(Debug, Display)]
+pub enum Error {
+  /// instance doesn't conform to the CDDL document: {0}
+  Invalid(String),
+  /// no synthetic value conforming to the CDDL document was found for {0}
+  Unresolved(String),
+  /// {0}
+  Generation(generator::Error),
+}
+
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Error::Generation(error) => Some(error),
+      _ => None,
+    }
+  }
+}
+
+// Number of format preserving replacements tried for a value before falling
+// back to generated values
+const RANDOM_ATTEMPTS: usize = 4;
+// Maximum number of generated values tried for a value
+const GENERATED_ATTEMPTS: usize = 64;
+// Number of random instances generated for each rule, in addition to those
+// generated exhaustively
+const RANDOM_INSTANCES: usize = 4;
+
+/// Anonymizer of instances conforming to a CDDL document. The structure of an
+/// instance is kept, while its text strings, byte strings, numbers and map keys
+/// are replaced with synthetic values that still conform to the document, so
+/// that realistic test data can be shared without leaking its content
+///
+/// Values are first replaced by random values of the same format, e.g. text of
+/// the same length with letters replaced by random letters and digits replaced
+/// by random digits, and integers with the same number of digits. Values that
+/// must be replaced differently to satisfy the document (e.g. because of a
+/// `.regexp` control) are replaced by values found in instances generated from
+/// the document. Values equal to a literal of the document (e.g. the key of a
+/// map member or a choice of an enumeration) as well as booleans and nulls are
+/// kept as they don't reveal anything beyond the document itself
+///
+/// # Example
+///
+/// ```
+/// use cddl::{anonymize::Anonymizer, cddl_from_str, generator::Target};
+/// use serde_json::json;
+///
+/// let cddl = cddl_from_str(
+///   r#"
+///     person = { name: tstr, email: email, role: "admin" / "user" }
+///     email = tstr .regexp "[a-z]+@example\\.com"
+///   "#,
+///   false,
+/// )
+/// .unwrap();
+///
+/// #[cfg(feature = "additional-controls")]
+/// let mut anonymizer = Anonymizer::new(&cddl, Target::Json, 42, None);
+/// #[cfg(not(feature = "additional-controls"))]
+/// let mut anonymizer = Anonymizer::new(&cddl, Target::Json, 42);
+///
+/// let person = json!({ "name": "Jane Doe", "email": "jane@example.com", "role": "admin" });
+/// let anonymized = anonymizer.anonymize_json(&person).unwrap();
+///
+/// assert_ne!(anonymized["name"], person["name"]);
+/// assert_eq!(anonymized["name"].as_str().unwrap().len(), 8);
+/// assert_ne!(anonymized["email"], person["email"]);
+/// assert_eq!(anonymized["role"], "admin");
+/// ```
+pub struct Anonymizer<'a> {
+  cddl: &'a CDDL<'a>,
+  target: Target,
+  #[cfg(feature = "additional-controls")]
+  enabled_features: Option<&'a [&'a str]>,
+  rng: StdRng,
+  // Literal values of the document
+  literals: Vec<Value>,
+  // Values found in instances generated from the document
+  generated: Vec<Value>,
+}
+
+impl<'a> Anonymizer<'a> {
+  /// New anonymizer of instances of the given CDDL document in the given
+  /// format, validated against the first type rule of the document. Synthetic
+  /// values are drawn from the given random seed
+  pub fn new(
+    cddl: &'a CDDL<'a>,
+    target: Target,
+    seed: u64,
+    #[cfg(feature = "additional-controls")] enabled_features: Option<&'a [&'a str]>,
+  ) -> Self {
+    let mut literals = Literals(Vec::new());
+    for rule in cddl.rules.iter() {
+      // Collecting literals is infallible
+      let _ = literals.visit_rule(rule);
+    }
+
+    let mut generated = Vec::new();
+    let mut generator = Generator::new(cddl, target, seed);
+    for rule in cddl.rules.iter() {
+      let name = match rule {
+        Rule::Type { rule, .. } if rule.generic_params.is_none() => rule.name.to_string(),
+        _ => continue,
+      };
+
+      // Rules the generator can't generate instances of are skipped, as their
+      // values can only be replaced in the same format
+      let mut instances = generator.generate_all(&name).unwrap_or_default();
+      for _ in 0..RANDOM_INSTANCES {
+        instances.extend(generator.generate(&name).ok());
+      }
+
+      for instance in instances.iter() {
+        collect_scalars(instance, &mut generated);
+      }
+    }
+
+    Anonymizer {
+      cddl,
+      target,
+      #[cfg(feature = "additional-controls")]
+      enabled_features,
+      rng: StdRng::seed_from_u64(seed),
+      literals: literals.0,
+      generated,
+    }
+  }
+
+  /// Format of the instances anonymized
+  pub fn target(&self) -> Target {
+    self.target
+  }
+
+  /// Anonymize a JSON document
+  pub fn anonymize_json(&mut self, json: &serde_json::Value) -> Result<serde_json::Value> {
+    to_json(&self.anonymize(&from_json(json))?).map_err(Error::Generation)
+  }
+
+  /// Anonymize a CBOR data item, or a JSON document converted with
+  /// [`generator::from_json`] if the anonymizer targets JSON
+  pub fn anonymize(&mut self, instance: &Value) -> Result<Value> {
+    self.validate(instance).map_err(Error::Invalid)?;
+
+    let mut leaves = Vec::new();
+    collect_leaves(instance, &mut Vec::new(), &mut leaves);
+
+    // Typically, replacing every value in the same format conforms to the
+    // document
+    let mut anonymized = instance.clone();
+    for path in leaves.iter() {
+      let value = value_at_mut(&mut anonymized, path);
+      if !self.literals.contains(value) {
+        *value = self.same_format(value);
+      }
+    }
+    if self.validate(&anonymized).is_ok() {
+      return Ok(anonymized);
+    }
+
+    // Otherwise values are replaced one at a time, so that the value making
+    // the instance invalid is known
+    let mut anonymized = instance.clone();
+    for path in leaves.iter() {
+      let original = value_at_mut(&mut anonymized, path).clone();
+      if self.literals.contains(&original) {
+        continue;
+      }
+
+      let mut candidates = (0..RANDOM_ATTEMPTS)
+        .map(|_| self.same_format(&original))
+        .collect::<Vec<_>>();
+      let mut generated = self
+        .generated
+        .iter()
+        .filter(|v| is_same_kind(v, &original) && **v != original)
+        .cloned()
+        .collect::<Vec<_>>();
+      generated.shuffle(&mut self.rng);
+      candidates.extend(generated.into_iter().take(GENERATED_ATTEMPTS));
+
+      let mut resolved = false;
+      for candidate in candidates.into_iter() {
+        if candidate == original || is_duplicate_key(&anonymized, path, &candidate) {
+          continue;
+        }
+
+        *value_at_mut(&mut anonymized, path) = candidate;
+        if self.validate(&anonymized).is_ok() {
+          resolved = true;
+          break;
+        }
+      }
+
+      if !resolved {
+        return Err(Error::Unresolved(location(instance, path)));
+      }
+    }
+
+    Ok(anonymized)
+  }
+
+  fn validate(&self, instance: &Value) -> std::result::Result<(), String> {
+    match self.target {
+      Target::Json => {
+        let json = to_json(instance).map_err(|e| e.to_string())?;
+
+        #[cfg(feature = "additional-controls")]
+        let mut jv = JSONValidator::new(self.cddl, json, self.enabled_features);
+        #[cfg(not(feature = "additional-controls"))]
+        let mut jv = JSONValidator::new(self.cddl, json);
+
+        jv.validate().map_err(|e| e.to_string())
+      }
+      Target::Cbor => {
+        #[cfg(feature = "additional-controls")]
+        let mut cv = CBORValidator::new(self.cddl, instance.clone(), self.enabled_features);
+        #[cfg(not(feature = "additional-controls"))]
+        let mut cv = CBORValidator::new(self.cddl, instance.clone());
+
+        cv.validate().map_err(|e| e.to_string())
+      }
+    }
+  }
+
+  // Random value in the same format as the given value
+  fn same_format(&mut self, value: &Value) -> Value {
+    match value {
+      Value::Text(text) => Value::Text(
+        text
+          .chars()
+          .map(|c| {
+            if c.is_ascii_digit() {
+              self.rng.gen_range('0'..='9')
+            } else if c.is_ascii_uppercase() {
+              self.rng.gen_range('A'..='Z')
+            } else if c.is_alphabetic() {
+              self.rng.gen_range('a'..='z')
+            } else {
+              c
+            }
+          })
+          .collect(),
+      ),
+      Value::Bytes(bytes) => Value::Bytes((0..bytes.len()).map(|_| self.rng.gen()).collect()),
+      Value::Integer(i) => {
+        let i = i128::from(*i);
+        // Integers keep their sign and number of digits
+        let digits = i.unsigned_abs().to_string().len() as u32;
+        let lower = if digits == 1 {
+          0
+        } else {
+          10i128.pow(digits - 1)
+        };
+        let upper = 10i128.saturating_pow(digits) - 1;
+        let magnitude = self.rng.gen_range(lower..=upper);
+        let i = if i < 0 { -magnitude.max(1) } else { magnitude };
+
+        Integer::try_from(i)
+          .map(Value::Integer)
+          .unwrap_or_else(|_| value.clone())
+      }
+      Value::Float(f) if f.is_finite() => {
+        // Floats keep their sign and order of magnitude
+        let magnitude = f.abs().max(1.0);
+        let f = f.signum() * self.rng.gen_range(0.0..magnitude * 2.0);
+        Value::Float((f * 100.0).round() / 100.0)
+      }
+      _ => value.clone(),
+    }
+  }
+}
+
+// Step from a value to one of its children
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step {
+  // Array item at the given index
+  Item(usize),
+  // Key of the map entry at the given index
+  Key(usize),
+  // Value of the map entry at the given index
+  Value(usize),
+  // Content of tagged data
+  Tagged,
+}
+
+// Paths to the scalar values (including map keys) of a value, depth first
+fn collect_leaves(value: &Value, path: &mut Vec<Step>, leaves: &mut Vec<Vec<Step>>) {
+  let mut visit = |step, value: &Value, leaves: &mut Vec<Vec<Step>>| {
+    path.push(step);
+    collect_leaves(value, path, leaves);
+    path.pop();
+  };
+
+  match value {
+    Value::Array(items) => {
+      for (idx, item) in items.iter().enumerate() {
+        visit(Step::Item(idx), item, leaves);
+      }
+    }
+    Value::Map(entries) => {
+      for (idx, (k, v)) in entries.iter().enumerate() {
+        visit(Step::Key(idx), k, leaves);
+        visit(Step::Value(idx), v, leaves);
+      }
+    }
+    Value::Tag(_, content) => visit(Step::Tagged, content, leaves),
+    _ => leaves.push(path.clone()),
+  }
+}
+
+fn collect_scalars(value: &Value, scalars: &mut Vec<Value>) {
+  match value {
+    Value::Array(items) => items.iter().for_each(|v| collect_scalars(v, scalars)),
+    Value::Map(entries) => entries.iter().for_each(|(k, v)| {
+      collect_scalars(k, scalars);
+      collect_scalars(v, scalars);
+    }),
+    Value::Tag(_, content) => collect_scalars(content, scalars),
+    v => {
+      if !scalars.contains(v) {
+        scalars.push(v.clone());
+      }
+    }
+  }
+}
+
+fn value_at_mut<'v>(value: &'v mut Value, path: &[Step]) -> &'v mut Value {
+  path.iter().fold(value, |value, step| match (value, step) {
+    (Value::Array(items), Step::Item(idx)) => &mut items[*idx],
+    (Value::Map(entries), Step::Key(idx)) => &mut entries[*idx].0,
+    (Value::Map(entries), Step::Value(idx)) => &mut entries[*idx].1,
+    (Value::Tag(_, content), Step::Tagged) => content,
+    _ => unreachable!("paths are collected from values of the same structure"),
+  })
+}
+
+// Whether replacing the map key at the given path with the given value would
+// duplicate another key of the map
+fn is_duplicate_key(value: &Value, path: &[Step], key: &Value) -> bool {
+  let (last, parent) = match path.split_last() {
+    Some((Step::Key(idx), parent)) => (*idx, parent),
+    _ => return false,
+  };
+
+  let mut value = value;
+  for step in parent.iter() {
+    value = match (value, step) {
+      (Value::Array(items), Step::Item(idx)) => &items[*idx],
+      (Value::Map(entries), Step::Value(idx)) => &entries[*idx].1,
+      (Value::Tag(_, content), Step::Tagged) => content,
+      _ => return false,
+    };
+  }
+
+  matches!(value, Value::Map(entries) if entries
+    .iter()
+    .enumerate()
+    .any(|(idx, (k, _))| idx != last && k == key))
+}
+
+fn is_same_kind(a: &Value, b: &Value) -> bool {
+  std::mem::discriminant(a) == std::mem::discriminant(b)
+}
+
+// Location of the value at the given path, with map entries identified by the
+// keys of the original instance
+fn location(instance: &Value, path: &[Step]) -> String {
+  let mut location = String::new();
+  let mut value = instance;
+  for step in path.iter() {
+    match (value, step) {
+      (Value::Array(items), Step::Item(idx)) => {
+        location.push_str(&format!("/{}", idx));
+        value = &items[*idx];
+      }
+      (Value::Map(entries), Step::Key(idx)) | (Value::Map(entries), Step::Value(idx)) => {
+        match &entries[*idx].0 {
+          Value::Text(key) => location.push_str(&format!("/{}", key)),
+          key => location.push_str(&format!("/{:?}", key)),
+        }
+
+        if let Step::Key(_) = step {
+          location.push_str(" (key)");
+        }
+
+        value = &entries[*idx].1;
+      }
+      (Value::Tag(_, content), Step::Tagged) => value = content,
+      _ => break,
+    }
+  }
+
+  if location.is_empty() {
+    location.push('/');
+  }
+
+  location
+}
+
+// Collects the literal values of a document
+struct Literals(Vec<Value>);
+
+impl Literals {
+  fn push(&mut self, value: Value) {
+    if !self.0.contains(&value) {
+      self.0.push(value);
+    }
+  }
+}
+
+impl<'a> Visitor<'a, Infallible> for Literals {
+  fn visit_type2(&mut self, t2: &Type2<'a>) -> visitor::Result<Infallible> {
+    match t2 {
+      Type2::TextValue { value, .. } => self.push(Value::Text(value.to_string())),
+      Type2::UintValue { value, .. } => self.push(Value::Integer((*value as u64).into())),
+      Type2::IntValue { value, .. } => self.push(Value::Integer((*value as i64).into())),
+      Type2::FloatValue { value, .. } => self.push(Value::Float(*value)),
+      Type2::UTF8ByteString { value, .. } => self.push(Value::Bytes(value.to_vec())),
+      _ => (),
+    }
+
+    visitor::walk_type2(self, t2)
+  }
+
+  fn visit_memberkey(&mut self, mk: &MemberKey<'a>) -> visitor::Result<Infallible> {
+    match mk {
+      MemberKey::Bareword { ident, .. } => self.push(Value::Text(ident.ident.to_string())),
+      MemberKey::Value { value, .. } => self.push(
+        crate::validator::cbor::token_value_into_cbor_value(value.clone()),
+      ),
+      _ => (),
+    }
+
+    visitor::walk_memberkey(self, mk)
+  }
+
+  fn visit_value(&mut self, value: &token::Value<'a>) -> visitor::Result<Infallible> {
+    self.push(crate::validator::cbor::token_value_into_cbor_value(
+      value.clone(),
+    ));
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cddl_from_str;
+
+  use indoc::indoc;
+  use serde_json::json;
+
+  #[test]
+  fn verify_anonymize() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = indoc!(
+      r#"
+        account = {
+          id: uint,
+          owner: person,
+          status: "active" / "closed",
+          ? scores: { * tstr => 0..100 },
+          tags: [* tstr .size (1..4)],
+          balance: float,
+        }
+        person = { name: tstr, email: email }
+        email = tstr .regexp "[a-z]+@example\\.com"
+      "#
+    );
+    let cddl = cddl_from_str(cddl, false)?;
+
+    let account = json!({
+      "id": 1234,
+      "owner": { "name": "Jane Doe", "email": "jane@example.com" },
+      "status": "active",
+      "scores": { "alice": 87, "bob": 42 },
+      "tags": ["vip", "eu"],
+      "balance": 1520.5,
+    });
+
+    #[cfg(feature = "additional-controls")]
+    let mut anonymizer = Anonymizer::new(&cddl, Target::Json, 7, None);
+    #[cfg(not(feature = "additional-controls"))]
+    let mut anonymizer = Anonymizer::new(&cddl, Target::Json, 7);
+
+    let anonymized = anonymizer.anonymize_json(&account)?;
+
+    let id = anonymized["id"].as_u64().unwrap();
+    assert!((1000..10000).contains(&id) && id != 1234);
+    assert_eq!(anonymized["status"], "active");
+
+    let name = anonymized["owner"]["name"].as_str().unwrap();
+    assert_ne!(name, "Jane Doe");
+    assert_eq!(name.len(), 8);
+    assert_eq!(&name[4..5], " ");
+
+    let email = anonymized["owner"]["email"].as_str().unwrap();
+    assert_ne!(email, "jane@example.com");
+    assert!(email.ends_with("@example.com"));
+
+    let scores = anonymized["scores"].as_object().unwrap();
+    assert_eq!(scores.len(), 2);
+    assert!(!scores.contains_key("alice") && !scores.contains_key("bob"));
+    assert!(scores.values().all(|s| s.as_u64().unwrap() <= 100));
+
+    assert_eq!(anonymized["tags"].as_array().unwrap().len(), 2);
+    assert_ne!(anonymized["tags"], account["tags"]);
+
+    // The same seed yields the same synthetic values
+    #[cfg(feature = "additional-controls")]
+    let mut anonymizer = Anonymizer::new(&cddl, Target::Json, 7, None);
+    #[cfg(not(feature = "additional-controls"))]
+    let mut anonymizer = Anonymizer::new(&cddl, Target::Json, 7);
+    assert_eq!(anonymizer.anonymize_json(&account)?, anonymized);
+
+    assert!(matches!(
+      anonymizer.anonymize_json(&json!({ "id": "x" })),
+      Err(Error::Invalid(_))
+    ));
+
+    Ok(())
+  }
+}
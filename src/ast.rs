@@ -7,8 +7,11 @@ use std::{fmt, marker::PhantomData};
 #[cfg(feature = "std")]
 use std::borrow::Cow;
 
-#[cfg(target_arch = "wasm32")]
-use serde::{self, Serialize};
+#[cfg(any(target_arch = "wasm32", feature = "ast-serde"))]
+use serde::Serialize;
+
+#[cfg(feature = "ast-serde")]
+use serde::Deserialize;
 
 #[cfg(not(feature = "std"))]
 use alloc::{
@@ -23,9 +26,10 @@ use alloc::{
 pub type Span = (usize, usize, usize);
 
 #[cfg(feature = "ast-comments")]
+#[cfg_attr(feature = "ast-serde", derive(Serialize, Deserialize))]
 #[derive(Default, Debug, PartialEq, Clone)]
 #[doc(hidden)]
-pub struct Comments<'a>(pub Vec<&'a str>);
+pub struct Comments<'a>(pub Vec<Cow<'a, str>>);
 
 #[cfg(feature = "ast-comments")]
 impl<'a> Comments<'a> {
@@ -64,15 +68,15 @@ impl<'a> fmt::Display for Comments<'a> {
 /// ```abnf
 /// cddl = S 1*(rule S)
 /// ```
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Default, Debug, PartialEq, Clone)]
 pub struct CDDL<'a> {
   /// Zero or more production rules
-  #[cfg_attr(target_arch = "wasm32", serde(borrow))]
   pub rules: Vec<Rule<'a>>,
 
   #[cfg(feature = "ast-comments")]
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   pub comments: Option<Comments<'a>>,
 }
@@ -137,11 +141,12 @@ impl<'a> fmt::Display for CDDL<'a> {
 /// EALPHA = ALPHA / "@" / "_" / "$"
 /// DIGIT = %x30-39
 /// ```
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, Clone)]
 pub struct Identifier<'a> {
   /// Identifier
-  pub ident: &'a str,
+  pub ident: Cow<'a, str>,
   /// Optional socket
   pub socket: Option<SocketPlug>,
   /// Span
@@ -151,7 +156,21 @@ pub struct Identifier<'a> {
 
 impl<'a> PartialEq for Identifier<'a> {
   fn eq(&self, other: &Self) -> bool {
-    self.to_string() == other.to_string()
+    // Compares the displayed identifiers, socket included, without allocating
+    // them since identifiers are compared for every rule lookup
+    let socket = |ident: &Self| match ident.socket {
+      Some(SocketPlug::TYPE) => "$",
+      Some(SocketPlug::GROUP) => "$$",
+      None => "",
+    };
+
+    let (s1, s2) = (socket(self), socket(other));
+
+    s1.len() + self.ident.len() == s2.len() + other.ident.len()
+      && s1
+        .bytes()
+        .chain(self.ident.bytes())
+        .eq(s2.bytes().chain(other.ident.bytes()))
   }
 }
 
@@ -176,7 +195,7 @@ impl<'a> From<&'static str> for Identifier<'a> {
         if let Some(c) = socket.next() {
           if c == '$' {
             return Identifier {
-              ident,
+              ident: ident.into(),
               socket: Some(SocketPlug::GROUP),
               #[cfg(feature = "ast-span")]
               span: (0, 0, 0),
@@ -185,7 +204,7 @@ impl<'a> From<&'static str> for Identifier<'a> {
         }
 
         return Identifier {
-          ident,
+          ident: ident.into(),
           socket: Some(SocketPlug::TYPE),
           #[cfg(feature = "ast-span")]
           span: (0, 0, 0),
@@ -194,7 +213,7 @@ impl<'a> From<&'static str> for Identifier<'a> {
     }
 
     Identifier {
-      ident,
+      ident: ident.into(),
       socket: None,
       #[cfg(feature = "ast-span")]
       span: (0, 0, 0),
@@ -220,20 +239,20 @@ impl<'a> From<Token<'a>> for Identifier<'a> {
 /// rule = typename [genericparm] S assignt S type
 ///     / groupname [genericparm] S assigng S grpent
 /// ```
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub enum Rule<'a> {
   /// Type expression
   Type {
     /// Type rule
-    #[cfg_attr(target_arch = "wasm32", serde(borrow))]
     rule: TypeRule<'a>,
     /// Span
     #[cfg(feature = "ast-span")]
     span: Span,
 
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_after_rule: Option<Comments<'a>>,
   },
@@ -246,7 +265,7 @@ pub enum Rule<'a> {
     span: Span,
 
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_after_rule: Option<Comments<'a>>,
   },
@@ -321,7 +340,7 @@ impl<'a> fmt::Display for Rule<'a> {
         #[cfg(feature = "ast-comments")]
         if let Some(comments) = comments_after_rule {
           if comments.any_non_newline() {
-            if let Some(&"\n") = comments.0.first() {
+            if comments.0.first().is_some_and(|c| c == "\n") {
               rule_str.push_str(&comments.to_string());
             } else {
               rule_str.push_str(&format!(" {}", comments));
@@ -344,7 +363,7 @@ impl<'a> fmt::Display for Rule<'a> {
         #[cfg(feature = "ast-comments")]
         if let Some(comments) = comments_after_rule {
           if comments.any_non_newline() {
-            if let Some(&"\n") = comments.0.first() {
+            if comments.0.first().is_some_and(|c| c == "\n") {
               rule_str.push_str(&comments.to_string());
             } else {
               rule_str.push_str(&format!(" {}", comments));
@@ -382,11 +401,11 @@ impl<'a> Rule<'a> {
 /// ```abnf
 /// typename [genericparm] S assignt S type
 /// ```
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct TypeRule<'a> {
   /// Type name identifier
-  #[cfg_attr(target_arch = "wasm32", serde(borrow))]
   pub name: Identifier<'a>,
   /// Optional generic parameters
   pub generic_params: Option<GenericParams<'a>>,
@@ -396,11 +415,11 @@ pub struct TypeRule<'a> {
   pub value: Type<'a>,
 
   #[cfg(feature = "ast-comments")]
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   pub comments_before_assignt: Option<Comments<'a>>,
   #[cfg(feature = "ast-comments")]
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   pub comments_after_assignt: Option<Comments<'a>>,
 }
@@ -440,11 +459,11 @@ impl<'a> fmt::Display for TypeRule<'a> {
 /// ```abnf
 /// groupname [genericparm] S assigng S grpent
 /// ```
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct GroupRule<'a> {
   /// Group name identifier
-  #[cfg_attr(target_arch = "wasm32", serde(borrow))]
   pub name: Identifier<'a>,
   /// Optional generic parameters
   pub generic_params: Option<GenericParams<'a>>,
@@ -454,11 +473,11 @@ pub struct GroupRule<'a> {
   pub entry: GroupEntry<'a>,
 
   #[cfg(feature = "ast-comments")]
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   pub comments_before_assigng: Option<Comments<'a>>,
   #[cfg(feature = "ast-comments")]
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   pub comments_after_assigng: Option<Comments<'a>>,
 }
@@ -498,7 +517,8 @@ impl<'a> fmt::Display for GroupRule<'a> {
 /// ```abnf
 /// genericparm =  "<" S id S *("," S id S ) ">"
 /// ```
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, PartialEq, Clone, Default)]
 pub struct GenericParams<'a> {
   /// List of generic parameters
@@ -509,18 +529,19 @@ pub struct GenericParams<'a> {
 }
 
 /// Generic parameter
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct GenericParam<'a> {
   /// Generic parameter
   pub param: Identifier<'a>,
 
   #[cfg(feature = "ast-comments")]
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   pub comments_before_ident: Option<Comments<'a>>,
   #[cfg(feature = "ast-comments")]
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   pub comments_after_ident: Option<Comments<'a>>,
 }
@@ -557,7 +578,8 @@ impl<'a> fmt::Display for GenericParams<'a> {
 /// ```abnf
 /// genericarg = "<" S type1 S *("," S type1 S )  ">"
 /// ```
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct GenericArgs<'a> {
   /// Generic arguments
@@ -579,18 +601,19 @@ impl<'a> GenericArgs<'a> {
 }
 
 /// Generic argument
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct GenericArg<'a> {
   /// Generic argument
   pub arg: Box<Type1<'a>>,
 
   #[cfg(feature = "ast-comments")]
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   pub comments_before_type: Option<Comments<'a>>,
   #[cfg(feature = "ast-comments")]
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   pub comments_after_type: Option<Comments<'a>>,
 }
@@ -627,7 +650,8 @@ impl<'a> fmt::Display for GenericArgs<'a> {
 /// ```abnf
 /// type = type1 *(S "/" S  type1)
 /// ```
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Type<'a> {
   /// Type choices
@@ -661,18 +685,19 @@ impl<'a> Type<'a> {
 }
 
 /// Type choice
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 
 pub struct TypeChoice<'a> {
   /// Type choice
   pub type1: Type1<'a>,
   #[cfg(feature = "ast-comments")]
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   pub comments_before_type: Option<Comments<'a>>,
   #[cfg(feature = "ast-comments")]
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   pub comments_after_type: Option<Comments<'a>>,
 }
@@ -766,7 +791,8 @@ impl<'a> Type<'a> {
 /// ```abnf
 /// type1 = type2 [S (rangeop / ctlop) S type2]
 /// ```
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Type1<'a> {
   /// Type
@@ -778,7 +804,7 @@ pub struct Type1<'a> {
   pub span: Span,
 
   #[cfg(feature = "ast-comments")]
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   pub comments_after_type: Option<Comments<'a>>,
 }
@@ -836,7 +862,8 @@ impl<'a> From<Value<'a>> for Type1<'a> {
   }
 }
 
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 /// Range or control operator
 pub struct Operator<'a> {
@@ -846,11 +873,11 @@ pub struct Operator<'a> {
   pub type2: Type2<'a>,
 
   #[cfg(feature = "ast-comments")]
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   pub comments_before_operator: Option<Comments<'a>>,
   #[cfg(feature = "ast-comments")]
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   pub comments_after_operator: Option<Comments<'a>>,
 }
@@ -911,7 +938,8 @@ impl<'a> fmt::Display for Type1<'a> {
 /// rangeop = "..." / ".."
 /// ctlop = "." id
 /// ```
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub enum RangeCtlOp<'a> {
   /// Range operator
@@ -925,7 +953,7 @@ pub enum RangeCtlOp<'a> {
   /// Control operator
   CtlOp {
     /// Control identifier
-    ctrl: &'a str,
+    ctrl: Cow<'a, str>,
     /// Span
     #[cfg(feature = "ast-span")]
     span: Span,
@@ -962,7 +990,8 @@ impl<'a> fmt::Display for RangeCtlOp<'a> {
 ///     / "#" DIGIT ["." uint]                ; major/ai
 ///     / "#"                                 ; any
 /// ```
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum Type2<'a> {
   /// Integer value
@@ -1048,11 +1077,11 @@ pub enum Type2<'a> {
     span: Span,
 
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_before_type: Option<Comments<'a>>,
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_after_type: Option<Comments<'a>>,
   },
@@ -1066,11 +1095,11 @@ pub enum Type2<'a> {
     span: Span,
 
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_before_group: Option<Comments<'a>>,
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_after_group: Option<Comments<'a>>,
   },
@@ -1084,11 +1113,11 @@ pub enum Type2<'a> {
     span: Span,
 
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_before_group: Option<Comments<'a>>,
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_after_group: Option<Comments<'a>>,
   },
@@ -1104,7 +1133,7 @@ pub enum Type2<'a> {
     span: Span,
 
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments: Option<Comments<'a>>,
   },
@@ -1118,15 +1147,15 @@ pub enum Type2<'a> {
     span: Span,
 
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments: Option<Comments<'a>>,
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_before_group: Option<Comments<'a>>,
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_after_group: Option<Comments<'a>>,
   },
@@ -1142,7 +1171,7 @@ pub enum Type2<'a> {
     span: Span,
 
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments: Option<Comments<'a>>,
   },
@@ -1152,6 +1181,9 @@ pub enum Type2<'a> {
   TaggedData {
     /// Tag
     tag: Option<usize>,
+    /// Type the tag number is an instance of, if given as a type instead of a
+    /// number, e.g. `#6.<1000..1999>(tstr)` or `#6.<n>(t)` in a generic rule
+    tag_type: Option<Box<Type<'a>>>,
     /// Type
     t: Type<'a>,
     /// Span
@@ -1159,11 +1191,11 @@ pub enum Type2<'a> {
     span: Span,
 
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_before_type: Option<Comments<'a>>,
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_after_type: Option<Comments<'a>>,
   },
@@ -1194,7 +1226,8 @@ impl<'a> fmt::Display for Type2<'a> {
     match self {
       Type2::IntValue { value, .. } => write!(f, "{}", value),
       Type2::UintValue { value, .. } => write!(f, "{}", value),
-      Type2::FloatValue { value, .. } => write!(f, "{}", value),
+      // Floats keep their fraction so they aren't read back as integers
+      Type2::FloatValue { value, .. } => write!(f, "{:?}", value),
       Type2::TextValue { value, .. } => write!(f, "\"{}\"", value),
       Type2::UTF8ByteString { value, .. } => write!(
         f,
@@ -1380,7 +1413,7 @@ impl<'a> fmt::Display for Type2<'a> {
         generic_args,
         ..
       } => {
-        let mut t2_str = String::new();
+        let mut t2_str = String::from("~");
 
         #[cfg(feature = "ast-comments")]
         if let Some(comments) = comments {
@@ -1458,6 +1491,7 @@ impl<'a> fmt::Display for Type2<'a> {
       }
       Type2::TaggedData {
         tag,
+        tag_type,
         #[cfg(feature = "ast-comments")]
         comments_before_type,
         t,
@@ -1469,6 +1503,8 @@ impl<'a> fmt::Display for Type2<'a> {
 
         if let Some(tag_uint) = tag {
           t2_str.push_str(&format!(".{}", tag_uint));
+        } else if let Some(tag_type) = tag_type {
+          t2_str.push_str(&format!(".<{}>", tag_type));
         }
 
         t2_str.push('(');
@@ -1516,7 +1552,7 @@ impl<'a> From<RangeValue<'a>> for Type2<'a> {
     match rv {
       RangeValue::IDENT(ident, socket) => Type2::Typename {
         ident: Identifier {
-          ident,
+          ident: ident.into(),
           socket,
           #[cfg(feature = "ast-span")]
           span,
@@ -1646,6 +1682,7 @@ pub fn tag_from_token<'a>(token: &Token) -> Option<Type2<'a>> {
   match token {
     Token::TDATE => Some(Type2::TaggedData {
       tag: Some(0),
+      tag_type: None,
       t: type_from_token(Token::TSTR),
       #[cfg(feature = "ast-comments")]
       comments_after_type: None,
@@ -1656,6 +1693,7 @@ pub fn tag_from_token<'a>(token: &Token) -> Option<Type2<'a>> {
     }),
     Token::TIME => Some(Type2::TaggedData {
       tag: Some(1),
+      tag_type: None,
       t: type_from_token(Token::NUMBER),
       #[cfg(feature = "ast-comments")]
       comments_before_type: None,
@@ -1666,6 +1704,7 @@ pub fn tag_from_token<'a>(token: &Token) -> Option<Type2<'a>> {
     }),
     Token::BIGUINT => Some(Type2::TaggedData {
       tag: Some(2),
+      tag_type: None,
       t: type_from_token(Token::BSTR),
       #[cfg(feature = "ast-comments")]
       comments_before_type: None,
@@ -1676,6 +1715,7 @@ pub fn tag_from_token<'a>(token: &Token) -> Option<Type2<'a>> {
     }),
     Token::BIGNINT => Some(Type2::TaggedData {
       tag: Some(3),
+      tag_type: None,
       t: type_from_token(Token::BSTR),
       #[cfg(feature = "ast-comments")]
       comments_before_type: None,
@@ -1688,6 +1728,7 @@ pub fn tag_from_token<'a>(token: &Token) -> Option<Type2<'a>> {
     Token::BIGFLOAT => unimplemented!(),
     Token::EB64URL => Some(Type2::TaggedData {
       tag: Some(21),
+      tag_type: None,
       t: type_from_token(Token::ANY),
       #[cfg(feature = "ast-comments")]
       comments_before_type: None,
@@ -1698,6 +1739,7 @@ pub fn tag_from_token<'a>(token: &Token) -> Option<Type2<'a>> {
     }),
     Token::EB64LEGACY => Some(Type2::TaggedData {
       tag: Some(22),
+      tag_type: None,
       t: type_from_token(Token::ANY),
       #[cfg(feature = "ast-comments")]
       comments_before_type: None,
@@ -1708,6 +1750,7 @@ pub fn tag_from_token<'a>(token: &Token) -> Option<Type2<'a>> {
     }),
     Token::EB16 => Some(Type2::TaggedData {
       tag: Some(23),
+      tag_type: None,
       t: type_from_token(Token::ANY),
       #[cfg(feature = "ast-comments")]
       comments_before_type: None,
@@ -1718,6 +1761,7 @@ pub fn tag_from_token<'a>(token: &Token) -> Option<Type2<'a>> {
     }),
     Token::ENCODEDCBOR => Some(Type2::TaggedData {
       tag: Some(24),
+      tag_type: None,
       t: type_from_token(Token::BSTR),
       #[cfg(feature = "ast-comments")]
       comments_before_type: None,
@@ -1728,6 +1772,7 @@ pub fn tag_from_token<'a>(token: &Token) -> Option<Type2<'a>> {
     }),
     Token::URI => Some(Type2::TaggedData {
       tag: Some(32),
+      tag_type: None,
       t: type_from_token(Token::TSTR),
       #[cfg(feature = "ast-comments")]
       comments_before_type: None,
@@ -1738,6 +1783,7 @@ pub fn tag_from_token<'a>(token: &Token) -> Option<Type2<'a>> {
     }),
     Token::B64URL => Some(Type2::TaggedData {
       tag: Some(33),
+      tag_type: None,
       t: type_from_token(Token::TSTR),
       #[cfg(feature = "ast-comments")]
       comments_before_type: None,
@@ -1748,6 +1794,7 @@ pub fn tag_from_token<'a>(token: &Token) -> Option<Type2<'a>> {
     }),
     Token::B64LEGACY => Some(Type2::TaggedData {
       tag: Some(34),
+      tag_type: None,
       t: type_from_token(Token::TSTR),
       #[cfg(feature = "ast-comments")]
       comments_before_type: None,
@@ -1758,6 +1805,7 @@ pub fn tag_from_token<'a>(token: &Token) -> Option<Type2<'a>> {
     }),
     Token::REGEXP => Some(Type2::TaggedData {
       tag: Some(35),
+      tag_type: None,
       t: type_from_token(Token::TSTR),
       #[cfg(feature = "ast-comments")]
       comments_before_type: None,
@@ -1768,6 +1816,7 @@ pub fn tag_from_token<'a>(token: &Token) -> Option<Type2<'a>> {
     }),
     Token::MIMEMESSAGE => Some(Type2::TaggedData {
       tag: Some(36),
+      tag_type: None,
       t: type_from_token(Token::TSTR),
       #[cfg(feature = "ast-comments")]
       comments_before_type: None,
@@ -1778,6 +1827,7 @@ pub fn tag_from_token<'a>(token: &Token) -> Option<Type2<'a>> {
     }),
     Token::CBORANY => Some(Type2::TaggedData {
       tag: Some(55799),
+      tag_type: None,
       t: type_from_token(Token::ANY),
       #[cfg(feature = "ast-comments")]
       comments_before_type: None,
@@ -1822,11 +1872,11 @@ pub fn type_from_token(token: Token) -> Type {
 /// ```abnf
 /// group = grpchoice * (S "//" S grpchoice)
 /// ```
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Group<'a> {
   /// Group choices
-  #[cfg_attr(target_arch = "wasm32", serde(borrow))]
   pub group_choices: Vec<GroupChoice<'a>>,
   /// Span
   #[cfg(feature = "ast-span")]
@@ -1916,12 +1966,12 @@ impl<'a> fmt::Display for Group<'a> {
 /// ```
 ///
 /// If tuple is true, then entry is marked by a trailing comma
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct GroupChoice<'a> {
   /// Group entries where the second item in the tuple indicates where or not a
   /// trailing comma is present
-  #[cfg_attr(target_arch = "wasm32", serde(borrow))]
   pub group_entries: Vec<(GroupEntry<'a>, OptionalComma<'a>)>,
   /// Span
   #[cfg(feature = "ast-span")]
@@ -1930,7 +1980,7 @@ pub struct GroupChoice<'a> {
   // No trailing comments since these will be captured by the S ["," S] matching
   // rule
   #[cfg(feature = "ast-comments")]
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   pub comments_before_grpchoice: Option<Comments<'a>>,
 }
@@ -2156,24 +2206,24 @@ impl<'a> fmt::Display for GroupChoice<'a> {
 ///       / [occur S] groupname [genericarg]  ; preempted by above
 ///       / [occur S] "(" S group S ")"
 /// ```
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum GroupEntry<'a> {
   /// Value group entry type
   ValueMemberKey {
     /// Group entry
-    #[cfg_attr(target_arch = "wasm32", serde(borrow))]
     ge: Box<ValueMemberKeyEntry<'a>>,
     /// Span
     #[cfg(feature = "ast-span")]
     span: Span,
 
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     leading_comments: Option<Comments<'a>>,
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     trailing_comments: Option<Comments<'a>>,
   },
@@ -2181,18 +2231,17 @@ pub enum GroupEntry<'a> {
   /// Group entry from a named group or type
   TypeGroupname {
     /// Group entry
-    #[cfg_attr(target_arch = "wasm32", serde(borrow))]
     ge: TypeGroupnameEntry<'a>,
     /// span
     #[cfg(feature = "ast-span")]
     span: Span,
 
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     leading_comments: Option<Comments<'a>>,
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     trailing_comments: Option<Comments<'a>>,
   },
@@ -2208,17 +2257,27 @@ pub enum GroupEntry<'a> {
     span: Span,
 
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_before_group: Option<Comments<'a>>,
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_after_group: Option<Comments<'a>>,
   },
 }
 
 impl<'a> GroupEntry<'a> {
+  /// Return `Span` for `GroupEntry`
+  #[cfg(feature = "ast-span")]
+  pub fn span(&self) -> Span {
+    match self {
+      GroupEntry::ValueMemberKey { span, .. } => *span,
+      GroupEntry::TypeGroupname { span, .. } => *span,
+      GroupEntry::InlineGroup { span, .. } => *span,
+    }
+  }
+
   #[cfg(feature = "ast-comments")]
   fn has_trailing_comments(&self) -> bool {
     matches!(self,
@@ -2235,14 +2294,15 @@ impl<'a> GroupEntry<'a> {
 }
 
 /// Optional comma
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct OptionalComma<'a> {
   /// Optional comma
   pub optional_comma: bool,
 
   #[cfg(feature = "ast-comments")]
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   pub trailing_comments: Option<Comments<'a>>,
 
@@ -2267,7 +2327,7 @@ impl<'a> fmt::Display for OptionalComma<'a> {
           }
         }
 
-        for (idx, &comment) in comments.0.iter().enumerate() {
+        for (idx, comment) in comments.0.iter().enumerate() {
           if idx == 0 && comment != "\n" {
             optcomma_str.push_str(&format!(";{}\n", comment));
           } else if idx == 0 {
@@ -2429,14 +2489,15 @@ impl<'a> fmt::Display for GroupEntry<'a> {
 }
 
 /// Occurrence indicator
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Occurrence<'a> {
   /// Occurrence indicator
   pub occur: Occur,
 
   #[cfg(feature = "ast-comments")]
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   pub comments: Option<Comments<'a>>,
 
@@ -2466,7 +2527,8 @@ impl<'a> fmt::Display for Occurrence<'a> {
 /// ```abnf
 /// [occur S] [memberkey S] type
 /// ```
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct ValueMemberKeyEntry<'a> {
   /// Optional occurrence indicator
@@ -2474,7 +2536,6 @@ pub struct ValueMemberKeyEntry<'a> {
   /// Optional member key
   pub member_key: Option<MemberKey<'a>>,
   /// Entry type
-  #[cfg_attr(target_arch = "wasm32", serde(borrow))]
   pub entry_type: Type<'a>,
 }
 
@@ -2497,13 +2558,13 @@ impl<'a> fmt::Display for ValueMemberKeyEntry<'a> {
 }
 
 /// Group entry from a named type or group
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypeGroupnameEntry<'a> {
   /// Optional occurrence indicator
   pub occur: Option<Occurrence<'a>>,
   /// Type or group name identifier
-  #[cfg_attr(target_arch = "wasm32", serde(borrow))]
   pub name: Identifier<'a>,
   /// Optional generic arguments
   pub generic_args: Option<GenericArgs<'a>>,
@@ -2533,13 +2594,13 @@ impl<'a> fmt::Display for TypeGroupnameEntry<'a> {
 ///           / bareword S ":"
 ///           / value S ":"
 /// ```
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum MemberKey<'a> {
   /// Type expression
   Type1 {
     /// Type1
-    #[cfg_attr(target_arch = "wasm32", serde(borrow))]
     t1: Box<Type1<'a>>,
     /// Is cut indicator present
     is_cut: bool,
@@ -2548,15 +2609,15 @@ pub enum MemberKey<'a> {
     span: Span,
 
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_before_cut: Option<Comments<'a>>,
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_after_cut: Option<Comments<'a>>,
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_after_arrowmap: Option<Comments<'a>>,
   },
@@ -2564,42 +2625,41 @@ pub enum MemberKey<'a> {
   /// Bareword string type
   Bareword {
     /// Identifier
-    #[cfg_attr(target_arch = "wasm32", serde(borrow))]
     ident: Identifier<'a>,
     /// Span
     #[cfg(feature = "ast-span")]
     span: Span,
 
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments: Option<Comments<'a>>,
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_after_colon: Option<Comments<'a>>,
   },
 
-  /// Value type
+  /// Value followed by the colon shortcut, which implies a cut. Values followed
+  /// by "=>" are parsed as type expressions
   Value {
     /// Value
-    #[cfg_attr(target_arch = "wasm32", serde(borrow))]
     value: Value<'a>,
     /// Span
     #[cfg(feature = "ast-span")]
     span: Span,
 
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments: Option<Comments<'a>>,
     #[cfg(feature = "ast-comments")]
-    #[cfg_attr(target_arch = "wasm32", serde(skip))]
+    #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
     #[doc(hidden)]
     comments_after_colon: Option<Comments<'a>>,
   },
 
-  #[cfg_attr(target_arch = "wasm32", serde(skip))]
+  #[cfg_attr(all(target_arch = "wasm32", not(feature = "ast-serde")), serde(skip))]
   #[doc(hidden)]
   NonMemberKey {
     non_member_key: NonMemberKey<'a>,
@@ -2610,6 +2670,7 @@ pub enum MemberKey<'a> {
   },
 }
 
+#[cfg_attr(feature = "ast-serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 #[doc(hidden)]
 pub enum NonMemberKey<'a> {
@@ -2774,7 +2835,8 @@ impl<'a> fmt::Display for MemberKey<'a> {
 ///       / "+"
 ///       / "?"
 /// ```
-#[cfg_attr(target_arch = "wasm32", derive(Serialize))]
+#[cfg_attr(any(target_arch = "wasm32", feature = "ast-serde"), derive(Serialize))]
+#[cfg_attr(feature = "ast-serde", derive(Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub enum Occur {
   /// Occurrence indicator in the form n*m, where n is an optional lower limit
@@ -2848,6 +2910,662 @@ impl fmt::Display for Occur {
   }
 }
 
+// Conversions of the AST into owned values that don't borrow from the source
+// input, e.g. for caching a parsed document or sending it across threads
+
+fn owned_str(s: Cow<str>) -> Cow<'static, str> {
+  Cow::Owned(s.into_owned())
+}
+
+fn owned_bytes(b: Cow<[u8]>) -> Cow<'static, [u8]> {
+  Cow::Owned(b.into_owned())
+}
+
+#[cfg(feature = "ast-comments")]
+fn owned_comments(comments: Option<Comments>) -> Option<Comments<'static>> {
+  comments.map(Comments::into_owned)
+}
+
+#[cfg(feature = "ast-comments")]
+impl<'a> Comments<'a> {
+  /// Convert into owned comments
+  pub fn into_owned(self) -> Comments<'static> {
+    Comments(self.0.into_iter().map(owned_str).collect())
+  }
+}
+
+impl<'a> CDDL<'a> {
+  /// Convert into an owned document that doesn't borrow from the input it was
+  /// parsed from, so it can outlive the input, e.g. to be cached or sent to
+  /// another thread
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use cddl::{ast::CDDL, cddl_from_str};
+  ///
+  /// fn parse(input: String) -> CDDL<'static> {
+  ///   cddl_from_str(&input, true).unwrap().into_owned()
+  /// }
+  ///
+  /// let cddl = parse("message = { id: uint, body: tstr .size 64 }".to_string());
+  /// let handle = std::thread::spawn(move || cddl.rules.len());
+  /// assert_eq!(handle.join().unwrap(), 1);
+  /// ```
+  pub fn into_owned(self) -> CDDL<'static> {
+    CDDL {
+      rules: self.rules.into_iter().map(Rule::into_owned).collect(),
+      #[cfg(feature = "ast-comments")]
+      comments: owned_comments(self.comments),
+    }
+  }
+}
+
+impl<'a> Identifier<'a> {
+  /// Convert into an owned identifier
+  pub fn into_owned(self) -> Identifier<'static> {
+    Identifier {
+      ident: owned_str(self.ident),
+      socket: self.socket,
+      #[cfg(feature = "ast-span")]
+      span: self.span,
+    }
+  }
+}
+
+impl<'a> Rule<'a> {
+  /// Convert into an owned rule
+  pub fn into_owned(self) -> Rule<'static> {
+    match self {
+      Rule::Type {
+        rule,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_after_rule,
+      } => Rule::Type {
+        rule: rule.into_owned(),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_after_rule: owned_comments(comments_after_rule),
+      },
+      Rule::Group {
+        rule,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_after_rule,
+      } => Rule::Group {
+        rule: Box::new(rule.into_owned()),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_after_rule: owned_comments(comments_after_rule),
+      },
+    }
+  }
+}
+
+impl<'a> TypeRule<'a> {
+  /// Convert into an owned type rule
+  pub fn into_owned(self) -> TypeRule<'static> {
+    TypeRule {
+      name: self.name.into_owned(),
+      generic_params: self.generic_params.map(GenericParams::into_owned),
+      is_type_choice_alternate: self.is_type_choice_alternate,
+      value: self.value.into_owned(),
+      #[cfg(feature = "ast-comments")]
+      comments_before_assignt: owned_comments(self.comments_before_assignt),
+      #[cfg(feature = "ast-comments")]
+      comments_after_assignt: owned_comments(self.comments_after_assignt),
+    }
+  }
+}
+
+impl<'a> GroupRule<'a> {
+  /// Convert into an owned group rule
+  pub fn into_owned(self) -> GroupRule<'static> {
+    GroupRule {
+      name: self.name.into_owned(),
+      generic_params: self.generic_params.map(GenericParams::into_owned),
+      is_group_choice_alternate: self.is_group_choice_alternate,
+      entry: self.entry.into_owned(),
+      #[cfg(feature = "ast-comments")]
+      comments_before_assigng: owned_comments(self.comments_before_assigng),
+      #[cfg(feature = "ast-comments")]
+      comments_after_assigng: owned_comments(self.comments_after_assigng),
+    }
+  }
+}
+
+impl<'a> GenericParams<'a> {
+  /// Convert into owned generic parameters
+  pub fn into_owned(self) -> GenericParams<'static> {
+    GenericParams {
+      params: self
+        .params
+        .into_iter()
+        .map(|p| GenericParam {
+          param: p.param.into_owned(),
+          #[cfg(feature = "ast-comments")]
+          comments_before_ident: owned_comments(p.comments_before_ident),
+          #[cfg(feature = "ast-comments")]
+          comments_after_ident: owned_comments(p.comments_after_ident),
+        })
+        .collect(),
+      #[cfg(feature = "ast-span")]
+      span: self.span,
+    }
+  }
+}
+
+impl<'a> GenericArgs<'a> {
+  /// Convert into owned generic arguments
+  pub fn into_owned(self) -> GenericArgs<'static> {
+    GenericArgs {
+      args: self
+        .args
+        .into_iter()
+        .map(|a| GenericArg {
+          arg: Box::new(a.arg.into_owned()),
+          #[cfg(feature = "ast-comments")]
+          comments_before_type: owned_comments(a.comments_before_type),
+          #[cfg(feature = "ast-comments")]
+          comments_after_type: owned_comments(a.comments_after_type),
+        })
+        .collect(),
+      #[cfg(feature = "ast-span")]
+      span: self.span,
+    }
+  }
+}
+
+impl<'a> Type<'a> {
+  /// Convert into an owned type
+  pub fn into_owned(self) -> Type<'static> {
+    Type {
+      type_choices: self
+        .type_choices
+        .into_iter()
+        .map(|tc| TypeChoice {
+          type1: tc.type1.into_owned(),
+          #[cfg(feature = "ast-comments")]
+          comments_before_type: owned_comments(tc.comments_before_type),
+          #[cfg(feature = "ast-comments")]
+          comments_after_type: owned_comments(tc.comments_after_type),
+        })
+        .collect(),
+      #[cfg(feature = "ast-span")]
+      span: self.span,
+    }
+  }
+}
+
+impl<'a> Type1<'a> {
+  /// Convert into an owned type
+  pub fn into_owned(self) -> Type1<'static> {
+    Type1 {
+      type2: self.type2.into_owned(),
+      operator: self.operator.map(|o| Operator {
+        operator: match o.operator {
+          RangeCtlOp::RangeOp {
+            is_inclusive,
+            #[cfg(feature = "ast-span")]
+            span,
+          } => RangeCtlOp::RangeOp {
+            is_inclusive,
+            #[cfg(feature = "ast-span")]
+            span,
+          },
+          RangeCtlOp::CtlOp {
+            ctrl,
+            #[cfg(feature = "ast-span")]
+            span,
+          } => RangeCtlOp::CtlOp {
+            ctrl: owned_str(ctrl),
+            #[cfg(feature = "ast-span")]
+            span,
+          },
+        },
+        type2: o.type2.into_owned(),
+        #[cfg(feature = "ast-comments")]
+        comments_before_operator: owned_comments(o.comments_before_operator),
+        #[cfg(feature = "ast-comments")]
+        comments_after_operator: owned_comments(o.comments_after_operator),
+      }),
+      #[cfg(feature = "ast-span")]
+      span: self.span,
+      #[cfg(feature = "ast-comments")]
+      comments_after_type: owned_comments(self.comments_after_type),
+    }
+  }
+}
+
+impl<'a> Type2<'a> {
+  /// Convert into an owned type
+  pub fn into_owned(self) -> Type2<'static> {
+    match self {
+      Type2::IntValue {
+        value,
+        #[cfg(feature = "ast-span")]
+        span,
+      } => Type2::IntValue {
+        value,
+        #[cfg(feature = "ast-span")]
+        span,
+      },
+      Type2::UintValue {
+        value,
+        #[cfg(feature = "ast-span")]
+        span,
+      } => Type2::UintValue {
+        value,
+        #[cfg(feature = "ast-span")]
+        span,
+      },
+      Type2::FloatValue {
+        value,
+        #[cfg(feature = "ast-span")]
+        span,
+      } => Type2::FloatValue {
+        value,
+        #[cfg(feature = "ast-span")]
+        span,
+      },
+      Type2::TextValue {
+        value,
+        #[cfg(feature = "ast-span")]
+        span,
+      } => Type2::TextValue {
+        value: owned_str(value),
+        #[cfg(feature = "ast-span")]
+        span,
+      },
+      Type2::UTF8ByteString {
+        value,
+        #[cfg(feature = "ast-span")]
+        span,
+      } => Type2::UTF8ByteString {
+        value: owned_bytes(value),
+        #[cfg(feature = "ast-span")]
+        span,
+      },
+      Type2::B16ByteString {
+        value,
+        #[cfg(feature = "ast-span")]
+        span,
+      } => Type2::B16ByteString {
+        value: owned_bytes(value),
+        #[cfg(feature = "ast-span")]
+        span,
+      },
+      Type2::B64ByteString {
+        value,
+        #[cfg(feature = "ast-span")]
+        span,
+      } => Type2::B64ByteString {
+        value: owned_bytes(value),
+        #[cfg(feature = "ast-span")]
+        span,
+      },
+      Type2::Typename {
+        ident,
+        generic_args,
+        #[cfg(feature = "ast-span")]
+        span,
+      } => Type2::Typename {
+        ident: ident.into_owned(),
+        generic_args: generic_args.map(GenericArgs::into_owned),
+        #[cfg(feature = "ast-span")]
+        span,
+      },
+      Type2::ParenthesizedType {
+        pt,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_before_type,
+        #[cfg(feature = "ast-comments")]
+        comments_after_type,
+      } => Type2::ParenthesizedType {
+        pt: pt.into_owned(),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_before_type: owned_comments(comments_before_type),
+        #[cfg(feature = "ast-comments")]
+        comments_after_type: owned_comments(comments_after_type),
+      },
+      Type2::Map {
+        group,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_before_group,
+        #[cfg(feature = "ast-comments")]
+        comments_after_group,
+      } => Type2::Map {
+        group: group.into_owned(),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_before_group: owned_comments(comments_before_group),
+        #[cfg(feature = "ast-comments")]
+        comments_after_group: owned_comments(comments_after_group),
+      },
+      Type2::Array {
+        group,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_before_group,
+        #[cfg(feature = "ast-comments")]
+        comments_after_group,
+      } => Type2::Array {
+        group: group.into_owned(),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_before_group: owned_comments(comments_before_group),
+        #[cfg(feature = "ast-comments")]
+        comments_after_group: owned_comments(comments_after_group),
+      },
+      Type2::Unwrap {
+        ident,
+        generic_args,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments,
+      } => Type2::Unwrap {
+        ident: ident.into_owned(),
+        generic_args: generic_args.map(GenericArgs::into_owned),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments: owned_comments(comments),
+      },
+      Type2::ChoiceFromInlineGroup {
+        group,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments,
+        #[cfg(feature = "ast-comments")]
+        comments_before_group,
+        #[cfg(feature = "ast-comments")]
+        comments_after_group,
+      } => Type2::ChoiceFromInlineGroup {
+        group: group.into_owned(),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments: owned_comments(comments),
+        #[cfg(feature = "ast-comments")]
+        comments_before_group: owned_comments(comments_before_group),
+        #[cfg(feature = "ast-comments")]
+        comments_after_group: owned_comments(comments_after_group),
+      },
+      Type2::ChoiceFromGroup {
+        ident,
+        generic_args,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments,
+      } => Type2::ChoiceFromGroup {
+        ident: ident.into_owned(),
+        generic_args: generic_args.map(GenericArgs::into_owned),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments: owned_comments(comments),
+      },
+      Type2::TaggedData {
+        tag,
+        tag_type,
+        t,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_before_type,
+        #[cfg(feature = "ast-comments")]
+        comments_after_type,
+      } => Type2::TaggedData {
+        tag,
+        tag_type: tag_type.map(|t| Box::new(t.into_owned())),
+        t: t.into_owned(),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_before_type: owned_comments(comments_before_type),
+        #[cfg(feature = "ast-comments")]
+        comments_after_type: owned_comments(comments_after_type),
+      },
+      Type2::DataMajorType {
+        mt,
+        constraint,
+        #[cfg(feature = "ast-span")]
+        span,
+      } => Type2::DataMajorType {
+        mt,
+        constraint,
+        #[cfg(feature = "ast-span")]
+        span,
+      },
+      #[cfg(feature = "ast-span")]
+      Type2::Any(span) => Type2::Any(span),
+      #[cfg(not(feature = "ast-span"))]
+      Type2::Any => Type2::Any,
+    }
+  }
+}
+
+impl<'a> Group<'a> {
+  /// Convert into an owned group
+  pub fn into_owned(self) -> Group<'static> {
+    Group {
+      group_choices: self
+        .group_choices
+        .into_iter()
+        .map(GroupChoice::into_owned)
+        .collect(),
+      #[cfg(feature = "ast-span")]
+      span: self.span,
+    }
+  }
+}
+
+impl<'a> GroupChoice<'a> {
+  /// Convert into an owned group choice
+  pub fn into_owned(self) -> GroupChoice<'static> {
+    GroupChoice {
+      group_entries: self
+        .group_entries
+        .into_iter()
+        .map(|(ge, oc)| {
+          (
+            ge.into_owned(),
+            OptionalComma {
+              optional_comma: oc.optional_comma,
+              #[cfg(feature = "ast-comments")]
+              trailing_comments: owned_comments(oc.trailing_comments),
+              _a: PhantomData,
+            },
+          )
+        })
+        .collect(),
+      #[cfg(feature = "ast-span")]
+      span: self.span,
+      #[cfg(feature = "ast-comments")]
+      comments_before_grpchoice: owned_comments(self.comments_before_grpchoice),
+    }
+  }
+}
+
+impl<'a> GroupEntry<'a> {
+  /// Convert into an owned group entry
+  pub fn into_owned(self) -> GroupEntry<'static> {
+    match self {
+      GroupEntry::ValueMemberKey {
+        ge,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        leading_comments,
+        #[cfg(feature = "ast-comments")]
+        trailing_comments,
+      } => GroupEntry::ValueMemberKey {
+        ge: Box::new(ValueMemberKeyEntry {
+          occur: ge.occur.map(Occurrence::into_owned),
+          member_key: ge.member_key.map(MemberKey::into_owned),
+          entry_type: ge.entry_type.into_owned(),
+        }),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        leading_comments: owned_comments(leading_comments),
+        #[cfg(feature = "ast-comments")]
+        trailing_comments: owned_comments(trailing_comments),
+      },
+      GroupEntry::TypeGroupname {
+        ge,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        leading_comments,
+        #[cfg(feature = "ast-comments")]
+        trailing_comments,
+      } => GroupEntry::TypeGroupname {
+        ge: TypeGroupnameEntry {
+          occur: ge.occur.map(Occurrence::into_owned),
+          name: ge.name.into_owned(),
+          generic_args: ge.generic_args.map(GenericArgs::into_owned),
+        },
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        leading_comments: owned_comments(leading_comments),
+        #[cfg(feature = "ast-comments")]
+        trailing_comments: owned_comments(trailing_comments),
+      },
+      GroupEntry::InlineGroup {
+        occur,
+        group,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_before_group,
+        #[cfg(feature = "ast-comments")]
+        comments_after_group,
+      } => GroupEntry::InlineGroup {
+        occur: occur.map(Occurrence::into_owned),
+        group: group.into_owned(),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_before_group: owned_comments(comments_before_group),
+        #[cfg(feature = "ast-comments")]
+        comments_after_group: owned_comments(comments_after_group),
+      },
+    }
+  }
+}
+
+impl<'a> Occurrence<'a> {
+  /// Convert into an owned occurrence indicator
+  pub fn into_owned(self) -> Occurrence<'static> {
+    Occurrence {
+      occur: self.occur,
+      #[cfg(feature = "ast-comments")]
+      comments: owned_comments(self.comments),
+      _a: PhantomData,
+    }
+  }
+}
+
+impl<'a> MemberKey<'a> {
+  /// Convert into an owned member key
+  pub fn into_owned(self) -> MemberKey<'static> {
+    match self {
+      MemberKey::Type1 {
+        t1,
+        is_cut,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_before_cut,
+        #[cfg(feature = "ast-comments")]
+        comments_after_cut,
+        #[cfg(feature = "ast-comments")]
+        comments_after_arrowmap,
+      } => MemberKey::Type1 {
+        t1: Box::new(t1.into_owned()),
+        is_cut,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_before_cut: owned_comments(comments_before_cut),
+        #[cfg(feature = "ast-comments")]
+        comments_after_cut: owned_comments(comments_after_cut),
+        #[cfg(feature = "ast-comments")]
+        comments_after_arrowmap: owned_comments(comments_after_arrowmap),
+      },
+      MemberKey::Bareword {
+        ident,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments,
+        #[cfg(feature = "ast-comments")]
+        comments_after_colon,
+      } => MemberKey::Bareword {
+        ident: ident.into_owned(),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments: owned_comments(comments),
+        #[cfg(feature = "ast-comments")]
+        comments_after_colon: owned_comments(comments_after_colon),
+      },
+      MemberKey::Value {
+        value,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments,
+        #[cfg(feature = "ast-comments")]
+        comments_after_colon,
+      } => MemberKey::Value {
+        value: value.into_owned(),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments: owned_comments(comments),
+        #[cfg(feature = "ast-comments")]
+        comments_after_colon: owned_comments(comments_after_colon),
+      },
+      MemberKey::NonMemberKey {
+        non_member_key,
+        #[cfg(feature = "ast-comments")]
+        comments_before_type_or_group,
+        #[cfg(feature = "ast-comments")]
+        comments_after_type_or_group,
+      } => MemberKey::NonMemberKey {
+        non_member_key: match non_member_key {
+          NonMemberKey::Group(group) => NonMemberKey::Group(group.into_owned()),
+          NonMemberKey::Type(t) => NonMemberKey::Type(t.into_owned()),
+        },
+        #[cfg(feature = "ast-comments")]
+        comments_before_type_or_group: owned_comments(comments_before_type_or_group),
+        #[cfg(feature = "ast-comments")]
+        comments_after_type_or_group: owned_comments(comments_after_type_or_group),
+      },
+    }
+  }
+}
+
 #[cfg(test)]
 #[allow(unused_imports)]
 #[cfg(feature = "ast-comments")]
@@ -5,15 +5,40 @@
 #[macro_use]
 extern crate log;
 
-use cddl::{cddl_from_str, validate_cbor_from_slice, validate_json_from_str};
-use clap::{ArgGroup, Args, Parser, Subcommand};
+#[cfg(feature = "ast-span")]
+use cddl::docs::Docs;
+#[cfg(feature = "templates")]
+use cddl::template::Model;
+#[cfg(feature = "control-plugins")]
+use cddl::validator::plugin::ControlPlugins;
+use cddl::{
+  anonymize::Anonymizer,
+  ast::{GenericArgs, Identifier, Type2, CDDL},
+  cddl_from_str,
+  diag::cbor_from_diag,
+  diff::diff_from_str,
+  distill::{distill, Coverage},
+  generator::Target,
+  json_schema::cddl_from_json_schema,
+  lexer_from_str,
+  lint::{report_diagnostics, Severity},
+  order::RuleOrder,
+  parser::Parser as CDDLParser,
+  replay::{Instance, Options as ReplayOptions, Session},
+  validator::{cbor, json, DisabledFeatureBehavior, FeatureUse, Validator},
+};
+use clap::{ArgEnum, ArgGroup, Args, Parser, Subcommand};
 
 use simplelog::*;
 use std::{
+  borrow::Cow,
   error::Error,
   fs::{self, File},
-  io::{self, BufReader, Read},
+  io::{self, BufRead, BufReader, Read, Write},
+  panic,
   path::Path,
+  thread,
+  time::{Duration, Instant},
 };
 
 #[derive(Parser)]
@@ -36,6 +61,165 @@ enum Commands {
     file: String,
   },
   Validate(Validate),
+  #[clap(
+    name = "diff",
+    about = "Report semantic differences between two CDDL documents. Exits with a non-zero status if any change is not backwards-compatible"
+  )]
+  Diff {
+    #[clap(help = "Path to the original CDDL document")]
+    old: String,
+    #[clap(help = "Path to the updated CDDL document")]
+    new: String,
+  },
+  #[clap(
+    name = "replay",
+    about = "Reproduce a validation session recorded with `validate --record`, printing its outcome. Exits with a non-zero status if the outcome differs from the recorded one"
+  )]
+  Replay {
+    #[clap(help = "Path to the replay file")]
+    file: String,
+    #[clap(
+      long = "trace",
+      help = "Print which type choice matched at each location validated against one, and why the choices preceding it did not"
+    )]
+    trace: bool,
+  },
+  #[clap(
+    name = "shell",
+    about = "Interactively explore a CDDL document, showing its rules and validating instances entered as JSON or CBOR diagnostic notation against them. Enter :help for the available commands"
+  )]
+  Shell {
+    #[clap(short = 'c', long = "cddl", help = "Path to CDDL document to load")]
+    file: Option<String>,
+    #[clap(
+      short = 'f',
+      long = "features",
+      help = "Optional features to enable during validation",
+      use_value_delimiter = true
+    )]
+    features: Option<Vec<String>>,
+  },
+  Bench(Bench),
+  Distill(Distill),
+  Coverage(CoverageArgs),
+  Anonymize(Anonymize),
+  #[clap(
+    name = "lint",
+    about = "Report constructs in a CDDL document that are likely mistakes, such as unused rules, shadowed names, duplicate map keys, ineffective occurrence indicators, ranges with inverted bounds and unsatisfiable constraints. Exits with a non-zero status if any error is reported"
+  )]
+  Lint {
+    #[clap(short = 'c', long = "cddl", help = "Path to CDDL document")]
+    file: String,
+  },
+  #[clap(
+    name = "sort",
+    about = "Print a CDDL document with its rules in a canonical order, starting with the root rule. Comments are not preserved"
+  )]
+  Sort {
+    #[clap(short = 'c', long = "cddl", help = "Path to CDDL document")]
+    file: String,
+    #[clap(
+      long = "order",
+      help = "Order of the rules",
+      arg_enum,
+      default_value = "topological"
+    )]
+    order: SortOrder,
+    #[clap(
+      short = 'o',
+      long = "output",
+      help = "Path to write the sorted document to instead of stdout"
+    )]
+    output: Option<String>,
+  },
+  #[clap(
+    name = "diagram",
+    about = "Render railroad diagrams of the rules of a CDDL document as SVG images"
+  )]
+  Diagram {
+    #[clap(help = "Path to CDDL document")]
+    file: String,
+    #[clap(
+      long = "rule",
+      help = "Rule to render. If omitted, every rule is rendered to its own file in the output directory"
+    )]
+    rule: Option<String>,
+    #[clap(
+      short = 'o',
+      long = "output",
+      help = "Path to write the diagram to instead of stdout, or directory to write the diagrams to, named after their rules, if no rule is given"
+    )]
+    output: Option<String>,
+  },
+  #[clap(
+    name = "from-json-schema",
+    about = "Convert a JSON Schema document to CDDL. Constructs without a CDDL equivalent are dropped or approximated and reported as warnings"
+  )]
+  FromJsonSchema {
+    #[clap(help = "Path to JSON Schema document")]
+    file: String,
+    #[clap(
+      long = "root",
+      help = "Name of the rule for the root schema",
+      default_value = "root"
+    )]
+    root: String,
+    #[clap(
+      short = 'o',
+      long = "output",
+      help = "Path to write the CDDL document to instead of stdout"
+    )]
+    output: Option<String>,
+  },
+  #[cfg(feature = "templates")]
+  #[clap(
+    name = "template",
+    about = "Generate code or other artifacts from a CDDL document by rendering a MiniJinja template with a model of its rules"
+  )]
+  Template {
+    #[clap(short = 'c', long = "cddl", help = "Path to CDDL document")]
+    file: String,
+    #[clap(
+      short = 't',
+      long = "template",
+      help = "Path to the template",
+      required_unless_present = "print-model"
+    )]
+    template: Option<String>,
+    #[clap(
+      long = "print-model",
+      help = "Print the model fed to templates as JSON instead of rendering a template"
+    )]
+    print_model: bool,
+    #[clap(
+      short = 'o',
+      long = "output",
+      help = "Path to write the output to instead of stdout"
+    )]
+    output: Option<String>,
+  },
+  #[cfg(feature = "ast-span")]
+  #[clap(
+    name = "docs",
+    about = "Generate documentation of a CDDL document from the comments describing its rules and group entries"
+  )]
+  Docs {
+    #[clap(short = 'c', long = "cddl", help = "Path to CDDL document")]
+    file: String,
+    #[clap(
+      long = "format",
+      help = "Format of the documentation",
+      arg_enum,
+      default_value = "markdown"
+    )]
+    format: DocsFormat,
+    #[clap(
+      short = 'o',
+      long = "output",
+      help = "Path to write the documentation to instead of stdout"
+    )]
+    output: Option<String>,
+  },
 }
 
 #[derive(Args)]
@@ -51,10 +235,16 @@ struct Validate {
     use_value_delimiter = true
   )]
   features: Option<Vec<String>>,
+  #[cfg(feature = "additional-controls")]
+  #[clap(
+    long = "warn-disabled-features",
+    help = "Accept data matching types guarded by features that aren't enabled, warning about the features used, rather than rejecting it"
+  )]
+  warn_disabled_features: bool,
   #[clap(
     short = 'j',
     long = "json",
-    help = "JSON document(s) to validate",
+    help = "JSON document(s) to validate. Glob patterns (e.g. 'fixtures/**/*.json') are expanded",
     use_value_delimiter = true,
     multiple_values = true
   )]
@@ -62,7 +252,7 @@ struct Validate {
   #[clap(
     short = 'c',
     long = "cbor",
-    help = "CBOR binary file(s) to validate",
+    help = "CBOR binary file(s) to validate. Glob patterns (e.g. 'fixtures/**/*.cbor') are expanded",
     multiple_values = true,
     use_value_delimiter = true
   )]
@@ -72,22 +262,1216 @@ struct Validate {
     help = "JSON or CBOR input from stdin. Assumes UTF-8 encoding is JSON, otherwise parses as CBOR"
   )]
   stdin: bool,
+  #[clap(
+    short = 'r',
+    long = "rule",
+    help = "Rule to validate against instead of the first type rule in the CDDL document. Generic rules are given arguments inline, e.g. \"message<tstr, uint>\""
+  )]
+  rule: Option<String>,
+  #[cfg(feature = "control-plugins")]
+  #[clap(
+    long = "control-plugin",
+    help = "WebAssembly module implementing a control operator, e.g. \".iban=iban.wasm\". Can be given multiple times",
+    multiple_occurrences = true
+  )]
+  control_plugins: Vec<String>,
+  #[clap(
+    long = "jobs",
+    help = "Number of files to validate in parallel. Defaults to the available parallelism"
+  )]
+  jobs: Option<usize>,
+  #[clap(
+    long = "report",
+    help = "Path to write a report of the validated files to"
+  )]
+  report: Option<String>,
+  #[clap(
+    long = "report-format",
+    help = "Format of the report",
+    arg_enum,
+    default_value = "json",
+    requires = "report"
+  )]
+  report_format: ReportFormat,
+  #[clap(
+    long = "record",
+    help = "Path to write a replay file to, capturing the CDDL document, the options, the instance and the decisions taken to validate a single JSON document or CBOR binary file, e.g. to attach to a bug report. Reproduce it with the replay subcommand"
+  )]
+  record: Option<String>,
+  #[clap(
+    long = "redact",
+    help = "Replace the content of the recorded instance with synthetic values, keeping its structure",
+    requires = "record"
+  )]
+  redact: bool,
+}
+
+#[derive(Args)]
+#[clap(
+  about = "Select a small subset of valid JSON documents and/or CBOR binary files that exercises every rule and type choice exercised by all of them, e.g. to build a fast regression suite from production traffic. Prints the paths of the selected files"
+)]
+#[clap(group(ArgGroup::new("targets").required(true).multiple(true).args(&["json", "cbor"])))]
+struct Distill {
+  #[clap(short = 'd', long = "cddl", help = "CDDL document")]
+  cddl: String,
+  #[clap(
+    short = 'f',
+    long = "features",
+    help = "Optional features to enable during validation",
+    use_value_delimiter = true
+  )]
+  features: Option<Vec<String>>,
+  #[clap(
+    short = 'j',
+    long = "json",
+    help = "JSON document(s) to select from. Glob patterns (e.g. 'corpus/**/*.json') are expanded",
+    use_value_delimiter = true,
+    multiple_values = true
+  )]
+  json: Option<Vec<String>>,
+  #[clap(
+    short = 'c',
+    long = "cbor",
+    help = "CBOR binary file(s) to select from. Glob patterns (e.g. 'corpus/**/*.cbor') are expanded",
+    multiple_values = true,
+    use_value_delimiter = true
+  )]
+  cbor: Option<Vec<String>>,
+  #[clap(
+    long = "jobs",
+    help = "Number of files to validate in parallel. Defaults to the available parallelism"
+  )]
+  jobs: Option<usize>,
+}
+
+#[derive(Args)]
+#[clap(
+  name = "coverage",
+  about = "Report which rules, type choices and group choices of a CDDL document are exercised by a corpus of valid JSON documents and/or CBOR binary files, listing those that aren't"
+)]
+#[clap(group(ArgGroup::new("targets").required(true).multiple(true).args(&["json", "cbor"])))]
+struct CoverageArgs {
+  #[clap(short = 'd', long = "cddl", help = "CDDL document")]
+  cddl: String,
+  #[clap(
+    short = 'f',
+    long = "features",
+    help = "Optional features to enable during validation",
+    use_value_delimiter = true
+  )]
+  features: Option<Vec<String>>,
+  #[clap(
+    short = 'j',
+    long = "json",
+    help = "JSON document(s) of the corpus. Glob patterns (e.g. 'corpus/**/*.json') are expanded",
+    use_value_delimiter = true,
+    multiple_values = true
+  )]
+  json: Option<Vec<String>>,
+  #[clap(
+    short = 'c',
+    long = "cbor",
+    help = "CBOR binary file(s) of the corpus. Glob patterns (e.g. 'corpus/**/*.cbor') are expanded",
+    multiple_values = true,
+    use_value_delimiter = true
+  )]
+  cbor: Option<Vec<String>>,
+  #[clap(
+    long = "format",
+    help = "Format of the report",
+    arg_enum,
+    default_value = "text"
+  )]
+  format: CoverageFormat,
+  #[clap(
+    long = "fail-under",
+    help = "Exit with a non-zero status if less than the given percentage of rules and choices is exercised"
+  )]
+  fail_under: Option<f64>,
+  #[clap(
+    long = "jobs",
+    help = "Number of files to validate in parallel. Defaults to the available parallelism"
+  )]
+  jobs: Option<usize>,
+}
+
+#[derive(Args)]
+#[clap(
+  about = "Replace the text strings, byte strings, numbers and map keys of valid JSON documents and/or CBOR binary files with synthetic values that still conform to a CDDL document, e.g. to share realistic test data without leaking production content. Values equal to literals of the CDDL document are kept"
+)]
+#[clap(group(ArgGroup::new("targets").required(true).multiple(true).args(&["json", "cbor"])))]
+struct Anonymize {
+  #[clap(short = 'd', long = "cddl", help = "CDDL document")]
+  cddl: String,
+  #[clap(
+    short = 'f',
+    long = "features",
+    help = "Optional features to enable during validation",
+    use_value_delimiter = true
+  )]
+  features: Option<Vec<String>>,
+  #[clap(
+    short = 'j',
+    long = "json",
+    help = "JSON document(s) to anonymize. Glob patterns (e.g. 'corpus/**/*.json') are expanded",
+    use_value_delimiter = true,
+    multiple_values = true
+  )]
+  json: Option<Vec<String>>,
+  #[clap(
+    short = 'c',
+    long = "cbor",
+    help = "CBOR binary file(s) to anonymize. Glob patterns (e.g. 'corpus/**/*.cbor') are expanded",
+    multiple_values = true,
+    use_value_delimiter = true
+  )]
+  cbor: Option<Vec<String>>,
+  #[clap(
+    short = 'o',
+    long = "output-dir",
+    help = "Directory to write the anonymized files to. Relative paths of the input files are mirrored"
+  )]
+  output_dir: String,
+  #[clap(
+    long = "seed",
+    help = "Seed of the synthetic values, making the output reproducible",
+    default_value = "0"
+  )]
+  seed: u64,
+}
+
+/// Format of the report written by the validate subcommand
+#[derive(Clone, Copy, ArgEnum)]
+enum ReportFormat {
+  Json,
+  Junit,
+}
+
+/// Format of the report printed by the coverage subcommand
+#[derive(Clone, Copy, ArgEnum)]
+enum CoverageFormat {
+  Text,
+  Json,
+}
+
+/// Format of the documentation generated by the docs subcommand
+#[cfg(feature = "ast-span")]
+#[derive(Clone, Copy, ArgEnum)]
+enum DocsFormat {
+  Markdown,
+  Html,
+}
+
+/// Order of the rules printed by the sort subcommand
+#[derive(Clone, Copy, ArgEnum)]
+enum SortOrder {
+  Topological,
+  Alphabetical,
+}
+
+/// Format of a validated file
+#[derive(Clone, Copy)]
+enum DataFormat {
+  Json,
+  Cbor,
+}
+
+impl DataFormat {
+  fn as_str(&self) -> &'static str {
+    match self {
+      DataFormat::Json => "json",
+      DataFormat::Cbor => "cbor",
+    }
+  }
+}
+
+/// Outcome of validating a single file
+struct FileResult {
+  path: String,
+  format: DataFormat,
+  error: Option<String>,
+  duration: Duration,
+  // Uses of features by a valid file
+  feature_uses: Vec<FeatureUse>,
+}
+
+#[derive(Args)]
+#[clap(
+  about = "Benchmark compiling a CDDL document, parsing a JSON document or CBOR binary file and validating it"
+)]
+struct Bench {
+  #[clap(help = "Path to CDDL document")]
+  cddl: String,
+  #[clap(
+    help = "Path to JSON document or CBOR binary file. Files without a .json extension are parsed as CBOR unless they are valid UTF-8"
+  )]
+  data: String,
+  #[clap(
+    short = 'n',
+    long = "iterations",
+    help = "Number of iterations of each phase",
+    default_value_t = 100
+  )]
+  iterations: usize,
+  #[clap(
+    short = 't',
+    long = "time-limit",
+    help = "Maximum number of seconds to spend on each phase. Fewer iterations are run if the limit is reached"
+  )]
+  time_limit: Option<f64>,
+  #[clap(
+    short = 'f',
+    long = "features",
+    help = "Optional features to enable during validation",
+    use_value_delimiter = true
+  )]
+  features: Option<Vec<String>>,
+}
+
+/// Statistical summary of the durations of a benchmarked phase
+struct Summary {
+  iterations: usize,
+  min: Duration,
+  mean: Duration,
+  median: Duration,
+  p95: Duration,
+  max: Duration,
+  std_dev: Duration,
+}
+
+impl Summary {
+  fn from_samples(mut samples: Vec<Duration>) -> Option<Self> {
+    if samples.is_empty() {
+      return None;
+    }
+
+    samples.sort();
+
+    let iterations = samples.len();
+    let secs = samples.iter().map(|s| s.as_secs_f64()).collect::<Vec<_>>();
+    let mean = secs.iter().sum::<f64>() / iterations as f64;
+    let variance = secs.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / iterations as f64;
+    let percentile = |p: f64| samples[((iterations - 1) as f64 * p).round() as usize];
+
+    Some(Summary {
+      iterations,
+      min: samples[0],
+      mean: Duration::from_secs_f64(mean),
+      median: percentile(0.5),
+      p95: percentile(0.95),
+      max: samples[iterations - 1],
+      std_dev: Duration::from_secs_f64(variance.sqrt()),
+    })
+  }
+}
+
+impl std::fmt::Display for Summary {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{} iterations, min {:?}, mean {:?} ± {:?}, median {:?}, p95 {:?}, max {:?}",
+      self.iterations, self.min, self.mean, self.std_dev, self.median, self.p95, self.max
+    )
+  }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-  TermLogger::init(
-    LevelFilter::Info,
-    ConfigBuilder::new()
-      .set_time_level(LevelFilter::Off)
-      .build(),
-    TerminalMode::Mixed,
-    ColorChoice::Auto,
-  )?;
+/// Time the given closure until the number of iterations or the time limit is
+/// reached, whichever comes first
+fn bench<F>(iterations: usize, time_limit: Option<Duration>, mut f: F) -> Option<Summary>
+where
+  F: FnMut(),
+{
+  let start = Instant::now();
+  let mut samples = Vec::with_capacity(iterations);
+  for _ in 0..iterations {
+    if let Some(time_limit) = time_limit {
+      if start.elapsed() >= time_limit {
+        break;
+      }
+    }
+
+    let iteration = Instant::now();
+    f();
+    samples.push(iteration.elapsed());
+  }
+
+  Summary::from_samples(samples)
+}
+
+/// Parse a rule name along with any generic arguments, e.g. `message<tstr, uint>`
+fn parse_root_rule(rule: &str) -> Result<(&str, Option<GenericArgs<'_>>), Box<dyn Error>> {
+  let mut p = CDDLParser::new(rule, Box::new(lexer_from_str(rule).iter()))?;
+  match p.parse_type2()? {
+    // Identifiers parsed from text borrow from it
+    Type2::Typename {
+      ident: Identifier {
+        ident: Cow::Borrowed(name),
+        ..
+      },
+      generic_args,
+      ..
+    } => Ok((name, generic_args)),
+    _ => Err(format!("{} is not a valid rule name", rule).into()),
+  }
+}
+
+/// Stands in for the plugins implementing control operators when built without
+/// support for them
+#[cfg(not(feature = "control-plugins"))]
+struct ControlPlugins;
+
+/// Load control operator plugins given as `<control operator>=<path>`
+#[cfg(feature = "control-plugins")]
+fn load_control_plugins(args: &[String]) -> Result<ControlPlugins, Box<dyn Error>> {
+  let mut plugins = ControlPlugins::default();
+  for arg in args {
+    let (ctrl, path) = arg.split_once('=').ok_or_else(|| {
+      format!(
+        "invalid control plugin {:?}, expected <control operator>=<path>",
+        arg
+      )
+    })?;
+    plugins
+      .load(ctrl, path)
+      .map_err(|e| format!("{}: {}", path, e))?;
+  }
+
+  Ok(plugins)
+}
+
+#[cfg_attr(
+  not(all(feature = "control-plugins", feature = "additional-controls")),
+  allow(unused_variables)
+)]
+fn validate_json<'a>(
+  cddl: &'a CDDL<'a>,
+  root_rule: Option<&(&'a str, Option<GenericArgs<'a>>)>,
+  json: &str,
+  control_plugins: Option<&'a ControlPlugins>,
+  #[cfg(feature = "additional-controls")] enabled_features: Option<&'a [&'a str]>,
+  disabled_feature_behavior: DisabledFeatureBehavior,
+) -> Result<Vec<FeatureUse>, json::Error> {
+  let json = serde_json::from_str::<serde_json::Value>(json).map_err(json::Error::JSONParsing)?;
+
+  #[cfg(feature = "additional-controls")]
+  let mut jv = json::JSONValidator::new(cddl, json, enabled_features);
+  #[cfg(not(feature = "additional-controls"))]
+  let mut jv = json::JSONValidator::new(cddl, json);
+
+  if let Some((name, generic_args)) = root_rule {
+    jv.set_root_rule(name, generic_args.clone());
+  }
+  #[cfg(feature = "control-plugins")]
+  if let Some(control_plugins) = control_plugins {
+    jv.set_control_plugins(control_plugins);
+  }
+  #[cfg(feature = "additional-controls")]
+  jv.set_disabled_feature_behavior(disabled_feature_behavior);
+
+  jv.validate()?;
+
+  Ok(jv.feature_uses().to_vec())
+}
+
+#[cfg_attr(
+  not(all(feature = "control-plugins", feature = "additional-controls")),
+  allow(unused_variables)
+)]
+fn validate_cbor<'a>(
+  cddl: &'a CDDL<'a>,
+  root_rule: Option<&(&'a str, Option<GenericArgs<'a>>)>,
+  cbor_slice: &[u8],
+  control_plugins: Option<&'a ControlPlugins>,
+  #[cfg(feature = "additional-controls")] enabled_features: Option<&'a [&'a str]>,
+  disabled_feature_behavior: DisabledFeatureBehavior,
+) -> Result<Vec<FeatureUse>, cbor::Error<std::io::Error>> {
+  let cbor: ciborium::value::Value =
+    ciborium::de::from_reader(cbor_slice).map_err(cbor::Error::CBORParsing)?;
+
+  #[cfg(feature = "additional-controls")]
+  let mut cv = cbor::CBORValidator::new(cddl, cbor, enabled_features);
+  #[cfg(not(feature = "additional-controls"))]
+  let mut cv = cbor::CBORValidator::new(cddl, cbor);
+
+  cv.set_cbor_slice(cbor_slice);
+  if let Some((name, generic_args)) = root_rule {
+    cv.set_root_rule(name, generic_args.clone());
+  }
+  #[cfg(feature = "control-plugins")]
+  if let Some(control_plugins) = control_plugins {
+    cv.set_control_plugins(control_plugins);
+  }
+  #[cfg(feature = "additional-controls")]
+  cv.set_disabled_feature_behavior(disabled_feature_behavior);
+
+  <cbor::CBORValidator as Validator<cbor::Error<std::io::Error>>>::validate(&mut cv)?;
+
+  Ok(cv.feature_uses().to_vec())
+}
+
+/// Expand glob patterns into the paths of the files they match, in sorted
+/// order. Arguments without glob metacharacters are taken as paths verbatim so
+/// that missing files are reported
+fn expand_patterns(patterns: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+  let mut files = Vec::new();
+  for pattern in patterns {
+    if !pattern.contains(&['*', '?', '['][..]) {
+      files.push(pattern.clone());
+      continue;
+    }
+
+    let mut matches = Vec::new();
+    for entry in glob::glob(pattern)? {
+      let path = entry?;
+      if path.is_file() {
+        matches.push(path.to_string_lossy().into_owned());
+      }
+    }
+
+    if matches.is_empty() {
+      warn!("No files match {:?}", pattern);
+    }
+
+    matches.sort();
+    files.append(&mut matches);
+  }
+
+  Ok(files)
+}
+
+/// JSON documents and CBOR binary files matching the given patterns
+fn data_files(
+  json: Option<&[String]>,
+  cbor: Option<&[String]>,
+) -> Result<Vec<(String, DataFormat)>, Box<dyn Error>> {
+  let mut files = Vec::new();
+  if let Some(patterns) = json {
+    for file in expand_patterns(patterns)? {
+      files.push((file, DataFormat::Json));
+    }
+  }
+  if let Some(patterns) = cbor {
+    for file in expand_patterns(patterns)? {
+      files.push((file, DataFormat::Cbor));
+    }
+  }
+
+  Ok(files)
+}
+
+/// Stack size of the threads spawned by `parallel_map`, matching the main
+/// thread on most platforms so that a file validates the same regardless of
+/// the number of jobs
+const WORKER_STACK_SIZE: usize = 8 << 20;
+
+/// Apply the given function to each item on up to the given number of threads,
+/// defaulting to the available parallelism. Items are processed in contiguous
+/// chunks so that results are returned in the order of the items. A single
+/// chunk is processed on the calling thread, and a panic on any thread is
+/// propagated to the caller
+fn parallel_map<T, R, F>(items: &[T], jobs: Option<usize>, f: F) -> Vec<R>
+where
+  T: Sync,
+  R: Send,
+  F: Fn(&T) -> R + Sync,
+{
+  let jobs = jobs
+    .or_else(|| thread::available_parallelism().map(|n| n.get()).ok())
+    .unwrap_or(1)
+    .max(1);
+  let chunk_size = items.len().div_ceil(jobs).max(1);
+
+  if chunk_size >= items.len() {
+    return items.iter().map(f).collect();
+  }
+
+  thread::scope(|s| {
+    let handles = items
+      .chunks(chunk_size)
+      .map(|chunk| {
+        let f = &f;
+        thread::Builder::new()
+          .stack_size(WORKER_STACK_SIZE)
+          .spawn_scoped(s, move || chunk.iter().map(f).collect::<Vec<_>>())
+          .expect("failed to spawn thread")
+      })
+      .collect::<Vec<_>>();
+
+    handles
+      .into_iter()
+      .flat_map(|h| h.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+      .collect()
+  })
+}
+
+/// Validate a JSON document or CBOR binary file
+fn validate_file<'a>(
+  cddl: &'a CDDL<'a>,
+  root_rule: Option<&(&'a str, Option<GenericArgs<'a>>)>,
+  path: &str,
+  format: DataFormat,
+  control_plugins: Option<&'a ControlPlugins>,
+  #[cfg(feature = "additional-controls")] enabled_features: Option<&'a [&'a str]>,
+  disabled_feature_behavior: DisabledFeatureBehavior,
+) -> FileResult {
+  let start = Instant::now();
+
+  let outcome = match format {
+    DataFormat::Json => match fs::read_to_string(path) {
+      #[cfg(feature = "additional-controls")]
+      Ok(json) => validate_json(
+        cddl,
+        root_rule,
+        &json,
+        control_plugins,
+        enabled_features,
+        disabled_feature_behavior,
+      )
+      .map_err(|e| e.to_string()),
+      #[cfg(not(feature = "additional-controls"))]
+      Ok(json) => validate_json(
+        cddl,
+        root_rule,
+        &json,
+        control_plugins,
+        disabled_feature_behavior,
+      )
+      .map_err(|e| e.to_string()),
+      Err(e) => Err(e.to_string()),
+    },
+    DataFormat::Cbor => match fs::read(path) {
+      #[cfg(feature = "additional-controls")]
+      Ok(data) => validate_cbor(
+        cddl,
+        root_rule,
+        &data,
+        control_plugins,
+        enabled_features,
+        disabled_feature_behavior,
+      )
+      .map_err(|e| e.to_string()),
+      #[cfg(not(feature = "additional-controls"))]
+      Ok(data) => validate_cbor(
+        cddl,
+        root_rule,
+        &data,
+        control_plugins,
+        disabled_feature_behavior,
+      )
+      .map_err(|e| e.to_string()),
+      Err(e) => Err(e.to_string()),
+    },
+  };
+
+  FileResult {
+    path: path.to_string(),
+    format,
+    error: outcome.as_ref().err().map(|e| e.trim_end().to_string()),
+    duration: start.elapsed(),
+    feature_uses: outcome.unwrap_or_default(),
+  }
+}
+
+/// Rules and type choices exercised by a valid JSON document or CBOR binary
+/// file
+fn file_coverage<'a>(
+  cddl: &'a CDDL<'a>,
+  path: &str,
+  format: DataFormat,
+  #[cfg(feature = "additional-controls")] enabled_features: Option<&'a [&'a str]>,
+) -> Result<Coverage, String> {
+  match format {
+    DataFormat::Json => {
+      let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+      let json = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+      #[cfg(feature = "additional-controls")]
+      return cddl
+        .json_coverage(json, enabled_features)
+        .map_err(|e| e.to_string());
+      #[cfg(not(feature = "additional-controls"))]
+      cddl.json_coverage(json).map_err(|e| e.to_string())
+    }
+    DataFormat::Cbor => {
+      let data = fs::read(path).map_err(|e| e.to_string())?;
+      let cbor = ciborium::de::from_reader(&data[..]).map_err(|e| e.to_string())?;
+      #[cfg(feature = "additional-controls")]
+      return cddl
+        .cbor_coverage(cbor, enabled_features)
+        .map_err(|e| e.to_string());
+      #[cfg(not(feature = "additional-controls"))]
+      cddl.cbor_coverage(cbor).map_err(|e| e.to_string())
+    }
+  }
+}
+
+/// Anonymize the JSON document or CBOR binary file at the given path and write
+/// it to the given path
+fn anonymize_file(anonymizer: &mut Anonymizer, path: &str, output: &Path) -> Result<(), String> {
+  let data = match anonymizer.target() {
+    Target::Json => {
+      let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+      let json = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+      let json = anonymizer
+        .anonymize_json(&json)
+        .map_err(|e| e.to_string())?;
+      let mut data = serde_json::to_vec_pretty(&json).map_err(|e| e.to_string())?;
+      data.push(b'\n');
+      data
+    }
+    Target::Cbor => {
+      let data = fs::read(path).map_err(|e| e.to_string())?;
+      let cbor = ciborium::de::from_reader(&data[..]).map_err(|e| e.to_string())?;
+      let cbor = anonymizer.anonymize(&cbor).map_err(|e| e.to_string())?;
+      let mut data = Vec::new();
+      ciborium::ser::into_writer(&cbor, &mut data).map_err(|e| e.to_string())?;
+      data
+    }
+  };
+
+  if let Some(parent) = output.parent() {
+    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+  }
+
+  fs::write(output, data).map_err(|e| e.to_string())
+}
+
+/// Escape text for inclusion in XML attributes and character data
+fn escape_xml(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&apos;"),
+      c => escaped.push(c),
+    }
+  }
+
+  escaped
+}
+
+/// Names of the features used by a valid file, in sorted order
+fn features_used(feature_uses: &[FeatureUse]) -> Vec<&str> {
+  let mut features = feature_uses
+    .iter()
+    .map(|u| u.feature.as_str())
+    .collect::<Vec<_>>();
+  features.sort_unstable();
+  features.dedup();
+  features
+}
+
+/// Log the features used by a valid file, warning about uses of features that
+/// aren't enabled
+fn log_feature_uses(path: &str, feature_uses: &[FeatureUse]) {
+  let features = features_used(feature_uses);
+  if !features.is_empty() {
+    info!("{:?} uses features: {}", path, features.join(", "));
+  }
+
+  for feature_use in feature_uses.iter().filter(|u| !u.enabled) {
+    warn!("{:?}: {}", path, feature_use);
+  }
+}
+
+/// Report of validated files in the given format
+fn report(cddl: &str, results: &[FileResult], format: ReportFormat) -> String {
+  let failed = results.iter().filter(|r| r.error.is_some()).count();
+
+  match format {
+    ReportFormat::Json => {
+      let files = results
+        .iter()
+        .map(|r| {
+          serde_json::json!({
+            "path": r.path,
+            "format": r.format.as_str(),
+            "passed": r.error.is_none(),
+            "error": r.error,
+            "duration_ms": r.duration.as_secs_f64() * 1000.0,
+            "features": features_used(&r.feature_uses),
+          })
+        })
+        .collect::<Vec<_>>();
+
+      serde_json::to_string_pretty(&serde_json::json!({
+        "cddl": cddl,
+        "total": results.len(),
+        "passed": results.len() - failed,
+        "failed": failed,
+        "files": files,
+      }))
+      .unwrap_or_default()
+    }
+    ReportFormat::Junit => {
+      let time = results
+        .iter()
+        .map(|r| r.duration.as_secs_f64())
+        .sum::<f64>();
+      let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        results.len(),
+        failed,
+        time,
+        escape_xml(cddl),
+        results.len(),
+        failed,
+        time
+      );
+
+      for r in results {
+        xml.push_str(&format!(
+          "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+          r.format.as_str(),
+          escape_xml(&r.path),
+          r.duration.as_secs_f64()
+        ));
+        match &r.error {
+          Some(e) => xml.push_str(&format!(
+            ">\n      <failure message=\"validation failed\">{}</failure>\n    </testcase>\n",
+            escape_xml(e)
+          )),
+          None => xml.push_str("/>\n"),
+        }
+      }
+
+      xml.push_str("  </testsuite>\n</testsuites>\n");
+      xml
+    }
+  }
+}
+
+/// Commands of the shell subcommand
+const SHELL_HELP: &str = "\
+:load <path>        Load a CDDL document
+:reload             Load the CDDL document again, e.g. after editing it
+:rules              List the rules of the document
+:show <rule>        Print the definition of a rule
+:rule [<rule>]      Validate against the given rule, e.g. message<tstr>, or the
+                    first type rule of the document if omitted
+:features [<names>] Enable the given comma separated features, or none
+:format json|cbor   Format of instances entered without :json or :cbor
+:json <instance>    Validate a JSON instance
+:cbor <instance>    Validate a CBOR instance in diagnostic notation
+:trace on|off       Print which type choice matched at each location
+:help               Print this help
+:quit               Exit the shell
+
+Anything else is validated as an instance in the current format. Instances
+continue on the following lines until their brackets are balanced";
+
+/// State of the shell subcommand
+struct Shell {
+  // Path and content of the loaded CDDL document
+  path: Option<String>,
+  cddl: Option<String>,
+  rule: Option<String>,
+  features: Option<Vec<String>>,
+  format: DataFormat,
+  trace: bool,
+}
+
+impl Shell {
+  /// Run the given input, returning whether or not to exit the shell
+  fn run(&mut self, input: &str) -> bool {
+    let input = input.trim();
+    if input.is_empty() {
+      return false;
+    }
+
+    if !input.starts_with(':') {
+      self.validate(self.format, input);
+
+      return false;
+    }
+
+    let (command, arg) = match input.split_once(char::is_whitespace) {
+      Some((command, arg)) => (command, arg.trim()),
+      None => (input, ""),
+    };
+
+    match command {
+      ":load" if !arg.is_empty() => self.load(arg),
+      ":reload" => match self.path.clone() {
+        Some(path) => self.load(&path),
+        None => println!("no CDDL document is loaded"),
+      },
+      ":rules" => self.rules(),
+      ":show" if !arg.is_empty() => self.show(arg),
+      ":rule" if arg.is_empty() => self.rule = None,
+      ":rule" => match parse_root_rule(arg) {
+        Ok(_) => self.rule = Some(arg.to_string()),
+        Err(e) => println!("{}", e),
+      },
+      ":features" if arg.is_empty() => self.features = None,
+      ":features" => self.features = Some(arg.split(',').map(|f| f.trim().to_string()).collect()),
+      ":format" if arg == "json" => self.format = DataFormat::Json,
+      ":format" if arg == "cbor" => self.format = DataFormat::Cbor,
+      ":json" if !arg.is_empty() => self.validate(DataFormat::Json, arg),
+      ":cbor" if !arg.is_empty() => self.validate(DataFormat::Cbor, arg),
+      ":trace" if arg == "on" => self.trace = true,
+      ":trace" if arg == "off" => self.trace = false,
+      ":help" => println!("{}", SHELL_HELP),
+      ":quit" | ":q" => return true,
+      _ => println!(
+        "invalid command {}, enter :help for the available commands",
+        input
+      ),
+    }
+
+    false
+  }
+
+  /// Load the CDDL document at the given path, keeping the document loaded so
+  /// far if it is not conformant
+  fn load(&mut self, path: &str) {
+    let cddl = match fs::read_to_string(path) {
+      Ok(cddl) => cddl,
+      Err(e) => {
+        println!("failed to read {}: {}", path, e);
+
+        return;
+      }
+    };
+
+    match cddl_from_str(&cddl, false) {
+      Ok(c) => println!("loaded {} rules from {}", c.rules.len(), path),
+      Err(e) => {
+        println!("{} is not conformant:\n{}", path, e.to_string().trim_end());
+
+        return;
+      }
+    }
+
+    self.path = Some(path.to_string());
+    self.cddl = Some(cddl);
+  }
+
+  /// Parse the loaded CDDL document, which was conformant when loaded
+  fn document(&self) -> Option<CDDL<'_>> {
+    match &self.cddl {
+      Some(cddl) => cddl_from_str(cddl, false).ok(),
+      None => {
+        println!("no CDDL document is loaded, load one with :load <path>");
+
+        None
+      }
+    }
+  }
+
+  fn rules(&self) {
+    if let Some(cddl) = self.document() {
+      let mut names = Vec::<String>::new();
+      for rule in cddl.rules.iter() {
+        let name = rule.name();
+        if !names.contains(&name) {
+          names.push(name);
+        }
+      }
+
+      println!("{}", names.join("\n"));
+    }
+  }
+
+  fn show(&self, name: &str) {
+    if let Some(cddl) = self.document() {
+      let rules = cddl
+        .rules
+        .iter()
+        .filter(|rule| rule.name() == name)
+        .collect::<Vec<_>>();
+      if rules.is_empty() {
+        println!("no rule named {} is defined", name);
+      }
+
+      for rule in rules {
+        println!("{}", rule.to_string().trim_end());
+      }
+    }
+  }
+
+  /// Validate an instance in the given format, printing the outcome
+  fn validate(&self, format: DataFormat, instance: &str) {
+    let cddl = match &self.cddl {
+      Some(cddl) => cddl,
+      None => {
+        println!("no CDDL document is loaded, load one with :load <path>");
+
+        return;
+      }
+    };
+
+    let instance = match format {
+      DataFormat::Json => match serde_json::from_str(instance) {
+        Ok(json) => Instance::Json(json),
+        Err(e) => {
+          println!("error parsing JSON: {}", e);
+
+          return;
+        }
+      },
+      DataFormat::Cbor => match cbor_from_diag(instance) {
+        Ok(cbor) => {
+          let mut bytes = Vec::new();
+          if let Err(e) = ciborium::ser::into_writer(&cbor, &mut bytes) {
+            println!("error encoding CBOR: {}", e);
+
+            return;
+          }
+
+          Instance::Cbor(bytes)
+        }
+        Err(e) => {
+          println!("error parsing diagnostic notation: {}", e);
+
+          return;
+        }
+      },
+    };
+
+    let options = ReplayOptions {
+      rule: self.rule.clone(),
+      features: self.features.clone(),
+      redact: false,
+    };
+    let session = match Session::record(cddl, instance, options) {
+      Ok(session) => session,
+      Err(e) => {
+        println!("{}", e);
+
+        return;
+      }
+    };
+
+    let outcome = session.outcome();
+    if self.trace {
+      for explanation in outcome.trace.iter() {
+        println!("{}", explanation);
+      }
+    }
+
+    if outcome.is_valid() {
+      println!("valid");
+
+      return;
+    }
+
+    println!("invalid:");
+    for e in outcome.errors.iter() {
+      if e.cddl_location.is_empty() {
+        println!("  {}", e);
+      } else {
+        println!("  {} (in rule {})", e, e.cddl_location);
+      }
+    }
+  }
+}
+
+/// Whether or not input entered in the shell has unclosed brackets, braces,
+/// parentheses or strings, and so continues on the next line
+fn is_incomplete(input: &str) -> bool {
+  let mut depth = 0;
+  let mut quote = None;
+  let mut escaped = false;
+  for c in input.chars() {
+    match quote {
+      Some(_) if escaped => escaped = false,
+      Some(_) if c == '\\' => escaped = true,
+      Some(q) if c == q => quote = None,
+      Some(_) => {}
+      None => match c {
+        '"' | '\'' => quote = Some(c),
+        '[' | '{' | '(' => depth += 1,
+        ']' | '}' | ')' => depth -= 1,
+        _ => {}
+      },
+    }
+  }
+
+  depth > 0 || quote.is_some()
+}
+
+/// Read and run the input of the shell subcommand until it is exited or the
+/// input ends
+fn run_shell(file: Option<&str>, features: Option<Vec<String>>) -> Result<(), Box<dyn Error>> {
+  let mut shell = Shell {
+    path: None,
+    cddl: None,
+    rule: None,
+    features,
+    format: DataFormat::Json,
+    trace: false,
+  };
+  if let Some(file) = file {
+    shell.load(file);
+  }
+
+  let stdin = io::stdin();
+  let mut lines = stdin.lock().lines();
+  let mut input = String::new();
+  loop {
+    print!("{}", if input.is_empty() { "cddl> " } else { "  ... " });
+    io::stdout().flush()?;
+
+    let line = match lines.next() {
+      Some(line) => line?,
+      None => break,
+    };
+
+    input.push_str(&line);
+    input.push('\n');
+    if is_incomplete(&input) {
+      continue;
+    }
+
+    if shell.run(&input) {
+      break;
+    }
+    input.clear();
+  }
+
+  Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+  TermLogger::init(
+    LevelFilter::Info,
+    ConfigBuilder::new()
+      .set_time_level(LevelFilter::Off)
+      .build(),
+    TerminalMode::Mixed,
+    ColorChoice::Auto,
+  )?;
+
+  let cli = Cli::parse();
+
+  match &cli.command {
+    Commands::CompileCddl { file } => {
+      let p = Path::new(file);
+      if !p.exists() {
+        error!("CDDL document {:?} does not exist", p);
+
+        return Ok(());
+      }
+
+      let file_content = fs::read_to_string(file)?;
+      if let Err(e) = cddl_from_str(&file_content, false) {
+        error!("{} is not conformant:\n{}", file, e.to_string().trim_end());
+
+        std::process::exit(1);
+      }
+
+      info!("{} is conformant", file);
+    }
+    Commands::Lint { file } => {
+      let p = Path::new(file);
+      if !p.exists() {
+        error!("CDDL document {:?} does not exist", p);
+
+        return Ok(());
+      }
+
+      let file_content = fs::read_to_string(file)?;
+      let cddl = match cddl_from_str(&file_content, false) {
+        Ok(cddl) => cddl,
+        Err(e) => {
+          error!("{} is not conformant:\n{}", file, e.to_string().trim_end());
+
+          std::process::exit(1);
+        }
+      };
+
+      let diagnostics = cddl.lint();
+      if diagnostics.is_empty() {
+        info!("No issues found in {}", file);
+
+        return Ok(());
+      }
+
+      report_diagnostics(&file_content, &diagnostics, true)?;
+
+      let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+      info!(
+        "Found {} issues in {}, {} of which are errors",
+        diagnostics.len(),
+        file,
+        errors
+      );
+
+      if errors > 0 {
+        std::process::exit(1);
+      }
+    }
+    #[cfg(feature = "ast-span")]
+    Commands::Docs {
+      file,
+      format,
+      output,
+    } => {
+      let p = Path::new(file);
+      if !p.exists() {
+        error!("CDDL document {:?} does not exist", p);
+
+        return Ok(());
+      }
+
+      let docs = match Docs::from_cddl(&fs::read_to_string(file)?) {
+        Ok(docs) => docs,
+        Err(e) => {
+          error!("{} is not conformant:\n{}", file, e.to_string().trim_end());
+
+          std::process::exit(1);
+        }
+      };
+
+      let docs = match format {
+        DocsFormat::Markdown => docs.to_markdown(),
+        DocsFormat::Html => docs.to_html(),
+      };
+
+      match output {
+        Some(output) => fs::write(output, docs)?,
+        None => print!("{}", docs),
+      }
+    }
+    Commands::Sort {
+      file,
+      order,
+      output,
+    } => {
+      let p = Path::new(file);
+      if !p.exists() {
+        error!("CDDL document {:?} does not exist", p);
+
+        return Ok(());
+      }
+
+      let file_content = fs::read_to_string(file)?;
+      let cddl = match cddl_from_str(&file_content, false) {
+        Ok(cddl) => cddl,
+        Err(e) => {
+          error!("{} is not conformant:\n{}", file, e.to_string().trim_end());
 
-  let cli = Cli::parse();
+          std::process::exit(1);
+        }
+      };
 
-  match &cli.command {
-    Commands::CompileCddl { file } => {
+      let sorted = cddl.to_string_ordered(match order {
+        SortOrder::Topological => RuleOrder::Topological,
+        SortOrder::Alphabetical => RuleOrder::Alphabetical,
+      });
+
+      match output {
+        Some(output) => fs::write(output, sorted)?,
+        None => print!("{}", sorted),
+      }
+    }
+    Commands::Diagram { file, rule, output } => {
       let p = Path::new(file);
       if !p.exists() {
         error!("CDDL document {:?} does not exist", p);
@@ -96,9 +1480,120 @@ fn main() -> Result<(), Box<dyn Error>> {
       }
 
       let file_content = fs::read_to_string(file)?;
-      cddl_from_str(&file_content, true).map(|_| ())?;
+      let cddl = match cddl_from_str(&file_content, false) {
+        Ok(cddl) => cddl,
+        Err(e) => {
+          error!("{} is not conformant:\n{}", file, e.to_string().trim_end());
 
-      info!("{} is conformant", file);
+          std::process::exit(1);
+        }
+      };
+
+      match (rule, output) {
+        (Some(rule), output) => {
+          let svg = match cddl.railroad_diagram(rule) {
+            Some(diagram) => diagram.to_svg(),
+            None => {
+              error!("rule {} is not defined in {}", rule, file);
+
+              std::process::exit(1);
+            }
+          };
+
+          match output {
+            Some(output) => fs::write(output, svg)?,
+            None => print!("{}", svg),
+          }
+        }
+        (None, Some(output)) => {
+          fs::create_dir_all(output)?;
+
+          let rules = cddl.rule_order(RuleOrder::Definition);
+          for rule in rules.iter() {
+            if let Some(diagram) = cddl.railroad_diagram(rule) {
+              fs::write(
+                Path::new(output).join(format!("{}.svg", rule)),
+                diagram.to_svg(),
+              )?;
+            }
+          }
+
+          info!("Rendered {} diagrams to {}", rules.len(), output);
+        }
+        (None, None) => {
+          error!("an output directory is required to render every rule");
+
+          std::process::exit(1);
+        }
+      }
+    }
+    #[cfg(feature = "templates")]
+    Commands::Template {
+      file,
+      template,
+      print_model,
+      output,
+    } => {
+      let p = Path::new(file);
+      if !p.exists() {
+        error!("CDDL document {:?} does not exist", p);
+
+        return Ok(());
+      }
+
+      let model = match Model::from_cddl(&fs::read_to_string(file)?) {
+        Ok(model) => model,
+        Err(e) => {
+          error!("{} can't be modeled:\n{}", file, e.to_string().trim_end());
+
+          std::process::exit(1);
+        }
+      };
+
+      let rendered = match template {
+        Some(template) if !print_model => match model.render(&fs::read_to_string(template)?) {
+          Ok(rendered) => rendered,
+          Err(e) => {
+            error!("{}", e);
+
+            std::process::exit(1);
+          }
+        },
+        _ => format!("{}\n", serde_json::to_string_pretty(&model)?),
+      };
+
+      match output {
+        Some(output) => fs::write(output, rendered)?,
+        None => print!("{}", rendered),
+      }
+    }
+    Commands::FromJsonSchema { file, root, output } => {
+      let p = Path::new(file);
+      if !p.exists() {
+        error!("JSON Schema document {:?} does not exist", p);
+
+        return Ok(());
+      }
+
+      let schema: serde_json::Value = serde_json::from_reader(BufReader::new(File::open(file)?))?;
+      let conversion = match cddl_from_json_schema(&schema, root) {
+        Ok(conversion) => conversion,
+        Err(e) => {
+          error!("{} can't be converted: {}", file, e);
+
+          std::process::exit(1);
+        }
+      };
+
+      for warning in conversion.warnings.iter() {
+        warn!("{}", warning);
+      }
+
+      let cddl = conversion.cddl.to_string();
+      match output {
+        Some(output) => fs::write(output, cddl)?,
+        None => print!("{}", cddl),
+      }
     }
     Commands::CompileJson { file } => {
       let p = Path::new(file);
@@ -114,12 +1609,446 @@ fn main() -> Result<(), Box<dyn Error>> {
 
       return Ok(());
     }
+    Commands::Diff { old, new } => {
+      for file in [old, new] {
+        let p = Path::new(file);
+        if !p.exists() {
+          error!("CDDL document {:?} does not exist", p);
+
+          return Ok(());
+        }
+      }
+
+      let diff = diff_from_str(&fs::read_to_string(old)?, &fs::read_to_string(new)?)?;
+
+      if diff.is_empty() {
+        info!("No semantic differences between {} and {}", old, new);
+
+        return Ok(());
+      }
+
+      for change in diff.changes.iter() {
+        info!("{}", change);
+      }
+
+      let breaking_changes = diff.breaking_changes().count();
+      if breaking_changes > 0 {
+        error!(
+          "{} of {} changed rules are not backwards-compatible",
+          breaking_changes,
+          diff.changes.len()
+        );
+
+        std::process::exit(1);
+      }
+    }
+    Commands::Bench(bench_args) => {
+      for file in [&bench_args.cddl, &bench_args.data] {
+        let p = Path::new(file);
+        if !p.exists() {
+          error!("File {:?} does not exist", p);
+
+          return Ok(());
+        }
+      }
+
+      #[cfg(feature = "additional-controls")]
+      let enabled_features: Option<Vec<&str>> = bench_args
+        .features
+        .as_ref()
+        .map(|f| f.iter().map(|s| s.as_str()).collect());
+
+      let time_limit = bench_args.time_limit.map(Duration::from_secs_f64);
+      let cddl_str = fs::read_to_string(&bench_args.cddl)?;
+      let data = fs::read(&bench_args.data)?;
+
+      let is_json = Path::new(&bench_args.data)
+        .extension()
+        .map(|e| e == "json")
+        .unwrap_or_else(|| std::str::from_utf8(&data).is_ok());
+
+      let cddl = match cddl_from_str(&cddl_str, false) {
+        Ok(cddl) => cddl,
+        Err(e) => {
+          error!(
+            "{} is not conformant:\n{}",
+            bench_args.cddl,
+            e.to_string().trim_end()
+          );
+
+          std::process::exit(1);
+        }
+      };
+      if let Some(s) = bench(bench_args.iterations, time_limit, || {
+        let _ = cddl_from_str(&cddl_str, false);
+      }) {
+        info!("compile: {}", s);
+      }
+
+      if is_json {
+        let json_str = std::str::from_utf8(&data)?;
+        let value: serde_json::Value = serde_json::from_str(json_str)?;
+        if let Some(s) = bench(bench_args.iterations, time_limit, || {
+          let _ = serde_json::from_str::<serde_json::Value>(json_str);
+        }) {
+          info!("parse: {}", s);
+        }
+
+        let validate = || {
+          #[cfg(feature = "additional-controls")]
+          let mut jv = json::JSONValidator::new(&cddl, value.clone(), enabled_features.as_deref());
+          #[cfg(not(feature = "additional-controls"))]
+          let mut jv = json::JSONValidator::new(&cddl, value.clone());
+
+          jv.validate()
+        };
+
+        if let Err(e) = validate() {
+          warn!(
+            "Validation of {:?} failed: {}",
+            bench_args.data,
+            e.to_string().trim_end()
+          );
+        }
+
+        if let Some(s) = bench(bench_args.iterations, time_limit, || {
+          let _ = validate();
+        }) {
+          info!("validate: {}", s);
+        }
+      } else {
+        let value: ciborium::value::Value = ciborium::de::from_reader(&data[..])?;
+        if let Some(s) = bench(bench_args.iterations, time_limit, || {
+          let _ = ciborium::de::from_reader::<ciborium::value::Value, _>(&data[..]);
+        }) {
+          info!("parse: {}", s);
+        }
+
+        let validate = || -> cbor::Result<std::io::Error> {
+          #[cfg(feature = "additional-controls")]
+          let mut cv = cbor::CBORValidator::new(&cddl, value.clone(), enabled_features.as_deref());
+          #[cfg(not(feature = "additional-controls"))]
+          let mut cv = cbor::CBORValidator::new(&cddl, value.clone());
+
+          cv.validate()
+        };
+
+        if let Err(e) = validate() {
+          warn!(
+            "Validation of {:?} failed: {}",
+            bench_args.data,
+            e.to_string().trim_end()
+          );
+        }
+
+        if let Some(s) = bench(bench_args.iterations, time_limit, || {
+          let _ = validate();
+        }) {
+          info!("validate: {}", s);
+        }
+      }
+    }
+    Commands::Distill(distill_args) => {
+      #[cfg(feature = "additional-controls")]
+      let enabled_features: Option<Vec<&str>> = distill_args
+        .features
+        .as_ref()
+        .map(|f| f.iter().map(|s| s.as_str()).collect());
+
+      let p = Path::new(&distill_args.cddl);
+      if !p.exists() {
+        error!("CDDL document {:?} does not exist", p);
+
+        return Ok(());
+      }
+
+      let cddl_str = fs::read_to_string(&distill_args.cddl)?;
+      let cddl = match cddl_from_str(&cddl_str, false) {
+        Ok(cddl) => cddl,
+        Err(e) => {
+          error!(
+            "{} is not conformant:\n{}",
+            distill_args.cddl,
+            e.to_string().trim_end()
+          );
+
+          std::process::exit(1);
+        }
+      };
+
+      let files = data_files(distill_args.json.as_deref(), distill_args.cbor.as_deref())?;
+      let coverages = parallel_map(&files, distill_args.jobs, |(file, format)| {
+        #[cfg(feature = "additional-controls")]
+        return file_coverage(&cddl, file, *format, enabled_features.as_deref());
+        #[cfg(not(feature = "additional-controls"))]
+        file_coverage(&cddl, file, *format)
+      });
+
+      // Invalid files are left out of the selection
+      let mut valid_files = Vec::new();
+      let mut valid_coverages = Vec::new();
+      for ((file, _), coverage) in files.iter().zip(coverages) {
+        match coverage {
+          Ok(coverage) => {
+            valid_files.push(file);
+            valid_coverages.push(coverage);
+          }
+          Err(e) => warn!(
+            "Skipping {:?}, which failed validation: {}",
+            file,
+            e.trim_end()
+          ),
+        }
+      }
+
+      let selected = distill(&valid_coverages);
+      for idx in selected.iter() {
+        println!("{}", valid_files[*idx]);
+      }
+
+      let covered = valid_coverages
+        .iter()
+        .flatten()
+        .collect::<std::collections::BTreeSet<_>>();
+      info!(
+        "Selected {} of {} valid files, exercising {} rules and choices",
+        selected.len(),
+        valid_files.len(),
+        covered.len()
+      );
+    }
+    Commands::Coverage(coverage_args) => {
+      #[cfg(feature = "additional-controls")]
+      let enabled_features: Option<Vec<&str>> = coverage_args
+        .features
+        .as_ref()
+        .map(|f| f.iter().map(|s| s.as_str()).collect());
+
+      let p = Path::new(&coverage_args.cddl);
+      if !p.exists() {
+        error!("CDDL document {:?} does not exist", p);
+
+        return Ok(());
+      }
+
+      let cddl_str = fs::read_to_string(&coverage_args.cddl)?;
+      let cddl = match cddl_from_str(&cddl_str, false) {
+        Ok(cddl) => cddl,
+        Err(e) => {
+          error!(
+            "{} is not conformant:\n{}",
+            coverage_args.cddl,
+            e.to_string().trim_end()
+          );
+
+          std::process::exit(1);
+        }
+      };
+
+      let files = data_files(coverage_args.json.as_deref(), coverage_args.cbor.as_deref())?;
+      let coverages = parallel_map(&files, coverage_args.jobs, |(file, format)| {
+        #[cfg(feature = "additional-controls")]
+        return file_coverage(&cddl, file, *format, enabled_features.as_deref());
+        #[cfg(not(feature = "additional-controls"))]
+        file_coverage(&cddl, file, *format)
+      });
+
+      // Invalid files don't exercise the document
+      let mut valid_coverages = Vec::new();
+      for ((file, _), coverage) in files.iter().zip(coverages) {
+        match coverage {
+          Ok(coverage) => valid_coverages.push(coverage),
+          Err(e) => warn!(
+            "Skipping {:?}, which failed validation: {}",
+            file,
+            e.trim_end()
+          ),
+        }
+      }
+
+      let report = cddl.coverage_report(&valid_coverages);
+      match coverage_args.format {
+        CoverageFormat::Text => println!("{}", report),
+        CoverageFormat::Json => println!(
+          "{}",
+          serde_json::to_string_pretty(&serde_json::json!({
+            "cddl": coverage_args.cddl,
+            "files": valid_coverages.len(),
+            "exercised": report.exercised.len(),
+            "total": report.exercised.len() + report.unexercised.len(),
+            "ratio": report.ratio(),
+            "unexercised": report
+              .unexercised
+              .iter()
+              .map(|item| item.to_string())
+              .collect::<Vec<_>>(),
+          }))?
+        ),
+      }
+
+      if let Some(fail_under) = coverage_args.fail_under {
+        if report.ratio() * 100.0 < fail_under {
+          error!(
+            "{:.1}% of rules and choices are exercised, less than the required {}%",
+            report.ratio() * 100.0,
+            fail_under
+          );
+
+          std::process::exit(1);
+        }
+      }
+    }
+    Commands::Shell { file, features } => run_shell(file.as_deref(), features.clone())?,
+    Commands::Replay { file, trace } => {
+      let p = Path::new(file);
+      if !p.exists() {
+        error!("Replay file {:?} does not exist", p);
+
+        return Ok(());
+      }
+
+      let session = match Session::from_str(&fs::read_to_string(file)?) {
+        Ok(session) => session,
+        Err(e) => {
+          error!("{} is not a valid replay file: {}", file, e);
+
+          std::process::exit(1);
+        }
+      };
+      if session.crate_version() != env!("CARGO_PKG_VERSION") {
+        warn!(
+          "{} was recorded with version {} of cddl, replaying with version {}",
+          file,
+          session.crate_version(),
+          env!("CARGO_PKG_VERSION")
+        );
+      }
+
+      let outcome = match session.replay() {
+        Ok(outcome) => outcome,
+        Err(e) => {
+          error!("Failed to replay {}: {}", file, e);
+
+          std::process::exit(1);
+        }
+      };
+
+      if *trace {
+        for explanation in outcome.trace.iter() {
+          info!("{}", explanation);
+        }
+      }
+      if outcome.is_valid() {
+        info!("Validation is successful");
+      }
+      for e in outcome.errors.iter() {
+        error!("Validation failed at {}", e);
+      }
+
+      if outcome == *session.outcome() {
+        info!("Replay of {} reproduces the recorded outcome", file);
+      } else {
+        error!(
+          "Replay of {} diverges from the recorded outcome, which was:",
+          file
+        );
+        for e in session.outcome().errors.iter() {
+          error!("  {}", e);
+        }
+
+        std::process::exit(1);
+      }
+    }
+    Commands::Anonymize(anonymize_args) => {
+      #[cfg(feature = "additional-controls")]
+      let enabled_features: Option<Vec<&str>> = anonymize_args
+        .features
+        .as_ref()
+        .map(|f| f.iter().map(|s| s.as_str()).collect());
+
+      let p = Path::new(&anonymize_args.cddl);
+      if !p.exists() {
+        error!("CDDL document {:?} does not exist", p);
+
+        return Ok(());
+      }
+
+      let cddl_str = fs::read_to_string(&anonymize_args.cddl)?;
+      let cddl = match cddl_from_str(&cddl_str, false) {
+        Ok(cddl) => cddl,
+        Err(e) => {
+          error!(
+            "{} is not conformant:\n{}",
+            anonymize_args.cddl,
+            e.to_string().trim_end()
+          );
+
+          std::process::exit(1);
+        }
+      };
+
+      let new_anonymizer = |target| {
+        #[cfg(feature = "additional-controls")]
+        return Anonymizer::new(
+          &cddl,
+          target,
+          anonymize_args.seed,
+          enabled_features.as_deref(),
+        );
+        #[cfg(not(feature = "additional-controls"))]
+        Anonymizer::new(&cddl, target, anonymize_args.seed)
+      };
+      let mut json_anonymizer = None;
+      let mut cbor_anonymizer = None;
+
+      let files = data_files(
+        anonymize_args.json.as_deref(),
+        anonymize_args.cbor.as_deref(),
+      )?;
+      let mut failed = 0;
+      for (file, format) in files.iter() {
+        let anonymizer = match format {
+          DataFormat::Json => json_anonymizer.get_or_insert_with(|| new_anonymizer(Target::Json)),
+          DataFormat::Cbor => cbor_anonymizer.get_or_insert_with(|| new_anonymizer(Target::Cbor)),
+        };
+
+        // Absolute paths can't be mirrored, so only their file name is kept
+        let path = Path::new(file);
+        let output = if path.is_absolute() {
+          Path::new(&anonymize_args.output_dir).join(path.file_name().unwrap_or_default())
+        } else {
+          Path::new(&anonymize_args.output_dir).join(path)
+        };
+
+        match anonymize_file(anonymizer, file, &output) {
+          Ok(()) => info!("Anonymized {:?} to {:?}", file, output),
+          Err(e) => {
+            error!("Failed to anonymize {:?}: {}", file, e.trim_end());
+            failed += 1;
+          }
+        }
+      }
+
+      if failed > 0 {
+        error!("Failed to anonymize {} of {} files", failed, files.len());
+
+        std::process::exit(1);
+      }
+    }
     Commands::Validate(validate) => {
       #[cfg(feature = "additional-controls")]
       let enabled_features: Option<Vec<&str>> = validate
         .features
         .as_ref()
         .map(|f| f.iter().map(|s| s.as_str()).collect());
+      #[cfg(feature = "additional-controls")]
+      let disabled_feature_behavior = if validate.warn_disabled_features {
+        DisabledFeatureBehavior::Warn
+      } else {
+        DisabledFeatureBehavior::Reject
+      };
+      #[cfg(not(feature = "additional-controls"))]
+      let disabled_feature_behavior = DisabledFeatureBehavior::default();
 
       #[cfg(feature = "additional-controls")]
       if let Some(enabled_features) = &enabled_features {
@@ -144,61 +2073,82 @@ fn main() -> Result<(), Box<dyn Error>> {
       }
 
       let cddl_str = fs::read_to_string(&validate.cddl)?;
+      let cddl = match cddl_from_str(&cddl_str, false) {
+        Ok(cddl) => cddl,
+        Err(e) => {
+          error!(
+            "{} is not conformant:\n{}",
+            validate.cddl,
+            e.to_string().trim_end()
+          );
 
-      if let Some(files) = &validate.json {
-        for file in files {
-          let p = Path::new(file);
-          if !p.exists() {
-            error!("File {:?} does not exist", p);
+          std::process::exit(1);
+        }
+      };
 
-            continue;
-          }
+      let root_rule = match validate.rule.as_deref().map(parse_root_rule).transpose() {
+        Ok(root_rule) => root_rule,
+        Err(e) => {
+          error!("Invalid rule: {}", e.to_string().trim_end());
 
-          #[cfg(feature = "additional-controls")]
-          let r = validate_json_from_str(
-            &cddl_str,
-            &fs::read_to_string(file)?,
-            enabled_features.as_deref(),
-          );
-          #[cfg(not(feature = "additional-controls"))]
-          let r = validate_json_from_str(&cddl_str, &fs::read_to_string(file)?);
+          std::process::exit(1);
+        }
+      };
 
-          match r {
-            Ok(()) => {
-              info!("Validation of {:?} is successful", p);
-            }
-            Err(e) => {
-              error!("Validation of {:?} failed: {}", p, e.to_string().trim_end());
-            }
-          }
+      #[cfg(feature = "control-plugins")]
+      let control_plugins = match load_control_plugins(&validate.control_plugins) {
+        Ok(control_plugins) => control_plugins,
+        Err(e) => {
+          error!("Invalid control plugin: {}", e.to_string().trim_end());
+
+          std::process::exit(1);
         }
-      }
+      };
+      #[cfg(not(feature = "control-plugins"))]
+      let control_plugins = ControlPlugins;
 
-      if let Some(files) = &validate.cbor {
-        for file in files {
-          let p = Path::new(file);
-          if !p.exists() {
-            error!("CBOR binary file {:?} does not exist", p);
+      let files = data_files(validate.json.as_deref(), validate.cbor.as_deref())?;
+      if validate.record.is_some() && (files.len() != 1 || validate.stdin) {
+        error!("Recording requires a single JSON document or CBOR binary file");
 
-            continue;
-          }
-          let mut f = File::open(p)?;
-          let mut data = Vec::new();
-          f.read_to_end(&mut data)?;
+        std::process::exit(1);
+      }
+      #[cfg(feature = "control-plugins")]
+      if validate.record.is_some() && !validate.control_plugins.is_empty() {
+        error!("Validation with control plugins can't be recorded");
 
-          #[cfg(feature = "additional-controls")]
-          let c = validate_cbor_from_slice(&cddl_str, &data, None);
-          #[cfg(not(feature = "additional-controls"))]
-          let c = validate_cbor_from_slice(&cddl_str, &data);
+        std::process::exit(1);
+      }
 
-          match c {
-            Ok(()) => {
-              info!("Validation of {:?} is successful", p);
-            }
-            Err(e) => {
-              error!("Validation of {:?} failed: {}", p, e.to_string().trim_end());
-            }
+      let mut results = parallel_map(&files, validate.jobs, |(file, format)| {
+        #[cfg(feature = "additional-controls")]
+        return validate_file(
+          &cddl,
+          root_rule.as_ref(),
+          file,
+          *format,
+          Some(&control_plugins),
+          enabled_features.as_deref(),
+          disabled_feature_behavior,
+        );
+        #[cfg(not(feature = "additional-controls"))]
+        validate_file(
+          &cddl,
+          root_rule.as_ref(),
+          file,
+          *format,
+          Some(&control_plugins),
+          disabled_feature_behavior,
+        )
+      });
+
+      for r in results.iter() {
+        match &r.error {
+          None => {
+            info!("Validation of {:?} is successful", r.path);
+            log_feature_uses(&r.path, &r.feature_uses);
           }
+          Some(e) => error!("Validation of {:?} failed: {}", r.path, e),
         }
       }
 
@@ -210,33 +2160,130 @@ fn main() -> Result<(), Box<dyn Error>> {
         reader.read_to_end(&mut data)?;
         if let Ok(json) = std::str::from_utf8(&data) {
           #[cfg(feature = "additional-controls")]
-          let r = validate_json_from_str(&cddl_str, json, None);
+          let r = validate_json(
+            &cddl,
+            root_rule.as_ref(),
+            json,
+            Some(&control_plugins),
+            enabled_features.as_deref(),
+            disabled_feature_behavior,
+          );
           #[cfg(not(feature = "additional-controls"))]
-          let r = validate_json_from_str(&cddl_str, json);
+          let r = validate_json(
+            &cddl,
+            root_rule.as_ref(),
+            json,
+            Some(&control_plugins),
+            disabled_feature_behavior,
+          );
 
-          match r {
-            Ok(()) => {
+          match &r {
+            Ok(feature_uses) => {
               info!("Validation from stdin is successful");
+              log_feature_uses("<stdin>", feature_uses);
             }
             Err(e) => {
               error!("Validation from stdin failed: {}", e.to_string().trim_end());
             }
           }
+
+          results.push(FileResult {
+            path: "<stdin>".to_string(),
+            format: DataFormat::Json,
+            error: r
+              .as_ref()
+              .err()
+              .map(|e| e.to_string().trim_end().to_string()),
+            duration: Duration::default(),
+            feature_uses: r.unwrap_or_default(),
+          });
         } else {
           #[cfg(feature = "additional-controls")]
-          let c = validate_cbor_from_slice(&cddl_str, &data, enabled_features.as_deref());
+          let c = validate_cbor(
+            &cddl,
+            root_rule.as_ref(),
+            &data,
+            Some(&control_plugins),
+            enabled_features.as_deref(),
+            disabled_feature_behavior,
+          );
           #[cfg(not(feature = "additional-controls"))]
-          let c = validate_cbor_from_slice(&cddl_str, &data);
+          let c = validate_cbor(
+            &cddl,
+            root_rule.as_ref(),
+            &data,
+            Some(&control_plugins),
+            disabled_feature_behavior,
+          );
 
-          match c {
-            Ok(()) => {
+          match &c {
+            Ok(feature_uses) => {
               info!("Validation from stdin is successful");
+              log_feature_uses("<stdin>", feature_uses);
             }
             Err(e) => {
               error!("Validation from stdin failed: {}", e.to_string().trim_end());
             }
           }
+
+          results.push(FileResult {
+            path: "<stdin>".to_string(),
+            format: DataFormat::Cbor,
+            error: c
+              .as_ref()
+              .err()
+              .map(|e| e.to_string().trim_end().to_string()),
+            duration: Duration::default(),
+            feature_uses: c.unwrap_or_default(),
+          });
+        }
+      }
+
+      let failed = results.iter().filter(|r| r.error.is_some()).count();
+      if results.len() > 1 {
+        let summary = format!(
+          "Validated {} files: {} passed, {} failed",
+          results.len(),
+          results.len() - failed,
+          failed
+        );
+        if failed > 0 {
+          error!("{}", summary);
+        } else {
+          info!("{}", summary);
+        }
+      }
+
+      if let Some(path) = &validate.report {
+        fs::write(
+          path,
+          report(&validate.cddl, &results, validate.report_format),
+        )?;
+        info!("Wrote report to {:?}", path);
+      }
+
+      if let (Some(path), [(file, format)]) = (&validate.record, &files[..]) {
+        let instance = match format {
+          DataFormat::Json => Instance::Json(serde_json::from_str(&fs::read_to_string(file)?)?),
+          DataFormat::Cbor => Instance::Cbor(fs::read(file)?),
+        };
+        let options = ReplayOptions {
+          rule: validate.rule.clone(),
+          features: validate.features.clone(),
+          redact: validate.redact,
+        };
+
+        let session = Session::record(&cddl_str, instance, options)?;
+        if validate.redact && session.outcome().is_valid() != (failed == 0) {
+          warn!("Redacting {:?} changed the outcome of its validation, so the replay file doesn't reproduce it", file);
         }
+
+        fs::write(path, format!("{}\n", session))?;
+        info!("Recorded the validation of {:?} to {:?}", file, path);
+      }
+
+      if failed > 0 {
+        std::process::exit(1);
       }
     }
   }
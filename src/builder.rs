@@ -0,0 +1,1039 @@
+#![cfg(feature = "std")]
+
+use crate::{
+  ast::*,
+  token::{self, SocketPlug},
+};
+
+use std::{borrow::Cow, fmt, marker::PhantomData};
+
+/// Owned builder of a CDDL document, for constructing documents
+/// programmatically rather than parsing them from text. The builder doesn't
+/// borrow from anything, so it can be assembled incrementally and passed
+/// around freely. The AST of the document is borrowed from the builder with
+/// [`CddlBuilder::build`], and the `Display` implementation of the builder
+/// serializes the document to CDDL text
+///
+/// # Example
+///
+/// ```
+/// use cddl::builder::{CddlBuilder, EntryBuilder, GroupBuilder, TypeBuilder, TypeRuleBuilder};
+///
+/// let mut builder = CddlBuilder::new();
+/// builder.type_rule(TypeRuleBuilder::new(
+///   "person",
+///   TypeBuilder::map(
+///     GroupBuilder::new()
+///       .entry(EntryBuilder::member("name", TypeBuilder::typename("tstr")))
+///       .entry(EntryBuilder::member("age", TypeBuilder::typename("uint")).optional()),
+///   ),
+/// ));
+/// builder.type_rule(TypeRuleBuilder::new(
+///   "people",
+///   TypeBuilder::array(
+///     GroupBuilder::new().entry(EntryBuilder::value(TypeBuilder::typename("person")).zero_or_more()),
+///   ),
+/// ));
+///
+/// let cddl = builder.build();
+/// assert_eq!(cddl.rules.len(), 2);
+/// assert_eq!(
+///   builder.to_string(),
+///   "person = { name: tstr, ? age: uint }\n\npeople = [ * person ]\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CddlBuilder {
+  rules: Vec<RuleBuilder>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum RuleBuilder {
+  Type(TypeRuleBuilder),
+  Group(GroupRuleBuilder),
+}
+
+impl CddlBuilder {
+  /// New builder of an empty document
+  pub fn new() -> Self {
+    CddlBuilder::default()
+  }
+
+  /// Append a type rule to the document
+  pub fn type_rule(&mut self, rule: TypeRuleBuilder) -> &mut Self {
+    self.rules.push(RuleBuilder::Type(rule));
+    self
+  }
+
+  /// Append a group rule to the document
+  pub fn group_rule(&mut self, rule: GroupRuleBuilder) -> &mut Self {
+    self.rules.push(RuleBuilder::Group(rule));
+    self
+  }
+
+  /// Remove the rules with the given name from the document, returning
+  /// whether any was removed
+  pub fn remove_rule(&mut self, name: &str) -> bool {
+    let len = self.rules.len();
+    self.rules.retain(|r| match r {
+      RuleBuilder::Type(rule) => rule.name != name,
+      RuleBuilder::Group(rule) => rule.name != name,
+    });
+
+    self.rules.len() != len
+  }
+
+  /// AST of the document
+  pub fn build(&self) -> CDDL<'_> {
+    CDDL {
+      rules: self
+        .rules
+        .iter()
+        .map(|r| match r {
+          RuleBuilder::Type(rule) => rule.build(),
+          RuleBuilder::Group(rule) => rule.build(),
+        })
+        .collect(),
+      #[cfg(feature = "ast-comments")]
+      comments: None,
+    }
+  }
+}
+
+impl fmt::Display for CddlBuilder {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.build())
+  }
+}
+
+/// Builder of a type rule, e.g. `name = tstr`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeRuleBuilder {
+  name: String,
+  generic_params: Vec<String>,
+  is_type_choice_alternate: bool,
+  value: TypeBuilder,
+}
+
+impl TypeRuleBuilder {
+  /// New type rule assigning the type to the name
+  pub fn new(name: impl Into<String>, value: TypeBuilder) -> Self {
+    TypeRuleBuilder {
+      name: name.into(),
+      generic_params: Vec::new(),
+      is_type_choice_alternate: false,
+      value,
+    }
+  }
+
+  /// Make the rule generic over the given parameters
+  pub fn generic_params<I, S>(mut self, params: I) -> Self
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    self.generic_params = params.into_iter().map(Into::into).collect();
+    self
+  }
+
+  /// Make the rule extend an existing rule with additional choices (`/=`)
+  pub fn choice_alternate(mut self) -> Self {
+    self.is_type_choice_alternate = true;
+    self
+  }
+
+  fn build(&self) -> Rule<'_> {
+    Rule::Type {
+      rule: TypeRule {
+        name: identifier(&self.name),
+        generic_params: generic_params(&self.generic_params),
+        is_type_choice_alternate: self.is_type_choice_alternate,
+        value: self.value.build(),
+        #[cfg(feature = "ast-comments")]
+        comments_before_assignt: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_assignt: None,
+      },
+      #[cfg(feature = "ast-span")]
+      span: Span::default(),
+      #[cfg(feature = "ast-comments")]
+      comments_after_rule: None,
+    }
+  }
+}
+
+/// Builder of a group rule, e.g. `name = (a: int, b: tstr)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupRuleBuilder {
+  name: String,
+  generic_params: Vec<String>,
+  is_group_choice_alternate: bool,
+  group: GroupBuilder,
+}
+
+impl GroupRuleBuilder {
+  /// New group rule assigning the group to the name
+  pub fn new(name: impl Into<String>, group: GroupBuilder) -> Self {
+    GroupRuleBuilder {
+      name: name.into(),
+      generic_params: Vec::new(),
+      is_group_choice_alternate: false,
+      group,
+    }
+  }
+
+  /// Make the rule generic over the given parameters
+  pub fn generic_params<I, S>(mut self, params: I) -> Self
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    self.generic_params = params.into_iter().map(Into::into).collect();
+    self
+  }
+
+  /// Make the rule extend an existing rule with additional choices (`//=`)
+  pub fn choice_alternate(mut self) -> Self {
+    self.is_group_choice_alternate = true;
+    self
+  }
+
+  fn build(&self) -> Rule<'_> {
+    Rule::Group {
+      rule: Box::new(GroupRule {
+        name: identifier(&self.name),
+        generic_params: generic_params(&self.generic_params),
+        is_group_choice_alternate: self.is_group_choice_alternate,
+        entry: GroupEntry::InlineGroup {
+          occur: None,
+          group: self.group.build(),
+          #[cfg(feature = "ast-span")]
+          span: Span::default(),
+          #[cfg(feature = "ast-comments")]
+          comments_before_group: None,
+          #[cfg(feature = "ast-comments")]
+          comments_after_group: None,
+        },
+        #[cfg(feature = "ast-comments")]
+        comments_before_assigng: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_assigng: None,
+      }),
+      #[cfg(feature = "ast-span")]
+      span: Span::default(),
+      #[cfg(feature = "ast-comments")]
+      comments_after_rule: None,
+    }
+  }
+}
+
+/// Builder of a type, consisting of one or more type choices
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeBuilder {
+  choices: Vec<Type1Builder>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Type1Builder {
+  type2: Type2Builder,
+  operator: Option<(OperatorBuilder, Type2Builder)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum OperatorBuilder {
+  Range(bool),
+  Control(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Type2Builder {
+  Typename(String, Vec<TypeBuilder>),
+  Text(String),
+  Uint(usize),
+  Int(isize),
+  Float(f64),
+  Parenthesized(TypeBuilder),
+  Map(GroupBuilder),
+  Array(GroupBuilder),
+  Unwrap(String),
+  ChoiceFromGroup(String),
+  ChoiceFromInlineGroup(GroupBuilder),
+  Tagged(Option<usize>, TypeBuilder),
+  Any,
+}
+
+impl TypeBuilder {
+  fn from_type2(type2: Type2Builder) -> Self {
+    TypeBuilder {
+      choices: vec![Type1Builder {
+        type2,
+        operator: None,
+      }],
+    }
+  }
+
+  /// Reference to a type by name, e.g. `tstr`
+  pub fn typename(name: impl Into<String>) -> Self {
+    TypeBuilder::from_type2(Type2Builder::Typename(name.into(), Vec::new()))
+  }
+
+  /// Instantiation of a generic type with the given arguments, e.g.
+  /// `message<tstr, uint>`
+  pub fn generic(name: impl Into<String>, args: Vec<TypeBuilder>) -> Self {
+    TypeBuilder::from_type2(Type2Builder::Typename(name.into(), args))
+  }
+
+  /// Text string value, e.g. `"a"`. The text is written as is, so it must not
+  /// contain unescaped double quotes
+  pub fn text(value: impl Into<String>) -> Self {
+    TypeBuilder::from_type2(Type2Builder::Text(value.into()))
+  }
+
+  /// Unsigned integer value
+  pub fn uint(value: usize) -> Self {
+    TypeBuilder::from_type2(Type2Builder::Uint(value))
+  }
+
+  /// Integer value
+  pub fn int(value: isize) -> Self {
+    TypeBuilder::from_type2(Type2Builder::Int(value))
+  }
+
+  /// Floating-point value
+  pub fn float(value: f64) -> Self {
+    TypeBuilder::from_type2(Type2Builder::Float(value))
+  }
+
+  /// Map of the entries of the group, e.g. `{ a: int }`
+  pub fn map(group: GroupBuilder) -> Self {
+    TypeBuilder::from_type2(Type2Builder::Map(group))
+  }
+
+  /// Array of the entries of the group, e.g. `[* int]`
+  pub fn array(group: GroupBuilder) -> Self {
+    TypeBuilder::from_type2(Type2Builder::Array(group))
+  }
+
+  /// Unwrapped type, e.g. `~name`
+  pub fn unwrap(name: impl Into<String>) -> Self {
+    TypeBuilder::from_type2(Type2Builder::Unwrap(name.into()))
+  }
+
+  /// Choice from the values of the named group, e.g. `&colors`
+  pub fn choice_from_group(name: impl Into<String>) -> Self {
+    TypeBuilder::from_type2(Type2Builder::ChoiceFromGroup(name.into()))
+  }
+
+  /// Choice from the values of the group, e.g. `&(a: 1, b: 2)`
+  pub fn choice_from_inline_group(group: GroupBuilder) -> Self {
+    TypeBuilder::from_type2(Type2Builder::ChoiceFromInlineGroup(group))
+  }
+
+  /// Tagged data item, e.g. `#6.32(tstr)`, or data item with any tag if no
+  /// tag is given
+  pub fn tagged(tag: Option<usize>, value: TypeBuilder) -> Self {
+    TypeBuilder::from_type2(Type2Builder::Tagged(tag, value))
+  }
+
+  /// Any data item (`#`)
+  pub fn any() -> Self {
+    TypeBuilder::from_type2(Type2Builder::Any)
+  }
+
+  /// Range of values between the bounds, e.g. `0..10`, with the upper bound
+  /// excluded unless the range is inclusive
+  pub fn range(lower: TypeBuilder, upper: TypeBuilder, is_inclusive: bool) -> Self {
+    TypeBuilder {
+      choices: vec![Type1Builder {
+        type2: lower.into_type2(),
+        operator: Some((OperatorBuilder::Range(is_inclusive), upper.into_type2())),
+      }],
+    }
+  }
+
+  /// Type constrained by a control operator, e.g. `tstr .size 10`. The
+  /// operator may be given with or without its leading dot. Types with
+  /// several choices or an operator already are parenthesized
+  pub fn control(self, ctrl: &str, arg: TypeBuilder) -> Self {
+    let ctrl = if ctrl.starts_with('.') {
+      ctrl.to_string()
+    } else {
+      format!(".{}", ctrl)
+    };
+
+    TypeBuilder {
+      choices: vec![Type1Builder {
+        type2: self.into_type2(),
+        operator: Some((OperatorBuilder::Control(ctrl), arg.into_type2())),
+      }],
+    }
+  }
+
+  /// Add the choices of another type to the type, e.g. `tstr / uint`
+  pub fn or(mut self, other: TypeBuilder) -> Self {
+    self.choices.extend(other.choices);
+    self
+  }
+
+  // Single type without an operator, parenthesized if needed
+  fn into_type2(mut self) -> Type2Builder {
+    match self.choices.as_slice() {
+      [Type1Builder { operator: None, .. }] => self.choices.remove(0).type2,
+      _ => Type2Builder::Parenthesized(self),
+    }
+  }
+
+  fn build(&self) -> Type<'_> {
+    Type {
+      type_choices: self
+        .choices
+        .iter()
+        .map(|tc| TypeChoice {
+          type1: tc.build(),
+          #[cfg(feature = "ast-comments")]
+          comments_before_type: None,
+          #[cfg(feature = "ast-comments")]
+          comments_after_type: None,
+        })
+        .collect(),
+      #[cfg(feature = "ast-span")]
+      span: Span::default(),
+    }
+  }
+
+  fn build_type1(&self) -> Type1<'_> {
+    match self.choices.as_slice() {
+      [t1] => t1.build(),
+      _ => Type1 {
+        type2: parenthesized(self),
+        operator: None,
+        #[cfg(feature = "ast-span")]
+        span: Span::default(),
+        #[cfg(feature = "ast-comments")]
+        comments_after_type: None,
+      },
+    }
+  }
+}
+
+impl Type1Builder {
+  fn build(&self) -> Type1<'_> {
+    Type1 {
+      type2: self.type2.build(),
+      operator: self.operator.as_ref().map(|(operator, type2)| Operator {
+        operator: match operator {
+          OperatorBuilder::Range(is_inclusive) => RangeCtlOp::RangeOp {
+            is_inclusive: *is_inclusive,
+            #[cfg(feature = "ast-span")]
+            span: Span::default(),
+          },
+          OperatorBuilder::Control(ctrl) => RangeCtlOp::CtlOp {
+            ctrl: Cow::Borrowed(ctrl),
+            #[cfg(feature = "ast-span")]
+            span: Span::default(),
+          },
+        },
+        type2: type2.build(),
+        #[cfg(feature = "ast-comments")]
+        comments_before_operator: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_operator: None,
+      }),
+      #[cfg(feature = "ast-span")]
+      span: Span::default(),
+      #[cfg(feature = "ast-comments")]
+      comments_after_type: None,
+    }
+  }
+}
+
+impl Type2Builder {
+  fn build(&self) -> Type2<'_> {
+    #[cfg(feature = "ast-span")]
+    let span = Span::default();
+
+    match self {
+      Type2Builder::Typename(name, args) => Type2::Typename {
+        ident: identifier(name),
+        generic_args: generic_args(args),
+        #[cfg(feature = "ast-span")]
+        span,
+      },
+      Type2Builder::Text(value) => Type2::TextValue {
+        value: Cow::Borrowed(value),
+        #[cfg(feature = "ast-span")]
+        span,
+      },
+      Type2Builder::Uint(value) => Type2::UintValue {
+        value: *value,
+        #[cfg(feature = "ast-span")]
+        span,
+      },
+      Type2Builder::Int(value) => Type2::IntValue {
+        value: *value,
+        #[cfg(feature = "ast-span")]
+        span,
+      },
+      Type2Builder::Float(value) => Type2::FloatValue {
+        value: *value,
+        #[cfg(feature = "ast-span")]
+        span,
+      },
+      Type2Builder::Parenthesized(t) => parenthesized(t),
+      Type2Builder::Map(group) => Type2::Map {
+        group: group.build(),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_before_group: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_group: None,
+      },
+      Type2Builder::Array(group) => Type2::Array {
+        group: group.build(),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_before_group: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_group: None,
+      },
+      Type2Builder::Unwrap(name) => Type2::Unwrap {
+        ident: identifier(name),
+        generic_args: None,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments: None,
+      },
+      Type2Builder::ChoiceFromGroup(name) => Type2::ChoiceFromGroup {
+        ident: identifier(name),
+        generic_args: None,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments: None,
+      },
+      Type2Builder::ChoiceFromInlineGroup(group) => Type2::ChoiceFromInlineGroup {
+        group: group.build(),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments: None,
+        #[cfg(feature = "ast-comments")]
+        comments_before_group: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_group: None,
+      },
+      Type2Builder::Tagged(tag, t) => Type2::TaggedData {
+        tag: *tag,
+        tag_type: None,
+        t: t.build(),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_before_type: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_type: None,
+      },
+      #[cfg(feature = "ast-span")]
+      Type2Builder::Any => Type2::Any(span),
+      #[cfg(not(feature = "ast-span"))]
+      Type2Builder::Any => Type2::Any,
+    }
+  }
+}
+
+/// Builder of a group, consisting of one or more group choices
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupBuilder {
+  choices: Vec<Vec<EntryBuilder>>,
+}
+
+impl Default for GroupBuilder {
+  fn default() -> Self {
+    GroupBuilder {
+      choices: vec![Vec::new()],
+    }
+  }
+}
+
+impl GroupBuilder {
+  /// New empty group
+  pub fn new() -> Self {
+    GroupBuilder::default()
+  }
+
+  /// Append an entry to the last choice of the group
+  pub fn entry(mut self, entry: EntryBuilder) -> Self {
+    if let Some(entries) = self.choices.last_mut() {
+      entries.push(entry);
+    }
+    self
+  }
+
+  /// Add the choices of another group to the group, e.g. `(a: int // b: tstr)`
+  pub fn or(mut self, other: GroupBuilder) -> Self {
+    self.choices.extend(other.choices);
+    self
+  }
+
+  fn build(&self) -> Group<'_> {
+    Group {
+      group_choices: self
+        .choices
+        .iter()
+        .map(|entries| GroupChoice {
+          group_entries: entries
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| {
+              (
+                entry.build(),
+                OptionalComma {
+                  optional_comma: idx + 1 < entries.len(),
+                  #[cfg(feature = "ast-comments")]
+                  trailing_comments: None,
+                  _a: PhantomData,
+                },
+              )
+            })
+            .collect(),
+          #[cfg(feature = "ast-span")]
+          span: Span::default(),
+          #[cfg(feature = "ast-comments")]
+          comments_before_grpchoice: None,
+        })
+        .collect(),
+      #[cfg(feature = "ast-span")]
+      span: Span::default(),
+    }
+  }
+}
+
+/// Builder of a group entry, with an optional occurrence indicator
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryBuilder {
+  occur: Option<OccurBuilder>,
+  kind: EntryKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum OccurBuilder {
+  Exact(Option<usize>, Option<usize>),
+  ZeroOrMore,
+  OneOrMore,
+  Optional,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum EntryKind {
+  Member(Option<KeyBuilder>, TypeBuilder),
+  Groupname(String, Vec<TypeBuilder>),
+  Inline(GroupBuilder),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum KeyBuilder {
+  Bareword(String),
+  Text(String),
+  Int(isize),
+  Type(TypeBuilder, bool),
+}
+
+impl EntryBuilder {
+  fn from_kind(kind: EntryKind) -> Self {
+    EntryBuilder { occur: None, kind }
+  }
+
+  /// Entry keyed by a name, e.g. `name: tstr`. Names that aren't valid
+  /// identifiers are written as text keys, e.g. `"first name": tstr`
+  pub fn member(name: impl Into<String>, value: TypeBuilder) -> Self {
+    let name = name.into();
+    let key = if is_identifier(&name) {
+      KeyBuilder::Bareword(name)
+    } else {
+      KeyBuilder::Text(name)
+    };
+
+    EntryBuilder::from_kind(EntryKind::Member(Some(key), value))
+  }
+
+  /// Entry keyed by a text string, e.g. `"name": tstr`
+  pub fn text_key(key: impl Into<String>, value: TypeBuilder) -> Self {
+    EntryBuilder::from_kind(EntryKind::Member(Some(KeyBuilder::Text(key.into())), value))
+  }
+
+  /// Entry keyed by an integer, e.g. `1: tstr`
+  pub fn int_key(key: isize, value: TypeBuilder) -> Self {
+    EntryBuilder::from_kind(EntryKind::Member(Some(KeyBuilder::Int(key)), value))
+  }
+
+  /// Entry keyed by a type, e.g. `tstr => int`, or `tstr ^ => int` if the
+  /// key is a cut
+  pub fn type_key(key: TypeBuilder, value: TypeBuilder, is_cut: bool) -> Self {
+    EntryBuilder::from_kind(EntryKind::Member(
+      Some(KeyBuilder::Type(key, is_cut)),
+      value,
+    ))
+  }
+
+  /// Entry without a key, e.g. an array item
+  pub fn value(value: TypeBuilder) -> Self {
+    EntryBuilder::from_kind(EntryKind::Member(None, value))
+  }
+
+  /// Reference to a group by name, e.g. `header`
+  pub fn groupname(name: impl Into<String>) -> Self {
+    EntryBuilder::from_kind(EntryKind::Groupname(name.into(), Vec::new()))
+  }
+
+  /// Instantiation of a generic group with the given arguments
+  pub fn generic_groupname(name: impl Into<String>, args: Vec<TypeBuilder>) -> Self {
+    EntryBuilder::from_kind(EntryKind::Groupname(name.into(), args))
+  }
+
+  /// Inline group, e.g. `(a: int, b: int)`
+  pub fn group(group: GroupBuilder) -> Self {
+    EntryBuilder::from_kind(EntryKind::Inline(group))
+  }
+
+  /// Make the entry optional (`?`)
+  pub fn optional(mut self) -> Self {
+    self.occur = Some(OccurBuilder::Optional);
+    self
+  }
+
+  /// Allow zero or more occurrences of the entry (`*`)
+  pub fn zero_or_more(mut self) -> Self {
+    self.occur = Some(OccurBuilder::ZeroOrMore);
+    self
+  }
+
+  /// Allow one or more occurrences of the entry (`+`)
+  pub fn one_or_more(mut self) -> Self {
+    self.occur = Some(OccurBuilder::OneOrMore);
+    self
+  }
+
+  /// Bound the occurrences of the entry, e.g. `1*3`
+  pub fn occurs(mut self, lower: Option<usize>, upper: Option<usize>) -> Self {
+    self.occur = Some(OccurBuilder::Exact(lower, upper));
+    self
+  }
+
+  fn build(&self) -> GroupEntry<'_> {
+    #[cfg(feature = "ast-span")]
+    let span = Span::default();
+
+    let occur = self.occur.as_ref().map(|o| Occurrence {
+      occur: o.build(),
+      #[cfg(feature = "ast-comments")]
+      comments: None,
+      _a: PhantomData,
+    });
+
+    match &self.kind {
+      EntryKind::Member(key, value) => GroupEntry::ValueMemberKey {
+        ge: Box::new(ValueMemberKeyEntry {
+          occur,
+          member_key: key.as_ref().map(|k| k.build()),
+          entry_type: value.build(),
+        }),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        leading_comments: None,
+        #[cfg(feature = "ast-comments")]
+        trailing_comments: None,
+      },
+      EntryKind::Groupname(name, args) => GroupEntry::TypeGroupname {
+        ge: TypeGroupnameEntry {
+          occur,
+          name: identifier(name),
+          generic_args: generic_args(args),
+        },
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        leading_comments: None,
+        #[cfg(feature = "ast-comments")]
+        trailing_comments: None,
+      },
+      EntryKind::Inline(group) => GroupEntry::InlineGroup {
+        occur,
+        group: group.build(),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_before_group: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_group: None,
+      },
+    }
+  }
+}
+
+impl OccurBuilder {
+  fn build(&self) -> Occur {
+    #[cfg(feature = "ast-span")]
+    let span = Span::default();
+
+    match self {
+      OccurBuilder::Exact(lower, upper) => Occur::Exact {
+        lower: *lower,
+        upper: *upper,
+        #[cfg(feature = "ast-span")]
+        span,
+      },
+      #[cfg(feature = "ast-span")]
+      OccurBuilder::ZeroOrMore => Occur::ZeroOrMore(span),
+      #[cfg(not(feature = "ast-span"))]
+      OccurBuilder::ZeroOrMore => Occur::ZeroOrMore,
+      #[cfg(feature = "ast-span")]
+      OccurBuilder::OneOrMore => Occur::OneOrMore(span),
+      #[cfg(not(feature = "ast-span"))]
+      OccurBuilder::OneOrMore => Occur::OneOrMore,
+      #[cfg(feature = "ast-span")]
+      OccurBuilder::Optional => Occur::Optional(span),
+      #[cfg(not(feature = "ast-span"))]
+      OccurBuilder::Optional => Occur::Optional,
+    }
+  }
+}
+
+impl KeyBuilder {
+  fn build(&self) -> MemberKey<'_> {
+    #[cfg(feature = "ast-span")]
+    let span = Span::default();
+
+    match self {
+      KeyBuilder::Bareword(name) => MemberKey::Bareword {
+        ident: identifier(name),
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_colon: None,
+      },
+      KeyBuilder::Text(_) | KeyBuilder::Int(_) => MemberKey::Value {
+        value: match self {
+          KeyBuilder::Text(key) => token::Value::TEXT(Cow::Borrowed(key)),
+          KeyBuilder::Int(key) if *key >= 0 => token::Value::UINT(*key as usize),
+          KeyBuilder::Int(key) => token::Value::INT(*key),
+          _ => unreachable!(),
+        },
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_colon: None,
+      },
+      KeyBuilder::Type(key, is_cut) => MemberKey::Type1 {
+        t1: Box::new(key.build_type1()),
+        is_cut: *is_cut,
+        #[cfg(feature = "ast-span")]
+        span,
+        #[cfg(feature = "ast-comments")]
+        comments_before_cut: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_cut: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_arrowmap: None,
+      },
+    }
+  }
+}
+
+// Identifier for the name, with the socket indicated by its `$` or `$$`
+// prefix, if any
+fn identifier(name: &str) -> Identifier<'_> {
+  let (ident, socket) = if let Some(ident) = name.strip_prefix("$$") {
+    (ident, Some(SocketPlug::GROUP))
+  } else if let Some(ident) = name.strip_prefix('$') {
+    (ident, Some(SocketPlug::TYPE))
+  } else {
+    (name, None)
+  };
+
+  Identifier {
+    ident: Cow::Borrowed(ident),
+    socket,
+    #[cfg(feature = "ast-span")]
+    span: Span::default(),
+  }
+}
+
+fn generic_params(params: &[String]) -> Option<GenericParams<'_>> {
+  if params.is_empty() {
+    return None;
+  }
+
+  Some(GenericParams {
+    params: params
+      .iter()
+      .map(|p| GenericParam {
+        param: identifier(p),
+        #[cfg(feature = "ast-comments")]
+        comments_before_ident: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_ident: None,
+      })
+      .collect(),
+    #[cfg(feature = "ast-span")]
+    span: Span::default(),
+  })
+}
+
+fn generic_args(args: &[TypeBuilder]) -> Option<GenericArgs<'_>> {
+  if args.is_empty() {
+    return None;
+  }
+
+  Some(GenericArgs {
+    args: args
+      .iter()
+      .map(|a| GenericArg {
+        arg: Box::new(a.build_type1()),
+        #[cfg(feature = "ast-comments")]
+        comments_before_type: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_type: None,
+      })
+      .collect(),
+    #[cfg(feature = "ast-span")]
+    span: Span::default(),
+  })
+}
+
+fn parenthesized(t: &TypeBuilder) -> Type2<'_> {
+  Type2::ParenthesizedType {
+    pt: t.build(),
+    #[cfg(feature = "ast-span")]
+    span: Span::default(),
+    #[cfg(feature = "ast-comments")]
+    comments_before_type: None,
+    #[cfg(feature = "ast-comments")]
+    comments_after_type: None,
+  }
+}
+
+// Whether the name can be written as an identifier
+//
+// id = EALPHA *(*("-" / ".") (EALPHA / DIGIT))
+fn is_identifier(name: &str) -> bool {
+  let is_ealpha = |c: char| c.is_ascii_alphabetic() || c == '@' || c == '_' || c == '$';
+
+  let mut chars = name.chars();
+  if !chars.next().is_some_and(is_ealpha) {
+    return false;
+  }
+
+  name
+    .chars()
+    .all(|c| is_ealpha(c) || c.is_ascii_digit() || c == '-' || c == '.')
+    && !name.ends_with('-')
+    && !name.ends_with('.')
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{cddl_from_str, diff::normalize};
+
+  #[test]
+  fn verify_builder() {
+    let mut builder = CddlBuilder::new();
+    builder
+      .type_rule(
+        TypeRuleBuilder::new(
+          "message",
+          TypeBuilder::map(
+            GroupBuilder::new()
+              .entry(EntryBuilder::member("id", TypeBuilder::typename("uint")))
+              .entry(
+                EntryBuilder::member(
+                  "first name",
+                  TypeBuilder::typename("tstr").control(
+                    "size",
+                    TypeBuilder::range(TypeBuilder::uint(1), TypeBuilder::uint(64), true),
+                  ),
+                )
+                .optional(),
+              )
+              .entry(EntryBuilder::int_key(
+                -1,
+                TypeBuilder::generic("body", vec![TypeBuilder::typename("T")]),
+              ))
+              .entry(
+                EntryBuilder::type_key(
+                  TypeBuilder::typename("tstr"),
+                  TypeBuilder::typename("any"),
+                  false,
+                )
+                .zero_or_more(),
+              ),
+          ),
+        )
+        .generic_params(["T"]),
+      )
+      .type_rule(
+        TypeRuleBuilder::new(
+          "body",
+          TypeBuilder::array(
+            GroupBuilder::new()
+              .entry(EntryBuilder::value(TypeBuilder::typename("T")).occurs(Some(1), Some(3)))
+              .or(GroupBuilder::new().entry(EntryBuilder::groupname("$$extension"))),
+          ),
+        )
+        .generic_params(["T"]),
+      )
+      .type_rule(
+        TypeRuleBuilder::new(
+          "body",
+          TypeBuilder::tagged(Some(24), TypeBuilder::typename("bstr")),
+        )
+        .generic_params(["T"])
+        .choice_alternate(),
+      )
+      .group_rule(GroupRuleBuilder::new(
+        "header",
+        GroupBuilder::new()
+          .entry(EntryBuilder::text_key(
+            "kind",
+            TypeBuilder::text("a").or(TypeBuilder::int(-2)),
+          ))
+          .entry(
+            EntryBuilder::group(
+              GroupBuilder::new().entry(EntryBuilder::member("v", TypeBuilder::float(1.5))),
+            )
+            .optional(),
+          ),
+      ));
+
+    let text = builder.to_string();
+    let expected = r#"
+      message<T> = {
+        id: uint,
+        ? "first name": tstr .size (1..64),
+        -1: body<T>,
+        * tstr => any
+      }
+      body<T> = [1*3 T // $$extension]
+      body<T> /= #6.24(bstr)
+      header = ("kind": "a" / -2, ? (v: 1.5))
+    "#;
+    let parsed = cddl_from_str(&text, true).unwrap();
+    assert_eq!(normalize(&parsed.to_string()), normalize(expected));
+    assert_eq!(
+      normalize(&builder.build().to_string()),
+      normalize(&parsed.to_string())
+    );
+
+    assert!(builder.remove_rule("header"));
+    assert!(!builder.remove_rule("header"));
+    assert_eq!(builder.build().rules.len(), 3);
+  }
+}
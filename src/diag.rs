@@ -0,0 +1,485 @@
+#![cfg(feature = "std")]
+#![cfg(feature = "cbor")]
+
+use ciborium::value::{Integer, Value};
+use displaydoc::Display;
+use std::convert::TryFrom;
+
+/// Alias for `Result` with an error of type `diag::Error`
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Diagnostic notation parsing error
+#[derive(Debug, Display, PartialEq)]
+pub enum Error {
+  /// unexpected end of input
+  UnexpectedEnd,
+  /// unexpected {found:?} at offset {offset}
+  Unexpected {
+    /// Character found
+    found: char,
+    /// Byte offset of the character in the input
+    offset: usize,
+  },
+  /// invalid {kind} at offset {offset}: {reason}
+  Invalid {
+    /// Kind of item that is invalid, e.g. "number"
+    kind: &'static str,
+    /// Byte offset of the item in the input
+    offset: usize,
+    /// Why the item is invalid
+    reason: String,
+  },
+  /// {0} is not supported
+  Unsupported(String),
+}
+
+impl std::error::Error for Error {}
+
+/// Parse a single data item written in CBOR diagnostic notation (RFC 8949
+/// section 8 and RFC 8610 appendix G), e.g. `{1: h'a1b2', "tags": [_ 0("x")]}`.
+/// Indefinite length markers and encoding indicators are accepted but don't
+/// affect the value. Comments are delimited by slashes
+///
+/// # Example
+///
+/// ```
+/// use cddl::diag::cbor_from_diag;
+/// use ciborium::value::Value;
+///
+/// let value = cbor_from_diag(r#"[1, -2.5, "a", h'ff']"#).unwrap();
+/// assert_eq!(
+///   value,
+///   Value::Array(vec![
+///     Value::Integer(1.into()),
+///     Value::Float(-2.5),
+///     Value::Text("a".into()),
+///     Value::Bytes(vec![0xff]),
+///   ])
+/// );
+/// ```
+pub fn cbor_from_diag(input: &str) -> Result<Value> {
+  let mut p = DiagParser { input, pos: 0 };
+  let value = p.parse_item()?;
+  p.skip_trivia()?;
+
+  match p.peek() {
+    Some(found) => Err(Error::Unexpected {
+      found,
+      offset: p.pos,
+    }),
+    None => Ok(value),
+  }
+}
+
+struct DiagParser<'a> {
+  input: &'a str,
+  pos: usize,
+}
+
+impl<'a> DiagParser<'a> {
+  fn peek(&self) -> Option<char> {
+    self.input[self.pos..].chars().next()
+  }
+
+  fn bump(&mut self) -> Option<char> {
+    let c = self.peek()?;
+    self.pos += c.len_utf8();
+    Some(c)
+  }
+
+  fn expect(&mut self, expected: char) -> Result<()> {
+    self.skip_trivia()?;
+    match self.bump() {
+      Some(c) if c == expected => Ok(()),
+      Some(found) => Err(Error::Unexpected {
+        found,
+        offset: self.pos - found.len_utf8(),
+      }),
+      None => Err(Error::UnexpectedEnd),
+    }
+  }
+
+  // Skip whitespace and comments
+  fn skip_trivia(&mut self) -> Result<()> {
+    loop {
+      match self.peek() {
+        Some(c) if c.is_whitespace() => {
+          self.bump();
+        }
+        Some('/') => {
+          self.bump();
+          match self.input[self.pos..].find('/') {
+            Some(len) => self.pos += len + 1,
+            None => return Err(Error::UnexpectedEnd),
+          }
+        }
+        _ => return Ok(()),
+      }
+    }
+  }
+
+  // Consume the `_` marking an indefinite length item, if any
+  fn skip_indefinite_marker(&mut self) -> Result<bool> {
+    self.skip_trivia()?;
+    if self.peek() == Some('_') {
+      self.bump();
+      return Ok(true);
+    }
+
+    Ok(false)
+  }
+
+  // Consume an encoding indicator such as `_1` following an item, if any
+  fn skip_encoding_indicator(&mut self) {
+    let rest = &self.input[self.pos..];
+    if rest.starts_with('_') && rest[1..].starts_with(|c: char| c.is_ascii_digit()) {
+      self.pos += 2;
+    }
+  }
+
+  fn parse_item(&mut self) -> Result<Value> {
+    self.skip_trivia()?;
+
+    let start = self.pos;
+    let value = match self.peek().ok_or(Error::UnexpectedEnd)? {
+      '[' => {
+        self.bump();
+        self.skip_indefinite_marker()?;
+        let mut items = Vec::new();
+        self.parse_sequence(']', |p| {
+          items.push(p.parse_item()?);
+          Ok(())
+        })?;
+        Value::Array(items)
+      }
+      '{' => {
+        self.bump();
+        self.skip_indefinite_marker()?;
+        let mut entries = Vec::new();
+        self.parse_sequence('}', |p| {
+          let key = p.parse_item()?;
+          p.expect(':')?;
+          entries.push((key, p.parse_item()?));
+          Ok(())
+        })?;
+        Value::Map(entries)
+      }
+      '(' => self.parse_indefinite_string()?,
+      '"' => Value::Text(self.parse_text()?),
+      '\'' => Value::Bytes(self.parse_quoted()?.into_bytes()),
+      c if c.is_ascii_alphabetic() => self.parse_word(start)?,
+      _ => self.parse_number(start)?,
+    };
+
+    self.skip_encoding_indicator();
+
+    Ok(value)
+  }
+
+  // Parse comma separated items up to the given closing delimiter, which is
+  // consumed. A trailing comma is not allowed
+  fn parse_sequence<F>(&mut self, close: char, mut item: F) -> Result<()>
+  where
+    F: FnMut(&mut Self) -> Result<()>,
+  {
+    self.skip_trivia()?;
+    if self.peek() == Some(close) {
+      self.bump();
+      return Ok(());
+    }
+
+    loop {
+      item(self)?;
+      self.skip_trivia()?;
+      match self.bump() {
+        Some(',') => continue,
+        Some(c) if c == close => return Ok(()),
+        Some(found) => {
+          return Err(Error::Unexpected {
+            found,
+            offset: self.pos - found.len_utf8(),
+          })
+        }
+        None => return Err(Error::UnexpectedEnd),
+      }
+    }
+  }
+
+  // Parse the chunks of an indefinite length text or byte string, e.g.
+  // `(_ h'01', h'02')`, into a definite length string
+  fn parse_indefinite_string(&mut self) -> Result<Value> {
+    let start = self.pos;
+    self.bump();
+    if !self.skip_indefinite_marker()? {
+      return Err(Error::Invalid {
+        kind: "indefinite length string",
+        offset: start,
+        reason: "expected _ after (".to_string(),
+      });
+    }
+
+    let mut chunks = Vec::new();
+    self.parse_sequence(')', |p| {
+      chunks.push(p.parse_item()?);
+      Ok(())
+    })?;
+
+    let invalid = |reason: &str| Error::Invalid {
+      kind: "indefinite length string",
+      offset: start,
+      reason: reason.to_string(),
+    };
+
+    match chunks.first() {
+      Some(Value::Text(_)) | None => {
+        let mut text = String::new();
+        for chunk in chunks {
+          match chunk {
+            Value::Text(t) => text.push_str(&t),
+            _ => return Err(invalid("chunks must all be text strings")),
+          }
+        }
+        Ok(Value::Text(text))
+      }
+      Some(Value::Bytes(_)) => {
+        let mut bytes = Vec::new();
+        for chunk in chunks {
+          match chunk {
+            Value::Bytes(mut b) => bytes.append(&mut b),
+            _ => return Err(invalid("chunks must all be byte strings")),
+          }
+        }
+        Ok(Value::Bytes(bytes))
+      }
+      Some(_) => Err(invalid("chunks must be text or byte strings")),
+    }
+  }
+
+  // Parse a double quoted text string, with the escapes of JSON strings
+  fn parse_text(&mut self) -> Result<String> {
+    let start = self.pos;
+    self.bump();
+    loop {
+      match self.bump().ok_or(Error::UnexpectedEnd)? {
+        '\\' => {
+          self.bump().ok_or(Error::UnexpectedEnd)?;
+        }
+        '"' => break,
+        _ => {}
+      }
+    }
+
+    serde_json::from_str(&self.input[start..self.pos]).map_err(|e| Error::Invalid {
+      kind: "text string",
+      offset: start,
+      reason: e.to_string(),
+    })
+  }
+
+  // Parse the content of a single quoted string, in which `\'` and `\\` are
+  // escaped
+  fn parse_quoted(&mut self) -> Result<String> {
+    self.bump();
+    let mut content = String::new();
+    loop {
+      match self.bump().ok_or(Error::UnexpectedEnd)? {
+        '\\' => content.push(self.bump().ok_or(Error::UnexpectedEnd)?),
+        '\'' => return Ok(content),
+        c => content.push(c),
+      }
+    }
+  }
+
+  // Parse an item starting with a letter: a simple value, a special float, or
+  // a byte string with an encoding prefix such as `h'` or `b64'`
+  fn parse_word(&mut self, start: usize) -> Result<Value> {
+    while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric()) {
+      self.bump();
+    }
+
+    let word = &self.input[start..self.pos];
+    if self.peek() == Some('\'') {
+      let content = self.parse_quoted()?;
+      let content = content
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+      let invalid = |reason: String| Error::Invalid {
+        kind: "byte string",
+        offset: start,
+        reason,
+      };
+
+      let bytes = match word {
+        "h" => base16::decode(&content).map_err(|e| invalid(e.to_string()))?,
+        "b64" => {
+          let content = content.trim_end_matches('=');
+          let config = if content.contains(&['-', '_'][..]) {
+            base64::URL_SAFE_NO_PAD
+          } else {
+            base64::STANDARD_NO_PAD
+          };
+          base64::decode_config(content, config).map_err(|e| invalid(e.to_string()))?
+        }
+        _ => return Err(Error::Unsupported(format!("byte string prefix {}", word))),
+      };
+
+      return Ok(Value::Bytes(bytes));
+    }
+
+    match word {
+      "true" => Ok(Value::Bool(true)),
+      "false" => Ok(Value::Bool(false)),
+      "null" => Ok(Value::Null),
+      "NaN" => Ok(Value::Float(f64::NAN)),
+      "Infinity" => Ok(Value::Float(f64::INFINITY)),
+      "undefined" | "simple" => Err(Error::Unsupported(format!("{} value", word))),
+      _ => Err(Error::Invalid {
+        kind: "item",
+        offset: start,
+        reason: format!("unknown word {}", word),
+      }),
+    }
+  }
+
+  // Parse an integer, a float, or a tag number along with its tagged item
+  fn parse_number(&mut self, start: usize) -> Result<Value> {
+    if self.peek() == Some('-') {
+      self.bump();
+      if self.input[self.pos..].starts_with("Infinity") {
+        self.pos += "Infinity".len();
+        return Ok(Value::Float(f64::NEG_INFINITY));
+      }
+    }
+
+    let mut prev = None;
+    while let Some(c) = self.peek() {
+      let is_exponent_sign = matches!(c, '+' | '-')
+        && matches!(prev, Some('e') | Some('E'))
+        && !self.input[start..self.pos].contains(&['x', 'X'][..]);
+      if !(c.is_ascii_alphanumeric() || c == '.' || is_exponent_sign) {
+        break;
+      }
+
+      prev = self.bump();
+    }
+
+    let literal = &self.input[start..self.pos];
+    let invalid = |reason: String| Error::Invalid {
+      kind: "number",
+      offset: start,
+      reason,
+    };
+    if literal.is_empty() || literal == "-" {
+      return match self.peek() {
+        Some(found) => Err(Error::Unexpected {
+          found,
+          offset: self.pos,
+        }),
+        None => Err(Error::UnexpectedEnd),
+      };
+    }
+
+    let (negative, digits) = match literal.strip_prefix('-') {
+      Some(digits) => (true, digits),
+      None => (false, literal),
+    };
+    let radix = match digits.get(..2) {
+      Some("0x") | Some("0X") => Some(16),
+      Some("0o") | Some("0O") => Some(8),
+      Some("0b") | Some("0B") => Some(2),
+      _ => None,
+    };
+
+    if radix.is_none() && digits.contains(&['.', 'e', 'E'][..]) {
+      return literal
+        .parse::<f64>()
+        .map(Value::Float)
+        .map_err(|e| invalid(e.to_string()));
+    }
+
+    let magnitude = match radix {
+      Some(radix) => u128::from_str_radix(&digits[2..], radix),
+      None => digits.parse::<u128>(),
+    }
+    .map_err(|e| invalid(e.to_string()))?;
+
+    // A tag number is immediately followed by the tagged item in parentheses
+    if !negative && self.peek() == Some('(') {
+      let tag = u64::try_from(magnitude).map_err(|e| invalid(e.to_string()))?;
+      self.bump();
+      let item = self.parse_item()?;
+      self.expect(')')?;
+
+      return Ok(Value::Tag(tag, Box::new(item)));
+    }
+
+    let value = if negative {
+      i128::try_from(magnitude).map(|m| -m)
+    } else {
+      i128::try_from(magnitude)
+    }
+    .map_err(|e| invalid(e.to_string()))?;
+
+    Integer::try_from(value)
+      .map(Value::Integer)
+      .map_err(|_| invalid(format!("{} is out of the range of CBOR integers", literal)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_diag() -> Result<()> {
+    let int = |i: i64| Value::Integer(i.into());
+
+    assert_eq!(cbor_from_diag("0")?, int(0));
+    assert_eq!(cbor_from_diag("-17")?, int(-17));
+    assert_eq!(cbor_from_diag("0x1f_1")?, int(31));
+    assert_eq!(
+      cbor_from_diag("18446744073709551615")?,
+      Value::Integer(u64::MAX.into())
+    );
+    assert_eq!(cbor_from_diag("1.5e+3")?, Value::Float(1500.0));
+    assert_eq!(
+      cbor_from_diag("-Infinity")?,
+      Value::Float(f64::NEG_INFINITY)
+    );
+    assert_eq!(cbor_from_diag(r#""a\"bé""#)?, Value::Text("a\"bé".into()));
+    assert_eq!(cbor_from_diag("h'01 02'")?, Value::Bytes(vec![1, 2]));
+    assert_eq!(cbor_from_diag("b64'AQI'")?, Value::Bytes(vec![1, 2]));
+    assert_eq!(cbor_from_diag("'hi'")?, Value::Bytes(b"hi".to_vec()));
+    assert_eq!(cbor_from_diag(r#"(_ "a", "b")"#)?, Value::Text("ab".into()));
+    assert_eq!(
+      cbor_from_diag(r#"{_ 1: [_ true, null] / comment /, "k": 1(1363896240)}"#)?,
+      Value::Map(vec![
+        (int(1), Value::Array(vec![Value::Bool(true), Value::Null])),
+        (
+          Value::Text("k".into()),
+          Value::Tag(1, Box::new(int(1363896240)))
+        ),
+      ])
+    );
+
+    assert_eq!(cbor_from_diag("[1, 2"), Err(Error::UnexpectedEnd));
+    assert_eq!(
+      cbor_from_diag("[1 2]"),
+      Err(Error::Unexpected {
+        found: '2',
+        offset: 3
+      })
+    );
+    assert!(matches!(
+      cbor_from_diag("h'0'"),
+      Err(Error::Invalid { offset: 0, .. })
+    ));
+    assert!(matches!(
+      cbor_from_diag("undefined"),
+      Err(Error::Unsupported(_))
+    ));
+
+    Ok(())
+  }
+}
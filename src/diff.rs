@@ -0,0 +1,874 @@
+#![cfg(feature = "std")]
+
+use crate::{analysis, ast::*, token};
+
+use std::{collections::BTreeMap, fmt};
+
+/// Compatibility of a change between two CDDL documents. A change is
+/// backwards-compatible if every instance that is valid against the old
+/// document remains valid against the new document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+  /// Existing instances remain valid
+  Compatible,
+  /// Existing instances may no longer be valid
+  Breaking,
+}
+
+impl fmt::Display for Compatibility {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Compatibility::Compatible => write!(f, "compatible"),
+      Compatibility::Breaking => write!(f, "breaking"),
+    }
+  }
+}
+
+/// Kind of change made to a rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+  /// Rule only exists in the new document
+  Added,
+  /// Rule only exists in the old document
+  Removed,
+  /// Rule exists in both documents, but its definition changed
+  Modified,
+}
+
+impl fmt::Display for ChangeKind {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ChangeKind::Added => write!(f, "+"),
+      ChangeKind::Removed => write!(f, "-"),
+      ChangeKind::Modified => write!(f, "~"),
+    }
+  }
+}
+
+/// Individual change within a modified rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeDetail {
+  /// Description of the change
+  pub description: String,
+  /// Compatibility of the change
+  pub compatibility: Compatibility,
+}
+
+/// Change made to a rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+  /// Rule name
+  pub rule: String,
+  /// Kind of change
+  pub kind: ChangeKind,
+  /// Compatibility of the change as a whole
+  pub compatibility: Compatibility,
+  /// Individual changes within a modified rule
+  pub details: Vec<ChangeDetail>,
+}
+
+impl fmt::Display for Change {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} {} ({})", self.kind, self.rule, self.compatibility)?;
+
+    for detail in self.details.iter() {
+      write!(f, "\n    {} ({})", detail.description, detail.compatibility)?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Semantic differences between two CDDL documents. Whitespace, comments and
+/// the order of map members and type choices are disregarded
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Diff {
+  /// Changed rules, ordered by rule name
+  pub changes: Vec<Change>,
+}
+
+impl Diff {
+  /// Whether the documents are semantically equivalent
+  pub fn is_empty(&self) -> bool {
+    self.changes.is_empty()
+  }
+
+  /// Whether all changes are backwards-compatible
+  pub fn is_compatible(&self) -> bool {
+    self
+      .changes
+      .iter()
+      .all(|c| c.compatibility == Compatibility::Compatible)
+  }
+
+  /// Changes that are not backwards-compatible
+  pub fn breaking_changes(&self) -> impl Iterator<Item = &Change> {
+    self
+      .changes
+      .iter()
+      .filter(|c| c.compatibility == Compatibility::Breaking)
+  }
+}
+
+impl fmt::Display for Diff {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for change in self.changes.iter() {
+      writeln!(f, "{}", change)?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Compare two CDDL documents and report added, removed and modified rules.
+/// Replacing a type is compatible if the new type matches every value of the
+/// old type, e.g. `uint` with `int` or `0..10` with `0..20`, as decided by
+/// [`analysis::is_subtype`]. Replacements whose compatibility can't be decided
+/// are reported as breaking
+///
+/// # Example
+///
+/// ```
+/// use cddl::{cddl_from_str, diff::diff};
+///
+/// let old = cddl_from_str("person = { name: tstr, age: uint }", true).unwrap();
+/// let new = cddl_from_str("person = { name: tstr, ? email: tstr }", true).unwrap();
+///
+/// let diff = diff(&old, &new);
+/// assert!(!diff.is_compatible());
+/// ```
+pub fn diff(old: &CDDL, new: &CDDL) -> Diff {
+  let docs = Documents { old, new };
+  let old_rules = rules_by_name(old);
+  let new_rules = rules_by_name(new);
+
+  let mut changes = Vec::new();
+
+  for (name, old_defs) in old_rules.iter() {
+    match new_rules.get(name) {
+      Some(new_defs) => {
+        let details = diff_rule_defs(docs, old_defs, new_defs);
+        if !details.is_empty() {
+          changes.push(Change {
+            rule: name.clone(),
+            kind: ChangeKind::Modified,
+            compatibility: overall_compatibility(&details),
+            details,
+          });
+        }
+      }
+      None => changes.push(Change {
+        rule: name.clone(),
+        kind: ChangeKind::Removed,
+        compatibility: Compatibility::Breaking,
+        details: Vec::new(),
+      }),
+    }
+  }
+
+  for name in new_rules.keys() {
+    if !old_rules.contains_key(name) {
+      changes.push(Change {
+        rule: name.clone(),
+        kind: ChangeKind::Added,
+        compatibility: Compatibility::Compatible,
+        details: Vec::new(),
+      });
+    }
+  }
+
+  changes.sort_by(|a, b| a.rule.cmp(&b.rule));
+
+  Diff { changes }
+}
+
+/// Compare two CDDL document strings and report added, removed and modified
+/// rules
+#[cfg(not(target_arch = "wasm32"))]
+pub fn diff_from_str(old: &str, new: &str) -> Result<Diff, crate::parser::Error> {
+  let old = crate::cddl_from_str(old, false)?;
+  let new = crate::cddl_from_str(new, false)?;
+
+  Ok(diff(&old, &new))
+}
+
+// Documents being compared, in which the names referenced by the types of
+// their rules are resolved
+#[derive(Clone, Copy)]
+struct Documents<'c, 'a> {
+  old: &'c CDDL<'a>,
+  new: &'c CDDL<'a>,
+}
+
+// Rules keyed by name. Type and group choice alternates (`/=` and `//=`) are
+// collected under the name of the rule they extend
+fn rules_by_name<'a, 'b>(cddl: &'b CDDL<'a>) -> BTreeMap<String, Vec<&'b Rule<'a>>> {
+  let mut rules: BTreeMap<String, Vec<&Rule>> = BTreeMap::new();
+  for rule in cddl.rules.iter() {
+    rules.entry(rule.name()).or_default().push(rule);
+  }
+
+  rules
+}
+
+fn overall_compatibility(details: &[ChangeDetail]) -> Compatibility {
+  if details
+    .iter()
+    .any(|d| d.compatibility == Compatibility::Breaking)
+  {
+    Compatibility::Breaking
+  } else {
+    Compatibility::Compatible
+  }
+}
+
+fn detail(description: String, compatibility: Compatibility) -> ChangeDetail {
+  ChangeDetail {
+    description,
+    compatibility,
+  }
+}
+
+fn compatible_if(is_compatible: bool) -> Compatibility {
+  if is_compatible {
+    Compatibility::Compatible
+  } else {
+    Compatibility::Breaking
+  }
+}
+
+fn diff_rule_defs(docs: Documents, old: &[&Rule], new: &[&Rule]) -> Vec<ChangeDetail> {
+  let old_text = old
+    .iter()
+    .map(|r| normalize(&r.to_string()))
+    .collect::<Vec<_>>();
+  let new_text = new
+    .iter()
+    .map(|r| normalize(&r.to_string()))
+    .collect::<Vec<_>>();
+
+  if old_text == new_text {
+    return Vec::new();
+  }
+
+  let old_params = old.first().and_then(|r| generic_params(r));
+  let new_params = new.first().and_then(|r| generic_params(r));
+  if old_params != new_params {
+    return vec![detail(
+      format!(
+        "generic parameters changed from <{}> to <{}>",
+        old_params.unwrap_or_default().join(", "),
+        new_params.unwrap_or_default().join(", ")
+      ),
+      Compatibility::Breaking,
+    )];
+  }
+
+  match (old, new) {
+    ([Rule::Type { rule: old, .. }], [Rule::Type { rule: new, .. }]) => {
+      diff_types(docs, &old.value, &new.value)
+    }
+    ([Rule::Group { rule: old, .. }], [Rule::Group { rule: new, .. }]) => {
+      diff_group_entries(docs, &[&old.entry], &[&new.entry], false)
+    }
+    _ if old
+      .iter()
+      .chain(new.iter())
+      .all(|r| matches!(r, Rule::Type { .. })) =>
+    {
+      let old_choices = rule_type_choices(old);
+      let new_choices = rule_type_choices(new);
+      diff_type_choices(docs, &old_choices, &new_choices, None)
+    }
+    _ => {
+      let old_choices = rule_choices(old);
+      let new_choices = rule_choices(new);
+      diff_choices(&old_choices, &new_choices)
+    }
+  }
+}
+
+fn generic_params(rule: &Rule) -> Option<Vec<String>> {
+  let params = match rule {
+    Rule::Type { rule, .. } => rule.generic_params.as_ref(),
+    Rule::Group { rule, .. } => rule.generic_params.as_ref(),
+  }?;
+
+  Some(
+    params
+      .params
+      .iter()
+      .map(|gp| gp.param.to_string())
+      .collect(),
+  )
+}
+
+// Normalized type choices across a type rule and its alternates, along with
+// their definitions
+fn rule_type_choices<'b, 'a>(rules: &[&'b Rule<'a>]) -> Vec<(String, &'b Type1<'a>)> {
+  rules
+    .iter()
+    .flat_map(|rule| match rule {
+      Rule::Type { rule, .. } => type_choices(&rule.value),
+      Rule::Group { .. } => Vec::new(),
+    })
+    .collect()
+}
+
+// Normalized type or group choices across a rule and its alternates
+fn rule_choices(rules: &[&Rule]) -> Vec<String> {
+  let mut choices = Vec::new();
+  for rule in rules.iter() {
+    match rule {
+      Rule::Type { rule, .. } => {
+        for tc in rule.value.type_choices.iter() {
+          choices.push(normalize(&tc.type1.to_string()));
+        }
+      }
+      Rule::Group { rule, .. } => choices.push(normalize(&rule.entry.to_string())),
+    }
+  }
+
+  choices
+}
+
+fn diff_choices(old: &[String], new: &[String]) -> Vec<ChangeDetail> {
+  let mut details = Vec::new();
+
+  for choice in old.iter().filter(|c| !new.contains(c)) {
+    details.push(detail(
+      format!("removed choice `{}`", choice),
+      Compatibility::Breaking,
+    ));
+  }
+
+  for choice in new.iter().filter(|c| !old.contains(c)) {
+    details.push(detail(
+      format!("added choice `{}`", choice),
+      Compatibility::Compatible,
+    ));
+  }
+
+  details
+}
+
+// Changes of the type choices of a rule, or of the given member. A removed
+// choice is compatible if the new choices match all of its values, in which
+// case the new choices are listed as its replacement rather than as added
+fn diff_type_choices(
+  docs: Documents,
+  old: &[(String, &Type1)],
+  new: &[(String, &Type1)],
+  member: Option<&str>,
+) -> Vec<ChangeDetail> {
+  let mut details = Vec::new();
+  let mut is_any_widened = false;
+
+  let new_types = new.iter().map(|(_, t1)| *t1).collect::<Vec<_>>();
+  let new_text = new
+    .iter()
+    .map(|(choice, _)| choice.as_str())
+    .collect::<Vec<_>>()
+    .join(" / ");
+
+  for (choice, t1) in old.iter().filter(|(c, _)| !new.iter().any(|(n, _)| n == c)) {
+    let is_widened =
+      analysis::type_choices_subtype_across(docs.old, &[*t1], docs.new, &new_types) == Ok(true);
+    let description = match (member, is_widened) {
+      (Some(key), true) => format!("widened type `{}` of `{}` to `{}`", choice, key, new_text),
+      (Some(key), false) => format!("removed type `{}` from `{}`", choice, key),
+      (None, true) => format!("widened choice `{}` to `{}`", choice, new_text),
+      (None, false) => format!("removed choice `{}`", choice),
+    };
+
+    is_any_widened |= is_widened;
+    details.push(detail(description, compatible_if(is_widened)));
+  }
+
+  if is_any_widened {
+    return details;
+  }
+
+  for (choice, _) in new.iter().filter(|(c, _)| !old.iter().any(|(o, _)| o == c)) {
+    let description = match member {
+      Some(key) => format!("added type `{}` to `{}`", choice, key),
+      None => format!("added choice `{}`", choice),
+    };
+
+    details.push(detail(description, Compatibility::Compatible));
+  }
+
+  details
+}
+
+fn diff_types(docs: Documents, old: &Type, new: &Type) -> Vec<ChangeDetail> {
+  if let ([old_tc], [new_tc]) = (&old.type_choices[..], &new.type_choices[..]) {
+    if old_tc.type1.operator.is_none() && new_tc.type1.operator.is_none() {
+      match (&old_tc.type1.type2, &new_tc.type1.type2) {
+        (Type2::Map { group: old, .. }, Type2::Map { group: new, .. }) => {
+          if let Some(details) = diff_groups(docs, old, new, false) {
+            return details;
+          }
+        }
+        (Type2::Array { group: old, .. }, Type2::Array { group: new, .. }) => {
+          if let Some(details) = diff_groups(docs, old, new, true) {
+            return details;
+          }
+        }
+        _ => (),
+      }
+    }
+  }
+
+  diff_type_choices(docs, &type_choices(old), &type_choices(new), None)
+}
+
+// Normalized type choices of a type along with their definitions
+fn type_choices<'b, 'a>(t: &'b Type<'a>) -> Vec<(String, &'b Type1<'a>)> {
+  t.type_choices
+    .iter()
+    .map(|tc| (normalize(&tc.type1.to_string()), &tc.type1))
+    .collect()
+}
+
+// Structural comparison of groups with a single group choice. Returns `None` if
+// either group has multiple group choices
+fn diff_groups(
+  docs: Documents,
+  old: &Group,
+  new: &Group,
+  is_array: bool,
+) -> Option<Vec<ChangeDetail>> {
+  if let ([old], [new]) = (&old.group_choices[..], &new.group_choices[..]) {
+    let old_entries = old
+      .group_entries
+      .iter()
+      .map(|(ge, _)| ge)
+      .collect::<Vec<_>>();
+    let new_entries = new
+      .group_entries
+      .iter()
+      .map(|(ge, _)| ge)
+      .collect::<Vec<_>>();
+
+    return Some(diff_group_entries(
+      docs,
+      &old_entries,
+      &new_entries,
+      is_array,
+    ));
+  }
+
+  None
+}
+
+fn diff_group_entries(
+  docs: Documents,
+  old: &[&GroupEntry],
+  new: &[&GroupEntry],
+  is_array: bool,
+) -> Vec<ChangeDetail> {
+  if is_array {
+    return diff_array_entries(docs, old, new);
+  }
+
+  let mut details = Vec::new();
+
+  for old_entry in old.iter() {
+    let key = entry_key(old_entry);
+    match new.iter().find(|e| entry_key(e) == key) {
+      Some(new_entry) => details.append(&mut diff_entry(docs, &key, old_entry, new_entry)),
+      None => details.push(detail(
+        format!("removed {} member `{}`", requiredness(old_entry), key),
+        Compatibility::Breaking,
+      )),
+    }
+  }
+
+  for new_entry in new.iter() {
+    let key = entry_key(new_entry);
+    if !old.iter().any(|e| entry_key(e) == key) {
+      details.push(detail(
+        format!("added {} member `{}`", requiredness(new_entry), key),
+        compatible_if(is_optional(new_entry)),
+      ));
+    }
+  }
+
+  details
+}
+
+// Array entries are positional, so entries are compared by index
+fn diff_array_entries(
+  docs: Documents,
+  old: &[&GroupEntry],
+  new: &[&GroupEntry],
+) -> Vec<ChangeDetail> {
+  let mut details = Vec::new();
+
+  for (idx, (old_entry, new_entry)) in old.iter().zip(new.iter()).enumerate() {
+    details.append(&mut diff_entry(
+      docs,
+      &format!("{}", idx),
+      old_entry,
+      new_entry,
+    ));
+  }
+
+  for (idx, old_entry) in old.iter().enumerate().skip(new.len()) {
+    details.push(detail(
+      format!(
+        "removed {} entry {} `{}`",
+        requiredness(old_entry),
+        idx,
+        entry_key(old_entry)
+      ),
+      Compatibility::Breaking,
+    ));
+  }
+
+  for (idx, new_entry) in new.iter().enumerate().skip(old.len()) {
+    details.push(detail(
+      format!(
+        "added {} entry {} `{}`",
+        requiredness(new_entry),
+        idx,
+        entry_key(new_entry)
+      ),
+      compatible_if(is_optional(new_entry)),
+    ));
+  }
+
+  details
+}
+
+fn diff_entry(docs: Documents, key: &str, old: &GroupEntry, new: &GroupEntry) -> Vec<ChangeDetail> {
+  let mut details = Vec::new();
+
+  let (old_lower, old_upper) = occurrence_bounds(old);
+  let (new_lower, new_upper) = occurrence_bounds(new);
+  if (old_lower, old_upper) != (new_lower, new_upper) {
+    let widened = new_lower <= old_lower
+      && match (old_upper, new_upper) {
+        (_, None) => true,
+        (Some(old_upper), Some(new_upper)) => new_upper >= old_upper,
+        (None, Some(_)) => false,
+      };
+
+    details.push(detail(
+      format!(
+        "occurrence of `{}` changed from {} to {}",
+        key,
+        format_bounds(old_lower, old_upper),
+        format_bounds(new_lower, new_upper)
+      ),
+      compatible_if(widened),
+    ));
+  }
+
+  match (old, new) {
+    (GroupEntry::ValueMemberKey { ge: old, .. }, GroupEntry::ValueMemberKey { ge: new, .. }) => {
+      let old_choices = type_choices(&old.entry_type);
+      let new_choices = type_choices(&new.entry_type);
+      details.append(&mut diff_type_choices(
+        docs,
+        &old_choices,
+        &new_choices,
+        Some(key),
+      ));
+    }
+    _ => {
+      let old_text = normalize(&entry_without_occurrence(old));
+      let new_text = normalize(&entry_without_occurrence(new));
+      if old_text != new_text {
+        // Names in arrays are parsed as group names but usually refer to
+        // types, e.g. `[* uint]`
+        let is_widened = match (old, new) {
+          (
+            GroupEntry::TypeGroupname { ge: old, .. },
+            GroupEntry::TypeGroupname { ge: new, .. },
+          ) if old.generic_args.is_none() && new.generic_args.is_none() => {
+            analysis::is_subtype_across(
+              docs.old,
+              &old.name.to_string(),
+              docs.new,
+              &new.name.to_string(),
+            ) == Ok(true)
+          }
+          _ => false,
+        };
+
+        let description = if is_widened {
+          format!("widened `{}` to `{}`", old_text, new_text)
+        } else {
+          format!("`{}` changed to `{}`", old_text, new_text)
+        };
+
+        details.push(detail(description, compatible_if(is_widened)));
+      }
+    }
+  }
+
+  details
+}
+
+// Identifies a group entry across documents. Member entries are identified by
+// their key, other entries by their definition
+fn entry_key(entry: &GroupEntry) -> String {
+  if let GroupEntry::ValueMemberKey { ge, .. } = entry {
+    match &ge.member_key {
+      Some(MemberKey::Bareword { ident, .. }) => return ident.to_string(),
+      Some(MemberKey::Value {
+        value: token::Value::TEXT(t),
+        ..
+      }) => return t.to_string(),
+      Some(MemberKey::Value { value, .. }) => return value.to_string(),
+      Some(MemberKey::Type1 { t1, .. }) => return normalize(&t1.to_string()),
+      _ => (),
+    }
+  }
+
+  normalize(&entry_without_occurrence(entry))
+}
+
+fn entry_without_occurrence(entry: &GroupEntry) -> String {
+  match entry {
+    GroupEntry::ValueMemberKey { ge, .. } => match &ge.member_key {
+      Some(mk) => format!("{} {}", mk, ge.entry_type),
+      None => ge.entry_type.to_string(),
+    },
+    GroupEntry::TypeGroupname { ge, .. } => match &ge.generic_args {
+      Some(ga) => format!("{}{}", ge.name, ga),
+      None => ge.name.to_string(),
+    },
+    GroupEntry::InlineGroup { group, .. } => format!("({})", group),
+  }
+}
+
+fn occurrence<'a, 'b>(entry: &'b GroupEntry<'a>) -> Option<&'b Occur> {
+  match entry {
+    GroupEntry::ValueMemberKey { ge, .. } => ge.occur.as_ref().map(|o| &o.occur),
+    GroupEntry::TypeGroupname { ge, .. } => ge.occur.as_ref().map(|o| &o.occur),
+    GroupEntry::InlineGroup { occur, .. } => occur.as_ref().map(|o| &o.occur),
+  }
+}
+
+// Lower and optional upper bound of the occurrence of a group entry
+fn occurrence_bounds(entry: &GroupEntry) -> (usize, Option<usize>) {
+  match occurrence(entry) {
+    None => (1, Some(1)),
+    #[cfg(feature = "ast-span")]
+    Some(Occur::Optional(_)) => (0, Some(1)),
+    #[cfg(not(feature = "ast-span"))]
+    Some(Occur::Optional) => (0, Some(1)),
+    #[cfg(feature = "ast-span")]
+    Some(Occur::ZeroOrMore(_)) => (0, None),
+    #[cfg(not(feature = "ast-span"))]
+    Some(Occur::ZeroOrMore) => (0, None),
+    #[cfg(feature = "ast-span")]
+    Some(Occur::OneOrMore(_)) => (1, None),
+    #[cfg(not(feature = "ast-span"))]
+    Some(Occur::OneOrMore) => (1, None),
+    Some(Occur::Exact { lower, upper, .. }) => (lower.unwrap_or(0), *upper),
+  }
+}
+
+fn format_bounds(lower: usize, upper: Option<usize>) -> String {
+  match upper {
+    Some(upper) => format!("{}..{}", lower, upper),
+    None => format!("{}..", lower),
+  }
+}
+
+fn is_optional(entry: &GroupEntry) -> bool {
+  occurrence_bounds(entry).0 == 0
+}
+
+fn requiredness(entry: &GroupEntry) -> &'static str {
+  if is_optional(entry) {
+    "optional"
+  } else {
+    "required"
+  }
+}
+
+// Representation of the given CDDL text with comments and insignificant
+// whitespace removed
+pub(crate) fn normalize(input: &str) -> String {
+  let is_word_char = |c: char| c.is_alphanumeric() || "_-.$@".contains(c);
+
+  let mut output = String::with_capacity(input.len());
+  let mut chars = input.chars();
+  let mut quote: Option<char> = None;
+  let mut pending_space = false;
+
+  while let Some(c) = chars.next() {
+    if let Some(q) = quote {
+      output.push(c);
+      if c == '\\' {
+        if let Some(escaped) = chars.next() {
+          output.push(escaped);
+        }
+      } else if c == q {
+        quote = None;
+      }
+
+      continue;
+    }
+
+    if c == ';' {
+      for c in chars.by_ref() {
+        if c == '\n' {
+          break;
+        }
+      }
+
+      pending_space = true;
+      continue;
+    }
+
+    if c.is_whitespace() {
+      pending_space = true;
+      continue;
+    }
+
+    if pending_space && output.ends_with(is_word_char) && is_word_char(c) {
+      output.push(' ');
+    }
+    pending_space = false;
+
+    if c == '"' || c == '\'' || c == '`' {
+      quote = Some(c);
+    }
+
+    output.push(c);
+  }
+
+  output
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use indoc::indoc;
+
+  #[test]
+  fn verify_diff() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let old = indoc!(
+      r#"
+        person = {
+          name: tstr,
+          age: uint,
+          ? nickname: tstr,
+        }
+
+        color = "red" / "green"
+        point = [x: int, y: int]
+        legacy = bstr
+      "#
+    );
+
+    let new = indoc!(
+      r#"
+        ; comments and formatting are disregarded
+        person = {
+          ? nickname: tstr, ; made optional
+          name: tstr / null,
+          ? email: tstr,
+        }
+
+        color = "green" / "red" / "blue"
+        point = [x: int, y: int, ? z: int]
+        address = { street: tstr }
+      "#
+    );
+
+    let diff = diff_from_str(old, new)?;
+
+    assert!(!diff.is_compatible());
+    assert_eq!(
+      diff
+        .changes
+        .iter()
+        .map(|c| (c.rule.as_str(), c.kind, c.compatibility))
+        .collect::<Vec<_>>(),
+      vec![
+        ("address", ChangeKind::Added, Compatibility::Compatible),
+        ("color", ChangeKind::Modified, Compatibility::Compatible),
+        ("legacy", ChangeKind::Removed, Compatibility::Breaking),
+        ("person", ChangeKind::Modified, Compatibility::Breaking),
+        ("point", ChangeKind::Modified, Compatibility::Compatible),
+      ]
+    );
+
+    let person = diff.changes.iter().find(|c| c.rule == "person").unwrap();
+    assert_eq!(
+      person
+        .details
+        .iter()
+        .map(|d| (d.description.as_str(), d.compatibility))
+        .collect::<Vec<_>>(),
+      vec![
+        ("added type `null` to `name`", Compatibility::Compatible),
+        ("removed required member `age`", Compatibility::Breaking),
+        ("added optional member `email`", Compatibility::Compatible),
+      ]
+    );
+
+    assert!(diff_from_str(old, old)?.is_empty());
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_diff_by_containment() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cases = [
+      ("m = { x: uint }", "m = { x: int }", true),
+      ("m = { x: int }", "m = { x: uint }", false),
+      ("m = 0..10", "m = uint", true),
+      ("m = uint", "m = 0..10", false),
+      ("m = 0..10", "m = 0..20", true),
+      ("m = 0..20", "m = 0..10", false),
+      ("m = uint .size 1", "m = uint .size 2", true),
+      ("m = uint .size 2", "m = uint .size 1", false),
+      ("m = tstr .size 3", "m = tstr .size (1..5)", true),
+      ("m = tstr .size (1..5)", "m = tstr .size 3", false),
+      // The size of a string is exact, so the old strings no longer match
+      ("m = tstr .size 3", "m = tstr .size 5", false),
+      ("m = [* uint]", "m = [* int]", true),
+      ("m = [* int]", "m = [* uint]", false),
+      ("m = [* small]\nsmall = 0..10", "m = [* uint]", true),
+      ("m = [* uint]", "m = [* small]\nsmall = 0..10", false),
+      ("m = { x: small }\nsmall = 0..10", "m = { x: uint }", true),
+      ("m = { x: uint }", "m = { x: small }\nsmall = 0..10", false),
+    ];
+
+    for (old, new, is_compatible) in cases.iter() {
+      let diff = diff_from_str(old, new)?;
+      let m = diff.changes.iter().find(|c| c.rule == "m").unwrap();
+
+      assert_eq!(
+        m.compatibility,
+        compatible_if(*is_compatible),
+        "{} -> {}: {:?}",
+        old,
+        new,
+        m.details
+      );
+    }
+
+    let diff = diff_from_str("m = { x: uint }", "m = { x: int }")?;
+    assert_eq!(
+      diff.changes[0].details[0].description,
+      "widened type `uint` of `x` to `int`"
+    );
+
+    let diff = diff_from_str("m = uint", "m = 0..10")?;
+    assert_eq!(
+      diff.changes[0].details[0].description,
+      "removed choice `uint`"
+    );
+
+    Ok(())
+  }
+}
@@ -0,0 +1,443 @@
+#![cfg(feature = "std")]
+#![cfg(feature = "json")]
+#![cfg(feature = "cbor")]
+#![cfg(not(feature = "lsp"))]
+#![cfg(not(target_arch = "wasm32"))]
+
+use crate::{
+  ast::{Group, GroupRule, Type, TypeRule, CDDL},
+  validator::{
+    cbor::{self, CBORValidator, ReaderError},
+    json::{self, JSONValidator},
+    RuleLabels, Validator,
+  },
+  visitor::{self, Visitor},
+};
+
+use std::{collections::BTreeSet, convert::Infallible, fmt};
+
+/// Part of a CDDL document exercised by a valid instance
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CoverageItem {
+  /// Rule a location in the instance validated against
+  Rule(String),
+  /// Type choice a location in the instance matched
+  Choice {
+    /// Name of the innermost rule containing the type choice
+    rule: String,
+    /// Index of the choice
+    index: usize,
+    /// Choice
+    choice: String,
+  },
+  /// Group choice a location in the instance matched
+  GroupChoice {
+    /// Name of the innermost rule containing the group choice
+    rule: String,
+    /// Index of the choice
+    index: usize,
+    /// Choice
+    choice: String,
+  },
+}
+
+impl fmt::Display for CoverageItem {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      CoverageItem::Rule(rule) => write!(f, "rule {}", rule),
+      CoverageItem::Choice {
+        rule,
+        index,
+        choice,
+      } if rule.is_empty() => write!(f, "choice {} ({})", index, choice),
+      CoverageItem::Choice {
+        rule,
+        index,
+        choice,
+      } => write!(f, "choice {} ({}) of rule {}", index, choice, rule),
+      CoverageItem::GroupChoice {
+        rule,
+        index,
+        choice,
+      } if rule.is_empty() => write!(f, "group choice {} ({})", index, choice),
+      CoverageItem::GroupChoice {
+        rule,
+        index,
+        choice,
+      } => write!(f, "group choice {} ({}) of rule {}", index, choice, rule),
+    }
+  }
+}
+
+/// Rules, type choices and group choices of a CDDL document exercised by a
+/// valid instance
+pub type Coverage = BTreeSet<CoverageItem>;
+
+/// Coverage of the rules, type choices and group choices of a CDDL document by
+/// a corpus of instances
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverageReport {
+  /// Items of the document exercised by at least one instance
+  pub exercised: Coverage,
+  /// Items of the document no instance exercised
+  pub unexercised: Coverage,
+}
+
+impl CoverageReport {
+  /// Fraction of the items of the document exercised, 1 if it has none
+  pub fn ratio(&self) -> f64 {
+    let total = self.exercised.len() + self.unexercised.len();
+    if total == 0 {
+      return 1.0;
+    }
+
+    self.exercised.len() as f64 / total as f64
+  }
+}
+
+impl fmt::Display for CoverageReport {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "{} of {} rules and choices exercised ({:.1}%)",
+      self.exercised.len(),
+      self.exercised.len() + self.unexercised.len(),
+      self.ratio() * 100.0
+    )?;
+
+    for item in self.unexercised.iter() {
+      write!(f, "\n  unexercised {}", item)?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Select a small subset of instances that exercises every rule and type
+/// choice exercised by the given instances, returning the indices of the
+/// selected instances in ascending order. Instances are picked greedily by
+/// the number of items they add to the coverage of the subset, so the subset
+/// isn't necessarily the smallest possible one
+///
+/// # Example
+///
+/// ```
+/// use cddl::{cddl_from_str, distill::distill};
+///
+/// let cddl = cddl_from_str("value = int / tstr / bool", false).unwrap();
+/// let corpus = ["1", "2", "\"a\"", "3", "true", "\"b\""];
+///
+/// let coverages = corpus
+///   .iter()
+///   .map(|json| {
+///     let json = serde_json::from_str(json).unwrap();
+///     #[cfg(feature = "additional-controls")]
+///     return cddl.json_coverage(json, None).unwrap();
+///     #[cfg(not(feature = "additional-controls"))]
+///     cddl.json_coverage(json).unwrap()
+///   })
+///   .collect::<Vec<_>>();
+///
+/// assert_eq!(distill(&coverages), vec![0, 2, 4]);
+/// ```
+pub fn distill(coverages: &[Coverage]) -> Vec<usize> {
+  let mut covered = Coverage::new();
+  let mut selected = Vec::new();
+
+  loop {
+    // Ties are broken in favor of earlier instances
+    let best = coverages
+      .iter()
+      .enumerate()
+      .map(|(idx, c)| (c.difference(&covered).count(), idx))
+      .max_by(|(a, a_idx), (b, b_idx)| a.cmp(b).then(b_idx.cmp(a_idx)));
+
+    match best {
+      Some((added, idx)) if added > 0 => {
+        covered.extend(coverages[idx].iter().cloned());
+        selected.push(idx);
+      }
+      _ => break,
+    }
+  }
+
+  selected.sort_unstable();
+  selected
+}
+
+impl<'a> CDDL<'a> {
+  /// Validate a JSON document and return the rules, type choices and group
+  /// choices it exercises
+  pub fn json_coverage(
+    &self,
+    json: serde_json::Value,
+    #[cfg(feature = "additional-controls")] enabled_features: Option<&[&str]>,
+  ) -> std::result::Result<Coverage, json::Error> {
+    let rule_labels = self.coverage_labels();
+
+    #[cfg(feature = "additional-controls")]
+    let mut jv = JSONValidator::new(self, json, enabled_features);
+    #[cfg(not(feature = "additional-controls"))]
+    let mut jv = JSONValidator::new(self, json);
+
+    jv.set_rule_labels(&rule_labels);
+    jv.set_explain_choices(true);
+    jv.validate()?;
+
+    Ok(coverage(
+      jv.labeled_paths().iter().map(|lp| lp.rule.clone()),
+      jv.choice_explanations()
+        .iter()
+        .map(|e| (e.rule.clone(), e.index, e.choice.clone(), e.is_group_choice)),
+    ))
+  }
+
+  /// Validate a CBOR data item and return the rules, type choices and group
+  /// choices it exercises
+  pub fn cbor_coverage(
+    &self,
+    cbor: ciborium::value::Value,
+    #[cfg(feature = "additional-controls")] enabled_features: Option<&[&str]>,
+  ) -> std::result::Result<Coverage, cbor::Error<ReaderError>> {
+    let rule_labels = self.coverage_labels();
+
+    #[cfg(feature = "additional-controls")]
+    let mut cv = CBORValidator::new(self, cbor, enabled_features);
+    #[cfg(not(feature = "additional-controls"))]
+    let mut cv = CBORValidator::new(self, cbor);
+
+    cv.set_rule_labels(&rule_labels);
+    cv.set_explain_choices(true);
+    cv.validate()?;
+
+    Ok(coverage(
+      cv.labeled_paths().iter().map(|lp| lp.rule.clone()),
+      cv.choice_explanations()
+        .iter()
+        .map(|e| (e.rule.clone(), e.index, e.choice.clone(), e.is_group_choice)),
+    ))
+  }
+
+  /// Report which rules, type choices and group choices of the document are
+  /// exercised by the given coverages of a corpus of instances, and which
+  /// aren't
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use cddl::{cddl_from_str, distill::CoverageItem};
+  ///
+  /// let cddl = cddl_from_str("value = int / tstr", false).unwrap();
+  /// let json = serde_json::json!(1);
+  /// #[cfg(feature = "additional-controls")]
+  /// let coverage = cddl.json_coverage(json, None).unwrap();
+  /// #[cfg(not(feature = "additional-controls"))]
+  /// let coverage = cddl.json_coverage(json).unwrap();
+  ///
+  /// let report = cddl.coverage_report(&[coverage]);
+  /// assert_eq!(report.ratio(), 2.0 / 3.0);
+  /// assert!(report.unexercised.contains(&CoverageItem::Choice {
+  ///   rule: "value".to_string(),
+  ///   index: 1,
+  ///   choice: "tstr".to_string(),
+  /// }));
+  /// ```
+  pub fn coverage_report(&self, coverages: &[Coverage]) -> CoverageReport {
+    let mut branches = Branches::default();
+    for rule in self.rules.iter() {
+      let _ = branches.visit_rule(rule);
+    }
+
+    let (exercised, unexercised) = branches
+      .items
+      .into_iter()
+      .partition(|item| coverages.iter().any(|c| c.contains(item)));
+
+    CoverageReport {
+      exercised,
+      unexercised,
+    }
+  }
+
+  // Every rule is labeled so that the validators collect each location
+  // validated against a rule
+  fn coverage_labels(&self) -> RuleLabels {
+    self.rules.iter().map(|r| (r.name(), Vec::new())).collect()
+  }
+}
+
+fn coverage(
+  rules: impl Iterator<Item = String>,
+  choices: impl Iterator<Item = (String, usize, String, bool)>,
+) -> Coverage {
+  rules
+    .map(CoverageItem::Rule)
+    .chain(choices.map(|(rule, index, choice, is_group_choice)| {
+      if is_group_choice {
+        CoverageItem::GroupChoice {
+          rule,
+          index,
+          choice,
+        }
+      } else {
+        CoverageItem::Choice {
+          rule,
+          index,
+          choice,
+        }
+      }
+    }))
+    .collect()
+}
+
+// Collects the rules, type choices and group choices of a document
+#[derive(Default)]
+struct Branches {
+  rule: String,
+  items: Coverage,
+}
+
+impl<'a> Visitor<'a, Infallible> for Branches {
+  fn visit_type_rule(&mut self, tr: &TypeRule<'a>) -> visitor::Result<Infallible> {
+    self.rule = tr.name.to_string();
+    self.items.insert(CoverageItem::Rule(self.rule.clone()));
+
+    visitor::walk_type_rule(self, tr)
+  }
+
+  fn visit_group_rule(&mut self, gr: &GroupRule<'a>) -> visitor::Result<Infallible> {
+    self.rule = gr.name.to_string();
+    self.items.insert(CoverageItem::Rule(self.rule.clone()));
+
+    visitor::walk_group_rule(self, gr)
+  }
+
+  fn visit_type(&mut self, t: &Type<'a>) -> visitor::Result<Infallible> {
+    if t.type_choices.len() > 1 {
+      for (index, tc) in t.type_choices.iter().enumerate() {
+        self.items.insert(CoverageItem::Choice {
+          rule: self.rule.clone(),
+          index,
+          choice: tc.type1.to_string(),
+        });
+      }
+    }
+
+    visitor::walk_type(self, t)
+  }
+
+  fn visit_group(&mut self, g: &Group<'a>) -> visitor::Result<Infallible> {
+    if g.group_choices.len() > 1 {
+      for (index, gc) in g.group_choices.iter().enumerate() {
+        self.items.insert(CoverageItem::GroupChoice {
+          rule: self.rule.clone(),
+          index,
+          choice: gc.to_string().trim().to_string(),
+        });
+      }
+    }
+
+    visitor::walk_group(self, g)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cddl_from_str;
+
+  use indoc::indoc;
+
+  #[test]
+  fn verify_distill() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = indoc!(
+      r#"
+        message = { id: uint, ? sender: person, body: text / bytes-body }
+        person = { name: tstr }
+        bytes-body = [* uint]
+      "#
+    );
+    let cddl = cddl_from_str(cddl, true)?;
+
+    let corpus = [
+      r#"{ "id": 1, "body": "hello" }"#,
+      r#"{ "id": 2, "body": "world" }"#,
+      r#"{ "id": 3, "sender": { "name": "Joe" }, "body": "hi" }"#,
+      r#"{ "id": 4, "body": [1, 2] }"#,
+      r#"{ "id": 5, "sender": { "name": "Jane" }, "body": [3] }"#,
+    ];
+
+    let mut coverages = Vec::new();
+    for json in corpus.iter() {
+      let json = serde_json::from_str(json)?;
+      #[cfg(feature = "additional-controls")]
+      coverages.push(cddl.json_coverage(json, None)?);
+      #[cfg(not(feature = "additional-controls"))]
+      coverages.push(cddl.json_coverage(json)?);
+    }
+
+    assert!(coverages[2].contains(&CoverageItem::Rule("person".to_string())));
+    assert!(coverages[3].contains(&CoverageItem::Choice {
+      rule: "message".to_string(),
+      index: 1,
+      choice: "bytes-body".to_string(),
+    }));
+
+    // The last instance exercises everything but the text body
+    assert_eq!(distill(&coverages), vec![0, 4]);
+    assert_eq!(distill(&coverages[..4]), vec![2, 3]);
+
+    #[cfg(feature = "additional-controls")]
+    assert!(cddl
+      .json_coverage(serde_json::json!({ "id": "x" }), None)
+      .is_err());
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_coverage_report() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = indoc!(
+      r#"
+        message = { "kind" => "ping", ? id: uint // "kind" => "data", body: note / blob }
+        note = tstr
+        blob = bstr
+      "#
+    );
+    let cddl = cddl_from_str(cddl, true)?;
+
+    let mut coverages = Vec::new();
+    for json in [
+      r#"{ "kind": "ping" }"#,
+      r#"{ "kind": "data", "body": "hi" }"#,
+    ] {
+      let json = serde_json::from_str(json)?;
+      #[cfg(feature = "additional-controls")]
+      coverages.push(cddl.json_coverage(json, None)?);
+      #[cfg(not(feature = "additional-controls"))]
+      coverages.push(cddl.json_coverage(json)?);
+    }
+
+    assert!(coverages[0].contains(&CoverageItem::GroupChoice {
+      rule: "message".to_string(),
+      index: 0,
+      choice: r#""kind" => "ping", ? id: uint"#.to_string(),
+    }));
+
+    let report = cddl.coverage_report(&coverages);
+    assert_eq!(
+      report.unexercised.iter().cloned().collect::<Vec<_>>(),
+      vec![
+        CoverageItem::Rule("blob".to_string()),
+        CoverageItem::Choice {
+          rule: "message".to_string(),
+          index: 1,
+          choice: "blob".to_string(),
+        },
+      ]
+    );
+    assert_eq!(report.exercised.len(), 5);
+
+    Ok(())
+  }
+}
@@ -0,0 +1,506 @@
+#![cfg(feature = "std")]
+#![cfg(feature = "ast-span")]
+#![cfg(not(target_arch = "wasm32"))]
+
+use crate::{
+  annotations::{Annotation, RuleComments},
+  ast::*,
+  cddl_from_str,
+  lexer::Lexer,
+  parser,
+  prelude::{self, PreludeType},
+  token::Token,
+  visitor::{self, Visitor},
+};
+
+use std::{convert::Infallible, fmt::Write};
+
+/// Documentation of a group entry of a rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryDoc {
+  /// Entry as written, e.g. `? age: uint`
+  pub entry: String,
+  /// Text of the comments on the lines immediately preceding the entry and of
+  /// the comment trailing it
+  pub description: String,
+}
+
+/// Documentation of a rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleDoc {
+  /// Rule name
+  pub name: String,
+  /// Definitions of the rule as written, including those extending it with
+  /// additional choices
+  pub definitions: Vec<String>,
+  /// Text of the comments attached to the rule, excluding annotations
+  pub description: String,
+  /// Entries of the groups defined by the rule, e.g. the members of a map
+  pub entries: Vec<EntryDoc>,
+  /// Rules referenced by the rule
+  pub references: Vec<String>,
+  /// Rules referencing the rule
+  pub referenced_by: Vec<String>,
+  /// Types of the standard prelude used by the rule
+  pub prelude_types: Vec<&'static PreludeType>,
+}
+
+/// Documentation of a CDDL document extracted from its comments. Comments on
+/// the lines immediately preceding a rule or group entry, as well as comments
+/// trailing it on the same line, describe it. Annotations (e.g. `;@example`)
+/// are not part of descriptions
+///
+/// # Example
+///
+/// ```
+/// use cddl::docs::Docs;
+///
+/// let docs = Docs::from_cddl(
+///   r#"
+///     ; A person
+///     person = {
+///       name: tstr, ; Full name
+///       ? address: address,
+///     }
+///
+///     address = [street: tstr, number: uint]
+///   "#,
+/// )
+/// .unwrap();
+///
+/// assert_eq!(docs.rules[0].description, "A person");
+/// assert_eq!(docs.rules[0].entries[0].description, "Full name");
+/// assert_eq!(docs.rules[0].references, vec!["address"]);
+/// assert!(docs.to_markdown().contains("[address](#address)"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Docs {
+  /// Rule documentation, in order of definition
+  pub rules: Vec<RuleDoc>,
+}
+
+impl Docs {
+  /// Extract the documentation of a CDDL document string
+  pub fn from_cddl(input: &str) -> std::result::Result<Self, parser::Error> {
+    let cddl = cddl_from_str(input, false)?;
+    let rule_comments = RuleComments::from_cddl(input).map_err(parser::Error::LEXER)?;
+    let comments = SourceComments::from_cddl(input).map_err(parser::Error::LEXER)?;
+    let graph = cddl.dependency_graph();
+
+    let mut docs = Docs::default();
+    for rule in cddl.rules.iter() {
+      let name = rule.name();
+      let span = rule.span();
+      let definition = input[span.0..span.1].trim().to_string();
+
+      let entries = rule_entries(rule)
+        .into_iter()
+        .map(|ge| {
+          let span = ge.span();
+          EntryDoc {
+            entry: input[span.0..span.1]
+              .trim()
+              .trim_end_matches(',')
+              .trim_end()
+              .to_string(),
+            description: comments.describing(input, span),
+          }
+        })
+        .collect::<Vec<_>>();
+
+      let mut prelude_types = PreludeTypes(Vec::new());
+      let _ = prelude_types.visit_rule(rule);
+
+      // Rules extended with additional choices are documented once
+      if let Some(doc) = docs.rules.iter_mut().find(|r| r.name == name) {
+        doc.definitions.push(definition);
+        doc.entries.extend(entries);
+        for t in prelude_types.0 {
+          if !doc.prelude_types.contains(&t) {
+            doc.prelude_types.push(t);
+          }
+        }
+        continue;
+      }
+
+      docs.rules.push(RuleDoc {
+        description: description(
+          rule_comments
+            .comments(&name)
+            .iter()
+            .filter(|c| Annotation::from_comment(c).is_none())
+            .map(|c| c.as_str()),
+        ),
+        definitions: vec![definition],
+        entries,
+        references: graph
+          .dependencies(&name)
+          .into_iter()
+          .map(String::from)
+          .collect(),
+        referenced_by: graph
+          .dependents(&name)
+          .into_iter()
+          .map(String::from)
+          .collect(),
+        prelude_types: prelude_types.0,
+        name,
+      });
+    }
+
+    Ok(docs)
+  }
+
+  /// Render the documentation as Markdown. Each rule is given a section with
+  /// an anchor named after the rule, which references to the rule link to
+  pub fn to_markdown(&self) -> String {
+    let mut md = String::new();
+
+    for (idx, rule) in self.rules.iter().enumerate() {
+      if idx > 0 {
+        md.push('\n');
+      }
+
+      let _ = writeln!(md, "## <a id=\"{}\"></a>{}\n", rule.name, rule.name);
+
+      if !rule.description.is_empty() {
+        let _ = writeln!(md, "{}\n", rule.description);
+      }
+
+      md.push_str("```cddl\n");
+      for definition in rule.definitions.iter() {
+        let _ = writeln!(md, "{}", definition);
+      }
+      md.push_str("```\n");
+
+      if !rule.entries.is_empty() {
+        md.push_str("\n| Entry | Description |\n| --- | --- |\n");
+        for entry in rule.entries.iter() {
+          let _ = writeln!(
+            md,
+            "| `{}` | {} |",
+            markdown_cell(&entry.entry),
+            markdown_cell(&entry.description)
+          );
+        }
+      }
+
+      for (label, rules) in [
+        ("References", &rule.references),
+        ("Referenced by", &rule.referenced_by),
+      ] {
+        if !rules.is_empty() {
+          let links = rules
+            .iter()
+            .map(|r| format!("[{}](#{})", r, r))
+            .collect::<Vec<_>>();
+          let _ = writeln!(md, "\n{}: {}", label, links.join(", "));
+        }
+      }
+
+      if !rule.prelude_types.is_empty() {
+        let types = rule
+          .prelude_types
+          .iter()
+          .map(|t| format!("`{}` ({})", t.name, t.description))
+          .collect::<Vec<_>>();
+        let _ = writeln!(md, "\nPrelude types: {}", types.join(", "));
+      }
+    }
+
+    md
+  }
+
+  /// Render the documentation as a standalone HTML document. Each rule is
+  /// given a section with an id named after the rule, which references to the
+  /// rule link to
+  pub fn to_html(&self) -> String {
+    let mut html = String::from(
+      "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>CDDL documentation</title>\n</head>\n<body>\n",
+    );
+
+    for rule in self.rules.iter() {
+      let name = escape_html(&rule.name);
+      let _ = writeln!(html, "<section id=\"{}\">\n<h2>{}</h2>", name, name);
+
+      if !rule.description.is_empty() {
+        let _ = writeln!(html, "<p>{}</p>", escape_html(&rule.description));
+      }
+
+      let definitions = rule
+        .definitions
+        .iter()
+        .map(|d| escape_html(d))
+        .collect::<Vec<_>>();
+      let _ = writeln!(html, "<pre><code>{}</code></pre>", definitions.join("\n"));
+
+      if !rule.entries.is_empty() {
+        html.push_str("<table>\n<tr><th>Entry</th><th>Description</th></tr>\n");
+        for entry in rule.entries.iter() {
+          let _ = writeln!(
+            html,
+            "<tr><td><code>{}</code></td><td>{}</td></tr>",
+            escape_html(&entry.entry),
+            escape_html(&entry.description)
+          );
+        }
+        html.push_str("</table>\n");
+      }
+
+      for (label, rules) in [
+        ("References", &rule.references),
+        ("Referenced by", &rule.referenced_by),
+      ] {
+        if !rules.is_empty() {
+          let links = rules
+            .iter()
+            .map(|r| format!("<a href=\"#{}\">{}</a>", escape_html(r), escape_html(r)))
+            .collect::<Vec<_>>();
+          let _ = writeln!(html, "<p>{}: {}</p>", label, links.join(", "));
+        }
+      }
+
+      if !rule.prelude_types.is_empty() {
+        let types = rule
+          .prelude_types
+          .iter()
+          .map(|t| {
+            format!(
+              "<code title=\"{}\">{}</code>",
+              escape_html(t.description),
+              escape_html(t.name)
+            )
+          })
+          .collect::<Vec<_>>();
+        let _ = writeln!(html, "<p>Prelude types: {}</p>", types.join(", "));
+      }
+
+      html.push_str("</section>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+  }
+}
+
+// Comments of a document with the byte offsets of their leading `;` and
+// whether they are the first token on their line
+struct SourceComments<'a>(Vec<(usize, &'a str, bool)>);
+
+impl<'a> SourceComments<'a> {
+  fn from_cddl(input: &'a str) -> std::result::Result<Self, crate::lexer::Error> {
+    let mut comments = Vec::new();
+    let mut lexer = Lexer::new(input);
+    let mut at_line_start = true;
+    loop {
+      let (position, token) = lexer.next_token()?;
+      match token {
+        Token::EOF => break,
+        Token::COMMENT(comment) => {
+          comments.push((position.range.0, comment, at_line_start));
+          // Comments are terminated by (and consume) the end of the line
+          at_line_start = true;
+        }
+        Token::NEWLINE => at_line_start = true,
+        _ => at_line_start = false,
+      }
+    }
+
+    Ok(SourceComments(comments))
+  }
+
+  // Text of the comments on the lines immediately preceding the given span
+  // and of the comment trailing it on the same line
+  fn describing(&self, input: &str, span: Span) -> String {
+    let mut leading = Vec::new();
+    let mut cursor = span.0;
+    for (start, comment, own_line) in self.0.iter().rev() {
+      let end = start + 1 + comment.len();
+      if end > cursor {
+        continue;
+      }
+
+      // Only whitespace and the line break terminating the comment may
+      // separate it from what it describes
+      let between = &input[end..cursor];
+      if !own_line || !between.trim().is_empty() || between.matches('\n').count() > 1 {
+        break;
+      }
+
+      leading.push(*comment);
+      cursor = *start;
+    }
+    leading.reverse();
+
+    let trailing = self.0.iter().find(|(start, ..)| {
+      *start >= span.1
+        && input[span.1..*start]
+          .chars()
+          .all(|c| c == ',' || (c.is_whitespace() && c != '\n' && c != '\r'))
+    });
+
+    description(
+      leading
+        .into_iter()
+        .chain(trailing.map(|(_, comment, _)| *comment))
+        .filter(|c| Annotation::from_comment(c).is_none()),
+    )
+  }
+}
+
+fn description<'a>(comments: impl Iterator<Item = &'a str>) -> String {
+  comments
+    .map(|c| c.trim())
+    .filter(|c| !c.is_empty())
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+// Collects the prelude types referenced by a rule, in order of first use
+struct PreludeTypes(Vec<&'static PreludeType>);
+
+impl<'a> Visitor<'a, Infallible> for PreludeTypes {
+  fn visit_identifier(&mut self, ident: &Identifier<'a>) -> visitor::Result<Infallible> {
+    if let Some(t) = prelude::describe(&ident.ident) {
+      if !self.0.contains(&t) {
+        self.0.push(t);
+      }
+    }
+
+    Ok(())
+  }
+
+  fn visit_memberkey(&mut self, mk: &MemberKey<'a>) -> visitor::Result<Infallible> {
+    // Barewords are keys rather than references to types
+    if let MemberKey::Bareword { .. } = mk {
+      return Ok(());
+    }
+
+    visitor::walk_memberkey(self, mk)
+  }
+}
+
+// Entries of the groups defined directly by a rule
+fn rule_entries<'b, 'a>(rule: &'b Rule<'a>) -> Vec<&'b GroupEntry<'a>> {
+  let groups = match rule {
+    Rule::Type { rule, .. } => rule
+      .value
+      .type_choices
+      .iter()
+      .filter_map(|tc| match &tc.type1.type2 {
+        Type2::Map { group, .. } | Type2::Array { group, .. } => Some(group),
+        _ => None,
+      })
+      .collect::<Vec<_>>(),
+    Rule::Group { rule, .. } => match &rule.entry {
+      GroupEntry::InlineGroup {
+        occur: None, group, ..
+      } => vec![group],
+      entry => return vec![entry],
+    },
+  };
+
+  groups
+    .into_iter()
+    .flat_map(|g| g.group_choices.iter())
+    .flat_map(|gc| gc.group_entries.iter().map(|(ge, _)| ge))
+    .collect()
+}
+
+fn markdown_cell(text: &str) -> String {
+  text.replace('|', "\\|").replace('\n', "<br>")
+}
+
+fn escape_html(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      c => escaped.push(c),
+    }
+  }
+
+  escaped
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use indoc::indoc;
+
+  #[test]
+  fn verify_docs() -> std::result::Result<(), parser::Error> {
+    let input = indoc!(
+      r#"
+        ; A message exchanged by peers
+        ;@example { "id": 1, "body": "hi" }
+        message = {
+          ; Unique identifier
+          id: uint,
+          ? sender: person, ; Sender, if known
+
+          body: tstr / bytes, ; Payload | text or bytes
+          "a;b" => int
+        }
+
+        person = [name: tstr, ? age: uint]
+        person /= tstr ; Just a name
+      "#
+    );
+
+    let docs = Docs::from_cddl(input)?;
+
+    assert_eq!(docs.rules.len(), 2);
+
+    let message = &docs.rules[0];
+    assert_eq!(message.description, "A message exchanged by peers");
+    assert_eq!(
+      message
+        .entries
+        .iter()
+        .map(|e| (e.entry.as_str(), e.description.as_str()))
+        .collect::<Vec<_>>(),
+      vec![
+        ("id: uint", "Unique identifier"),
+        ("? sender: person", "Sender, if known"),
+        ("body: tstr / bytes", "Payload | text or bytes"),
+        (r#""a;b" => int"#, ""),
+      ]
+    );
+    assert_eq!(message.references, vec!["person"]);
+
+    let person = &docs.rules[1];
+    assert_eq!(person.definitions.len(), 2);
+    assert_eq!(person.entries.len(), 2);
+    assert_eq!(person.referenced_by, vec!["message"]);
+    assert_eq!(
+      person
+        .prelude_types
+        .iter()
+        .map(|t| t.name)
+        .collect::<Vec<_>>(),
+      vec!["tstr", "uint"]
+    );
+
+    let md = docs.to_markdown();
+    assert!(md.starts_with("## <a id=\"message\"></a>message\n\nA message exchanged by peers\n"));
+    assert!(md.contains("| `body: tstr / bytes` | Payload \\| text or bytes |"));
+    assert!(md.contains("```cddl\nperson = [name: tstr, ? age: uint]\nperson /= tstr\n```"));
+    assert!(md.contains("Referenced by: [message](#message)"));
+    assert!(md.contains(
+      "Prelude types: `uint` (Unsigned integer), `tstr` (Text string encoded in UTF-8), `bytes`"
+    ));
+
+    let html = docs.to_html();
+    assert!(html.contains("<section id=\"person\">"));
+    assert!(html.contains("<td><code>&quot;a;b&quot; =&gt; int</code></td>"));
+    assert!(html.contains("<p>References: <a href=\"#person\">person</a></p>"));
+    assert!(html.contains("<code title=\"Unsigned integer\">uint</code>"));
+
+    Ok(())
+  }
+}
@@ -55,6 +55,7 @@ pub enum MsgType {
   EmptyByteStringLiteral,
   InvalidHexFloat,
   InvalidExponent,
+  InvalidUTF8Sequence,
 }
 
 impl From<MsgType> for ErrorMsg {
@@ -183,7 +184,13 @@ impl From<MsgType> for ErrorMsg {
       MsgType::InvalidExponent => ErrorMsg {
         short: "invalid exponent".into(),
         extended: None,
-      }
+      },
+      MsgType::InvalidUTF8Sequence => ErrorMsg {
+        short: "invalid UTF-8 sequence replaced with U+FFFD".into(),
+        extended: Some(
+          "the input is not valid UTF-8 and may have been saved with a different encoding".into(),
+        ),
+      },
     }
   }
 }
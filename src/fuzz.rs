@@ -0,0 +1,616 @@
+#![cfg(all(feature = "std", any(feature = "json", feature = "cbor")))]
+
+#[cfg(feature = "json")]
+use crate::generator::{from_json, to_json};
+use crate::{
+  ast::CDDL,
+  generator::{to_cbor, Error, FloatWidth, Generator, Result, Target},
+};
+
+use ciborium::value::{Integer, Value};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::convert::TryFrom;
+
+// Number of times a mutation is retried when the mutated instance can't be
+// encoded in the target format or exceeds the maximum size
+const MAX_ATTEMPTS: usize = 16;
+
+// Integers at the boundaries of the lengths of CBOR argument encodings and of
+// the integer types decoders commonly map them to
+const BOUNDARY_INTS: [i128; 16] = [
+  0,
+  1,
+  -1,
+  23,
+  24,
+  -24,
+  -25,
+  255,
+  256,
+  65_535,
+  65_536,
+  4_294_967_295,
+  4_294_967_296,
+  9_223_372_036_854_775_807,
+  18_446_744_073_709_551_615,
+  -18_446_744_073_709_551_616,
+];
+
+// Floating-point numbers at the boundaries of half, single and double precision
+const BOUNDARY_FLOATS: [f64; 8] = [
+  0.0,
+  -0.0,
+  65_504.0,
+  5.960_464_477_539_063e-8,
+  3.402_823_466_385_288_6e38,
+  f64::MIN_POSITIVE,
+  f64::MAX,
+  f64::MIN,
+];
+
+// Tag numbers data items are wrapped in, e.g. date/time, encoded CBOR and the
+// self-described CBOR magic number
+const TAGS: [u64; 6] = [0, 1, 2, 24, 32, 55_799];
+
+/// Structure-aware mutator of instances of a rule of a CDDL document, for
+/// fuzzing decoders of the protocols the document describes. Rather than
+/// flipping bits of encoded bytes, mutations decode the instance and rewrite
+/// its data items: subtrees are replaced by ones of instances generated from
+/// the document, scalars by the literals and range bounds the document uses or
+/// by values at the boundaries of their encoding, and arrays and maps gain,
+/// lose or duplicate entries. Mutated instances thus mostly stay close enough
+/// to the document to get past the early checks of a decoder.
+///
+/// [`Driver::mutate`] and [`Driver::crossover`] take the arguments of the
+/// custom mutator and custom crossover hooks of libFuzzer, e.g. as exposed by
+/// the `fuzz_mutator!` and `fuzz_crossover!` macros of `libfuzzer-sys`, and
+/// reseed the driver on each call so that mutations are reproducible. They
+/// equally serve the custom mutator interface of AFL++
+///
+/// # Example
+///
+/// ```
+/// use cddl::{cddl_from_str, fuzz::Driver, generator::Target};
+///
+/// let cddl = cddl_from_str("message = { id: uint, body: tstr / bstr }", true).unwrap();
+/// let mut driver = Driver::new(&cddl, Target::Cbor, None).unwrap();
+///
+/// // Seed the corpus of the fuzzer with instances generated from the document
+/// let mut data = driver.seeds().swap_remove(0);
+/// let size = data.len();
+///
+/// // Called by the fuzzer with a buffer of up to `max_size` bytes
+/// data.resize(64, 0);
+/// let size = driver.mutate(&mut data, size, 64, 42);
+/// assert!(size <= 64);
+/// ```
+pub struct Driver<'a> {
+  cddl: &'a CDDL<'a>,
+  target: Target,
+  rule: String,
+  rng: StdRng,
+  // Instances generated from the document, whose subtrees are spliced into
+  // mutated instances
+  corpus: Vec<Value>,
+  // Scalars and map keys of the generated instances, such as literals and the
+  // bounds of ranges and sizes
+  dictionary: Vec<Value>,
+  /// Maximum number of mutations applied to an instance at once
+  pub max_mutations: usize,
+  /// Width of the floating-point numbers in mutated CBOR
+  pub float_width: FloatWidth,
+}
+
+impl<'a> Driver<'a> {
+  /// New driver mutating instances of the given rule of a CDDL document in
+  /// the given format. If no rule is given, the root rule is used. Instances of
+  /// the rule covering its type and group choices and occurrence and range
+  /// bounds are generated up front
+  pub fn new(cddl: &'a CDDL<'a>, target: Target, rule: Option<&str>) -> Result<Self> {
+    let mut generator = Generator::new(cddl, target, 0);
+    let rule = match rule {
+      Some(rule) => rule.to_string(),
+      None => generator
+        .root_rule()
+        .ok_or_else(|| Error::MissingRule("root".to_string()))?
+        .to_string(),
+    };
+
+    let mut corpus = generator.generate_all(&rule)?;
+    if let Ok(instance) = generator.minimal(&rule) {
+      corpus.push(instance);
+    }
+    if let Ok(instance) = generator.maximal(&rule) {
+      corpus.push(instance);
+    }
+    if corpus.is_empty() {
+      return Err(Error::Unsatisfiable(rule));
+    }
+
+    let mut dictionary = Vec::new();
+    for instance in corpus.iter() {
+      collect_scalars(instance, &mut dictionary);
+    }
+
+    Ok(Driver {
+      cddl,
+      target,
+      rule,
+      rng: StdRng::seed_from_u64(0),
+      corpus,
+      dictionary,
+      max_mutations: 4,
+      float_width: FloatWidth::default(),
+    })
+  }
+
+  /// Name of the rule the instances are mutated from
+  pub fn rule(&self) -> &str {
+    &self.rule
+  }
+
+  /// Encoded instances generated from the document, to seed the corpus of the
+  /// fuzzer with
+  pub fn seeds(&self) -> Vec<Vec<u8>> {
+    self
+      .corpus
+      .iter()
+      .filter_map(|instance| self.encode(instance))
+      .collect()
+  }
+
+  /// Encoded random instance of the rule generated from the given seed, e.g.
+  /// to stand in for inputs that don't decode
+  pub fn generate(&self, seed: u64) -> Result<Vec<u8>> {
+    let mut generator = Generator::new(self.cddl, self.target, seed);
+    generator.float_width = self.float_width;
+    let instance = generator.generate(&self.rule)?;
+
+    self
+      .encode(&instance)
+      .ok_or_else(|| Error::UnsupportedJSON(format!("{:?}", instance)))
+  }
+
+  /// Apply between one and `max_mutations` random mutations to an instance
+  pub fn mutate_value(&mut self, value: &mut Value) {
+    let mutations = self.rng.gen_range(1..=self.max_mutations.max(1));
+    for _ in 0..mutations {
+      let idx = self.rng.gen_range(0..node_count(value));
+      if let Some(node) = node_mut(value, idx) {
+        self.mutate_node(node);
+      }
+    }
+  }
+
+  /// Mutate the encoded instance in the first `size` bytes of `data` in place,
+  /// returning the size of the mutated instance, which is at most `max_size`
+  /// and the length of `data`. Inputs that don't decode are replaced by an
+  /// instance generated from the document. Mutations are determined by the
+  /// seed
+  pub fn mutate(&mut self, data: &mut [u8], size: usize, max_size: usize, seed: u32) -> usize {
+    self.rng = StdRng::seed_from_u64(seed.into());
+    let max_size = max_size.min(data.len());
+    let size = size.min(data.len());
+
+    let instance = self.decode(&data[..size]);
+    for _ in 0..MAX_ATTEMPTS {
+      let mut value = match &instance {
+        Some(instance) => instance.clone(),
+        None => self.random_instance(),
+      };
+      self.mutate_value(&mut value);
+
+      if let Some(len) = self.write(&value, &mut data[..max_size]) {
+        return len;
+      }
+    }
+
+    // Fall back to the smallest generated instance that fits
+    let mut seeds = self.seeds();
+    seeds.sort_by_key(|seed| seed.len());
+    match seeds.first() {
+      Some(seed) if seed.len() <= max_size => {
+        data[..seed.len()].copy_from_slice(seed);
+        seed.len()
+      }
+      _ => size,
+    }
+  }
+
+  /// Write to `out` the first encoded instance with a random subtree replaced
+  /// by a random subtree of the second encoded instance, returning the size of
+  /// the resulting instance. Returns `0` if no such instance fits in `out`.
+  /// Crossovers are determined by the seed
+  pub fn crossover(&mut self, data1: &[u8], data2: &[u8], out: &mut [u8], seed: u32) -> usize {
+    self.rng = StdRng::seed_from_u64(seed.into());
+
+    let first = self.decode(data1);
+    let second = self.decode(data2);
+    for _ in 0..MAX_ATTEMPTS {
+      let mut value = match &first {
+        Some(instance) => instance.clone(),
+        None => self.random_instance(),
+      };
+      let donor = match &second {
+        Some(instance) => instance.clone(),
+        None => self.random_instance(),
+      };
+
+      let subtree = random_node(&mut self.rng, &donor).clone();
+      let idx = self.rng.gen_range(0..node_count(&value));
+      if let Some(node) = node_mut(&mut value, idx) {
+        *node = subtree;
+      }
+
+      if let Some(len) = self.write(&value, out) {
+        return len;
+      }
+    }
+
+    0
+  }
+
+  fn mutate_node(&mut self, node: &mut Value) {
+    match self.rng.gen_range(0..5) {
+      // Replace with a scalar of the document, of the same kind if possible
+      0 | 1 => {
+        let same_kind = self
+          .dictionary
+          .iter()
+          .filter(|v| std::mem::discriminant(*v) == std::mem::discriminant(node))
+          .collect::<Vec<_>>();
+        let replacement = if self.rng.gen_bool(0.75) && !same_kind.is_empty() {
+          same_kind.choose(&mut self.rng).map(|v| (*v).clone())
+        } else {
+          self.dictionary.choose(&mut self.rng).cloned()
+        };
+        if let Some(replacement) = replacement {
+          *node = replacement;
+        }
+      }
+      // Splice in a subtree of a generated instance
+      2 => {
+        let instance = self.random_instance();
+        *node = random_node(&mut self.rng, &instance).clone();
+      }
+      // Wrap in or unwrap a tag
+      3 if self.target == Target::Cbor => match node {
+        Value::Tag(_, inner) => *node = std::mem::replace(inner.as_mut(), Value::Null),
+        _ => {
+          let tag = *TAGS.choose(&mut self.rng).unwrap_or(&0);
+          *node = Value::Tag(tag, Box::new(std::mem::replace(node, Value::Null)));
+        }
+      },
+      _ => self.tweak(node),
+    }
+  }
+
+  // Nudge a data item towards the boundaries of its type, or add, remove or
+  // duplicate the entries of arrays and maps
+  fn tweak(&mut self, node: &mut Value) {
+    match node {
+      Value::Integer(i) => {
+        let i = i128::from(*i);
+        let tweaked = match self.rng.gen_range(0..4) {
+          0 => i.saturating_add(1),
+          1 => i.saturating_sub(1),
+          2 => i.saturating_neg(),
+          _ => *BOUNDARY_INTS.choose(&mut self.rng).unwrap_or(&0),
+        };
+        if let Ok(tweaked) = Integer::try_from(tweaked) {
+          *node = Value::Integer(tweaked);
+        }
+      }
+      Value::Float(f) => {
+        *f = match self.rng.gen_range(0..3) {
+          0 => -*f,
+          1 if self.target == Target::Cbor => *[f64::NAN, f64::INFINITY, f64::NEG_INFINITY]
+            .choose(&mut self.rng)
+            .unwrap_or(&f64::NAN),
+          _ => *BOUNDARY_FLOATS.choose(&mut self.rng).unwrap_or(&0.0),
+        };
+      }
+      Value::Bool(b) => *b = !*b,
+      Value::Text(t) => {
+        let chars = t.chars().collect::<Vec<_>>();
+        *t = match self.rng.gen_range(0..4) {
+          0 => chars[..self.rng.gen_range(0..=chars.len())]
+            .iter()
+            .collect(),
+          1 => t.repeat(2),
+          2 => {
+            let mut chars = chars;
+            let c = *['\0', '"', '\\', 'é', '\u{10ffff}', 'a']
+              .choose(&mut self.rng)
+              .unwrap_or(&'a');
+            chars.insert(self.rng.gen_range(0..=chars.len()), c);
+            chars.into_iter().collect()
+          }
+          _ => String::new(),
+        };
+      }
+      Value::Bytes(b) => match self.rng.gen_range(0..4) {
+        0 => b.truncate(self.rng.gen_range(0..=b.len())),
+        1 if !b.is_empty() => {
+          let idx = self.rng.gen_range(0..b.len());
+          b[idx] ^= 1 << self.rng.gen_range(0..8);
+        }
+        2 => {
+          let len = self.rng.gen_range(1..=8);
+          b.extend((0..len).map(|_| self.rng.gen::<u8>()));
+        }
+        _ => b.clear(),
+      },
+      Value::Array(a) => match self.rng.gen_range(0..4) {
+        0 if !a.is_empty() => {
+          a.remove(self.rng.gen_range(0..a.len()));
+        }
+        1 if !a.is_empty() => {
+          let item = a[self.rng.gen_range(0..a.len())].clone();
+          a.insert(self.rng.gen_range(0..=a.len()), item);
+        }
+        2 if a.len() > 1 => {
+          let (i, j) = (
+            self.rng.gen_range(0..a.len()),
+            self.rng.gen_range(0..a.len()),
+          );
+          a.swap(i, j);
+        }
+        _ => {
+          let instance = self.random_instance();
+          a.push(random_node(&mut self.rng, &instance).clone());
+        }
+      },
+      Value::Map(m) => match self.rng.gen_range(0..3) {
+        0 if !m.is_empty() => {
+          m.remove(self.rng.gen_range(0..m.len()));
+        }
+        // Duplicate keys are only representable in CBOR
+        1 if !m.is_empty() && self.target == Target::Cbor => {
+          let entry = m[self.rng.gen_range(0..m.len())].clone();
+          m.push(entry);
+        }
+        _ => {
+          let keys = self
+            .dictionary
+            .iter()
+            .filter(|v| matches!(v, Value::Text(_) | Value::Integer(_)))
+            .collect::<Vec<_>>();
+          if let (Some(key), Some(value)) = (
+            keys.choose(&mut self.rng).map(|k| (*k).clone()),
+            self.dictionary.choose(&mut self.rng).cloned(),
+          ) {
+            m.push((key, value));
+          }
+        }
+      },
+      Value::Tag(tag, _) => *tag = *TAGS.choose(&mut self.rng).unwrap_or(&0),
+      _ => {
+        if let Some(replacement) = self.dictionary.choose(&mut self.rng) {
+          *node = replacement.clone();
+        }
+      }
+    }
+  }
+
+  fn random_instance(&mut self) -> Value {
+    self
+      .corpus
+      .choose(&mut self.rng)
+      .cloned()
+      .unwrap_or(Value::Null)
+  }
+
+  // Encode the value into `out` if it fits, returning its size
+  fn write(&self, value: &Value, out: &mut [u8]) -> Option<usize> {
+    let bytes = self.encode(value)?;
+    if bytes.len() > out.len() {
+      return None;
+    }
+
+    out[..bytes.len()].copy_from_slice(&bytes);
+    Some(bytes.len())
+  }
+
+  fn encode(&self, value: &Value) -> Option<Vec<u8>> {
+    match self.target {
+      Target::Cbor => to_cbor(value, self.float_width).ok(),
+      #[cfg(feature = "json")]
+      Target::Json => to_json(value)
+        .ok()
+        .and_then(|json| serde_json::to_vec(&json).ok()),
+      #[cfg(not(feature = "json"))]
+      Target::Json => None,
+    }
+  }
+
+  fn decode(&self, data: &[u8]) -> Option<Value> {
+    match self.target {
+      Target::Cbor => ciborium::de::from_reader(data).ok(),
+      #[cfg(feature = "json")]
+      Target::Json => serde_json::from_slice(data)
+        .ok()
+        .map(|json| from_json(&json)),
+      #[cfg(not(feature = "json"))]
+      Target::Json => None,
+    }
+  }
+}
+
+// Collect the distinct scalars and map keys of a value
+fn collect_scalars(value: &Value, scalars: &mut Vec<Value>) {
+  match value {
+    Value::Array(a) => a.iter().for_each(|v| collect_scalars(v, scalars)),
+    Value::Map(m) => m.iter().for_each(|(k, v)| {
+      collect_scalars(k, scalars);
+      collect_scalars(v, scalars);
+    }),
+    Value::Tag(_, v) => collect_scalars(v, scalars),
+    _ => {
+      if !scalars.contains(value) {
+        scalars.push(value.clone());
+      }
+    }
+  }
+}
+
+// Number of data items of a value, including itself and map keys
+fn node_count(value: &Value) -> usize {
+  1 + match value {
+    Value::Array(a) => a.iter().map(node_count).sum(),
+    Value::Map(m) => m.iter().map(|(k, v)| node_count(k) + node_count(v)).sum(),
+    Value::Tag(_, v) => node_count(v),
+    _ => 0,
+  }
+}
+
+// Data item of a value at the given index in pre-order, where map keys precede
+// their values
+fn node_mut(value: &mut Value, mut idx: usize) -> Option<&mut Value> {
+  if idx == 0 {
+    return Some(value);
+  }
+  idx -= 1;
+
+  let children: Vec<&mut Value> = match value {
+    Value::Array(a) => a.iter_mut().collect(),
+    Value::Map(m) => m.iter_mut().flat_map(|(k, v)| vec![k, v]).collect(),
+    Value::Tag(_, v) => vec![v.as_mut()],
+    _ => Vec::new(),
+  };
+  for child in children.into_iter() {
+    let count = node_count(child);
+    if idx < count {
+      return node_mut(child, idx);
+    }
+    idx -= count;
+  }
+
+  None
+}
+
+fn random_node<'v>(rng: &mut StdRng, value: &'v Value) -> &'v Value {
+  let mut idx = rng.gen_range(0..node_count(value));
+  let mut node = value;
+  'descend: while idx > 0 {
+    idx -= 1;
+    let children: Vec<&Value> = match node {
+      Value::Array(a) => a.iter().collect(),
+      Value::Map(m) => m.iter().flat_map(|(k, v)| vec![k, v]).collect(),
+      Value::Tag(_, v) => vec![v.as_ref()],
+      _ => Vec::new(),
+    };
+    for child in children.into_iter() {
+      let count = node_count(child);
+      if idx < count {
+        node = child;
+        continue 'descend;
+      }
+      idx -= count;
+    }
+    break;
+  }
+
+  node
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{cddl_from_str, validate_cbor_from_slice};
+  use indoc::indoc;
+
+  fn is_valid_cbor(cddl: &str, cbor: &[u8]) -> bool {
+    #[cfg(feature = "additional-controls")]
+    return validate_cbor_from_slice(cddl, cbor, None).is_ok();
+    #[cfg(not(feature = "additional-controls"))]
+    return validate_cbor_from_slice(cddl, cbor).is_ok();
+  }
+
+  const CDDL: &str = indoc!(
+    r#"
+      message = {
+        id: uint .size 4,
+        kind: "ping" / "pong",
+        ? payload: bstr .size 16,
+        tags: [* tstr],
+      }
+    "#
+  );
+
+  #[test]
+  fn verify_seeds() {
+    let cddl = cddl_from_str(CDDL, true).unwrap();
+    let driver = Driver::new(&cddl, Target::Cbor, None).unwrap();
+    assert_eq!(driver.rule(), "message");
+
+    let seeds = driver.seeds();
+    assert!(seeds.len() > 1);
+    for seed in seeds.iter() {
+      assert!(is_valid_cbor(CDDL, seed));
+    }
+  }
+
+  #[cfg(feature = "json")]
+  #[test]
+  fn verify_json_seeds() {
+    let json_cddl = "message = { id: 0..10, kind: \"ping\" / \"pong\" }";
+    let cddl = cddl_from_str(json_cddl, true).unwrap();
+    let driver = Driver::new(&cddl, Target::Json, None).unwrap();
+    for seed in driver.seeds().iter() {
+      let json = std::str::from_utf8(seed).unwrap();
+      #[cfg(feature = "additional-controls")]
+      assert!(crate::validate_json_from_str(json_cddl, json, None).is_ok());
+      #[cfg(not(feature = "additional-controls"))]
+      assert!(crate::validate_json_from_str(json_cddl, json).is_ok());
+    }
+  }
+
+  #[test]
+  fn verify_mutate() {
+    let cddl = cddl_from_str(CDDL, true).unwrap();
+    let mut driver = Driver::new(&cddl, Target::Cbor, None).unwrap();
+    let seed = driver.seeds().swap_remove(0);
+
+    let mut valid = 0;
+    let mut invalid = 0;
+    for s in 0..200 {
+      let mut data = seed.clone();
+      data.resize(128, 0);
+      let size = driver.mutate(&mut data, seed.len(), 128, s);
+      assert!(size <= 128);
+
+      // Mutated instances decode and are reproducible from the seed
+      let mutated = &data[..size];
+      assert!(ciborium::de::from_reader::<Value, _>(mutated).is_ok());
+      let mut again = seed.clone();
+      again.resize(128, 0);
+      assert_eq!(driver.mutate(&mut again, seed.len(), 128, s), size);
+      assert_eq!(&again[..size], mutated);
+
+      if is_valid_cbor(CDDL, mutated) {
+        valid += 1;
+      } else {
+        invalid += 1;
+      }
+    }
+    assert!(valid > 0 && invalid > 0);
+
+    // Inputs that don't decode are replaced by generated instances
+    let mut data = vec![0xff; 128];
+    let size = driver.mutate(&mut data, 3, 128, 0);
+    assert!(ciborium::de::from_reader::<Value, _>(&data[..size]).is_ok());
+  }
+
+  #[test]
+  fn verify_crossover() {
+    let cddl = cddl_from_str(CDDL, true).unwrap();
+    let mut driver = Driver::new(&cddl, Target::Cbor, None).unwrap();
+    let seeds = driver.seeds();
+
+    let mut out = vec![0; 256];
+    let size = driver.crossover(&seeds[0], &seeds[seeds.len() - 1], &mut out, 7);
+    assert!(size > 0);
+    assert!(ciborium::de::from_reader::<Value, _>(&out[..size]).is_ok());
+  }
+}
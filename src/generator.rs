@@ -0,0 +1,2208 @@
+#![cfg(all(feature = "std", any(feature = "json", feature = "cbor")))]
+
+use crate::{
+  ast::*,
+  parser,
+  token::{self, lookup_control_from_str, lookup_ident, Token},
+  validator::{
+    format_regex, group_choice_alternates_from_ident, group_rule_from_ident,
+    is_ident_byte_string_data_type, is_ident_float_data_type, is_ident_integer_data_type,
+    is_ident_nint_data_type, is_ident_string_data_type, is_ident_uint_data_type, rule_from_ident,
+    text_value_from_ident, type_choice_alternates_from_ident, unwrap_rule_from_ident,
+  },
+};
+
+use chrono::TimeZone;
+use ciborium::value::{Integer, Value};
+use displaydoc::Display;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::{collections::HashMap, convert::TryFrom};
+
+/// Alias for `Result` with an error of type `generator::Error`
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Generation error
+#[derive(Debug, Display)]
+pub enum Error {
+  /// rule {0} is not defined
+  MissingRule(String),
+  /// {0} is not supported by the generator
+  Unsupported(String),
+  /// {0} cannot be represented in JSON
+  UnsupportedJSON(String),
+  /// no value satisfies {0}
+  Unsatisfiable(String),
+  /// maximum depth of {0} exceeded while generating {1}
+  MaxDepth(usize, String),
+  /// CDDL parsing error: {0}
+  CDDLParsing(parser::Error),
+  /// error encoding CBOR: {0}
+  CBOREncoding(String),
+}
+
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Error::CDDLParsing(error) => Some(error),
+      _ => None,
+    }
+  }
+}
+
+/// Data format the generated instances must be representable in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+  /// JSON documents. Byte strings, tags and non-text map keys are not
+  /// representable
+  Json,
+  /// CBOR data items
+  Cbor,
+}
+
+/// Width of the floating-point numbers in encoded CBOR, e.g. to match the
+/// deterministic encoding profile mandated by a protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FloatWidth {
+  /// Shortest of half, single and double precision that represents the value
+  /// exactly, per the preferred serialization of
+  /// [RFC 8949](https://www.rfc-editor.org/rfc/rfc8949#section-4.1)
+  #[default]
+  Shortest,
+  /// Always double precision
+  Double,
+  /// Half precision where it represents the value exactly, otherwise double
+  /// precision
+  HalfOrDouble,
+}
+
+// Map keys (if any) and values generated from a group
+type Entries = Vec<(Option<Value>, Value)>;
+
+// Integer bounds of a numeric type
+type IntBounds = (i128, i128);
+
+// Range typical random integers and lengths are drawn from, unless constrained
+// otherwise
+const TYPICAL_INT: IntBounds = (-1000, 1000);
+const TYPICAL_LEN: (usize, usize) = (0, 8);
+
+/// Generator of example instances conforming to a CDDL document. Instances are
+/// either generated randomly from a seed, exhaustively by enumerating every
+/// choice, occurrence bound and range bound up to a maximum number of
+/// instances, or as the smallest or largest instance
+pub struct Generator<'a> {
+  cddl: &'a CDDL<'a>,
+  target: Target,
+  rng: StdRng,
+  exhaustive: bool,
+  // Minimal and maximal generation enumerate values as exhaustive generation
+  // does, but only keep the smallest or largest value at each choice
+  extreme: Option<Extreme>,
+  depth: usize,
+  // Generic arguments of the rules currently being generated
+  generic_args: Vec<HashMap<&'a str, &'a Type1<'a>>>,
+  /// Maximum number of repetitions of entries with an unbounded occurrence
+  /// indicator (e.g. `*` or `+`)
+  pub max_occurrences: usize,
+  /// Maximum depth of nested rule references. Beyond half of this depth,
+  /// optional entries are omitted to terminate recursive rules
+  pub max_depth: usize,
+  /// Maximum number of instances produced by exhaustive generation
+  pub max_instances: usize,
+  /// Width of the floating-point numbers in CBOR encoded by the generator,
+  /// e.g. the byte strings of `.cbor` controls
+  pub float_width: FloatWidth,
+}
+
+impl<'a> Generator<'a> {
+  /// New generator for the given CDDL document, target format and random seed
+  pub fn new(cddl: &'a CDDL<'a>, target: Target, seed: u64) -> Self {
+    Generator {
+      cddl,
+      target,
+      rng: StdRng::seed_from_u64(seed),
+      exhaustive: false,
+      extreme: None,
+      depth: 0,
+      generic_args: Vec::new(),
+      max_occurrences: 3,
+      max_depth: 16,
+      max_instances: 64,
+      float_width: FloatWidth::default(),
+    }
+  }
+
+  /// Name of the root rule, which is the first type rule without generic
+  /// parameters
+  pub fn root_rule(&self) -> Option<&'a str> {
+    self.cddl.rules.iter().find_map(|r| match r {
+      Rule::Type { rule, .. } if rule.generic_params.is_none() => Some(rule.name.ident.as_ref()),
+      _ => None,
+    })
+  }
+
+  /// Generate a random instance of the given rule
+  pub fn generate(&mut self, rule: &str) -> Result<Value> {
+    self.exhaustive = false;
+    self.extreme = None;
+
+    let mut values = self.generate_rule(rule)?;
+    if values.is_empty() {
+      return Err(Error::Unsatisfiable(rule.to_string()));
+    }
+
+    Ok(values.swap_remove(0))
+  }
+
+  /// Exhaustively generate instances of the given rule, covering each type and
+  /// group choice, the lower and upper bound of each occurrence indicator,
+  /// range and `.size` constraint, and both boolean values. Unconstrained
+  /// values are represented by a single boundary value (e.g. `0` for `uint`).
+  /// At most `max_instances` instances are generated
+  pub fn generate_all(&mut self, rule: &str) -> Result<Vec<Value>> {
+    self.exhaustive = true;
+    self.extreme = None;
+
+    let values = self.generate_rule(rule)?;
+    let mut unique = Vec::with_capacity(values.len());
+    for v in values.into_iter() {
+      if !unique.contains(&v) {
+        unique.push(v);
+      }
+    }
+
+    Ok(unique)
+  }
+
+  /// Generate the smallest instance of the given rule, e.g. as a starting
+  /// template for hand-authored fixtures. Optional entries are omitted,
+  /// occurrences and lengths (including `.size` constraints) are at their lower
+  /// bound, numbers are as close to zero as their type or range allows, and
+  /// the choice with the shortest CBOR encoding is picked among alternatives
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use cddl::{
+  ///   cddl_from_str,
+  ///   generator::{to_json, Generator, Target},
+  /// };
+  ///
+  /// let cddl = cddl_from_str(
+  ///   "person = { name: tstr .size (2..16), ? age: uint, tags: [+ \"a\" / 1] }",
+  ///   true,
+  /// )
+  /// .unwrap();
+  /// let mut generator = Generator::new(&cddl, Target::Json, 0);
+  ///
+  /// let person = to_json(&generator.minimal("person").unwrap()).unwrap();
+  /// assert_eq!(person["name"].as_str().unwrap().len(), 2);
+  /// assert!(person.get("age").is_none());
+  /// assert_eq!(person["tags"], serde_json::json!([1]));
+  /// ```
+  pub fn minimal(&mut self, rule: &str) -> Result<Value> {
+    self.generate_extreme(rule, Extreme::Smallest)
+  }
+
+  /// Generate the largest instance of the given rule, e.g. to stress-test
+  /// decoders written against the document. Optional entries are included up
+  /// to half of `max_depth`, occurrences are at their upper bound (capped at
+  /// `max_occurrences` unless the lower bound exceeds it), lengths (including
+  /// `.size` constraints) are at their upper bound, numbers are at the
+  /// endpoint of their type or range with the greatest magnitude, and the
+  /// choice with the longest CBOR encoding is picked among alternatives
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use cddl::{
+  ///   cddl_from_str,
+  ///   generator::{to_json, Generator, Target},
+  /// };
+  ///
+  /// let cddl = cddl_from_str(
+  ///   "person = { name: tstr .size (2..16), ? age: 0..120, tags: [1*2 \"a\" / 1] }",
+  ///   true,
+  /// )
+  /// .unwrap();
+  /// let mut generator = Generator::new(&cddl, Target::Json, 0);
+  ///
+  /// let person = to_json(&generator.maximal("person").unwrap()).unwrap();
+  /// assert_eq!(person["name"].as_str().unwrap().len(), 16);
+  /// assert_eq!(person["age"], 120);
+  /// assert_eq!(person["tags"], serde_json::json!(["a", "a"]));
+  /// ```
+  pub fn maximal(&mut self, rule: &str) -> Result<Value> {
+    self.generate_extreme(rule, Extreme::Largest)
+  }
+
+  fn generate_extreme(&mut self, rule: &str, extreme: Extreme) -> Result<Value> {
+    self.exhaustive = true;
+    self.extreme = Some(extreme);
+
+    let values = self.generate_rule(rule)?;
+    self
+      .select(values, Self::size)
+      .pop()
+      .ok_or_else(|| Error::Unsatisfiable(rule.to_string()))
+  }
+
+  fn generate_rule(&mut self, rule: &str) -> Result<Vec<Value>> {
+    self.depth = 0;
+    self.generic_args.clear();
+
+    let cddl = self.cddl;
+    let ident = cddl
+      .rules
+      .iter()
+      .find_map(|r| match r {
+        Rule::Type { rule: tr, .. } if tr.name.to_string() == rule => Some(&tr.name),
+        _ => None,
+      })
+      .ok_or_else(|| Error::MissingRule(rule.to_string()))?;
+
+    self.gen_ident(ident, None)
+  }
+
+  // Combine the values generated from each alternative. Random generation
+  // picks a single alternative, falling back to the others if it can't be
+  // generated
+  fn gen_alternatives<T, F>(&mut self, alternatives: &[T], mut gen: F) -> Result<Vec<Value>>
+  where
+    F: FnMut(&mut Self, &T) -> Result<Vec<Value>>,
+  {
+    let mut indices = (0..alternatives.len()).collect::<Vec<_>>();
+    if !self.exhaustive {
+      indices.shuffle(&mut self.rng);
+    }
+
+    let mut values = Vec::new();
+    let mut error = None;
+    for idx in indices.into_iter() {
+      match gen(self, &alternatives[idx]) {
+        Ok(mut v) => {
+          values.append(&mut v);
+          if !self.exhaustive && !values.is_empty() {
+            break;
+          }
+        }
+        Err(e) => error = Some(e),
+      }
+    }
+
+    if values.is_empty() {
+      if let Some(e) = error {
+        return Err(e);
+      }
+    }
+
+    if self.extreme.is_some() {
+      return Ok(self.select(values, Self::size));
+    }
+
+    values.truncate(self.max_instances);
+
+    Ok(values)
+  }
+
+  fn gen_type(&mut self, t: &'a Type<'a>) -> Result<Vec<Value>> {
+    let choices = t
+      .type_choices
+      .iter()
+      .map(|tc| &tc.type1)
+      .collect::<Vec<_>>();
+    self.gen_alternatives(&choices, |g, t1| g.gen_type1(t1))
+  }
+
+  fn gen_type1(&mut self, t1: &'a Type1<'a>) -> Result<Vec<Value>> {
+    match &t1.operator {
+      None => self.gen_type2(&t1.type2),
+      Some(Operator {
+        operator: RangeCtlOp::RangeOp { is_inclusive, .. },
+        type2,
+        ..
+      }) => self.gen_range(&t1.type2, type2, *is_inclusive),
+      Some(Operator {
+        operator: RangeCtlOp::CtlOp { ctrl, .. },
+        type2,
+        ..
+      }) => self.gen_control(&t1.type2, ctrl, type2),
+    }
+  }
+
+  fn gen_type2(&mut self, t2: &'a Type2<'a>) -> Result<Vec<Value>> {
+    match t2 {
+      Type2::IntValue { value, .. } => Ok(vec![int_value(*value as i128)]),
+      Type2::UintValue { value, .. } => Ok(vec![int_value(*value as i128)]),
+      Type2::FloatValue { value, .. } => Ok(vec![Value::Float(*value)]),
+      Type2::TextValue { value, .. } => Ok(vec![Value::Text(value.to_string())]),
+      Type2::UTF8ByteString { value, .. } => self.bytes(vec![value.to_vec()]),
+      Type2::B16ByteString { value, .. } => {
+        let bytes = base16::decode(value)
+          .map_err(|e| Error::Unsatisfiable(format!("base16 byte string: {}", e)))?;
+        self.bytes(vec![bytes])
+      }
+      Type2::B64ByteString { value, .. } => {
+        let bytes = base64::decode_config(value, base64::URL_SAFE)
+          .map_err(|e| Error::Unsatisfiable(format!("base64 byte string: {}", e)))?;
+        self.bytes(vec![bytes])
+      }
+      Type2::Typename {
+        ident,
+        generic_args,
+        ..
+      } => self.gen_ident(ident, generic_args.as_ref()),
+      Type2::ParenthesizedType { pt, .. } => self.gen_type(pt),
+      Type2::Map { group, .. } => {
+        let entries = self.gen_group(group, true)?;
+        entries.into_iter().map(|e| self.map_value(e)).collect()
+      }
+      Type2::Array { group, .. } => {
+        let entries = self.gen_group(group, false)?;
+        Ok(
+          entries
+            .into_iter()
+            .map(|e| Value::Array(e.into_iter().map(|(_, v)| v).collect()))
+            .collect(),
+        )
+      }
+      Type2::Unwrap {
+        ident,
+        generic_args,
+        ..
+      } => match unwrap_rule_from_ident(self.cddl, ident) {
+        Some(Rule::Type { rule, .. }) => {
+          let tagged = rule
+            .value
+            .type_choices
+            .iter()
+            .find_map(|tc| match &tc.type1.type2 {
+              Type2::TaggedData { t, .. } => Some(t),
+              _ => None,
+            })
+            .ok_or_else(|| {
+              Error::Unsupported(format!("unwrapping {} outside of a group", ident))
+            })?;
+
+          self.with_rule(ident, generic_args.as_ref(), |g| g.gen_type(tagged))
+        }
+        _ => Err(Error::MissingRule(ident.to_string())),
+      },
+      Type2::ChoiceFromInlineGroup { group, .. } => {
+        let mut choices = Vec::new();
+        for gc in group.group_choices.iter() {
+          for (ge, _) in gc.group_entries.iter() {
+            self.choices_from_group_entry(ge, &mut choices)?;
+          }
+        }
+
+        self.gen_alternatives(&choices, |g, t1| g.gen_type1(t1))
+      }
+      Type2::ChoiceFromGroup { ident, .. } => {
+        let mut choices = Vec::new();
+        for ge in group_choice_alternates_from_ident(self.cddl, ident).into_iter() {
+          self.choices_from_group_entry(ge, &mut choices)?;
+        }
+
+        if choices.is_empty() {
+          return Err(Error::MissingRule(ident.to_string()));
+        }
+
+        self.gen_alternatives(&choices, |g, t1| g.gen_type1(t1))
+      }
+      Type2::TaggedData {
+        tag, tag_type, t, ..
+      } => {
+        self.require_cbor("tagged data")?;
+
+        let tag = match tag_type {
+          // The first tag number generated for the type of the tag number
+          Some(tag_type) => self
+            .gen_type(tag_type)?
+            .into_iter()
+            .find_map(|v| match v {
+              Value::Integer(i) => u64::try_from(i128::from(i)).ok(),
+              _ => None,
+            })
+            .ok_or_else(|| Error::Unsatisfiable(format!("tag number {}", tag_type)))?,
+          None => tag.unwrap_or(0) as u64,
+        };
+        Ok(
+          self
+            .gen_type(t)?
+            .into_iter()
+            .map(|v| Value::Tag(tag, Box::new(v)))
+            .collect(),
+        )
+      }
+      Type2::DataMajorType { mt, constraint, .. } => self.gen_major_type(*mt, *constraint),
+      #[cfg(feature = "ast-span")]
+      Type2::Any(_) => self.gen_any(),
+      #[cfg(not(feature = "ast-span"))]
+      Type2::Any => self.gen_any(),
+    }
+  }
+
+  // Type choices enumerated from a group entry (i.e. `&(a: 1, b: 2)`)
+  fn choices_from_group_entry(
+    &self,
+    ge: &'a GroupEntry<'a>,
+    choices: &mut Vec<&'a Type1<'a>>,
+  ) -> Result<()> {
+    match ge {
+      GroupEntry::ValueMemberKey { ge, .. } => {
+        choices.extend(ge.entry_type.type_choices.iter().map(|tc| &tc.type1));
+      }
+      GroupEntry::TypeGroupname { ge, .. } => match rule_from_ident(self.cddl, &ge.name) {
+        Some(Rule::Type { rule, .. }) => {
+          choices.extend(rule.value.type_choices.iter().map(|tc| &tc.type1));
+        }
+        Some(Rule::Group { .. }) => {
+          for ge in group_choice_alternates_from_ident(self.cddl, &ge.name).into_iter() {
+            self.choices_from_group_entry(ge, choices)?;
+          }
+        }
+        None => return Err(Error::MissingRule(ge.name.to_string())),
+      },
+      GroupEntry::InlineGroup { group, .. } => {
+        for gc in group.group_choices.iter() {
+          for (ge, _) in gc.group_entries.iter() {
+            self.choices_from_group_entry(ge, choices)?;
+          }
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  // Generate values of a type given by an identifier, which is either a
+  // generic parameter, a rule or a prelude type
+  fn gen_ident(
+    &mut self,
+    ident: &'a Identifier<'a>,
+    generic_args: Option<&'a GenericArgs<'a>>,
+  ) -> Result<Vec<Value>> {
+    if let Some(arg) = self.generic_arg(ident) {
+      // Generic arguments are evaluated in the scope of the referencing rule
+      let scope = self.generic_args.pop();
+      let values = self.gen_type1(arg);
+      if let Some(scope) = scope {
+        self.generic_args.push(scope);
+      }
+
+      return values;
+    }
+
+    let alternates = type_choice_alternates_from_ident(self.cddl, ident);
+    if !alternates.is_empty() {
+      let choices = alternates
+        .iter()
+        .flat_map(|t| t.type_choices.iter().map(|tc| &tc.type1))
+        .collect::<Vec<_>>();
+
+      return self.with_rule(ident, generic_args, |g| {
+        g.gen_alternatives(&choices, |g, t1| g.gen_type1(t1))
+      });
+    }
+
+    if group_rule_from_ident(self.cddl, ident).is_some() {
+      return Err(Error::Unsupported(format!(
+        "group {} used as a type",
+        ident
+      )));
+    }
+
+    self.gen_prelude(ident)
+  }
+
+  fn generic_arg(&self, ident: &Identifier) -> Option<&'a Type1<'a>> {
+    if ident.socket.is_some() {
+      return None;
+    }
+
+    self
+      .generic_args
+      .last()
+      .and_then(|args| args.get(ident.ident.as_ref()).copied())
+  }
+
+  // Generate values within the scope of the given rule, binding its generic
+  // parameters to the given arguments
+  fn with_rule<F>(
+    &mut self,
+    ident: &Identifier<'a>,
+    generic_args: Option<&'a GenericArgs<'a>>,
+    gen: F,
+  ) -> Result<Vec<Value>>
+  where
+    F: FnOnce(&mut Self) -> Result<Vec<Value>>,
+  {
+    if self.depth >= self.max_depth {
+      return Err(Error::MaxDepth(self.max_depth, ident.to_string()));
+    }
+
+    let mut args = HashMap::new();
+    if let (Some(rule), Some(generic_args)) = (rule_from_ident(self.cddl, ident), generic_args) {
+      let params = match rule {
+        Rule::Type { rule, .. } => rule.generic_params.as_ref(),
+        Rule::Group { rule, .. } => rule.generic_params.as_ref(),
+      };
+
+      if let Some(params) = params {
+        for (param, arg) in params.params.iter().zip(generic_args.args.iter()) {
+          args.insert(param.param.ident.as_ref(), arg.arg.as_ref());
+        }
+      }
+    }
+
+    // Generic arguments of an enclosing rule remain visible if the referenced
+    // rule isn't generic
+    let args = if args.is_empty() {
+      self.generic_args.last().cloned().unwrap_or_default()
+    } else {
+      args
+    };
+
+    self.depth += 1;
+    self.generic_args.push(args);
+    let values = gen(self);
+    self.generic_args.pop();
+    self.depth -= 1;
+
+    values
+  }
+
+  fn gen_prelude(&mut self, ident: &Identifier) -> Result<Vec<Value>> {
+    match lookup_ident(&ident.ident) {
+      Token::FALSE => Ok(vec![Value::Bool(false)]),
+      Token::TRUE => Ok(vec![Value::Bool(true)]),
+      Token::BOOL => Ok(self.pick(vec![Value::Bool(false), Value::Bool(true)])),
+      Token::NIL | Token::NULL => Ok(vec![Value::Null]),
+      Token::UINT | Token::UNSIGNED => self.gen_int((0, u64::MAX as i128)),
+      Token::NINT => self.gen_int((i64::MIN as i128, -1)),
+      Token::INT | Token::INTEGER => self.gen_int((i64::MIN as i128, u64::MAX as i128)),
+      Token::TIME => self.gen_int((0, u32::MAX as i128)),
+      Token::FLOAT16 => self.gen_float(-65504.0, 65504.0),
+      Token::FLOAT32 | Token::FLOAT1632 => self.gen_float(f32::MIN as f64, f32::MAX as f64),
+      Token::FLOAT | Token::FLOAT64 | Token::FLOAT3264 => self.gen_float(f64::MIN, f64::MAX),
+      Token::NUMBER => {
+        let mut values = self.gen_int((i64::MIN as i128, u64::MAX as i128))?;
+        values.append(&mut self.gen_float(f64::MIN, f64::MAX)?);
+        Ok(self.pick(values))
+      }
+      Token::TSTR | Token::TEXT | Token::REGEXP | Token::MIMEMESSAGE => self.gen_text(TYPICAL_LEN),
+      Token::BSTR | Token::BYTES => self.gen_bytes(TYPICAL_LEN),
+      Token::TDATE => {
+        let seconds = self.gen_int((0, 4_102_444_800))?;
+        Ok(
+          seconds
+            .iter()
+            .filter_map(|s| s.as_integer().map(i128::from))
+            .map(|s| {
+              let dt = chrono::Utc
+                .timestamp_opt(s as i64, 0)
+                .single()
+                .unwrap_or_default();
+              Value::Text(dt.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+            })
+            .collect(),
+        )
+      }
+      Token::URI => Ok(
+        self
+          .gen_text((1, 8))?
+          .into_iter()
+          .filter_map(|t| t.into_text().ok())
+          .map(|t| Value::Text(format!("https://example.com/{}", t)))
+          .collect(),
+      ),
+      Token::B64URL | Token::B64LEGACY => {
+        let (lower, upper) = self.bounds(TYPICAL_LEN);
+        let mut values = Vec::new();
+        for len in lower..=upper {
+          if self.exhaustive && len != lower && len != upper {
+            continue;
+          }
+
+          let bytes = self.random_bytes(len);
+          values.push(Value::Text(base64::encode_config(
+            &bytes,
+            base64::URL_SAFE_NO_PAD,
+          )));
+        }
+        Ok(self.pick(values))
+      }
+      Token::ANY => self.gen_any(),
+      Token::UNDEFINED => Err(Error::Unsupported("undefined".to_string())),
+      token @ Token::BIGUINT
+      | token @ Token::BIGNINT
+      | token @ Token::BIGINT
+      | token @ Token::DECFRAC
+      | token @ Token::BIGFLOAT
+      | token @ Token::EB64URL
+      | token @ Token::EB64LEGACY
+      | token @ Token::EB16
+      | token @ Token::ENCODEDCBOR
+      | token @ Token::CBORANY => self.gen_prelude_tag(token),
+      _ => Err(Error::MissingRule(ident.to_string())),
+    }
+  }
+
+  // Prelude types defined as tagged data items
+  fn gen_prelude_tag(&mut self, token: Token) -> Result<Vec<Value>> {
+    self.require_cbor(&token.to_string())?;
+
+    let tagged = |tag: u64, values: Vec<Value>| {
+      values
+        .into_iter()
+        .map(|v| Value::Tag(tag, Box::new(v)))
+        .collect::<Vec<_>>()
+    };
+
+    match token {
+      Token::BIGUINT => Ok(tagged(2, self.gen_bytes((1, 9))?)),
+      Token::BIGNINT => Ok(tagged(3, self.gen_bytes((1, 9))?)),
+      Token::BIGINT => {
+        let mut values = tagged(2, self.gen_bytes((1, 9))?);
+        values.append(&mut tagged(3, self.gen_bytes((1, 9))?));
+        Ok(self.pick(values))
+      }
+      Token::DECFRAC | Token::BIGFLOAT => {
+        let tag = if let Token::DECFRAC = token { 4 } else { 5 };
+        let exponents = self.gen_int(TYPICAL_INT)?;
+        let mantissas = self.gen_int(TYPICAL_INT)?;
+        let mut values = Vec::new();
+        for e in exponents.iter() {
+          for m in mantissas.iter() {
+            values.push(Value::Array(vec![e.clone(), m.clone()]));
+          }
+        }
+        Ok(tagged(tag, values))
+      }
+      Token::EB64URL => Ok(tagged(21, self.gen_any()?)),
+      Token::EB64LEGACY => Ok(tagged(22, self.gen_any()?)),
+      Token::EB16 => Ok(tagged(23, self.gen_any()?)),
+      Token::ENCODEDCBOR => {
+        let encoded = self
+          .gen_any()?
+          .iter()
+          .map(|v| to_cbor(v, self.float_width))
+          .collect::<Result<Vec<_>>>()?;
+        Ok(tagged(24, encoded.into_iter().map(Value::Bytes).collect()))
+      }
+      _ => Ok(tagged(55799, self.gen_any()?)),
+    }
+  }
+
+  fn gen_any(&mut self) -> Result<Vec<Value>> {
+    let mut values = vec![Value::Null, Value::Bool(true)];
+    values.append(&mut self.gen_int(TYPICAL_INT)?);
+    values.append(&mut self.gen_text(TYPICAL_LEN)?);
+    Ok(self.pick(values))
+  }
+
+  fn gen_major_type(&mut self, mt: u8, constraint: Option<usize>) -> Result<Vec<Value>> {
+    match (mt, constraint) {
+      (0, Some(c)) => Ok(vec![int_value(c as i128)]),
+      (0, None) => self.gen_int((0, u64::MAX as i128)),
+      (1, Some(c)) => Ok(vec![int_value(-1 - c as i128)]),
+      (1, None) => self.gen_int((i64::MIN as i128, -1)),
+      (2, _) => self.gen_bytes(TYPICAL_LEN),
+      (3, _) => self.gen_text(TYPICAL_LEN),
+      (4, _) => Ok(vec![Value::Array(Vec::new())]),
+      (5, _) => Ok(vec![Value::Map(Vec::new())]),
+      (6, tag) => {
+        self.require_cbor("tagged data")?;
+        let tag = tag.unwrap_or(0) as u64;
+        Ok(
+          self
+            .gen_any()?
+            .into_iter()
+            .map(|v| Value::Tag(tag, Box::new(v)))
+            .collect(),
+        )
+      }
+      (7, Some(20)) => Ok(vec![Value::Bool(false)]),
+      (7, Some(21)) => Ok(vec![Value::Bool(true)]),
+      (7, Some(22)) | (7, None) => Ok(vec![Value::Null]),
+      (7, Some(25)) | (7, Some(26)) | (7, Some(27)) => self.gen_float(-1000.0, 1000.0),
+      _ => Err(Error::Unsupported(format!(
+        "major type {} with constraint {:?}",
+        mt, constraint
+      ))),
+    }
+  }
+
+  fn gen_range(
+    &mut self,
+    lower: &'a Type2<'a>,
+    upper: &'a Type2<'a>,
+    is_inclusive: bool,
+  ) -> Result<Vec<Value>> {
+    let range = || {
+      format!(
+        "range {}{}{}",
+        lower,
+        if is_inclusive { ".." } else { "..." },
+        upper
+      )
+    };
+
+    match (self.numeric_value(lower), self.numeric_value(upper)) {
+      (Some(Value::Integer(l)), Some(Value::Integer(u))) => {
+        let l = i128::from(l);
+        let u = if is_inclusive {
+          i128::from(u)
+        } else {
+          i128::from(u) - 1
+        };
+        if l > u {
+          return Err(Error::Unsatisfiable(range()));
+        }
+
+        self.gen_int_within((l, u))
+      }
+      (Some(l), Some(u)) => {
+        let l = numeric_as_f64(&l).ok_or_else(|| Error::Unsatisfiable(range()))?;
+        let u = numeric_as_f64(&u).ok_or_else(|| Error::Unsatisfiable(range()))?;
+        if l > u || (!is_inclusive && l >= u) {
+          return Err(Error::Unsatisfiable(range()));
+        }
+
+        match self.extreme {
+          Some(Extreme::Smallest) => {
+            let f = if l > 0.0 {
+              l
+            } else if u > 0.0 || (is_inclusive && u == 0.0) {
+              0.0
+            } else if is_inclusive {
+              u
+            } else {
+              l
+            };
+            return Ok(vec![Value::Float(f)]);
+          }
+          Some(Extreme::Largest) => {
+            let f = if is_inclusive && u.abs() >= l.abs() {
+              u
+            } else {
+              l
+            };
+            return Ok(vec![Value::Float(f)]);
+          }
+          None => {}
+        }
+
+        if self.exhaustive {
+          return Ok(if is_inclusive && l != u {
+            vec![Value::Float(l), Value::Float(u)]
+          } else {
+            vec![Value::Float(l)]
+          });
+        }
+
+        let f = if is_inclusive {
+          self.rng.gen_range(l..=u)
+        } else {
+          self.rng.gen_range(l..u)
+        };
+        Ok(vec![Value::Float(f)])
+      }
+      _ => Err(Error::Unsupported(range())),
+    }
+  }
+
+  // Numeric value given by a literal or by a rule defined as a literal
+  fn numeric_value(&self, t2: &Type2) -> Option<Value> {
+    match t2 {
+      Type2::IntValue { value, .. } => Some(int_value(*value as i128)),
+      Type2::UintValue { value, .. } => Some(int_value(*value as i128)),
+      Type2::FloatValue { value, .. } => Some(Value::Float(*value)),
+      Type2::Typename { ident, .. } => {
+        if let Some(arg) = self.generic_arg(ident) {
+          if arg.operator.is_none() {
+            return self.numeric_value(&arg.type2);
+          }
+        }
+
+        match type_choice_alternates_from_ident(self.cddl, ident)[..] {
+          [Type {
+            ref type_choices, ..
+          }] if type_choices.len() == 1 && type_choices[0].type1.operator.is_none() => {
+            self.numeric_value(&type_choices[0].type1.type2)
+          }
+          _ => None,
+        }
+      }
+      Type2::ParenthesizedType { pt, .. }
+        if pt.type_choices.len() == 1 && pt.type_choices[0].type1.operator.is_none() =>
+      {
+        self.numeric_value(&pt.type_choices[0].type1.type2)
+      }
+      _ => None,
+    }
+  }
+
+  fn gen_control(
+    &mut self,
+    target: &'a Type2<'a>,
+    ctrl: &str,
+    controller: &'a Type2<'a>,
+  ) -> Result<Vec<Value>> {
+    match lookup_control_from_str(ctrl) {
+      Some(Token::SIZE) => self.gen_size(target, controller),
+      Some(Token::REGEXP) | Some(Token::PCRE) => self.gen_regexp(target, controller),
+      Some(Token::BITS) => self.gen_bits(target, controller),
+      Some(token @ Token::LT)
+      | Some(token @ Token::LE)
+      | Some(token @ Token::GT)
+      | Some(token @ Token::GE) => self.gen_comparison(target, token, controller),
+      Some(Token::EQ) => self.gen_type2(controller),
+      Some(Token::NE) => {
+        let excluded = {
+          let exhaustive = self.exhaustive;
+          self.exhaustive = true;
+          let excluded = self.gen_type2(controller);
+          self.exhaustive = exhaustive;
+          excluded?
+        };
+
+        // Regenerate a bounded number of times in case a random value equals
+        // the excluded value
+        for _ in 0..16 {
+          let values = self
+            .gen_type2(target)?
+            .into_iter()
+            .filter(|v| !excluded.contains(v))
+            .collect::<Vec<_>>();
+          if !values.is_empty() {
+            return Ok(values);
+          }
+        }
+
+        Err(Error::Unsatisfiable(format!(
+          "{} .ne {}",
+          target, controller
+        )))
+      }
+      Some(Token::DEFAULT) => self.gen_type2(target),
+      Some(Token::CBOR) | Some(Token::CBORSEQ) => {
+        self.require_cbor(ctrl)?;
+
+        let is_seq = matches!(lookup_control_from_str(ctrl), Some(Token::CBORSEQ));
+        let mut values = Vec::new();
+        for v in self.gen_type2(controller)?.into_iter() {
+          let bytes = match v {
+            Value::Array(items) if is_seq => {
+              let mut bytes = Vec::new();
+              for item in items.iter() {
+                bytes.append(&mut to_cbor(item, self.float_width)?);
+              }
+              bytes
+            }
+            _ if is_seq => {
+              return Err(Error::Unsupported(format!(
+                ".cborseq with non-array controller {}",
+                controller
+              )))
+            }
+            v => to_cbor(&v, self.float_width)?,
+          };
+
+          values.push(Value::Bytes(bytes));
+        }
+
+        Ok(values)
+      }
+      #[cfg(feature = "additional-controls")]
+      Some(Token::FEATURE) => self.gen_type2(target),
+      #[cfg(feature = "additional-controls")]
+      Some(Token::CAT) => {
+        let prefixes = self.gen_type2(target)?;
+        let suffixes = self.gen_type2(controller)?;
+        let mut values = Vec::new();
+        for p in prefixes.iter() {
+          for s in suffixes.iter() {
+            values.push(match (p, s) {
+              (Value::Text(p), Value::Text(s)) => Value::Text(format!("{}{}", p, s)),
+              (Value::Bytes(p), Value::Bytes(s)) => Value::Bytes([&p[..], &s[..]].concat()),
+              _ => {
+                return Err(Error::Unsatisfiable(format!(
+                  "{} .cat {}",
+                  target, controller
+                )))
+              }
+            });
+          }
+        }
+        Ok(values)
+      }
+      #[cfg(feature = "additional-controls")]
+      Some(Token::PLUS) => match (self.numeric_value(target), self.numeric_value(controller)) {
+        (Some(Value::Integer(t)), Some(Value::Integer(c))) => {
+          Ok(vec![int_value(i128::from(t) + i128::from(c))])
+        }
+        (Some(t), Some(c)) => match (numeric_as_f64(&t), numeric_as_f64(&c)) {
+          (Some(t), Some(c)) => Ok(vec![Value::Float(t + c)]),
+          _ => Err(Error::Unsatisfiable(format!(
+            "{} .plus {}",
+            target, controller
+          ))),
+        },
+        _ => Err(Error::Unsupported(format!(
+          "{} .plus {}",
+          target, controller
+        ))),
+      },
+      _ => Err(Error::Unsupported(format!("control operator {}", ctrl))),
+    }
+  }
+
+  // `.size` constrains the length of text and byte strings, and the number of
+  // bytes an unsigned integer fits in
+  fn gen_size(&mut self, target: &'a Type2<'a>, controller: &'a Type2<'a>) -> Result<Vec<Value>> {
+    let bounds = self
+      .size_bounds(controller)
+      .ok_or_else(|| Error::Unsupported(format!(".size {}", controller)))?;
+
+    match self.base_kind(target) {
+      Some(BaseKind::Text) => self.gen_text(bounds),
+      Some(BaseKind::Bytes) => self.gen_bytes(bounds),
+      Some(BaseKind::Uint) => {
+        let max = if bounds.1 >= 16 {
+          u128::MAX as i128
+        } else {
+          (1i128 << (8 * bounds.1)) - 1
+        };
+        self.gen_int_within((0, max.min(u64::MAX as i128)))
+      }
+      _ => Err(Error::Unsupported(format!(
+        "{} .size {}",
+        target, controller
+      ))),
+    }
+  }
+
+  fn size_bounds(&self, controller: &Type2) -> Option<(usize, usize)> {
+    if let Some(Value::Integer(size)) = self.numeric_value(controller) {
+      let size = usize::try_from(i128::from(size)).ok()?;
+      return Some((size, size));
+    }
+
+    if let Type2::ParenthesizedType { pt, .. } = controller {
+      if let [TypeChoice {
+        type1:
+          Type1 {
+            type2: lower,
+            operator:
+              Some(Operator {
+                operator: RangeCtlOp::RangeOp { is_inclusive, .. },
+                type2: upper,
+                ..
+              }),
+            ..
+          },
+        ..
+      }] = &pt.type_choices[..]
+      {
+        if let (Some(Value::Integer(l)), Some(Value::Integer(u))) =
+          (self.numeric_value(lower), self.numeric_value(upper))
+        {
+          let l = usize::try_from(i128::from(l)).ok()?;
+          let u = usize::try_from(i128::from(u)).ok()?;
+          let u = if *is_inclusive { u } else { u.checked_sub(1)? };
+          if l <= u {
+            return Some((l, u));
+          }
+        }
+      }
+    }
+
+    None
+  }
+
+  fn gen_regexp(&mut self, target: &'a Type2<'a>, controller: &'a Type2<'a>) -> Result<Vec<Value>> {
+    if !matches!(self.base_kind(target), Some(BaseKind::Text)) {
+      return Err(Error::Unsupported(format!("{} .regexp", target)));
+    }
+
+    let pattern = match controller {
+      Type2::TextValue { value, .. } => value.to_string(),
+      Type2::Typename { ident, .. } => match text_value_from_ident(self.cddl, ident) {
+        Some(Type2::TextValue { value, .. }) => value.to_string(),
+        _ => return Err(Error::Unsupported(format!(".regexp {}", controller))),
+      },
+      _ => return Err(Error::Unsupported(format!(".regexp {}", controller))),
+    };
+
+    // Text strings must be JSON unescaped per
+    // https://datatracker.ietf.org/doc/html/rfc8610#section-3.1
+    let pattern = serde_json::from_str::<serde_json::Value>(&format!("\"{}\"", pattern))
+      .ok()
+      .and_then(|p| p.as_str().and_then(format_regex))
+      .ok_or_else(|| Error::Unsupported(format!("malformed regex {}", pattern)))?;
+
+    let regex = rand_regex::Regex::compile(&pattern, self.max_occurrences as u32)
+      .map_err(|e| Error::Unsupported(format!("regex {}: {}", pattern, e)))?;
+
+    Ok(vec![Value::Text(self.rng.sample::<String, _>(&regex))])
+  }
+
+  // `.bits` constrains which bits may be set in an unsigned integer or byte
+  // string
+  fn gen_bits(&mut self, target: &'a Type2<'a>, controller: &'a Type2<'a>) -> Result<Vec<Value>> {
+    let exhaustive = self.exhaustive;
+    self.exhaustive = true;
+    let bits = self.gen_type2(controller);
+    self.exhaustive = exhaustive;
+
+    let mut bits = bits?
+      .into_iter()
+      .filter_map(|v| v.as_integer().and_then(|i| u32::try_from(i).ok()))
+      .collect::<Vec<_>>();
+    bits.sort_unstable();
+    bits.dedup();
+
+    let kind = self.base_kind(target);
+
+    let mut selections = vec![Vec::new(), bits.clone()];
+    if !self.exhaustive {
+      selections = vec![bits
+        .iter()
+        .copied()
+        .filter(|_| self.rng.gen_bool(0.5))
+        .collect()];
+    }
+
+    let mut values = Vec::new();
+    for selection in selections.into_iter() {
+      match kind {
+        Some(BaseKind::Uint) => {
+          let n = selection
+            .iter()
+            .filter(|b| **b < 64)
+            .fold(0u64, |n, b| n | (1 << b));
+          values.push(int_value(n as i128));
+        }
+        Some(BaseKind::Bytes) => {
+          self.require_cbor("bstr")?;
+          let len = selection.last().map(|b| *b as usize / 8 + 1).unwrap_or(0);
+          let mut bytes = vec![0u8; len];
+          for b in selection.iter() {
+            bytes[*b as usize / 8] |= 1 << (b % 8);
+          }
+          values.push(Value::Bytes(bytes));
+        }
+        _ => return Err(Error::Unsupported(format!("{} .bits", target))),
+      }
+    }
+
+    Ok(self.pick(values))
+  }
+
+  fn gen_comparison(
+    &mut self,
+    target: &'a Type2<'a>,
+    op: Token,
+    controller: &'a Type2<'a>,
+  ) -> Result<Vec<Value>> {
+    let unsatisfiable = || Error::Unsatisfiable(format!("{} {} {}", target, op, controller));
+
+    let bound = self
+      .numeric_value(controller)
+      .ok_or_else(|| Error::Unsupported(format!("{} {} {}", target, op, controller)))?;
+
+    match (self.base_kind(target), &bound) {
+      (Some(BaseKind::Uint), Value::Integer(b))
+      | (Some(BaseKind::Nint), Value::Integer(b))
+      | (Some(BaseKind::Int), Value::Integer(b)) => {
+        let (mut lower, mut upper) = match self.base_kind(target) {
+          Some(BaseKind::Uint) => (0, u64::MAX as i128),
+          Some(BaseKind::Nint) => (i64::MIN as i128, -1),
+          _ => (i64::MIN as i128, u64::MAX as i128),
+        };
+
+        let b = i128::from(*b);
+        match op {
+          Token::LT => upper = upper.min(b - 1),
+          Token::LE => upper = upper.min(b),
+          Token::GT => lower = lower.max(b + 1),
+          _ => lower = lower.max(b),
+        }
+
+        if lower > upper {
+          return Err(unsatisfiable());
+        }
+
+        self.gen_int((lower, upper))
+      }
+      (Some(BaseKind::Float), _) => {
+        let b = numeric_as_f64(&bound).ok_or_else(unsatisfiable)?;
+        let (lower, upper) = match op {
+          Token::LT | Token::LE => (b - 1000.0, b),
+          _ => (b, b + 1000.0),
+        };
+
+        let values = self.gen_float(lower, upper)?;
+        Ok(
+          values
+            .into_iter()
+            .filter(|v| match (v, &op) {
+              (Value::Float(f), Token::LT) => *f < b,
+              (Value::Float(f), Token::GT) => *f > b,
+              _ => true,
+            })
+            .collect(),
+        )
+      }
+      _ => Err(Error::Unsupported(format!(
+        "{} {} {}",
+        target, op, controller
+      ))),
+    }
+  }
+
+  // Prelude type a control operator target resolves to
+  fn base_kind(&self, target: &Type2) -> Option<BaseKind> {
+    match target {
+      Type2::Typename { ident, .. } => {
+        if let Some(arg) = self.generic_arg(ident) {
+          return self.base_kind(&arg.type2);
+        }
+
+        if is_ident_string_data_type(self.cddl, ident) {
+          Some(BaseKind::Text)
+        } else if is_ident_byte_string_data_type(self.cddl, ident) {
+          Some(BaseKind::Bytes)
+        } else if is_ident_uint_data_type(self.cddl, ident) {
+          Some(BaseKind::Uint)
+        } else if is_ident_nint_data_type(self.cddl, ident) {
+          Some(BaseKind::Nint)
+        } else if is_ident_integer_data_type(self.cddl, ident) {
+          Some(BaseKind::Int)
+        } else if is_ident_float_data_type(self.cddl, ident) {
+          Some(BaseKind::Float)
+        } else {
+          None
+        }
+      }
+      Type2::DataMajorType { mt: 0, .. } => Some(BaseKind::Uint),
+      Type2::DataMajorType { mt: 1, .. } => Some(BaseKind::Nint),
+      Type2::DataMajorType { mt: 2, .. } => Some(BaseKind::Bytes),
+      Type2::DataMajorType { mt: 3, .. } => Some(BaseKind::Text),
+      _ => None,
+    }
+  }
+
+  fn gen_group(&mut self, group: &'a Group<'a>, is_map: bool) -> Result<Vec<Entries>> {
+    let choices = group.group_choices.iter().collect::<Vec<_>>();
+    self.gen_entry_alternatives(&choices, |g, gc| g.gen_group_choice(gc, is_map))
+  }
+
+  // Same as `gen_alternatives`, but for group entries
+  fn gen_entry_alternatives<T, F>(&mut self, alternatives: &[T], mut gen: F) -> Result<Vec<Entries>>
+  where
+    F: FnMut(&mut Self, &T) -> Result<Vec<Entries>>,
+  {
+    let mut indices = (0..alternatives.len()).collect::<Vec<_>>();
+    if !self.exhaustive {
+      indices.shuffle(&mut self.rng);
+    }
+
+    let mut entries = Vec::new();
+    let mut error = None;
+    for idx in indices.into_iter() {
+      match gen(self, &alternatives[idx]) {
+        Ok(mut e) => {
+          entries.append(&mut e);
+          if !self.exhaustive && !entries.is_empty() {
+            break;
+          }
+        }
+        Err(e) => error = Some(e),
+      }
+    }
+
+    if entries.is_empty() {
+      if let Some(e) = error {
+        return Err(e);
+      }
+    }
+
+    if self.extreme.is_some() {
+      return Ok(self.select(entries, Self::entries_size));
+    }
+
+    entries.truncate(self.max_instances);
+
+    Ok(entries)
+  }
+
+  fn gen_group_choice(&mut self, gc: &'a GroupChoice<'a>, is_map: bool) -> Result<Vec<Entries>> {
+    let mut combinations = vec![Entries::new()];
+    for (ge, _) in gc.group_entries.iter() {
+      let entries = self.gen_group_entry(ge, is_map)?;
+      combinations = self.product(&combinations, &entries);
+    }
+
+    Ok(combinations)
+  }
+
+  fn gen_group_entry(&mut self, ge: &'a GroupEntry<'a>, is_map: bool) -> Result<Vec<Entries>> {
+    let (lower, upper) = occurrence_bounds(ge);
+    let upper = upper.min(lower.max(self.max_occurrences));
+
+    // Omit optional entries beyond half of the maximum depth so recursive rules
+    // terminate
+    let counts = if self.depth * 2 > self.max_depth {
+      vec![lower]
+    } else if let Some(extreme) = self.extreme {
+      vec![match extreme {
+        Extreme::Smallest => lower,
+        Extreme::Largest => upper,
+      }]
+    } else if self.exhaustive {
+      let mut counts = vec![lower];
+      if upper != lower {
+        counts.push(upper);
+      }
+      counts
+    } else {
+      vec![self.rng.gen_range(lower..=upper)]
+    };
+
+    let mut combinations = Vec::new();
+    let mut error = None;
+    for count in counts.into_iter() {
+      let mut repetitions = vec![Entries::new()];
+      let mut result = Ok(());
+      for idx in 0..count {
+        // Minimal and maximal generation yield a single deterministic
+        // repetition, which is repeated rather than regenerated so that
+        // nesting is generated in linear time
+        if self.extreme.is_some() && idx > 0 {
+          let single = repetitions.clone();
+          for _ in 1..count {
+            repetitions = self.product(&repetitions, &single);
+          }
+          break;
+        }
+
+        // Exhaustive generation enumerates each repetition identically
+        if self.exhaustive && idx > 0 {
+          let single = self.gen_single_entry(ge, is_map)?;
+          repetitions = self.product(&repetitions, &single);
+          continue;
+        }
+
+        match self.gen_single_entry(ge, is_map) {
+          Ok(single) => repetitions = self.product(&repetitions, &single),
+          Err(e) => {
+            result = Err(e);
+            break;
+          }
+        }
+      }
+
+      match result {
+        Ok(()) => combinations.append(&mut repetitions),
+        Err(e) => error = Some(e),
+      }
+    }
+
+    if combinations.is_empty() {
+      if let Some(e) = error {
+        return Err(e);
+      }
+    }
+
+    Ok(combinations)
+  }
+
+  fn gen_single_entry(&mut self, ge: &'a GroupEntry<'a>, is_map: bool) -> Result<Vec<Entries>> {
+    match ge {
+      GroupEntry::ValueMemberKey { ge, .. } => {
+        if let [TypeChoice {
+          type1:
+            Type1 {
+              type2:
+                Type2::Unwrap {
+                  ident,
+                  generic_args,
+                  ..
+                },
+              operator: None,
+              ..
+            },
+          ..
+        }] = &ge.entry_type.type_choices[..]
+        {
+          return self.gen_unwrapped_group(ident, generic_args.as_ref(), is_map);
+        }
+
+        let values = self.gen_type(&ge.entry_type)?;
+
+        if !is_map {
+          return Ok(values.into_iter().map(|v| vec![(None, v)]).collect());
+        }
+
+        let keys = match &ge.member_key {
+          Some(MemberKey::Bareword { ident, .. }) => vec![Value::Text(ident.ident.to_string())],
+          Some(MemberKey::Value { value, .. }) => vec![self.literal_value(value)?],
+          Some(MemberKey::Type1 { t1, .. }) => self.gen_type1(t1)?,
+          _ => return Err(Error::Unsupported(format!("map entry {}", ge))),
+        };
+
+        if self.target == Target::Json && keys.iter().any(|k| !k.is_text()) {
+          return Err(Error::UnsupportedJSON(format!("map key of entry {}", ge)));
+        }
+
+        let mut entries = Vec::new();
+        for k in keys.iter() {
+          for v in values.iter() {
+            entries.push(vec![(Some(k.clone()), v.clone())]);
+          }
+        }
+        entries.truncate(self.max_instances);
+
+        Ok(entries)
+      }
+      GroupEntry::TypeGroupname { ge, .. } => {
+        if self.generic_arg(&ge.name).is_none() {
+          let alternates = group_choice_alternates_from_ident(self.cddl, &ge.name);
+          if !alternates.is_empty() {
+            return self.with_rule_entries(&ge.name, ge.generic_args.as_ref(), |g| {
+              g.gen_entry_alternatives(&alternates, |g, ge| g.gen_group_entry(ge, is_map))
+            });
+          }
+        }
+
+        if is_map {
+          return Err(Error::Unsupported(format!(
+            "type {} used as a map entry",
+            ge.name
+          )));
+        }
+
+        Ok(
+          self
+            .gen_ident(&ge.name, ge.generic_args.as_ref())?
+            .into_iter()
+            .map(|v| vec![(None, v)])
+            .collect(),
+        )
+      }
+      GroupEntry::InlineGroup { group, .. } => self.gen_group(group, is_map),
+    }
+  }
+
+  // Entries of the map or array type an unwrapped identifier refers to
+  fn gen_unwrapped_group(
+    &mut self,
+    ident: &'a Identifier<'a>,
+    generic_args: Option<&'a GenericArgs<'a>>,
+    is_map: bool,
+  ) -> Result<Vec<Entries>> {
+    let group = match unwrap_rule_from_ident(self.cddl, ident) {
+      Some(Rule::Type { rule, .. }) => {
+        rule
+          .value
+          .type_choices
+          .iter()
+          .find_map(|tc| match &tc.type1.type2 {
+            Type2::Map { group, .. } if is_map => Some(group),
+            Type2::Array { group, .. } if !is_map => Some(group),
+            _ => None,
+          })
+      }
+      _ => None,
+    }
+    .ok_or_else(|| Error::Unsupported(format!("unwrapping {}", ident)))?;
+
+    self.with_rule_entries(ident, generic_args, |g| g.gen_group(group, is_map))
+  }
+
+  // Same as `with_rule`, but for group entries
+  fn with_rule_entries<F>(
+    &mut self,
+    ident: &Identifier<'a>,
+    generic_args: Option<&'a GenericArgs<'a>>,
+    gen: F,
+  ) -> Result<Vec<Entries>>
+  where
+    F: FnOnce(&mut Self) -> Result<Vec<Entries>>,
+  {
+    let mut entries = None;
+    self.with_rule(ident, generic_args, |g| {
+      entries = Some(gen(g)?);
+      Ok(Vec::new())
+    })?;
+
+    Ok(entries.unwrap_or_default())
+  }
+
+  fn literal_value(&self, value: &token::Value) -> Result<Value> {
+    match value {
+      token::Value::INT(i) => Ok(int_value(*i as i128)),
+      token::Value::UINT(u) => Ok(int_value(*u as i128)),
+      token::Value::FLOAT(f) => Ok(Value::Float(*f)),
+      token::Value::TEXT(t) => Ok(Value::Text(t.to_string())),
+      token::Value::BYTE(b) => {
+        self.require_cbor("bstr")?;
+        let bytes = match b {
+          token::ByteValue::UTF8(b) => b.to_vec(),
+          token::ByteValue::B16(b) => base16::decode(b)
+            .map_err(|e| Error::Unsatisfiable(format!("base16 byte string: {}", e)))?,
+          token::ByteValue::B64(b) => base64::decode_config(b, base64::URL_SAFE)
+            .map_err(|e| Error::Unsatisfiable(format!("base64 byte string: {}", e)))?,
+        };
+        Ok(Value::Bytes(bytes))
+      }
+    }
+  }
+
+  fn map_value(&self, entries: Entries) -> Result<Value> {
+    let mut map: Vec<(Value, Value)> = Vec::with_capacity(entries.len());
+    for (k, v) in entries.into_iter() {
+      let k = k.ok_or_else(|| Error::Unsupported("map entry without a key".to_string()))?;
+
+      // Keys generated from types (e.g. `* tstr => int`) may collide
+      if !map.iter().any(|(existing, _)| *existing == k) {
+        map.push((k, v));
+      }
+    }
+
+    Ok(Value::Map(map))
+  }
+
+  // Cartesian product of the given entry combinations
+  fn product(&self, prefixes: &[Entries], suffixes: &[Entries]) -> Vec<Entries> {
+    let mut combinations = Vec::new();
+    for p in prefixes.iter() {
+      for s in suffixes.iter() {
+        if combinations.len() == self.max_instances {
+          return combinations;
+        }
+
+        let mut entries = p.clone();
+        entries.extend(s.iter().cloned());
+        combinations.push(entries);
+      }
+    }
+
+    combinations
+  }
+
+  // Random generation picks one of the given values, and minimal and maximal
+  // generation the smallest and largest
+  fn pick(&mut self, mut values: Vec<Value>) -> Vec<Value> {
+    if self.extreme.is_some() {
+      return self.select(values, Self::size);
+    }
+
+    if self.exhaustive || values.is_empty() {
+      return values;
+    }
+
+    let idx = self.rng.gen_range(0..values.len());
+    vec![values.swap_remove(idx)]
+  }
+
+  // Bounds of a length or count. Random generation draws from the given bounds,
+  // exhaustive generation only yields the lower and upper bound, and minimal
+  // and maximal generation the lower and upper bound respectively
+  fn bounds(&mut self, bounds: (usize, usize)) -> (usize, usize) {
+    match self.extreme {
+      Some(Extreme::Smallest) => return (bounds.0, bounds.0),
+      Some(Extreme::Largest) => return (bounds.1, bounds.1),
+      None => {}
+    }
+
+    if self.exhaustive {
+      return bounds;
+    }
+
+    let len = self.rng.gen_range(bounds.0..=bounds.1);
+    (len, len)
+  }
+
+  fn gen_int(&mut self, bounds: IntBounds) -> Result<Vec<Value>> {
+    if self.exhaustive {
+      return self.gen_int_within(bounds);
+    }
+
+    // Random integers are typically small, but occasionally at the bounds of
+    // the type
+    let lower = bounds.0.max(TYPICAL_INT.0);
+    let upper = bounds.1.min(TYPICAL_INT.1);
+    if lower > upper || self.rng.gen_ratio(1, 8) {
+      return self.gen_int_within(bounds);
+    }
+
+    self.gen_int_within((lower, upper))
+  }
+
+  fn gen_int_within(&mut self, (lower, upper): IntBounds) -> Result<Vec<Value>> {
+    match self.extreme {
+      Some(Extreme::Smallest) => return Ok(vec![int_value(0.max(lower).min(upper))]),
+      Some(Extreme::Largest) if upper.abs() >= lower.abs() => return Ok(vec![int_value(upper)]),
+      Some(Extreme::Largest) => return Ok(vec![int_value(lower)]),
+      None => {}
+    }
+
+    if self.exhaustive {
+      return Ok(if lower == upper {
+        vec![int_value(lower)]
+      } else {
+        vec![int_value(lower), int_value(upper)]
+      });
+    }
+
+    Ok(vec![int_value(self.rng.gen_range(lower..=upper))])
+  }
+
+  fn gen_float(&mut self, lower: f64, upper: f64) -> Result<Vec<Value>> {
+    match self.extreme {
+      Some(Extreme::Smallest) => return Ok(vec![Value::Float(0f64.max(lower).min(upper))]),
+      Some(Extreme::Largest) if upper.abs() >= lower.abs() => return Ok(vec![Value::Float(upper)]),
+      Some(Extreme::Largest) => return Ok(vec![Value::Float(lower)]),
+      None => {}
+    }
+
+    if self.exhaustive {
+      return Ok(vec![Value::Float(lower), Value::Float(upper)]);
+    }
+
+    let lower = lower.max(TYPICAL_INT.0 as f64);
+    let upper = upper.min(TYPICAL_INT.1 as f64);
+    let f = self.rng.gen_range(lower..=upper);
+
+    // Keep random floats readable
+    Ok(vec![Value::Float((f * 100.0).round() / 100.0)])
+  }
+
+  fn gen_text(&mut self, bounds: (usize, usize)) -> Result<Vec<Value>> {
+    let (lower, upper) = self.bounds(bounds);
+    let mut values = vec![Value::Text(self.random_text(lower))];
+    if upper != lower {
+      values.push(Value::Text(self.random_text(upper)));
+    }
+
+    Ok(values)
+  }
+
+  fn gen_bytes(&mut self, bounds: (usize, usize)) -> Result<Vec<Value>> {
+    self.require_cbor("bstr")?;
+
+    let (lower, upper) = self.bounds(bounds);
+    let mut values = vec![Value::Bytes(self.random_bytes(lower))];
+    if upper != lower {
+      values.push(Value::Bytes(self.random_bytes(upper)));
+    }
+
+    Ok(values)
+  }
+
+  fn random_text(&mut self, len: usize) -> String {
+    (0..len)
+      .map(|_| self.rng.sample(rand::distributions::Alphanumeric) as char)
+      .collect()
+  }
+
+  fn random_bytes(&mut self, len: usize) -> Vec<u8> {
+    (0..len).map(|_| self.rng.gen()).collect()
+  }
+
+  fn bytes(&self, values: Vec<Vec<u8>>) -> Result<Vec<Value>> {
+    self.require_cbor("bstr")?;
+    Ok(values.into_iter().map(Value::Bytes).collect())
+  }
+
+  // Smallest or largest of the given items by the length of their CBOR
+  // encoding, or the first of equally sized items. Items that can't be encoded
+  // are only selected if no other item can
+  fn select<T>(&self, items: Vec<T>, size: fn(&Self, &T) -> Option<usize>) -> Vec<T> {
+    let largest = self.extreme == Some(Extreme::Largest);
+
+    let mut selected: Option<(Option<usize>, T)> = None;
+    for item in items.into_iter() {
+      let item_size = size(self, &item);
+      let is_better = match &selected {
+        None => true,
+        Some((None, _)) => item_size.is_some(),
+        Some((Some(best), _)) => match item_size {
+          Some(s) if largest => s > *best,
+          Some(s) => s < *best,
+          None => false,
+        },
+      };
+
+      if is_better {
+        selected = Some((item_size, item));
+      }
+    }
+
+    selected.map(|(_, item)| item).into_iter().collect()
+  }
+
+  fn entries_size(&self, entries: &Entries) -> Option<usize> {
+    let mut size = 0;
+    for (k, v) in entries.iter() {
+      if let Some(k) = k {
+        size += self.size(k)?;
+      }
+      size += self.size(v)?;
+    }
+
+    Some(size)
+  }
+
+  fn size(&self, value: &Value) -> Option<usize> {
+    to_cbor(value, self.float_width)
+      .ok()
+      .map(|bytes| bytes.len())
+  }
+
+  fn require_cbor(&self, item: &str) -> Result<()> {
+    if self.target == Target::Json {
+      return Err(Error::UnsupportedJSON(item.to_string()));
+    }
+
+    Ok(())
+  }
+}
+
+// Instance generated by minimal or maximal generation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Extreme {
+  Smallest,
+  Largest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BaseKind {
+  Text,
+  Bytes,
+  Uint,
+  Nint,
+  Int,
+  Float,
+}
+
+fn int_value(i: i128) -> Value {
+  Value::Integer(Integer::try_from(i).unwrap_or_else(|_| {
+    if i < 0 {
+      Integer::from(i64::MIN)
+    } else {
+      Integer::from(u64::MAX)
+    }
+  }))
+}
+
+fn numeric_as_f64(value: &Value) -> Option<f64> {
+  match value {
+    Value::Integer(i) => Some(i128::from(*i) as f64),
+    Value::Float(f) => Some(*f),
+    _ => None,
+  }
+}
+
+// Lower and upper bound of the occurrence of a group entry
+fn occurrence_bounds(ge: &GroupEntry) -> (usize, usize) {
+  let occur = match ge {
+    GroupEntry::ValueMemberKey { ge, .. } => ge.occur.as_ref(),
+    GroupEntry::TypeGroupname { ge, .. } => ge.occur.as_ref(),
+    GroupEntry::InlineGroup { occur, .. } => occur.as_ref(),
+  };
+
+  match occur.map(|o| &o.occur) {
+    None => (1, 1),
+    #[cfg(feature = "ast-span")]
+    Some(Occur::Optional(_)) => (0, 1),
+    #[cfg(not(feature = "ast-span"))]
+    Some(Occur::Optional) => (0, 1),
+    #[cfg(feature = "ast-span")]
+    Some(Occur::ZeroOrMore(_)) => (0, usize::MAX),
+    #[cfg(not(feature = "ast-span"))]
+    Some(Occur::ZeroOrMore) => (0, usize::MAX),
+    #[cfg(feature = "ast-span")]
+    Some(Occur::OneOrMore(_)) => (1, usize::MAX),
+    #[cfg(not(feature = "ast-span"))]
+    Some(Occur::OneOrMore) => (1, usize::MAX),
+    Some(Occur::Exact { lower, upper, .. }) => (lower.unwrap_or(0), upper.unwrap_or(usize::MAX)),
+  }
+}
+
+// Write the initial byte and argument of a CBOR data item in its shortest form
+fn encode_head(major: u8, argument: u64, bytes: &mut Vec<u8>) {
+  let major = major << 5;
+  if argument < 24 {
+    bytes.push(major | argument as u8);
+  } else if let Ok(argument) = u8::try_from(argument) {
+    bytes.extend_from_slice(&[major | 24, argument]);
+  } else if let Ok(argument) = u16::try_from(argument) {
+    bytes.push(major | 25);
+    bytes.extend_from_slice(&argument.to_be_bytes());
+  } else if let Ok(argument) = u32::try_from(argument) {
+    bytes.push(major | 26);
+    bytes.extend_from_slice(&argument.to_be_bytes());
+  } else {
+    bytes.push(major | 27);
+    bytes.extend_from_slice(&argument.to_be_bytes());
+  }
+}
+
+fn encode_float(f: f64, float_width: FloatWidth, bytes: &mut Vec<u8>) {
+  let half = half::f16::from_f64(f);
+  let is_half_exact = half.to_f64() == f || (f.is_nan() && half.is_nan());
+
+  match float_width {
+    FloatWidth::Shortest | FloatWidth::HalfOrDouble if is_half_exact => {
+      bytes.push(0xf9);
+      bytes.extend_from_slice(&half.to_be_bytes());
+    }
+    FloatWidth::Shortest if f as f32 as f64 == f => {
+      bytes.push(0xfa);
+      bytes.extend_from_slice(&(f as f32).to_be_bytes());
+    }
+    _ => {
+      bytes.push(0xfb);
+      bytes.extend_from_slice(&f.to_be_bytes());
+    }
+  }
+}
+
+fn encode_value(value: &Value, float_width: FloatWidth, bytes: &mut Vec<u8>) -> Result<()> {
+  match value {
+    Value::Integer(i) => {
+      let i = i128::from(*i);
+      if i < 0 {
+        encode_head(1, (-1 - i) as u64, bytes);
+      } else {
+        encode_head(0, i as u64, bytes);
+      }
+    }
+    Value::Bytes(b) => {
+      encode_head(2, b.len() as u64, bytes);
+      bytes.extend_from_slice(b);
+    }
+    Value::Text(t) => {
+      encode_head(3, t.len() as u64, bytes);
+      bytes.extend_from_slice(t.as_bytes());
+    }
+    Value::Array(a) => {
+      encode_head(4, a.len() as u64, bytes);
+      for v in a.iter() {
+        encode_value(v, float_width, bytes)?;
+      }
+    }
+    Value::Map(m) => {
+      encode_head(5, m.len() as u64, bytes);
+      for (k, v) in m.iter() {
+        encode_value(k, float_width, bytes)?;
+        encode_value(v, float_width, bytes)?;
+      }
+    }
+    Value::Tag(tag, v) => {
+      encode_head(6, *tag, bytes);
+      encode_value(v, float_width, bytes)?;
+    }
+    Value::Bool(false) => bytes.push(0xf4),
+    Value::Bool(true) => bytes.push(0xf5),
+    Value::Null => bytes.push(0xf6),
+    Value::Float(f) => encode_float(*f, float_width, bytes),
+    _ => {
+      return Err(Error::CBOREncoding(format!(
+        "unsupported value {:?}",
+        value
+      )))
+    }
+  }
+
+  Ok(())
+}
+
+/// Encode a generated or converted value as CBOR, with floating-point numbers
+/// encoded in the given width. Lengths and integers are always encoded in
+/// their shortest form
+///
+/// # Example
+///
+/// ```
+/// use cddl::generator::{to_cbor, FloatWidth};
+/// use ciborium::value::Value;
+///
+/// let value = Value::Float(1.5);
+/// assert_eq!(to_cbor(&value, FloatWidth::Shortest).unwrap(), vec![0xf9, 0x3e, 0x00]);
+/// assert_eq!(to_cbor(&value, FloatWidth::Double).unwrap().len(), 9);
+/// ```
+pub fn to_cbor(value: &Value, float_width: FloatWidth) -> Result<Vec<u8>> {
+  let mut bytes = Vec::new();
+  encode_value(value, float_width, &mut bytes)?;
+  Ok(bytes)
+}
+
+/// Convert a generated value into a JSON value
+#[cfg(feature = "json")]
+pub fn to_json(value: &Value) -> Result<serde_json::Value> {
+  match value {
+    Value::Integer(i) => {
+      let i = i128::from(*i);
+      if let Ok(u) = u64::try_from(i) {
+        Ok(serde_json::Value::from(u))
+      } else if let Ok(i) = i64::try_from(i) {
+        Ok(serde_json::Value::from(i))
+      } else {
+        Err(Error::UnsupportedJSON(format!("integer {}", i)))
+      }
+    }
+    Value::Float(f) => serde_json::Number::from_f64(*f)
+      .map(serde_json::Value::Number)
+      .ok_or_else(|| Error::UnsupportedJSON(format!("float {}", f))),
+    Value::Text(t) => Ok(serde_json::Value::String(t.clone())),
+    Value::Bool(b) => Ok(serde_json::Value::Bool(*b)),
+    Value::Null => Ok(serde_json::Value::Null),
+    Value::Array(a) => Ok(serde_json::Value::Array(
+      a.iter().map(to_json).collect::<Result<Vec<_>>>()?,
+    )),
+    Value::Map(m) => {
+      let mut object = serde_json::Map::new();
+      for (k, v) in m.iter() {
+        let k = k
+          .as_text()
+          .ok_or_else(|| Error::UnsupportedJSON("non-text map key".to_string()))?;
+        object.insert(k.to_string(), to_json(v)?);
+      }
+      Ok(serde_json::Value::Object(object))
+    }
+    Value::Bytes(_) => Err(Error::UnsupportedJSON("bstr".to_string())),
+    Value::Tag(..) => Err(Error::UnsupportedJSON("tagged data".to_string())),
+    _ => Err(Error::UnsupportedJSON(format!("{:?}", value))),
+  }
+}
+
+/// Convert a JSON value into a value that can be encoded as CBOR with
+/// [`to_cbor`]. Numbers without a fractional part or exponent are converted
+/// into integers, and other numbers into floats
+#[cfg(feature = "json")]
+pub fn from_json(json: &serde_json::Value) -> Value {
+  match json {
+    serde_json::Value::Null => Value::Null,
+    serde_json::Value::Bool(b) => Value::Bool(*b),
+    serde_json::Value::Number(n) => {
+      if let Some(u) = n.as_u64() {
+        Value::Integer(u.into())
+      } else if let Some(i) = n.as_i64() {
+        Value::Integer(i.into())
+      } else {
+        Value::Float(n.as_f64().unwrap_or(f64::NAN))
+      }
+    }
+    serde_json::Value::String(s) => Value::Text(s.clone()),
+    serde_json::Value::Array(a) => Value::Array(a.iter().map(from_json).collect()),
+    serde_json::Value::Object(o) => Value::Map(
+      o.iter()
+        .map(|(k, v)| (Value::Text(k.clone()), from_json(v)))
+        .collect(),
+    ),
+  }
+}
+
+/// Generate a random JSON document conforming to the given rule of a CDDL
+/// document string. If no rule is given, the root rule is used
+///
+/// # Example
+///
+/// ```
+/// use cddl::generator::generate_json_from_str;
+///
+/// let json = generate_json_from_str("person = { name: tstr, age: 0..120 }", None, 42).unwrap();
+/// assert!(json["age"].as_u64().unwrap() <= 120);
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "json")]
+pub fn generate_json_from_str(
+  cddl: &str,
+  rule: Option<&str>,
+  seed: u64,
+) -> Result<serde_json::Value> {
+  let cddl = crate::cddl_from_str(cddl, false).map_err(Error::CDDLParsing)?;
+  let mut generator = Generator::new(&cddl, Target::Json, seed);
+  let rule = match rule {
+    Some(rule) => rule,
+    None => generator
+      .root_rule()
+      .ok_or_else(|| Error::MissingRule("root".to_string()))?,
+  };
+
+  to_json(&generator.generate(rule)?)
+}
+
+/// Generate random CBOR bytes conforming to the given rule of a CDDL document
+/// string. If no rule is given, the root rule is used. Floating-point numbers
+/// are encoded in their shortest form; encode values from a [`Generator`] with
+/// [`to_cbor`] and its [`Generator::float_width`] to select another width
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "cbor")]
+pub fn generate_cbor_from_str(cddl: &str, rule: Option<&str>, seed: u64) -> Result<Vec<u8>> {
+  let cddl = crate::cddl_from_str(cddl, false).map_err(Error::CDDLParsing)?;
+  let mut generator = Generator::new(&cddl, Target::Cbor, seed);
+  let rule = match rule {
+    Some(rule) => rule,
+    None => generator
+      .root_rule()
+      .ok_or_else(|| Error::MissingRule("root".to_string()))?,
+  };
+
+  to_cbor(&generator.generate(rule)?, FloatWidth::Shortest)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{cddl_from_str, validate_cbor_from_slice, validate_json_from_str};
+  use indoc::indoc;
+
+  const CDDL: &str = indoc!(
+    r#"
+      person = {
+        name: tstr .size (1..16),
+        age: 0..120,
+        ? email: tstr .regexp "[a-z]+@example\\.com",
+        tags: [* tag],
+        role: role,
+        ? address: address,
+        scores: { * tstr => float },
+        ids: [+ uint .lt 100],
+      }
+
+      tag = tstr
+      role = "admin" / "user" / "guest"
+      address = { street: tstr, house: uint .size 2, geo: point<float> }
+      point<T> = [x: T, y: T]
+    "#
+  );
+
+  #[test]
+  fn verify_random_json_conforms() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    for seed in 0..32 {
+      let json = generate_json_from_str(CDDL, None, seed)?;
+
+      #[cfg(feature = "additional-controls")]
+      validate_json_from_str(CDDL, &json.to_string(), None)?;
+      #[cfg(not(feature = "additional-controls"))]
+      validate_json_from_str(CDDL, &json.to_string())?;
+    }
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_random_cbor_conforms() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = indoc!(
+      r#"
+        message = [
+          id: bstr .size 16,
+          payload: bytes .cbor header,
+          flags: uint .bits flag,
+        ]
+
+        header = { 1 => int, ? 4 => bstr, ? 5 => #6.18([* int]) }
+        flag = 0 / 1 / 2
+      "#
+    );
+
+    for seed in 0..32 {
+      let cbor = generate_cbor_from_str(cddl, None, seed)?;
+
+      #[cfg(feature = "additional-controls")]
+      validate_cbor_from_slice(cddl, &cbor, None)?;
+      #[cfg(not(feature = "additional-controls"))]
+      validate_cbor_from_slice(cddl, &cbor)?;
+    }
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_exhaustive_generation() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = indoc!(
+      r#"
+        entry = {
+          color: "red" / "green" / "blue",
+          ? count: 1..3,
+        }
+      "#
+    );
+
+    let cddl = cddl_from_str(cddl, true)?;
+    let mut generator = Generator::new(&cddl, Target::Json, 0);
+    let values = generator
+      .generate_all("entry")?
+      .iter()
+      .map(to_json)
+      .collect::<Result<Vec<_>>>()?;
+
+    // 3 colors, each with the count omitted or at either bound of its range
+    assert_eq!(values.len(), 9);
+    assert!(values.contains(&serde_json::json!({ "color": "blue", "count": 3 })));
+    assert!(values.contains(&serde_json::json!({ "color": "red" })));
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_minimal_generation() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let input = indoc!(
+      r#"
+        message = {
+          id: uint,
+          ? note: tstr,
+          name: tstr .size (3..10),
+          tags: [+ tag],
+          kind: "long-kind-name" / 7,
+          level: -5..5,
+          offset: -8..-2,
+          ratio: 1.5..2.5,
+          flags: uint .bits flag,
+        }
+        tag = tstr .size 2 / int
+        flag = &(a: 1, b: 4)
+      "#
+    );
+
+    let cddl = cddl_from_str(input, true)?;
+    let mut generator = Generator::new(&cddl, Target::Cbor, 0);
+    let value = generator.minimal("message")?;
+    let json = to_json(&value)?;
+
+    assert_eq!(json["name"].as_str().map(str::len), Some(3));
+    let mut json = json;
+    json["name"] = serde_json::json!("abc");
+    assert_eq!(
+      json,
+      serde_json::json!({
+        "id": 0,
+        "name": "abc",
+        "tags": [0],
+        "kind": 7,
+        "level": 0,
+        "offset": -2,
+        "ratio": 1.5,
+        "flags": 0,
+      })
+    );
+
+    let cbor = to_cbor(&value, FloatWidth::Shortest)?;
+    #[cfg(feature = "additional-controls")]
+    validate_cbor_from_slice(input, &cbor, None)?;
+    #[cfg(not(feature = "additional-controls"))]
+    validate_cbor_from_slice(input, &cbor)?;
+
+    // Minimal generation only depends on the seed for the contents of strings
+    let mut generator = Generator::new(&cddl, Target::Cbor, 0);
+    assert_eq!(generator.minimal("message")?, value);
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_maximal_generation() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let input = indoc!(
+      r#"
+        message = {
+          id: uint,
+          ? note: tstr .size (0..4),
+          name: tstr .size (3..10),
+          tags: [+ tag],
+          pair: [2*3 bool],
+          kind: "long-kind-name" / 7,
+          level: -5..3,
+          offset: -8..-2,
+          ? child: message,
+        }
+        tag = tstr .size 2 / int
+      "#
+    );
+
+    let cddl = cddl_from_str(input, true)?;
+    let mut generator = Generator::new(&cddl, Target::Cbor, 0);
+    let value = generator.maximal("message")?;
+    let json = to_json(&value)?;
+
+    assert_eq!(json["id"], serde_json::json!(u64::MAX));
+    assert_eq!(json["note"].as_str().map(str::len), Some(4));
+    assert_eq!(json["name"].as_str().map(str::len), Some(10));
+    assert_eq!(
+      json["tags"].as_array().map(Vec::len),
+      Some(generator.max_occurrences)
+    );
+    assert!(json["tags"][0].is_i64() || json["tags"][0].is_u64());
+    assert_eq!(json["pair"].as_array().map(Vec::len), Some(3));
+    assert_eq!(json["kind"], "long-kind-name");
+    assert_eq!(json["level"], -5);
+    assert_eq!(json["offset"], -8);
+
+    // Optional entries nest up to half of the maximum depth
+    assert!(json["child"].is_object());
+    let mut depth = 0;
+    let mut message = &json;
+    while message["child"].is_object() {
+      message = &message["child"];
+      depth += 1;
+    }
+    assert!(depth > 0 && depth * 2 <= generator.max_depth);
+
+    let cbor = to_cbor(&value, FloatWidth::Shortest)?;
+    #[cfg(feature = "additional-controls")]
+    validate_cbor_from_slice(input, &cbor, None)?;
+    #[cfg(not(feature = "additional-controls"))]
+    validate_cbor_from_slice(input, &cbor)?;
+
+    let mut generator = Generator::new(&cddl, Target::Cbor, 0);
+    assert_eq!(generator.maximal("message")?, value);
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_float_width() -> Result<()> {
+    let json = serde_json::json!({ "half": 1.5, "single": 100000.0, "double": 0.1, "int": 2 });
+    let value = from_json(&json);
+
+    let floats = |float_width| -> Result<Vec<u8>> {
+      Ok(
+        to_cbor(&value, float_width)?
+          .into_iter()
+          .filter(|b| (0xf9..=0xfb).contains(b))
+          .collect(),
+      )
+    };
+    assert_eq!(floats(FloatWidth::Shortest)?, vec![0xfb, 0xf9, 0xfa]);
+    assert_eq!(floats(FloatWidth::Double)?, vec![0xfb, 0xfb, 0xfb]);
+    assert_eq!(floats(FloatWidth::HalfOrDouble)?, vec![0xfb, 0xf9, 0xfb]);
+
+    let shortest = to_cbor(&value, FloatWidth::Shortest)?;
+    let double = to_cbor(&value, FloatWidth::Double)?;
+    assert_eq!(shortest.len() + 6 + 4, double.len());
+    for bytes in [shortest, double].iter() {
+      let decoded: Value =
+        ciborium::de::from_reader(&bytes[..]).map_err(|e| Error::CBOREncoding(e.to_string()))?;
+      assert_eq!(decoded, value);
+    }
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_json_unsupported() -> Result<()> {
+    assert!(matches!(
+      generate_json_from_str("data = bstr", None, 0),
+      Err(Error::UnsupportedJSON(_))
+    ));
+    assert!(generate_json_from_str("data = bstr / tstr", None, 0)?.is_string());
+
+    Ok(())
+  }
+}
@@ -0,0 +1,1386 @@
+#![cfg(feature = "std")]
+
+use crate::{annotations::RuleComments, ast::*, lexer_from_str, parser};
+
+use std::{borrow::Cow, collections::BTreeMap, fmt};
+
+// Upper bound on the nesting of rules expanded while checking whether an
+// argument satisfies a bound. Guards against recursive rules
+const MAX_BOUND_DEPTH: usize = 32;
+
+/// Generic resolution result
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Generic resolution error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+  /// Generic arguments are given to a rule that isn't defined
+  MissingRule(String),
+  /// Number of generic arguments doesn't match the number of generic
+  /// parameters of the rule
+  ArgumentCount {
+    /// Rule name
+    rule: String,
+    /// Number of generic parameters
+    expected: usize,
+    /// Number of generic arguments
+    found: usize,
+  },
+  /// Generic rule references itself, directly or indirectly, and therefore
+  /// can't be instantiated inline
+  Recursive(String),
+  /// Generic rule is used in a position where its instantiation can't be
+  /// expressed inline
+  Unsupported(String),
+  /// Generic argument doesn't satisfy the bound of its generic parameter
+  Bound {
+    /// Rule name
+    rule: String,
+    /// Generic parameter name
+    param: String,
+    /// Bound of the generic parameter
+    bound: String,
+    /// Generic argument
+    arg: String,
+  },
+  /// Bound given for a generic parameter the rule doesn't have
+  InvalidBound {
+    /// Rule name
+    rule: String,
+    /// Bound of the generic parameter
+    bound: String,
+  },
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Error::MissingRule(rule) => write!(f, "no rule named {} is defined", rule),
+      Error::ArgumentCount {
+        rule,
+        expected,
+        found,
+      } => write!(
+        f,
+        "rule {} expects {} generic argument(s), got {}",
+        rule, expected, found
+      ),
+      Error::Recursive(rule) => write!(
+        f,
+        "generic rule {} is recursive and cannot be instantiated",
+        rule
+      ),
+      Error::Unsupported(reason) => write!(f, "{}", reason),
+      Error::Bound {
+        rule,
+        param,
+        bound,
+        arg,
+      } => write!(
+        f,
+        "argument {} of generic parameter {} of rule {} does not satisfy bound {}",
+        arg, param, rule, bound
+      ),
+      Error::InvalidBound { rule, bound } => write!(
+        f,
+        "rule {} has no generic parameter matching bound {}",
+        rule, bound
+      ),
+    }
+  }
+}
+
+impl std::error::Error for Error {}
+
+/// Bound constraining the arguments given for a generic parameter, attached to
+/// a generic rule via a `;@bound` annotation
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenericBound {
+  /// Generic parameter name
+  pub param: String,
+  /// CDDL type every argument given for the parameter must conform to
+  pub bound: String,
+}
+
+impl fmt::Display for GenericBound {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}: {}", self.param, self.bound)
+  }
+}
+
+impl GenericBound {
+  /// Parse the bound as a CDDL type
+  pub fn bound_type(&self) -> std::result::Result<Type<'_>, parser::Error> {
+    parser::Parser::new(&self.bound, Box::new(lexer_from_str(&self.bound).iter()))?.parse_type(None)
+  }
+}
+
+/// Bounds of generic parameters, keyed by rule name
+pub type GenericBounds = BTreeMap<String, Vec<GenericBound>>;
+
+/// Collect the bounds attached to generic rules via `;@bound` annotations in a
+/// given CDDL document string. Each annotation bounds a single generic
+/// parameter by a CDDL type
+///
+/// ```cddl
+/// ;@bound K: tstr / int
+/// ;@bound V: any
+/// table<K, V> = { * K => V }
+/// ```
+pub fn generic_bounds_from_str(cddl: &str) -> std::result::Result<GenericBounds, parser::Error> {
+  let rule_comments = RuleComments::from_cddl(cddl).map_err(parser::Error::LEXER)?;
+
+  let mut generic_bounds = GenericBounds::new();
+  for (rule, _) in rule_comments.iter() {
+    for annotation in rule_comments.annotations(rule) {
+      if annotation.name != "bound" {
+        continue;
+      }
+
+      let generic_bound = match annotation.args.split_once(':') {
+        Some((param, bound)) if !param.trim().is_empty() && !bound.trim().is_empty() => {
+          GenericBound {
+            param: param.trim().to_string(),
+            bound: bound.trim().to_string(),
+          }
+        }
+        _ => {
+          return Err(parser::Error::CDDL(format!(
+            "invalid bound annotation {} of rule {}, expected @bound <param>: <type>",
+            annotation, rule
+          )))
+        }
+      };
+
+      generic_bound.bound_type()?;
+
+      generic_bounds
+        .entry(rule.to_string())
+        .or_default()
+        .push(generic_bound);
+    }
+  }
+
+  Ok(generic_bounds)
+}
+
+impl<'a> CDDL<'a> {
+  /// Monomorphize the document, returning a new document in which every use of
+  /// a generic rule is replaced by the rule's definition with its generic
+  /// parameters substituted by the given arguments. Generic rule definitions
+  /// are not included in the returned document
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use cddl::cddl_from_str;
+  ///
+  /// let cddl = cddl_from_str(
+  ///   "message = envelope<tstr>\nenvelope<t> = { id: uint, body: t }",
+  ///   true,
+  /// )
+  /// .unwrap();
+  ///
+  /// let resolved = cddl.resolve_generics().unwrap();
+  /// assert_eq!(resolved.rules.len(), 1);
+  /// assert_eq!(
+  ///   resolved.rules[0].to_string().trim(),
+  ///   "message = { id: uint, body: tstr }"
+  /// );
+  /// ```
+  pub fn resolve_generics(&self) -> Result<CDDL<'a>> {
+    self.resolve_generics_with_bounds(&GenericBounds::new())
+  }
+
+  /// Monomorphize the document as with [`CDDL::resolve_generics`], checking
+  /// that the arguments of each instantiation satisfy the bounds of the generic
+  /// rule's parameters. An argument satisfies a bound if every value conforming
+  /// to the argument also conforms to the bound, e.g. `1..10` satisfies `uint`
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use cddl::{cddl_from_str, generics::generic_bounds_from_str};
+  ///
+  /// let input = "a = list<tstr>\nlist<T> = [* T] ;@bound T: uint";
+  /// let bounds = generic_bounds_from_str(input).unwrap();
+  /// let cddl = cddl_from_str(input, true).unwrap();
+  ///
+  /// assert_eq!(
+  ///   cddl.resolve_generics_with_bounds(&bounds).unwrap_err().to_string(),
+  ///   "argument tstr of generic parameter T of rule list does not satisfy bound uint"
+  /// );
+  /// ```
+  pub fn resolve_generics_with_bounds(&self, bounds: &GenericBounds) -> Result<CDDL<'a>> {
+    let mut resolver = Resolver {
+      cddl: self,
+      bounds,
+      instantiating: Vec::new(),
+    };
+
+    let mut rules = Vec::with_capacity(self.rules.len());
+    for rule in self.rules.iter() {
+      let mut rule = rule.clone();
+      match &mut rule {
+        Rule::Type { rule, .. } => {
+          if rule.generic_params.is_some() {
+            continue;
+          }
+
+          resolver.type_(&mut rule.value, &[])?;
+        }
+        Rule::Group { rule, .. } => {
+          if rule.generic_params.is_some() {
+            continue;
+          }
+
+          resolver.group_entry(&mut rule.entry, &[])?;
+        }
+      }
+
+      rules.push(rule);
+    }
+
+    Ok(CDDL {
+      rules,
+      #[cfg(feature = "ast-comments")]
+      comments: self.comments.clone(),
+    })
+  }
+}
+
+// Generic parameters in scope and the arguments bound to them. Arguments are
+// resolved in the scope of the use site before being bound, so they are
+// substituted verbatim
+type Bindings<'a> = [(Cow<'a, str>, Type1<'a>)];
+
+// Instantiated generic rule
+enum Instance<'a> {
+  Type(Type<'a>),
+  Group(Group<'a>),
+}
+
+struct Resolver<'a, 'b> {
+  cddl: &'b CDDL<'a>,
+  bounds: &'b GenericBounds,
+  // Generic rules currently being instantiated, used to detect recursion
+  instantiating: Vec<Cow<'a, str>>,
+}
+
+impl<'a, 'b> Resolver<'a, 'b> {
+  fn instantiate(
+    &mut self,
+    ident: &Identifier<'a>,
+    args: &mut GenericArgs<'a>,
+    bindings: &Bindings<'a>,
+  ) -> Result<Instance<'a>> {
+    for arg in args.args.iter_mut() {
+      self.type1(&mut arg.arg, bindings)?;
+    }
+
+    if self.instantiating.contains(&ident.ident) {
+      return Err(Error::Recursive(ident.ident.to_string()));
+    }
+
+    let definitions = self
+      .cddl
+      .rules
+      .iter()
+      .filter(|r| r.name() == ident.ident)
+      .collect::<Vec<_>>();
+    if definitions.is_empty() {
+      return Err(Error::MissingRule(ident.ident.to_string()));
+    }
+
+    self.instantiating.push(ident.ident.clone());
+
+    let mut instance: Option<Instance<'a>> = None;
+    for definition in definitions {
+      let rule_bindings = match definition {
+        Rule::Type { rule, .. } => bind(ident, rule.generic_params.as_ref(), args)?,
+        Rule::Group { rule, .. } => bind(ident, rule.generic_params.as_ref(), args)?,
+      };
+      self.check_bounds(ident, &rule_bindings)?;
+
+      // Rules extended with additional choices (i.e. `/=` or `//=`) are
+      // instantiated as the union of the choices of each definition
+      match (definition, &mut instance) {
+        (Rule::Type { rule, .. }, None) => {
+          let mut t = rule.value.clone();
+          self.type_(&mut t, &rule_bindings)?;
+          instance = Some(Instance::Type(t));
+        }
+        (Rule::Type { rule, .. }, Some(Instance::Type(t))) => {
+          let mut choice = rule.value.clone();
+          self.type_(&mut choice, &rule_bindings)?;
+          t.type_choices.append(&mut choice.type_choices);
+        }
+        (Rule::Group { rule, .. }, None) => {
+          let mut entry = rule.entry.clone();
+          self.group_entry(&mut entry, &rule_bindings)?;
+          instance = Some(Instance::Group(group_from_entry(entry)));
+        }
+        (Rule::Group { rule, .. }, Some(Instance::Group(g))) => {
+          let mut entry = rule.entry.clone();
+          self.group_entry(&mut entry, &rule_bindings)?;
+          g.group_choices
+            .append(&mut group_from_entry(entry).group_choices);
+        }
+        _ => {
+          return Err(Error::Unsupported(format!(
+            "rule {} is defined as both a type and a group",
+            ident
+          )))
+        }
+      }
+    }
+
+    self.instantiating.pop();
+
+    // At least one definition exists, so an instance has been created
+    Ok(instance.unwrap())
+  }
+
+  fn check_bounds(&self, ident: &Identifier<'a>, bindings: &Bindings<'a>) -> Result<()> {
+    let bounds = match self.bounds.get(ident.ident.as_ref()) {
+      Some(bounds) => bounds,
+      None => return Ok(()),
+    };
+
+    for generic_bound in bounds.iter() {
+      let arg = bindings
+        .iter()
+        .find(|(p, _)| *p == generic_bound.param)
+        .map(|(_, a)| a)
+        .ok_or_else(|| Error::InvalidBound {
+          rule: ident.ident.to_string(),
+          bound: generic_bound.to_string(),
+        })?;
+
+      // Bounds are parsed when collected, so a bound that fails to parse here
+      // was constructed by hand
+      let bound = generic_bound
+        .bound_type()
+        .map_err(|e| Error::Unsupported(e.to_string()))?;
+
+      if !type1_satisfies(self.cddl, arg, &bound, 0) {
+        return Err(Error::Bound {
+          rule: ident.ident.to_string(),
+          param: generic_bound.param.clone(),
+          bound: generic_bound.bound.clone(),
+          arg: arg.to_string(),
+        });
+      }
+    }
+
+    Ok(())
+  }
+
+  fn type_(&mut self, t: &mut Type<'a>, bindings: &Bindings<'a>) -> Result<()> {
+    for tc in t.type_choices.iter_mut() {
+      self.type1(&mut tc.type1, bindings)?;
+    }
+
+    Ok(())
+  }
+
+  fn type1(&mut self, t1: &mut Type1<'a>, bindings: &Bindings<'a>) -> Result<()> {
+    // A parameter that makes up an entire type expression is replaced by its
+    // argument as is, preserving any operator of the argument
+    if t1.operator.is_none() {
+      if let Some(arg) = bound_arg(&t1.type2, bindings) {
+        *t1 = arg.clone();
+        return Ok(());
+      }
+    }
+
+    self.type2(&mut t1.type2, bindings)?;
+    if let Some(o) = &mut t1.operator {
+      self.type2(&mut o.type2, bindings)?;
+    }
+
+    Ok(())
+  }
+
+  fn type2(&mut self, t2: &mut Type2<'a>, bindings: &Bindings<'a>) -> Result<()> {
+    if let Some(arg) = bound_arg(t2, bindings) {
+      *t2 = type2_from_type1(arg.clone());
+      return Ok(());
+    }
+
+    match t2 {
+      Type2::Typename {
+        ident,
+        generic_args: Some(ga),
+        ..
+      } => match self.instantiate(ident, ga, bindings)? {
+        Instance::Type(t) => *t2 = type2_from_type(t),
+        Instance::Group(_) => {
+          return Err(Error::Unsupported(format!(
+            "group rule {} cannot be used as a type",
+            ident
+          )))
+        }
+      },
+      Type2::ChoiceFromGroup {
+        ident,
+        generic_args,
+        #[cfg(feature = "ast-span")]
+        span,
+        ..
+      } => {
+        if let Some(arg) = bindings
+          .iter()
+          .find(|(p, _)| *p == ident.ident)
+          .map(|(_, a)| a)
+        {
+          let (name, args) = typename_from_type1(arg).ok_or_else(|| {
+            Error::Unsupported(format!(
+              "argument {} of parameter {} cannot be used as a group name",
+              arg, ident
+            ))
+          })?;
+          *ident = name;
+          *generic_args = args;
+        } else if let Some(ga) = generic_args {
+          match self.instantiate(ident, ga, bindings)? {
+            Instance::Group(group) => {
+              *t2 = Type2::ChoiceFromInlineGroup {
+                group,
+                #[cfg(feature = "ast-span")]
+                span: *span,
+                #[cfg(feature = "ast-comments")]
+                comments: None,
+                #[cfg(feature = "ast-comments")]
+                comments_before_group: None,
+                #[cfg(feature = "ast-comments")]
+                comments_after_group: None,
+              }
+            }
+            Instance::Type(_) => {
+              return Err(Error::Unsupported(format!(
+                "type rule {} cannot be used as a group",
+                ident
+              )))
+            }
+          }
+        }
+      }
+      Type2::Unwrap {
+        ident,
+        generic_args,
+        ..
+      } => {
+        if let Some(arg) = bindings
+          .iter()
+          .find(|(p, _)| *p == ident.ident)
+          .map(|(_, a)| a)
+        {
+          let (name, args) = typename_from_type1(arg).ok_or_else(|| {
+            Error::Unsupported(format!(
+              "argument {} of parameter {} cannot be unwrapped",
+              arg, ident
+            ))
+          })?;
+          *ident = name;
+          *generic_args = args;
+        } else if let Some(ga) = generic_args {
+          let ident = ident.clone();
+          match self.instantiate(&ident, ga, bindings)? {
+            Instance::Type(t) => match single_type2(t) {
+              Ok(Type2::TaggedData { t, .. }) => *t2 = type2_from_type(t),
+              _ => {
+                return Err(Error::Unsupported(format!(
+                  "unwrapped generic rule {} can only be instantiated inline as a group entry",
+                  ident
+                )))
+              }
+            },
+            Instance::Group(_) => {
+              return Err(Error::Unsupported(format!(
+                "group rule {} cannot be unwrapped",
+                ident
+              )))
+            }
+          }
+        }
+      }
+      Type2::ParenthesizedType { pt, .. } => self.type_(pt, bindings)?,
+      Type2::TaggedData { tag_type, t, .. } => {
+        if let Some(tag_type) = tag_type {
+          self.type_(tag_type, bindings)?;
+        }
+        self.type_(t, bindings)?
+      }
+      Type2::Map { group, .. }
+      | Type2::Array { group, .. }
+      | Type2::ChoiceFromInlineGroup { group, .. } => self.group(group, bindings)?,
+      _ => {}
+    }
+
+    Ok(())
+  }
+
+  fn group(&mut self, g: &mut Group<'a>, bindings: &Bindings<'a>) -> Result<()> {
+    for gc in g.group_choices.iter_mut() {
+      for (ge, _) in gc.group_entries.iter_mut() {
+        self.group_entry(ge, bindings)?;
+      }
+    }
+
+    Ok(())
+  }
+
+  fn group_entry(&mut self, entry: &mut GroupEntry<'a>, bindings: &Bindings<'a>) -> Result<()> {
+    #[cfg(feature = "ast-span")]
+    let span = entry.span();
+
+    match entry {
+      GroupEntry::ValueMemberKey { ge, .. } => {
+        // An unwrapped generic map or array entry contributes the entries of
+        // its group
+        if ge.member_key.is_none() && ge.entry_type.type_choices.len() == 1 {
+          if let Type2::Unwrap {
+            ident,
+            generic_args: Some(ga),
+            ..
+          } = &mut ge.entry_type.type_choices[0].type1.type2
+          {
+            let ident = ident.clone();
+            if let Instance::Type(t) = self.instantiate(&ident, ga, bindings)? {
+              if let Ok(Type2::Map { group, .. }) | Ok(Type2::Array { group, .. }) = single_type2(t)
+              {
+                *entry = GroupEntry::InlineGroup {
+                  occur: ge.occur.clone(),
+                  group,
+                  #[cfg(feature = "ast-span")]
+                  span,
+                  #[cfg(feature = "ast-comments")]
+                  comments_before_group: None,
+                  #[cfg(feature = "ast-comments")]
+                  comments_after_group: None,
+                };
+
+                return Ok(());
+              }
+            }
+          }
+        }
+
+        match &mut ge.member_key {
+          Some(MemberKey::Type1 { t1, .. }) => self.type1(t1, bindings)?,
+          Some(MemberKey::NonMemberKey {
+            non_member_key: NonMemberKey::Type(t),
+            ..
+          }) => self.type_(t, bindings)?,
+          Some(MemberKey::NonMemberKey {
+            non_member_key: NonMemberKey::Group(g),
+            ..
+          }) => self.group(g, bindings)?,
+          _ => {}
+        }
+
+        self.type_(&mut ge.entry_type, bindings)
+      }
+      GroupEntry::TypeGroupname { ge, .. } => {
+        let instance = if let Some(arg) = bindings
+          .iter()
+          .find(|(p, _)| *p == ge.name.ident)
+          .map(|(_, a)| a)
+        {
+          if let Some((name, args)) = typename_from_type1(arg) {
+            ge.name = name;
+            ge.generic_args = args;
+            return Ok(());
+          }
+
+          Instance::Type(type_from_type1(arg.clone()))
+        } else if let Some(ga) = &mut ge.generic_args {
+          let name = ge.name.clone();
+          self.instantiate(&name, ga, bindings)?
+        } else {
+          return Ok(());
+        };
+
+        let occur = ge.occur.clone();
+        *entry = match instance {
+          Instance::Type(entry_type) => GroupEntry::ValueMemberKey {
+            ge: Box::new(ValueMemberKeyEntry {
+              occur,
+              member_key: None,
+              entry_type,
+            }),
+            #[cfg(feature = "ast-span")]
+            span,
+            #[cfg(feature = "ast-comments")]
+            leading_comments: None,
+            #[cfg(feature = "ast-comments")]
+            trailing_comments: None,
+          },
+          Instance::Group(group) => GroupEntry::InlineGroup {
+            occur,
+            group,
+            #[cfg(feature = "ast-span")]
+            span,
+            #[cfg(feature = "ast-comments")]
+            comments_before_group: None,
+            #[cfg(feature = "ast-comments")]
+            comments_after_group: None,
+          },
+        };
+
+        Ok(())
+      }
+      GroupEntry::InlineGroup { group, .. } => self.group(group, bindings),
+    }
+  }
+}
+
+// Number of type and group entry nodes of the document. If expanded, the
+// number of nodes of the document returned by `CDDL::resolve_generics` is
+// counted instead, without instantiating any generic rules. Counting stops as
+// soon as the count exceeds the limit, so documents whose instantiation blows
+// up exponentially are sized in time proportional to the limit
+pub(crate) fn document_size(cddl: &CDDL, expand: bool, limit: usize) -> usize {
+  let mut sizer = Sizer {
+    cddl,
+    expand,
+    limit,
+    size: 0,
+    scopes: vec![Scope {
+      rule: None,
+      parent: 0,
+      bindings: Vec::new(),
+    }],
+  };
+
+  for rule in cddl.rules.iter() {
+    match rule {
+      Rule::Type { rule, .. } if !expand || rule.generic_params.is_none() => {
+        sizer.type_(&rule.value, 0)
+      }
+      Rule::Group { rule, .. } if !expand || rule.generic_params.is_none() => {
+        sizer.group_entry(&rule.entry, 0)
+      }
+      _ => {}
+    }
+  }
+
+  sizer.size
+}
+
+// Instantiation of a generic rule, binding its parameters to the arguments of
+// the use site along with the scope the arguments are counted in
+struct Scope<'c, 'a> {
+  rule: Option<Cow<'a, str>>,
+  parent: usize,
+  bindings: Vec<(Cow<'a, str>, &'c Type1<'a>, usize)>,
+}
+
+struct Sizer<'c, 'a> {
+  cddl: &'c CDDL<'a>,
+  expand: bool,
+  limit: usize,
+  size: usize,
+  scopes: Vec<Scope<'c, 'a>>,
+}
+
+impl<'c, 'a> Sizer<'c, 'a> {
+  fn type_(&mut self, t: &'c Type<'a>, scope: usize) {
+    for tc in t.type_choices.iter() {
+      self.type1(&tc.type1, scope);
+    }
+  }
+
+  fn type1(&mut self, t1: &'c Type1<'a>, scope: usize) {
+    self.type2(&t1.type2, scope);
+    if let Some(o) = &t1.operator {
+      self.type2(&o.type2, scope);
+    }
+  }
+
+  fn type2(&mut self, t2: &'c Type2<'a>, scope: usize) {
+    if self.size > self.limit {
+      return;
+    }
+
+    match t2 {
+      Type2::Typename {
+        ident,
+        generic_args,
+        ..
+      }
+      | Type2::ChoiceFromGroup {
+        ident,
+        generic_args,
+        ..
+      }
+      | Type2::Unwrap {
+        ident,
+        generic_args,
+        ..
+      } => self.name(ident, generic_args.as_ref(), scope),
+      Type2::ParenthesizedType { pt: t, .. }
+      | Type2::TaggedData {
+        tag_type: None, t, ..
+      } => {
+        self.size += 1;
+        self.type_(t, scope);
+      }
+      Type2::TaggedData {
+        tag_type: Some(tag_type),
+        t,
+        ..
+      } => {
+        self.size += 1;
+        self.type_(tag_type, scope);
+        self.type_(t, scope);
+      }
+      Type2::Map { group, .. }
+      | Type2::Array { group, .. }
+      | Type2::ChoiceFromInlineGroup { group, .. } => {
+        self.size += 1;
+        self.group(group, scope);
+      }
+      _ => self.size += 1,
+    }
+  }
+
+  fn group(&mut self, g: &'c Group<'a>, scope: usize) {
+    for gc in g.group_choices.iter() {
+      for (ge, _) in gc.group_entries.iter() {
+        self.group_entry(ge, scope);
+      }
+    }
+  }
+
+  fn group_entry(&mut self, entry: &'c GroupEntry<'a>, scope: usize) {
+    if self.size > self.limit {
+      return;
+    }
+
+    match entry {
+      GroupEntry::ValueMemberKey { ge, .. } => {
+        self.size += 1;
+        match &ge.member_key {
+          Some(MemberKey::Type1 { t1, .. }) => self.type1(t1, scope),
+          Some(MemberKey::NonMemberKey {
+            non_member_key: NonMemberKey::Type(t),
+            ..
+          }) => self.type_(t, scope),
+          Some(MemberKey::NonMemberKey {
+            non_member_key: NonMemberKey::Group(g),
+            ..
+          }) => self.group(g, scope),
+          _ => {}
+        }
+        self.type_(&ge.entry_type, scope);
+      }
+      GroupEntry::TypeGroupname { ge, .. } => self.name(&ge.name, ge.generic_args.as_ref(), scope),
+      GroupEntry::InlineGroup { group, .. } => {
+        self.size += 1;
+        self.group(group, scope);
+      }
+    }
+  }
+
+  // A type or group name, which is replaced by the argument bound to it or by
+  // the instantiation of the generic rule it refers to
+  fn name(
+    &mut self,
+    ident: &'c Identifier<'a>,
+    generic_args: Option<&'c GenericArgs<'a>>,
+    scope: usize,
+  ) {
+    // Recursive instantiations aren't expanded, as they can't be resolved
+    if !self.expand || self.instantiating(&ident.ident, scope) {
+      self.size += 1;
+      for arg in generic_args.iter().flat_map(|ga| ga.args.iter()) {
+        self.type1(&arg.arg, scope);
+      }
+
+      return;
+    }
+
+    let args = match generic_args {
+      Some(ga) => ga,
+      None => {
+        match self.scopes[scope]
+          .bindings
+          .iter()
+          .find(|(p, ..)| *p == ident.ident)
+          .map(|(_, arg, arg_scope)| (*arg, *arg_scope))
+        {
+          Some((arg, arg_scope)) => self.type1(arg, arg_scope),
+          None => self.size += 1,
+        }
+
+        return;
+      }
+    };
+
+    let cddl = self.cddl;
+    for rule in cddl.rules.iter().filter(|r| r.name() == ident.ident) {
+      let params = match rule {
+        Rule::Type { rule, .. } => rule.generic_params.as_ref(),
+        Rule::Group { rule, .. } => rule.generic_params.as_ref(),
+      };
+      let bindings = params
+        .iter()
+        .flat_map(|gp| gp.params.iter())
+        .zip(args.args.iter())
+        .map(|(p, a)| (p.param.ident.clone(), &*a.arg, scope))
+        .collect();
+
+      self.scopes.push(Scope {
+        rule: Some(ident.ident.clone()),
+        parent: scope,
+        bindings,
+      });
+      let rule_scope = self.scopes.len() - 1;
+      match rule {
+        Rule::Type { rule, .. } => self.type_(&rule.value, rule_scope),
+        Rule::Group { rule, .. } => self.group_entry(&rule.entry, rule_scope),
+      }
+      self.scopes.pop();
+    }
+  }
+
+  fn instantiating(&self, rule: &str, mut scope: usize) -> bool {
+    while scope != 0 {
+      if self.scopes[scope].rule.as_deref() == Some(rule) {
+        return true;
+      }
+      scope = self.scopes[scope].parent;
+    }
+
+    false
+  }
+}
+
+// Whether every value conforming to the argument also conforms to the bound.
+// The check is conservative, i.e. arguments that can't be shown to conform to
+// the bound don't satisfy it
+fn type1_satisfies(cddl: &CDDL, arg: &Type1, bound: &Type, depth: usize) -> bool {
+  if depth > MAX_BOUND_DEPTH {
+    return false;
+  }
+
+  if bound
+    .type_choices
+    .iter()
+    .any(|tc| tc.type1.to_string() == arg.to_string())
+  {
+    return true;
+  }
+
+  match &arg.operator {
+    // A range satisfies the bound if both of its ends do
+    Some(Operator {
+      operator: RangeCtlOp::RangeOp { .. },
+      type2,
+      ..
+    }) => {
+      type2_satisfies(cddl, &arg.type2, bound, depth) && type2_satisfies(cddl, type2, bound, depth)
+    }
+    // A control operator only narrows the type it is applied to
+    Some(Operator {
+      operator: RangeCtlOp::CtlOp { .. },
+      ..
+    })
+    | None => type2_satisfies(cddl, &arg.type2, bound, depth),
+  }
+}
+
+fn type2_satisfies(cddl: &CDDL, arg: &Type2, bound: &Type, depth: usize) -> bool {
+  match arg {
+    Type2::ParenthesizedType { pt, .. } => pt
+      .type_choices
+      .iter()
+      .all(|tc| type1_satisfies(cddl, &tc.type1, bound, depth + 1)),
+    Type2::Typename {
+      ident,
+      generic_args: None,
+      ..
+    } if cddl.rules.iter().any(|r| r.name() == ident.ident) => {
+      // Every choice of every definition of the rule must satisfy the bound
+      cddl
+        .rules
+        .iter()
+        .filter(|r| r.name() == ident.ident)
+        .all(|r| match r {
+          Rule::Type { rule, .. } => rule
+            .value
+            .type_choices
+            .iter()
+            .all(|tc| type1_satisfies(cddl, &tc.type1, bound, depth + 1)),
+          Rule::Group { .. } => false,
+        })
+    }
+    _ => bound
+      .type_choices
+      .iter()
+      .any(|tc| choice_accepts(cddl, &tc.type1, arg, depth)),
+  }
+}
+
+// Whether a choice of a bound accepts every value of the argument
+fn choice_accepts(cddl: &CDDL, choice: &Type1, arg: &Type2, depth: usize) -> bool {
+  match (&choice.type2, &choice.operator) {
+    (_, None) if choice.type2.to_string() == arg.to_string() => true,
+    (
+      Type2::Typename {
+        ident,
+        generic_args: None,
+        ..
+      },
+      None,
+    ) => {
+      if cddl.rules.iter().any(|r| r.name() == ident.ident) {
+        return cddl.rules.iter().any(|r| match r {
+          Rule::Type { rule, .. } if rule.name.ident == ident.ident => {
+            type2_satisfies(cddl, arg, &rule.value, depth + 1)
+          }
+          _ => false,
+        });
+      }
+
+      match arg {
+        Type2::Typename { ident: arg, .. } => is_prelude_subtype(&arg.ident, &ident.ident),
+        _ => ident.ident == "any" || prelude_accepts_value(&ident.ident, arg),
+      }
+    }
+    (
+      lower,
+      Some(Operator {
+        operator: RangeCtlOp::RangeOp { is_inclusive, .. },
+        type2: upper,
+        ..
+      }),
+    ) => match (
+      numeric_value(lower),
+      numeric_value(upper),
+      numeric_value(arg),
+    ) {
+      (Some(lower), Some(upper), Some(value)) => {
+        value >= lower && (value < upper || (*is_inclusive && value == upper))
+      }
+      _ => false,
+    },
+    _ => false,
+  }
+}
+
+// Whether every value of the standard prelude type `sub` is also a value of the
+// standard prelude type `sup`
+fn is_prelude_subtype(sub: &str, sup: &str) -> bool {
+  let canonical = |name| match name {
+    "text" => "tstr",
+    "bytes" => "bstr",
+    "null" => "nil",
+    name => name,
+  };
+  let (sub, sup) = (canonical(sub), canonical(sup));
+
+  sub == sup
+    || match sup {
+      "any" => true,
+      "int" => matches!(sub, "uint" | "nint"),
+      "integer" => matches!(
+        sub,
+        "int" | "uint" | "nint" | "bigint" | "biguint" | "bignint" | "unsigned"
+      ),
+      "unsigned" => matches!(sub, "uint" | "biguint"),
+      "bigint" => matches!(sub, "biguint" | "bignint"),
+      "number" => matches!(
+        sub,
+        "int"
+          | "uint"
+          | "nint"
+          | "float"
+          | "float16"
+          | "float32"
+          | "float64"
+          | "float16-32"
+          | "float32-64"
+      ),
+      "float" => matches!(
+        sub,
+        "float16" | "float32" | "float64" | "float16-32" | "float32-64"
+      ),
+      "float16-32" => matches!(sub, "float16" | "float32"),
+      "float32-64" => matches!(sub, "float32" | "float64"),
+      "bool" => matches!(sub, "true" | "false"),
+      _ => false,
+    }
+}
+
+// Whether the literal value is a value of the standard prelude type
+fn prelude_accepts_value(prelude: &str, value: &Type2) -> bool {
+  match value {
+    Type2::UintValue { .. } => {
+      matches!(prelude, "uint" | "int" | "integer" | "unsigned" | "number")
+    }
+    Type2::IntValue { value, .. } if *value >= 0 => {
+      matches!(prelude, "uint" | "int" | "integer" | "unsigned" | "number")
+    }
+    Type2::IntValue { .. } => matches!(prelude, "nint" | "int" | "integer" | "number"),
+    Type2::FloatValue { .. } => matches!(
+      prelude,
+      "float" | "float16" | "float32" | "float64" | "float16-32" | "float32-64" | "number"
+    ),
+    Type2::TextValue { .. } => matches!(prelude, "tstr" | "text"),
+    Type2::UTF8ByteString { .. } | Type2::B16ByteString { .. } | Type2::B64ByteString { .. } => {
+      matches!(prelude, "bstr" | "bytes")
+    }
+    _ => false,
+  }
+}
+
+fn numeric_value(t2: &Type2) -> Option<f64> {
+  match t2 {
+    Type2::IntValue { value, .. } => Some(*value as f64),
+    Type2::UintValue { value, .. } => Some(*value as f64),
+    Type2::FloatValue { value, .. } => Some(*value),
+    _ => None,
+  }
+}
+
+// Bind the generic arguments to the generic parameters of a rule
+fn bind<'a>(
+  ident: &Identifier<'a>,
+  params: Option<&GenericParams<'a>>,
+  args: &GenericArgs<'a>,
+) -> Result<Vec<(Cow<'a, str>, Type1<'a>)>> {
+  let params = params.map(|gp| &gp.params[..]).unwrap_or_default();
+  if params.len() != args.args.len() {
+    return Err(Error::ArgumentCount {
+      rule: ident.ident.to_string(),
+      expected: params.len(),
+      found: args.args.len(),
+    });
+  }
+
+  Ok(
+    params
+      .iter()
+      .zip(args.args.iter())
+      .map(|(p, a)| (p.param.ident.clone(), (*a.arg).clone()))
+      .collect(),
+  )
+}
+
+// Argument bound to the given type if it is a reference to a generic parameter
+fn bound_arg<'a, 'c>(t2: &Type2<'a>, bindings: &'c Bindings<'a>) -> Option<&'c Type1<'a>> {
+  if let Type2::Typename {
+    ident,
+    generic_args: None,
+    ..
+  } = t2
+  {
+    return bindings
+      .iter()
+      .find(|(p, _)| *p == ident.ident)
+      .map(|(_, a)| a);
+  }
+
+  None
+}
+
+// Name and generic arguments of an argument that is a plain type or group name
+fn typename_from_type1<'a>(t1: &Type1<'a>) -> Option<(Identifier<'a>, Option<GenericArgs<'a>>)> {
+  match t1 {
+    Type1 {
+      type2: Type2::Typename {
+        ident,
+        generic_args,
+        ..
+      },
+      operator: None,
+      ..
+    } => Some((ident.clone(), generic_args.clone())),
+    _ => None,
+  }
+}
+
+// Sole type2 of a type without choices or operators
+fn single_type2<'a>(mut t: Type<'a>) -> std::result::Result<Type2<'a>, Type<'a>> {
+  if t.type_choices.len() == 1 && t.type_choices[0].type1.operator.is_none() {
+    return Ok(t.type_choices.remove(0).type1.type2);
+  }
+
+  Err(t)
+}
+
+fn type2_from_type1(t1: Type1<'_>) -> Type2<'_> {
+  if t1.operator.is_none() {
+    return t1.type2;
+  }
+
+  Type2::from(t1)
+}
+
+fn type2_from_type(t: Type<'_>) -> Type2<'_> {
+  match single_type2(t) {
+    Ok(t2) => t2,
+    Err(t) => Type2::ParenthesizedType {
+      #[cfg(feature = "ast-span")]
+      span: t.span,
+      pt: t,
+      #[cfg(feature = "ast-comments")]
+      comments_before_type: None,
+      #[cfg(feature = "ast-comments")]
+      comments_after_type: None,
+    },
+  }
+}
+
+fn type_from_type1(t1: Type1<'_>) -> Type<'_> {
+  Type {
+    #[cfg(feature = "ast-span")]
+    span: t1.span,
+    type_choices: vec![TypeChoice {
+      type1: t1,
+      #[cfg(feature = "ast-comments")]
+      comments_before_type: None,
+      #[cfg(feature = "ast-comments")]
+      comments_after_type: None,
+    }],
+  }
+}
+
+// Group rules whose entry is a parenthesized group (e.g. `a<t> = (b: t // c:
+// t)`) contribute the choices of the group, other entries form a group of
+// their own
+fn group_from_entry(entry: GroupEntry<'_>) -> Group<'_> {
+  match entry {
+    GroupEntry::InlineGroup {
+      occur: None, group, ..
+    } => group,
+    entry => {
+      #[cfg(feature = "ast-span")]
+      let span = entry.span();
+
+      #[cfg_attr(not(feature = "ast-span"), allow(unused_mut))]
+      let mut gc = GroupChoice::new(vec![entry]);
+      #[cfg(feature = "ast-span")]
+      {
+        gc.span = span;
+      }
+
+      Group {
+        group_choices: vec![gc],
+        #[cfg(feature = "ast-span")]
+        span,
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cddl_from_str;
+
+  fn resolve(input: &str) -> Result<String> {
+    let cddl = cddl_from_str(input, true).unwrap();
+    let resolved = cddl.resolve_generics()?;
+
+    Ok(
+      resolved
+        .rules
+        .iter()
+        .map(|r| r.to_string().trim().to_string())
+        .collect::<Vec<_>>()
+        .join("\n"),
+    )
+  }
+
+  #[test]
+  fn verify_resolve_generics() -> Result<()> {
+    assert_eq!(
+      resolve("message = envelope<tstr, 1..10>\nenvelope<t, n> = { body: t, count: n }")?,
+      resolve("message = { body: tstr, count: 1..10 }")?
+    );
+
+    // Nested instantiations are resolved in the scope of the use site
+    assert_eq!(
+      resolve("a = pair<uint>\npair<t> = [t, list<t>]\nlist<t> = [* t]")?,
+      resolve("a = [uint, [* uint]]")?
+    );
+
+    // Group rules are instantiated inline as group entries
+    assert_eq!(
+      resolve("rect = { interval<0>, interval<1> }\ninterval<b> = (\"test\" => b)")?,
+      resolve("rect = { (\"test\" => 0), (\"test\" => 1) }")?
+    );
+
+    // Unwrapped and enumerated instantiations contribute their groups
+    assert_eq!(
+      resolve("a = { ~base<uint>, c: tstr }\nbase<t> = { b: t }")?,
+      resolve("a = { (b: uint), c: tstr }")?
+    );
+    assert_eq!(
+      resolve("a = &colors<1>\ncolors<v> = (red: v, blue: 2)")?,
+      resolve("a = &(red: 1, blue: 2)")?
+    );
+
+    // Arguments with operators are kept intact
+    assert_eq!(
+      resolve("a = wrap<uint .size 2>\nwrap<t> = [t]")?,
+      resolve("a = [uint .size 2]")?
+    );
+
+    assert_eq!(
+      resolve("a = list<int>\nlist<t> = [* list<t>]"),
+      Err(Error::Recursive("list".to_string()))
+    );
+    assert_eq!(
+      resolve("a = pair<int>\npair<k, v> = [k, v]"),
+      Err(Error::ArgumentCount {
+        rule: "pair".to_string(),
+        expected: 2,
+        found: 1
+      })
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_resolve_nested_generics() -> Result<()> {
+    // Instantiations given as arguments are resolved before being bound
+    assert_eq!(
+      resolve("a = dict<list<uint>>\ndict<v> = { * tstr => v }\nlist<t> = [* t]")?,
+      resolve("a = { * tstr => [* uint] }")?
+    );
+    assert_eq!(
+      resolve("a = pair<pair<uint, tstr>, bool>\npair<k, v> = [k, v]")?,
+      resolve("a = [[uint, tstr], bool]")?
+    );
+
+    // Generic parameters shadow rules of the same name, which remain defined
+    assert_eq!(
+      resolve("a = wrap<uint>\nwrap<t> = [t, b]\nt = tstr\nb = t")?,
+      resolve("a = [uint, b]\nt = tstr\nb = t")?
+    );
+    assert_eq!(
+      resolve("a = wrap<tstr>\nwrap<list> = [list]\nlist<t> = [* t]")?,
+      resolve("a = [tstr]")?
+    );
+
+    // Parameters of the enclosing rule are bound in nested instantiations
+    assert_eq!(
+      resolve("a = outer<int>\nouter<t> = { inner: inner<t, t> }\ninner<x, y> = [x, y]")?,
+      resolve("a = { inner: [int, int] }")?
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_resolve_generics_errors() -> Result<()> {
+    assert_eq!(
+      resolve("a = pair<int, tstr, bool>\npair<k, v> = [k, v]"),
+      Err(Error::ArgumentCount {
+        rule: "pair".to_string(),
+        expected: 2,
+        found: 3
+      })
+    );
+    assert_eq!(
+      resolve("a = b<int>\nb = [* tstr]"),
+      Err(Error::ArgumentCount {
+        rule: "b".to_string(),
+        expected: 0,
+        found: 1
+      })
+    );
+    assert_eq!(
+      resolve("a = list<list<int, int>>\nlist<t> = [* t]"),
+      Err(Error::ArgumentCount {
+        rule: "list".to_string(),
+        expected: 1,
+        found: 2
+      })
+    );
+    assert_eq!(
+      resolve("a = missing<int>"),
+      Err(Error::MissingRule("missing".to_string()))
+    );
+
+    // Recursion through other generic rules can't be instantiated inline
+    assert_eq!(
+      resolve("a = tree<int>\ntree<t> = [t, * children<t>]\nchildren<t> = tree<t>"),
+      Err(Error::Recursive("tree".to_string()))
+    );
+
+    // Recursion through non-generic rules is kept as a reference
+    assert_eq!(
+      resolve("tree = node<tree>\nnode<t> = [* t]")?,
+      resolve("tree = [* tree]")?
+    );
+    assert_eq!(
+      resolve("a = node<int>\nnode<t> = [t, * a]")?,
+      resolve("a = [int, * a]")?
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_generic_bounds() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let resolve = |input: &str| -> std::result::Result<(), Box<dyn std::error::Error>> {
+      let bounds = generic_bounds_from_str(input)?;
+      cddl_from_str(input, true)?.resolve_generics_with_bounds(&bounds)?;
+
+      Ok(())
+    };
+
+    let list = "list<T> = [* T] ;@bound T: uint / tstr\n";
+    for arg in [
+      "uint",
+      "1..10",
+      "5",
+      "\"text\"",
+      "text",
+      "uint .size 2",
+      "(0 / tstr)",
+      "id",
+    ] {
+      resolve(&format!("a = list<{}>\nid = uint\n{}", arg, list))?;
+    }
+    for arg in ["int", "-1", "float", "bstr", "(uint / bstr)", "[* uint]"] {
+      assert!(resolve(&format!("a = list<{}>\n{}", arg, list)).is_err());
+    }
+
+    // Bounds given as ranges and rules
+    resolve("a = byte<16>\n;@bound B: 0..255\nbyte<B> = [B]")?;
+    assert!(resolve("a = byte<256>\n;@bound B: 0..255\nbyte<B> = [B]").is_err());
+    resolve("a = m<int>\n;@bound K: key\nm<K> = { * K => any }\nkey = number / tstr")?;
+
+    // Arguments are checked in the scope of the use site
+    let input = "a = outer<bstr>\nouter<T> = inner<T>\ninner<U> = [U] ;@bound U: tstr";
+    let cddl = cddl_from_str(input, true)?;
+    assert_eq!(
+      cddl.resolve_generics_with_bounds(&generic_bounds_from_str(input)?),
+      Err(Error::Bound {
+        rule: "inner".to_string(),
+        param: "U".to_string(),
+        bound: "tstr".to_string(),
+        arg: "bstr".to_string(),
+      })
+    );
+
+    assert!(generic_bounds_from_str("list<T> = [* T] ;@bound T").is_err());
+    assert!(matches!(
+      cddl_from_str("a = list<uint>\nlist<T> = [* T] ;@bound U: uint", true)?
+        .resolve_generics_with_bounds(&generic_bounds_from_str(
+          "a = list<uint>\nlist<T> = [* T] ;@bound U: uint"
+        )?),
+      Err(Error::InvalidBound { .. })
+    ));
+
+    Ok(())
+  }
+}
@@ -0,0 +1,537 @@
+#![cfg(feature = "std")]
+
+use crate::ast::*;
+
+use std::{
+  borrow::Cow,
+  collections::{BTreeMap, BTreeSet},
+};
+
+/// Rules referenced by each rule of a CDDL document
+///
+/// # Example
+///
+/// ```
+/// use cddl::cddl_from_str;
+///
+/// let mut cddl = cddl_from_str(
+///   "message = { header: header }\nheader = { id: uint }\nunused = tstr",
+///   true,
+/// )
+/// .unwrap();
+///
+/// let graph = cddl.dependency_graph();
+/// assert_eq!(graph.unreachable_from("message"), vec!["unused"]);
+///
+/// // Prune the document down to the rules used by `message`
+/// let reachable = graph.reachable_from("message");
+/// cddl.rules.retain(|r| reachable.contains(&r.name().as_str()));
+/// assert_eq!(cddl.rules.len(), 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DependencyGraph {
+  // Rule names in order of definition. Rules extended with additional choices
+  // (i.e. `/=` or `//=`) only appear once
+  rules: Vec<String>,
+  dependencies: BTreeMap<String, Vec<String>>,
+  // Rules that must be instantiated in order to instantiate each rule
+  requirements: BTreeMap<String, Requirement>,
+}
+
+/// Rules that reference each other, directly or indirectly
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle {
+  /// Rules in the cycle, in order of definition
+  pub rules: Vec<String>,
+  /// Whether or not the cycle is guarded by an occurrence indicator allowing
+  /// zero occurrences or by a choice that doesn't recurse, meaning that the
+  /// rules have finite instances (e.g. `tree = [* tree]` or `list = nil /
+  /// [uint, list]`). Unguarded cycles (e.g. `a = [a]`) can't be satisfied by
+  /// any data item
+  pub is_guarded: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Requirement {
+  Satisfied,
+  Rule(String),
+  All(Vec<Requirement>),
+  Any(Vec<Requirement>),
+}
+
+impl Requirement {
+  fn is_satisfied(&self, satisfiable: &BTreeSet<&str>) -> bool {
+    match self {
+      Requirement::Satisfied => true,
+      Requirement::Rule(rule) => satisfiable.contains(rule.as_str()),
+      Requirement::All(requirements) => requirements.iter().all(|r| r.is_satisfied(satisfiable)),
+      Requirement::Any(requirements) => requirements.iter().any(|r| r.is_satisfied(satisfiable)),
+    }
+  }
+}
+
+impl DependencyGraph {
+  /// Names of all rules, in order of definition
+  pub fn rules(&self) -> impl Iterator<Item = &str> {
+    self.rules.iter().map(|r| r.as_str())
+  }
+
+  /// Rules directly referenced by the given rule, in order of reference
+  pub fn dependencies(&self, rule: &str) -> Vec<&str> {
+    self
+      .dependencies
+      .get(rule)
+      .map(|d| d.iter().map(|r| r.as_str()).collect())
+      .unwrap_or_default()
+  }
+
+  /// Rules directly referencing the given rule, in order of definition
+  pub fn dependents(&self, rule: &str) -> Vec<&str> {
+    self
+      .rules()
+      .filter(|r| self.dependencies(r).contains(&rule))
+      .collect()
+  }
+
+  /// Rules directly or indirectly referenced by the given root rule, including
+  /// the root rule itself, in order of definition
+  pub fn reachable_from(&self, root: &str) -> Vec<&str> {
+    let mut reachable = BTreeSet::new();
+    let mut pending = vec![root];
+    while let Some(rule) = pending.pop() {
+      if self.dependencies.contains_key(rule) && reachable.insert(rule) {
+        pending.extend(self.dependencies(rule));
+      }
+    }
+
+    self.rules().filter(|r| reachable.contains(r)).collect()
+  }
+
+  /// Rules that are neither the given root rule nor referenced by it, directly
+  /// or indirectly, in order of definition
+  pub fn unreachable_from(&self, root: &str) -> Vec<&str> {
+    let reachable = self.reachable_from(root);
+
+    self.rules().filter(|r| !reachable.contains(r)).collect()
+  }
+
+  /// Cycles of rules referencing each other, including rules referencing
+  /// themselves, in order of definition
+  pub fn cycles(&self) -> Vec<Cycle> {
+    let satisfiable = self.satisfiable_rules();
+
+    let mut tarjan = Tarjan {
+      graph: self,
+      index: 0,
+      indices: BTreeMap::new(),
+      low_links: BTreeMap::new(),
+      stack: Vec::new(),
+      components: Vec::new(),
+    };
+
+    for rule in self.rules() {
+      if !tarjan.indices.contains_key(rule) {
+        tarjan.visit(rule);
+      }
+    }
+
+    let position = |rule: &str| self.rules().position(|r| r == rule);
+
+    let mut cycles = tarjan
+      .components
+      .into_iter()
+      .filter(|c| c.len() > 1 || self.dependencies(c[0]).contains(&c[0]))
+      .map(|mut c| {
+        c.sort_by_key(|r| position(r));
+
+        Cycle {
+          is_guarded: c.iter().all(|r| satisfiable.contains(r)),
+          rules: c.into_iter().map(|r| r.to_string()).collect(),
+        }
+      })
+      .collect::<Vec<_>>();
+
+    cycles.sort_by_key(|c| position(&c.rules[0]));
+
+    cycles
+  }
+
+  /// Cycles that aren't guarded by an occurrence indicator or choice, meaning
+  /// that no data item can satisfy their rules
+  pub fn unguarded_cycles(&self) -> Vec<Cycle> {
+    self
+      .cycles()
+      .into_iter()
+      .filter(|c| !c.is_guarded)
+      .collect()
+  }
+
+  // Rules with finite instances, determined by iterating until no additional
+  // rules have their requirements satisfied
+  fn satisfiable_rules(&self) -> BTreeSet<&str> {
+    let mut satisfiable = BTreeSet::new();
+    loop {
+      let satisfied = self
+        .requirements
+        .iter()
+        .filter(|(rule, requirement)| {
+          !satisfiable.contains(rule.as_str()) && requirement.is_satisfied(&satisfiable)
+        })
+        .map(|(rule, _)| rule.as_str())
+        .collect::<Vec<_>>();
+
+      if satisfied.is_empty() {
+        return satisfiable;
+      }
+
+      satisfiable.extend(satisfied);
+    }
+  }
+}
+
+// Tarjan's strongly connected components algorithm
+struct Tarjan<'g> {
+  graph: &'g DependencyGraph,
+  index: usize,
+  indices: BTreeMap<&'g str, usize>,
+  low_links: BTreeMap<&'g str, usize>,
+  stack: Vec<&'g str>,
+  components: Vec<Vec<&'g str>>,
+}
+
+impl<'g> Tarjan<'g> {
+  fn visit(&mut self, rule: &'g str) {
+    self.indices.insert(rule, self.index);
+    self.low_links.insert(rule, self.index);
+    self.index += 1;
+    self.stack.push(rule);
+
+    for dependency in self.graph.dependencies(rule) {
+      if !self.indices.contains_key(dependency) {
+        self.visit(dependency);
+        let low_link = self.low_links[rule].min(self.low_links[dependency]);
+        self.low_links.insert(rule, low_link);
+      } else if self.stack.contains(&dependency) {
+        let low_link = self.low_links[rule].min(self.indices[dependency]);
+        self.low_links.insert(rule, low_link);
+      }
+    }
+
+    if self.low_links[rule] == self.indices[rule] {
+      let mut component = Vec::new();
+      while let Some(r) = self.stack.pop() {
+        component.push(r);
+        if r == rule {
+          break;
+        }
+      }
+
+      self.components.push(component);
+    }
+  }
+}
+
+impl<'a> CDDL<'a> {
+  /// Dependency graph of the rules of the document. Prelude types and
+  /// references to undefined rules are not included
+  pub fn dependency_graph(&self) -> DependencyGraph {
+    let defined = self.rules.iter().map(|r| r.name()).collect::<BTreeSet<_>>();
+
+    let mut graph = DependencyGraph::default();
+    for rule in self.rules.iter() {
+      graph.add_rule(rule, &defined);
+    }
+
+    graph
+  }
+}
+
+impl DependencyGraph {
+  // Adds a definition of a rule, given the names of the rules defined by the
+  // document. Definitions of rules extended with additional choices are merged
+  pub(crate) fn add_rule<'a>(&mut self, rule: &Rule<'a>, defined: &BTreeSet<String>) {
+    let name = rule.name();
+
+    let mut builder = Builder {
+      defined,
+      generic_params: Vec::new(),
+      dependencies: self.dependencies.remove(&name).unwrap_or_default(),
+    };
+
+    let requirement = builder.rule(rule);
+
+    // Rules extended with additional choices are satisfied by any of their
+    // definitions
+    let requirement = match self.requirements.remove(&name) {
+      Some(Requirement::Any(mut choices)) => {
+        choices.push(requirement);
+        Requirement::Any(choices)
+      }
+      Some(existing) => Requirement::Any(vec![existing, requirement]),
+      None => requirement,
+    };
+
+    if !self.rules.contains(&name) {
+      self.rules.push(name.clone());
+    }
+    self.dependencies.insert(name.clone(), builder.dependencies);
+    self.requirements.insert(name, requirement);
+  }
+
+  // Adds the rules of a graph built from other rules of the same document
+  pub(crate) fn extend(&mut self, other: &DependencyGraph) {
+    for rule in other.rules.iter() {
+      if !self.rules.contains(rule) {
+        self.rules.push(rule.clone());
+      }
+    }
+    self.dependencies.extend(
+      other
+        .dependencies
+        .iter()
+        .map(|(r, d)| (r.clone(), d.clone())),
+    );
+    self.requirements.extend(
+      other
+        .requirements
+        .iter()
+        .map(|(r, d)| (r.clone(), d.clone())),
+    );
+  }
+}
+
+struct Builder<'d, 'a> {
+  defined: &'d BTreeSet<String>,
+  generic_params: Vec<Cow<'a, str>>,
+  dependencies: Vec<String>,
+}
+
+impl<'d, 'a> Builder<'d, 'a> {
+  fn rule(&mut self, rule: &Rule<'a>) -> Requirement {
+    match rule {
+      Rule::Type { rule, .. } => {
+        self.generic_params = generic_params(rule.generic_params.as_ref());
+        self.type_(&rule.value)
+      }
+      Rule::Group { rule, .. } => {
+        self.generic_params = generic_params(rule.generic_params.as_ref());
+        self.group_entry(&rule.entry)
+      }
+    }
+  }
+
+  fn reference(
+    &mut self,
+    ident: &Identifier<'a>,
+    generic_args: Option<&GenericArgs<'a>>,
+  ) -> Requirement {
+    let mut requirements = Vec::new();
+
+    if let Some(args) = generic_args {
+      for arg in args.args.iter() {
+        requirements.push(self.type1(&arg.arg));
+      }
+    }
+
+    let name = ident.to_string();
+    if (ident.socket.is_some() || !self.generic_params.contains(&ident.ident))
+      && self.defined.contains(&name)
+    {
+      if !self.dependencies.contains(&name) {
+        self.dependencies.push(name.clone());
+      }
+
+      requirements.push(Requirement::Rule(name));
+    }
+
+    Requirement::All(requirements)
+  }
+
+  fn type_(&mut self, t: &Type<'a>) -> Requirement {
+    Requirement::Any(
+      t.type_choices
+        .iter()
+        .map(|tc| self.type1(&tc.type1))
+        .collect(),
+    )
+  }
+
+  fn type1(&mut self, t1: &Type1<'a>) -> Requirement {
+    let target = self.type2(&t1.type2);
+
+    match &t1.operator {
+      Some(o) => Requirement::All(vec![target, self.type2(&o.type2)]),
+      None => target,
+    }
+  }
+
+  fn type2(&mut self, t2: &Type2<'a>) -> Requirement {
+    match t2 {
+      Type2::Typename {
+        ident,
+        generic_args,
+        ..
+      }
+      | Type2::Unwrap {
+        ident,
+        generic_args,
+        ..
+      }
+      | Type2::ChoiceFromGroup {
+        ident,
+        generic_args,
+        ..
+      } => self.reference(ident, generic_args.as_ref()),
+      Type2::ParenthesizedType { pt, .. } => self.type_(pt),
+      Type2::TaggedData { tag_type, t, .. } => match tag_type {
+        Some(tag_type) => Requirement::All(vec![self.type_(tag_type), self.type_(t)]),
+        None => self.type_(t),
+      },
+      Type2::Map { group, .. } | Type2::Array { group, .. } => self.group(group),
+      Type2::ChoiceFromInlineGroup { group, .. } => {
+        // Any entry of the group can be chosen
+        let mut choices = Vec::new();
+        for gc in group.group_choices.iter() {
+          for (ge, _) in gc.group_entries.iter() {
+            choices.push(self.group_entry(ge));
+          }
+        }
+
+        Requirement::Any(choices)
+      }
+      _ => Requirement::Satisfied,
+    }
+  }
+
+  fn group(&mut self, g: &Group<'a>) -> Requirement {
+    Requirement::Any(
+      g.group_choices
+        .iter()
+        .map(|gc| {
+          Requirement::All(
+            gc.group_entries
+              .iter()
+              .map(|(ge, _)| self.group_entry(ge))
+              .collect(),
+          )
+        })
+        .collect(),
+    )
+  }
+
+  fn group_entry(&mut self, ge: &GroupEntry<'a>) -> Requirement {
+    let (occur, requirement) = match ge {
+      GroupEntry::ValueMemberKey { ge, .. } => {
+        let key = match &ge.member_key {
+          Some(MemberKey::Type1 { t1, .. }) => self.type1(t1),
+          _ => Requirement::Satisfied,
+        };
+
+        (
+          ge.occur.as_ref(),
+          Requirement::All(vec![key, self.type_(&ge.entry_type)]),
+        )
+      }
+      GroupEntry::TypeGroupname { ge, .. } => (
+        ge.occur.as_ref(),
+        self.reference(&ge.name, ge.generic_args.as_ref()),
+      ),
+      GroupEntry::InlineGroup { occur, group, .. } => (occur.as_ref(), self.group(group)),
+    };
+
+    if occur.map(|o| allows_zero(&o.occur)).unwrap_or(false) {
+      return Requirement::Satisfied;
+    }
+
+    requirement
+  }
+}
+
+fn generic_params<'a>(params: Option<&GenericParams<'a>>) -> Vec<Cow<'a, str>> {
+  params
+    .map(|gp| gp.params.iter().map(|p| p.param.ident.clone()).collect())
+    .unwrap_or_default()
+}
+
+fn allows_zero(occur: &Occur) -> bool {
+  match occur {
+    #[cfg(feature = "ast-span")]
+    Occur::Optional(_) | Occur::ZeroOrMore(_) => true,
+    #[cfg(not(feature = "ast-span"))]
+    Occur::Optional | Occur::ZeroOrMore => true,
+    Occur::Exact { lower, .. } => lower.unwrap_or(0) == 0,
+    _ => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::cddl_from_str;
+  use indoc::indoc;
+
+  #[test]
+  fn verify_dependency_graph() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = indoc!(
+      r#"
+        message = {
+          header: header,
+          body: body<payload>,
+          ? trailer: tstr,
+        }
+
+        header = { id: uint, ? parent: header }
+        body<T> = [* T]
+        payload = tree / nil
+        tree = [uint, * tree]
+
+        unguarded = [uint, unguarded]
+        a = { b: b }
+        b = [a]
+
+        unused = header
+        extension = uint
+        extension /= payload
+      "#
+    );
+
+    let cddl = cddl_from_str(cddl, true)?;
+    let graph = cddl.dependency_graph();
+
+    assert_eq!(
+      graph.dependencies("message"),
+      vec!["header", "payload", "body"]
+    );
+    assert!(graph.dependencies("body").is_empty());
+    assert_eq!(
+      graph.dependents("header"),
+      vec!["message", "header", "unused"]
+    );
+    assert_eq!(graph.dependencies("extension"), vec!["payload"]);
+
+    assert_eq!(
+      graph.reachable_from("message"),
+      vec!["message", "header", "body", "payload", "tree"]
+    );
+    assert_eq!(
+      graph.unreachable_from("message"),
+      vec!["unguarded", "a", "b", "unused", "extension"]
+    );
+
+    let cycles = graph.cycles();
+    assert_eq!(
+      cycles
+        .iter()
+        .map(|c| (c.rules.join(" "), c.is_guarded))
+        .collect::<Vec<_>>(),
+      vec![
+        ("header".to_string(), true),
+        ("tree".to_string(), true),
+        ("unguarded".to_string(), false),
+        ("a b".to_string(), false),
+      ]
+    );
+    assert_eq!(graph.unguarded_cycles().len(), 2);
+
+    Ok(())
+  }
+}
@@ -0,0 +1,1033 @@
+#![cfg(feature = "std")]
+
+use crate::{
+  builder::{CddlBuilder, EntryBuilder, GroupBuilder, TypeBuilder, TypeRuleBuilder},
+  token::lookup_ident,
+};
+
+use displaydoc::Display;
+use serde_json::{Map, Value};
+use std::{collections::HashMap, fmt};
+
+/// Alias for `Result` with an error of type `json_schema::Error`
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// JSON Schema conversion error
+#[derive(Debug, Display, PartialEq)]
+pub enum Error {
+  /// schema must be a JSON object or boolean, got {0}
+  InvalidSchema(String),
+}
+
+impl std::error::Error for Error {}
+
+/// Construct of a JSON Schema document that has no CDDL equivalent and was
+/// dropped or approximated during conversion
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+  /// JSON Pointer to the schema containing the construct, e.g.
+  /// `#/properties/name`
+  pub pointer: String,
+  /// What was dropped or approximated
+  pub message: String,
+}
+
+impl fmt::Display for Warning {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}: {}", self.pointer, self.message)
+  }
+}
+
+/// CDDL document converted from a JSON Schema document
+#[derive(Debug, Clone)]
+pub struct Conversion {
+  /// Converted document. Its first rule is the root schema, followed by a
+  /// rule for each definition
+  pub cddl: CddlBuilder,
+  /// Constructs that were dropped or approximated
+  pub warnings: Vec<Warning>,
+}
+
+/// Convert a JSON Schema document to a CDDL document whose first rule, named
+/// `root`, corresponds to the root schema. Each schema under `$defs` or
+/// `definitions` becomes a rule of its own, named after its key, and local
+/// `$ref`s to them become references to those rules.
+///
+/// Objects become maps, closed unless `additionalProperties` allows further
+/// entries, with `patternProperties` keyed by `tstr .regexp`. `enum` and
+/// `const` become choices of values, `anyOf` and `oneOf` type choices, and
+/// `allOf` of object schemas a map merging their properties. Bounds on
+/// strings, numbers and arrays become ranges, `.size` and comparison control
+/// operators and occurrence indicators. Constructs without a CDDL
+/// equivalent, such as `not`, conditionals or `uniqueItems`, are reported as
+/// warnings
+///
+/// # Example
+///
+/// ```
+/// use cddl::json_schema::cddl_from_json_schema;
+///
+/// let schema = serde_json::json!({
+///   "type": "object",
+///   "properties": {
+///     "name": { "type": "string", "maxLength": 64 },
+///     "role": { "enum": ["admin", "user"] }
+///   },
+///   "required": ["name"],
+///   "additionalProperties": false
+/// });
+///
+/// let conversion = cddl_from_json_schema(&schema, "person").unwrap();
+/// assert_eq!(
+///   conversion.cddl.to_string(),
+///   "person = { name: tstr .size (0..64), ? role: \"admin\" / \"user\" }\n"
+/// );
+/// assert!(conversion.warnings.is_empty());
+/// ```
+pub fn cddl_from_json_schema(schema: &Value, root: &str) -> Result<Conversion> {
+  if !matches!(schema, Value::Object(_) | Value::Bool(_)) {
+    return Err(Error::InvalidSchema(schema.to_string()));
+  }
+
+  let mut converter = Converter {
+    names: HashMap::new(),
+    warnings: Vec::new(),
+  };
+  let root = converter.rule_name(root);
+  converter.names.insert("#".to_string(), root.clone());
+
+  let mut definitions = Vec::new();
+  for keyword in ["$defs", "definitions"] {
+    match schema.get(keyword) {
+      Some(Value::Object(defs)) => {
+        for (name, def) in defs.iter() {
+          let pointer = format!("#/{}/{}", keyword, escape_pointer(name));
+          let rule = converter.rule_name(name);
+          converter.names.insert(pointer.clone(), rule.clone());
+          definitions.push((pointer, rule, def));
+        }
+      }
+      Some(_) => converter.warn("#", format!("{} is not an object", keyword)),
+      None => (),
+    }
+  }
+
+  let mut cddl = CddlBuilder::new();
+  cddl.type_rule(TypeRuleBuilder::new(root, converter.schema(schema, "#")));
+  for (pointer, rule, def) in definitions {
+    let value = converter.schema(def, &pointer);
+    cddl.type_rule(TypeRuleBuilder::new(rule, value));
+  }
+
+  Ok(Conversion {
+    cddl,
+    warnings: converter.warnings,
+  })
+}
+
+// Keywords that only annotate a schema and have no bearing on validation
+const ANNOTATIONS: &[&str] = &[
+  "$schema",
+  "$id",
+  "$comment",
+  "$anchor",
+  "$defs",
+  "definitions",
+  "title",
+  "description",
+  "default",
+  "examples",
+  "deprecated",
+  "readOnly",
+  "writeOnly",
+  "contentMediaType",
+  "contentEncoding",
+];
+
+// Keywords translated by the converter
+const TRANSLATED: &[&str] = &[
+  "$ref",
+  "type",
+  "enum",
+  "const",
+  "anyOf",
+  "oneOf",
+  "allOf",
+  "properties",
+  "required",
+  "additionalProperties",
+  "patternProperties",
+  "items",
+  "prefixItems",
+  "additionalItems",
+  "minItems",
+  "maxItems",
+  "minLength",
+  "maxLength",
+  "pattern",
+  "format",
+  "minimum",
+  "maximum",
+  "exclusiveMinimum",
+  "exclusiveMaximum",
+];
+
+struct Converter {
+  // Rule names by the JSON Pointer of the schema they were converted from
+  names: HashMap<String, String>,
+  warnings: Vec<Warning>,
+}
+
+impl Converter {
+  fn warn(&mut self, pointer: &str, message: String) {
+    self.warnings.push(Warning {
+      pointer: pointer.to_string(),
+      message,
+    });
+  }
+
+  // Valid and unique CDDL rule name for the given name. Names of the standard
+  // prelude are suffixed so as not to shadow the prelude
+  fn rule_name(&self, name: &str) -> String {
+    let mut rule = name
+      .chars()
+      .map(|c| {
+        if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@' | '$') {
+          c
+        } else {
+          '-'
+        }
+      })
+      .collect::<String>()
+      .trim_end_matches(['-', '.'])
+      .to_string();
+    if !rule.starts_with(|c: char| c.is_ascii_alphabetic() || matches!(c, '_' | '@' | '$')) {
+      rule.insert_str(0, "def-");
+    }
+    if lookup_ident(&rule).in_standard_prelude().is_some() {
+      rule.push_str("-def");
+    }
+
+    let mut unique = rule.clone();
+    let mut suffix = 2;
+    while self.names.values().any(|n| *n == unique) {
+      unique = format!("{}-{}", rule, suffix);
+      suffix += 1;
+    }
+
+    unique
+  }
+
+  fn schema(&mut self, schema: &Value, pointer: &str) -> TypeBuilder {
+    let schema = match schema {
+      Value::Bool(true) => return TypeBuilder::typename("any"),
+      Value::Bool(false) => {
+        self.warn(
+          pointer,
+          "schema false, which no instance matches, converted to any".to_string(),
+        );
+        return TypeBuilder::typename("any");
+      }
+      Value::Object(schema) => schema,
+      _ => {
+        self.warn(
+          pointer,
+          format!("invalid schema {} converted to any", schema),
+        );
+        return TypeBuilder::typename("any");
+      }
+    };
+
+    for keyword in schema.keys() {
+      if !ANNOTATIONS.contains(&keyword.as_str()) && !TRANSLATED.contains(&keyword.as_str()) {
+        self.warn(pointer, format!("keyword {} is not supported", keyword));
+      }
+    }
+
+    if let Some(reference) = schema.get("$ref") {
+      return self.reference(reference, pointer);
+    }
+    if let Some(value) = schema.get("const") {
+      return self
+        .value(value, pointer)
+        .unwrap_or_else(|| TypeBuilder::typename("any"));
+    }
+    if let Some(values) = schema.get("enum") {
+      return self.enumeration(values, pointer);
+    }
+    for keyword in ["anyOf", "oneOf"] {
+      if let Some(schemas) = schema.get(keyword) {
+        return self.choices(schemas, &format!("{}/{}", pointer, keyword));
+      }
+    }
+    if let Some(schemas) = schema.get("allOf") {
+      return self.all_of(schemas, &format!("{}/allOf", pointer));
+    }
+
+    let types = match schema.get("type") {
+      Some(Value::String(t)) => vec![t.as_str()],
+      Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+      Some(t) => {
+        self.warn(pointer, format!("invalid type {}", t));
+        Vec::new()
+      }
+      None
+        if schema.contains_key("properties")
+          || schema.contains_key("patternProperties")
+          || schema.contains_key("additionalProperties") =>
+      {
+        vec!["object"]
+      }
+      None if schema.contains_key("items") || schema.contains_key("prefixItems") => {
+        vec!["array"]
+      }
+      None => Vec::new(),
+    };
+
+    let mut choices: Option<TypeBuilder> = None;
+    for t in types {
+      let choice = match t {
+        "string" => self.string(schema, pointer),
+        "integer" => self.number(schema, pointer, "int"),
+        "number" => self.number(schema, pointer, "number"),
+        "boolean" => TypeBuilder::typename("bool"),
+        "null" => TypeBuilder::typename("null"),
+        "object" => self.object(schema, pointer),
+        "array" => self.array(schema, pointer),
+        t => {
+          self.warn(pointer, format!("unknown type {} converted to any", t));
+          TypeBuilder::typename("any")
+        }
+      };
+
+      choices = Some(match choices {
+        Some(choices) => choices.or(choice),
+        None => choice,
+      });
+    }
+
+    choices.unwrap_or_else(|| TypeBuilder::typename("any"))
+  }
+
+  fn reference(&mut self, reference: &Value, pointer: &str) -> TypeBuilder {
+    match reference.as_str().and_then(|r| self.names.get(r)) {
+      Some(rule) => TypeBuilder::typename(rule.clone()),
+      None => {
+        self.warn(
+          pointer,
+          format!(
+            "$ref {} doesn't refer to the root schema or a definition, converted to any",
+            reference
+          ),
+        );
+        TypeBuilder::typename("any")
+      }
+    }
+  }
+
+  // Literal value, if it can be written in CDDL
+  fn value(&mut self, value: &Value, pointer: &str) -> Option<TypeBuilder> {
+    let t = match value {
+      Value::Null => TypeBuilder::typename("null"),
+      Value::Bool(b) => TypeBuilder::typename(b.to_string()),
+      Value::String(s) => text(s),
+      Value::Number(n) => number(n),
+      Value::Array(_) | Value::Object(_) => {
+        self.warn(
+          pointer,
+          format!("array and object value {} can't be written in CDDL", value),
+        );
+        return None;
+      }
+    };
+
+    Some(t)
+  }
+
+  fn enumeration(&mut self, values: &Value, pointer: &str) -> TypeBuilder {
+    let pointer = format!("{}/enum", pointer);
+    let values = match values {
+      Value::Array(values) => values,
+      _ => {
+        self.warn(&pointer, "enum is not an array".to_string());
+        return TypeBuilder::typename("any");
+      }
+    };
+
+    let mut choices: Option<TypeBuilder> = None;
+    for (idx, value) in values.iter().enumerate() {
+      if let Some(value) = self.value(value, &format!("{}/{}", pointer, idx)) {
+        choices = Some(match choices {
+          Some(choices) => choices.or(value),
+          None => value,
+        });
+      }
+    }
+
+    choices.unwrap_or_else(|| TypeBuilder::typename("any"))
+  }
+
+  fn choices(&mut self, schemas: &Value, pointer: &str) -> TypeBuilder {
+    let schemas = match schemas {
+      Value::Array(schemas) if !schemas.is_empty() => schemas,
+      _ => {
+        self.warn(pointer, "expected a non-empty array of schemas".to_string());
+        return TypeBuilder::typename("any");
+      }
+    };
+
+    let mut choices: Option<TypeBuilder> = None;
+    for (idx, schema) in schemas.iter().enumerate() {
+      let choice = self.schema(schema, &format!("{}/{}", pointer, idx));
+      choices = Some(match choices {
+        Some(choices) => choices.or(choice),
+        None => choice,
+      });
+    }
+
+    choices.unwrap_or_else(|| TypeBuilder::typename("any"))
+  }
+
+  // Object schemas of allOf are merged into a single map. Other schemas can't
+  // be intersected
+  fn all_of(&mut self, schemas: &Value, pointer: &str) -> TypeBuilder {
+    let schemas = match schemas {
+      Value::Array(schemas) if !schemas.is_empty() => schemas,
+      _ => {
+        self.warn(pointer, "expected a non-empty array of schemas".to_string());
+        return TypeBuilder::typename("any");
+      }
+    };
+    if let [schema] = schemas.as_slice() {
+      return self.schema(schema, &format!("{}/0", pointer));
+    }
+
+    let mut merged = Map::new();
+    for (idx, schema) in schemas.iter().enumerate() {
+      let schema = match schema.as_object() {
+        Some(schema)
+          if schema.get("type").is_none_or(|t| t == "object")
+            && schema.keys().all(|k| {
+              ANNOTATIONS.contains(&k.as_str()) || OBJECT_KEYWORDS.contains(&k.as_str())
+            }) =>
+        {
+          schema
+        }
+        _ => {
+          self.warn(
+            &format!("{}/{}", pointer, idx),
+            "allOf can only be converted for object schemas, converted to any".to_string(),
+          );
+          return TypeBuilder::typename("any");
+        }
+      };
+
+      for (keyword, value) in schema.iter() {
+        match (keyword.as_str(), merged.get_mut(keyword), value) {
+          ("properties" | "patternProperties", Some(Value::Object(m)), Value::Object(value)) => {
+            m.extend(value.clone())
+          }
+          ("required", Some(Value::Array(r)), Value::Array(value)) => r.extend(value.clone()),
+          // A schema without additional properties closes the merged object
+          ("additionalProperties", Some(_), Value::Bool(false)) => {
+            merged.insert(keyword.clone(), value.clone());
+          }
+          ("additionalProperties", Some(_), _) => (),
+          (_, None, _) => {
+            merged.insert(keyword.clone(), value.clone());
+          }
+          _ => (),
+        }
+      }
+    }
+
+    self.object(&merged, pointer)
+  }
+
+  fn string(&mut self, schema: &Map<String, Value>, pointer: &str) -> TypeBuilder {
+    let mut t = match schema.get("format").and_then(Value::as_str) {
+      Some("date-time") => return TypeBuilder::typename("tdate"),
+      Some("uri") => return TypeBuilder::typename("uri"),
+      Some(format) => {
+        self.warn(pointer, format!("format {} is not checked", format));
+        TypeBuilder::typename("tstr")
+      }
+      None => TypeBuilder::typename("tstr"),
+    };
+
+    let min = schema.get("minLength").and_then(Value::as_u64);
+    let max = schema.get("maxLength").and_then(Value::as_u64);
+    let pattern = schema.get("pattern").and_then(Value::as_str);
+    match (min, max) {
+      _ if pattern.is_some() && (min.is_some() || max.is_some()) => self.warn(
+        pointer,
+        "minLength and maxLength can't be combined with pattern".to_string(),
+      ),
+      (min, Some(max)) => {
+        let size = TypeBuilder::range(
+          TypeBuilder::uint(min.unwrap_or(0) as usize),
+          TypeBuilder::uint(max as usize),
+          true,
+        );
+        t = t.control(".size", size);
+      }
+      (Some(min), None) if min > 0 => self.warn(
+        pointer,
+        "minLength without maxLength can't be converted".to_string(),
+      ),
+      _ => (),
+    }
+    if let Some(pattern) = pattern {
+      t = t.control(".regexp", text(pattern));
+    }
+
+    t
+  }
+
+  fn number(&mut self, schema: &Map<String, Value>, pointer: &str, name: &str) -> TypeBuilder {
+    let bound = |keyword: &str| match schema.get(keyword) {
+      Some(Value::Number(n)) => Some(n),
+      _ => None,
+    };
+    let lower = bound("minimum")
+      .map(|n| (n, true))
+      .or_else(|| bound("exclusiveMinimum").map(|n| (n, false)));
+    let upper = bound("maximum")
+      .map(|n| (n, true))
+      .or_else(|| bound("exclusiveMaximum").map(|n| (n, false)));
+
+    match (lower, upper) {
+      // Ranges require both bounds to be of the same kind, and can only
+      // exclude the upper bound
+      (Some((lower, true)), Some((upper, is_inclusive)))
+        if name == "int" || lower.is_f64() == upper.is_f64() =>
+      {
+        if name == "number" {
+          self.warn(
+            pointer,
+            format!(
+              "range of number only matches {}",
+              if lower.is_f64() { "floats" } else { "integers" }
+            ),
+          );
+        }
+        TypeBuilder::range(number(lower), number(upper), is_inclusive)
+      }
+      (Some(_), Some(_)) => {
+        self.warn(pointer, "bounds converted to lower bound only".to_string());
+        let (lower, is_inclusive) = lower.unwrap();
+        TypeBuilder::typename(name).control(if is_inclusive { ".ge" } else { ".gt" }, number(lower))
+      }
+      (Some((lower, is_inclusive)), None) => {
+        TypeBuilder::typename(name).control(if is_inclusive { ".ge" } else { ".gt" }, number(lower))
+      }
+      (None, Some((upper, is_inclusive))) => {
+        TypeBuilder::typename(name).control(if is_inclusive { ".le" } else { ".lt" }, number(upper))
+      }
+      (None, None) => TypeBuilder::typename(name),
+    }
+  }
+
+  fn object(&mut self, schema: &Map<String, Value>, pointer: &str) -> TypeBuilder {
+    let mut group = GroupBuilder::new();
+
+    let required = schema
+      .get("required")
+      .and_then(Value::as_array)
+      .map(|r| r.iter().filter_map(Value::as_str).collect::<Vec<_>>())
+      .unwrap_or_default();
+
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+      for (name, property) in properties.iter() {
+        let value = self.schema(
+          property,
+          &format!("{}/properties/{}", pointer, escape_pointer(name)),
+        );
+        let entry = EntryBuilder::member(escape_text(name), value);
+        group = group.entry(if required.contains(&name.as_str()) {
+          entry
+        } else {
+          entry.optional()
+        });
+      }
+    }
+    for name in required.iter() {
+      if !schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .is_some_and(|p| p.contains_key(*name))
+      {
+        group = group.entry(EntryBuilder::member(
+          escape_text(name),
+          TypeBuilder::typename("any"),
+        ));
+      }
+    }
+
+    if let Some(patterns) = schema.get("patternProperties").and_then(Value::as_object) {
+      for (pattern, property) in patterns.iter() {
+        let value = self.schema(
+          property,
+          &format!("{}/patternProperties/{}", pointer, escape_pointer(pattern)),
+        );
+        let key = TypeBuilder::typename("tstr").control(".regexp", text(pattern));
+        group = group.entry(EntryBuilder::type_key(key, value, false).zero_or_more());
+      }
+    }
+
+    // Objects are open unless additionalProperties says otherwise
+    match schema.get("additionalProperties") {
+      Some(Value::Bool(false)) => (),
+      None | Some(Value::Bool(true)) => {
+        group = group.entry(
+          EntryBuilder::type_key(
+            TypeBuilder::typename("tstr"),
+            TypeBuilder::typename("any"),
+            false,
+          )
+          .zero_or_more(),
+        )
+      }
+      Some(additional) => {
+        let value = self.schema(additional, &format!("{}/additionalProperties", pointer));
+        group = group
+          .entry(EntryBuilder::type_key(TypeBuilder::typename("tstr"), value, false).zero_or_more())
+      }
+    }
+
+    TypeBuilder::map(group)
+  }
+
+  fn array(&mut self, schema: &Map<String, Value>, pointer: &str) -> TypeBuilder {
+    let mut group = GroupBuilder::new();
+
+    // Tuples are given by prefixItems, or by an array of items before draft
+    // 2020-12, with the remaining items given by items or additionalItems
+    let (prefix, prefix_keyword, rest, rest_keyword) = match schema.get("prefixItems") {
+      Some(Value::Array(prefix)) => (
+        prefix.as_slice(),
+        "prefixItems",
+        schema.get("items"),
+        "items",
+      ),
+      _ => match schema.get("items") {
+        Some(Value::Array(prefix)) => (
+          prefix.as_slice(),
+          "items",
+          schema.get("additionalItems"),
+          "additionalItems",
+        ),
+        items => (&[][..], "", items, "items"),
+      },
+    };
+
+    for (idx, item) in prefix.iter().enumerate() {
+      let value = self.schema(item, &format!("{}/{}/{}", pointer, prefix_keyword, idx));
+      group = group.entry(EntryBuilder::value(value));
+    }
+
+    let min = schema
+      .get("minItems")
+      .and_then(Value::as_u64)
+      .map(|n| n as usize);
+    let max = schema
+      .get("maxItems")
+      .and_then(Value::as_u64)
+      .map(|n| n as usize);
+    if !prefix.is_empty() && (min.is_some() || max.is_some()) {
+      self.warn(
+        pointer,
+        "minItems and maxItems of tuples are not converted".to_string(),
+      );
+    }
+
+    match rest {
+      Some(Value::Bool(false)) => (),
+      rest if !prefix.is_empty() && rest.is_none() => {
+        group = group.entry(EntryBuilder::value(TypeBuilder::typename("any")).zero_or_more())
+      }
+      rest => {
+        let value = match rest {
+          Some(rest) => self.schema(rest, &format!("{}/{}", pointer, rest_keyword)),
+          None => TypeBuilder::typename("any"),
+        };
+        let entry = EntryBuilder::value(value);
+        group = group.entry(match (prefix.is_empty(), min, max) {
+          (true, None | Some(0), None) | (false, ..) => entry.zero_or_more(),
+          (true, Some(1), None) => entry.one_or_more(),
+          (true, min, max) => entry.occurs(min.filter(|min| *min > 0), max),
+        });
+      }
+    }
+
+    TypeBuilder::array(group)
+  }
+}
+
+// Keywords allowed in the object schemas merged by allOf
+const OBJECT_KEYWORDS: &[&str] = &[
+  "type",
+  "properties",
+  "required",
+  "additionalProperties",
+  "patternProperties",
+];
+
+// Text string value, escaped as in JSON as required of CDDL text strings
+fn text(value: &str) -> TypeBuilder {
+  TypeBuilder::text(escape_text(value))
+}
+
+fn escape_text(value: &str) -> String {
+  let escaped = Value::String(value.to_string()).to_string();
+  escaped[1..escaped.len() - 1].to_string()
+}
+
+fn number(n: &serde_json::Number) -> TypeBuilder {
+  match (n.as_i64(), n.as_u64(), n.as_f64()) {
+    (Some(i), ..) if i < 0 => TypeBuilder::int(i as isize),
+    (_, Some(u), _) => TypeBuilder::uint(u as usize),
+    (.., Some(f)) => TypeBuilder::float(f),
+    _ => TypeBuilder::typename("number"),
+  }
+}
+
+// Escape a key of an object to be a reference token of a JSON Pointer
+fn escape_pointer(key: &str) -> String {
+  key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+#[cfg(feature = "json")]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+  use super::*;
+  use crate::{cddl_from_str, validator::validate_json_from_str};
+
+  use serde_json::json;
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn verify_cddl_from_json_schema() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let schema = json!({
+      "$schema": "https://json-schema.org/draft/2020-12/schema",
+      "type": "object",
+      "properties": {
+        "id": { "type": "integer", "minimum": 1 },
+        "status": { "enum": ["open", "closed", null] },
+        "items": { "type": "array", "items": { "$ref": "#/$defs/item" }, "minItems": 1 },
+        "note": { "type": ["string", "null"], "maxLength": 140 },
+        "point": {
+          "type": "array",
+          "prefixItems": [{ "type": "number" }, { "type": "number" }],
+          "items": false
+        }
+      },
+      "required": ["id", "items"],
+      "patternProperties": { "^x-": true },
+      "additionalProperties": false,
+      "$defs": {
+        "item": {
+          "type": "object",
+          "properties": {
+            "sku": { "type": "string", "pattern": "^[A-Z]{3}\\.[0-9]+$" },
+            "quantity": { "type": "integer", "minimum": 1, "exclusiveMaximum": 100 }
+          },
+          "required": ["sku"],
+          "not": { "required": ["discount"] }
+        },
+        "int": { "type": "integer", "multipleOf": 2 }
+      }
+    });
+
+    let conversion = cddl_from_json_schema(&schema, "order")?;
+    assert_eq!(
+      conversion.warnings,
+      vec![
+        Warning {
+          pointer: "#/$defs/int".to_string(),
+          message: "keyword multipleOf is not supported".to_string(),
+        },
+        Warning {
+          pointer: "#/$defs/item".to_string(),
+          message: "keyword not is not supported".to_string(),
+        },
+      ]
+    );
+
+    let cddl = conversion.cddl.to_string();
+    assert!(cddl.contains("int-def = int"));
+    assert!(cddl.contains(r#"sku: tstr .regexp "^[A-Z]{3}\\.[0-9]+$""#));
+
+    let validate = |json: serde_json::Value| validate_json_from_str(&cddl, &json.to_string(), None);
+    validate(json!({ "id": 1, "items": [{ "sku": "ABC.1", "quantity": 99 }], "x-trace": 1 }))?;
+    validate(json!({ "id": 1, "items": [{ "sku": "ABC.1" }], "status": null, "point": [1.5, 2] }))?;
+    assert!(validate(json!({ "id": 0, "items": [{ "sku": "ABC.1" }] })).is_err());
+    assert!(validate(json!({ "id": 1, "items": [] })).is_err());
+    assert!(validate(json!({ "id": 1, "items": [{ "sku": "abc" }] })).is_err());
+    assert!(validate(json!({ "id": 1, "items": [{ "sku": "ABC.1", "quantity": 100 }] })).is_err());
+    assert!(validate(json!({ "id": 1, "items": [{ "sku": "ABC.1" }], "extra": 1 })).is_err());
+
+    assert_eq!(
+      cddl_from_json_schema(&json!([]), "root").unwrap_err(),
+      Error::InvalidSchema("[]".to_string())
+    );
+
+    Ok(())
+  }
+
+  // Document as printed after parsing, so that documents can be compared
+  // regardless of their layout
+  fn reformat(cddl: &str) -> String {
+    cddl_from_str(cddl, true).unwrap().to_string()
+  }
+
+  fn convert(schema: serde_json::Value) -> (String, Vec<String>) {
+    let conversion = cddl_from_json_schema(&schema, "root").unwrap();
+    (
+      reformat(&conversion.cddl.to_string()),
+      conversion.warnings.iter().map(|w| w.to_string()).collect(),
+    )
+  }
+
+  #[test]
+  fn verify_choices() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let (cddl, warnings) = convert(json!({
+      "anyOf": [
+        { "type": "string" },
+        { "type": "integer", "minimum": 0, "maximum": 10 },
+        { "oneOf": [{ "type": "boolean" }, { "const": "none" }] }
+      ]
+    }));
+    assert_eq!(cddl, reformat("root = tstr / 0..10 / bool / \"none\""));
+    assert!(warnings.is_empty());
+
+    let validate = |json: serde_json::Value| validate_json_from_str(&cddl, &json.to_string(), None);
+    validate(json!("text"))?;
+    validate(json!(10))?;
+    validate(json!(true))?;
+    assert!(validate(json!(11)).is_err());
+    assert!(validate(json!(null)).is_err());
+
+    let (cddl, _) = convert(json!({ "type": ["string", "null"] }));
+    assert_eq!(cddl, reformat("root = tstr / null"));
+
+    let (cddl, warnings) = convert(json!({ "anyOf": [] }));
+    assert_eq!(cddl, reformat("root = any"));
+    assert_eq!(
+      warnings,
+      vec!["#/anyOf: expected a non-empty array of schemas"]
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_ranges() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let integer = |bounds: serde_json::Value| {
+      let mut schema = json!({ "type": "integer" });
+      schema
+        .as_object_mut()
+        .unwrap()
+        .extend(bounds.as_object().unwrap().clone());
+      convert(schema)
+    };
+
+    assert_eq!(
+      integer(json!({ "minimum": 1, "maximum": 10 })),
+      (reformat("root = 1..10"), vec![])
+    );
+    assert_eq!(
+      integer(json!({ "minimum": -5, "exclusiveMaximum": 5 })),
+      (reformat("root = -5...5"), vec![])
+    );
+    assert_eq!(
+      integer(json!({ "exclusiveMinimum": 0 })),
+      (reformat("root = int .gt 0"), vec![])
+    );
+    assert_eq!(
+      integer(json!({ "maximum": 100 })),
+      (reformat("root = int .le 100"), vec![])
+    );
+    assert_eq!(
+      integer(json!({ "exclusiveMinimum": 0, "maximum": 10 })),
+      (
+        reformat("root = int .gt 0"),
+        vec!["#: bounds converted to lower bound only".to_string()]
+      )
+    );
+
+    assert_eq!(
+      convert(json!({ "type": "number", "minimum": 0.0, "maximum": 1.0 })),
+      (
+        reformat("root = 0.0..1.0"),
+        vec!["#: range of number only matches floats".to_string()]
+      )
+    );
+    // Float bounds are printed as floats, so the range doesn't match integers
+    let (cddl, _) = convert(json!({ "type": "number", "minimum": 0.0, "maximum": 1.0 }));
+    validate_json_from_str(&cddl, "0.5", None)?;
+    assert!(validate_json_from_str(&cddl, "1.5", None).is_err());
+    assert_eq!(
+      convert(json!({ "type": "number", "minimum": 0, "maximum": 1.5 })),
+      (
+        reformat("root = number .ge 0"),
+        vec!["#: bounds converted to lower bound only".to_string()]
+      )
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn verify_members() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let (cddl, warnings) = convert(json!({
+      "type": "object",
+      "properties": {
+        "id": { "type": "integer" },
+        "name": { "type": "string" }
+      },
+      "required": ["id", "owner"],
+      "additionalProperties": { "type": "boolean" }
+    }));
+    assert_eq!(
+      cddl,
+      reformat("root = { id: int, ? name: tstr, owner: any, * tstr => bool }")
+    );
+    assert!(warnings.is_empty());
+
+    let validate = |json: serde_json::Value| validate_json_from_str(&cddl, &json.to_string(), None);
+    validate(json!({ "id": 1, "owner": null }))?;
+    validate(json!({ "id": 1, "name": "a", "owner": "b", "extra": true }))?;
+    assert!(validate(json!({ "name": "a", "owner": "b" })).is_err());
+    assert!(validate(json!({ "id": 1 })).is_err());
+    assert!(validate(json!({ "id": 1, "owner": "b", "extra": 1 })).is_err());
+
+    // Objects are open by default
+    let (cddl, _) = convert(json!({ "properties": { "id": { "type": "integer" } } }));
+    assert_eq!(cddl, reformat("root = { ? id: int, * tstr => any }"));
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_arrays() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let items = |bounds: serde_json::Value| {
+      let mut schema = json!({ "type": "array", "items": { "type": "string" } });
+      schema
+        .as_object_mut()
+        .unwrap()
+        .extend(bounds.as_object().unwrap().clone());
+      convert(schema).0
+    };
+
+    assert_eq!(items(json!({})), reformat("root = [* tstr]"));
+    assert_eq!(items(json!({ "minItems": 0 })), reformat("root = [* tstr]"));
+    assert_eq!(items(json!({ "minItems": 1 })), reformat("root = [+ tstr]"));
+    assert_eq!(
+      items(json!({ "maxItems": 3 })),
+      reformat("root = [*3 tstr]")
+    );
+    assert_eq!(
+      items(json!({ "minItems": 2, "maxItems": 5 })),
+      reformat("root = [2*5 tstr]")
+    );
+
+    let cddl = items(json!({ "minItems": 2, "maxItems": 3 }));
+    let validate = |json: serde_json::Value| validate_json_from_str(&cddl, &json.to_string(), None);
+    validate(json!(["a", "b"]))?;
+    validate(json!(["a", "b", "c"]))?;
+    assert!(validate(json!(["a"])).is_err());
+    assert!(validate(json!(["a", "b", "c", "d"])).is_err());
+    assert!(validate(json!(["a", 1])).is_err());
+
+    let (cddl, warnings) = convert(json!({
+      "type": "array",
+      "prefixItems": [{ "type": "string" }],
+      "items": { "type": "integer" },
+      "maxItems": 3
+    }));
+    assert_eq!(cddl, reformat("root = [tstr, * int]"));
+    assert_eq!(
+      warnings,
+      vec!["#: minItems and maxItems of tuples are not converted"]
+    );
+
+    // Draft 7 tuples are open unless additionalItems says otherwise
+    let (cddl, _) = convert(json!({ "type": "array", "items": [{ "type": "string" }] }));
+    assert_eq!(cddl, reformat("root = [tstr, * any]"));
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_patterns() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let (cddl, warnings) = convert(json!({ "type": "string", "pattern": "^[a-z]+-[0-9]+$" }));
+    assert_eq!(cddl, reformat("root = tstr .regexp \"^[a-z]+-[0-9]+$\""));
+    assert!(warnings.is_empty());
+
+    let validate = |json: serde_json::Value| validate_json_from_str(&cddl, &json.to_string(), None);
+    validate(json!("abc-123"))?;
+    assert!(validate(json!("ABC-123")).is_err());
+    assert!(validate(json!("abc-")).is_err());
+
+    let (cddl, warnings) = convert(json!({ "type": "string", "pattern": "^a", "maxLength": 3 }));
+    assert_eq!(cddl, reformat("root = tstr .regexp \"^a\""));
+    assert_eq!(
+      warnings,
+      vec!["#: minLength and maxLength can't be combined with pattern"]
+    );
+
+    let (cddl, _) = convert(json!({ "patternProperties": { "^x-": { "type": "integer" } } }));
+    assert_eq!(
+      cddl,
+      reformat("root = { * tstr .regexp \"^x-\" => int, * tstr => any }")
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_recursive_references() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let (cddl, warnings) = convert(json!({
+      "$ref": "#/$defs/node",
+      "$defs": {
+        "node": {
+          "type": "object",
+          "properties": {
+            "value": { "type": "integer" },
+            "children": { "type": "array", "items": { "$ref": "#/$defs/node" } }
+          },
+          "required": ["value"],
+          "additionalProperties": false
+        }
+      }
+    }));
+    assert_eq!(
+      cddl,
+      reformat("root = node\nnode = { ? children: [* node], value: int }")
+    );
+    assert!(warnings.is_empty());
+
+    let validate = |json: serde_json::Value| validate_json_from_str(&cddl, &json.to_string(), None);
+    validate(json!({ "value": 1, "children": [{ "value": 2, "children": [{ "value": 3 }] }] }))?;
+    assert!(validate(json!({ "value": 1, "children": [{ "children": [] }] })).is_err());
+
+    // The root schema is referred to as #
+    let (cddl, _) = convert(json!({
+      "type": "array",
+      "items": { "anyOf": [{ "type": "integer" }, { "$ref": "#" }] }
+    }));
+    assert_eq!(cddl, reformat("root = [* int / root]"));
+
+    let (cddl, warnings) = convert(json!({ "$ref": "other.json#/node" }));
+    assert_eq!(cddl, reformat("root = any"));
+    assert_eq!(
+      warnings,
+      vec![
+        "#: $ref \"other.json#/node\" doesn't refer to the root schema or a definition, converted to any"
+      ]
+    );
+
+    Ok(())
+  }
+}
@@ -5,11 +5,6 @@ use super::{
   },
   token::{self, ByteValue, Token, Value},
 };
-use codespan_reporting::{
-  diagnostic::{Diagnostic, Label},
-  files::SimpleFiles,
-  term,
-};
 use std::{
   fmt,
   iter::Peekable,
@@ -17,6 +12,12 @@ use std::{
   str::{self, CharIndices},
 };
 
+#[cfg(feature = "std")]
+use codespan_reporting::{
+  diagnostic::{Diagnostic, Label},
+  files::SimpleFiles,
+  term,
+};
 #[cfg(feature = "std")]
 use std::{borrow::Cow, string};
 
@@ -24,10 +25,11 @@ use std::{borrow::Cow, string};
 use alloc::{
   borrow::Cow,
   string::{self, String, ToString},
-  vec::Vec,
 };
 use lexical_core as lexical;
 
+#[cfg(feature = "ast-span")]
+use crate::ast::Span;
 #[cfg(target_arch = "wasm32")]
 use serde::Serialize;
 
@@ -84,12 +86,85 @@ pub enum LexerErrorType {
   /// Error parsing float
   PARSEFLOAT(lexical::Error),
   /// Error parsing hexfloat
+  #[cfg(feature = "std")]
   PARSEHEXF(hexf_parse::ParseHexfError),
 }
 
+impl Error {
+  /// Position of the input at which the error occurred
+  pub fn position(&self) -> Position {
+    self.position
+  }
+
+  // Message describing the error at its position
+  pub(crate) fn label_message(&self) -> String {
+    match &self.error_type {
+      LexerErrorType::LEXER(le) => ErrorMsg::from(*le).to_string(),
+      LexerErrorType::UTF8(utf8e) => utf8e.to_string(),
+      LexerErrorType::BASE16(b16e) => b16e.to_string(),
+      LexerErrorType::BASE64(b64e) => b64e.to_string(),
+      LexerErrorType::PARSEINT(pie) => pie.to_string(),
+      LexerErrorType::PARSEFLOAT(pfe) => format!("{:#?}", pfe),
+      #[cfg(feature = "std")]
+      LexerErrorType::PARSEHEXF(phf) => format!("{:#?}", phf),
+    }
+  }
+}
+
 #[cfg(feature = "std")]
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match &self.error_type {
+      LexerErrorType::UTF8(utf8e) => Some(utf8e),
+      LexerErrorType::PARSEINT(pie) => Some(pie),
+      LexerErrorType::PARSEHEXF(phf) => Some(phf),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for Error {
+  fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+    Some(Box::new("cddl::lexer"))
+  }
+
+  fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+    match &self.error_type {
+      LexerErrorType::LEXER(le) => ErrorMsg::from(*le)
+        .extended
+        .map(|e| Box::new(e) as Box<dyn fmt::Display>),
+      _ => None,
+    }
+  }
 
+  fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+    Some(&self.input)
+  }
+
+  fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+    Some(Box::new(std::iter::once(miette::LabeledSpan::new(
+      Some(self.label_message()),
+      self.position.range.0,
+      self.position.range.1 - self.position.range.0,
+    ))))
+  }
+}
+
+#[cfg(not(feature = "std"))]
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "lexer error at {}:{}: {}",
+      self.position.line,
+      self.position.column,
+      self.label_message()
+    )
+  }
+}
+
+#[cfg(feature = "std")]
 impl fmt::Display for Error {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     let mut files = SimpleFiles::new();
@@ -254,6 +329,7 @@ impl From<(&str, Position, lexical::Error)> for Error {
   }
 }
 
+#[cfg(feature = "std")]
 impl From<(&str, Position, hexf_parse::ParseHexfError)> for Error {
   fn from(e: (&str, Position, hexf_parse::ParseHexfError)) -> Self {
     Error {
@@ -303,6 +379,92 @@ pub fn lexer_from_str(str_input: &str) -> Lexer {
   Lexer::new(str_input)
 }
 
+/// Stream of the tokens of a CDDL document along with their spans, including
+/// the whitespace, newline and comment trivia the parser skips. The spans of
+/// the tokens cover the whole input without gaps or overlaps, which makes the
+/// stream suitable for syntax highlighters. Sequences of characters that fail
+/// to lex are yielded as [`Token::ILLEGAL`] rather than ending the stream
+///
+/// # Example
+///
+/// ```
+/// use cddl::{lexer::token_stream, token::Token};
+///
+/// let tokens = token_stream("a = uint ; count").collect::<Vec<_>>();
+/// assert_eq!(tokens[0], (Token::IDENT("a", None), (0, 1, 1)));
+/// assert_eq!(tokens[1], (Token::WHITESPACE(" "), (1, 2, 1)));
+/// assert_eq!(tokens[6], (Token::COMMENT(" count"), (9, 16, 1)));
+/// ```
+#[cfg(feature = "ast-span")]
+pub struct TokenStream<'a> {
+  input: &'a str,
+  offset: usize,
+  line: usize,
+}
+
+/// Creates a `TokenStream` from a string slice
+#[cfg(feature = "ast-span")]
+pub fn token_stream(input: &str) -> TokenStream<'_> {
+  TokenStream {
+    input,
+    offset: 0,
+    line: 1,
+  }
+}
+
+#[cfg(feature = "ast-span")]
+impl<'a> Iterator for TokenStream<'a> {
+  type Item = (Token<'a>, Span);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let rest = &self.input[self.offset..];
+    let c = rest.chars().next()?;
+
+    let (token, len) = if c == '\n' || rest.starts_with("\r\n") {
+      (Token::NEWLINE, if c == '\n' { 1 } else { 2 })
+    } else if c.is_whitespace() {
+      let len = rest
+        .find(|c: char| !c.is_whitespace() || c == '\n' || c == '\r')
+        .unwrap_or(rest.len())
+        // A lone carriage return is whitespace
+        .max(c.len_utf8());
+      (Token::WHITESPACE(&rest[..len]), len)
+    } else if c == ';' {
+      let len = rest.find(&['\n', '\r'][..]).unwrap_or(rest.len());
+      (Token::COMMENT(&rest[1..len]), len)
+    } else {
+      let mut lexer = Lexer::new(rest);
+      let token = lexer.next_token();
+      // Tokens end where the lexer stopped reading
+      let len = lexer
+        .input
+        .peek()
+        .map(|(idx, _)| *idx)
+        .unwrap_or(rest.len());
+
+      match token {
+        Ok((_, token)) if len > 0 && token != Token::EOF => (token, len),
+        // Skip to the next whitespace, or past the offending character if the
+        // lexer didn't advance
+        _ => {
+          let len = rest
+            .find(char::is_whitespace)
+            .filter(|len| *len > 0)
+            .unwrap_or(c.len_utf8())
+            .max(len.min(rest.len()));
+          (Token::ILLEGAL(&rest[..len]), len)
+        }
+      }
+    };
+
+    let span = (self.offset, self.offset + len, self.line);
+    self.line += rest[..len].matches('\n').count();
+    self.offset += len;
+
+    Some((token, span))
+  }
+}
+
 impl<'a> Lexer<'a> {
   /// Creates a new `Lexer` from a given `&str` input
   pub fn new(str_input: &'a str) -> Lexer<'a> {
@@ -486,6 +648,17 @@ impl<'a> Lexer<'a> {
             match self.peek_char() {
               Some(&c) if c.1 == '.' => {
                 let _ = self.read_char()?;
+
+                // Tag number given as a type, e.g. `#6.<uint>`, which is lexed
+                // separately
+                if let Some(&c) = self.peek_char() {
+                  if c.1 == '<' {
+                    self.position.range = (token_offset, self.position.index + 1);
+
+                    return Ok((self.position, Token::TAG(Some(t as u8), None)));
+                  }
+                }
+
                 let (idx, _) = self.read_char()?;
 
                 self.position.range = (token_offset, self.position.index + 1);
@@ -539,12 +712,20 @@ impl<'a> Lexer<'a> {
               return Ok((self.position, Token::RANGEOP(true)));
             } else if is_ealpha(c.1) {
               // Controlop
-              let ctrlop =
-                token::lookup_control_from_str(self.read_identifier(idx)?).ok_or_else(|| {
+              let ident = self.read_identifier(idx)?;
+              let ctrlop = match token::lookup_control_from_str(ident) {
+                Some(ctrlop) => ctrlop,
+                // Unknown control operators are left to be implemented by
+                // control operator plugins
+                #[cfg(feature = "control-plugins")]
+                None => Token::CONTROL(ident),
+                #[cfg(not(feature = "control-plugins"))]
+                None => {
                   self.position.range = (token_offset, self.position.index + 1);
 
-                  Error::from((self.str_input, self.position, InvalidControlOperator))
-                })?;
+                  return Err((self.str_input, self.position, InvalidControlOperator).into());
+                }
+              };
 
               self.position.range = (token_offset, self.position.index + 1);
               return Ok((self.position, ctrlop));
@@ -624,7 +805,10 @@ impl<'a> Lexer<'a> {
 
           self.position.range = (token_offset, self.position.index + 1);
 
-          Ok((self.position, Token::ILLEGAL(&self.str_input[idx..=idx])))
+          Ok((
+            self.position,
+            Token::ILLEGAL(&self.str_input[idx..idx + ch.len_utf8()]),
+          ))
         }
       }
     } else {
@@ -634,28 +818,37 @@ impl<'a> Lexer<'a> {
   }
 
   fn read_identifier(&mut self, idx: usize) -> Result<&'a str> {
-    let mut end_idx = idx;
+    // Index past the last character read, which may span multiple bytes
+    let mut end_idx = idx
+      + self.str_input[idx..]
+        .chars()
+        .next()
+        .map_or(0, |c| c.len_utf8());
 
     while let Some(&c) = self.peek_char() {
       if is_ealpha(c.1) || is_digit(c.1) || c.1 == '.' || c.1 == '-' {
         match c.1 {
           // Check for range
           '.' => {
-            end_idx = self.read_char()?.0;
+            let dot_idx = self.read_char()?.0;
+            end_idx = dot_idx + 1;
 
             if let Some(&c) = self.peek_char() {
               if c.1 == '\u{0020}' {
-                return Ok(&self.str_input[idx..end_idx]);
+                return Ok(&self.str_input[idx..dot_idx]);
               }
             }
           }
-          _ => end_idx = self.read_char()?.0,
+          _ => {
+            let (char_idx, ch) = self.read_char()?;
+            end_idx = char_idx + ch.len_utf8();
+          }
         }
       } else {
         break;
       }
     }
-    Ok(&self.str_input[idx..=end_idx])
+    Ok(&self.str_input[idx..end_idx])
   }
 
   fn read_text_value(&mut self, idx: usize) -> Result<&'a str> {
@@ -875,14 +1068,12 @@ impl<'a> Lexer<'a> {
 
             if is_signed {
               return Ok(Token::VALUE(Value::FLOAT(
-                hexf_parse::parse_hexf64(&self.str_input[signed_idx..=end_idx], false)
-                  .map_err(|e| Error::from((self.str_input, self.position, e)))?,
+                self.parse_hexfloat(&self.str_input[signed_idx..=end_idx])?,
               )));
             }
 
             return Ok(Token::VALUE(Value::FLOAT(
-              hexf_parse::parse_hexf64(&self.str_input[idx..=end_idx], false)
-                .map_err(|e| Error::from((self.str_input, self.position, e)))?,
+              self.parse_hexfloat(&self.str_input[idx..=end_idx])?,
             )));
           }
 
@@ -1005,11 +1196,61 @@ impl<'a> Lexer<'a> {
     Ok((end_index, &self.str_input[idx..=end_index]))
   }
 
+  #[cfg(feature = "std")]
+  fn parse_hexfloat(&self, hexfloat: &str) -> Result<f64> {
+    hexf_parse::parse_hexf64(hexfloat, false)
+      .map_err(|e| Error::from((self.str_input, self.position, e)))
+  }
+
+  #[cfg(not(feature = "std"))]
+  fn parse_hexfloat(&self, hexfloat: &str) -> Result<f64> {
+    parse_hexf64(hexfloat).ok_or_else(|| (self.str_input, self.position, InvalidHexFloat).into())
+  }
+
   fn peek_char(&mut self) -> Option<&(usize, char)> {
     self.input.peek()
   }
 }
 
+// Parses a hexfloat of the form `[-]0x<hexdigits>[.<hexdigits>]p[+-]<digits>`
+// in the absence of `hexf-parse`, which requires `std`. Mantissas of more than
+// 16 significant hex digits are rejected
+#[cfg(not(feature = "std"))]
+fn parse_hexf64(hexfloat: &str) -> Option<f64> {
+  let (negative, hexfloat) = match hexfloat.strip_prefix('-') {
+    Some(hexfloat) => (true, hexfloat),
+    None => (false, hexfloat),
+  };
+  let hexfloat = hexfloat
+    .strip_prefix("0x")
+    .or_else(|| hexfloat.strip_prefix("0X"))?;
+  let (mantissa, exponent) = hexfloat.split_once(|c| c == 'p' || c == 'P')?;
+  let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+  let digits = integer.trim_start_matches('0').len() + fraction.len();
+  if integer.is_empty() && fraction.is_empty() || digits > 16 {
+    return None;
+  }
+
+  let mut value = 0u64;
+  for c in integer.chars().chain(fraction.chars()) {
+    value = (value << 4) | c.to_digit(16)? as u64;
+  }
+
+  let mut exponent = exponent.parse::<i32>().ok()? - 4 * fraction.len() as i32;
+  let mut value = value as f64;
+  while exponent > 0 && value.is_finite() {
+    value *= 2.0;
+    exponent -= 1;
+  }
+  while exponent < 0 && value != 0.0 {
+    value /= 2.0;
+    exponent += 1;
+  }
+
+  Some(if negative { -value } else { value })
+}
+
 fn is_ealpha(ch: char) -> bool {
   ch.is_alphabetic() || ch == '@' || ch == '_' || ch == '$'
 }
@@ -1371,4 +1612,60 @@ mod tests {
       }
     }
   }
+
+  #[cfg(feature = "ast-span")]
+  #[test]
+  fn verify_token_stream() {
+    let input = indoc!(
+      r#"
+        ; A comment
+        message = { type: "x" / 1..3, ? $$ext, * tstr => bstr .size 8 }
+        id = #6.32(tstr) ; trailing
+        bad = h'zz' é
+      "#
+    )
+    .replacen("{ ", "{\t", 1)
+    .replacen("}\n", "}\r\n", 1);
+    let input = input.as_str();
+
+    let tokens = token_stream(input).collect::<Vec<_>>();
+
+    // Spans cover the whole input without gaps or overlaps
+    let mut offset = 0;
+    for (_, (start, end, _)) in tokens.iter() {
+      assert_eq!(*start, offset);
+      assert!(end > start);
+      offset = *end;
+    }
+    assert_eq!(offset, input.len());
+
+    let significant = tokens
+      .iter()
+      .filter(|(t, _)| !matches!(t, WHITESPACE(_) | NEWLINE))
+      .map(|(t, (start, end, line))| (t.clone(), &input[*start..*end], *line))
+      .collect::<Vec<_>>();
+    assert_eq!(significant[0], (COMMENT(" A comment"), "; A comment", 1));
+    assert_eq!(significant[1], (IDENT("message", None), "message", 2));
+    assert_eq!(significant[6], (VALUE(Value::TEXT("x".into())), "\"x\"", 2));
+    assert_eq!(significant[9], (RANGEOP(true), "..", 2));
+    assert_eq!(
+      significant[13],
+      (IDENT("ext", Some(SocketPlug::GROUP)), "$$ext", 2)
+    );
+    assert_eq!(significant[17], (ARROWMAP, "=>", 2));
+    assert_eq!(significant[19], (SIZE, ".size", 2));
+    assert_eq!(significant[24], (TAG(Some(6), Some(32)), "#6.32", 3));
+    assert_eq!(significant[28], (COMMENT(" trailing"), "; trailing", 3));
+    assert_eq!(significant[31], (ILLEGAL("h'zz'"), "h'zz'", 4));
+    assert_eq!(significant[32], (IDENT("é", None), "é", 4));
+
+    assert!(tokens.contains(&(WHITESPACE("\t"), (23, 24, 2))));
+    assert_eq!(
+      tokens
+        .iter()
+        .filter(|(t, (start, end, _))| *t == NEWLINE && &input[*start..*end] == "\r\n")
+        .count(),
+      1
+    );
+  }
 }
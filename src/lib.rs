@@ -46,7 +46,7 @@
 //! - [ ] Generate dummy JSON from conformant CDDL
 //! - [x] As close to zero-copy as possible
 //! - [x] Compile WebAssembly target for browser and Node.js
-//! - [x] `no_std` support (lexing and parsing only)
+//! - [x] `no_std` support (lexing, parsing and CBOR validation)
 //! - [x] Language server implementation and Visual Studio Code Extension
 //!
 //! ## Non-goals
@@ -133,6 +133,116 @@
 //! docker run -i --rm -v $PWD:/data -w /data ghcr.io/anweiss/cddl-cli:0.9.0 validate --cddl reputon.cddl --stdin < reputon.json
 //! ```
 //!
+//! To validate many files at once, pass glob patterns (quoted so the shell
+//! doesn't expand them). Files are validated in parallel, a summary of passed
+//! and failed files is printed, and the command exits with a non-zero status if
+//! any file fails validation. A report can optionally be written in JSON or
+//! JUnit XML format for CI systems:
+//!
+//! ```sh
+//! cddl validate --cddl spec.cddl --cbor 'fixtures/**/*.cbor' --report report.xml --report-format junit
+//! ```
+//!
+//! By default, data is validated against the first type rule in the CDDL
+//! document. Use `--rule` to validate against any other rule instead. Generic
+//! rules are given their arguments inline:
+//!
+//! ```sh
+//! cddl validate --cddl message.cddl --rule "message<tstr, uint>" --json message.json
+//! ```
+//!
+//! To build a fast regression suite from a large corpus of valid instances
+//! (e.g. captured production traffic), a small subset exercising every rule
+//! and type choice exercised by the whole corpus can be selected. The paths of
+//! the selected files are printed, and files failing validation are skipped:
+//!
+//! ```sh
+//! cddl distill --cddl spec.cddl --cbor 'corpus/**/*.cbor' | xargs -I{} cp {} regression/
+//! ```
+//!
+//! To share realistic test data without leaking production content, the text
+//! strings, byte strings, numbers and map keys of valid instances can be
+//! replaced with synthetic values that still conform to the CDDL document. The
+//! structure of each instance is kept, as are values equal to literals of the
+//! document. Anonymized files are written to the output directory under the same
+//! relative paths:
+//!
+//! ```sh
+//! cddl anonymize --cddl spec.cddl --json 'corpus/**/*.json' --output-dir shared/ --seed 42
+//! ```
+//!
+//! To make a validation reproducible elsewhere, e.g. to attach to a bug report,
+//! the CDDL document, the options, the instance and the decisions taken to
+//! validate it (which type choice matched at each location and why the preceding
+//! choices did not) can be recorded into a single replay file. With `--redact`,
+//! the content of the instance is replaced with synthetic values keeping its
+//! structure. The `replay` subcommand validates the recorded instance again and
+//! exits with a non-zero status if the outcome differs from the recorded one:
+//!
+//! ```sh
+//! cddl validate --cddl spec.cddl --json account.json --record account.replay --redact
+//! cddl replay account.replay --trace
+//! ```
+//!
+//! You can also report semantic differences between two revisions of a CDDL
+//! document. Whitespace and comment changes are ignored, and each changed rule is
+//! classified as either backwards-compatible or breaking. The command exits with a
+//! non-zero status if any breaking change is detected, making it suitable for
+//! release pipelines:
+//!
+//! ```sh
+//! cddl diff old.cddl new.cddl
+//! ```
+//!
+//! Rules can be printed in a canonical order, starting with the root rule and
+//! followed by the rules it references in topological order (or alphabetically
+//! with `--order alphabetical`), so that revisions of a document can be published
+//! and compared consistently:
+//!
+//! ```sh
+//! cddl sort --cddl spec.cddl --order topological --output spec.sorted.cddl
+//! ```
+//!
+//! Constructs that are valid CDDL but likely mistakes can be reported with the
+//! `lint` subcommand, which flags unused rules, names shadowing rules or prelude
+//! types, duplicate map keys, occurrence indicators that have no effect, ranges
+//! with inverted bounds and constraints no value satisfies, such as `.gt 100000`
+//! on a rule defined as `uint .size 2` or a `.default` value outside its type.
+//! Each diagnostic has a severity and points at the offending source, and the
+//! command exits with a non-zero status if any error is reported:
+//!
+//! ```sh
+//! cddl lint --cddl spec.cddl
+//! ```
+//!
+//! Documentation can be generated in Markdown or HTML from the comments
+//! describing rules and group entries. Comments on the lines immediately preceding
+//! a rule or entry, or trailing it on the same line, describe it, and each rule
+//! links to the rules it references and is referenced by:
+//!
+//! ```sh
+//! cddl docs --cddl spec.cddl --format html --output spec.html
+//! ```
+//!
+//! To compare schema designs or crate versions, the time spent compiling a CDDL
+//! document, parsing a JSON document or CBOR binary file and validating it can be
+//! measured separately. Each phase reports the minimum, mean, standard deviation,
+//! median, 95th percentile and maximum duration over the given number of
+//! iterations, optionally limited to a number of seconds per phase:
+//!
+//! ```sh
+//! cddl bench reputon.cddl reputon.cbor --iterations 1000 --time-limit 5
+//! ```
+//!
+//! The CBOR validator borrows the nested data items it validates from the
+//! decoded data item rather than copying them. The heap allocations made while
+//! decoding and validating a large map-heavy CBOR data item are measured by a
+//! benchmark in this repository:
+//!
+//! ```sh
+//! cargo bench --bench cbor_validation
+//! ```
+//!
 //! ## Website
 //!
 //! You can also find a simple RFC 8610 conformance tool at
@@ -183,7 +293,9 @@
 //! cddl = "0.9.0"
 //! ```
 //!
-//! Both JSON and CBOR validation require `std`.
+//! JSON validation requires `std`. CBOR validation is also available in
+//! `no_std`, with the limitations described in [`no_std`
+//! support](#no_std-support).
 //!
 //! ### Feature flags
 //!
@@ -207,7 +319,7 @@
 //!
 //! **`--feature cbor`**
 //!
-//! Enable CBOR validation. Enabled by default.
+//! Enable CBOR validation. Enabled by default. Does not require `std`.
 //!
 //! **`--feature additional-controls`**
 //!
@@ -215,6 +327,28 @@
 //! [RFC 9165](https://datatracker.ietf.org/doc/html/rfc9165). Enabled by
 //! default.
 //!
+//! **`--feature miette`**
+//!
+//! Implement [`miette::Diagnostic`](https://docs.rs/miette) for lexer, parser
+//! and validation errors, labeling the spans of the input at which each error
+//! occurred. Validation errors label the CDDL type or group entry the data
+//! failed to match; since the validators do not retain the CDDL source, attach
+//! it with `miette::Report::with_source_code` to render the labeled snippets.
+//!
+//! **`--feature pcre`**
+//!
+//! Add `RegexEngine::Pcre`, which matches the `.regexp` and `.pcre` control
+//! operators with the backtracking engine of
+//! [fancy-regex](https://docs.rs/fancy-regex), supporting lookaround
+//! assertions and backreferences. Select it with `set_regex_engine` on the
+//! JSON and CBOR validators. Not enabled by default.
+//!
+//! **`--feature python`**
+//!
+//! Build Python bindings with [PyO3](https://pyo3.rs), exposing `parse`,
+//! `validate_json` and `validate_cbor` in a `cddl` extension module. Not enabled
+//! by default. See [Python bindings](#python-bindings).
+//!
 //! ### Parsing CDDL
 //!
 //! ```rust
@@ -374,7 +508,9 @@
 //! [CDDL-Freezer
 //! proposal](https://tools.ietf.org/html/draft-bormann-cbor-cddl-freezer-03#section-5.1)).
 //! Ensure that your regex string is properly JSON escaped when using this
-//! control.
+//! control. Patterns are matched with the linear-time engine of the `regex`
+//! crate by default, which rejects lookaround assertions and backreferences;
+//! the `pcre` feature adds a backtracking engine supporting them.
 //!
 //! If you've enabled the `additional-controls` feature, the table of controls
 //! below is also available for use:
@@ -404,6 +540,14 @@
 //! assert!(validate_json_from_str(cddl, json, Some(&["json"])).is_ok())
 //! ```
 //!
+//! Types guarded by a feature only match if the feature is enabled, so a single
+//! document can describe several variants of a protocol, e.g. a base and an
+//! extended profile. All features are enabled if no features are given. Matches
+//! of features that aren't enabled are rejected by default; call
+//! `set_disabled_feature_behavior(DisabledFeatureBehavior::Warn)` on the
+//! validator to accept them instead. The features each instance used, and where,
+//! are returned by `feature_uses` on the validator after validation.
+//!
 //! #### Comparing with JSON schema and JSON schema language
 //!
 //! [CDDL](https://tools.ietf.org/html/rfc8610), [JSON
@@ -489,9 +633,54 @@
 //! assert!(validate_cbor_from_slice(cddl, cbor, Some(&["cbor"])).is_ok())
 //! ```
 //!
+//! ### Stable API
+//!
+//! The `cddl::stable` module is a facade over parsing and validation that
+//! only changes in semver-compatible ways, for downstream crates that would
+//! rather not track changes to the internal AST. Documents are parsed into a
+//! versioned, owned view of the AST (`cddl::stable::ast::v1`), and validation
+//! errors are reported with a single error type for JSON and CBOR:
+//!
+//! ```rust
+//! use cddl::stable::{self, Error, Schema};
+//!
+//! let document = stable::parse("message = { id: uint }").unwrap();
+//! assert_eq!(document.rules[0].name, "message");
+//!
+//! let schema = Schema::new("message = { id: uint }").unwrap();
+//! match schema.validate_cbor(b"\xa1\x62id\x20") {
+//!   Err(Error::Validation(errors)) => assert_eq!(errors[0].byte_range, Some((4, 5))),
+//!   result => panic!("expected validation errors, got {:?}", result),
+//! }
+//! ```
+//!
+//! ## Python bindings
+//!
+//! With the `python` feature, the crate can be built as a Python extension
+//! module, e.g. with [maturin](https://www.maturin.rs) (`maturin build --features
+//! python,pyo3/extension-module`). As with the WebAssembly bindings, a document
+//! can be parsed once into a `Schema` and used to validate many JSON documents or
+//! CBOR data items. Invalid documents raise `cddl.ParseError` and invalid data
+//! raises `cddl.ValidationError`, both subclasses of `cddl.CDDLError`, whose
+//! `errors` attribute lists the individual errors as dicts:
+//!
+//! ```python
+//! import cddl
+//!
+//! schema = cddl.parse(text)
+//! try:
+//!     schema.validate_json(json, root_rule="person")
+//! except cddl.ValidationError as e:
+//!     for error in e.errors:
+//!         print(f"{error['location']} ({error['rule']}): {error['reason']}")
+//! ```
+//!
+//! `cddl.validate_json(cddl, json)` and `cddl.validate_cbor(cddl, cbor)` parse the
+//! document and validate in a single call.
+//!
 //! ## `no_std` support
 //!
-//! Only the lexer and parser can be used in a `no_std` context provided that a
+//! The lexer, parser and AST can be used in a `no_std` context provided that a
 //! heap allocator is available. This can be enabled by opting out of the
 //! default features in your `Cargo.toml` file as follows:
 //!
@@ -503,10 +692,27 @@
 //! Zero-copy parsing is implemented to the extent that is possible. Allocation
 //! is required for error handling and diagnostics.
 //!
-//! Both JSON and CBOR validation are dependent on their respective heap
-//! allocated `Value` types, but since these types aren't supported in a
-//! `no_std` context, they subsequently aren't supported by this crate in
-//! `no_std`.
+//! CBOR validation, e.g. of messages received by an embedded device, can
+//! additionally be enabled in `no_std` with the `cbor` feature:
+//!
+//! ```toml
+//! [dependencies]
+//! cddl = { version = "0.9.0", default-features = false, features = ["cbor"] }
+//! ```
+//!
+//! The `no_std` CBOR validator is reduced in the following ways:
+//!
+//! - The `.regexp`, `.pcre`, `.abnf` and `.abnfb` control operators are
+//!   reported as unsupported
+//! - Values of the `tdate`, `time`, `uri` and `b64url` prelude types are only
+//!   checked against their underlying data type
+//! - Parser and lexer errors are reported as plain text rather than annotated
+//!   snippets of the input
+//! - `cbor::Error` does not implement `std::error::Error`, and the CBOR reader
+//!   error type is `ciborium_io::EndOfFile` rather than `std::io::Error` (see
+//!   `cbor::ReaderError`)
+//!
+//! JSON validation depends on `serde_json` and requires `std`.
 //!
 //! ## Projects using this crate
 //!
@@ -527,6 +733,11 @@ extern crate alloc;
 #[cfg(not(feature = "std"))]
 extern crate core as std;
 
+// The `cdylib` crate type needs a panic handler and a global allocator, which
+// hosted targets get from std even when the crate itself is `no_std`
+#[cfg(all(not(feature = "std"), not(target_os = "none")))]
+extern crate std as _;
+
 #[cfg(feature = "std")]
 extern crate serde_json;
 
@@ -536,19 +747,97 @@ extern crate uriparse;
 #[cfg(feature = "std")]
 extern crate base64_url;
 
+/// Structural analysis of CDDL types
+#[cfg(feature = "std")]
+pub mod analysis;
+/// Annotations embedded in rule comments
+pub mod annotations;
+/// Anonymization of instances with synthetic values conforming to CDDL
+pub mod anonymize;
 /// Abstract syntax tree representing a CDDL definition
 pub mod ast;
+/// Programmatic construction of CDDL documents
+#[cfg(feature = "std")]
+pub mod builder;
+/// Parsing of CBOR diagnostic notation
+#[cfg(feature = "std")]
+#[cfg(feature = "cbor")]
+pub mod diag;
+/// Semantic differences between CDDL documents
+#[cfg(feature = "std")]
+pub mod diff;
+/// Distillation of instance corpora by the rules and type choices they exercise
+pub mod distill;
+/// Documentation generation from the comments of CDDL documents
+pub mod docs;
 /// Static error messages
 #[allow(missing_docs)]
 pub mod error;
+/// Schema-aware mutation of instances for fuzzing
+#[cfg(all(feature = "std", any(feature = "json", feature = "cbor")))]
+#[cfg(not(feature = "lsp"))]
+pub mod fuzz;
+/// Example generation from CDDL
+#[cfg(all(feature = "std", any(feature = "json", feature = "cbor")))]
+#[cfg(not(feature = "lsp"))]
+pub mod generator;
+/// Generic rule resolution
+#[cfg(feature = "std")]
+pub mod generics;
+/// Rule dependency graph
+#[cfg(feature = "std")]
+pub mod graph;
+/// Conversion of JSON Schema documents to CDDL
+#[cfg(feature = "std")]
+pub mod json_schema;
 /// Lexer for CDDL
 pub mod lexer;
+/// Lints for CDDL documents
+#[cfg(feature = "std")]
+pub mod lint;
+/// Node identities and parent lookup for AST nodes
+#[cfg(feature = "std")]
+pub mod node;
+/// Source text normalization
+pub mod normalize;
+/// Canonical ordering of rules
+#[cfg(feature = "std")]
+pub mod order;
 /// Parser for CDDL
 pub mod parser;
+/// Metadata of the types of the standard prelude
+pub mod prelude;
+/// Python bindings
+#[cfg(feature = "python")]
+pub mod python;
+/// Memoized analyses of CDDL documents being edited
+#[cfg(feature = "std")]
+#[cfg(not(target_arch = "wasm32"))]
+pub mod query;
+/// Railroad diagrams of rules
+#[cfg(feature = "std")]
+pub mod railroad;
+/// Recording and replay of validation sessions for bug reports
+pub mod replay;
+/// Detection and extraction of inline groups repeated across rules
+#[cfg(feature = "std")]
+pub mod reuse;
+/// Self checks of CDDL documents
+pub mod self_check;
+/// Stable facade over the parsing and validation entry points and a frozen view
+/// of the AST, which only change in semver-compatible ways
+#[cfg(feature = "std")]
+pub mod stable;
+/// Code generation from templates fed with a model of a CDDL document
+#[cfg(feature = "templates")]
+pub mod template;
 /// CDDL tokens for lexing
 pub mod token;
-/// Validators for JSON and CBOR data structures
+/// Unwrap operator resolution
 #[cfg(feature = "std")]
+pub mod unwrap;
+/// Validators for JSON and CBOR data structures
+#[cfg(any(feature = "json", feature = "cbor"))]
 pub mod validator;
 
 /// CDDL AST visitor
@@ -565,6 +854,10 @@ pub use self::{
 
 #[doc(inline)]
 #[cfg(feature = "std")]
+#[cfg(not(target_arch = "wasm32"))]
+pub use self::parser::{cddl_from_bytes_lossy, cddl_from_str_partial};
+
+#[doc(inline)]
 #[cfg(feature = "cbor")]
 #[cfg(not(feature = "lsp"))]
 #[cfg(not(target_arch = "wasm32"))]
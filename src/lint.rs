@@ -0,0 +1,706 @@
+#![cfg(feature = "std")]
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::cddl_from_str;
+use crate::{
+  analysis,
+  ast::*,
+  graph::DependencyGraph,
+  parser,
+  token::lookup_ident,
+  visitor::{self, Visitor},
+};
+
+use codespan_reporting::{
+  diagnostic::{self, Label},
+  files::SimpleFiles,
+  term::{
+    self,
+    termcolor::{ColorChoice, StandardStream},
+  },
+};
+use std::{collections::BTreeSet, convert::Infallible, fmt};
+
+/// Severity of a lint diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+  /// Construct that no data item can satisfy
+  Error,
+  /// Construct that is likely a mistake
+  Warning,
+  /// Construct that is worth knowing about but may be intended
+  Info,
+}
+
+impl fmt::Display for Severity {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Severity::Error => write!(f, "error"),
+      Severity::Warning => write!(f, "warning"),
+      Severity::Info => write!(f, "info"),
+    }
+  }
+}
+
+/// Kind of issue reported by a lint diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Lint {
+  /// Rule that isn't referenced, directly or indirectly, by the root rule (the
+  /// first type rule of the document)
+  UnusedRule,
+  /// Rule or generic parameter named after a standard prelude type, or generic
+  /// parameter named after a rule
+  ShadowedName,
+  /// Member key appearing more than once within a group choice of a map
+  DuplicateKey,
+  /// Occurrence indicator within a group that is enumerated with `&`, where
+  /// it has no effect
+  MisplacedOccurrence,
+  /// Range or occurrence indicator whose lower bound exceeds its upper bound
+  InvertedBounds,
+  /// Range or control operator that no value satisfies given the type it
+  /// restricts, including types defined by other rules, e.g. `.gt 100000` on
+  /// a rule defined as `uint .size 2`, or a `.default` value outside the type
+  UnsatisfiableConstraint,
+}
+
+impl Lint {
+  /// Name of the lint, e.g. for use as a diagnostic code in editors
+  pub fn name(&self) -> &'static str {
+    match self {
+      Lint::UnusedRule => "unused-rule",
+      Lint::ShadowedName => "shadowed-name",
+      Lint::DuplicateKey => "duplicate-key",
+      Lint::MisplacedOccurrence => "misplaced-occurrence",
+      Lint::InvertedBounds => "inverted-bounds",
+      Lint::UnsatisfiableConstraint => "unsatisfiable-constraint",
+    }
+  }
+
+  /// Severity of diagnostics reported by the lint
+  pub fn severity(&self) -> Severity {
+    match self {
+      Lint::InvertedBounds | Lint::UnsatisfiableConstraint => Severity::Error,
+      Lint::ShadowedName | Lint::DuplicateKey | Lint::MisplacedOccurrence => Severity::Warning,
+      Lint::UnusedRule => Severity::Info,
+    }
+  }
+}
+
+impl fmt::Display for Lint {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+/// Issue found by linting a CDDL document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+  /// Lint reporting the issue
+  pub lint: Lint,
+  /// Severity of the issue
+  pub severity: Severity,
+  /// Description of the issue
+  pub message: String,
+  /// Span of the offending construct
+  #[cfg(feature = "ast-span")]
+  pub span: Span,
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    #[cfg(feature = "ast-span")]
+    return write!(
+      f,
+      "{}[{}] at line {}: {}",
+      self.severity, self.lint, self.span.2, self.message
+    );
+
+    #[cfg(not(feature = "ast-span"))]
+    write!(f, "{}[{}]: {}", self.severity, self.lint, self.message)
+  }
+}
+
+/// Lint a CDDL document string. See [`CDDL::lint`]
+#[cfg(not(target_arch = "wasm32"))]
+pub fn lint_from_str(cddl: &str) -> std::result::Result<Vec<Diagnostic>, parser::Error> {
+  Ok(cddl_from_str(cddl, false)?.lint())
+}
+
+/// Render diagnostics against the CDDL document string they were reported for,
+/// labeling the offending source. If `to_stderr` is true, the diagnostics are
+/// printed to stderr with colors and `None` is returned
+pub fn report_diagnostics(
+  input: &str,
+  diagnostics: &[Diagnostic],
+  to_stderr: bool,
+) -> std::result::Result<Option<String>, parser::Error> {
+  let mut files = SimpleFiles::new();
+  let file_id = files.add("input", input);
+
+  let config = term::Config::default();
+  let diagnostics = diagnostics.iter().map(|d| {
+    let diagnostic = match d.severity {
+      Severity::Error => diagnostic::Diagnostic::error(),
+      Severity::Warning => diagnostic::Diagnostic::warning(),
+      Severity::Info => diagnostic::Diagnostic::note(),
+    };
+
+    #[cfg(feature = "ast-span")]
+    let label = Label::primary(file_id, d.span.0..d.span.1);
+    #[cfg(not(feature = "ast-span"))]
+    let label = Label::primary(file_id, 0..0);
+
+    diagnostic
+      .with_code(d.lint.name())
+      .with_message(&d.message)
+      .with_labels(vec![label])
+  });
+
+  if to_stderr {
+    let writer = StandardStream::stderr(ColorChoice::Auto);
+    for diagnostic in diagnostics {
+      term::emit(&mut writer.lock(), &config, &files, &diagnostic)
+        .map_err(parser::Error::REPORTING)?;
+    }
+
+    return Ok(None);
+  }
+
+  let mut buffer = Vec::new();
+  let mut writer = term::termcolor::NoColor::new(&mut buffer);
+  for diagnostic in diagnostics {
+    term::emit(&mut writer, &config, &files, &diagnostic).map_err(parser::Error::REPORTING)?;
+  }
+
+  Ok(Some(
+    String::from_utf8(buffer).map_err(|e| parser::Error::UTF8(e.utf8_error()))?,
+  ))
+}
+
+impl<'a> CDDL<'a> {
+  /// Lint the document, reporting constructs that are valid CDDL but are
+  /// likely mistakes, such as unused rules, shadowed names, duplicate map keys,
+  /// occurrence indicators that have no effect, ranges with inverted bounds and
+  /// constraints contradicting the types they restrict.
+  /// Diagnostics are returned in order of appearance
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use cddl::lint::{lint_from_str, Lint};
+  ///
+  /// let diagnostics = lint_from_str(
+  ///   r#"
+  ///     person = { name: tstr, age: 0..150, name: tstr }
+  ///     score = 10..1
+  ///   "#,
+  /// )
+  /// .unwrap();
+  ///
+  /// assert_eq!(
+  ///   diagnostics.iter().map(|d| d.lint).collect::<Vec<_>>(),
+  ///   vec![Lint::DuplicateKey, Lint::UnusedRule, Lint::InvertedBounds]
+  /// );
+  /// ```
+  pub fn lint(&self) -> Vec<Diagnostic> {
+    let mut diagnostics = self.lint_unused_rules(&self.dependency_graph());
+
+    let mut enumerated = BTreeSet::new();
+    for rule in self.rules.iter() {
+      let lints = self.lint_rule(rule);
+      diagnostics.extend(lints.diagnostics);
+      enumerated.extend(lints.enumerated);
+    }
+
+    diagnostics.extend(self.lint_enumerated_groups(&enumerated));
+
+    sort_diagnostics(&mut diagnostics);
+    diagnostics
+  }
+
+  // Diagnostics that only depend on the definition of a rule and the names of
+  // the rules of the document
+  pub(crate) fn lint_rule(&self, rule: &Rule<'a>) -> RuleLints {
+    let mut lints = Lints::new(self);
+    lints.rule = rule.name();
+
+    lints.shadowed_names(rule);
+    // Linting is infallible
+    let _ = lints.visit_rule(rule);
+
+    RuleLints {
+      diagnostics: lints.diagnostics,
+      enumerated: lints.enumerated,
+    }
+  }
+
+  // Rules that aren't referenced by the root rule, given the dependency graph
+  // of the document
+  pub(crate) fn lint_unused_rules(&self, graph: &DependencyGraph) -> Vec<Diagnostic> {
+    let mut lints = Lints::new(self);
+
+    lints.unused_rules(graph);
+
+    lints.diagnostics
+  }
+
+  // Occurrence indicators of the named groups enumerated by the rules of the
+  // document
+  pub(crate) fn lint_enumerated_groups(&self, enumerated: &BTreeSet<String>) -> Vec<Diagnostic> {
+    let mut lints = Lints::new(self);
+
+    for rule in self.rules.iter() {
+      if let Rule::Group { rule, .. } = rule {
+        if enumerated.contains(&rule.name.to_string()) {
+          lints.rule = rule.name.to_string();
+          lints.enumerated_occurrences(&rule.entry);
+        }
+      }
+    }
+
+    lints.diagnostics
+  }
+}
+
+// Diagnostics of a rule and the names of the groups it enumerates
+#[derive(Debug, Default)]
+pub(crate) struct RuleLints {
+  pub(crate) diagnostics: Vec<Diagnostic>,
+  pub(crate) enumerated: BTreeSet<String>,
+}
+
+// Puts diagnostics in order of appearance
+pub(crate) fn sort_diagnostics(diagnostics: &mut [Diagnostic]) {
+  #[cfg(feature = "ast-span")]
+  diagnostics.sort_by_key(|d| (d.span.0, d.lint));
+  #[cfg(not(feature = "ast-span"))]
+  let _ = diagnostics;
+}
+
+// Collects the diagnostics of a document
+struct Lints<'a, 'b> {
+  cddl: &'b CDDL<'a>,
+  // Name of the rule being visited
+  rule: String,
+  // Names of the groups enumerated with `&groupname`
+  enumerated: BTreeSet<String>,
+  diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a, 'b> Lints<'a, 'b> {
+  fn new(cddl: &'b CDDL<'a>) -> Self {
+    Lints {
+      cddl,
+      rule: String::new(),
+      enumerated: BTreeSet::new(),
+      diagnostics: Vec::new(),
+    }
+  }
+
+  fn report(&mut self, lint: Lint, message: String, #[cfg(feature = "ast-span")] span: Span) {
+    self.diagnostics.push(Diagnostic {
+      lint,
+      severity: lint.severity(),
+      message,
+      #[cfg(feature = "ast-span")]
+      span,
+    });
+  }
+
+  fn unused_rules(&mut self, graph: &DependencyGraph) {
+    // Data is validated against the first type rule unless told otherwise
+    let root = match self
+      .cddl
+      .rules
+      .iter()
+      .find(|r| matches!(r, Rule::Type { .. }))
+    {
+      Some(root) => root.name(),
+      None => return,
+    };
+
+    let unreachable = graph.unreachable_from(&root);
+
+    let cddl = self.cddl;
+    for rule in cddl.rules.iter() {
+      let name = rule.name();
+      if rule.is_choice_alternate() || !unreachable.contains(&name.as_str()) {
+        continue;
+      }
+
+      self.report(
+        Lint::UnusedRule,
+        format!("rule {} is not referenced by the root rule {}", name, root),
+        #[cfg(feature = "ast-span")]
+        match rule {
+          Rule::Type { rule, .. } => rule.name.span,
+          Rule::Group { rule, .. } => rule.name.span,
+        },
+      );
+    }
+  }
+
+  fn shadowed_names(&mut self, rule: &Rule<'a>) {
+    let (name, generic_params, is_alternate) = match rule {
+      Rule::Type { rule, .. } => (
+        &rule.name,
+        &rule.generic_params,
+        rule.is_type_choice_alternate,
+      ),
+      Rule::Group { rule, .. } => (
+        &rule.name,
+        &rule.generic_params,
+        rule.is_group_choice_alternate,
+      ),
+    };
+
+    // The parser rejects these, but documents may also be constructed or
+    // transformed programmatically
+    if !is_alternate {
+      if let Some(prelude) = lookup_ident(&name.ident).in_standard_prelude() {
+        self.report(
+          Lint::ShadowedName,
+          format!(
+            "rule {} shadows the standard prelude type {}",
+            name, prelude
+          ),
+          #[cfg(feature = "ast-span")]
+          name.span,
+        );
+      }
+    }
+
+    for gp in generic_params.iter().flat_map(|gp| gp.params.iter()) {
+      let shadowed = if let Some(prelude) = lookup_ident(&gp.param.ident).in_standard_prelude() {
+        format!("the standard prelude type {}", prelude)
+      } else if self.cddl.rules.iter().any(|r| r.name() == gp.param.ident) {
+        format!("the rule {}", gp.param.ident)
+      } else {
+        continue;
+      };
+
+      self.report(
+        Lint::ShadowedName,
+        format!(
+          "generic parameter {} of rule {} shadows {}",
+          gp.param, name, shadowed
+        ),
+        #[cfg(feature = "ast-span")]
+        gp.param.span,
+      );
+    }
+  }
+
+  fn duplicate_keys(&mut self, group: &Group<'a>) {
+    for gc in group.group_choices.iter() {
+      let mut keys = BTreeSet::new();
+      for (ge, _) in gc.group_entries.iter() {
+        if let GroupEntry::ValueMemberKey { ge, .. } = ge {
+          if let Some(mk) = &ge.member_key {
+            if let Some(key) = literal_key(mk) {
+              if !keys.insert(key.clone()) {
+                self.report(
+                  Lint::DuplicateKey,
+                  format!(
+                    "key {} appears more than once in a map of rule {}",
+                    key, self.rule
+                  ),
+                  #[cfg(feature = "ast-span")]
+                  member_key_span(mk),
+                );
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+
+  // Reports the occurrence indicators of the entries of an enumerated group,
+  // including those of nested inline groups
+  fn enumerated_occurrences(&mut self, entry: &GroupEntry<'a>) {
+    let occur = match entry {
+      GroupEntry::ValueMemberKey { ge, .. } => &ge.occur,
+      GroupEntry::TypeGroupname { ge, .. } => &ge.occur,
+      GroupEntry::InlineGroup { occur, group, .. } => {
+        for gc in group.group_choices.iter() {
+          for (ge, _) in gc.group_entries.iter() {
+            self.enumerated_occurrences(ge);
+          }
+        }
+
+        occur
+      }
+    };
+
+    if let Some(o) = occur {
+      self.report(
+        Lint::MisplacedOccurrence,
+        format!(
+          "occurrence indicator {} has no effect in a group enumerated with & in rule {}",
+          o.occur, self.rule
+        ),
+        #[cfg(feature = "ast-span")]
+        occur_span(&o.occur),
+      );
+    }
+  }
+}
+
+impl<'a, 'b> Visitor<'a, Infallible> for Lints<'a, 'b> {
+  fn visit_type1(&mut self, t1: &Type1<'a>) -> visitor::Result<Infallible> {
+    if let Some(Operator {
+      operator: RangeCtlOp::RangeOp { is_inclusive, .. },
+      type2: upper,
+      ..
+    }) = &t1.operator
+    {
+      if let (Some(l), Some(u)) = (numeric_value(&t1.type2), numeric_value(upper)) {
+        if l > u || (l == u && !is_inclusive) {
+          self.report(
+            Lint::InvertedBounds,
+            format!(
+              "range {} in rule {} has inverted bounds, so no value satisfies it",
+              t1, self.rule
+            ),
+            #[cfg(feature = "ast-span")]
+            t1.span,
+          );
+
+          return visitor::walk_type1(self, t1);
+        }
+      }
+    }
+
+    if let Some(reason) = analysis::unsatisfiable_constraint(self.cddl, t1) {
+      self.report(
+        Lint::UnsatisfiableConstraint,
+        format!(
+          "no value satisfies {} in rule {}, since {}",
+          t1, self.rule, reason
+        ),
+        #[cfg(feature = "ast-span")]
+        t1.span,
+      );
+    }
+
+    visitor::walk_type1(self, t1)
+  }
+
+  fn visit_type2(&mut self, t2: &Type2<'a>) -> visitor::Result<Infallible> {
+    match t2 {
+      Type2::Map { group, .. } => self.duplicate_keys(group),
+      Type2::ChoiceFromInlineGroup { group, .. } => {
+        for gc in group.group_choices.iter() {
+          for (ge, _) in gc.group_entries.iter() {
+            self.enumerated_occurrences(ge);
+          }
+        }
+      }
+      Type2::ChoiceFromGroup { ident, .. } => {
+        self.enumerated.insert(ident.to_string());
+      }
+      _ => (),
+    }
+
+    visitor::walk_type2(self, t2)
+  }
+
+  fn visit_occurrence(&mut self, o: &Occurrence<'a>) -> visitor::Result<Infallible> {
+    if let Occur::Exact {
+      lower: Some(lower),
+      upper: Some(upper),
+      ..
+    } = &o.occur
+    {
+      if lower > upper {
+        self.report(
+          Lint::InvertedBounds,
+          format!(
+            "occurrence indicator {} in rule {} has inverted bounds",
+            o.occur, self.rule
+          ),
+          #[cfg(feature = "ast-span")]
+          occur_span(&o.occur),
+        );
+      }
+    }
+
+    Ok(())
+  }
+}
+
+// Normalized text of a member key that is a literal value. Barewords are
+// equivalent to text string keys
+fn literal_key(mk: &MemberKey) -> Option<String> {
+  match mk {
+    MemberKey::Bareword { ident, .. } => Some(format!("\"{}\"", ident.ident)),
+    MemberKey::Value { value, .. } => Some(value.to_string()),
+    MemberKey::Type1 { t1, .. } if t1.operator.is_none() => match &t1.type2 {
+      t2 @ Type2::TextValue { .. }
+      | t2 @ Type2::IntValue { .. }
+      | t2 @ Type2::UintValue { .. }
+      | t2 @ Type2::FloatValue { .. }
+      | t2 @ Type2::UTF8ByteString { .. }
+      | t2 @ Type2::B16ByteString { .. }
+      | t2 @ Type2::B64ByteString { .. } => Some(t2.to_string()),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+fn numeric_value(t2: &Type2) -> Option<f64> {
+  match t2 {
+    Type2::IntValue { value, .. } => Some(*value as f64),
+    Type2::UintValue { value, .. } => Some(*value as f64),
+    Type2::FloatValue { value, .. } => Some(*value),
+    _ => None,
+  }
+}
+
+#[cfg(feature = "ast-span")]
+fn member_key_span(mk: &MemberKey) -> Span {
+  match mk {
+    MemberKey::Type1 { span, .. }
+    | MemberKey::Bareword { span, .. }
+    | MemberKey::Value { span, .. } => *span,
+    MemberKey::NonMemberKey { .. } => Span::default(),
+  }
+}
+
+#[cfg(feature = "ast-span")]
+fn occur_span(o: &Occur) -> Span {
+  match o {
+    Occur::Exact { span, .. } => *span,
+    Occur::ZeroOrMore(span) | Occur::OneOrMore(span) | Occur::Optional(span) => *span,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use indoc::indoc;
+
+  #[test]
+  fn verify_lint() -> std::result::Result<(), parser::Error> {
+    let input = indoc!(
+      r#"
+        message = {
+          id: uint,
+          "id" => tstr,
+          ? body: envelope<tstr>,
+          color: &colors,
+          shade: &( ? light: 1, dark: 2 ),
+          3*1 tstr => any
+          // id: uint
+        }
+        envelope<record> = [record, 1..1.5, 5...5]
+        record = { id: uint }
+        colors = ( * red: 1, green: 2 )
+        unused = 10..1
+      "#
+    );
+
+    let diagnostics = lint_from_str(input)?;
+
+    assert_eq!(
+      diagnostics
+        .iter()
+        .map(|d| (d.lint, d.severity))
+        .collect::<Vec<_>>(),
+      vec![
+        (Lint::DuplicateKey, Severity::Warning),
+        (Lint::MisplacedOccurrence, Severity::Warning),
+        (Lint::InvertedBounds, Severity::Error),
+        (Lint::ShadowedName, Severity::Warning),
+        (Lint::InvertedBounds, Severity::Error),
+        (Lint::UnusedRule, Severity::Info),
+        (Lint::MisplacedOccurrence, Severity::Warning),
+        (Lint::UnusedRule, Severity::Info),
+        (Lint::InvertedBounds, Severity::Error),
+      ]
+    );
+    assert_eq!(
+      diagnostics[0].message,
+      r#"key "id" appears more than once in a map of rule message"#
+    );
+    assert_eq!(
+      diagnostics[3].to_string(),
+      "warning[shadowed-name] at line 10: generic parameter record of rule envelope shadows the rule record"
+    );
+    // The rule is shadowed wherever it is referenced
+    assert_eq!(
+      diagnostics[5].message,
+      "rule record is not referenced by the root rule message"
+    );
+    assert_eq!(
+      diagnostics[7].message,
+      "rule unused is not referenced by the root rule message"
+    );
+
+    let report = report_diagnostics(input, &diagnostics[4..5], false)?.unwrap();
+    assert!(report.starts_with("error[inverted-bounds]: range 5...5 in rule envelope"));
+    assert!(report.contains("input:10:37"));
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_unsatisfiable_constraints() -> std::result::Result<(), parser::Error> {
+    let input = indoc!(
+      r#"
+        message = {
+          port: port,
+          retries: byte .default 300,
+          level: level .eq 7,
+          timeout: min .. max,
+          ratio: 0.5..0.25,
+          count: uint .lt 10,
+        }
+        port = u16 .gt 100000
+        u16 = uint .size 2
+        byte = uint .size 1
+        level = 0..5
+        min = 10
+        max = 1
+      "#
+    );
+
+    let diagnostics = lint_from_str(input)?
+      .into_iter()
+      .filter(|d| d.lint != Lint::UnusedRule)
+      .map(|d| (d.lint, d.message))
+      .collect::<Vec<_>>();
+
+    assert_eq!(
+      diagnostics,
+      vec![
+        (
+          Lint::UnsatisfiableConstraint,
+          "no value satisfies byte .default 300 in rule message, since its default value 300 is not a value of byte, which ranges over 0..255".to_string()
+        ),
+        (
+          Lint::UnsatisfiableConstraint,
+          "no value satisfies level .eq 7 in rule message, since 7 is not a value of level, which ranges over 0..5".to_string()
+        ),
+        (
+          Lint::UnsatisfiableConstraint,
+          "no value satisfies min .. max in rule message, since its lower bound 10 exceeds its upper bound 1".to_string()
+        ),
+        (
+          Lint::InvertedBounds,
+          "range 0.5..0.25 in rule message has inverted bounds, so no value satisfies it".to_string()
+        ),
+        (
+          Lint::UnsatisfiableConstraint,
+          "no value satisfies u16 .gt 100000 in rule port, since u16 only ranges over 0..65535".to_string()
+        ),
+      ]
+    );
+
+    Ok(())
+  }
+}
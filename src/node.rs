@@ -0,0 +1,742 @@
+#![cfg(feature = "std")]
+
+use crate::ast::*;
+
+use std::collections::HashMap;
+
+/// Identity of a node of an indexed CDDL document. Nodes are numbered in
+/// pre-order, so the same document always yields the same IDs, and distinct
+/// nodes have distinct IDs even if they are equal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+impl NodeId {
+  /// Position of the node in the pre-order of the document
+  pub fn index(self) -> usize {
+    self.0
+  }
+}
+
+/// Reference to a node of a CDDL document
+#[derive(Debug, Clone, Copy)]
+pub enum NodeRef<'a, 'b> {
+  /// Document
+  CDDL(&'b CDDL<'a>),
+  /// Rule
+  Rule(&'b Rule<'a>),
+  /// Type rule
+  TypeRule(&'b TypeRule<'a>),
+  /// Group rule
+  GroupRule(&'b GroupRule<'a>),
+  /// Generic parameter
+  GenericParam(&'b GenericParam<'a>),
+  /// Generic argument
+  GenericArg(&'b GenericArg<'a>),
+  /// Type
+  Type(&'b Type<'a>),
+  /// Type choice
+  TypeChoice(&'b TypeChoice<'a>),
+  /// Type with an optional operator
+  Type1(&'b Type1<'a>),
+  /// Range or control operator
+  Operator(&'b Operator<'a>),
+  /// Type expression
+  Type2(&'b Type2<'a>),
+  /// Group
+  Group(&'b Group<'a>),
+  /// Group choice
+  GroupChoice(&'b GroupChoice<'a>),
+  /// Group entry
+  GroupEntry(&'b GroupEntry<'a>),
+  /// Member key
+  MemberKey(&'b MemberKey<'a>),
+  /// Occurrence indicator
+  Occurrence(&'b Occurrence<'a>),
+  /// Identifier
+  Identifier(&'b Identifier<'a>),
+}
+
+impl<'a, 'b> NodeRef<'a, 'b> {
+  // Nodes are identified by their kind and address, which distinguishes nodes
+  // that are equal but distinct, as well as nodes of different kinds sharing
+  // an address, e.g. a type rule and its name
+  fn key(&self) -> (u8, usize) {
+    match self {
+      NodeRef::CDDL(n) => (0, *n as *const _ as usize),
+      NodeRef::Rule(n) => (1, *n as *const _ as usize),
+      NodeRef::TypeRule(n) => (2, *n as *const _ as usize),
+      NodeRef::GroupRule(n) => (3, *n as *const _ as usize),
+      NodeRef::GenericParam(n) => (4, *n as *const _ as usize),
+      NodeRef::GenericArg(n) => (5, *n as *const _ as usize),
+      NodeRef::Type(n) => (6, *n as *const _ as usize),
+      NodeRef::TypeChoice(n) => (7, *n as *const _ as usize),
+      NodeRef::Type1(n) => (8, *n as *const _ as usize),
+      NodeRef::Operator(n) => (9, *n as *const _ as usize),
+      NodeRef::Type2(n) => (10, *n as *const _ as usize),
+      NodeRef::Group(n) => (11, *n as *const _ as usize),
+      NodeRef::GroupChoice(n) => (12, *n as *const _ as usize),
+      NodeRef::GroupEntry(n) => (13, *n as *const _ as usize),
+      NodeRef::MemberKey(n) => (14, *n as *const _ as usize),
+      NodeRef::Occurrence(n) => (15, *n as *const _ as usize),
+      NodeRef::Identifier(n) => (16, *n as *const _ as usize),
+    }
+  }
+
+  // Span of nodes that record one. The spans of other nodes are derived from
+  // their children
+  #[cfg(feature = "ast-span")]
+  fn span(&self) -> Option<Span> {
+    let span = match self {
+      NodeRef::Rule(r) => r.span(),
+      NodeRef::Type(t) => t.span,
+      NodeRef::Type1(t1) => t1.span,
+      NodeRef::Type2(t2) => match t2 {
+        Type2::IntValue { span, .. }
+        | Type2::UintValue { span, .. }
+        | Type2::FloatValue { span, .. }
+        | Type2::TextValue { span, .. }
+        | Type2::UTF8ByteString { span, .. }
+        | Type2::B16ByteString { span, .. }
+        | Type2::B64ByteString { span, .. }
+        | Type2::Typename { span, .. }
+        | Type2::ParenthesizedType { span, .. }
+        | Type2::Map { span, .. }
+        | Type2::Array { span, .. }
+        | Type2::Unwrap { span, .. }
+        | Type2::ChoiceFromInlineGroup { span, .. }
+        | Type2::ChoiceFromGroup { span, .. }
+        | Type2::TaggedData { span, .. }
+        | Type2::DataMajorType { span, .. }
+        | Type2::Any(span) => *span,
+      },
+      // Operators span from the operator to the bound type
+      NodeRef::Operator(o) => {
+        let (start, _, line) = match o.operator {
+          RangeCtlOp::RangeOp { span, .. } | RangeCtlOp::CtlOp { span, .. } => span,
+        };
+        (start, NodeRef::Type2(&o.type2).span()?.1, line)
+      }
+      NodeRef::Group(g) => g.span,
+      NodeRef::GroupChoice(gc) => gc.span,
+      NodeRef::GroupEntry(ge) => ge.span(),
+      NodeRef::MemberKey(MemberKey::Type1 { span, .. })
+      | NodeRef::MemberKey(MemberKey::Bareword { span, .. })
+      | NodeRef::MemberKey(MemberKey::Value { span, .. }) => *span,
+      NodeRef::Occurrence(o) => match o.occur {
+        Occur::Exact { span, .. }
+        | Occur::ZeroOrMore(span)
+        | Occur::OneOrMore(span)
+        | Occur::Optional(span) => span,
+      },
+      NodeRef::Identifier(ident) => ident.span,
+      _ => return None,
+    };
+
+    // Nodes built rather than parsed have empty default spans
+    if span.1 == 0 {
+      return None;
+    }
+
+    Some(span)
+  }
+
+  fn children(&self) -> Vec<NodeRef<'a, 'b>> {
+    let mut children = Vec::new();
+    match *self {
+      NodeRef::CDDL(cddl) => children.extend(cddl.rules.iter().map(NodeRef::Rule)),
+      NodeRef::Rule(Rule::Type { rule, .. }) => children.push(NodeRef::TypeRule(rule)),
+      NodeRef::Rule(Rule::Group { rule, .. }) => children.push(NodeRef::GroupRule(rule)),
+      NodeRef::TypeRule(tr) => {
+        children.push(NodeRef::Identifier(&tr.name));
+        children.extend(params_of(&tr.generic_params));
+        children.push(NodeRef::Type(&tr.value));
+      }
+      NodeRef::GroupRule(gr) => {
+        children.push(NodeRef::Identifier(&gr.name));
+        children.extend(params_of(&gr.generic_params));
+        children.push(NodeRef::GroupEntry(&gr.entry));
+      }
+      NodeRef::GenericParam(gp) => children.push(NodeRef::Identifier(&gp.param)),
+      NodeRef::GenericArg(ga) => children.push(NodeRef::Type1(&ga.arg)),
+      NodeRef::Type(t) => children.extend(t.type_choices.iter().map(NodeRef::TypeChoice)),
+      NodeRef::TypeChoice(tc) => children.push(NodeRef::Type1(&tc.type1)),
+      NodeRef::Type1(t1) => {
+        children.push(NodeRef::Type2(&t1.type2));
+        children.extend(t1.operator.iter().map(NodeRef::Operator));
+      }
+      NodeRef::Operator(o) => children.push(NodeRef::Type2(&o.type2)),
+      NodeRef::Type2(t2) => match t2 {
+        Type2::Typename {
+          ident,
+          generic_args,
+          ..
+        }
+        | Type2::Unwrap {
+          ident,
+          generic_args,
+          ..
+        }
+        | Type2::ChoiceFromGroup {
+          ident,
+          generic_args,
+          ..
+        } => {
+          children.push(NodeRef::Identifier(ident));
+          children.extend(args_of(generic_args));
+        }
+        Type2::ParenthesizedType { pt, .. } => children.push(NodeRef::Type(pt)),
+        Type2::TaggedData { tag_type, t, .. } => {
+          if let Some(tag_type) = tag_type {
+            children.push(NodeRef::Type(tag_type));
+          }
+          children.push(NodeRef::Type(t));
+        }
+        Type2::Map { group, .. }
+        | Type2::Array { group, .. }
+        | Type2::ChoiceFromInlineGroup { group, .. } => children.push(NodeRef::Group(group)),
+        _ => {}
+      },
+      NodeRef::Group(g) => children.extend(g.group_choices.iter().map(NodeRef::GroupChoice)),
+      NodeRef::GroupChoice(gc) => children.extend(
+        gc.group_entries
+          .iter()
+          .map(|(ge, _)| NodeRef::GroupEntry(ge)),
+      ),
+      NodeRef::GroupEntry(GroupEntry::ValueMemberKey { ge, .. }) => {
+        children.extend(ge.occur.iter().map(NodeRef::Occurrence));
+        children.extend(ge.member_key.iter().map(NodeRef::MemberKey));
+        children.push(NodeRef::Type(&ge.entry_type));
+      }
+      NodeRef::GroupEntry(GroupEntry::TypeGroupname { ge, .. }) => {
+        children.extend(ge.occur.iter().map(NodeRef::Occurrence));
+        children.push(NodeRef::Identifier(&ge.name));
+        children.extend(args_of(&ge.generic_args));
+      }
+      NodeRef::GroupEntry(GroupEntry::InlineGroup { occur, group, .. }) => {
+        children.extend(occur.iter().map(NodeRef::Occurrence));
+        children.push(NodeRef::Group(group));
+      }
+      NodeRef::MemberKey(mk) => match mk {
+        MemberKey::Type1 { t1, .. } => children.push(NodeRef::Type1(t1)),
+        MemberKey::Bareword { ident, .. } => children.push(NodeRef::Identifier(ident)),
+        MemberKey::NonMemberKey {
+          non_member_key: NonMemberKey::Group(group),
+          ..
+        } => children.push(NodeRef::Group(group)),
+        MemberKey::NonMemberKey {
+          non_member_key: NonMemberKey::Type(t),
+          ..
+        } => children.push(NodeRef::Type(t)),
+        MemberKey::Value { .. } => {}
+      },
+      NodeRef::Occurrence(_) | NodeRef::Identifier(_) => {}
+    }
+
+    children
+  }
+}
+
+fn params_of<'a, 'b>(
+  generic_params: &'b Option<GenericParams<'a>>,
+) -> impl Iterator<Item = NodeRef<'a, 'b>> {
+  generic_params
+    .iter()
+    .flat_map(|gp| gp.params.iter().map(NodeRef::GenericParam))
+}
+
+fn args_of<'a, 'b>(
+  generic_args: &'b Option<GenericArgs<'a>>,
+) -> impl Iterator<Item = NodeRef<'a, 'b>> {
+  generic_args
+    .iter()
+    .flat_map(|ga| ga.args.iter().map(NodeRef::GenericArg))
+}
+
+struct Entry<'a, 'b> {
+  node: NodeRef<'a, 'b>,
+  parent: Option<NodeId>,
+  // Position of the node among the children of its parent
+  position: usize,
+  children: Vec<NodeId>,
+  #[cfg(feature = "ast-span")]
+  span: Option<Span>,
+}
+
+/// AST types of the nodes of an index
+pub trait Node<'a>: Sized {
+  /// Node as a reference to this type, or `None` if the node is of another
+  /// type
+  fn downcast<'b>(node: NodeRef<'a, 'b>) -> Option<&'b Self>;
+}
+
+/// Index of the nodes of a CDDL document, assigning each node an ID and
+/// recording the parent and children of each node. Nodes are looked up by
+/// identity rather than by value, in constant time
+///
+/// # Example
+///
+/// ```
+/// use cddl::{cddl_from_str, node::{NodeIndex, NodeRef}};
+///
+/// let cddl = cddl_from_str(r#"a = "x" / [ "x" ]"#, true).unwrap();
+/// let index = NodeIndex::new(&cddl);
+///
+/// let mut texts = index
+///   .nodes()
+///   .filter(|(_, node)| matches!(node, NodeRef::Type2(t2) if t2.to_string() == "\"x\""))
+///   .map(|(id, _)| id);
+/// let (first, second) = (texts.next().unwrap(), texts.next().unwrap());
+///
+/// // Equal nodes have distinct IDs and parents
+/// assert_ne!(first, second);
+/// assert_ne!(index.parent(first), index.parent(second));
+/// ```
+pub struct NodeIndex<'a, 'b> {
+  entries: Vec<Entry<'a, 'b>>,
+  ids: HashMap<(u8, usize), NodeId>,
+}
+
+impl<'a, 'b> NodeIndex<'a, 'b> {
+  /// Index the nodes of a CDDL document. The document itself is the root node
+  pub fn new(cddl: &'b CDDL<'a>) -> Self {
+    let mut index = NodeIndex {
+      entries: Vec::new(),
+      ids: HashMap::new(),
+    };
+
+    // Nodes are visited in pre-order, with children pushed in reverse so that
+    // they are popped in order
+    let mut stack = vec![(NodeRef::CDDL(cddl), None)];
+    while let Some((node, parent)) = stack.pop() {
+      let id = NodeId(index.entries.len());
+      let mut position = 0;
+      if let Some(NodeId(parent)) = parent {
+        let siblings = &mut index.entries[parent].children;
+        position = siblings.len();
+        siblings.push(id);
+      }
+
+      index.ids.insert(node.key(), id);
+      index.entries.push(Entry {
+        node,
+        parent,
+        position,
+        children: Vec::new(),
+        #[cfg(feature = "ast-span")]
+        span: node.span(),
+      });
+
+      stack.extend(node.children().into_iter().rev().map(|c| (c, Some(id))));
+    }
+
+    // Nodes without a span of their own cover their children. Children follow
+    // their parent in pre-order, so their spans are known when visited in
+    // reverse
+    #[cfg(feature = "ast-span")]
+    for idx in (0..index.entries.len()).rev() {
+      if index.entries[idx].span.is_some() {
+        continue;
+      }
+
+      let span = index.entries[idx]
+        .children
+        .iter()
+        .filter_map(|c| index.entries[c.0].span)
+        .reduce(|a, b| (a.0.min(b.0), a.1.max(b.1), a.2.min(b.2)));
+      index.entries[idx].span = span;
+    }
+
+    index
+  }
+
+  /// ID of the given node, or `None` if the node isn't part of the indexed
+  /// document
+  pub fn id(&self, node: NodeRef) -> Option<NodeId> {
+    self.ids.get(&node.key()).copied()
+  }
+
+  /// Node with the given ID
+  pub fn node(&self, id: NodeId) -> Option<NodeRef<'a, 'b>> {
+    self.entries.get(id.0).map(|e| e.node)
+  }
+
+  /// Parent of the node with the given ID, or `None` for the root node
+  pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+    self.entries.get(id.0)?.parent
+  }
+
+  /// Children of the node with the given ID, in order
+  pub fn children(&self, id: NodeId) -> &[NodeId] {
+    self
+      .entries
+      .get(id.0)
+      .map(|e| e.children.as_slice())
+      .unwrap_or_default()
+  }
+
+  /// Sibling following the node with the given ID
+  pub fn next_sibling(&self, id: NodeId) -> Option<NodeId> {
+    let entry = self.entries.get(id.0)?;
+
+    self
+      .children(entry.parent?)
+      .get(entry.position + 1)
+      .copied()
+  }
+
+  /// Sibling preceding the node with the given ID
+  pub fn previous_sibling(&self, id: NodeId) -> Option<NodeId> {
+    let entry = self.entries.get(id.0)?;
+
+    self
+      .children(entry.parent?)
+      .get(entry.position.checked_sub(1)?)
+      .copied()
+  }
+
+  /// Ancestors of the node with the given ID, from its parent up to the root
+  /// node
+  pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+    std::iter::successors(self.parent(id), move |id| self.parent(*id))
+  }
+
+  /// Closest ancestor of the given type of the node with the given ID, e.g.
+  /// the type rule defining a type
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use cddl::{
+  ///   ast::TypeRule,
+  ///   cddl_from_str,
+  ///   node::{NodeIndex, NodeRef},
+  /// };
+  ///
+  /// let cddl = cddl_from_str("a = [ int ]\nb = [ tstr ]", true).unwrap();
+  /// let index = NodeIndex::new(&cddl);
+  ///
+  /// let (id, _) = index
+  ///   .nodes()
+  ///   .find(|(_, node)| matches!(node, NodeRef::Identifier(ident) if ident.ident == "tstr"))
+  ///   .unwrap();
+  /// assert_eq!(index.ancestor::<TypeRule>(id).unwrap().name.ident, "b");
+  /// ```
+  pub fn ancestor<T: Node<'a>>(&self, id: NodeId) -> Option<&'b T> {
+    self
+      .ancestors(id)
+      .find_map(|id| self.node(id).and_then(T::downcast))
+  }
+
+  /// Span of the node with the given ID in the source of the document. Nodes
+  /// that don't record a span, e.g. type rules, span their children
+  #[cfg(feature = "ast-span")]
+  pub fn span(&self, id: NodeId) -> Option<Span> {
+    self.entries.get(id.0)?.span
+  }
+
+  /// Nodes whose span contains the given byte offset in the source of the
+  /// document, from the root node down to the narrowest node, e.g. to find
+  /// the node under the cursor of an editor. Empty if no node contains the
+  /// offset
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use cddl::{cddl_from_str, node::{NodeIndex, NodeRef}};
+  ///
+  /// let input = "a = { b: tstr }";
+  /// let cddl = cddl_from_str(input, true).unwrap();
+  /// let index = NodeIndex::new(&cddl);
+  ///
+  /// let path = index.at(input.find("tstr").unwrap() + 1);
+  /// assert!(matches!(
+  ///   path.last().and_then(|id| index.node(*id)),
+  ///   Some(NodeRef::Identifier(ident)) if ident.ident == "tstr"
+  /// ));
+  /// assert_eq!(path.first(), cddl.node_id(&index).as_ref());
+  /// ```
+  #[cfg(feature = "ast-span")]
+  pub fn at(&self, offset: usize) -> Vec<NodeId> {
+    let contains = |id: NodeId| {
+      self
+        .span(id)
+        .is_some_and(|(start, end, _)| start <= offset && offset < end)
+    };
+
+    let mut path = Vec::new();
+    let mut id = NodeId(0);
+    if self.entries.is_empty() || !contains(id) {
+      return path;
+    }
+
+    // Spans of children are nested within the span of their parent, so the
+    // narrowest node is found by descending through the narrowest child
+    // containing the offset
+    loop {
+      path.push(id);
+
+      let child = self
+        .children(id)
+        .iter()
+        .copied()
+        .filter(|c| contains(*c))
+        .min_by_key(|c| self.span(*c).map(|(start, end, _)| end - start));
+      match child {
+        Some(child) => id = child,
+        None => return path,
+      }
+    }
+  }
+
+  /// Nodes whose span contains the given 1-based line and column in the given
+  /// source of the document, as returned by [`NodeIndex::at`]. Columns are
+  /// counted in characters, consistent with the lexer
+  #[cfg(feature = "ast-span")]
+  pub fn at_line_column(&self, input: &str, line: usize, column: usize) -> Vec<NodeId> {
+    let mut start = 0;
+    for (idx, l) in input.split_inclusive('\n').enumerate() {
+      if idx + 1 == line {
+        return l
+          .char_indices()
+          .nth(column.saturating_sub(1))
+          .map(|(offset, _)| self.at(start + offset))
+          .unwrap_or_default();
+      }
+      start += l.len();
+    }
+
+    Vec::new()
+  }
+
+  /// Nodes of the document in pre-order, along with their IDs
+  pub fn nodes(&self) -> impl Iterator<Item = (NodeId, NodeRef<'a, 'b>)> + '_ {
+    self
+      .entries
+      .iter()
+      .enumerate()
+      .map(|(idx, e)| (NodeId(idx), e.node))
+  }
+}
+
+// Nodes expose their ID in an index, e.g. to look up their parent, and can be
+// downcast from references to nodes
+macro_rules! nodes {
+  ($($node:ident),*) => {
+    $(
+      impl<'a> Node<'a> for $node<'a> {
+        fn downcast<'b>(node: NodeRef<'a, 'b>) -> Option<&'b Self> {
+          match node {
+            NodeRef::$node(node) => Some(node),
+            _ => None,
+          }
+        }
+      }
+
+      impl<'a> $node<'a> {
+        /// ID of the node in the given index, or `None` if the node isn't part
+        /// of the indexed document
+        pub fn node_id(&self, index: &NodeIndex) -> Option<NodeId> {
+          index.id(NodeRef::$node(self))
+        }
+      }
+    )*
+  };
+}
+
+nodes!(
+  CDDL,
+  Rule,
+  TypeRule,
+  GroupRule,
+  GenericParam,
+  GenericArg,
+  Type,
+  TypeChoice,
+  Type1,
+  Operator,
+  Type2,
+  Group,
+  GroupChoice,
+  GroupEntry,
+  MemberKey,
+  Occurrence,
+  Identifier
+);
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cddl_from_str;
+
+  #[test]
+  fn verify_node_index() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let input = r#"a = { x: "v", y: "v" }"#;
+    let cddl = cddl_from_str(input, true)?;
+    let index = NodeIndex::new(&cddl);
+
+    let (x, y) = match &cddl.rules[0] {
+      Rule::Type { rule, .. } => match &rule.value.type_choices[0].type1.type2 {
+        Type2::Map { group, .. } => match &group.group_choices[0].group_entries[..] {
+          [(GroupEntry::ValueMemberKey { ge: x, .. }, _), (GroupEntry::ValueMemberKey { ge: y, .. }, _)] => {
+            (x, y)
+          }
+          entries => panic!("expected two entries, got {:?}", entries),
+        },
+        t2 => panic!("expected map, got {:?}", t2),
+      },
+      rule => panic!("expected type rule, got {:?}", rule),
+    };
+
+    // Equal values are distinct nodes with distinct parents
+    let x_value = &x.entry_type.type_choices[0].type1.type2;
+    let y_value = &y.entry_type.type_choices[0].type1.type2;
+    assert_eq!(x_value.to_string(), y_value.to_string());
+    let (x_id, y_id) = (
+      x_value.node_id(&index).unwrap(),
+      y_value.node_id(&index).unwrap(),
+    );
+    assert_ne!(x_id, y_id);
+    assert_ne!(index.parent(x_id), index.parent(y_id));
+
+    // Ancestors lead up to the document through the entry of the value
+    let ancestors = index.ancestors(x_id).collect::<Vec<_>>();
+    assert_eq!(ancestors.len(), 13);
+    assert!(matches!(
+      index.node(ancestors[3]),
+      Some(NodeRef::GroupEntry(GroupEntry::ValueMemberKey { ge, .. })) if std::ptr::eq(ge.as_ref(), x.as_ref())
+    ));
+    assert_eq!(ancestors.last(), cddl.node_id(&index).as_ref());
+    assert_eq!(index.parent(cddl.node_id(&index).unwrap()), None);
+
+    // Children are listed in order, i.e. member key before value
+    let entry = index.node(ancestors[3]).and_then(|n| index.id(n)).unwrap();
+    let children = index.children(entry);
+    assert_eq!(children.len(), 2);
+    assert!(matches!(
+      index.node(children[0]),
+      Some(NodeRef::MemberKey(_))
+    ));
+    assert_eq!(index.children(x_id), &[]);
+
+    // IDs only depend on the document
+    let reparsed = cddl_from_str(input, true)?;
+    let reindexed = NodeIndex::new(&reparsed);
+    assert_eq!(
+      index
+        .nodes()
+        .map(|(id, node)| format!("{:?} {:?}", id, node))
+        .collect::<Vec<_>>(),
+      reindexed
+        .nodes()
+        .map(|(id, node)| format!("{:?} {:?}", id, node))
+        .collect::<Vec<_>>()
+    );
+    assert_eq!(x_value.node_id(&reindexed), None);
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_node_navigation() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = cddl_from_str("a = [ int, tstr, bool ]\nb = (c: uint)", true)?;
+    let index = NodeIndex::new(&cddl);
+
+    let ident = |name: &str| {
+      index
+        .nodes()
+        .find(|(_, node)| matches!(node, NodeRef::Identifier(ident) if ident.ident == name))
+        .map(|(id, _)| id)
+        .unwrap()
+    };
+    let entry = |id| index.parent(id).unwrap();
+
+    // Siblings are the other entries of the array
+    let (int, tstr, bool) = (
+      entry(ident("int")),
+      entry(ident("tstr")),
+      entry(ident("bool")),
+    );
+    assert_eq!(index.next_sibling(int), Some(tstr));
+    assert_eq!(index.next_sibling(tstr), Some(bool));
+    assert_eq!(index.next_sibling(bool), None);
+    assert_eq!(index.previous_sibling(tstr), Some(int));
+    assert_eq!(index.previous_sibling(int), None);
+    assert_eq!(
+      index.children(index.parent(int).unwrap()),
+      &[int, tstr, bool]
+    );
+
+    let root = cddl.node_id(&index).unwrap();
+    assert_eq!(index.next_sibling(root), None);
+    assert_eq!(index.previous_sibling(root), None);
+
+    // Typed ancestors
+    assert_eq!(index.ancestor::<TypeRule>(tstr).unwrap().name.ident, "a");
+    assert!(index.ancestor::<GroupRule>(tstr).is_none());
+    assert_eq!(
+      index
+        .ancestor::<GroupRule>(ident("uint"))
+        .unwrap()
+        .name
+        .ident,
+      "b"
+    );
+    assert!(std::ptr::eq(
+      index.ancestor::<CDDL>(ident("uint")).unwrap(),
+      &cddl
+    ));
+    assert!(matches!(
+      index.ancestor::<GroupEntry>(ident("uint")),
+      Some(GroupEntry::ValueMemberKey { .. })
+    ));
+
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(feature = "ast-span")]
+  fn verify_node_at_position() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let input = "a = { b: tstr .size 3 }\nc = [* int]\n";
+    let cddl = cddl_from_str(input, true)?;
+    let index = NodeIndex::new(&cddl);
+    let last = |path: Vec<NodeId>| path.last().and_then(|id| index.node(*id));
+
+    // Innermost node at an offset, along with its ancestors
+    let path = index.at(input.find("tstr").unwrap() + 2);
+    assert!(matches!(
+      last(path.clone()),
+      Some(NodeRef::Identifier(ident)) if ident.ident == "tstr"
+    ));
+    assert_eq!(path.first(), cddl.node_id(&index).as_ref());
+    assert_eq!(
+      index.ancestors(*path.last().unwrap()).collect::<Vec<_>>(),
+      path.iter().rev().skip(1).copied().collect::<Vec<_>>()
+    );
+    assert!(matches!(
+      last(index.at(input.find(".size").unwrap())),
+      Some(NodeRef::Operator(_))
+    ));
+
+    // Nodes without a span of their own span their children
+    let rule = cddl.rules[1].node_id(&index).unwrap();
+    let type_rule = index.children(rule)[0];
+    assert_eq!(index.span(type_rule), Some((24, 35, 2)));
+
+    // Lines and columns are 1-based
+    assert!(matches!(
+      last(index.at_line_column(input, 2, 5)),
+      Some(NodeRef::Type2(Type2::Array { .. }))
+    ));
+    assert!(matches!(
+      last(index.at_line_column(input, 2, 6)),
+      Some(NodeRef::Occurrence(_))
+    ));
+
+    // Offsets between rules only fall within the document
+    assert_eq!(index.at(input.find('\n').unwrap()), vec![NodeId(0)]);
+    assert_eq!(index.at(input.len() + 1), Vec::new());
+    assert_eq!(index.at_line_column(input, 3, 1), Vec::new());
+
+    Ok(())
+  }
+}
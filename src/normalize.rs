@@ -0,0 +1,393 @@
+#[cfg(feature = "ast-span")]
+use crate::ast::Span;
+use crate::lexer::Position;
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+
+/// How tab characters outside of text and byte string literals are handled.
+/// RFC 8610 only permits spaces and newlines as whitespace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabPolicy {
+  /// Leave tabs as is
+  Keep,
+  /// Replace each tab with spaces up to the next tab stop of the given width
+  Expand(usize),
+}
+
+/// How comments are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentPolicy {
+  /// Keep comments, replacing control characters with spaces
+  Keep,
+  /// Remove comments, including `;@` annotations
+  Strip,
+}
+
+/// Options for [`normalize_source_with_options`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NormalizeOptions {
+  /// Tab policy. Defaults to expanding tabs to tab stops of width 2
+  pub tabs: TabPolicy,
+  /// Comment policy. Defaults to keeping comments
+  pub comments: CommentPolicy,
+}
+
+impl Default for NormalizeOptions {
+  fn default() -> Self {
+    NormalizeOptions {
+      tabs: TabPolicy::Expand(2),
+      comments: CommentPolicy::Keep,
+    }
+  }
+}
+
+/// Normalized CDDL source text along with a mapping of its positions back to
+/// the original text
+#[derive(Debug, Clone)]
+pub struct NormalizedSource<'a> {
+  /// Normalized text
+  pub text: String,
+  /// Mapping of positions in the normalized text to the original text
+  pub map: SourceMap<'a>,
+}
+
+/// Mapping of byte offsets in normalized source text to byte offsets in the
+/// original text
+#[derive(Debug, Clone)]
+pub struct SourceMap<'a> {
+  original: &'a str,
+  // Contiguous runs of normalized text in order of their normalized offset.
+  // Offsets within verbatim runs map one-to-one, whereas all offsets within a
+  // replacement (e.g. an expanded tab) map to the start of the replaced text
+  runs: Vec<Run>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Run {
+  normalized: usize,
+  original: usize,
+  verbatim: bool,
+}
+
+impl<'a> SourceMap<'a> {
+  /// Original text
+  pub fn original(&self) -> &'a str {
+    self.original
+  }
+
+  /// Byte offset in the original text corresponding to the given byte offset
+  /// in the normalized text
+  pub fn original_offset(&self, offset: usize) -> usize {
+    let idx = self.runs.partition_point(|r| r.normalized <= offset);
+    if idx == 0 {
+      return offset.min(self.original.len());
+    }
+
+    let run = self.runs[idx - 1];
+    if run.verbatim {
+      (run.original + offset - run.normalized).min(self.original.len())
+    } else {
+      run.original
+    }
+  }
+
+  /// Position in the original text corresponding to the given position in the
+  /// normalized text, with line and column recomputed against the original
+  /// text
+  pub fn original_position(&self, position: Position) -> Position {
+    let index = self.original_offset(position.index);
+    let (line, column) = self.line_column(index);
+
+    Position {
+      line,
+      column,
+      range: (
+        self.original_offset(position.range.0),
+        self.original_offset(position.range.1),
+      ),
+      index,
+    }
+  }
+
+  /// Span in the original text corresponding to the given span in the
+  /// normalized text
+  #[cfg(feature = "ast-span")]
+  pub fn original_span(&self, span: Span) -> Span {
+    let start = self.original_offset(span.0);
+
+    (
+      start,
+      self.original_offset(span.1),
+      self.line_column(start).0,
+    )
+  }
+
+  // 1-based line and column of the given byte offset. Columns are counted in
+  // characters, consistent with the lexer
+  fn line_column(&self, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(self.original.len());
+    while !self.original.is_char_boundary(offset) {
+      offset -= 1;
+    }
+
+    let preceding = &self.original[..offset];
+    let line_start = preceding.rfind('\n').map_or(0, |idx| idx + 1);
+
+    (
+      preceding.matches('\n').count() + 1,
+      preceding[line_start..].chars().count() + 1,
+    )
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+  Code,
+  Text,
+  Bytes,
+  Comment,
+}
+
+struct Normalizer<'a> {
+  options: NormalizeOptions,
+  text: String,
+  map: SourceMap<'a>,
+  column: usize,
+  // Whitespace not yet emitted since it may turn out to be trailing
+  pending: Vec<(usize, char)>,
+}
+
+impl<'a> Normalizer<'a> {
+  fn push(&mut self, offset: usize, c: char) {
+    let verbatim_offset = self
+      .map
+      .runs
+      .last()
+      .filter(|r| r.verbatim)
+      .map(|r| r.original + self.text.len() - r.normalized);
+    if verbatim_offset != Some(offset) {
+      self.map.runs.push(Run {
+        normalized: self.text.len(),
+        original: offset,
+        verbatim: true,
+      });
+    }
+
+    self.text.push(c);
+    self.column = if c == '\n' { 0 } else { self.column + 1 };
+  }
+
+  fn push_replacement(&mut self, offset: usize, s: &str) {
+    self.map.runs.push(Run {
+      normalized: self.text.len(),
+      original: offset,
+      verbatim: false,
+    });
+
+    self.text.push_str(s);
+    self.column += s.chars().count();
+  }
+
+  fn push_whitespace(&mut self, offset: usize, c: char) {
+    match (c, self.options.tabs) {
+      ('\t', TabPolicy::Expand(width)) if width > 0 => {
+        let spaces = width - self.column % width;
+        self.push_replacement(offset, &" ".repeat(spaces));
+      }
+      ('\t', TabPolicy::Expand(_)) => self.push_replacement(offset, ""),
+      _ => self.push(offset, c),
+    }
+  }
+
+  fn flush_pending(&mut self) {
+    for (offset, c) in core::mem::take(&mut self.pending) {
+      self.push_whitespace(offset, c);
+    }
+  }
+}
+
+/// Normalize CDDL source text prior to parsing using the default
+/// [`NormalizeOptions`]. See [`normalize_source_with_options`]
+///
+/// # Example
+///
+/// ```
+/// use cddl::{cddl_from_str, normalize::normalize_source};
+///
+/// let input = "\u{feff}person = {\r\n\tname: tstr, ; full name  \r\n}\r\n";
+/// let normalized = normalize_source(input);
+/// assert_eq!(normalized.text, "person = {\n  name: tstr, ; full name\n}\n");
+///
+/// // Offsets in the normalized text map back to the original text
+/// let offset = normalized.text.find("tstr").unwrap();
+/// assert_eq!(&input[normalized.map.original_offset(offset)..][..4], "tstr");
+///
+/// assert!(cddl_from_str(&normalized.text, false).is_ok());
+/// ```
+pub fn normalize_source(input: &str) -> NormalizedSource<'_> {
+  normalize_source_with_options(input, &NormalizeOptions::default())
+}
+
+/// Normalize CDDL source text prior to parsing:
+///
+/// - A leading byte order mark is removed
+/// - `\r\n` and lone `\r` line endings are converted to `\n`
+/// - Trailing whitespace is removed from each line, except within byte string
+///   literals which may span multiple lines
+/// - Tabs outside of text and byte string literals are handled according to
+///   the [`TabPolicy`]
+/// - Control characters within comments are replaced with spaces, or comments
+///   are removed altogether, according to the [`CommentPolicy`]
+///
+/// The returned [`SourceMap`] maps offsets and positions in the normalized text,
+/// such as those of parser errors, back to the original text
+pub fn normalize_source_with_options<'a>(
+  input: &'a str,
+  options: &NormalizeOptions,
+) -> NormalizedSource<'a> {
+  let mut n = Normalizer {
+    options: *options,
+    text: String::with_capacity(input.len()),
+    map: SourceMap {
+      original: input,
+      runs: Vec::new(),
+    },
+    column: 0,
+    pending: Vec::new(),
+  };
+
+  let mut state = State::Code;
+  let mut chars = input.char_indices().peekable();
+  if let Some((_, '\u{feff}')) = chars.peek() {
+    chars.next();
+  }
+
+  while let Some((offset, c)) = chars.next() {
+    // Line endings
+    if c == '\r' || c == '\n' {
+      if c == '\r' && matches!(chars.peek(), Some((_, '\n'))) {
+        continue;
+      }
+
+      if state == State::Bytes {
+        n.flush_pending();
+      } else {
+        n.pending.clear();
+        if matches!(state, State::Comment | State::Text) {
+          state = State::Code;
+        }
+      }
+
+      if c == '\r' {
+        n.push_replacement(offset, "\n");
+        n.column = 0;
+      } else {
+        n.push(offset, c);
+      }
+
+      continue;
+    }
+
+    match state {
+      State::Code => {
+        if c == ' ' || c == '\t' {
+          n.pending.push((offset, c));
+          continue;
+        }
+
+        if c == ';' && options.comments == CommentPolicy::Strip {
+          n.pending.clear();
+          state = State::Comment;
+          continue;
+        }
+
+        n.flush_pending();
+        n.push(offset, c);
+
+        match c {
+          '"' => state = State::Text,
+          '\'' => state = State::Bytes,
+          ';' => state = State::Comment,
+          _ => {}
+        }
+      }
+      State::Text | State::Bytes => {
+        n.flush_pending();
+        n.push(offset, c);
+
+        if c == '\\' {
+          if let Some((offset, c)) = chars.next_if(|(_, c)| *c != '\r' && *c != '\n') {
+            n.push(offset, c);
+          }
+        } else if (c == '"' && state == State::Text) || (c == '\'' && state == State::Bytes) {
+          state = State::Code;
+        }
+      }
+      State::Comment => {
+        if options.comments == CommentPolicy::Strip {
+          continue;
+        }
+
+        if c.is_whitespace() {
+          n.pending.push((offset, c));
+        } else if c.is_control() {
+          n.pending.push((offset, ' '));
+        } else {
+          n.flush_pending();
+          n.push(offset, c);
+        }
+      }
+    }
+  }
+
+  if state == State::Bytes {
+    n.flush_pending();
+  }
+
+  NormalizedSource {
+    text: n.text,
+    map: n.map,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn verify_normalize_source() {
+    let input = "\u{feff}a = \"x;\ty\"\t; comment\u{7}\r\nb = '\tz \r\n  ' \t\r\nc =\tint\r";
+    let normalized = normalize_source(input);
+    assert_eq!(
+      normalized.text,
+      "a = \"x;\ty\"  ; comment\nb = '\tz \n  '\nc = int\n"
+    );
+
+    // Offsets are mapped through removed, replaced and expanded text
+    for needle in ["a", "comment", "b", "z", "c", "int"] {
+      let offset = normalized.text.find(needle).unwrap();
+      let original = normalized.map.original_offset(offset);
+      assert_eq!(&input[original..original + needle.len()], needle);
+    }
+
+    let offset = normalized.text.find("int").unwrap();
+    let position = normalized.map.original_position(Position {
+      line: 4,
+      column: 5,
+      range: (offset, offset + 3),
+      index: offset,
+    });
+    assert_eq!((position.line, position.column), (4, 5));
+    assert_eq!(&input[position.range.0..position.range.1], "int");
+
+    let stripped = normalize_source_with_options(
+      input,
+      &NormalizeOptions {
+        tabs: TabPolicy::Keep,
+        comments: CommentPolicy::Strip,
+      },
+    );
+    assert_eq!(stripped.text, "a = \"x;\ty\"\nb = '\tz \n  '\nc =\tint\n");
+  }
+}
@@ -0,0 +1,217 @@
+#![cfg(feature = "std")]
+
+use crate::{ast::*, graph::DependencyGraph};
+
+use std::collections::BTreeSet;
+
+/// Order of the rules of a CDDL document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleOrder {
+  /// Order of definition
+  Definition,
+  /// Root rule first, followed by the other rules in topological order, such
+  /// that rules are printed before the rules they reference (except within
+  /// cycles). Ties are broken by order of reference, which groups rules with
+  /// the rules they reference. Rules unreachable from the root rule follow,
+  /// grouped with the unreferenced rules reaching them in alphabetical order
+  Topological,
+  /// Root rule first, followed by the other rules in alphabetical order
+  Alphabetical,
+}
+
+impl<'a> CDDL<'a> {
+  /// Names of the rules of the document in the given order. The root rule is
+  /// the first type rule of the document, which data is validated against by
+  /// default. Rules extended with additional choices (i.e. `/=` or `//=`) only
+  /// appear once
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use cddl::{cddl_from_str, order::RuleOrder};
+  ///
+  /// let cddl = cddl_from_str(
+  ///   "message = { header: header, body: body }\nbody = [* id]\nheader = { id: id }\nid = uint",
+  ///   true,
+  /// )
+  /// .unwrap();
+  ///
+  /// assert_eq!(
+  ///   cddl.rule_order(RuleOrder::Topological),
+  ///   vec!["message", "header", "body", "id"]
+  /// );
+  /// assert_eq!(
+  ///   cddl.rule_order(RuleOrder::Alphabetical),
+  ///   vec!["message", "body", "header", "id"]
+  /// );
+  /// ```
+  pub fn rule_order(&self, order: RuleOrder) -> Vec<String> {
+    let graph = self.dependency_graph();
+    let mut rules = graph.rules().map(|r| r.to_string()).collect::<Vec<_>>();
+
+    let root = self
+      .rules
+      .iter()
+      .find(|r| matches!(r, Rule::Type { .. }))
+      .or_else(|| self.rules.first())
+      .map(|r| r.name());
+    let root = match root {
+      Some(root) => root,
+      None => return rules,
+    };
+
+    match order {
+      RuleOrder::Definition => rules,
+      RuleOrder::Alphabetical => {
+        rules.sort_by(|a, b| (*a != root).cmp(&(*b != root)).then(a.cmp(b)));
+        rules
+      }
+      RuleOrder::Topological => {
+        // Reversing the post-order of a depth first search yields a
+        // topological order. Dependencies are visited in reverse so that the
+        // reversed order follows the order of reference
+        let mut visited = BTreeSet::new();
+        let mut order = Vec::new();
+        let mut search = |starts: &[&str], visited: &mut BTreeSet<String>| {
+          let mut post_order = Vec::new();
+          for start in starts.iter().rev() {
+            visit(&graph, start, visited, &mut post_order);
+          }
+          order.extend(post_order.into_iter().rev());
+        };
+
+        search(&[root.as_str()], &mut visited);
+
+        // Rules unreachable from the root rule are grouped with the first
+        // unreferenced rule reaching them, in alphabetical order
+        rules.sort();
+        let unreferenced = rules
+          .iter()
+          .filter(|r| !visited.contains(*r) && graph.dependents(r).is_empty())
+          .map(|r| r.as_str())
+          .collect::<Vec<_>>();
+        search(&unreferenced, &mut visited);
+
+        // Remaining rules are only reachable from cycles
+        for rule in rules.iter() {
+          search(&[rule.as_str()], &mut visited);
+        }
+
+        order
+      }
+    }
+  }
+
+  /// Sort the rules of the document in the given order. Rules extended with
+  /// additional choices are kept together in order of definition
+  pub fn sort_rules(&mut self, order: RuleOrder) {
+    let names = self.rule_order(order);
+
+    self
+      .rules
+      .sort_by_key(|r| names.iter().position(|n| *n == r.name()));
+  }
+
+  /// Print the document with its rules sorted in the given order, e.g. to
+  /// publish documents in a canonical order
+  pub fn to_string_ordered(&self, order: RuleOrder) -> String {
+    let mut cddl = self.clone();
+    cddl.sort_rules(order);
+
+    cddl.to_string()
+  }
+}
+
+fn visit(
+  graph: &DependencyGraph,
+  rule: &str,
+  visited: &mut BTreeSet<String>,
+  post_order: &mut Vec<String>,
+) {
+  if !visited.insert(rule.to_string()) {
+    return;
+  }
+
+  for dependency in graph.dependencies(rule).into_iter().rev() {
+    visit(graph, dependency, visited, post_order);
+  }
+
+  post_order.push(rule.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cddl_from_str;
+
+  use indoc::indoc;
+
+  #[test]
+  fn verify_rule_order() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = indoc!(
+      r#"
+        extension = { name: tstr, value: value }
+        value = int / tstr / [* value]
+        message = { header: header, body: body, ? ext: [* extension] }
+        body = { id: id, parts: [* part] }
+        part = tstr / id
+        orphan = id
+        header = { id: id, kind: kind }
+        kind = "a"
+        kind /= "b"
+        id = uint
+      "#
+    );
+    let mut cddl = cddl_from_str(cddl, true)?;
+
+    assert_eq!(
+      cddl.rule_order(RuleOrder::Topological),
+      vec![
+        "extension",
+        "value",
+        "message",
+        "header",
+        "kind",
+        "body",
+        "part",
+        "orphan",
+        "id",
+      ]
+    );
+    assert_eq!(
+      cddl.rule_order(RuleOrder::Alphabetical),
+      vec![
+        "extension",
+        "body",
+        "header",
+        "id",
+        "kind",
+        "message",
+        "orphan",
+        "part",
+        "value",
+      ]
+    );
+    assert_eq!(cddl.rule_order(RuleOrder::Definition)[2], "message");
+
+    // The order doesn't depend on the order of definition
+    let mut reordered = cddl.clone();
+    reordered.rules.swap(2, 9);
+    reordered.rules.swap(4, 6);
+    assert_eq!(
+      reordered.to_string_ordered(RuleOrder::Topological),
+      cddl.to_string_ordered(RuleOrder::Topological)
+    );
+
+    // Rules are printed in the sorted order, with alternates kept together
+    cddl.sort_rules(RuleOrder::Topological);
+    let printed = cddl.to_string();
+    assert!(printed.contains("kind = \"a\"\nkind /= \"b\"\n"));
+    assert_eq!(
+      cddl_from_str(&printed, true)?.rule_order(RuleOrder::Definition),
+      cddl.rule_order(RuleOrder::Topological)
+    );
+
+    Ok(())
+  }
+}
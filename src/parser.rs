@@ -8,17 +8,22 @@ use super::{
   token::{self, SocketPlug, Token},
 };
 
-use std::{cmp::Ordering, marker::PhantomData, mem, result};
+use std::{cmp::Ordering, marker::PhantomData, mem, result, str};
 
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+
+use displaydoc::Display;
+
+#[cfg(feature = "std")]
 use codespan_reporting::{
   diagnostic::{Diagnostic, Label},
   files::SimpleFiles,
-  term,
+  term::{
+    self,
+    termcolor::{ColorChoice, StandardStream},
+  },
 };
-use displaydoc::Display;
-
-#[cfg(feature = "std")]
-use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
 #[cfg(feature = "std")]
 use std::borrow::Cow;
 
@@ -26,9 +31,12 @@ use std::borrow::Cow;
 use alloc::{
   borrow::{Cow, ToOwned},
   boxed::Box,
+  collections::VecDeque,
   string::{String, ToString},
   vec::Vec,
 };
+#[cfg(not(feature = "std"))]
+use core::fmt::Write;
 
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
@@ -42,6 +50,9 @@ pub type Result<T> = result::Result<T, Error>;
 /// Parser type
 pub struct Parser<'a> {
   tokens: Box<dyn Iterator<Item = lexer::Item<'a>> + 'a>,
+  // Tokens read past the peek token by lookahead, which are consumed before
+  // any further tokens
+  lookahead: VecDeque<lexer::Item<'a>>,
   str_input: &'a str,
   cur_token: Token<'a>,
   peek_token: Token<'a>,
@@ -52,10 +63,41 @@ pub struct Parser<'a> {
   /// Vec of collected parsing errors
   pub errors: Vec<Error>,
   #[cfg(feature = "ast-span")]
-  visited_rule_idents: Vec<(&'a str, Span)>,
+  visited_rule_idents: Vec<(Cow<'a, str>, Span)>,
   #[cfg(not(feature = "ast-span"))]
-  visited_rule_idents: Vec<&'a str>,
-  current_rule_generic_param_idents: Option<Vec<&'a str>>,
+  visited_rule_idents: Vec<Cow<'a, str>>,
+  current_rule_generic_param_idents: Option<Vec<Cow<'a, str>>>,
+  // Names of types beyond the standard prelude that rules may reference
+  // without defining them
+  extended_prelude: Vec<String>,
+  // Nesting depth of the type or group being parsed, and the depth past which
+  // parsing fails rather than risk overflowing the stack
+  depth: usize,
+  max_depth: usize,
+}
+
+/// Rule that failed to parse, marking its place in a partially parsed document
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorNode<'a> {
+  /// Name of the rule, if the rule starts with an identifier
+  pub name: Option<&'a str>,
+  /// Index in the parsed rules at which the rule would have been inserted
+  pub position: usize,
+  /// Span of the input skipped up to the next rule
+  #[cfg(feature = "ast-span")]
+  pub span: Span,
+}
+
+/// CDDL document parsed with error recovery, along with the rules that failed
+/// to parse and the errors collected while parsing
+#[derive(Debug)]
+pub struct PartialCDDL<'a> {
+  /// Rules that were parsed successfully
+  pub cddl: CDDL<'a>,
+  /// Rules that failed to parse, in order
+  pub error_nodes: Vec<ErrorNode<'a>>,
+  /// Collected parsing errors
+  pub errors: Vec<Error>,
 }
 
 /// Parsing error types
@@ -81,15 +123,117 @@ pub enum Error {
   /// Lexing error
   LEXER(lexer::Error),
   /// Regex error
+  #[cfg(feature = "std")]
   #[displaydoc("regex parsing error: {0}")]
   REGEX(regex::Error),
   #[displaydoc("incremental parsing error")]
   /// Incremental parsing error
   INCREMENTAL,
+  /// Errors collected while parsing a CDDL document
+  #[displaydoc("{report}")]
+  PARSING {
+    /// CDDL document input
+    input: String,
+    /// Collected parsing errors
+    errors: Vec<Error>,
+    /// Report of each error annotated with the corresponding input
+    report: String,
+  },
+  /// Error reporting parsing errors
+  #[cfg(feature = "std")]
+  #[displaydoc("error reporting parsing errors: {0}")]
+  REPORTING(codespan_reporting::files::Error),
+  /// Input is not valid UTF-8
+  #[displaydoc("{0}")]
+  UTF8(str::Utf8Error),
+  /// CDDL document exceeds the configured schema limits
+  #[displaydoc("schema too large: {0}")]
+  SchemaTooLarge(String),
+}
+
+impl Error {
+  /// Position of the input at which the error occurred. For errors collected
+  /// while parsing a CDDL document, the position of the first error
+  #[cfg(feature = "ast-span")]
+  pub fn position(&self) -> Option<Position> {
+    match self {
+      Error::PARSER { position, .. } => Some(*position),
+      Error::LEXER(e) => Some(e.position()),
+      Error::PARSING { errors, .. } => errors.first().and_then(|e| e.position()),
+      _ => None,
+    }
+  }
 }
 
 #[cfg(feature = "std")]
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Error::LEXER(e) => Some(e),
+      Error::REGEX(e) => Some(e),
+      Error::PARSING { errors, .. } => errors
+        .first()
+        .map(|e| e as &(dyn std::error::Error + 'static)),
+      Error::REPORTING(e) => Some(e),
+      Error::UTF8(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for Error {
+  fn code<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+    match self {
+      Error::LEXER(e) => e.code(),
+      #[cfg(feature = "std")]
+      Error::REGEX(_) => Some(Box::new("cddl::regex")),
+      Error::SchemaTooLarge(_) => Some(Box::new("cddl::schema_too_large")),
+      _ => Some(Box::new("cddl::parser")),
+    }
+  }
+
+  fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+    match self {
+      Error::PARSER { msg, .. } => msg
+        .extended
+        .as_ref()
+        .map(|e| Box::new(e) as Box<dyn std::fmt::Display>),
+      Error::LEXER(e) => e.help(),
+      Error::PARSING { errors, .. } => errors.iter().find_map(|e| e.help()),
+      _ => None,
+    }
+  }
+
+  fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+    match self {
+      Error::LEXER(e) => e.source_code(),
+      Error::PARSING { input, .. } => Some(input),
+      _ => None,
+    }
+  }
+
+  fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+    match self {
+      Error::LEXER(e) => e.labels(),
+      Error::PARSING { errors, .. } => Some(Box::new(
+        errors
+          .iter()
+          .filter_map(|e| e.labels())
+          .flatten()
+          .collect::<Vec<_>>()
+          .into_iter(),
+      )),
+      #[cfg(feature = "ast-span")]
+      Error::PARSER { position, msg } => Some(Box::new(std::iter::once(miette::LabeledSpan::new(
+        Some(msg.to_string()),
+        position.range.0,
+        position.range.1 - position.range.0,
+      )))),
+      _ => None,
+    }
+  }
+}
 
 impl<'a> Parser<'a> {
   /// Create a new `Parser` from a given str input and iterator over
@@ -110,6 +254,7 @@ impl<'a> Parser<'a> {
   ) -> Result<Parser<'a>> {
     let mut p = Parser {
       tokens,
+      lookahead: VecDeque::new(),
       str_input,
       cur_token: Token::EOF,
       peek_token: Token::EOF,
@@ -120,6 +265,9 @@ impl<'a> Parser<'a> {
       parser_position: Position::default(),
       visited_rule_idents: Vec::default(),
       current_rule_generic_param_idents: None,
+      extended_prelude: Vec::default(),
+      depth: 0,
+      max_depth: usize::MAX,
     };
 
     p.next_token()?;
@@ -149,10 +297,7 @@ impl<'a> Parser<'a> {
   /// }
   /// ```
   #[cfg(feature = "std")]
-  pub fn report_errors(
-    &self,
-    to_stderr: bool,
-  ) -> std::result::Result<Option<String>, Box<dyn std::error::Error>> {
+  pub fn report_errors(&self, to_stderr: bool) -> Result<Option<String>> {
     if self.errors.is_empty() {
       return Ok(None);
     }
@@ -190,16 +335,47 @@ impl<'a> Parser<'a> {
       // its minimum version to 1.41
       match term::emit(&mut writer.lock(), &config, &files, &diagnostic) {
         Ok(_) => return Ok(None),
-        Err(e) => return Err(Box::from(e)),
+        Err(e) => return Err(Error::REPORTING(e)),
       };
     }
 
     let mut buffer = Vec::new();
     let mut writer = term::termcolor::NoColor::new(&mut buffer);
 
-    term::emit(&mut writer, &config, &files, &diagnostic)?;
+    term::emit(&mut writer, &config, &files, &diagnostic).map_err(Error::REPORTING)?;
+
+    Ok(Some(
+      String::from_utf8(buffer).map_err(|e| Error::UTF8(e.utf8_error()))?,
+    ))
+  }
+
+  /// Collect the parsing errors into an `Error::PARSING`, or
+  /// `Error::INCREMENTAL` if there aren't any
+  #[cfg(feature = "std")]
+  fn into_parsing_error(self) -> Error {
+    match self.report_errors(false) {
+      Ok(Some(report)) => Error::PARSING {
+        input: self.str_input.to_string(),
+        errors: self.errors,
+        report,
+      },
+      Ok(None) => Error::INCREMENTAL,
+      Err(e) => e,
+    }
+  }
 
-    Ok(Some(String::from_utf8(buffer)?))
+  /// Collect the parsing errors into an `Error::PARSING`, or
+  /// `Error::INCREMENTAL` if there aren't any
+  #[cfg(not(feature = "std"))]
+  fn into_parsing_error(self) -> Error {
+    match self.report_errors() {
+      Some(report) => Error::PARSING {
+        input: self.str_input.to_string(),
+        errors: self.errors,
+        report,
+      },
+      None => Error::INCREMENTAL,
+    }
   }
 
   /// Print parser errors if there are any. Used with the `Error::PARSER`
@@ -224,11 +400,7 @@ impl<'a> Parser<'a> {
       return None;
     }
 
-    let mut files = SimpleFiles::new();
-
-    let file_id = files.add("input", self.str_input);
-
-    let mut labels = Vec::new();
+    let mut report = String::from("error: parser errors\n");
     for error in self.errors.iter() {
       if let Error::PARSER {
         #[cfg(feature = "ast-span")]
@@ -236,34 +408,21 @@ impl<'a> Parser<'a> {
         msg,
       } = error
       {
-        labels.push(
-          #[cfg(feature = "ast-span")]
-          Label::primary(file_id, position.range.0..position.range.1).with_message(msg.to_string()),
-          #[cfg(not(feature = "ast-span"))]
-          Label::primary(file_id, 0..0).with_message(msg.to_string()),
-        );
+        #[cfg(feature = "ast-span")]
+        let _ = writeln!(report, "{}:{}: {}", position.line, position.column, msg);
+        #[cfg(not(feature = "ast-span"))]
+        let _ = writeln!(report, "{}", msg);
       }
     }
 
-    let diagnostic = Diagnostic::error()
-      .with_message("parser errors")
-      .with_labels(labels);
-
-    let config = term::Config::default();
-
-    let mut buffer = Vec::new();
-    let mut writer = term::termcolor::NoColor::new(&mut buffer);
-
-    term::emit(&mut writer, &config, &files, &diagnostic).ok()?;
-
-    String::from_utf8(buffer).ok()
+    Some(report)
   }
 
   fn next_token(&mut self) -> Result<()> {
     mem::swap(&mut self.cur_token, &mut self.peek_token);
     mem::swap(&mut self.lexer_position, &mut self.peek_lexer_position);
 
-    if let Some(next_token) = self.tokens.next() {
+    if let Some(next_token) = self.lookahead.pop_front().or_else(|| self.tokens.next()) {
       let nt = next_token.map_err(Error::LEXER)?;
       self.peek_token = nt.1;
       self.peek_lexer_position = nt.0;
@@ -272,6 +431,24 @@ impl<'a> Parser<'a> {
     Ok(())
   }
 
+  // Advances to the next rule. When recovering, lexing errors are collected
+  // and skipped as well
+  fn skip_to_next_rule(&mut self, recover: bool) -> Result<()> {
+    if !recover {
+      return self.advance_to_next_rule();
+    }
+
+    // Each lexing error consumes input, which bounds the number of errors
+    for _ in 0..=self.str_input.len() {
+      match self.advance_to_next_rule() {
+        Err(Error::LEXER(e)) => self.errors.push(lexer_diagnostic(e)),
+        result => return result,
+      }
+    }
+
+    Err(Error::INCREMENTAL)
+  }
+
   fn advance_to_next_rule(&mut self) -> Result<()> {
     let mut is_possible_rule = false;
 
@@ -297,14 +474,20 @@ impl<'a> Parser<'a> {
 
     while let Token::COMMENT(_comment) = self.cur_token {
       #[cfg(feature = "lsp")]
-      comments.get_or_insert(Comments::default()).0.push(_comment);
+      comments
+        .get_or_insert(Comments::default())
+        .0
+        .push(Cow::Borrowed(_comment));
 
       self.next_token()?;
     }
 
     while let Token::NEWLINE = self.cur_token {
       #[cfg(feature = "lsp")]
-      comments.get_or_insert(Comments::default()).0.push("\n");
+      comments
+        .get_or_insert(Comments::default())
+        .0
+        .push(Cow::Borrowed("\n"));
 
       self.next_token()?;
     }
@@ -312,7 +495,7 @@ impl<'a> Parser<'a> {
     if let Token::COMMENT(_) = self.cur_token {
       if let Some(c) = self.collect_comments()? {
         #[cfg_attr(not(feature = "lsp"), allow(unused_mut))]
-        for comment in c.0.iter() {
+        for comment in c.0.into_iter() {
           comments.get_or_insert(Comments::default()).0.push(comment);
         }
       }
@@ -325,7 +508,10 @@ impl<'a> Parser<'a> {
   fn advance_newline(&mut self) -> Result<()> {
     while let Token::NEWLINE = self.cur_token {
       #[cfg(feature = "lsp")]
-      comments.get_or_insert(Comments::default()).0.push("\n");
+      comments
+        .get_or_insert(Comments::default())
+        .0
+        .push("\n".into());
 
       self.next_token()?;
     }
@@ -333,8 +519,105 @@ impl<'a> Parser<'a> {
     Ok(())
   }
 
+  /// Set the names of types beyond the standard prelude, e.g. those of RFC
+  /// 9090, that rules may reference without defining them
+  pub fn set_extended_prelude(&mut self, names: &[&str]) {
+    self.extended_prelude = names.iter().map(|name| name.to_string()).collect();
+  }
+
+  /// Set the maximum nesting depth of types and groups, past which parsing
+  /// fails with `Error::SchemaTooLarge`. Each array, map, parenthesized type
+  /// or group, generic argument list and tagged type nests one level deeper
+  pub fn set_max_depth(&mut self, max_depth: usize) {
+    self.max_depth = max_depth;
+  }
+
+  // Descend one level of nesting, failing if the maximum depth is exceeded
+  fn enter_nesting(&mut self) -> Result<()> {
+    if self.depth >= self.max_depth {
+      return Err(Error::SchemaTooLarge(format!(
+        "types and groups nested more than {} levels deep",
+        self.max_depth
+      )));
+    }
+
+    self.depth += 1;
+
+    Ok(())
+  }
+
   /// Parses into a `CDDL` AST
   pub fn parse_cddl(&mut self) -> Result<CDDL<'a>> {
+    let (c, _) = self.parse_rules(false)?;
+
+    // TODO: implement second pass over parenthesized type rules whose contents
+    // are Type2::Typename, and if the identifier refers to another group rule
+    // per the match rules in Appendix C, refactor rule into a group rule:
+    //
+    // "A rule defines a name for a type expression (production "type") or for a
+    // group expression (production "grpent"), with the intention that the
+    // semantics does not change when the name is replaced by its (parenthesized
+    // if needed) definition.  Note that whether the name defined by a rule
+    // stands for a type or a group isn't always determined by syntax alone:
+    // e.g., "a = b" can make "a" a type if "b" is a type, or a group if "b" is
+    // a group.  More subtly, in "a = (b)", "a" may be used as a type if "b" is
+    // a type, or as a group both when "b" is a group and when "b" is a type (a
+    // good convention to make the latter case stand out to the human reader is
+    // to write "a = (b,)")."
+    if !self.errors.is_empty() {
+      return Err(Error::INCREMENTAL);
+    }
+
+    if c.rules.is_empty() {
+      self.errors.push(Error::PARSER {
+        #[cfg(feature = "ast-span")]
+        position: self.parser_position,
+        msg: NoRulesDefined.into(),
+      });
+
+      return Err(Error::INCREMENTAL);
+    }
+
+    Ok(c)
+  }
+
+  /// Parses into a partial `CDDL` AST, recovering from errors rather than
+  /// failing. Rules that fail to parse, including on lexing errors, are
+  /// skipped up to the next rule and returned as error nodes, and the errors
+  /// are collected in `errors`
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use cddl::{lexer::Lexer, parser::Parser};
+  ///
+  /// let input = "a = int\nb = [ tstr, * ]\nc = uint";
+  /// let mut p = Parser::new(input, Box::new(Lexer::new(input).iter())).unwrap();
+  /// let (cddl, error_nodes) = p.parse_cddl_partial().unwrap();
+  ///
+  /// assert_eq!(cddl.rules.len(), 2);
+  /// assert_eq!(error_nodes[0].name, Some("b"));
+  /// assert_eq!(error_nodes[0].position, 1);
+  /// assert!(!p.errors.is_empty());
+  /// ```
+  pub fn parse_cddl_partial(&mut self) -> Result<(CDDL<'a>, Vec<ErrorNode<'a>>)> {
+    let (c, error_nodes) = self.parse_rules(true)?;
+
+    if c.rules.is_empty() && error_nodes.is_empty() {
+      self.errors.push(Error::PARSER {
+        #[cfg(feature = "ast-span")]
+        position: self.parser_position,
+        msg: NoRulesDefined.into(),
+      });
+    }
+
+    Ok((c, error_nodes))
+  }
+
+  // Parses rules up to the end of the input, skipping to the next rule after
+  // a rule fails to parse. When recovering, lexing errors are collected as
+  // well rather than returned
+  fn parse_rules(&mut self, recover: bool) -> Result<(CDDL<'a>, Vec<ErrorNode<'a>>)> {
     #[cfg(not(feature = "ast-comments"))]
     self.advance_newline()?;
 
@@ -343,8 +626,16 @@ impl<'a> Parser<'a> {
       comments: self.collect_comments()?,
       ..Default::default()
     };
+    let mut error_nodes = Vec::new();
 
     while self.cur_token != Token::EOF {
+      #[cfg(feature = "ast-span")]
+      let begin = self.lexer_position;
+      let name = match self.cur_token {
+        Token::IDENT(ident, _) => Some(ident),
+        _ => None,
+      };
+
       match self.parse_rule() {
         Ok(r) => {
           let rule_exists =
@@ -366,19 +657,56 @@ impl<'a> Parser<'a> {
           }
 
           c.rules.push(r);
+          continue;
         }
         Err(Error::INCREMENTAL) => {
           if !self.cur_token_is(Token::EOF) {
-            self.advance_to_next_rule()?;
+            self.skip_to_next_rule(recover)?;
           }
         }
+        Err(Error::LEXER(e)) if recover => {
+          self.errors.push(lexer_diagnostic(e));
+          self.skip_to_next_rule(recover)?;
+        }
         Err(e) => return Err(e),
       }
+
+      #[cfg(feature = "ast-span")]
+      let span = {
+        let end = if self.cur_token_is(Token::EOF) {
+          self.str_input.len()
+        } else {
+          self.lexer_position.range.0
+        };
+        let skipped = self.str_input.get(begin.range.0..end).unwrap_or_default();
+
+        (
+          begin.range.0,
+          begin.range.0 + skipped.trim_end().len(),
+          begin.line,
+        )
+      };
+
+      error_nodes.push(ErrorNode {
+        name,
+        position: c.rules.len(),
+        #[cfg(feature = "ast-span")]
+        span,
+      });
     }
 
+    // Rules that failed to parse aren't reported as missing, nor are types of
+    // the extended prelude
+    let extended_prelude = &self.extended_prelude;
+    let is_defined = |rule: &str| {
+      c.rules.iter().any(|r| r.name() == rule)
+        || error_nodes.iter().any(|n: &ErrorNode| n.name == Some(rule))
+        || extended_prelude.iter().any(|name| name == rule)
+    };
+
     #[cfg(feature = "ast-span")]
     for (rule, span) in self.visited_rule_idents.iter() {
-      if !c.rules.iter().any(|r| r.name() == *rule) {
+      if !is_defined(rule) {
         self.errors.push(Error::PARSER {
           position: Position {
             column: 0,
@@ -396,7 +724,7 @@ impl<'a> Parser<'a> {
 
     #[cfg(not(feature = "ast-span"))]
     for rule in self.visited_rule_idents.iter() {
-      if !c.rules.iter().any(|r| r.name() == *rule) {
+      if !is_defined(rule) {
         self.errors.push(Error::PARSER {
           msg: ErrorMsg {
             short: format!("missing definition for rule {}", rule),
@@ -406,35 +734,7 @@ impl<'a> Parser<'a> {
       }
     }
 
-    // TODO: implement second pass over parenthesized type rules whose contents
-    // are Type2::Typename, and if the identifier refers to another group rule
-    // per the match rules in Appendix C, refactor rule into a group rule:
-    //
-    // "A rule defines a name for a type expression (production "type") or for a
-    // group expression (production "grpent"), with the intention that the
-    // semantics does not change when the name is replaced by its (parenthesized
-    // if needed) definition.  Note that whether the name defined by a rule
-    // stands for a type or a group isn't always determined by syntax alone:
-    // e.g., "a = b" can make "a" a type if "b" is a type, or a group if "b" is
-    // a group.  More subtly, in "a = (b)", "a" may be used as a type if "b" is
-    // a type, or as a group both when "b" is a group and when "b" is a type (a
-    // good convention to make the latter case stand out to the human reader is
-    // to write "a = (b,)")."
-    if !self.errors.is_empty() {
-      return Err(Error::INCREMENTAL);
-    }
-
-    if c.rules.is_empty() {
-      self.errors.push(Error::PARSER {
-        #[cfg(feature = "ast-span")]
-        position: self.parser_position,
-        msg: NoRulesDefined.into(),
-      });
-
-      return Err(Error::INCREMENTAL);
-    }
-
-    Ok(c)
+    Ok((c, error_nodes))
   }
 
   #[allow(missing_docs)]
@@ -472,7 +772,7 @@ impl<'a> Parser<'a> {
       let mut param_list = Vec::default();
 
       for param in params.params.iter() {
-        param_list.push(param.param.ident);
+        param_list.push(param.param.ident.clone());
       }
 
       self.current_rule_generic_param_idents = Some(param_list);
@@ -742,17 +1042,11 @@ impl<'a> Parser<'a> {
         })
       }
       _ => {
-        // If type rule is an unwrap type, advance token after parsing type
-        let advance_token = matches!(self.cur_token, Token::UNWRAP);
         #[cfg(feature = "ast-comments")]
         let mut t = self.parse_type(None)?;
         #[cfg(not(feature = "ast-comments"))]
         let t = self.parse_type(None)?;
 
-        if advance_token {
-          self.next_token()?;
-        }
-
         #[cfg(feature = "ast-comments")]
         let comments_after_rule = if let Some(comments) = t.comments_after_type() {
           Some(comments)
@@ -1123,14 +1417,14 @@ impl<'a> Parser<'a> {
           span,
         })
       }
-      _ => token::control_str_from_token(&self.cur_token).map(|ctrl| {
+      _ => token::control_name(&self.cur_token).map(|ctrl| {
         #[cfg(feature = "ast-span")]
         {
           span.0 = self.lexer_position.range.0;
         }
 
         RangeCtlOp::CtlOp {
-          ctrl,
+          ctrl: ctrl.into(),
           #[cfg(feature = "ast-span")]
           span,
         }
@@ -1191,6 +1485,14 @@ impl<'a> Parser<'a> {
 
   #[allow(missing_docs)]
   pub fn parse_type2(&mut self) -> Result<Type2<'a>> {
+    self.enter_nesting()?;
+    let t2 = self.parse_nested_type2();
+    self.depth -= 1;
+
+    t2
+  }
+
+  fn parse_nested_type2(&mut self) -> Result<Type2<'a>> {
     let t2 = match &self.cur_token {
       // value
       Token::VALUE(value) => {
@@ -1451,6 +1753,11 @@ impl<'a> Parser<'a> {
 
       // ~ typename [genericarg]
       Token::UNWRAP => {
+        #[cfg(feature = "ast-span")]
+        let begin_type2_range = self.lexer_position.range.0;
+        #[cfg(feature = "ast-span")]
+        let begin_type2_line = self.lexer_position.line;
+
         self.next_token()?;
 
         #[cfg(feature = "ast-comments")]
@@ -1470,33 +1777,43 @@ impl<'a> Parser<'a> {
           if self.peek_token_is(&Token::LANGLEBRACKET) {
             self.next_token()?;
 
+            let generic_args = Some(self.parse_genericargs()?);
+
             return Ok(Type2::Unwrap {
               #[cfg(feature = "ast-comments")]
               comments,
               ident,
-              generic_args: Some(self.parse_genericargs()?),
+              generic_args,
               #[cfg(feature = "ast-span")]
-              span: (0, 0, 0),
+              span: (
+                begin_type2_range,
+                self.parser_position.range.1,
+                begin_type2_line,
+              ),
             });
           }
 
-          return Ok(Type2::Unwrap {
+          Ok(Type2::Unwrap {
             #[cfg(feature = "ast-comments")]
             comments,
             ident,
             generic_args: None,
             #[cfg(feature = "ast-span")]
-            span: (0, 0, 0),
+            span: (
+              begin_type2_range,
+              self.lexer_position.range.1,
+              begin_type2_line,
+            ),
+          })
+        } else {
+          self.errors.push(Error::PARSER {
+            #[cfg(feature = "ast-span")]
+            position: self.parser_position,
+            msg: InvalidUnwrapSyntax.into(),
           });
-        }
-
-        self.errors.push(Error::PARSER {
-          #[cfg(feature = "ast-span")]
-          position: self.parser_position,
-          msg: InvalidUnwrapSyntax.into(),
-        });
 
-        Err(Error::INCREMENTAL)
+          Err(Error::INCREMENTAL)
+        }
       }
 
       // & ( group )
@@ -1614,6 +1931,30 @@ impl<'a> Parser<'a> {
           // Tagged data item containing the given type as the tagged value
           (Some(6), tag) => {
             self.next_token()?;
+
+            // Tag number given as a type, e.g. `#6.<uint>(t)`
+            let tag_type = if tag.is_none() && self.cur_token_is(Token::LANGLEBRACKET) {
+              self.next_token()?;
+
+              let tag_type = self.parse_type(None)?;
+
+              if !self.cur_token_is(Token::RANGLEBRACKET) {
+                self.errors.push(Error::PARSER {
+                  #[cfg(feature = "ast-span")]
+                  position: self.parser_position,
+                  msg: InvalidTagSyntax.into(),
+                });
+
+                return Err(Error::INCREMENTAL);
+              }
+
+              self.next_token()?;
+
+              Some(Box::new(tag_type))
+            } else {
+              None
+            };
+
             if !self.cur_token_is(Token::LPAREN) {
               self.errors.push(Error::PARSER {
                 #[cfg(feature = "ast-span")]
@@ -1650,6 +1991,7 @@ impl<'a> Parser<'a> {
 
             Ok(Type2::TaggedData {
               tag,
+              tag_type,
               #[cfg(feature = "ast-comments")]
               comments_before_type,
               t,
@@ -1761,6 +2103,14 @@ impl<'a> Parser<'a> {
 
   #[allow(missing_docs)]
   pub fn parse_group(&mut self) -> Result<Group<'a>> {
+    self.enter_nesting()?;
+    let group = self.parse_nested_group();
+    self.depth -= 1;
+
+    group
+  }
+
+  fn parse_nested_group(&mut self) -> Result<Group<'a>> {
     #[cfg(feature = "ast-span")]
     let begin_group_range =
       if let Token::LBRACE | Token::LPAREN | Token::LBRACKET | Token::GCHOICE = &self.cur_token {
@@ -1872,6 +2222,18 @@ impl<'a> Parser<'a> {
         return Ok(grpchoice);
       }
 
+      // A type which isn't a valid memberkey, e.g. a type choice, can't be
+      // followed by "=>"
+      if let Token::ARROWMAP | Token::CUT = &self.cur_token {
+        self.errors.push(Error::PARSER {
+          #[cfg(feature = "ast-span")]
+          position: self.lexer_position,
+          msg: InvalidGroupEntrySyntax.into(),
+        });
+
+        return Err(Error::INCREMENTAL);
+      }
+
       // Don't advance the token if it is part of a member key, comma or an
       // opening or closing map/group delimiter. Otherwise, advance
       if !self.cur_token_is(Token::RPAREN)
@@ -1887,7 +2249,11 @@ impl<'a> Parser<'a> {
         && !self.peek_token_is(&Token::COLON)
         && !self.peek_token_is(&Token::ARROWMAP)
         && !self.cur_token_is(Token::EOF)
-        && !matches!(self.cur_token, Token::IDENT(..))
+        && !matches!(
+          self.cur_token,
+          Token::IDENT(..) | Token::VALUE(_) | Token::TAG(..) | Token::UNWRAP | Token::GTOCHOICE
+        )
+        && self.cur_token.in_standard_prelude().is_none()
       {
         #[cfg(feature = "ast-span")]
         {
@@ -2032,16 +2398,20 @@ impl<'a> Parser<'a> {
         #[cfg(feature = "ast-span")]
         if let Some((name, generic_args, _)) = entry_type.groupname_entry() {
           if name.socket.is_none()
-            && token::lookup_ident(name.ident)
+            && token::lookup_ident(&name.ident)
               .in_standard_prelude()
               .is_none()
           {
             if let Some(params) = &self.current_rule_generic_param_idents {
-              if !params.iter().any(|&p| p == name.ident) {
-                self.visited_rule_idents.push((name.ident, name.span));
+              if !params.iter().any(|p| *p == name.ident) {
+                self
+                  .visited_rule_idents
+                  .push((name.ident.clone(), name.span));
               }
             } else {
-              self.visited_rule_idents.push((name.ident, name.span));
+              self
+                .visited_rule_idents
+                .push((name.ident.clone(), name.span));
             }
           }
 
@@ -2062,16 +2432,16 @@ impl<'a> Parser<'a> {
         #[cfg(not(feature = "ast-span"))]
         if let Some((name, generic_args)) = entry_type.groupname_entry() {
           if name.socket.is_none()
-            && token::lookup_ident(name.ident)
+            && token::lookup_ident(&name.ident)
               .in_standard_prelude()
               .is_none()
           {
             if let Some(params) = &self.current_rule_generic_param_idents {
-              if !params.iter().any(|&p| p == name.ident) {
-                self.visited_rule_idents.push(name.ident);
+              if !params.iter().any(|p| *p == name.ident) {
+                self.visited_rule_idents.push(name.ident.clone());
               }
             } else {
-              self.visited_rule_idents.push(name.ident);
+              self.visited_rule_idents.push(name.ident.clone());
             }
           }
 
@@ -2105,16 +2475,20 @@ impl<'a> Parser<'a> {
         #[cfg(feature = "ast-span")]
         if let Some((ident, _, _)) = entry_type.groupname_entry() {
           if ident.socket.is_none()
-            && token::lookup_ident(ident.ident)
+            && token::lookup_ident(&ident.ident)
               .in_standard_prelude()
               .is_none()
           {
             if let Some(params) = &self.current_rule_generic_param_idents {
-              if !params.iter().any(|&p| p == ident.ident) {
-                self.visited_rule_idents.push((ident.ident, ident.span));
+              if !params.iter().any(|p| *p == ident.ident) {
+                self
+                  .visited_rule_idents
+                  .push((ident.ident.clone(), ident.span));
               }
             } else {
-              self.visited_rule_idents.push((ident.ident, ident.span));
+              self
+                .visited_rule_idents
+                .push((ident.ident.clone(), ident.span));
             }
           }
         }
@@ -2122,16 +2496,16 @@ impl<'a> Parser<'a> {
         #[cfg(not(feature = "ast-span"))]
         if let Some((ident, _)) = entry_type.groupname_entry() {
           if ident.socket.is_none()
-            && token::lookup_ident(ident.ident)
+            && token::lookup_ident(&ident.ident)
               .in_standard_prelude()
               .is_none()
           {
             if let Some(params) = &self.current_rule_generic_param_idents {
-              if !params.iter().any(|&p| p == ident.ident) {
-                self.visited_rule_idents.push(ident.ident);
+              if !params.iter().any(|p| *p == ident.ident) {
+                self.visited_rule_idents.push(ident.ident.clone());
               }
             } else {
-              self.visited_rule_idents.push(ident.ident);
+              self.visited_rule_idents.push(ident.ident.clone());
             }
           }
         }
@@ -2195,16 +2569,20 @@ impl<'a> Parser<'a> {
         #[cfg(feature = "ast-span")]
         if let Some((ident, _, _)) = entry_type.groupname_entry() {
           if ident.socket.is_none()
-            && token::lookup_ident(ident.ident)
+            && token::lookup_ident(&ident.ident)
               .in_standard_prelude()
               .is_none()
           {
             if let Some(params) = &self.current_rule_generic_param_idents {
-              if !params.iter().any(|&p| p == ident.ident) {
-                self.visited_rule_idents.push((ident.ident, ident.span));
+              if !params.iter().any(|p| *p == ident.ident) {
+                self
+                  .visited_rule_idents
+                  .push((ident.ident.clone(), ident.span));
               }
             } else {
-              self.visited_rule_idents.push((ident.ident, ident.span));
+              self
+                .visited_rule_idents
+                .push((ident.ident.clone(), ident.span));
             }
           }
         }
@@ -2212,16 +2590,16 @@ impl<'a> Parser<'a> {
         #[cfg(not(feature = "ast-span"))]
         if let Some((ident, _)) = entry_type.groupname_entry() {
           if ident.socket.is_none()
-            && token::lookup_ident(ident.ident)
+            && token::lookup_ident(&ident.ident)
               .in_standard_prelude()
               .is_none()
           {
             if let Some(params) = &self.current_rule_generic_param_idents {
-              if !params.iter().any(|&p| p == ident.ident) {
-                self.visited_rule_idents.push(ident.ident);
+              if !params.iter().any(|p| *p == ident.ident) {
+                self.visited_rule_idents.push(ident.ident.clone());
               }
             } else {
-              self.visited_rule_idents.push(ident.ident);
+              self.visited_rule_idents.push(ident.ident.clone());
             }
           }
         }
@@ -2276,16 +2654,20 @@ impl<'a> Parser<'a> {
           }
 
           if name.socket.is_none()
-            && token::lookup_ident(name.ident)
+            && token::lookup_ident(&name.ident)
               .in_standard_prelude()
               .is_none()
           {
             if let Some(params) = &self.current_rule_generic_param_idents {
-              if !params.iter().any(|&p| p == name.ident) {
-                self.visited_rule_idents.push((name.ident, name.span));
+              if !params.iter().any(|p| *p == name.ident) {
+                self
+                  .visited_rule_idents
+                  .push((name.ident.clone(), name.span));
               }
             } else {
-              self.visited_rule_idents.push((name.ident, name.span));
+              self
+                .visited_rule_idents
+                .push((name.ident.clone(), name.span));
             }
           }
 
@@ -2314,16 +2696,16 @@ impl<'a> Parser<'a> {
           }
 
           if name.socket.is_none()
-            && token::lookup_ident(name.ident)
+            && token::lookup_ident(&name.ident)
               .in_standard_prelude()
               .is_none()
           {
             if let Some(params) = &self.current_rule_generic_param_idents {
-              if !params.iter().any(|&p| p == name.ident) {
-                self.visited_rule_idents.push(name.ident);
+              if !params.iter().any(|p| *p == name.ident) {
+                self.visited_rule_idents.push(name.ident.clone());
               }
             } else {
-              self.visited_rule_idents.push(name.ident);
+              self.visited_rule_idents.push(name.ident.clone());
             }
           }
 
@@ -2343,16 +2725,20 @@ impl<'a> Parser<'a> {
         #[cfg(feature = "ast-span")]
         if let Some((ident, _, _)) = entry_type.groupname_entry() {
           if ident.socket.is_none()
-            && token::lookup_ident(ident.ident)
+            && token::lookup_ident(&ident.ident)
               .in_standard_prelude()
               .is_none()
           {
             if let Some(params) = &self.current_rule_generic_param_idents {
-              if !params.iter().any(|&p| p == ident.ident) {
-                self.visited_rule_idents.push((ident.ident, ident.span));
+              if !params.iter().any(|p| *p == ident.ident) {
+                self
+                  .visited_rule_idents
+                  .push((ident.ident.clone(), ident.span));
               }
             } else {
-              self.visited_rule_idents.push((ident.ident, ident.span));
+              self
+                .visited_rule_idents
+                .push((ident.ident.clone(), ident.span));
             }
           }
         }
@@ -2360,16 +2746,16 @@ impl<'a> Parser<'a> {
         #[cfg(not(feature = "ast-span"))]
         if let Some((ident, _)) = entry_type.groupname_entry() {
           if ident.socket.is_none()
-            && token::lookup_ident(ident.ident)
+            && token::lookup_ident(&ident.ident)
               .in_standard_prelude()
               .is_none()
           {
             if let Some(params) = &self.current_rule_generic_param_idents {
-              if !params.iter().any(|&p| p == ident.ident) {
-                self.visited_rule_idents.push(ident.ident);
+              if !params.iter().any(|p| *p == ident.ident) {
+                self.visited_rule_idents.push(ident.ident.clone());
               }
             } else {
-              self.visited_rule_idents.push(ident.ident);
+              self.visited_rule_idents.push(ident.ident.clone());
             }
           }
         }
@@ -2581,6 +2967,28 @@ impl<'a> Parser<'a> {
     #[cfg(feature = "ast-span")]
     let begin_memberkey_line = self.lexer_position.line;
 
+    // Unlike a bareword or value followed by ":", "=>" or "^", a type1
+    // memberkey with generic arguments or a range or control operator can't be
+    // told apart from the type of a group entry by the next token, so look
+    // ahead past the type1 for the "=>"
+    let is_bareword_or_value = (self.cur_token.in_standard_prelude().is_some()
+      || matches!(self.cur_token, Token::IDENT(..) | Token::VALUE(_)))
+      && (self.peek_token_is(&Token::COLON)
+        || self.peek_token_is(&Token::ARROWMAP)
+        || self.peek_token_is(&Token::CUT));
+    if !is_bareword_or_value {
+      if let Some(has_operator) = self.type1_memberkey_ahead() {
+        if has_operator || !self.cur_token_is(Token::LPAREN) {
+          return self.parse_type1_memberkey(
+            #[cfg(feature = "ast-span")]
+            begin_memberkey_range,
+            #[cfg(feature = "ast-span")]
+            begin_memberkey_line,
+          );
+        }
+      }
+    }
+
     if let Some(t) = self.cur_token.in_standard_prelude() {
       return self.parse_memberkey_from_ident(
         is_optional,
@@ -2694,6 +3102,8 @@ impl<'a> Parser<'a> {
             self.parser_position.range.1 = self.lexer_position.range.1;
           }
 
+          let is_arrowmap = self.cur_token_is(Token::ARROWMAP);
+
           self.next_token()?;
 
           #[cfg(feature = "ast-comments")]
@@ -2701,7 +3111,28 @@ impl<'a> Parser<'a> {
           #[cfg(not(feature = "ast-comments"))]
           self.advance_newline()?;
 
-          Some(MemberKey::Value {
+          // Unlike the colon shortcut, a value followed by "=>" is a type
+          // expression without a cut
+          if is_arrowmap {
+            return Ok(Some(MemberKey::Type1 {
+              t1: Box::from(t1),
+              #[cfg(feature = "ast-comments")]
+              comments_before_cut: comments,
+              is_cut: false,
+              #[cfg(feature = "ast-comments")]
+              comments_after_cut: None,
+              #[cfg(feature = "ast-comments")]
+              comments_after_arrowmap: memberkey_comments,
+              #[cfg(feature = "ast-span")]
+              span: (
+                begin_memberkey_range,
+                self.parser_position.range.1,
+                begin_memberkey_line,
+              ),
+            }));
+          }
+
+          Some(MemberKey::Value {
             value,
             #[cfg(feature = "ast-comments")]
             comments,
@@ -2739,7 +3170,7 @@ impl<'a> Parser<'a> {
         #[cfg(not(feature = "ast-comments"))]
         self.advance_newline()?;
 
-        let mut tokens: Vec<lexer::Item> = Vec::new();
+        let mut tokens: Vec<(Position, Token)> = Vec::new();
 
         #[cfg(feature = "ast-comments")]
         let mut comments_after_type_or_group = None;
@@ -2776,7 +3207,7 @@ impl<'a> Parser<'a> {
             }
           }
 
-          tokens.push(Ok((self.lexer_position, self.cur_token.clone())));
+          tokens.push((self.lexer_position, self.cur_token.clone()));
 
           #[cfg(feature = "ast-span")]
           {
@@ -2803,9 +3234,28 @@ impl<'a> Parser<'a> {
           }
         }
 
+        // Parse tokens vec as type. Unless the type spans all of the tokens,
+        // e.g. "(int, tstr)" or "(+ int)", they're parsed as a group instead
+        let mut t = None;
+        if !has_group_entries {
+          let mut p = Parser::new(self.str_input, Box::new(tokens.clone().into_iter().map(Ok)))?;
+          match p.parse_type(None) {
+            Ok(pt)
+              if tokens.last().map(|(position, _)| position.range)
+                == Some(p.lexer_position.range) =>
+            {
+              t = Some(pt)
+            }
+            Ok(_) | Err(Error::INCREMENTAL) => (),
+            Err(e) => return Err(e),
+          }
+        }
+
         // Parse tokens vec as group
-        if has_group_entries {
-          let mut p = Parser::new(self.str_input, Box::new(tokens.into_iter()))?;
+        let t = if let Some(t) = t {
+          t
+        } else {
+          let mut p = Parser::new(self.str_input, Box::new(tokens.into_iter().map(Ok)))?;
           let group = match p.parse_group() {
             Ok(g) => g,
             Err(Error::INCREMENTAL) => {
@@ -2825,21 +3275,39 @@ impl<'a> Parser<'a> {
             #[cfg(feature = "ast-comments")]
             comments_after_type_or_group,
           }));
-        }
-
-        // Parse tokens vec as type
-        let mut p = Parser::new(self.str_input, Box::new(tokens.into_iter()))?;
-        let t = match p.parse_type(None) {
-          Ok(t) => t,
-          Err(Error::INCREMENTAL) => {
-            for e in p.errors.into_iter() {
-              self.errors.push(e);
-            }
+        };
 
-            return Err(Error::INCREMENTAL);
-          }
-          Err(e) => return Err(e),
+        // A parenthesized type followed by a range or control operator or
+        // further type choices is the type of a group entry
+        let n = self.skip_comments_ahead(0);
+        let is_type = match self.token_ahead(n) {
+          Some(Token::RANGEOP(_)) | Some(Token::TCHOICE) => true,
+          Some(t) => token::control_name(t).is_some(),
+          None => false,
         };
+        if is_type {
+          let entry_type = self.parse_type(Some(Type2::ParenthesizedType {
+            pt: t,
+            #[cfg(feature = "ast-comments")]
+            comments_before_type: comments_before_type_or_group,
+            #[cfg(feature = "ast-comments")]
+            comments_after_type: comments_after_type_or_group,
+            #[cfg(feature = "ast-span")]
+            span: (
+              begin_memberkey_range,
+              closing_parend_index,
+              begin_memberkey_line,
+            ),
+          }))?;
+
+          return Ok(Some(MemberKey::NonMemberKey {
+            non_member_key: NonMemberKey::Type(entry_type),
+            #[cfg(feature = "ast-comments")]
+            comments_before_type_or_group: None,
+            #[cfg(feature = "ast-comments")]
+            comments_after_type_or_group: None,
+          }));
+        }
 
         #[cfg(feature = "ast-comments")]
         let comments_before_cut = self.collect_comments()?;
@@ -2866,6 +3334,13 @@ impl<'a> Parser<'a> {
           #[cfg(feature = "ast-span")]
           let end_memberkey_range = self.lexer_position.range.1;
 
+          self.next_token()?;
+
+          #[cfg(feature = "ast-comments")]
+          let memberkey_comments = self.collect_comments()?;
+          #[cfg(not(feature = "ast-comments"))]
+          self.advance_newline()?;
+
           let t1 = Some(MemberKey::Type1 {
             t1: Box::from(Type1 {
               type2: Type2::ParenthesizedType {
@@ -2897,7 +3372,7 @@ impl<'a> Parser<'a> {
             #[cfg(feature = "ast-comments")]
             comments_after_cut,
             #[cfg(feature = "ast-comments")]
-            comments_after_arrowmap: None,
+            comments_after_arrowmap: memberkey_comments,
             #[cfg(feature = "ast-span")]
             span: (
               begin_memberkey_range,
@@ -2981,115 +3456,155 @@ impl<'a> Parser<'a> {
 
         Ok(t1)
       }
-      _ => {
-        let t1 = self.parse_type1(None)?;
+      _ => self.parse_type1_memberkey(
+        #[cfg(feature = "ast-span")]
+        begin_memberkey_range,
+        #[cfg(feature = "ast-span")]
+        begin_memberkey_line,
+      ),
+    }
+  }
 
-        #[cfg(feature = "ast-comments")]
-        let comments_before_cut = self.collect_comments()?;
-        #[cfg(not(feature = "ast-comments"))]
-        self.advance_newline()?;
+  // A type1 memberkey with an optional cut, i.e. type1 S ["^" S] "=>". If not
+  // followed by a cut or "=>", the type is the type of a group entry instead
+  fn parse_type1_memberkey(
+    &mut self,
+    #[cfg(feature = "ast-span")] begin_memberkey_range: usize,
+    #[cfg(feature = "ast-span")] begin_memberkey_line: usize,
+  ) -> Result<Option<MemberKey<'a>>> {
+    let t1 = self.parse_type1(None)?;
 
-        if let Token::CUT = &self.cur_token {
-          self.next_token()?;
+    #[cfg(feature = "ast-comments")]
+    let comments_before_cut = self.collect_comments()?;
+    #[cfg(not(feature = "ast-comments"))]
+    self.advance_newline()?;
 
-          #[cfg(feature = "ast-comments")]
-          let comments_after_cut = self.collect_comments()?;
-          #[cfg(not(feature = "ast-comments"))]
-          self.advance_newline()?;
+    if let Token::CUT = &self.cur_token {
+      self.next_token()?;
 
-          if !self.cur_token_is(Token::ARROWMAP) {
-            self.errors.push(Error::PARSER {
-              #[cfg(feature = "ast-span")]
-              position: self.lexer_position,
-              msg: InvalidMemberKeyArrowMapSyntax.into(),
-            });
-            return Err(Error::INCREMENTAL);
-          }
+      #[cfg(feature = "ast-comments")]
+      let comments_after_cut = self.collect_comments()?;
+      #[cfg(not(feature = "ast-comments"))]
+      self.advance_newline()?;
 
+      if !self.cur_token_is(Token::ARROWMAP) {
+        self.errors.push(Error::PARSER {
           #[cfg(feature = "ast-span")]
-          let end_memberkey_range = self.lexer_position.range.1;
+          position: self.lexer_position,
+          msg: InvalidMemberKeyArrowMapSyntax.into(),
+        });
+        return Err(Error::INCREMENTAL);
+      }
 
-          self.next_token()?;
+      #[cfg(feature = "ast-span")]
+      let end_memberkey_range = self.lexer_position.range.1;
 
-          #[cfg(feature = "ast-comments")]
-          let memberkey_comments = self.collect_comments()?;
-          #[cfg(not(feature = "ast-comments"))]
-          self.advance_newline()?;
+      self.next_token()?;
 
-          return Ok(Some(MemberKey::Type1 {
-            t1: Box::from(t1),
-            #[cfg(feature = "ast-comments")]
-            comments_before_cut,
-            is_cut: true,
-            #[cfg(feature = "ast-comments")]
-            comments_after_cut,
-            #[cfg(feature = "ast-comments")]
-            comments_after_arrowmap: memberkey_comments,
-            #[cfg(feature = "ast-span")]
-            span: (
-              begin_memberkey_range,
-              end_memberkey_range,
-              begin_memberkey_line,
-            ),
-          }));
-        }
+      #[cfg(feature = "ast-comments")]
+      let memberkey_comments = self.collect_comments()?;
+      #[cfg(not(feature = "ast-comments"))]
+      self.advance_newline()?;
 
-        let t1 = if let Token::ARROWMAP = &self.cur_token {
-          self.next_token()?;
+      return Ok(Some(MemberKey::Type1 {
+        t1: Box::from(t1),
+        #[cfg(feature = "ast-comments")]
+        comments_before_cut,
+        is_cut: true,
+        #[cfg(feature = "ast-comments")]
+        comments_after_cut,
+        #[cfg(feature = "ast-comments")]
+        comments_after_arrowmap: memberkey_comments,
+        #[cfg(feature = "ast-span")]
+        span: (
+          begin_memberkey_range,
+          end_memberkey_range,
+          begin_memberkey_line,
+        ),
+      }));
+    }
 
-          #[cfg(feature = "ast-span")]
-          {
-            self.parser_position.range.1 = self.lexer_position.range.1;
-          }
+    let t1 = if let Token::ARROWMAP = &self.cur_token {
+      self.next_token()?;
 
-          #[cfg(feature = "ast-comments")]
-          let memberkey_comments = self.collect_comments()?;
-          #[cfg(not(feature = "ast-comments"))]
-          self.advance_newline()?;
+      #[cfg(feature = "ast-span")]
+      {
+        self.parser_position.range.1 = self.lexer_position.range.1;
+      }
 
-          Some(MemberKey::Type1 {
-            t1: Box::from(t1),
-            #[cfg(feature = "ast-comments")]
-            comments_before_cut,
-            is_cut: false,
-            #[cfg(feature = "ast-comments")]
-            comments_after_cut: None,
-            #[cfg(feature = "ast-comments")]
-            comments_after_arrowmap: memberkey_comments,
-            #[cfg(feature = "ast-span")]
-            span: (
-              begin_memberkey_range,
-              self.parser_position.range.1,
-              begin_memberkey_line,
-            ),
-          })
-        } else {
-          Some(MemberKey::NonMemberKey {
-            non_member_key: NonMemberKey::Type(Type {
-              type_choices: vec![TypeChoice {
-                #[cfg(feature = "ast-comments")]
-                comments_before_type: None,
-                #[cfg(feature = "ast-comments")]
-                comments_after_type: None,
-                type1: t1,
-              }],
-              #[cfg(feature = "ast-span")]
-              span: (
-                begin_memberkey_range,
-                self.parser_position.range.1,
-                begin_memberkey_line,
-              ),
-            }),
-            #[cfg(feature = "ast-comments")]
-            comments_before_type_or_group: None,
-            #[cfg(feature = "ast-comments")]
-            comments_after_type_or_group: comments_before_cut,
-          })
-        };
+      #[cfg(feature = "ast-comments")]
+      let memberkey_comments = self.collect_comments()?;
+      #[cfg(not(feature = "ast-comments"))]
+      self.advance_newline()?;
 
-        Ok(t1)
+      Some(MemberKey::Type1 {
+        t1: Box::from(t1),
+        #[cfg(feature = "ast-comments")]
+        comments_before_cut,
+        is_cut: false,
+        #[cfg(feature = "ast-comments")]
+        comments_after_cut: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_arrowmap: memberkey_comments,
+        #[cfg(feature = "ast-span")]
+        span: (
+          begin_memberkey_range,
+          self.parser_position.range.1,
+          begin_memberkey_line,
+        ),
+      })
+    } else {
+      let mut type_choices = vec![TypeChoice {
+        #[cfg(feature = "ast-comments")]
+        comments_before_type: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_type: None,
+        type1: t1,
+      }];
+
+      // The type of the group entry may have further type choices
+      while let Token::TCHOICE = &self.cur_token {
+        self.next_token()?;
+
+        #[cfg(feature = "ast-comments")]
+        let comments_before_type = self.collect_comments()?;
+        #[cfg(not(feature = "ast-comments"))]
+        self.advance_newline()?;
+
+        let type1 = self.parse_type1(None)?;
+
+        #[cfg(feature = "ast-comments")]
+        let comments_after_type = self.collect_comments()?;
+        #[cfg(not(feature = "ast-comments"))]
+        self.advance_newline()?;
+
+        type_choices.push(TypeChoice {
+          #[cfg(feature = "ast-comments")]
+          comments_before_type,
+          #[cfg(feature = "ast-comments")]
+          comments_after_type,
+          type1,
+        });
       }
-    }
+
+      Some(MemberKey::NonMemberKey {
+        non_member_key: NonMemberKey::Type(Type {
+          type_choices,
+          #[cfg(feature = "ast-span")]
+          span: (
+            begin_memberkey_range,
+            self.parser_position.range.1,
+            begin_memberkey_line,
+          ),
+        }),
+        #[cfg(feature = "ast-comments")]
+        comments_before_type_or_group: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_type_or_group: comments_before_cut,
+      })
+    };
+
+    Ok(t1)
   }
 
   #[allow(missing_docs)]
@@ -3295,6 +3810,109 @@ impl<'a> Parser<'a> {
     Ok(false)
   }
 
+  /// Token `n` tokens past the current token, reading ahead of the peek token
+  /// as needed. Returns `None` past the last token or at a lexing error, which
+  /// is only reported once the token is consumed
+  fn token_ahead(&mut self, n: usize) -> Option<&Token<'a>> {
+    match n {
+      0 => Some(&self.cur_token),
+      1 => Some(&self.peek_token),
+      _ => {
+        while self.lookahead.len() < n - 1 {
+          self.lookahead.push_back(self.tokens.next()?);
+        }
+
+        match &self.lookahead[n - 2] {
+          Ok((_, t)) => Some(t),
+          Err(_) => None,
+        }
+      }
+    }
+  }
+
+  /// Index of the first token from index `n` onwards which isn't a comment or
+  /// newline
+  fn skip_comments_ahead(&mut self, mut n: usize) -> usize {
+    while let Some(Token::COMMENT(_)) | Some(Token::NEWLINE) = self.token_ahead(n) {
+      n += 1;
+    }
+
+    n
+  }
+
+  /// Index of the token following the type2 starting at index `n`, if any
+  fn skip_type2_ahead(&mut self, n: usize) -> Option<usize> {
+    match self.token_ahead(n)? {
+      Token::LPAREN | Token::LBRACE | Token::LBRACKET => self.skip_delimited_ahead(n),
+      Token::UNWRAP | Token::GTOCHOICE => self.skip_type2_ahead(n + 1),
+      Token::TAG(Some(6), _) => match self.token_ahead(n + 1) {
+        Some(Token::LPAREN) => self.skip_delimited_ahead(n + 1),
+        _ => Some(n + 1),
+      },
+      Token::TAG(..) | Token::VALUE(_) => Some(n + 1),
+      t if matches!(t, Token::IDENT(..)) || t.in_standard_prelude().is_some() => {
+        if let Some(Token::LANGLEBRACKET) = self.token_ahead(n + 1) {
+          let mut n = n + 2;
+          let mut depth = 0;
+          loop {
+            match self.token_ahead(n)? {
+              Token::LANGLEBRACKET => depth += 1,
+              Token::RANGLEBRACKET if depth == 0 => return Some(n + 1),
+              Token::RANGLEBRACKET => depth -= 1,
+              _ => (),
+            }
+            n += 1;
+          }
+        }
+
+        Some(n + 1)
+      }
+      _ => None,
+    }
+  }
+
+  /// Index of the token following the delimiter closing the one at index `n`,
+  /// if any
+  fn skip_delimited_ahead(&mut self, mut n: usize) -> Option<usize> {
+    let mut depth = 0;
+    loop {
+      match self.token_ahead(n)? {
+        Token::LPAREN | Token::LBRACE | Token::LBRACKET => depth += 1,
+        Token::RPAREN | Token::RBRACE | Token::RBRACKET => depth -= 1,
+        Token::EOF => return None,
+        _ => (),
+      }
+      n += 1;
+
+      if depth == 0 {
+        return Some(n);
+      }
+    }
+  }
+
+  /// Scans ahead for a type1 memberkey, i.e. type1 S ["^" S] "=>", without
+  /// consuming any tokens. Returns whether the type1 has a range or control
+  /// operator
+  fn type1_memberkey_ahead(&mut self) -> Option<bool> {
+    let mut n = self.skip_type2_ahead(0)?;
+    n = self.skip_comments_ahead(n);
+
+    let has_operator = match self.token_ahead(n)? {
+      Token::RANGEOP(_) => true,
+      t => token::control_name(t).is_some(),
+    };
+    if has_operator {
+      n = self.skip_comments_ahead(n + 1);
+      n = self.skip_type2_ahead(n)?;
+      n = self.skip_comments_ahead(n);
+    }
+
+    match self.token_ahead(n)? {
+      Token::CUT | Token::ARROWMAP => Some(has_operator),
+      _ => None,
+    }
+  }
+
   /// Create `ast::Identifier` from `Token::IDENT(ident)`
   fn identifier_from_ident_token(
     &self,
@@ -3302,7 +3920,7 @@ impl<'a> Parser<'a> {
     socket: Option<token::SocketPlug>,
   ) -> Identifier<'a> {
     Identifier {
-      ident,
+      ident: ident.into(),
       socket,
       #[cfg(feature = "ast-span")]
       span: (
@@ -3314,6 +3932,59 @@ impl<'a> Parser<'a> {
   }
 }
 
+// Lexing error as a parsing error, so that it's reported along with the other
+// collected errors
+fn lexer_diagnostic(e: lexer::Error) -> Error {
+  Error::PARSER {
+    #[cfg(feature = "ast-span")]
+    position: e.position(),
+    msg: ErrorMsg {
+      short: e.label_message(),
+      extended: None,
+    },
+  }
+}
+
+/// Returns a partial `ast::CDDL` from a `&str`, recovering from errors as with
+/// [`Parser::parse_cddl_partial`] so that all syntax errors of the document are
+/// reported at once
+///
+/// # Arguments
+///
+/// * `input` - A string slice with the CDDL text input
+/// * `print_stderr` - When true, print any errors to stderr
+///
+/// # Example
+///
+/// ```
+/// use cddl::parser::cddl_from_str_partial;
+///
+/// let input = "a = [ int, * ]\nb = tstr\nc = h'zz'";
+/// let partial = cddl_from_str_partial(input, false).unwrap();
+/// assert_eq!(partial.cddl.rules.len(), 1);
+/// assert_eq!(partial.error_nodes.len(), 2);
+/// assert_eq!(partial.errors.len(), 2);
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "std")]
+pub fn cddl_from_str_partial<'a>(
+  input: &'a str,
+  print_stderr: bool,
+) -> result::Result<PartialCDDL<'a>, Error> {
+  let mut p = Parser::new(input, Box::new(lexer::lexer_from_str(input).iter()))?;
+
+  let (cddl, error_nodes) = p.parse_cddl_partial()?;
+  if print_stderr {
+    p.report_errors(true)?;
+  }
+
+  Ok(PartialCDDL {
+    cddl,
+    error_nodes,
+    errors: p.errors,
+  })
+}
+
 /// Returns a `ast::CDDL` from a `&str`
 ///
 /// # Arguments
@@ -3330,75 +4001,291 @@ impl<'a> Parser<'a> {
 /// let _ = cddl_from_str(input, true);
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(feature = "std")]
-pub fn cddl_from_str(input: &str, print_stderr: bool) -> std::result::Result<CDDL, String> {
-  match Parser::new(input, Box::new(lexer::lexer_from_str(input).iter())).map_err(|e| e.to_string())
-  {
-    Ok(mut p) => match p.parse_cddl() {
-      Ok(c) => Ok(c),
-      Err(Error::INCREMENTAL) => {
-        let e = if print_stderr {
-          p.report_errors(true)
-        } else {
-          p.report_errors(false)
-        };
+pub fn cddl_from_str(input: &str, print_stderr: bool) -> result::Result<CDDL, Error> {
+  let mut p = Parser::new(input, Box::new(lexer::lexer_from_str(input).iter()))?;
+
+  match p.parse_cddl() {
+    Ok(c) => Ok(c),
+    Err(Error::INCREMENTAL) => {
+      if print_stderr {
+        p.report_errors(true)?;
+      }
 
-        if let Ok(Some(e)) = e {
-          return Err(e);
-        }
+      Err(p.into_parsing_error())
+    }
+    Err(e) => Err(e),
+  }
+}
 
-        Err(Error::INCREMENTAL.to_string())
+/// Returns a `ast::CDDL` from a `&str` as with [`cddl_from_str`], not reporting
+/// references to the given types beyond the standard prelude as missing
+/// definitions. The names of the types registered with a validator's extended
+/// prelude are returned by `ExtendedPrelude::names`
+///
+/// # Arguments
+///
+/// * `input` - A string slice with the CDDL text input
+/// * `extended_prelude` - Names of the types beyond the standard prelude
+/// * `print_stderr` - When true, print any errors to stderr
+///
+/// # Example
+///
+/// ```
+/// use cddl::parser::cddl_from_str_with_extended_prelude;
+///
+/// let input = r#"thing = [oid, tstr]"#;
+/// assert!(cddl_from_str_with_extended_prelude(input, &["oid"], false).is_ok());
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "std")]
+pub fn cddl_from_str_with_extended_prelude<'a>(
+  input: &'a str,
+  extended_prelude: &[&str],
+  print_stderr: bool,
+) -> result::Result<CDDL<'a>, Error> {
+  let mut p = Parser::new(input, Box::new(lexer::lexer_from_str(input).iter()))?;
+  p.set_extended_prelude(extended_prelude);
+
+  match p.parse_cddl() {
+    Ok(c) => Ok(c),
+    Err(Error::INCREMENTAL) => {
+      if print_stderr {
+        p.report_errors(true)?;
       }
-      Err(e) => Err(e.to_string()),
-    },
+
+      Err(p.into_parsing_error())
+    }
     Err(e) => Err(e),
   }
 }
 
-impl<'a> CDDL<'a> {
-  /// Parses CDDL from a byte slice
-  #[cfg(not(target_arch = "wasm32"))]
-  #[cfg(feature = "std")]
-  pub fn from_slice(input: &[u8]) -> std::result::Result<CDDL, String> {
-    let str_input = std::str::from_utf8(input).map_err(|e| e.to_string())?;
+/// Caps on the size of a CDDL document, protecting services that accept
+/// untrusted schemas from memory exhaustion
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaLimits {
+  /// Maximum length of the document in bytes. Defaults to 1 MiB
+  pub max_input_len: usize,
+  /// Maximum number of rules, counting each definition of a rule extended with
+  /// additional choices. Defaults to 10,000
+  pub max_rules: usize,
+  /// Maximum factor by which instantiating generic rules may grow the
+  /// document, measured in type and group entry nodes. Defaults to 100
+  pub max_generic_expansion: usize,
+  /// Maximum nesting depth of types and groups, bounding the stack used to
+  /// parse and walk the document. Defaults to 128, which unoptimized builds
+  /// may need more than the default 2 MiB stack of spawned threads to reach
+  pub max_depth: usize,
+  /// Maximum number of type and group entry nodes of the document before
+  /// instantiating generic rules. Defaults to 100,000
+  pub max_nodes: usize,
+}
 
-    match Parser::new(str_input, Box::new(lexer::Lexer::from_slice(input).iter()))
-      .map_err(|e| e.to_string())
-    {
-      Ok(mut p) => match p.parse_cddl() {
-        Ok(c) => Ok(c),
-        Err(Error::INCREMENTAL) => {
-          if let Ok(Some(e)) = p.report_errors(false) {
-            return Err(e);
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "std")]
+impl Default for SchemaLimits {
+  fn default() -> Self {
+    SchemaLimits {
+      max_input_len: 1 << 20,
+      max_rules: 10_000,
+      max_generic_expansion: 100,
+      max_depth: 128,
+      max_nodes: 100_000,
+    }
+  }
+}
+
+/// Returns a `ast::CDDL` from a `&str` as with [`cddl_from_str`], failing with
+/// `Error::SchemaTooLarge` if the document exceeds any of the given limits. The
+/// length of the input is checked before it is parsed, the nesting depth while
+/// it is parsed, and the growth of the document by generic rules without
+/// instantiating them
+///
+/// # Arguments
+///
+/// * `input` - A string slice with the CDDL text input
+/// * `limits` - Caps on the size of the document
+/// * `print_stderr` - When true, print any errors to stderr
+///
+/// # Example
+///
+/// ```
+/// use cddl::parser::{cddl_from_str_with_limits, Error, SchemaLimits};
+///
+/// let limits = SchemaLimits {
+///   max_generic_expansion: 10,
+///   ..Default::default()
+/// };
+///
+/// let input = "a = b<b<b<b<int>>>>\nb<t> = [t, t, t, t]";
+/// assert!(matches!(
+///   cddl_from_str_with_limits(input, &limits, false),
+///   Err(Error::SchemaTooLarge(_))
+/// ));
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "std")]
+pub fn cddl_from_str_with_limits<'a>(
+  input: &'a str,
+  limits: &SchemaLimits,
+  print_stderr: bool,
+) -> result::Result<CDDL<'a>, Error> {
+  if input.len() > limits.max_input_len {
+    return Err(Error::SchemaTooLarge(format!(
+      "input of {} bytes exceeds the limit of {} bytes",
+      input.len(),
+      limits.max_input_len
+    )));
+  }
+
+  let mut p = Parser::new(input, Box::new(lexer::lexer_from_str(input).iter()))?;
+  p.set_max_depth(limits.max_depth);
+
+  let cddl = match p.parse_cddl() {
+    Ok(c) => c,
+    Err(Error::INCREMENTAL) => {
+      if print_stderr {
+        p.report_errors(true)?;
+      }
+
+      return Err(p.into_parsing_error());
+    }
+    Err(e) => return Err(e),
+  };
+
+  if cddl.rules.len() > limits.max_rules {
+    return Err(Error::SchemaTooLarge(format!(
+      "{} rules exceed the limit of {} rules",
+      cddl.rules.len(),
+      limits.max_rules
+    )));
+  }
+
+  let size = crate::generics::document_size(&cddl, false, usize::MAX);
+  if size > limits.max_nodes {
+    return Err(Error::SchemaTooLarge(format!(
+      "{} type and group entry nodes exceed the limit of {} nodes",
+      size, limits.max_nodes
+    )));
+  }
+
+  let limit = size.saturating_mul(limits.max_generic_expansion);
+  if crate::generics::document_size(&cddl, true, limit) > limit {
+    return Err(Error::SchemaTooLarge(format!(
+      "instantiating generic rules grows the document by more than a factor of {}",
+      limits.max_generic_expansion
+    )));
+  }
+
+  Ok(cddl)
+}
+
+/// Returns a `ast::CDDL` from a byte slice that may not be valid UTF-8. Invalid
+/// UTF-8 sequences are replaced with U+FFFD rather than failing outright, and
+/// are reported as `Error::PARSER` diagnostics alongside the parsed `ast::CDDL`.
+/// If parsing fails, the diagnostics are included in the returned
+/// `Error::PARSING`
+///
+/// # Arguments
+///
+/// * `input` - A byte slice with the CDDL text input
+/// * `buf` - Buffer the decoded input is written to and borrowed from by the
+///   returned `ast::CDDL`. Any existing contents are replaced
+/// * `print_stderr` - When true, print any diagnostics and errors to stderr
+///
+/// # Example
+///
+/// ```
+/// use cddl::parser::cddl_from_bytes_lossy;
+///
+/// let input = b"myrule = int ; caf\xe9\n";
+/// let mut buf = String::new();
+/// let (cddl, diagnostics) = cddl_from_bytes_lossy(input, &mut buf, false).unwrap();
+/// assert_eq!(cddl.rules.len(), 1);
+/// assert_eq!(diagnostics.len(), 1);
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "std")]
+pub fn cddl_from_bytes_lossy<'a>(
+  input: &[u8],
+  buf: &'a mut String,
+  print_stderr: bool,
+) -> result::Result<(CDDL<'a>, Vec<Error>), Error> {
+  buf.clear();
+
+  let mut diagnostics = Vec::new();
+  let mut rest = input;
+  loop {
+    match str::from_utf8(rest) {
+      Ok(valid) => {
+        buf.push_str(valid);
+        break;
+      }
+      Err(e) => {
+        let (valid, invalid) = rest.split_at(e.valid_up_to());
+        buf.push_str(str::from_utf8(valid).map_err(Error::UTF8)?);
+
+        #[cfg(feature = "ast-span")]
+        let position = {
+          let line_start = buf.rfind('\n').map_or(0, |idx| idx + 1);
+
+          Position {
+            line: buf.matches('\n').count() + 1,
+            column: buf[line_start..].chars().count() + 1,
+            range: (buf.len(), buf.len() + '\u{fffd}'.len_utf8()),
+            index: buf.len(),
           }
+        };
 
-          Err(Error::INCREMENTAL.to_string())
-        }
-        Err(e) => Err(e.to_string()),
-      },
-      Err(e) => Err(e),
+        diagnostics.push(Error::PARSER {
+          #[cfg(feature = "ast-span")]
+          position,
+          msg: MsgType::InvalidUTF8Sequence.into(),
+        });
+
+        buf.push('\u{fffd}');
+        rest = &invalid[e.error_len().unwrap_or(invalid.len())..];
+      }
     }
   }
 
+  let input: &'a str = buf;
+  let mut p = Parser::new(input, Box::new(lexer::lexer_from_str(input).iter()))?;
+
+  match p.parse_cddl() {
+    Ok(c) => {
+      if print_stderr && !diagnostics.is_empty() {
+        p.errors = diagnostics;
+        p.report_errors(true)?;
+        diagnostics = p.errors;
+      }
+
+      Ok((c, diagnostics))
+    }
+    Err(Error::INCREMENTAL) => {
+      p.errors.splice(0..0, diagnostics);
+      if print_stderr {
+        p.report_errors(true)?;
+      }
+
+      Err(p.into_parsing_error())
+    }
+    Err(e) => Err(e),
+  }
+}
+
+impl<'a> CDDL<'a> {
   /// Parses CDDL from a byte slice
   #[cfg(not(target_arch = "wasm32"))]
-  #[cfg(not(feature = "std"))]
-  pub fn from_slice(input: &[u8]) -> std::result::Result<CDDL, String> {
-    let str_input = std::str::from_utf8(input).map_err(|e| e.to_string())?;
+  pub fn from_slice(input: &[u8]) -> result::Result<CDDL, Error> {
+    let str_input = str::from_utf8(input).map_err(Error::UTF8)?;
 
-    match Parser::new(str_input, Box::new(lexer::Lexer::from_slice(input).iter()))
-      .map_err(|e| e.to_string())
-    {
-      Ok(mut p) => match p.parse_cddl() {
-        Ok(c) => Ok(c),
-        Err(Error::INCREMENTAL) => {
-          if let Some(e) = p.report_errors() {
-            return Err(e);
-          }
+    let mut p = Parser::new(str_input, Box::new(lexer::Lexer::from_slice(input).iter()))?;
 
-          Err(Error::INCREMENTAL.to_string())
-        }
-        Err(e) => Err(e.to_string()),
-      },
+    match p.parse_cddl() {
+      Ok(c) => Ok(c),
+      Err(Error::INCREMENTAL) => Err(p.into_parsing_error()),
       Err(e) => Err(e),
     }
   }
@@ -3423,20 +4310,12 @@ impl<'a> CDDL<'a> {
 /// ```
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(not(feature = "std"))]
-pub fn cddl_from_str<'a>(input: &'a str) -> std::result::Result<CDDL<'a>, String> {
-  match Parser::new(input, Box::new(lexer::lexer_from_str(input).iter())).map_err(|e| e.to_string())
-  {
-    Ok(mut p) => match p.parse_cddl() {
-      Ok(c) => Ok(c),
-      Err(Error::INCREMENTAL) => {
-        if let Some(e) = p.report_errors() {
-          return Err(e);
-        }
+pub fn cddl_from_str(input: &str) -> result::Result<CDDL, Error> {
+  let mut p = Parser::new(input, Box::new(lexer::lexer_from_str(input).iter()))?;
 
-        Err(Error::INCREMENTAL.to_string())
-      }
-      Err(e) => Err(e.to_string()),
-    },
+  match p.parse_cddl() {
+    Ok(c) => Ok(c),
+    Err(Error::INCREMENTAL) => Err(p.into_parsing_error()),
     Err(e) => Err(e),
   }
 }
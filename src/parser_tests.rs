@@ -73,6 +73,197 @@ mod tests {
     }
   }
 
+  #[test]
+  #[cfg(feature = "std")]
+  fn verify_parsing_error_chain() {
+    use std::error::Error as _;
+
+    let input = indoc!(
+      r#"
+        a = 1234
+        a = b
+      "#
+    );
+
+    let e = cddl_from_str(input, false).unwrap_err();
+    let position = e.position().unwrap();
+    assert_eq!((position.line, position.range), (2, (9, 14)));
+
+    match &e {
+      Error::PARSING { errors, .. } => assert_eq!(errors.len(), 1),
+      _ => panic!("expected Error::PARSING, got {:?}", e),
+    }
+    assert!(e
+      .to_string()
+      .contains("rule with the same identifier is already defined"));
+    assert!(e.source().unwrap().is::<Error>());
+
+    let input = "a = h'zz'";
+    let e = cddl_from_str(input, false).unwrap_err();
+    let lexer_error = e.source().unwrap().downcast_ref::<crate::lexer::Error>();
+    assert!(lexer_error.is_some());
+    assert!(e.position().is_some());
+  }
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn verify_cddl_from_bytes_lossy() {
+    let input = b"a = tstr ; \xff\xfe comment\nb = \"caf\xc3\" / int\n";
+    let mut buf = String::new();
+    let (cddl, diagnostics) = cddl_from_bytes_lossy(input, &mut buf, false).unwrap();
+    assert_eq!(cddl.rules.len(), 2);
+    assert_eq!(diagnostics.len(), 3);
+
+    let positions = diagnostics
+      .iter()
+      .map(|d| {
+        let position = d.position().unwrap();
+        (position.line, position.column)
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(positions, vec![(1, 12), (1, 13), (2, 9)]);
+
+    let input = b"a = \xff\n";
+    let e = cddl_from_bytes_lossy(input, &mut buf, false).unwrap_err();
+    assert!(e.to_string().contains("invalid UTF-8 sequence"));
+  }
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn verify_cddl_from_str_partial() {
+    let input = indoc!(
+      r#"
+        a = { b: c, d: e }
+        c = [ int, * ]
+        e = h'zz'
+        f = tstr ; trailing
+        g = [c, h]
+      "#
+    );
+
+    let partial = cddl_from_str_partial(input, false).unwrap();
+    let names = |rules: &[Rule]| rules.iter().map(|r| r.name()).collect::<Vec<_>>();
+    assert_eq!(names(&partial.cddl.rules), vec!["a", "f", "g"]);
+
+    let error_nodes = partial
+      .error_nodes
+      .iter()
+      .map(|n| (n.name, n.position, &input[n.span.0..n.span.1]))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      error_nodes,
+      vec![
+        (Some("c"), 1, "c = [ int, * ]"),
+        (Some("e"), 1, "e = h'zz'"),
+      ]
+    );
+
+    // Syntax and lexing errors are reported for each broken rule, and
+    // references to broken rules aren't reported as missing, unlike references
+    // to undefined rules
+    let lines = partial
+      .errors
+      .iter()
+      .map(|e| e.position().unwrap().line)
+      .collect::<Vec<_>>();
+    assert_eq!(lines, vec![2, 3, 5]);
+    assert!(partial.errors[2]
+      .to_string()
+      .contains("missing definition for rule h"));
+
+    // Documents without errors parse as with `cddl_from_str`
+    let input = "a = int\nb = [* a]";
+    let partial = cddl_from_str_partial(input, false).unwrap();
+    assert_eq!(partial.cddl, cddl_from_str(input, false).unwrap());
+    assert!(partial.error_nodes.is_empty() && partial.errors.is_empty());
+  }
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn verify_schema_limits() {
+    let input = indoc!(
+      r#"
+        message = list8<pair<tstr>>
+        pair<t> = [t, t]
+        list8<t> = list4<list4<t>>
+        list4<t> = list2<list2<t>>
+        list2<t> = [t, t]
+      "#
+    );
+
+    let limits = SchemaLimits::default();
+    assert!(cddl_from_str_with_limits(input, &limits, false).is_ok());
+
+    let exceeds = |limits: &SchemaLimits| {
+      matches!(
+        cddl_from_str_with_limits(input, limits, false),
+        Err(Error::SchemaTooLarge(_))
+      )
+    };
+    assert!(exceeds(&SchemaLimits {
+      max_input_len: input.len() - 1,
+      ..limits
+    }));
+    assert!(exceeds(&SchemaLimits {
+      max_rules: 4,
+      ..limits
+    }));
+    assert!(exceeds(&SchemaLimits {
+      max_generic_expansion: 2,
+      ..limits
+    }));
+    assert!(exceeds(&SchemaLimits {
+      max_depth: 2,
+      ..limits
+    }));
+    assert!(exceeds(&SchemaLimits {
+      max_nodes: 8,
+      ..limits
+    }));
+
+    // Nesting is bounded while parsing, before the stack can overflow
+    let nested = format!("a = {}int{}", "[".repeat(100_000), "]".repeat(100_000));
+    let shallow = SchemaLimits {
+      max_depth: 16,
+      ..limits
+    };
+    assert!(matches!(
+      cddl_from_str_with_limits(&nested, &shallow, false),
+      Err(Error::SchemaTooLarge(_))
+    ));
+
+    // Recursive generic rules aren't expanded
+    let input = "tree<t> = [t, * tree<t>]\nforest = tree<tree<int>>";
+    assert!(cddl_from_str_with_limits(input, &limits, false).is_ok());
+  }
+
+  #[test]
+  #[cfg(feature = "miette")]
+  fn verify_parsing_error_diagnostic() {
+    use miette::Diagnostic;
+
+    let input = indoc!(
+      r#"
+        a = 1234
+        a = b
+        b = c
+        b = d
+      "#
+    );
+
+    let e = cddl_from_str(input, false).unwrap_err();
+    assert_eq!(e.code().unwrap().to_string(), "cddl::parser");
+    assert!(e.source_code().is_some());
+
+    let labels = e.labels().unwrap().collect::<Vec<_>>();
+    assert_eq!(labels.len(), 2);
+    assert_eq!((labels[0].offset(), labels[0].len()), (9, 5));
+    assert_eq!(
+      labels[0].label(),
+      Some("rule with the same identifier is already defined")
+    );
+  }
+
   #[test]
   fn verify_genericparams() -> Result<()> {
     let input = r#"<t, v>"#;
@@ -465,7 +656,7 @@ mod tests {
         },
         operator: Some(Operator {
           operator: RangeCtlOp::CtlOp {
-            ctrl: ".lt",
+            ctrl: ".lt".into(),
             span: (7, 10, 1),
           },
           type2: Type2::Typename {
@@ -533,7 +724,7 @@ mod tests {
         },
         operator: Some(Operator {
           operator: RangeCtlOp::CtlOp {
-            ctrl: ".eq",
+            ctrl: ".eq".into(),
             span: (16, 19, 1),
           },
           type2: Type2::TextValue {
@@ -567,6 +758,7 @@ mod tests {
       r#"$$tcp-option"#,
       r#"~group1"#,
       r#"#6.997(tstr)"#,
+      r#"#6.<n>(tstr)"#,
       r#"9.9"#,
       r#"#"#,
       r#"[*3 reputon]"#,
@@ -638,10 +830,59 @@ mod tests {
         },
         generic_args: None,
         comments: None,
-        span: (0, 0, 0),
+        span: (0, 7, 1),
       },
       Type2::TaggedData {
         tag: Some(997),
+        tag_type: None,
+        t: Type {
+          type_choices: vec![TypeChoice {
+            type1: Type1 {
+              type2: Type2::Typename {
+                ident: Identifier {
+                  ident: "tstr".into(),
+                  socket: None,
+                  span: (7, 11, 1),
+                },
+                generic_args: None,
+                span: (7, 11, 1),
+              },
+              operator: None,
+              comments_after_type: None,
+              span: (7, 11, 1),
+            },
+            comments_before_type: None,
+            comments_after_type: None,
+          }],
+          span: (7, 11, 1),
+        },
+        comments_before_type: None,
+        comments_after_type: None,
+        span: (0, 11, 1),
+      },
+      Type2::TaggedData {
+        tag: None,
+        tag_type: Some(Box::new(Type {
+          type_choices: vec![TypeChoice {
+            type1: Type1 {
+              type2: Type2::Typename {
+                ident: Identifier {
+                  ident: "n".into(),
+                  socket: None,
+                  span: (4, 5, 1),
+                },
+                generic_args: None,
+                span: (4, 5, 1),
+              },
+              operator: None,
+              comments_after_type: None,
+              span: (4, 5, 1),
+            },
+            comments_before_type: None,
+            comments_after_type: None,
+          }],
+          span: (4, 5, 1),
+        })),
         t: Type {
           type_choices: vec![TypeChoice {
             type1: Type1 {
@@ -1726,7 +1967,7 @@ mod tests {
                   type1: Type1 {
                     type2: Type2::Typename {
                       ident: Identifier {
-                        ident: "int",
+                        ident: "int".into(),
                         span: (14, 17, 1),
                         socket: None,
                       },
@@ -1865,4 +2106,81 @@ mod tests {
 
     Ok(())
   }
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn verify_memberkey_lookahead() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let inputs = [
+      // Type1 member keys that can't be resolved by the next token alone
+      ("a = { b<int> => tstr }\nb<t> = [t]", vec!["b<int> => tstr"]),
+      ("a = { 1..10 => tstr }", vec!["1..10 => tstr"]),
+      ("a = { tstr .size 3 => int }", vec!["tstr .size 3 => int"]),
+      ("a = { ~b => int }\nb = [tstr]", vec!["~b => int"]),
+      ("a = { (0) .. 9 => int }", vec!["(0)..9 => int"]),
+      ("a = { (tstr) ^ => int }", vec!["(tstr) ^ => int"]),
+      ("a = [ #6.1(int) => tstr ]", vec!["#6.1(int) => tstr"]),
+      // Types of group entries
+      ("a = [ [int] / tstr ]", vec!["[ int ] / tstr"]),
+      ("a = [ * {b: int} / tstr ]", vec!["* { b: int } / tstr"]),
+      ("a = [ (int) / tstr ]", vec!["(int) / tstr"]),
+      ("a = [ (1) .. 5 ]", vec!["(1)..5"]),
+      ("a = [ ~b, tstr ]\nb = [int]", vec!["~b", "tstr"]),
+      // Inline groups
+      ("a = [ (int, tstr) ]", vec!["( int, tstr )"]),
+      ("a = [ (int tstr) ]", vec!["( int tstr )"]),
+      ("a = [ (+ int) ]", vec!["( + int )"]),
+      ("a = [ int tstr ]", vec!["int", "tstr"]),
+    ];
+
+    for (input, expected_entries) in inputs.iter() {
+      let cddl = cddl_from_str(input, false)?;
+      let group = match &cddl.rules[0] {
+        Rule::Type { rule, .. } => match &rule.value.type_choices[0].type1.type2 {
+          Type2::Map { group, .. } | Type2::Array { group, .. } => group.clone(),
+          t => panic!("expected map or array, got {:?}", t),
+        },
+        r => panic!("expected type rule, got {:?}", r),
+      };
+      let entries = group.group_choices[0]
+        .group_entries
+        .iter()
+        .map(|(ge, _)| ge.to_string())
+        .collect::<Vec<_>>();
+
+      assert_eq!(&entries, expected_entries, "{}", input);
+    }
+
+    // A type choice isn't a valid member key, and an operator must be followed
+    // by a type
+    for input in ["a = { \"a\" / \"b\" => int }", "a = [ (int) .. ]"].iter() {
+      assert!(cddl_from_str(input, false).is_err(), "{}", input);
+    }
+
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn verify_into_owned() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let input = String::from(indoc!(
+      r#"
+        ; a message
+        message<t> = { id: uint, ? body: t .size 64, * tstr => 'data' }
+        payload = message<h'0102'> / message<b64'AQI='> / [+ (int, ~tag)]
+        tag = #6.32("http://example.com")
+      "#
+    ));
+
+    let owned: CDDL<'static> = cddl_from_str(&input, false)?.into_owned();
+    let copy = input.clone();
+    drop(input);
+
+    let expected = cddl_from_str(&copy, false)?;
+    assert_eq!(owned, expected);
+
+    let handle = std::thread::spawn(move || owned.to_string());
+    assert_eq!(handle.join().unwrap(), expected.to_string());
+
+    Ok(())
+  }
 }
@@ -0,0 +1,346 @@
+/// Metadata of a type of the standard prelude (RFC 8610 Appendix D)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreludeType {
+  /// Type name, e.g. `tstr`
+  pub name: &'static str,
+  /// Definition of the type in the standard prelude, e.g. `#3`
+  pub definition: &'static str,
+  /// Short description of the type
+  pub description: &'static str,
+  /// Encoding of values of the type in CBOR
+  pub cbor: &'static str,
+  /// Representation of values of the type in JSON, or `None` if the type has
+  /// no counterpart in JSON
+  pub json: Option<&'static str>,
+}
+
+/// Metadata of the given prelude type, or `None` if the name is not part of
+/// the standard prelude
+///
+/// # Example
+///
+/// ```
+/// let tstr = cddl::prelude::describe("tstr").unwrap();
+/// assert_eq!(tstr.definition, "#3");
+/// assert_eq!(tstr.json, Some("string"));
+///
+/// assert_eq!(cddl::prelude::describe("bstr").unwrap().json, None);
+/// assert!(cddl::prelude::describe("person").is_none());
+/// ```
+pub fn describe(name: &str) -> Option<&'static PreludeType> {
+  PRELUDE.iter().find(|t| t.name == name)
+}
+
+/// Metadata of all prelude types, in order of definition in RFC 8610
+pub fn types() -> &'static [PreludeType] {
+  &PRELUDE
+}
+
+static PRELUDE: [PreludeType; 40] = [
+  PreludeType {
+    name: "any",
+    definition: "#",
+    description: "Any data item",
+    cbor: "Any data item",
+    json: Some("any value"),
+  },
+  PreludeType {
+    name: "uint",
+    definition: "#0",
+    description: "Unsigned integer",
+    cbor: "Major type 0",
+    json: Some("non-negative integer number"),
+  },
+  PreludeType {
+    name: "nint",
+    definition: "#1",
+    description: "Negative integer",
+    cbor: "Major type 1",
+    json: Some("negative integer number"),
+  },
+  PreludeType {
+    name: "int",
+    definition: "uint / nint",
+    description: "Integer",
+    cbor: "Major type 0 or 1",
+    json: Some("integer number"),
+  },
+  PreludeType {
+    name: "bstr",
+    definition: "#2",
+    description: "Byte string",
+    cbor: "Major type 2",
+    json: None,
+  },
+  PreludeType {
+    name: "bytes",
+    definition: "bstr",
+    description: "Byte string, alias of bstr",
+    cbor: "Major type 2",
+    json: None,
+  },
+  PreludeType {
+    name: "tstr",
+    definition: "#3",
+    description: "Text string encoded in UTF-8",
+    cbor: "Major type 3",
+    json: Some("string"),
+  },
+  PreludeType {
+    name: "text",
+    definition: "tstr",
+    description: "Text string encoded in UTF-8, alias of tstr",
+    cbor: "Major type 3",
+    json: Some("string"),
+  },
+  PreludeType {
+    name: "tdate",
+    definition: "#6.0(tstr)",
+    description: "Date and time in the RFC 3339 format",
+    cbor: "Tag 0 enclosing a text string",
+    json: Some("string in the RFC 3339 date-time format"),
+  },
+  PreludeType {
+    name: "time",
+    definition: "#6.1(number)",
+    description: "Epoch-based date and time in seconds",
+    cbor: "Tag 1 enclosing an integer or floating-point number",
+    json: Some("number of seconds since the epoch"),
+  },
+  PreludeType {
+    name: "number",
+    definition: "int / float",
+    description: "Integer or floating-point number",
+    cbor: "Major type 0 or 1, or major type 7 with additional information 25 to 27",
+    json: Some("number"),
+  },
+  PreludeType {
+    name: "biguint",
+    definition: "#6.2(bstr)",
+    description: "Unsigned bignum",
+    cbor: "Tag 2 enclosing a byte string",
+    json: None,
+  },
+  PreludeType {
+    name: "bignint",
+    definition: "#6.3(bstr)",
+    description: "Negative bignum",
+    cbor: "Tag 3 enclosing a byte string",
+    json: None,
+  },
+  PreludeType {
+    name: "bigint",
+    definition: "biguint / bignint",
+    description: "Bignum",
+    cbor: "Tag 2 or 3 enclosing a byte string",
+    json: None,
+  },
+  PreludeType {
+    name: "integer",
+    definition: "int / bigint",
+    description: "Integer or bignum",
+    cbor: "Major type 0 or 1, or tag 2 or 3 enclosing a byte string",
+    json: Some("integer number"),
+  },
+  PreludeType {
+    name: "unsigned",
+    definition: "uint / biguint",
+    description: "Unsigned integer or unsigned bignum",
+    cbor: "Major type 0, or tag 2 enclosing a byte string",
+    json: Some("non-negative integer number"),
+  },
+  PreludeType {
+    name: "decfrac",
+    definition: "#6.4([e10: int, m: integer])",
+    description: "Decimal fraction",
+    cbor: "Tag 4 enclosing an array of a base-10 exponent and a mantissa",
+    json: None,
+  },
+  PreludeType {
+    name: "bigfloat",
+    definition: "#6.5([e2: int, m: integer])",
+    description: "Binary floating-point number with arbitrary precision",
+    cbor: "Tag 5 enclosing an array of a base-2 exponent and a mantissa",
+    json: None,
+  },
+  PreludeType {
+    name: "eb64url",
+    definition: "#6.21(any)",
+    description: "Data item whose byte strings are expected to be converted to base64url",
+    cbor: "Tag 21 enclosing any data item",
+    json: Some("enclosed value, with byte strings as base64url-encoded strings"),
+  },
+  PreludeType {
+    name: "eb64legacy",
+    definition: "#6.22(any)",
+    description: "Data item whose byte strings are expected to be converted to base64",
+    cbor: "Tag 22 enclosing any data item",
+    json: Some("enclosed value, with byte strings as base64-encoded strings"),
+  },
+  PreludeType {
+    name: "eb16",
+    definition: "#6.23(any)",
+    description: "Data item whose byte strings are expected to be converted to base16",
+    cbor: "Tag 23 enclosing any data item",
+    json: Some("enclosed value, with byte strings as base16-encoded strings"),
+  },
+  PreludeType {
+    name: "encoded-cbor",
+    definition: "#6.24(bstr)",
+    description: "Embedded CBOR data item",
+    cbor: "Tag 24 enclosing a byte string",
+    json: None,
+  },
+  PreludeType {
+    name: "uri",
+    definition: "#6.32(tstr)",
+    description: "URI as defined in RFC 3986",
+    cbor: "Tag 32 enclosing a text string",
+    json: Some("string containing a URI"),
+  },
+  PreludeType {
+    name: "b64url",
+    definition: "#6.33(tstr)",
+    description: "Text in base64url encoding",
+    cbor: "Tag 33 enclosing a text string",
+    json: Some("string in base64url encoding"),
+  },
+  PreludeType {
+    name: "b64legacy",
+    definition: "#6.34(tstr)",
+    description: "Text in base64 encoding",
+    cbor: "Tag 34 enclosing a text string",
+    json: Some("string in base64 encoding"),
+  },
+  PreludeType {
+    name: "regexp",
+    definition: "#6.35(tstr)",
+    description: "Regular expression",
+    cbor: "Tag 35 enclosing a text string",
+    json: Some("string containing a regular expression"),
+  },
+  PreludeType {
+    name: "mime-message",
+    definition: "#6.36(tstr)",
+    description: "MIME message, including all headers",
+    cbor: "Tag 36 enclosing a text string",
+    json: Some("string containing a MIME message"),
+  },
+  PreludeType {
+    name: "cbor-any",
+    definition: "#6.55799(any)",
+    description: "Self-described CBOR data item",
+    cbor: "Tag 55799 enclosing any data item",
+    json: Some("enclosed value"),
+  },
+  PreludeType {
+    name: "float16",
+    definition: "#7.25",
+    description: "Half-precision floating-point number",
+    cbor: "Major type 7, additional information 25",
+    json: Some("number"),
+  },
+  PreludeType {
+    name: "float32",
+    definition: "#7.26",
+    description: "Single-precision floating-point number",
+    cbor: "Major type 7, additional information 26",
+    json: Some("number"),
+  },
+  PreludeType {
+    name: "float64",
+    definition: "#7.27",
+    description: "Double-precision floating-point number",
+    cbor: "Major type 7, additional information 27",
+    json: Some("number"),
+  },
+  PreludeType {
+    name: "float16-32",
+    definition: "float16 / float32",
+    description: "Half- or single-precision floating-point number",
+    cbor: "Major type 7, additional information 25 or 26",
+    json: Some("number"),
+  },
+  PreludeType {
+    name: "float32-64",
+    definition: "float32 / float64",
+    description: "Single- or double-precision floating-point number",
+    cbor: "Major type 7, additional information 26 or 27",
+    json: Some("number"),
+  },
+  PreludeType {
+    name: "float",
+    definition: "float16-32 / float64",
+    description: "Floating-point number",
+    cbor: "Major type 7, additional information 25 to 27",
+    json: Some("number"),
+  },
+  PreludeType {
+    name: "false",
+    definition: "#7.20",
+    description: "Boolean false",
+    cbor: "Simple value 20",
+    json: Some("false"),
+  },
+  PreludeType {
+    name: "true",
+    definition: "#7.21",
+    description: "Boolean true",
+    cbor: "Simple value 21",
+    json: Some("true"),
+  },
+  PreludeType {
+    name: "bool",
+    definition: "false / true",
+    description: "Boolean",
+    cbor: "Simple value 20 or 21",
+    json: Some("true or false"),
+  },
+  PreludeType {
+    name: "nil",
+    definition: "#7.22",
+    description: "Null value",
+    cbor: "Simple value 22",
+    json: Some("null"),
+  },
+  PreludeType {
+    name: "null",
+    definition: "nil",
+    description: "Null value, alias of nil",
+    cbor: "Simple value 22",
+    json: Some("null"),
+  },
+  PreludeType {
+    name: "undefined",
+    definition: "#7.23",
+    description: "Undefined value",
+    cbor: "Simple value 23",
+    json: None,
+  },
+];
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::lexer::Lexer;
+  use crate::token::Token;
+
+  #[test]
+  fn verify_prelude_types() {
+    for t in types() {
+      let mut lexer = Lexer::new(t.name);
+      let (_, token) = lexer.next_token().unwrap();
+      assert_eq!(token.in_standard_prelude(), Some(t.name));
+      assert_eq!(describe(t.name), Some(t));
+    }
+    assert_eq!(types().len(), 40);
+
+    assert_eq!(describe("float").unwrap().json, Some("number"));
+    assert_eq!(describe("undefined").unwrap().cbor, "Simple value 23");
+    assert!(describe("TSTR").is_none());
+    assert_eq!(
+      Token::TSTR.in_standard_prelude().and_then(describe),
+      describe("tstr")
+    );
+  }
+}
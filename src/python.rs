@@ -0,0 +1,436 @@
+#![cfg(feature = "python")]
+#![cfg(not(feature = "lsp"))]
+#![cfg(not(target_arch = "wasm32"))]
+
+use crate::{
+  lexer::lexer_from_str,
+  parser::{self, Parser},
+  validator::{
+    cbor::{self, CBORValidator},
+    compiled::CompiledCddl,
+    json::{self, JSONValidator},
+    Validator,
+  },
+};
+
+use pyo3::{
+  create_exception,
+  exceptions::PyException,
+  prelude::*,
+  types::{PyDict, PyList},
+};
+
+create_exception!(
+  cddl,
+  CDDLError,
+  PyException,
+  "Base class of the errors raised by this module"
+);
+create_exception!(
+  cddl,
+  ParseError,
+  CDDLError,
+  "CDDL document is invalid. The `errors` attribute holds a list of dicts \
+   with `line`, `column` and `message` keys"
+);
+create_exception!(
+  cddl,
+  ValidationError,
+  CDDLError,
+  "Data is invalid. The `errors` attribute holds a list of dicts with \
+   `reason`, `location`, `rule`, `span` and `message` keys"
+);
+
+// Validation error reported to Python callers
+struct ValidationErrorObject {
+  // Reason the data is invalid
+  reason: String,
+  // Location in the data (in JSONPointer notation for JSON), empty at the root
+  location: String,
+  // Name of the innermost rule being validated, if any
+  rule: Option<String>,
+  // Span of the CDDL type or group entry being validated
+  span: Option<(usize, usize, usize)>,
+  // Human readable error message
+  message: String,
+}
+
+impl ValidationErrorObject {
+  // Error that isn't associated with a location in the data, e.g. a data
+  // parsing error
+  fn from_reason(reason: String) -> Self {
+    ValidationErrorObject {
+      message: reason.clone(),
+      reason,
+      location: String::new(),
+      rule: None,
+      span: None,
+    }
+  }
+
+  fn into_dict(self, py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("reason", self.reason)?;
+    dict.set_item("location", self.location)?;
+    dict.set_item("rule", self.rule)?;
+    dict.set_item("span", self.span)?;
+    dict.set_item("message", self.message)?;
+
+    Ok(dict)
+  }
+}
+
+impl From<&json::ValidationError> for ValidationErrorObject {
+  fn from(e: &json::ValidationError) -> Self {
+    ValidationErrorObject {
+      reason: e.reason.clone(),
+      location: e.json_location.clone(),
+      rule: Some(e.cddl_location.clone()).filter(|r| !r.is_empty()),
+      #[cfg(feature = "ast-span")]
+      span: e.cddl_span,
+      #[cfg(not(feature = "ast-span"))]
+      span: None,
+      message: e.to_string(),
+    }
+  }
+}
+
+impl From<&cbor::ValidationError> for ValidationErrorObject {
+  fn from(e: &cbor::ValidationError) -> Self {
+    ValidationErrorObject {
+      reason: e.reason.clone(),
+      location: e.cbor_location.clone(),
+      rule: Some(e.cddl_location.clone()).filter(|r| !r.is_empty()),
+      #[cfg(feature = "ast-span")]
+      span: e.cddl_span,
+      #[cfg(not(feature = "ast-span"))]
+      span: None,
+      message: e.to_string(),
+    }
+  }
+}
+
+// Attach the given error dicts to an exception as its `errors` attribute
+fn with_errors<'py>(
+  py: Python<'py>,
+  err: PyErr,
+  dicts: PyResult<Vec<Bound<'py, PyDict>>>,
+) -> PyErr {
+  match dicts
+    .and_then(|dicts| PyList::new(py, dicts))
+    .and_then(|dicts| err.value(py).setattr("errors", dicts))
+  {
+    Ok(()) => err,
+    Err(e) => e,
+  }
+}
+
+// ValidationError exception holding the given error objects
+fn validation_error(py: Python<'_>, errors: Vec<ValidationErrorObject>) -> PyErr {
+  let message = errors
+    .iter()
+    .map(|e| e.message.as_str())
+    .collect::<Vec<_>>()
+    .join("\n");
+  let err = ValidationError::new_err(message);
+
+  let dicts = errors
+    .into_iter()
+    .map(|e| e.into_dict(py))
+    .collect::<PyResult<Vec<_>>>();
+  with_errors(py, err, dicts)
+}
+
+// ParseError exception holding the errors collected by the parser
+fn parse_error(py: Python<'_>, errors: &[parser::Error]) -> PyErr {
+  let message = errors
+    .iter()
+    .map(|e| e.to_string())
+    .collect::<Vec<_>>()
+    .join("\n");
+  let err = ParseError::new_err(message);
+
+  let dicts = errors
+    .iter()
+    .map(|e| {
+      let dict = PyDict::new(py);
+      if let parser::Error::PARSER { position, msg } = e {
+        dict.set_item("line", position.line)?;
+        dict.set_item("column", position.column)?;
+        dict.set_item("message", msg.to_string())?;
+      } else {
+        dict.set_item("line", py.None())?;
+        dict.set_item("column", py.None())?;
+        dict.set_item("message", e.to_string())?;
+      }
+
+      Ok(dict)
+    })
+    .collect::<PyResult<Vec<_>>>();
+  with_errors(py, err, dicts)
+}
+
+/// CDDL document parsed and compiled once, which can be used to validate many
+/// JSON documents and CBOR data items without reparsing the document
+#[pyclass(frozen, module = "cddl")]
+pub struct Schema {
+  // Borrows from `input`, so it is declared (and therefore dropped) first
+  compiled: CompiledCddl<'static>,
+  // Source text of the document, which is never mutated and whose heap
+  // allocation doesn't move while the schema is alive
+  #[allow(dead_code)]
+  input: Box<str>,
+}
+
+impl Schema {
+  fn parse(py: Python<'_>, cddl: &str) -> PyResult<Self> {
+    let input: Box<str> = cddl.into();
+    // SAFETY: the compiled document only borrows from the heap allocation of
+    // `input`, which is owned by the schema, never mutated and dropped after
+    // the compiled document
+    let source: &'static str = unsafe { &*(input.as_ref() as *const str) };
+
+    let mut p = Parser::new(source, Box::new(lexer_from_str(source).iter()))
+      .map_err(|e| parse_error(py, &[e]))?;
+    let c = match p.parse_cddl() {
+      Ok(c) if p.errors.is_empty() => c,
+      Ok(_) | Err(parser::Error::INCREMENTAL) if !p.errors.is_empty() => {
+        return Err(parse_error(py, &p.errors))
+      }
+      Ok(_) => return Err(parse_error(py, &[parser::Error::INCREMENTAL])),
+      Err(e) => return Err(parse_error(py, &[e])),
+    };
+
+    let compiled =
+      CompiledCddl::from_parsed(source, c).map_err(|e| ParseError::new_err(e.to_string()))?;
+
+    Ok(Schema { compiled, input })
+  }
+}
+
+#[pymethods]
+impl Schema {
+  /// Parse and compile a CDDL document string. Raises `ParseError` if the
+  /// document is invalid
+  #[new]
+  fn new(py: Python<'_>, cddl: &str) -> PyResult<Self> {
+    Schema::parse(py, cddl)
+  }
+
+  /// Names of the rules defined by the document, which may be selected as the
+  /// root rule when validating
+  fn rule_names(&self) -> Vec<String> {
+    self
+      .compiled
+      .cddl()
+      .rules
+      .iter()
+      .map(|r| r.name())
+      .collect()
+  }
+
+  /// Validate a JSON string against the named rule, or the first type rule in
+  /// the document if no rule is given. Raises `ValidationError` if the JSON is
+  /// invalid. Enabled features only apply if the crate is built with the
+  /// `additional-controls` feature
+  #[pyo3(signature = (json, root_rule=None, enabled_features=None))]
+  fn validate_json(
+    &self,
+    py: Python<'_>,
+    json: &str,
+    root_rule: Option<&str>,
+    enabled_features: Option<Vec<String>>,
+  ) -> PyResult<()> {
+    let json = serde_json::from_str::<serde_json::Value>(json).map_err(|e| {
+      validation_error(
+        py,
+        vec![ValidationErrorObject::from_reason(format!(
+          "error parsing JSON: {}",
+          e
+        ))],
+      )
+    })?;
+
+    #[cfg(feature = "additional-controls")]
+    let enabled_features = enabled_features
+      .as_ref()
+      .map(|f| f.iter().map(String::as_str).collect::<Vec<_>>());
+    #[cfg(feature = "additional-controls")]
+    let mut jv = JSONValidator::new(self.compiled.cddl(), json, enabled_features.as_deref());
+    #[cfg(not(feature = "additional-controls"))]
+    let mut jv = {
+      let _ = enabled_features;
+      JSONValidator::new(self.compiled.cddl(), json)
+    };
+
+    jv.set_compiled(&self.compiled);
+    if let Some(rule) = root_rule {
+      jv.set_root_rule(rule, None);
+    }
+
+    match jv.validate() {
+      Ok(()) => Ok(()),
+      Err(json::Error::Validation(errors)) => Err(validation_error(
+        py,
+        errors.iter().map(ValidationErrorObject::from).collect(),
+      )),
+      Err(e) => Err(validation_error(
+        py,
+        vec![ValidationErrorObject::from_reason(e.to_string())],
+      )),
+    }
+  }
+
+  /// Validate an encoded CBOR data item against the named rule, or the first
+  /// type rule in the document if no rule is given. Raises `ValidationError`
+  /// as with `validate_json`
+  #[pyo3(signature = (cbor, root_rule=None, enabled_features=None))]
+  fn validate_cbor(
+    &self,
+    py: Python<'_>,
+    cbor: &[u8],
+    root_rule: Option<&str>,
+    enabled_features: Option<Vec<String>>,
+  ) -> PyResult<()> {
+    let cbor: ciborium::value::Value = ciborium::de::from_reader(cbor).map_err(|e| {
+      validation_error(
+        py,
+        vec![ValidationErrorObject::from_reason(format!(
+          "error parsing CBOR: {}",
+          e
+        ))],
+      )
+    })?;
+
+    #[cfg(feature = "additional-controls")]
+    let enabled_features = enabled_features
+      .as_ref()
+      .map(|f| f.iter().map(String::as_str).collect::<Vec<_>>());
+    #[cfg(feature = "additional-controls")]
+    let mut cv = CBORValidator::new(self.compiled.cddl(), cbor, enabled_features.as_deref());
+    #[cfg(not(feature = "additional-controls"))]
+    let mut cv = {
+      let _ = enabled_features;
+      CBORValidator::new(self.compiled.cddl(), cbor)
+    };
+
+    cv.set_compiled(&self.compiled);
+    if let Some(rule) = root_rule {
+      cv.set_root_rule(rule, None);
+    }
+
+    let result: cbor::Result<std::io::Error> = cv.validate();
+    match result {
+      Ok(()) => Ok(()),
+      Err(cbor::Error::Validation(errors)) => Err(validation_error(
+        py,
+        errors.iter().map(ValidationErrorObject::from).collect(),
+      )),
+      Err(e) => Err(validation_error(
+        py,
+        vec![ValidationErrorObject::from_reason(e.to_string())],
+      )),
+    }
+  }
+}
+
+/// Parse and compile a CDDL document string. Raises `ParseError` if the
+/// document is invalid
+#[pyfunction]
+fn parse(py: Python<'_>, cddl: &str) -> PyResult<Schema> {
+  Schema::parse(py, cddl)
+}
+
+/// Validate a JSON string against a CDDL document string. Raises `ParseError`
+/// if the document is invalid and `ValidationError` if the JSON is invalid
+#[pyfunction]
+#[pyo3(signature = (cddl, json, root_rule=None, enabled_features=None))]
+fn validate_json(
+  py: Python<'_>,
+  cddl: &str,
+  json: &str,
+  root_rule: Option<&str>,
+  enabled_features: Option<Vec<String>>,
+) -> PyResult<()> {
+  Schema::parse(py, cddl)?.validate_json(py, json, root_rule, enabled_features)
+}
+
+/// Validate an encoded CBOR data item against a CDDL document string. Raises
+/// `ParseError` if the document is invalid and `ValidationError` if the CBOR is
+/// invalid
+#[pyfunction]
+#[pyo3(signature = (cddl, cbor, root_rule=None, enabled_features=None))]
+fn validate_cbor(
+  py: Python<'_>,
+  cddl: &str,
+  cbor: &[u8],
+  root_rule: Option<&str>,
+  enabled_features: Option<Vec<String>>,
+) -> PyResult<()> {
+  Schema::parse(py, cddl)?.validate_cbor(py, cbor, root_rule, enabled_features)
+}
+
+/// Python module
+#[pymodule]
+fn cddl(m: &Bound<'_, PyModule>) -> PyResult<()> {
+  let py = m.py();
+
+  m.add("CDDLError", py.get_type::<CDDLError>())?;
+  m.add("ParseError", py.get_type::<ParseError>())?;
+  m.add("ValidationError", py.get_type::<ValidationError>())?;
+  m.add_class::<Schema>()?;
+  m.add_function(wrap_pyfunction!(parse, m)?)?;
+  m.add_function(wrap_pyfunction!(validate_json, m)?)?;
+  m.add_function(wrap_pyfunction!(validate_cbor, m)?)?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use pyo3::ffi::c_str;
+
+  #[test]
+  fn validate_from_python() {
+    pyo3::prepare_freethreaded_python();
+
+    Python::with_gil(|py| {
+      let module = PyModule::new(py, "cddl").unwrap();
+      cddl(&module).unwrap();
+
+      let locals = PyDict::new(py);
+      locals.set_item("cddl", module).unwrap();
+      py.run(
+        c_str!(
+          r#"
+schema = cddl.parse("person = { name: tstr, age: uint }\naddress = { street: tstr }")
+assert schema.rule_names() == ["person", "address"]
+
+schema.validate_json('{ "name": "Joe", "age": 42 }')
+schema.validate_json('{ "street": "Main" }', root_rule="address")
+schema.validate_cbor(bytes.fromhex("a1667374726565746444656d6f"), root_rule="address")
+
+try:
+    schema.validate_json('{ "name": "Joe", "age": "unknown" }')
+    assert False
+except cddl.ValidationError as e:
+    assert e.errors[0]["location"] == "/age"
+    assert e.errors[0]["rule"] == "person"
+
+try:
+    cddl.validate_json("person = {", "{}")
+    assert False
+except cddl.ParseError as e:
+    assert isinstance(e, cddl.CDDLError)
+    assert len(e.errors) > 0
+"#
+        ),
+        None,
+        Some(&locals),
+      )
+      .unwrap();
+    });
+  }
+}
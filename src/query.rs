@@ -0,0 +1,599 @@
+#![cfg(feature = "std")]
+#![cfg(not(target_arch = "wasm32"))]
+
+use crate::{
+  ast::*,
+  graph::DependencyGraph,
+  lint::{self, Diagnostic, RuleLints},
+  parser,
+  visitor::{self, Visitor},
+};
+
+#[cfg(all(any(feature = "json", feature = "cbor"), not(feature = "lsp")))]
+use crate::validator::compiled::{self, CompiledCddl};
+
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  convert::Infallible,
+};
+
+/// CDDL documents being edited, e.g. by a language server, whose analyses are
+/// memoized for each rule, so that repeated queries only analyze rules whose
+/// definitions changed since they were last queried
+///
+/// Analyses of a rule remain valid until the text of its definitions changes,
+/// or until a rule it mentions is defined or removed. Lints, which resolve the
+/// types of other rules, are also invalidated when a rule the rule depends on,
+/// directly or indirectly, changes. Rules that are only moved by edits
+/// elsewhere in the document keep their analyses, with spans shifted to the new
+/// location
+///
+/// The compiled document that validators look rules and precompiled controllers
+/// up in is memoized as well. It's compiled again once the document changes,
+/// reusing the regular expressions of the controllers that didn't
+///
+/// # Example
+///
+/// ```
+/// use cddl::query::Database;
+///
+/// let mut db = Database::default();
+/// db.set_document("spec.cddl", "message = { id: uint }\nid = uint")
+///   .unwrap();
+/// assert_eq!(db.lint("spec.cddl").unwrap().len(), 1);
+///
+/// // Only the edited rule is analyzed again
+/// let changed = db
+///   .set_document("spec.cddl", "message = { id: id }\nid = uint")
+///   .unwrap();
+/// assert_eq!(changed, vec!["message"]);
+/// assert!(db.lint("spec.cddl").unwrap().is_empty());
+///
+/// // Data is validated against the memoized compiled document
+/// #[cfg(all(feature = "json", not(feature = "lsp")))]
+/// {
+///   let compiled = db.compiled("spec.cddl").unwrap().unwrap();
+///   #[cfg(feature = "additional-controls")]
+///   assert!(compiled.validate_json(r#"{ "id": 1 }"#, None).is_ok());
+///   #[cfg(not(feature = "additional-controls"))]
+///   assert!(compiled.validate_json(r#"{ "id": 1 }"#).is_ok());
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct Database {
+  documents: BTreeMap<String, Document>,
+}
+
+#[derive(Debug)]
+struct Document {
+  cddl: CDDL<'static>,
+  // Names of the rules, in order of definition. Rules extended with additional
+  // choices only appear once
+  names: Vec<String>,
+  // Indices of the definitions of each rule
+  definitions: BTreeMap<String, Vec<usize>>,
+  rules: BTreeMap<String, RuleAnalysis>,
+  // Dependency graph assembled from the analyses of the rules
+  graph: Option<DependencyGraph>,
+  // Compiled document, along with the last successful compilation of a
+  // previous version of the document whose regular expressions are reused
+  #[cfg(all(any(feature = "json", feature = "cbor"), not(feature = "lsp")))]
+  compiled: Option<Result<CompiledCddl<'static>, compiled::Error>>,
+  #[cfg(all(any(feature = "json", feature = "cbor"), not(feature = "lsp")))]
+  previous: Option<CompiledCddl<'static>>,
+}
+
+#[derive(Debug)]
+struct RuleAnalysis {
+  // Text of the definitions of the rule the analyses are valid for
+  fingerprint: Vec<String>,
+  // Spans of the definitions of the rule the spans of the analyses refer to
+  #[cfg(feature = "ast-span")]
+  spans: Vec<Span>,
+  // Names of the rules and generic parameters mentioned by the definitions,
+  // known once the rule is first analyzed. Mentions only depend on the text of
+  // the definitions
+  mentions: Option<BTreeSet<String>>,
+  graph: Option<DependencyGraph>,
+  lints: Option<RuleLints>,
+}
+
+impl RuleAnalysis {
+  fn mentions_any<'n>(&self, names: impl IntoIterator<Item = &'n String>) -> bool {
+    match &self.mentions {
+      Some(mentions) => names.into_iter().any(|n| mentions.contains(n)),
+      None => false,
+    }
+  }
+
+  fn mentions(&mut self, definitions: &[&Rule]) -> &BTreeSet<String> {
+    self.mentions.get_or_insert_with(|| {
+      let mut mentions = Mentions(BTreeSet::new());
+      for rule in definitions.iter() {
+        let generic_params = match rule {
+          Rule::Type { rule, .. } => rule.generic_params.as_ref(),
+          Rule::Group { rule, .. } => rule.generic_params.as_ref(),
+        };
+        for gp in generic_params.iter().flat_map(|gp| gp.params.iter()) {
+          mentions.0.insert(gp.param.ident.to_string());
+        }
+
+        // Collecting names is infallible
+        let _ = mentions.visit_rule(rule);
+      }
+
+      mentions.0
+    })
+  }
+
+  // Shifts the spans of the analyses from the previous location of the
+  // definitions to their new location
+  #[cfg(feature = "ast-span")]
+  fn relocate(&mut self, spans: Vec<Span>) {
+    if let Some(lints) = &mut self.lints {
+      for d in lints.diagnostics.iter_mut() {
+        let moved = self
+          .spans
+          .iter()
+          .zip(spans.iter())
+          .find(|(old, _)| old.0 <= d.span.0 && d.span.0 <= old.1);
+        if let Some((old, new)) = moved {
+          d.span = (
+            d.span.0 - old.0 + new.0,
+            d.span.1 - old.0 + new.0,
+            d.span.2 - old.2 + new.2,
+          );
+        }
+      }
+    }
+
+    self.spans = spans;
+  }
+}
+
+// Names of the identifiers mentioned by a rule
+struct Mentions(BTreeSet<String>);
+
+impl<'a> Visitor<'a, Infallible> for Mentions {
+  fn visit_identifier(&mut self, ident: &Identifier<'a>) -> visitor::Result<Infallible> {
+    self.0.insert(ident.to_string());
+
+    Ok(())
+  }
+}
+
+impl Database {
+  /// Parse the given source of a document, identified by e.g. its URI, adding
+  /// or replacing it. Returns the names of the rules that were added, edited
+  /// or removed, in order of definition, followed by the removed rules. If the
+  /// source can't be parsed, the previous version of the document is kept
+  pub fn set_document(&mut self, id: &str, source: &str) -> Result<Vec<String>, parser::Error> {
+    let cddl = crate::cddl_from_str(source, false)?.into_owned();
+
+    let mut names = Vec::new();
+    let mut definitions = BTreeMap::<String, Vec<usize>>::new();
+    for (idx, rule) in cddl.rules.iter().enumerate() {
+      let name = rule.name();
+      if !definitions.contains_key(&name) {
+        names.push(name.clone());
+      }
+      definitions.entry(name).or_default().push(idx);
+    }
+
+    #[cfg_attr(
+      not(all(any(feature = "json", feature = "cbor"), not(feature = "lsp"))),
+      allow(unused_mut)
+    )]
+    let mut previous = self.documents.remove(id);
+
+    // The compilation of the previous version of the document is kept as is if
+    // the document is unchanged, and otherwise reused when it's compiled again
+    #[cfg(all(any(feature = "json", feature = "cbor"), not(feature = "lsp")))]
+    let (compiled, previous_compiled) = match previous.as_mut() {
+      Some(d) => match d.compiled.take() {
+        Some(Ok(compiled)) if *compiled.cddl() == cddl => (Some(Ok(compiled)), d.previous.take()),
+        Some(Ok(compiled)) => (None, Some(compiled)),
+        _ => (None, d.previous.take()),
+      },
+      None => (None, None),
+    };
+
+    let mut rules = previous.map(|d| d.rules).unwrap_or_default();
+
+    let mut changed = Vec::new();
+    let mut added = Vec::new();
+    for name in names.iter() {
+      let defs = definitions[name]
+        .iter()
+        .map(|idx| &cddl.rules[*idx])
+        .collect::<Vec<_>>();
+      let fingerprint = defs.iter().map(|r| fingerprint(r, source)).collect();
+      #[cfg(feature = "ast-span")]
+      let spans = defs.iter().map(|r| r.span()).collect::<Vec<_>>();
+
+      match rules.get_mut(name) {
+        Some(analysis) if analysis.fingerprint == fingerprint => {
+          #[cfg(feature = "ast-span")]
+          analysis.relocate(spans);
+        }
+        analysis => {
+          if analysis.is_none() {
+            added.push(name.clone());
+          }
+
+          changed.push(name.clone());
+          rules.insert(
+            name.clone(),
+            RuleAnalysis {
+              fingerprint,
+              #[cfg(feature = "ast-span")]
+              spans,
+              mentions: None,
+              graph: None,
+              lints: None,
+            },
+          );
+        }
+      }
+    }
+
+    let removed = rules
+      .keys()
+      .filter(|name| !definitions.contains_key(*name))
+      .cloned()
+      .collect::<Vec<_>>();
+    for name in removed.iter() {
+      rules.remove(name);
+    }
+
+    // Dependency graphs depend on whether the names mentioned by a rule are
+    // defined, and lints on the definitions of the rules mentioned by a rule,
+    // directly or indirectly
+    for analysis in rules.values_mut() {
+      if analysis.mentions_any(&added) || analysis.mentions_any(&removed) {
+        analysis.graph = None;
+      }
+    }
+
+    let mut stale = changed
+      .iter()
+      .chain(removed.iter())
+      .cloned()
+      .collect::<BTreeSet<_>>();
+    loop {
+      let dependents = rules
+        .iter()
+        .filter(|(name, analysis)| !stale.contains(*name) && analysis.mentions_any(&stale))
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+      if dependents.is_empty() {
+        break;
+      }
+
+      for name in dependents {
+        if let Some(analysis) = rules.get_mut(&name) {
+          analysis.lints = None;
+        }
+        stale.insert(name);
+      }
+    }
+
+    changed.extend(removed);
+
+    self.documents.insert(
+      id.to_string(),
+      Document {
+        cddl,
+        names,
+        definitions,
+        rules,
+        graph: None,
+        #[cfg(all(any(feature = "json", feature = "cbor"), not(feature = "lsp")))]
+        compiled,
+        #[cfg(all(any(feature = "json", feature = "cbor"), not(feature = "lsp")))]
+        previous: previous_compiled,
+      },
+    );
+
+    Ok(changed)
+  }
+
+  /// Remove a document, returning whether or not it existed
+  pub fn remove_document(&mut self, id: &str) -> bool {
+    self.documents.remove(id).is_some()
+  }
+
+  /// Identifiers of the documents
+  pub fn documents(&self) -> impl Iterator<Item = &str> {
+    self.documents.keys().map(|id| id.as_str())
+  }
+
+  /// Parsed document, e.g. to validate data against it without parsing it
+  /// again
+  pub fn cddl(&self, id: &str) -> Option<&CDDL<'static>> {
+    self.documents.get(id).map(|d| &d.cddl)
+  }
+
+  /// Definitions of the given rule of a document, in order of definition
+  pub fn definitions(&self, id: &str, name: &str) -> Vec<&Rule<'static>> {
+    self
+      .documents
+      .get(id)
+      .and_then(|d| {
+        d.definitions
+          .get(name)
+          .map(|defs| defs.iter().map(|idx| &d.cddl.rules[*idx]).collect())
+      })
+      .unwrap_or_default()
+  }
+
+  /// Dependency graph of a document. See [`CDDL::dependency_graph`]
+  pub fn dependency_graph(&mut self, id: &str) -> Option<&DependencyGraph> {
+    let document = self.documents.get_mut(id)?;
+    document.analyze();
+
+    if document.graph.is_none() {
+      let mut graph = DependencyGraph::default();
+      for name in document.names.iter() {
+        if let Some(g) = &document.rules[name].graph {
+          graph.extend(g);
+        }
+      }
+
+      document.graph = Some(graph);
+    }
+
+    document.graph.as_ref()
+  }
+
+  /// Compiled document to validate data against, e.g. with
+  /// [`CompiledCddl::validate_cbor`] or [`set_compiled`] on a validator.
+  /// Generic rules are left to be resolved during validation, as with
+  /// [`CompiledCddl::from_cddl`]
+  ///
+  /// [`set_compiled`]: crate::validator::cbor::CBORValidator::set_compiled
+  #[cfg(all(any(feature = "json", feature = "cbor"), not(feature = "lsp")))]
+  pub fn compiled(&mut self, id: &str) -> Option<Result<&CompiledCddl<'static>, &compiled::Error>> {
+    let document = self.documents.get_mut(id)?;
+
+    if document.compiled.is_none() {
+      let cddl = document.cddl.clone();
+      let compiled = match &document.previous {
+        Some(previous) => CompiledCddl::recompile(cddl, previous),
+        None => CompiledCddl::from_cddl(cddl),
+      };
+
+      if compiled.is_ok() {
+        document.previous = None;
+      }
+      document.compiled = Some(compiled);
+    }
+
+    document.compiled.as_ref().map(Result::as_ref)
+  }
+
+  /// Lint diagnostics of a document. See [`CDDL::lint`]
+  pub fn lint(&mut self, id: &str) -> Option<Vec<Diagnostic>> {
+    self.dependency_graph(id)?;
+
+    let document = &self.documents[id];
+    let graph = document.graph.as_ref()?;
+
+    let mut diagnostics = document.cddl.lint_unused_rules(graph);
+
+    let mut enumerated = BTreeSet::new();
+    for name in document.names.iter() {
+      if let Some(lints) = &document.rules[name].lints {
+        diagnostics.extend(lints.diagnostics.iter().cloned());
+        enumerated.extend(lints.enumerated.iter().cloned());
+      }
+    }
+
+    diagnostics.extend(document.cddl.lint_enumerated_groups(&enumerated));
+
+    lint::sort_diagnostics(&mut diagnostics);
+    Some(diagnostics)
+  }
+}
+
+impl Document {
+  // Analyzes the rules whose analyses aren't memoized
+  fn analyze(&mut self) {
+    let cddl = &self.cddl;
+    let defined = self.names.iter().cloned().collect::<BTreeSet<_>>();
+
+    for (name, analysis) in self.rules.iter_mut() {
+      if analysis.graph.is_some() && analysis.lints.is_some() {
+        continue;
+      }
+
+      let defs = self.definitions[name]
+        .iter()
+        .map(|idx| &cddl.rules[*idx])
+        .collect::<Vec<_>>();
+
+      analysis.mentions(&defs);
+
+      if analysis.graph.is_none() {
+        let mut graph = DependencyGraph::default();
+        for rule in defs.iter() {
+          graph.add_rule(rule, &defined);
+        }
+
+        analysis.graph = Some(graph);
+        self.graph = None;
+      }
+
+      if analysis.lints.is_none() {
+        let mut lints = RuleLints::default();
+        for rule in defs.iter() {
+          let rule_lints = cddl.lint_rule(rule);
+          lints.diagnostics.extend(rule_lints.diagnostics);
+          lints.enumerated.extend(rule_lints.enumerated);
+        }
+
+        analysis.lints = Some(lints);
+      }
+    }
+  }
+}
+
+// Text of a rule definition. Rules are compared by their source text where
+// available, since the spans of the analyses of rules that are equal but
+// formatted differently don't match
+fn fingerprint(rule: &Rule, source: &str) -> String {
+  #[cfg(feature = "ast-span")]
+  {
+    let span = rule.span();
+    if let Some(text) = source.get(span.0..span.1) {
+      return text.to_string();
+    }
+  }
+
+  #[cfg(not(feature = "ast-span"))]
+  let _ = source;
+
+  rule.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(unused_imports)]
+
+  use super::*;
+  use crate::cddl_from_str;
+
+  #[test]
+  fn verify_database() -> std::result::Result<(), parser::Error> {
+    let mut db = Database::default();
+
+    let source = r#"message = { header: header, body: body, id: uint, id: uint }
+header = { version: 1..0 }
+body = tstr
+unused = int
+"#;
+    let changed = db.set_document("spec", source)?;
+    assert_eq!(changed, vec!["message", "header", "body", "unused"]);
+
+    let cddl = cddl_from_str(source, false)?;
+    assert_eq!(db.dependency_graph("spec"), Some(&cddl.dependency_graph()));
+    assert_eq!(db.lint("spec"), Some(cddl.lint()));
+
+    // Edits shift the spans of the analyses of the rules that follow them
+    let source = r#"message = { header: header, body: body, id: uint }
+; Version of the protocol
+header = { version: 1..0 }
+body = tstr
+unused = int
+"#;
+    let changed = db.set_document("spec", source)?;
+    assert_eq!(changed, vec!["message"]);
+    assert!(db.documents["spec"].rules["header"].lints.is_some());
+
+    let cddl = cddl_from_str(source, false)?;
+    assert_eq!(db.lint("spec"), Some(cddl.lint()));
+
+    // Defining a rule invalidates the analyses of the rules mentioning it
+    let source = r#"message = { header: header, body: body, id: uint }
+; Version of the protocol
+header = { version: 1..0, ? extension: extension }
+body = tstr
+unused = int
+extension = unused
+"#;
+    let changed = db.set_document("spec", source)?;
+    assert_eq!(changed, vec!["header", "extension"]);
+    assert!(db.documents["spec"].rules["body"].lints.is_some());
+
+    let cddl = cddl_from_str(source, false)?;
+    assert_eq!(db.dependency_graph("spec"), Some(&cddl.dependency_graph()));
+    assert_eq!(db.lint("spec"), Some(cddl.lint()));
+
+    let source = "message = { header: header }\nheader = { version: uint }\n";
+    let changed = db.set_document("spec", source)?;
+    assert_eq!(
+      changed,
+      vec!["message", "header", "body", "extension", "unused"]
+    );
+    assert_eq!(db.lint("spec"), Some(Vec::new()));
+    assert_eq!(db.definitions("spec", "header").len(), 1);
+
+    // Editing a rule invalidates the lints of the rules depending on it
+    db.set_document("spec", "port = u16 .gt 100000\nu16 = uint .size 2\n")?;
+    assert_eq!(db.lint("spec").map(|d| d.len()), Some(1));
+    let source = "port = u16 .gt 100000\nu16 = uint .size 4\n";
+    assert_eq!(db.set_document("spec", source)?, vec!["u16"]);
+    assert_eq!(db.lint("spec"), Some(Vec::new()));
+
+    // Documents that can't be parsed are kept as they were
+    assert!(db.set_document("spec", "message = {").is_err());
+    assert_eq!(db.cddl("spec").map(|c| c.rules.len()), Some(2));
+
+    assert!(db.remove_document("spec"));
+    assert_eq!(db.lint("spec"), None);
+
+    Ok(())
+  }
+
+  #[cfg(all(any(feature = "json", feature = "cbor"), not(feature = "lsp")))]
+  #[test]
+  fn verify_compiled_document() -> std::result::Result<(), parser::Error> {
+    use crate::validator::RegexEngine;
+
+    let mut db = Database::default();
+
+    let regex = |db: &mut Database| {
+      db.compiled("spec")
+        .and_then(|compiled| compiled.ok())
+        .and_then(|compiled| compiled.regex(RegexEngine::default(), "[a-z]+"))
+        .map(|re| re as *const _ as usize)
+    };
+
+    db.set_document(
+      "spec",
+      "message = { name: name }
+name = tstr .regexp \"[a-z]+\"
+",
+    )?;
+    let compiled = regex(&mut db);
+    assert!(compiled.is_some());
+
+    // The compiled document is kept while the document is unchanged
+    db.set_document(
+      "spec",
+      "message = { name: name }
+name = tstr .regexp \"[a-z]+\"
+",
+    )?;
+    assert_eq!(regex(&mut db), compiled);
+
+    // Edits compile the document again, reusing the regular expressions of the
+    // unchanged controllers
+    db.set_document(
+      "spec",
+      "message = { name: name, id: uint }
+name = tstr .regexp \"[a-z]+\"
+",
+    )?;
+    assert!(db.documents["spec"].compiled.is_none());
+    assert!(regex(&mut db).is_some());
+    assert_eq!(
+      db.compiled("spec")
+        .and_then(|compiled| compiled.ok())
+        .map(|compiled| compiled.cddl().rules.len()),
+      Some(2)
+    );
+
+    db.set_document(
+      "spec",
+      "name = tstr .regexp \"[\"
+",
+    )?;
+    assert!(matches!(
+      db.compiled("spec"),
+      Some(Err(compiled::Error::InvalidController { .. }))
+    ));
+
+    Ok(())
+  }
+}
@@ -0,0 +1,604 @@
+#![cfg(feature = "std")]
+
+use crate::ast::*;
+
+use std::fmt::Write;
+
+/// Railroad diagram of a rule, read from left to right. Choices branch off the
+/// main line and occurrence indicators loop back over the repeated entry
+///
+/// # Example
+///
+/// ```
+/// use cddl::{cddl_from_str, railroad::Diagram};
+///
+/// let cddl = cddl_from_str("id = uint / tstr\nids = [* id]", true).unwrap();
+///
+/// assert_eq!(
+///   cddl.railroad_diagram("ids"),
+///   Some(Diagram::Sequence(vec![
+///     Diagram::Terminal("[".to_string()),
+///     Diagram::Optional(Box::new(Diagram::OneOrMore(
+///       Box::new(Diagram::NonTerminal("id".to_string())),
+///       None
+///     ))),
+///     Diagram::Terminal("]".to_string()),
+///   ]))
+/// );
+///
+/// let svg = cddl.railroad_diagram("id").unwrap().to_svg();
+/// assert!(svg.starts_with("<svg"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagram {
+  /// Literal value or punctuation, e.g. `"text"`, `1` or `{`
+  Terminal(String),
+  /// Reference to a rule or to a type of the standard prelude
+  NonTerminal(String),
+  /// Items following one another
+  Sequence(Vec<Diagram>),
+  /// Alternative items, the first of which is on the main line
+  Choice(Vec<Diagram>),
+  /// Item that may be skipped
+  Optional(Box<Diagram>),
+  /// Item that may be repeated, with a label describing the bounds of the
+  /// number of repetitions, if any
+  OneOrMore(Box<Diagram>, Option<String>),
+  /// Nothing, e.g. an empty group
+  Skip,
+}
+
+// Dimensions of the rendered diagram, in pixels
+const CHAR_WIDTH: usize = 8;
+const TEXT_PADDING: usize = 10;
+const BOX_HALF_HEIGHT: usize = 11;
+const ARC_RADIUS: usize = 10;
+const HORIZONTAL_GAP: usize = 10;
+const VERTICAL_GAP: usize = 8;
+const LABEL_HEIGHT: usize = 16;
+const MARGIN: usize = 20;
+const STUB_WIDTH: usize = 10;
+
+const STYLE: &str = "path { stroke-width: 2; stroke: #000; fill: none; } \
+rect { stroke-width: 2; stroke: #000; fill: #eef; } \
+.terminal rect { fill: #efe; } \
+text { font: 13px monospace; text-anchor: middle; } \
+.label { font-size: 11px; }";
+
+// Width, height above the main line and height below it
+#[derive(Debug, Clone, Copy)]
+struct Size {
+  width: usize,
+  up: usize,
+  down: usize,
+}
+
+impl Diagram {
+  /// Render the diagram as a standalone SVG image
+  pub fn to_svg(&self) -> String {
+    let size = self.size();
+    let width = size.width + 2 * (MARGIN + STUB_WIDTH);
+    let height = size.up + size.down + 2 * MARGIN;
+    let y = MARGIN + size.up;
+
+    let mut svg = format!(
+      "<svg xmlns=\"http://www.w3.org/2000/svg\" class=\"railroad-diagram\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n<style>{}</style>\n",
+      STYLE,
+      w = width,
+      h = height
+    );
+
+    // Start and end of the main line
+    let end = MARGIN + STUB_WIDTH + size.width;
+    let _ = writeln!(
+      svg,
+      "<path d=\"M{} {} v{} M{} {} h{}\"/>",
+      MARGIN,
+      y - BOX_HALF_HEIGHT / 2,
+      BOX_HALF_HEIGHT,
+      MARGIN,
+      y,
+      STUB_WIDTH
+    );
+    let _ = writeln!(
+      svg,
+      "<path d=\"M{} {} h{} M{} {} v{}\"/>",
+      end,
+      y,
+      STUB_WIDTH,
+      end + STUB_WIDTH,
+      y - BOX_HALF_HEIGHT / 2,
+      BOX_HALF_HEIGHT
+    );
+
+    self.render(&mut svg, MARGIN + STUB_WIDTH, y);
+
+    svg.push_str("</svg>\n");
+    svg
+  }
+
+  fn size(&self) -> Size {
+    match self {
+      Diagram::Terminal(text) | Diagram::NonTerminal(text) => Size {
+        width: text.chars().count() * CHAR_WIDTH + 2 * TEXT_PADDING,
+        up: BOX_HALF_HEIGHT,
+        down: BOX_HALF_HEIGHT,
+      },
+      Diagram::Sequence(items) => {
+        let sizes = items.iter().map(|i| i.size()).collect::<Vec<_>>();
+        Size {
+          width: sizes.iter().map(|s| s.width).sum::<usize>()
+            + HORIZONTAL_GAP * items.len().saturating_sub(1),
+          up: sizes.iter().map(|s| s.up).max().unwrap_or(0),
+          down: sizes.iter().map(|s| s.down).max().unwrap_or(0),
+        }
+      }
+      Diagram::Choice(items) => choice_size(&items.iter().collect::<Vec<_>>()).0,
+      Diagram::Optional(item) => choice_size(&[item, &Diagram::Skip]).0,
+      Diagram::OneOrMore(item, label) => {
+        let size = item.size();
+        Size {
+          width: size.width + 2 * ARC_RADIUS,
+          up: size.up,
+          down: loop_offset(size) + label.as_ref().map_or(0, |_| LABEL_HEIGHT),
+        }
+      }
+      Diagram::Skip => Size {
+        width: 0,
+        up: 0,
+        down: 0,
+      },
+    }
+  }
+
+  // Render the diagram with its main line entering at (x, y)
+  fn render(&self, svg: &mut String, x: usize, y: usize) {
+    match self {
+      Diagram::Terminal(text) | Diagram::NonTerminal(text) => {
+        let width = self.size().width;
+        let (class, radius) = match self {
+          Diagram::Terminal(_) => ("terminal", BOX_HALF_HEIGHT),
+          _ => ("non-terminal", 0),
+        };
+        let _ = writeln!(
+          svg,
+          "<g class=\"{}\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\"/><text x=\"{}\" y=\"{}\">{}</text></g>",
+          class,
+          x,
+          y - BOX_HALF_HEIGHT,
+          width,
+          2 * BOX_HALF_HEIGHT,
+          radius,
+          x + width / 2,
+          y + 4,
+          escape_xml(text)
+        );
+      }
+      Diagram::Sequence(items) => {
+        let mut cursor = x;
+        for (idx, item) in items.iter().enumerate() {
+          if idx > 0 {
+            let _ = writeln!(svg, "<path d=\"M{} {} h{}\"/>", cursor, y, HORIZONTAL_GAP);
+            cursor += HORIZONTAL_GAP;
+          }
+          item.render(svg, cursor, y);
+          cursor += item.size().width;
+        }
+      }
+      Diagram::Choice(items) => render_choice(svg, &items.iter().collect::<Vec<_>>(), x, y),
+      Diagram::Optional(item) => render_choice(svg, &[item, &Diagram::Skip], x, y),
+      Diagram::OneOrMore(item, label) => {
+        let size = item.size();
+        let width = size.width + 2 * ARC_RADIUS;
+        let offset = loop_offset(size);
+
+        let _ = writeln!(svg, "<path d=\"M{} {} h{}\"/>", x, y, ARC_RADIUS);
+        item.render(svg, x + ARC_RADIUS, y);
+        let _ = writeln!(
+          svg,
+          "<path d=\"M{} {} h{}\"/>",
+          x + ARC_RADIUS + size.width,
+          y,
+          ARC_RADIUS
+        );
+
+        // Loop from the end of the item back to its start
+        let _ = writeln!(
+          svg,
+          "<path d=\"M{} {} {} v{} {} H{} {} v-{} {}\"/>",
+          x + width - ARC_RADIUS,
+          y,
+          arc(1, 1, true),
+          offset - 2 * ARC_RADIUS,
+          arc(-1, 1, true),
+          x + ARC_RADIUS,
+          arc(-1, -1, true),
+          offset - 2 * ARC_RADIUS,
+          arc(1, -1, true)
+        );
+
+        if let Some(label) = label {
+          let _ = writeln!(
+            svg,
+            "<text class=\"label\" x=\"{}\" y=\"{}\">{}</text>",
+            x + width / 2,
+            y + offset + LABEL_HEIGHT - 4,
+            escape_xml(label)
+          );
+        }
+      }
+      Diagram::Skip => (),
+    }
+  }
+}
+
+// Size of a choice, along with the vertical offsets of its items from the main
+// line
+fn choice_size(items: &[&Diagram]) -> (Size, Vec<usize>) {
+  let sizes = items.iter().map(|i| i.size()).collect::<Vec<_>>();
+
+  let mut offsets = Vec::with_capacity(items.len());
+  let mut down = 0;
+  for (idx, size) in sizes.iter().enumerate() {
+    let offset = if idx == 0 {
+      0
+    } else {
+      (down + VERTICAL_GAP + size.up).max(2 * ARC_RADIUS)
+    };
+    offsets.push(offset);
+    down = offset + size.down;
+  }
+
+  (
+    Size {
+      width: sizes.iter().map(|s| s.width).max().unwrap_or(0) + 4 * ARC_RADIUS,
+      up: sizes.first().map_or(0, |s| s.up),
+      down,
+    },
+    offsets,
+  )
+}
+
+fn render_choice(svg: &mut String, items: &[&Diagram], x: usize, y: usize) {
+  let (size, offsets) = choice_size(items);
+  let inner_end = x + size.width - 2 * ARC_RADIUS;
+
+  for (item, offset) in items.iter().zip(offsets) {
+    let item_end = x + 2 * ARC_RADIUS + item.size().width;
+
+    if offset == 0 {
+      let _ = writeln!(svg, "<path d=\"M{} {} h{}\"/>", x, y, 2 * ARC_RADIUS);
+      item.render(svg, x + 2 * ARC_RADIUS, y);
+      let _ = writeln!(svg, "<path d=\"M{} {} H{}\"/>", item_end, y, x + size.width);
+      continue;
+    }
+
+    // Branch off the main line down to the item and back up after it
+    let _ = writeln!(
+      svg,
+      "<path d=\"M{} {} {} v{} {}\"/>",
+      x,
+      y,
+      arc(1, 1, true),
+      offset - 2 * ARC_RADIUS,
+      arc(1, 1, false)
+    );
+    item.render(svg, x + 2 * ARC_RADIUS, y + offset);
+    let _ = writeln!(
+      svg,
+      "<path d=\"M{} {} H{} {} v-{} {}\"/>",
+      item_end,
+      y + offset,
+      inner_end,
+      arc(1, -1, false),
+      offset - 2 * ARC_RADIUS,
+      arc(1, -1, true)
+    );
+  }
+}
+
+// Vertical offset of the line looping back over a repeated item
+fn loop_offset(size: Size) -> usize {
+  (size.down + VERTICAL_GAP).max(2 * ARC_RADIUS)
+}
+
+// Quarter arc ending one radius away from its start in both directions
+fn arc(dx: isize, dy: isize, clockwise: bool) -> String {
+  let r = ARC_RADIUS as isize;
+  format!(
+    "a{r} {r} 0 0 {} {} {}",
+    clockwise as u8,
+    dx * r,
+    dy * r,
+    r = r
+  )
+}
+
+fn escape_xml(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      c => escaped.push(c),
+    }
+  }
+
+  escaped
+}
+
+impl<'a> CDDL<'a> {
+  /// Railroad diagram of the given rule, or `None` if the rule isn't defined.
+  /// Rules extended with additional choices (i.e. `/=` or `//=`) are drawn
+  /// with all of their choices
+  pub fn railroad_diagram(&self, rule: &str) -> Option<Diagram> {
+    let mut choices = Vec::new();
+    for r in self.rules.iter().filter(|r| r.name() == rule) {
+      match r {
+        Rule::Type { rule, .. } => choices.extend(type_choices(&rule.value)),
+        Rule::Group { rule, .. } => choices.push(group_entry(&rule.entry)),
+      }
+    }
+
+    match choices.len() {
+      0 => None,
+      _ => Some(choice(choices)),
+    }
+  }
+}
+
+fn choice(mut choices: Vec<Diagram>) -> Diagram {
+  if choices.len() == 1 {
+    choices.remove(0)
+  } else {
+    Diagram::Choice(choices)
+  }
+}
+
+fn sequence(items: Vec<Diagram>) -> Diagram {
+  let mut flattened = Vec::with_capacity(items.len());
+  for item in items {
+    match item {
+      Diagram::Sequence(items) => flattened.extend(items),
+      Diagram::Skip => (),
+      item => flattened.push(item),
+    }
+  }
+
+  match flattened.len() {
+    0 => Diagram::Skip,
+    1 => flattened.remove(0),
+    _ => Diagram::Sequence(flattened),
+  }
+}
+
+fn type_choices(t: &Type) -> Vec<Diagram> {
+  t.type_choices.iter().map(|tc| type1(&tc.type1)).collect()
+}
+
+fn type1(t1: &Type1) -> Diagram {
+  match &t1.operator {
+    Some(o) => sequence(vec![
+      type2(&t1.type2),
+      Diagram::Terminal(o.operator.to_string()),
+      type2(&o.type2),
+    ]),
+    None => type2(&t1.type2),
+  }
+}
+
+fn type2(t2: &Type2) -> Diagram {
+  match t2 {
+    Type2::Typename {
+      ident,
+      generic_args,
+      ..
+    } => Diagram::NonTerminal(reference(ident, generic_args.as_ref())),
+    Type2::ParenthesizedType { pt, .. } => choice(type_choices(pt)),
+    Type2::Map { group: g, .. } => sequence(vec![
+      Diagram::Terminal("{".to_string()),
+      group(g),
+      Diagram::Terminal("}".to_string()),
+    ]),
+    Type2::Array { group: g, .. } => sequence(vec![
+      Diagram::Terminal("[".to_string()),
+      group(g),
+      Diagram::Terminal("]".to_string()),
+    ]),
+    Type2::Unwrap {
+      ident,
+      generic_args,
+      ..
+    } => Diagram::NonTerminal(format!("~{}", reference(ident, generic_args.as_ref()))),
+    Type2::ChoiceFromInlineGroup { group: g, .. } => sequence(vec![
+      Diagram::Terminal("&(".to_string()),
+      group(g),
+      Diagram::Terminal(")".to_string()),
+    ]),
+    Type2::ChoiceFromGroup {
+      ident,
+      generic_args,
+      ..
+    } => Diagram::NonTerminal(format!("&{}", reference(ident, generic_args.as_ref()))),
+    Type2::TaggedData {
+      tag, tag_type, t, ..
+    } => sequence(vec![
+      Diagram::Terminal(match (tag, tag_type) {
+        (Some(tag), _) => format!("#6.{}(", tag),
+        (None, Some(tag_type)) => format!("#6.<{}>(", tag_type),
+        (None, None) => "#6(".to_string(),
+      }),
+      choice(type_choices(t)),
+      Diagram::Terminal(")".to_string()),
+    ]),
+    _ => Diagram::Terminal(t2.to_string()),
+  }
+}
+
+fn reference(ident: &Identifier, generic_args: Option<&GenericArgs>) -> String {
+  match generic_args {
+    Some(ga) => format!("{}{}", ident, ga),
+    None => ident.to_string(),
+  }
+}
+
+fn group(g: &Group) -> Diagram {
+  choice(
+    g.group_choices
+      .iter()
+      .map(|gc| {
+        sequence(
+          gc.group_entries
+            .iter()
+            .map(|(ge, _)| group_entry(ge))
+            .collect(),
+        )
+      })
+      .collect(),
+  )
+}
+
+fn group_entry(ge: &GroupEntry) -> Diagram {
+  let (occur, entry) = match ge {
+    GroupEntry::ValueMemberKey { ge, .. } => {
+      let key = match &ge.member_key {
+        Some(MemberKey::Type1 { t1, is_cut, .. }) => sequence(vec![
+          type1(t1),
+          Diagram::Terminal(if *is_cut { "^ =>" } else { "=>" }.to_string()),
+        ]),
+        Some(MemberKey::Bareword { ident, .. }) => Diagram::Terminal(format!("{}:", ident)),
+        Some(MemberKey::Value { value, .. }) => Diagram::Terminal(format!("{}:", value)),
+        Some(MemberKey::NonMemberKey { .. }) | None => Diagram::Skip,
+      };
+
+      (
+        ge.occur.as_ref(),
+        sequence(vec![key, choice(type_choices(&ge.entry_type))]),
+      )
+    }
+    GroupEntry::TypeGroupname { ge, .. } => (
+      ge.occur.as_ref(),
+      Diagram::NonTerminal(reference(&ge.name, ge.generic_args.as_ref())),
+    ),
+    GroupEntry::InlineGroup {
+      occur, group: g, ..
+    } => (occur.as_ref(), group(g)),
+  };
+
+  match occur.map(|o| &o.occur) {
+    None => entry,
+    Some(Occur::Optional { .. }) => Diagram::Optional(Box::new(entry)),
+    Some(Occur::ZeroOrMore { .. }) => {
+      Diagram::Optional(Box::new(Diagram::OneOrMore(Box::new(entry), None)))
+    }
+    Some(Occur::OneOrMore { .. }) => Diagram::OneOrMore(Box::new(entry), None),
+    Some(o @ Occur::Exact { lower, upper, .. }) => {
+      let lower = lower.unwrap_or(0);
+      // At most one occurrence needs no loop
+      let entry = match upper {
+        Some(upper) if *upper <= 1 => entry,
+        _ => Diagram::OneOrMore(Box::new(entry), Some(o.to_string())),
+      };
+
+      if lower == 0 {
+        Diagram::Optional(Box::new(entry))
+      } else {
+        entry
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{cddl_from_str, parser};
+
+  use indoc::indoc;
+
+  #[test]
+  fn verify_railroad_diagram() -> std::result::Result<(), parser::Error> {
+    let cddl = cddl_from_str(
+      indoc!(
+        r#"
+          message = {
+            id: uint,
+            ? tags: [+ tstr],
+            2*3 "a" => int / float,
+            type: "ping" // type: "pong", payload,
+          }
+          payload = #6.24(bstr) / nil
+          payload /= 0..10
+          header = (version: 1)
+        "#
+      ),
+      false,
+    )?;
+
+    let terminal = |s: &str| Diagram::Terminal(s.to_string());
+    let non_terminal = |s: &str| Diagram::NonTerminal(s.to_string());
+
+    assert_eq!(
+      cddl.railroad_diagram("message"),
+      Some(Diagram::Sequence(vec![
+        terminal("{"),
+        Diagram::Choice(vec![
+          Diagram::Sequence(vec![
+            terminal("id:"),
+            non_terminal("uint"),
+            Diagram::Optional(Box::new(Diagram::Sequence(vec![
+              terminal("tags:"),
+              terminal("["),
+              Diagram::OneOrMore(Box::new(non_terminal("tstr")), None),
+              terminal("]"),
+            ]))),
+            Diagram::OneOrMore(
+              Box::new(Diagram::Sequence(vec![
+                terminal("\"a\""),
+                terminal("=>"),
+                Diagram::Choice(vec![non_terminal("int"), non_terminal("float")]),
+              ])),
+              Some("2*3".to_string())
+            ),
+            terminal("type:"),
+            terminal("\"ping\""),
+          ]),
+          Diagram::Sequence(vec![
+            terminal("type:"),
+            terminal("\"pong\""),
+            non_terminal("payload"),
+          ]),
+        ]),
+        terminal("}"),
+      ]))
+    );
+
+    assert_eq!(
+      cddl.railroad_diagram("payload"),
+      Some(Diagram::Choice(vec![
+        Diagram::Sequence(vec![
+          terminal("#6.24("),
+          non_terminal("bstr"),
+          terminal(")")
+        ]),
+        non_terminal("nil"),
+        Diagram::Sequence(vec![terminal("0"), terminal(".."), terminal("10")]),
+      ]))
+    );
+    assert_eq!(
+      cddl.railroad_diagram("header"),
+      Some(Diagram::Sequence(vec![terminal("version:"), terminal("1")]))
+    );
+    assert_eq!(cddl.railroad_diagram("undefined"), None);
+
+    let svg = cddl.railroad_diagram("message").unwrap().to_svg();
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.ends_with("</svg>\n"));
+    assert!(svg.contains("<text x=\"") && svg.contains(">&quot;ping&quot;</text>"));
+    assert!(svg.contains("<text class=\"label\""));
+
+    Ok(())
+  }
+}
@@ -0,0 +1,644 @@
+#![cfg(feature = "std")]
+#![cfg(feature = "json")]
+#![cfg(feature = "cbor")]
+#![cfg(not(feature = "lsp"))]
+#![cfg(not(target_arch = "wasm32"))]
+
+use crate::{
+  anonymize::Anonymizer,
+  ast::{GenericArgs, Identifier, Type2},
+  generator::Target,
+  lexer::lexer_from_str,
+  parser::{cddl_from_str, Parser},
+  validator::{cbor, json, ChoiceExplanation, RejectedChoice, Validator},
+};
+
+use ciborium::value::Value;
+use displaydoc::Display;
+use serde_json::json;
+use std::borrow::Cow;
+
+/// Alias for `Result` with an error of type `replay::Error`
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Recording or replay error
+#[derive(Debug, Display)]
+pub enum Error {
+  /// CDDL document is not conformant: {0}
+  CDDLParsing(String),
+  /// {0} is not a valid rule name
+  InvalidRule(String),
+  /// malformed replay file: {0}
+  Malformed(String),
+  /// unsupported replay file version {0}
+  UnsupportedVersion(u64),
+}
+
+impl std::error::Error for Error {}
+
+/// Version of the format of replay files written by this crate
+pub const FORMAT_VERSION: u64 = 1;
+
+// Seed of the synthetic values of redacted instances
+const REDACTION_SEED: u64 = 0;
+
+/// Instance validated in a session
+#[derive(Clone, Debug, PartialEq)]
+pub enum Instance {
+  /// JSON document
+  Json(serde_json::Value),
+  /// Encoded CBOR data item, kept as is so that decoding errors reproduce
+  Cbor(Vec<u8>),
+}
+
+/// Options a session was validated with
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Options {
+  /// Rule validated against instead of the first type rule in the document,
+  /// along with any generic arguments, e.g. `message<tstr, uint>`
+  pub rule: Option<String>,
+  /// Features enabled for the `.feature` control operator
+  pub features: Option<Vec<String>>,
+  /// Whether or not to replace the content of the instance with synthetic
+  /// values, keeping its structure
+  pub redact: bool,
+}
+
+/// Validation error of a session
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedError {
+  /// Location in the instance (in JSONPointer notation for JSON)
+  pub location: String,
+  /// Name of the innermost rule being validated when the error occurred
+  pub cddl_location: String,
+  /// Error message
+  pub reason: String,
+}
+
+impl std::fmt::Display for RecordedError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(
+      f,
+      "{}: {}",
+      if self.location.is_empty() {
+        "root"
+      } else {
+        &self.location
+      },
+      self.reason
+    )
+  }
+}
+
+/// Result of validating an instance, along with the decisions taken to reach
+/// it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Outcome {
+  /// Errors, empty if the instance is valid. Errors other than validation
+  /// errors, e.g. decoding errors, have an empty location
+  pub errors: Vec<RecordedError>,
+  /// For each location validated against a type choice, which choice matched
+  /// and why the choices preceding it did not
+  pub trace: Vec<ChoiceExplanation>,
+}
+
+impl Outcome {
+  /// Whether or not the instance is valid
+  pub fn is_valid(&self) -> bool {
+    self.errors.is_empty()
+  }
+}
+
+/// Validation session captured for reproduction elsewhere, e.g. attached to a
+/// bug report. A session holds the CDDL document, the options and the instance
+/// it was validated with, and the outcome of the validation, and is written to
+/// and read from a self-contained JSON replay file
+///
+/// The content of instances that can't be shared can be redacted when
+/// recording. Valid instances are then anonymized into instances that still
+/// conform to the document (see [`crate::anonymize`]), while the text and byte
+/// strings of invalid instances are masked, keeping their lengths. The outcome
+/// recorded is that of the redacted instance, so the replay file reproduces
+/// exactly, though it may no longer exhibit the original problem. Check with
+/// [`Session::outcome`] before sharing
+///
+/// # Example
+///
+/// ```
+/// use cddl::replay::{Instance, Options, Session};
+///
+/// let cddl = "message = { id: uint, body: tstr / bstr }";
+/// let instance = Instance::Json(serde_json::json!({ "id": "a", "body": "hi" }));
+/// let session = Session::record(cddl, instance, Options::default()).unwrap();
+/// assert_eq!(session.outcome().errors[0].location, "/id");
+///
+/// let file = session.to_string();
+/// let session = Session::from_str(&file).unwrap();
+/// assert_eq!(session.replay().unwrap(), *session.outcome());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Session {
+  cddl: String,
+  rule: Option<String>,
+  features: Option<Vec<String>>,
+  instance: Instance,
+  redacted: bool,
+  outcome: Outcome,
+  crate_version: String,
+}
+
+impl Session {
+  /// Validate an instance against a CDDL document and record the session
+  pub fn record(cddl: &str, instance: Instance, options: Options) -> Result<Self> {
+    let mut session = Session {
+      cddl: cddl.to_string(),
+      rule: options.rule,
+      features: options.features,
+      instance,
+      redacted: false,
+      outcome: Outcome::default(),
+      crate_version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+
+    session.outcome = session.replay()?;
+    if options.redact {
+      session.instance = session.redact()?;
+      session.redacted = true;
+      session.outcome = session.replay()?;
+    }
+
+    Ok(session)
+  }
+
+  /// Validate the recorded instance again, returning the outcome. The outcome
+  /// equals the recorded outcome unless validation behaves differently than
+  /// when the session was recorded
+  pub fn replay(&self) -> Result<Outcome> {
+    let cddl = cddl_from_str(&self.cddl, false).map_err(|e| Error::CDDLParsing(e.to_string()))?;
+    let root_rule = self.rule.as_deref().map(parse_rule).transpose()?;
+    #[cfg(feature = "additional-controls")]
+    let features = self
+      .features
+      .as_ref()
+      .map(|f| f.iter().map(|s| s.as_str()).collect::<Vec<_>>());
+
+    let mut outcome = Outcome::default();
+    let result = match &self.instance {
+      Instance::Json(instance) => {
+        #[cfg(feature = "additional-controls")]
+        let mut jv = json::JSONValidator::new(&cddl, instance.clone(), features.as_deref());
+        #[cfg(not(feature = "additional-controls"))]
+        let mut jv = json::JSONValidator::new(&cddl, instance.clone());
+
+        if let Some((name, generic_args)) = &root_rule {
+          jv.set_root_rule(name, generic_args.clone());
+        }
+        jv.set_explain_choices(true);
+
+        let result = jv.validate();
+        outcome.trace = jv.choice_explanations().to_vec();
+        match result {
+          Err(json::Error::Validation(errors)) => Err(
+            errors
+              .into_iter()
+              .map(|e| RecordedError {
+                location: e.json_location,
+                cddl_location: e.cddl_location,
+                reason: e.reason,
+              })
+              .collect(),
+          ),
+          result => result.map_err(|e| vec![other_error(e)]),
+        }
+      }
+      Instance::Cbor(bytes) => match ciborium::de::from_reader::<Value, _>(&bytes[..]) {
+        Ok(instance) => {
+          #[cfg(feature = "additional-controls")]
+          let mut cv = cbor::CBORValidator::new(&cddl, instance, features.as_deref());
+          #[cfg(not(feature = "additional-controls"))]
+          let mut cv = cbor::CBORValidator::new(&cddl, instance);
+
+          cv.set_cbor_slice(bytes);
+          if let Some((name, generic_args)) = &root_rule {
+            cv.set_root_rule(name, generic_args.clone());
+          }
+          cv.set_explain_choices(true);
+
+          let result = <_ as Validator<cbor::Error<cbor::ReaderError>>>::validate(&mut cv);
+          outcome.trace = cv.choice_explanations().to_vec();
+          match result {
+            Err(cbor::Error::Validation(errors)) => Err(
+              errors
+                .into_iter()
+                .map(|e| RecordedError {
+                  location: e.cbor_location,
+                  cddl_location: e.cddl_location,
+                  reason: e.reason,
+                })
+                .collect(),
+            ),
+            result => result.map_err(|e| vec![other_error(e)]),
+          }
+        }
+        Err(e) => Err(vec![other_error(
+          cbor::Error::<cbor::ReaderError>::CBORParsing(e),
+        )]),
+      },
+    };
+
+    if let Err(errors) = result {
+      outcome.errors = errors;
+    }
+
+    Ok(outcome)
+  }
+
+  /// CDDL document the instance was validated against
+  pub fn cddl(&self) -> &str {
+    &self.cddl
+  }
+
+  /// Options the instance was validated with
+  pub fn options(&self) -> Options {
+    Options {
+      rule: self.rule.clone(),
+      features: self.features.clone(),
+      redact: self.redacted,
+    }
+  }
+
+  /// Validated instance, redacted if so recorded
+  pub fn instance(&self) -> &Instance {
+    &self.instance
+  }
+
+  /// Recorded outcome of the validation
+  pub fn outcome(&self) -> &Outcome {
+    &self.outcome
+  }
+
+  /// Version of this crate the session was recorded with
+  pub fn crate_version(&self) -> &str {
+    &self.crate_version
+  }
+
+  /// Read a session from the contents of a replay file
+  #[allow(clippy::should_implement_trait)]
+  pub fn from_str(s: &str) -> Result<Self> {
+    let file =
+      serde_json::from_str::<serde_json::Value>(s).map_err(|e| Error::Malformed(e.to_string()))?;
+    Session::from_json(&file)
+  }
+
+  /// Read a session from a replay file parsed as JSON
+  pub fn from_json(file: &serde_json::Value) -> Result<Self> {
+    let version = file["version"]
+      .as_u64()
+      .ok_or_else(|| malformed("version"))?;
+    if version != FORMAT_VERSION {
+      return Err(Error::UnsupportedVersion(version));
+    }
+
+    let instance = match file["format"].as_str() {
+      Some("json") => Instance::Json(file["instance"].clone()),
+      Some("cbor") => Instance::Cbor(
+        file["instance"]
+          .as_str()
+          .and_then(|hex| base16::decode(hex).ok())
+          .ok_or_else(|| malformed("instance"))?,
+      ),
+      _ => return Err(malformed("format")),
+    };
+
+    let errors = file["errors"]
+      .as_array()
+      .ok_or_else(|| malformed("errors"))?
+      .iter()
+      .map(|e| {
+        Some(RecordedError {
+          location: e["location"].as_str()?.to_string(),
+          cddl_location: e["cddl_location"].as_str()?.to_string(),
+          reason: e["reason"].as_str()?.to_string(),
+        })
+      })
+      .collect::<Option<Vec<_>>>()
+      .ok_or_else(|| malformed("errors"))?;
+    let trace = file["trace"]
+      .as_array()
+      .ok_or_else(|| malformed("trace"))?
+      .iter()
+      .map(explanation_from_json)
+      .collect::<Option<Vec<_>>>()
+      .ok_or_else(|| malformed("trace"))?;
+
+    Ok(Session {
+      cddl: file["cddl"]
+        .as_str()
+        .ok_or_else(|| malformed("cddl"))?
+        .to_string(),
+      rule: file["rule"].as_str().map(|s| s.to_string()),
+      features: file["features"].as_array().map(|features| {
+        features
+          .iter()
+          .filter_map(|f| f.as_str().map(|s| s.to_string()))
+          .collect()
+      }),
+      instance,
+      redacted: file["redacted"].as_bool().unwrap_or_default(),
+      outcome: Outcome { errors, trace },
+      crate_version: file["crate_version"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string(),
+    })
+  }
+
+  /// Session as a replay file parsed as JSON
+  pub fn to_json(&self) -> serde_json::Value {
+    let (format, instance) = match &self.instance {
+      Instance::Json(instance) => ("json", instance.clone()),
+      Instance::Cbor(bytes) => ("cbor", json!(base16::encode_lower(bytes))),
+    };
+
+    json!({
+      "version": FORMAT_VERSION,
+      "crate_version": self.crate_version,
+      "cddl": self.cddl,
+      "rule": self.rule,
+      "features": self.features,
+      "format": format,
+      "instance": instance,
+      "redacted": self.redacted,
+      "valid": self.outcome.is_valid(),
+      "errors": self
+        .outcome
+        .errors
+        .iter()
+        .map(|e| json!({
+          "location": e.location,
+          "cddl_location": e.cddl_location,
+          "reason": e.reason,
+        }))
+        .collect::<Vec<_>>(),
+      "trace": self.outcome.trace.iter().map(explanation_to_json).collect::<Vec<_>>(),
+    })
+  }
+
+  // Instance with its content replaced, keeping its structure
+  fn redact(&self) -> Result<Instance> {
+    let cddl = cddl_from_str(&self.cddl, false).map_err(|e| Error::CDDLParsing(e.to_string()))?;
+    #[cfg(feature = "additional-controls")]
+    let features = self
+      .features
+      .as_ref()
+      .map(|f| f.iter().map(|s| s.as_str()).collect::<Vec<_>>());
+    let new_anonymizer = |target| {
+      #[cfg(feature = "additional-controls")]
+      return Anonymizer::new(&cddl, target, REDACTION_SEED, features.as_deref());
+      #[cfg(not(feature = "additional-controls"))]
+      Anonymizer::new(&cddl, target, REDACTION_SEED)
+    };
+
+    // Anonymization only succeeds for instances conforming to the first type
+    // rule of the document, so other instances are masked
+    Ok(match &self.instance {
+      Instance::Json(instance) => Instance::Json(
+        new_anonymizer(Target::Json)
+          .anonymize_json(instance)
+          .unwrap_or_else(|_| mask_json(instance)),
+      ),
+      Instance::Cbor(bytes) => match ciborium::de::from_reader::<Value, _>(&bytes[..]) {
+        Ok(instance) => {
+          let redacted = new_anonymizer(Target::Cbor)
+            .anonymize(&instance)
+            .unwrap_or_else(|_| mask_cbor(&instance));
+          let mut bytes = Vec::new();
+          ciborium::ser::into_writer(&redacted, &mut bytes)
+            .map_err(|e| Error::Malformed(e.to_string()))?;
+          Instance::Cbor(bytes)
+        }
+        // Bytes that don't decode carry no structure to keep
+        Err(_) => Instance::Cbor(Vec::new()),
+      },
+    })
+  }
+}
+
+impl std::fmt::Display for Session {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(
+      f,
+      "{}",
+      serde_json::to_string_pretty(&self.to_json()).map_err(|_| std::fmt::Error)?
+    )
+  }
+}
+
+/// Parse a rule name along with any generic arguments, e.g. `message<tstr, uint>`
+fn parse_rule(rule: &str) -> Result<(&str, Option<GenericArgs<'_>>)> {
+  let mut p = Parser::new(rule, Box::new(lexer_from_str(rule).iter()))
+    .map_err(|_| Error::InvalidRule(rule.to_string()))?;
+  match p.parse_type2() {
+    // Identifiers parsed from text borrow from it
+    Ok(Type2::Typename {
+      ident: Identifier {
+        ident: Cow::Borrowed(name),
+        ..
+      },
+      generic_args,
+      ..
+    }) => Ok((name, generic_args)),
+    _ => Err(Error::InvalidRule(rule.to_string())),
+  }
+}
+
+fn other_error(error: impl std::fmt::Display) -> RecordedError {
+  RecordedError {
+    location: String::new(),
+    cddl_location: String::new(),
+    reason: error.to_string().trim_end().to_string(),
+  }
+}
+
+fn malformed(field: &str) -> Error {
+  Error::Malformed(format!("missing or invalid field {:?}", field))
+}
+
+// Replace the characters of text strings with 'x', keeping map keys, which
+// carry the structure
+fn mask_json(json: &serde_json::Value) -> serde_json::Value {
+  match json {
+    serde_json::Value::String(s) => serde_json::Value::String("x".repeat(s.chars().count())),
+    serde_json::Value::Array(a) => serde_json::Value::Array(a.iter().map(mask_json).collect()),
+    serde_json::Value::Object(o) => {
+      serde_json::Value::Object(o.iter().map(|(k, v)| (k.clone(), mask_json(v))).collect())
+    }
+    json => json.clone(),
+  }
+}
+
+// Replace the characters of text strings with 'x' and the bytes of byte strings
+// with zeros, keeping map keys, which carry the structure
+fn mask_cbor(cbor: &Value) -> Value {
+  match cbor {
+    Value::Text(s) => Value::Text("x".repeat(s.chars().count())),
+    Value::Bytes(b) => Value::Bytes(vec![0; b.len()]),
+    Value::Array(a) => Value::Array(a.iter().map(mask_cbor).collect()),
+    Value::Map(m) => Value::Map(m.iter().map(|(k, v)| (k.clone(), mask_cbor(v))).collect()),
+    Value::Tag(tag, v) => Value::Tag(*tag, Box::new(mask_cbor(v))),
+    cbor => cbor.clone(),
+  }
+}
+
+fn explanation_to_json(explanation: &ChoiceExplanation) -> serde_json::Value {
+  json!({
+    "location": explanation.location,
+    "rule": explanation.rule,
+    "index": explanation.index,
+    "choice": explanation.choice,
+    "group_choice": explanation.is_group_choice,
+    "rejected": explanation
+      .rejected
+      .iter()
+      .map(|r| json!({
+        "index": r.index,
+        "choice": r.choice,
+        "reasons": r.reasons,
+      }))
+      .collect::<Vec<_>>(),
+  })
+}
+
+fn explanation_from_json(json: &serde_json::Value) -> Option<ChoiceExplanation> {
+  Some(ChoiceExplanation {
+    location: json["location"].as_str()?.to_string(),
+    rule: json["rule"].as_str()?.to_string(),
+    index: json["index"].as_u64()? as usize,
+    choice: json["choice"].as_str()?.to_string(),
+    // Replay files recorded before group choices were explained lack the field
+    is_group_choice: json["group_choice"].as_bool().unwrap_or(false),
+    rejected: json["rejected"]
+      .as_array()?
+      .iter()
+      .map(|r| {
+        Some(RejectedChoice {
+          index: r["index"].as_u64()? as usize,
+          choice: r["choice"].as_str()?.to_string(),
+          reasons: r["reasons"]
+            .as_array()?
+            .iter()
+            .map(|reason| reason.as_str().map(|s| s.to_string()))
+            .collect::<Option<Vec<_>>>()?,
+        })
+      })
+      .collect::<Option<Vec<_>>>()?,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use indoc::indoc;
+
+  const CDDL: &str = indoc!(
+    r#"
+      account = { owner: tstr, balance: int / float, ? note: tstr / bstr }
+      transfer = [from: tstr, to: tstr, amount: uint]
+    "#
+  );
+
+  #[test]
+  fn verify_record_and_replay() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let instance = Instance::Json(json!({ "owner": "Alice", "balance": 1.5, "note": 3 }));
+    let session = Session::record(CDDL, instance.clone(), Options::default())?;
+    assert!(!session.outcome().is_valid());
+    assert_eq!(session.outcome().errors[0].location, "/note");
+    assert!(session
+      .outcome()
+      .trace
+      .iter()
+      .any(|e| e.location == "/balance" && e.index == 1));
+    assert_eq!(session.instance(), &instance);
+
+    let replayed = Session::from_str(&session.to_string())?;
+    assert_eq!(replayed, session);
+    assert_eq!(replayed.replay()?, *session.outcome());
+
+    let cbor = |value: Value| {
+      let mut bytes = Vec::new();
+      ciborium::ser::into_writer(&value, &mut bytes).unwrap();
+      Instance::Cbor(bytes)
+    };
+    let options = Options {
+      rule: Some("transfer".to_string()),
+      ..Options::default()
+    };
+    let transfer = cbor(Value::Array(vec![
+      Value::Text("a".into()),
+      Value::Text("b".into()),
+      Value::Integer(1.into()),
+    ]));
+    let session = Session::record(CDDL, transfer, options.clone())?;
+    assert!(session.outcome().is_valid());
+    assert_eq!(Session::from_str(&session.to_string())?, session);
+
+    // Bytes that don't decode reproduce the decoding error
+    let session = Session::record(CDDL, Instance::Cbor(vec![0x82, 0x01]), options)?;
+    assert_eq!(session.outcome().errors.len(), 1);
+    assert_eq!(
+      Session::from_str(&session.to_string())?.replay()?,
+      *session.outcome()
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_redaction() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let options = Options {
+      redact: true,
+      ..Options::default()
+    };
+
+    // Valid instances are anonymized into valid instances
+    let instance = json!({ "owner": "Alice", "balance": 10 });
+    let session = Session::record(CDDL, Instance::Json(instance.clone()), options.clone())?;
+    assert!(session.outcome().is_valid());
+    match session.instance() {
+      Instance::Json(redacted) => assert_ne!(redacted["owner"], instance["owner"]),
+      instance => panic!("unexpected instance {:?}", instance),
+    }
+
+    // Invalid instances are masked and still fail at the same location
+    let instance = json!({ "owner": "Alice", "balance": "lots" });
+    let session = Session::record(CDDL, Instance::Json(instance), options)?;
+    assert_eq!(
+      session.instance(),
+      &Instance::Json(json!({ "owner": "xxxxx", "balance": "xxxx" }))
+    );
+    assert_eq!(session.outcome().errors[0].location, "/balance");
+    assert!(session.options().redact);
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_malformed_files() {
+    assert!(matches!(Session::from_str("{}"), Err(Error::Malformed(_))));
+    assert!(matches!(
+      Session::from_str(r#"{ "version": 99 }"#),
+      Err(Error::UnsupportedVersion(99))
+    ));
+    assert!(matches!(
+      Session::record(
+        "a = uint",
+        Instance::Json(json!(1)),
+        Options {
+          rule: Some("1".to_string()),
+          ..Options::default()
+        }
+      ),
+      Err(Error::InvalidRule(_))
+    ));
+  }
+}
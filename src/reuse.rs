@@ -0,0 +1,525 @@
+#![cfg(feature = "std")]
+
+use crate::ast::*;
+
+use std::collections::BTreeMap;
+
+/// Inline group appearing more than once in a document, e.g. the same map
+/// contents spelled out in several rules, which can be extracted into a shared
+/// group rule with [`CDDL::extract_group`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepeatedGroup {
+  /// Group, with comments, insignificant whitespace and trailing commas
+  /// removed
+  pub group: String,
+  /// Names of the rules of the occurrences of the group, in order of
+  /// appearance
+  pub rules: Vec<String>,
+  /// Spans of the occurrences of the group
+  #[cfg(feature = "ast-span")]
+  pub spans: Vec<Span>,
+  /// Group rule already defining the group, which the occurrences can
+  /// reference instead
+  pub existing_rule: Option<String>,
+  /// Name suggested for the shared group rule. This is the existing rule if
+  /// any, or else the member key the group is most often the value of, or a
+  /// name derived from the first rule of the occurrences. Suggested names don't
+  /// clash with the rules of the document
+  pub suggested_name: String,
+}
+
+impl<'a> CDDL<'a> {
+  /// Inline groups of map, array and group entries that appear more than
+  /// once, with at least two entries. Groups are compared structurally,
+  /// ignoring comments and formatting. Groups nested within a repeated group
+  /// are only reported if they also appear elsewhere, and generic rules are
+  /// skipped since their groups may depend on their parameters. Groups are
+  /// returned in order of first appearance
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use cddl::cddl_from_str;
+  ///
+  /// let cddl = cddl_from_str(
+  ///   r#"
+  ///     request = { id: uint, origin: { host: tstr, port: uint } }
+  ///     response = { id: uint, origin: { host: tstr, port: uint }, body: bstr }
+  ///   "#,
+  ///   true,
+  /// )
+  /// .unwrap();
+  ///
+  /// let repeated = cddl.repeated_groups();
+  /// assert_eq!(repeated.len(), 1);
+  /// assert_eq!(repeated[0].group, "host:tstr,port:uint");
+  /// assert_eq!(repeated[0].rules, vec!["request", "response"]);
+  /// assert_eq!(repeated[0].suggested_name, "origin");
+  /// ```
+  pub fn repeated_groups(&self) -> Vec<RepeatedGroup> {
+    let existing = existing_groups(self);
+
+    // Groups are walked mutably to share the traversal with extraction, so
+    // the walks are over a copy of the document
+    let mut cddl = self.clone();
+
+    let mut counts = BTreeMap::<String, usize>::new();
+    walk_groups(&mut cddl, &mut |group, _, _| {
+      if is_candidate(group) {
+        *counts.entry(group_key(group)).or_default() += 1;
+      }
+
+      false
+    });
+
+    let is_repeated = |key: &String| {
+      counts.get(key).is_some_and(|count| *count > 1)
+        || (existing.contains_key(key) && counts.contains_key(key))
+    };
+
+    // Only the outermost repeated groups are collected, since extracting them
+    // also extracts the groups nested within them
+    let mut repeated: Vec<(RepeatedGroup, BTreeMap<String, usize>)> = Vec::new();
+    walk_groups(&mut cddl, &mut |group, rule, key| {
+      let group_key = group_key(group);
+      if !is_candidate(group) || !is_repeated(&group_key) {
+        return false;
+      }
+
+      let idx = match repeated.iter().position(|(r, _)| r.group == group_key) {
+        Some(idx) => idx,
+        None => {
+          repeated.push((
+            RepeatedGroup {
+              existing_rule: existing.get(&group_key).cloned(),
+              group: group_key,
+              rules: Vec::new(),
+              #[cfg(feature = "ast-span")]
+              spans: Vec::new(),
+              suggested_name: String::new(),
+            },
+            BTreeMap::new(),
+          ));
+          repeated.len() - 1
+        }
+      };
+
+      let (r, keys) = &mut repeated[idx];
+      r.rules.push(rule.to_string());
+      #[cfg(feature = "ast-span")]
+      r.spans.push(group.span);
+      if let Some(key) = key {
+        *keys.entry(key.to_string()).or_default() += 1;
+      }
+
+      true
+    });
+
+    repeated
+      .into_iter()
+      .filter(|(r, _)| r.rules.len() > 1 || r.existing_rule.is_some())
+      .map(|(mut r, keys)| {
+        r.suggested_name = match &r.existing_rule {
+          Some(existing) => existing.clone(),
+          None => {
+            // Most frequent key, or the first of equally frequent keys
+            let key = keys
+              .iter()
+              .rev()
+              .max_by_key(|(_, count)| **count)
+              .map(|(key, _)| key.clone());
+            let base = key.unwrap_or_else(|| format!("{}-group", r.rules[0]));
+
+            unique_name(self, base)
+          }
+        };
+
+        r
+      })
+      .collect()
+  }
+
+  /// Extract the given repeated group into a group rule with the given name,
+  /// replacing each occurrence of the group with a reference to the rule. The
+  /// rule is appended to the document unless a rule with the given name
+  /// already exists, e.g. the existing rule defining the group
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use cddl::cddl_from_str;
+  ///
+  /// let cddl = cddl_from_str(
+  ///   r#"
+  ///     a = { x: int, y: int }
+  ///     b = [* { x: int, y: int }]
+  ///   "#,
+  ///   true,
+  /// )
+  /// .unwrap();
+  ///
+  /// let repeated = &cddl.repeated_groups()[0];
+  /// let extracted = cddl.extract_group(repeated, "point");
+  /// assert_eq!(
+  ///   extracted
+  ///     .rules
+  ///     .iter()
+  ///     .map(|r| r.to_string())
+  ///     .collect::<Vec<_>>(),
+  ///   vec!["a = { point }", "b = [ * { point } ]", "point = ( x: int, y: int )"]
+  /// );
+  /// ```
+  pub fn extract_group(&self, repeated: &RepeatedGroup, name: &'a str) -> CDDL<'a> {
+    let mut cddl = self.clone();
+
+    let mut extracted = None;
+    walk_groups(&mut cddl, &mut |group, _, _| {
+      if !is_candidate(group) || group_key(group) != repeated.group {
+        return false;
+      }
+
+      let reference = group_reference(name);
+      extracted.get_or_insert(std::mem::replace(group, reference));
+
+      true
+    });
+
+    if let Some(group) = extracted {
+      if !cddl.rules.iter().any(|r| r.name() == name) {
+        cddl.rules.push(group_rule(name, group));
+      }
+    }
+
+    cddl
+  }
+}
+
+// Groups with a single entry aren't worth extracting
+fn is_candidate(group: &Group) -> bool {
+  group
+    .group_choices
+    .iter()
+    .map(|gc| gc.group_entries.len())
+    .sum::<usize>()
+    > 1
+}
+
+// Text of a group with comments, insignificant whitespace and trailing commas
+// removed
+fn group_key(group: &Group) -> String {
+  group
+    .group_choices
+    .iter()
+    .map(|gc| {
+      gc.group_entries
+        .iter()
+        .map(|(ge, _)| crate::diff::normalize(&ge.to_string()))
+        .collect::<Vec<_>>()
+        .join(",")
+    })
+    .collect::<Vec<_>>()
+    .join("//")
+}
+
+// Groups defined by non-generic group rules, by their key
+fn existing_groups(cddl: &CDDL) -> BTreeMap<String, String> {
+  let mut existing = BTreeMap::new();
+  for rule in cddl.rules.iter() {
+    if let Rule::Group { rule, .. } = rule {
+      if let GroupRule {
+        generic_params: None,
+        is_group_choice_alternate: false,
+        entry: GroupEntry::InlineGroup {
+          occur: None, group, ..
+        },
+        ..
+      } = rule.as_ref()
+      {
+        if is_candidate(group) {
+          existing
+            .entry(group_key(group))
+            .or_insert_with(|| rule.name.to_string());
+        }
+      }
+    }
+  }
+
+  existing
+}
+
+fn unique_name(cddl: &CDDL, base: String) -> String {
+  let mut name = base.clone();
+  let mut suffix = 2;
+  while cddl.rules.iter().any(|r| r.name() == name) {
+    name = format!("{}-{}", base, suffix);
+    suffix += 1;
+  }
+
+  name
+}
+
+fn identifier<'a>(name: &'a str) -> Identifier<'a> {
+  Identifier {
+    ident: name.into(),
+    socket: None,
+    #[cfg(feature = "ast-span")]
+    span: Span::default(),
+  }
+}
+
+// Group consisting of a reference to the named group
+fn group_reference<'a>(name: &'a str) -> Group<'a> {
+  Group {
+    group_choices: vec![GroupChoice::new(vec![GroupEntry::TypeGroupname {
+      ge: TypeGroupnameEntry {
+        occur: None,
+        name: identifier(name),
+        generic_args: None,
+      },
+      #[cfg(feature = "ast-span")]
+      span: Span::default(),
+      #[cfg(feature = "ast-comments")]
+      leading_comments: None,
+      #[cfg(feature = "ast-comments")]
+      trailing_comments: None,
+    }])],
+    #[cfg(feature = "ast-span")]
+    span: Span::default(),
+  }
+}
+
+fn group_rule<'a>(name: &'a str, group: Group<'a>) -> Rule<'a> {
+  Rule::Group {
+    rule: Box::new(GroupRule {
+      name: identifier(name),
+      generic_params: None,
+      is_group_choice_alternate: false,
+      entry: GroupEntry::InlineGroup {
+        occur: None,
+        group,
+        #[cfg(feature = "ast-span")]
+        span: Span::default(),
+        #[cfg(feature = "ast-comments")]
+        comments_before_group: None,
+        #[cfg(feature = "ast-comments")]
+        comments_after_group: None,
+      },
+      #[cfg(feature = "ast-comments")]
+      comments_before_assigng: None,
+      #[cfg(feature = "ast-comments")]
+      comments_after_assigng: None,
+    }),
+    #[cfg(feature = "ast-span")]
+    span: Span::default(),
+    #[cfg(feature = "ast-comments")]
+    comments_after_rule: None,
+  }
+}
+
+// Callback for each inline group, given the name of its rule and the member
+// key of the entry the group is the value of, if any. The groups of a group
+// are skipped if it returns true
+type GroupFn<'c, 'a> = dyn FnMut(&mut Group<'a>, &str, Option<&str>) -> bool + 'c;
+
+// Walks the inline groups of the non-generic rules of a document. The groups
+// defining group rules are not inline and only their groups are walked
+fn walk_groups<'a>(cddl: &mut CDDL<'a>, f: &mut GroupFn<'_, 'a>) {
+  for rule in cddl.rules.iter_mut() {
+    let name = rule.name();
+    match rule {
+      Rule::Type { rule, .. } if rule.generic_params.is_none() => type_(&mut rule.value, &name, f),
+      Rule::Group { rule, .. } if rule.generic_params.is_none() => match &mut rule.entry {
+        GroupEntry::InlineGroup { group, .. } => group_entries(group, &name, f),
+        entry => group_entry(entry, &name, f),
+      },
+      _ => {}
+    }
+  }
+}
+
+fn type_<'a>(t: &mut Type<'a>, rule: &str, f: &mut GroupFn<'_, 'a>) {
+  for tc in t.type_choices.iter_mut() {
+    type1(&mut tc.type1, rule, None, f);
+  }
+}
+
+fn type1<'a>(t1: &mut Type1<'a>, rule: &str, key: Option<&str>, f: &mut GroupFn<'_, 'a>) {
+  type2(&mut t1.type2, rule, key, f);
+  if let Some(o) = &mut t1.operator {
+    type2(&mut o.type2, rule, None, f);
+  }
+}
+
+fn type2<'a>(t2: &mut Type2<'a>, rule: &str, key: Option<&str>, f: &mut GroupFn<'_, 'a>) {
+  match t2 {
+    Type2::Typename {
+      generic_args: Some(ga),
+      ..
+    }
+    | Type2::ChoiceFromGroup {
+      generic_args: Some(ga),
+      ..
+    } => {
+      for arg in ga.args.iter_mut() {
+        type1(&mut arg.arg, rule, None, f);
+      }
+    }
+    Type2::ParenthesizedType { pt: t, .. } | Type2::TaggedData { t, .. } => type_(t, rule, f),
+    Type2::Map { group, .. }
+    | Type2::Array { group, .. }
+    | Type2::ChoiceFromInlineGroup { group, .. } => inline_group(group, rule, key, f),
+    _ => {}
+  }
+}
+
+fn inline_group<'a>(g: &mut Group<'a>, rule: &str, key: Option<&str>, f: &mut GroupFn<'_, 'a>) {
+  if !f(g, rule, key) {
+    group_entries(g, rule, f);
+  }
+}
+
+fn group_entries<'a>(g: &mut Group<'a>, rule: &str, f: &mut GroupFn<'_, 'a>) {
+  for gc in g.group_choices.iter_mut() {
+    for (ge, _) in gc.group_entries.iter_mut() {
+      group_entry(ge, rule, f);
+    }
+  }
+}
+
+fn group_entry<'a>(entry: &mut GroupEntry<'a>, rule: &str, f: &mut GroupFn<'_, 'a>) {
+  match entry {
+    GroupEntry::ValueMemberKey { ge, .. } => {
+      match &mut ge.member_key {
+        Some(MemberKey::Type1 { t1, .. }) => type1(t1, rule, None, f),
+        Some(MemberKey::NonMemberKey {
+          non_member_key: NonMemberKey::Type(t),
+          ..
+        }) => type_(t, rule, f),
+        _ => {}
+      }
+
+      // Groups that are the only value of an entry are named after its key
+      let key = match &ge.member_key {
+        Some(MemberKey::Bareword { ident, .. }) => Some(ident.ident.as_ref()),
+        Some(MemberKey::Value {
+          value: crate::token::Value::TEXT(text),
+          ..
+        }) => Some(text.as_ref()),
+        _ => None,
+      };
+      match &mut ge.entry_type.type_choices[..] {
+        [tc] => type1(&mut tc.type1, rule, key, f),
+        _ => type_(&mut ge.entry_type, rule, f),
+      }
+    }
+    GroupEntry::TypeGroupname { ge, .. } => {
+      if let Some(ga) = &mut ge.generic_args {
+        for arg in ga.args.iter_mut() {
+          type1(&mut arg.arg, rule, None, f);
+        }
+      }
+    }
+    GroupEntry::InlineGroup { group, .. } => inline_group(group, rule, None, f),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::cddl_from_str;
+  use indoc::indoc;
+
+  #[test]
+  fn verify_repeated_groups() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let input = indoc!(
+      r#"
+        request = {
+          id: uint,
+          origin: { host: tstr, port: uint }, ; where the request came from
+          ? route: [* (hop: tstr, ttl: uint)],
+        }
+        response = {
+          id: uint,
+          origin: {
+            host: tstr,
+            port: uint,
+          },
+          body: bstr,
+        }
+        hop = (hop: tstr, ttl: uint)
+        redirect = [* (hop: tstr, ttl: uint)]
+        wrapper<t> = { host: tstr, port: t }
+        single = { id: uint } / [id: uint]
+      "#
+    );
+
+    let cddl = cddl_from_str(input, true)?;
+    let repeated = cddl.repeated_groups();
+
+    // Groups are compared regardless of comments and formatting, and groups
+    // already defined by a rule are repeated even if they appear only once
+    assert_eq!(
+      repeated
+        .iter()
+        .map(|r| (
+          r.group.as_str(),
+          r.rules.clone(),
+          r.existing_rule.as_deref(),
+          r.suggested_name.as_str()
+        ))
+        .collect::<Vec<_>>(),
+      vec![
+        (
+          "host:tstr,port:uint",
+          vec!["request".to_string(), "response".to_string()],
+          None,
+          "origin"
+        ),
+        (
+          "hop:tstr,ttl:uint",
+          vec!["request".to_string(), "redirect".to_string()],
+          Some("hop"),
+          "hop"
+        ),
+      ]
+    );
+    #[cfg(feature = "ast-span")]
+    assert_eq!(
+      &input[repeated[0].spans[0].0..repeated[0].spans[0].1],
+      "host: tstr, port: uint"
+    );
+
+    // Extracting into the existing rule leaves its definition in place
+    let extracted = cddl.extract_group(&repeated[1], "hop");
+    assert_eq!(extracted.rules.len(), cddl.rules.len());
+    let output = extracted.to_string();
+    assert_eq!(
+      crate::diff::normalize(&output).matches("[*(hop)]").count(),
+      2
+    );
+    let extracted = cddl_from_str(&output, true)?;
+    let remaining = extracted.repeated_groups();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].group, repeated[0].group);
+
+    let extracted = cddl.extract_group(&repeated[0], "origin");
+    let output = extracted.to_string();
+    assert_eq!(
+      extracted
+        .rules
+        .last()
+        .map(|r| crate::diff::normalize(&r.to_string())),
+      Some("origin=(host:tstr,port:uint)".to_string())
+    );
+    let extracted = cddl_from_str(&output, true)?;
+    assert_eq!(extracted.repeated_groups().len(), 1);
+
+    // Suggested names don't clash with existing rules
+    let cddl = cddl_from_str(
+      "a = [{ b: int, c: int }, { b: int, c: int }]\na-group = int",
+      true,
+    )?;
+    assert_eq!(cddl.repeated_groups()[0].suggested_name, "a-group-2");
+
+    Ok(())
+  }
+}
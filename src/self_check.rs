@@ -0,0 +1,292 @@
+#![cfg(feature = "std")]
+#![cfg(feature = "json")]
+#![cfg(not(feature = "lsp"))]
+#![cfg(not(target_arch = "wasm32"))]
+
+use crate::{
+  annotations::RuleComments,
+  ast::*,
+  cddl_from_str,
+  generator::{self, Generator, Target},
+  parser,
+  validator::{
+    control::{plus_operation, plus_operation_with_overflow},
+    json::JSONValidator,
+    OverflowBehavior, Validator,
+  },
+  visitor::{self, Visitor},
+};
+
+use std::{collections::BTreeMap, convert::Infallible, fmt};
+
+/// Example instances (as JSON text) attached to rules via `;@example`
+/// annotations, keyed by rule name
+pub type RuleExamples = BTreeMap<String, Vec<String>>;
+
+/// Inconsistency found by a self check of a CDDL document
+#[derive(Debug, Clone, PartialEq)]
+pub enum Finding {
+  /// Example attached to a rule doesn't conform to the rule
+  InvalidExample {
+    /// Rule name
+    rule: String,
+    /// Example
+    example: String,
+    /// Reason the example doesn't conform to the rule
+    reason: String,
+  },
+  /// No value conforms to a rule
+  Unsatisfiable {
+    /// Rule name
+    rule: String,
+    /// Reason no value conforms to the rule
+    reason: String,
+  },
+  /// Integer arithmetic of a control operator (e.g. `.plus`) in a rule
+  /// overflows the integer type of its target. Validation reports an error
+  /// unless the validator is configured to saturate or wrap
+  Overflow {
+    /// Rule name
+    rule: String,
+    /// Reason the arithmetic overflows
+    reason: String,
+  },
+}
+
+impl fmt::Display for Finding {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Finding::InvalidExample {
+        rule,
+        example,
+        reason,
+      } => write!(
+        f,
+        "example {} of rule {} is invalid: {}",
+        example,
+        rule,
+        reason.trim_end()
+      ),
+      Finding::Unsatisfiable { rule, reason } => {
+        write!(f, "rule {} is unsatisfiable: {}", rule, reason)
+      }
+      Finding::Overflow { rule, reason } => write!(f, "rule {}: {}", rule, reason),
+    }
+  }
+}
+
+/// Collect the example instances attached to rules via `;@example` annotations
+/// in a given CDDL document string. Each annotation holds a single example
+/// given as JSON text
+///
+/// ```cddl
+/// ;@example { "name": "Joe", "age": 42 }
+/// person = { name: tstr, age: uint }
+/// ```
+pub fn rule_examples_from_str(cddl: &str) -> std::result::Result<RuleExamples, parser::Error> {
+  let rule_comments = RuleComments::from_cddl(cddl).map_err(parser::Error::LEXER)?;
+
+  let mut rule_examples = RuleExamples::new();
+  for (rule, _) in rule_comments.iter() {
+    let examples = rule_comments
+      .annotations(rule)
+      .into_iter()
+      .filter(|a| a.name == "example" && !a.args.is_empty())
+      .map(|a| a.args)
+      .collect::<Vec<_>>();
+
+    if !examples.is_empty() {
+      rule_examples.insert(rule.to_string(), examples);
+    }
+  }
+
+  Ok(rule_examples)
+}
+
+/// Self check a CDDL document string, validating the examples embedded via
+/// `;@example` annotations against their rules and checking that every rule is
+/// satisfiable. See [`CDDL::self_check`]
+pub fn self_check_from_str(cddl: &str) -> std::result::Result<Vec<Finding>, parser::Error> {
+  let examples = rule_examples_from_str(cddl)?;
+  let cddl = cddl_from_str(cddl, false)?;
+
+  Ok(cddl.self_check(&examples))
+}
+
+impl<'a> CDDL<'a> {
+  /// Check the document for internal inconsistencies, returning all findings.
+  /// The given examples (e.g. collected from `;@example` annotations with
+  /// [`rule_examples_from_str`]) are validated against their rules, each
+  /// type rule without generic parameters is checked to be satisfied by at
+  /// least one value and the arithmetic of `.plus` controls is checked for
+  /// integer overflow. A document with findings is unlikely to validate what
+  /// its authors intended
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use cddl::self_check::{self_check_from_str, Finding};
+  ///
+  /// let findings = self_check_from_str(
+  ///   r#"
+  ///     ;@example { "name": "Joe", "age": "unknown" }
+  ///     person = { name: tstr, age: uint }
+  ///     score = 10..1
+  ///   "#,
+  /// )
+  /// .unwrap();
+  ///
+  /// assert_eq!(findings.len(), 2);
+  /// assert!(matches!(&findings[0], Finding::InvalidExample { rule, .. } if rule == "person"));
+  /// assert!(matches!(&findings[1], Finding::Unsatisfiable { rule, .. } if rule == "score"));
+  /// ```
+  pub fn self_check(&self, examples: &RuleExamples) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for (rule, examples) in examples.iter() {
+      for example in examples.iter() {
+        if let Err(reason) = self.validate_example(rule, example) {
+          findings.push(Finding::InvalidExample {
+            rule: rule.clone(),
+            example: example.clone(),
+            reason,
+          });
+        }
+      }
+    }
+
+    for rule in self.rules.iter() {
+      let name = match rule {
+        Rule::Type { rule, .. } if rule.generic_params.is_none() => rule.name.to_string(),
+        _ => continue,
+      };
+
+      // Values are generated for CBOR, which can represent any value
+      let mut generator = Generator::new(self, Target::Cbor, 0);
+      match generator.generate(&name) {
+        Err(e @ generator::Error::Unsatisfiable(_)) => findings.push(Finding::Unsatisfiable {
+          rule: name,
+          reason: e.to_string(),
+        }),
+        // Other generation errors (e.g. unsupported controls) are limitations
+        // of the generator rather than of the document
+        _ => continue,
+      }
+    }
+
+    for rule in self.rules.iter() {
+      let mut overflows = Overflows {
+        cddl: self,
+        rule: rule.name(),
+        findings: &mut findings,
+      };
+      // Finding overflows is infallible
+      let _ = overflows.visit_rule(rule);
+    }
+
+    findings
+  }
+
+  fn validate_example(&self, rule: &str, example: &str) -> std::result::Result<(), String> {
+    let json = serde_json::from_str::<serde_json::Value>(example).map_err(|e| e.to_string())?;
+
+    #[cfg(feature = "additional-controls")]
+    let mut jv = JSONValidator::new(self, json, None);
+    #[cfg(not(feature = "additional-controls"))]
+    let mut jv = JSONValidator::new(self, json);
+
+    jv.set_root_rule(rule, None);
+    jv.validate().map_err(|e| e.to_string())
+  }
+}
+
+// Collects the `.plus` controls of a rule whose integer arithmetic overflows
+struct Overflows<'a, 'b> {
+  cddl: &'b CDDL<'a>,
+  rule: String,
+  findings: &'b mut Vec<Finding>,
+}
+
+impl<'a, 'b> Visitor<'a, Infallible> for Overflows<'a, 'b> {
+  fn visit_control_operator(
+    &mut self,
+    target: &Type2<'a>,
+    ctrl: &str,
+    controller: &Type2<'a>,
+  ) -> visitor::Result<Infallible> {
+    if ctrl == ".plus" {
+      if let Err(reason) = plus_operation(self.cddl, target, controller) {
+        // Other errors are reported during validation. Nested controls
+        // aren't walked, so an overflow is only reported once
+        if plus_operation_with_overflow(self.cddl, target, controller, OverflowBehavior::Wrapping)
+          .is_ok()
+        {
+          self.findings.push(Finding::Overflow {
+            rule: self.rule.clone(),
+            reason,
+          });
+          return Ok(());
+        }
+      }
+    }
+
+    visitor::walk_control_operator(self, target, controller)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use indoc::indoc;
+
+  #[test]
+  fn verify_self_check() -> std::result::Result<(), parser::Error> {
+    let input = indoc!(
+      r#"
+        ;@example { "name": "Joe", "age": 42 }
+        ;@example { "name": "Jane" }
+        person = { name: tstr, age: age }
+
+        age = uint ;@example 42
+        ;@example "adult"
+        category = "child" / "teen"
+
+        record = { id: uint, score: score }
+        score = 10..1
+
+        max = 18446744073709551615
+        next = max .plus 1
+      "#
+    );
+
+    let findings = self_check_from_str(input)?;
+
+    assert_eq!(
+      findings
+        .iter()
+        .map(|f| match f {
+          Finding::InvalidExample { rule, example, .. } => format!("{} {}", rule, example),
+          Finding::Unsatisfiable { rule, .. } | Finding::Overflow { rule, .. } => rule.clone(),
+        })
+        .collect::<Vec<_>>(),
+      vec![
+        r#"category "adult""#,
+        r#"person { "name": "Jane" }"#,
+        "record",
+        "score",
+        "next",
+      ]
+    );
+    assert_eq!(
+      findings[3].to_string(),
+      "rule score is unsatisfiable: no value satisfies range 10..1"
+    );
+    assert_eq!(
+      findings[4].to_string(),
+      "rule next: overflow in .plus operation, 18446744073709551615 + 1 is out of the range of uint"
+    );
+
+    Ok(())
+  }
+}
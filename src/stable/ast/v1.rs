@@ -0,0 +1,580 @@
+use crate::{ast, token};
+
+/// CDDL document
+///
+/// # Example
+///
+/// ```
+/// use cddl::stable::{
+///   self,
+///   ast::v1::{GroupEntry, RuleKind, Type2},
+/// };
+///
+/// let document = stable::parse("message = { id: uint }")?;
+/// let rule = &document.rules[0];
+/// assert_eq!(rule.name, "message");
+///
+/// match &rule.kind {
+///   RuleKind::Type(t) => match &t.choices[0].type2 {
+///     Type2::Map(group) => {
+///       assert!(matches!(group.choices[0].entries[0], GroupEntry::Value { .. }))
+///     }
+///     _ => unreachable!(),
+///   },
+///   _ => unreachable!(),
+/// }
+/// # Ok::<(), stable::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Document {
+  /// Rules in order of definition
+  pub rules: Vec<Rule>,
+}
+
+/// Type or group rule
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Rule {
+  /// Name of the rule
+  pub name: String,
+  /// Names of the generic parameters of the rule
+  pub generic_params: Vec<String>,
+  /// Whether the rule extends a rule of the same name with additional choices,
+  /// i.e. is defined with `/=` or `//=`
+  pub is_choice_alternate: bool,
+  /// Definition of the rule
+  pub kind: RuleKind,
+}
+
+/// Definition of a rule
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum RuleKind {
+  /// Type rule
+  Type(Type),
+  /// Group rule
+  Group(GroupEntry),
+}
+
+/// Type choices
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Type {
+  /// Choices of the type, in order
+  pub choices: Vec<Type1>,
+}
+
+/// Type with an optional range or control operator
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Type1 {
+  /// Type, or left-hand side of the operator
+  pub type2: Type2,
+  /// Operator along with its right-hand side
+  pub operator: Option<(Operator, Type2)>,
+}
+
+/// Range or control operator
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Operator {
+  /// Range operator, i.e. `..` (inclusive) or `...` (exclusive)
+  Range {
+    /// Whether the upper bound is included in the range
+    inclusive: bool,
+  },
+  /// Control operator, including its leading dot, e.g. `.size`
+  Control(String),
+}
+
+/// Type expression
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Type2 {
+  /// Literal value
+  Value(Value),
+  /// Reference to a type rule or to a prelude type
+  Typename {
+    /// Name of the type
+    name: String,
+    /// Generic arguments
+    generic_args: Vec<Type1>,
+  },
+  /// Parenthesized type
+  Parenthesized(Type),
+  /// Map
+  Map(Group),
+  /// Array
+  Array(Group),
+  /// Unwrapped type, i.e. `~name`
+  Unwrap {
+    /// Name of the unwrapped type
+    name: String,
+    /// Generic arguments
+    generic_args: Vec<Type1>,
+  },
+  /// Choice from an inline group, i.e. `&( ... )`
+  ChoiceFromInlineGroup(Group),
+  /// Choice from a group rule, i.e. `&name`
+  ChoiceFromGroup {
+    /// Name of the group
+    name: String,
+    /// Generic arguments
+    generic_args: Vec<Type1>,
+  },
+  /// Tagged data item, i.e. `#6.tag(type)`
+  Tagged {
+    /// Tag number, if any
+    tag: Option<u64>,
+    /// Type of the tag content
+    t: Type,
+  },
+  /// Data item of a major type, i.e. `#major.constraint`
+  MajorType {
+    /// Major type
+    major: u8,
+    /// Additional information constraint, if any
+    constraint: Option<u64>,
+  },
+  /// Any data item, i.e. `#`
+  Any,
+}
+
+/// Literal value
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Value {
+  /// Negative integer
+  Int(i64),
+  /// Unsigned integer
+  Uint(u64),
+  /// Floating point number
+  Float(f64),
+  /// Text string
+  Text(String),
+  /// Unprefixed byte string, e.g. `'abc'`
+  Bytes(String),
+  /// Base16 encoded byte string, as written between the quotes of `h'...'`
+  Base16Bytes(String),
+  /// Base64 encoded byte string, as written between the quotes of `b64'...'`
+  Base64Bytes(String),
+}
+
+/// Group choices
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Group {
+  /// Choices of the group, in order
+  pub choices: Vec<GroupChoice>,
+}
+
+/// Entries of a group choice
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct GroupChoice {
+  /// Entries of the choice, in order
+  pub entries: Vec<GroupEntry>,
+}
+
+/// Group entry
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum GroupEntry {
+  /// Entry of a type, with an optional member key
+  Value {
+    /// Occurrence indicator
+    occurrence: Option<Occurrence>,
+    /// Member key
+    key: Option<MemberKey>,
+    /// Type of the entry
+    value: Type,
+  },
+  /// Reference to a type or group rule
+  Groupname {
+    /// Occurrence indicator
+    occurrence: Option<Occurrence>,
+    /// Name of the rule
+    name: String,
+    /// Generic arguments
+    generic_args: Vec<Type1>,
+  },
+  /// Parenthesized group
+  Inline {
+    /// Occurrence indicator
+    occurrence: Option<Occurrence>,
+    /// Inlined group
+    group: Group,
+  },
+}
+
+/// Occurrence indicator
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Occurrence {
+  /// `?`
+  Optional,
+  /// `*`
+  ZeroOrMore,
+  /// `+`
+  OneOrMore,
+  /// `n*m`, where either bound may be omitted
+  Exact {
+    /// Lower bound
+    lower: Option<u64>,
+    /// Upper bound
+    upper: Option<u64>,
+  },
+}
+
+/// Member key
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum MemberKey {
+  /// Type key, i.e. `type => ` or `type ^ =>` when cut
+  Type {
+    /// Type of the key
+    key: Type1,
+    /// Whether the key is cut
+    cut: bool,
+  },
+  /// Bareword key, i.e. `name:`
+  Bareword(String),
+  /// Value key, e.g. `1:` or `"name":`
+  Value(Value),
+}
+
+impl From<&ast::CDDL<'_>> for Document {
+  fn from(cddl: &ast::CDDL) -> Self {
+    Document {
+      rules: cddl.rules.iter().map(Rule::from).collect(),
+    }
+  }
+}
+
+impl From<&ast::Rule<'_>> for Rule {
+  fn from(rule: &ast::Rule) -> Self {
+    let (generic_params, is_choice_alternate, kind) = match rule {
+      ast::Rule::Type { rule, .. } => (
+        &rule.generic_params,
+        rule.is_type_choice_alternate,
+        RuleKind::Type(Type::from(&rule.value)),
+      ),
+      ast::Rule::Group { rule, .. } => (
+        &rule.generic_params,
+        rule.is_group_choice_alternate,
+        RuleKind::Group(GroupEntry::from(&rule.entry)),
+      ),
+    };
+
+    Rule {
+      name: rule.name(),
+      generic_params: generic_params
+        .iter()
+        .flat_map(|gp| gp.params.iter().map(|p| p.param.ident.to_string()))
+        .collect(),
+      is_choice_alternate,
+      kind,
+    }
+  }
+}
+
+impl From<&ast::Type<'_>> for Type {
+  fn from(t: &ast::Type) -> Self {
+    Type {
+      choices: t
+        .type_choices
+        .iter()
+        .map(|tc| Type1::from(&tc.type1))
+        .collect(),
+    }
+  }
+}
+
+impl From<&ast::Type1<'_>> for Type1 {
+  fn from(t1: &ast::Type1) -> Self {
+    Type1 {
+      type2: Type2::from(&t1.type2),
+      operator: t1.operator.as_ref().map(|o| {
+        let operator = match &o.operator {
+          ast::RangeCtlOp::RangeOp { is_inclusive, .. } => Operator::Range {
+            inclusive: *is_inclusive,
+          },
+          ast::RangeCtlOp::CtlOp { ctrl, .. } => Operator::Control(ctrl.to_string()),
+        };
+
+        (operator, Type2::from(&o.type2))
+      }),
+    }
+  }
+}
+
+impl From<&ast::Type2<'_>> for Type2 {
+  fn from(t2: &ast::Type2) -> Self {
+    match t2 {
+      ast::Type2::IntValue { value, .. } => Type2::Value(Value::Int(*value as i64)),
+      ast::Type2::UintValue { value, .. } => Type2::Value(Value::Uint(*value as u64)),
+      ast::Type2::FloatValue { value, .. } => Type2::Value(Value::Float(*value)),
+      ast::Type2::TextValue { value, .. } => Type2::Value(Value::Text(value.to_string())),
+      ast::Type2::UTF8ByteString { value, .. } => Type2::Value(Value::Bytes(text(value))),
+      ast::Type2::B16ByteString { value, .. } => Type2::Value(Value::Base16Bytes(text(value))),
+      ast::Type2::B64ByteString { value, .. } => Type2::Value(Value::Base64Bytes(text(value))),
+      ast::Type2::Typename {
+        ident,
+        generic_args,
+        ..
+      } => Type2::Typename {
+        name: ident.ident.to_string(),
+        generic_args: args(generic_args),
+      },
+      ast::Type2::ParenthesizedType { pt, .. } => Type2::Parenthesized(Type::from(pt)),
+      ast::Type2::Map { group, .. } => Type2::Map(Group::from(group)),
+      ast::Type2::Array { group, .. } => Type2::Array(Group::from(group)),
+      ast::Type2::Unwrap {
+        ident,
+        generic_args,
+        ..
+      } => Type2::Unwrap {
+        name: ident.ident.to_string(),
+        generic_args: args(generic_args),
+      },
+      ast::Type2::ChoiceFromInlineGroup { group, .. } => {
+        Type2::ChoiceFromInlineGroup(Group::from(group))
+      }
+      ast::Type2::ChoiceFromGroup {
+        ident,
+        generic_args,
+        ..
+      } => Type2::ChoiceFromGroup {
+        name: ident.ident.to_string(),
+        generic_args: args(generic_args),
+      },
+      // Tag numbers given as types, e.g. `#6.<uint>(t)`, aren't represented by
+      // this version of the view, so such tags read as any tag
+      ast::Type2::TaggedData { tag, t, .. } => Type2::Tagged {
+        tag: tag.map(|tag| tag as u64),
+        t: Type::from(t),
+      },
+      ast::Type2::DataMajorType { mt, constraint, .. } => Type2::MajorType {
+        major: *mt,
+        constraint: constraint.map(|c| c as u64),
+      },
+      ast::Type2::Any { .. } => Type2::Any,
+    }
+  }
+}
+
+impl From<&token::Value<'_>> for Value {
+  fn from(value: &token::Value) -> Self {
+    match value {
+      token::Value::INT(value) => Value::Int(*value as i64),
+      token::Value::UINT(value) => Value::Uint(*value as u64),
+      token::Value::FLOAT(value) => Value::Float(*value),
+      token::Value::TEXT(value) => Value::Text(value.to_string()),
+      token::Value::BYTE(token::ByteValue::UTF8(value)) => Value::Bytes(text(value)),
+      token::Value::BYTE(token::ByteValue::B16(value)) => Value::Base16Bytes(text(value)),
+      token::Value::BYTE(token::ByteValue::B64(value)) => Value::Base64Bytes(text(value)),
+    }
+  }
+}
+
+impl From<&ast::Group<'_>> for Group {
+  fn from(group: &ast::Group) -> Self {
+    Group {
+      choices: group
+        .group_choices
+        .iter()
+        .map(|gc| GroupChoice {
+          entries: gc
+            .group_entries
+            .iter()
+            .map(|(ge, _)| GroupEntry::from(ge))
+            .collect(),
+        })
+        .collect(),
+    }
+  }
+}
+
+impl From<&ast::GroupEntry<'_>> for GroupEntry {
+  fn from(entry: &ast::GroupEntry) -> Self {
+    match entry {
+      ast::GroupEntry::ValueMemberKey { ge, .. } => GroupEntry::Value {
+        occurrence: ge.occur.as_ref().map(|o| Occurrence::from(&o.occur)),
+        key: ge.member_key.as_ref().and_then(member_key),
+        value: Type::from(&ge.entry_type),
+      },
+      ast::GroupEntry::TypeGroupname { ge, .. } => GroupEntry::Groupname {
+        occurrence: ge.occur.as_ref().map(|o| Occurrence::from(&o.occur)),
+        name: ge.name.ident.to_string(),
+        generic_args: args(&ge.generic_args),
+      },
+      ast::GroupEntry::InlineGroup { occur, group, .. } => GroupEntry::Inline {
+        occurrence: occur.as_ref().map(|o| Occurrence::from(&o.occur)),
+        group: Group::from(group),
+      },
+    }
+  }
+}
+
+impl From<&ast::Occur> for Occurrence {
+  fn from(occur: &ast::Occur) -> Self {
+    match occur {
+      ast::Occur::Optional { .. } => Occurrence::Optional,
+      ast::Occur::ZeroOrMore { .. } => Occurrence::ZeroOrMore,
+      ast::Occur::OneOrMore { .. } => Occurrence::OneOrMore,
+      ast::Occur::Exact { lower, upper, .. } => Occurrence::Exact {
+        lower: lower.map(|l| l as u64),
+        upper: upper.map(|u| u as u64),
+      },
+    }
+  }
+}
+
+// Member keys only remain unresolved non-member keys while parsing, so they
+// never appear in parsed documents
+fn member_key(key: &ast::MemberKey) -> Option<MemberKey> {
+  match key {
+    ast::MemberKey::Type1 { t1, is_cut, .. } => Some(MemberKey::Type {
+      key: Type1::from(t1.as_ref()),
+      cut: *is_cut,
+    }),
+    ast::MemberKey::Bareword { ident, .. } => Some(MemberKey::Bareword(ident.ident.to_string())),
+    ast::MemberKey::Value { value, .. } => Some(MemberKey::Value(Value::from(value))),
+    ast::MemberKey::NonMemberKey { .. } => None,
+  }
+}
+
+fn args(generic_args: &Option<ast::GenericArgs>) -> Vec<Type1> {
+  generic_args
+    .iter()
+    .flat_map(|ga| ga.args.iter().map(|a| Type1::from(a.arg.as_ref())))
+    .collect()
+}
+
+fn text(value: &[u8]) -> String {
+  String::from_utf8_lossy(value).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cddl_from_str;
+
+  use indoc::indoc;
+
+  #[test]
+  fn verify_v1_document() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = indoc!(
+      r#"
+        message<t> = [id: uint .size 8, ? body: t, * h'01' ^ => 1..10]
+        kind = "a" / #6.32(tstr)
+        kind /= &(b: 1, c: 2)
+        header = (+ (id: int), ~kind)
+      "#
+    );
+    let document = Document::from(&cddl_from_str(cddl, true)?);
+
+    assert_eq!(
+      document.rules[0],
+      Rule {
+        name: "message".to_string(),
+        generic_params: vec!["t".to_string()],
+        is_choice_alternate: false,
+        kind: RuleKind::Type(Type {
+          choices: vec![Type1 {
+            type2: Type2::Array(Group {
+              choices: vec![GroupChoice {
+                entries: vec![
+                  GroupEntry::Value {
+                    occurrence: None,
+                    key: Some(MemberKey::Bareword("id".to_string())),
+                    value: Type {
+                      choices: vec![Type1 {
+                        type2: Type2::Typename {
+                          name: "uint".to_string(),
+                          generic_args: vec![],
+                        },
+                        operator: Some((
+                          Operator::Control(".size".to_string()),
+                          Type2::Value(Value::Uint(8)),
+                        )),
+                      }],
+                    },
+                  },
+                  GroupEntry::Value {
+                    occurrence: Some(Occurrence::Optional),
+                    key: Some(MemberKey::Bareword("body".to_string())),
+                    value: Type {
+                      choices: vec![Type1 {
+                        type2: Type2::Typename {
+                          name: "t".to_string(),
+                          generic_args: vec![],
+                        },
+                        operator: None,
+                      }],
+                    },
+                  },
+                  GroupEntry::Value {
+                    occurrence: Some(Occurrence::ZeroOrMore),
+                    key: Some(MemberKey::Type {
+                      key: Type1 {
+                        type2: Type2::Value(Value::Base16Bytes("01".to_string())),
+                        operator: None,
+                      },
+                      cut: true,
+                    }),
+                    value: Type {
+                      choices: vec![Type1 {
+                        type2: Type2::Value(Value::Uint(1)),
+                        operator: Some((
+                          Operator::Range { inclusive: true },
+                          Type2::Value(Value::Uint(10)),
+                        )),
+                      }],
+                    },
+                  },
+                ],
+              }],
+            }),
+            operator: None,
+          }],
+        }),
+      }
+    );
+
+    match &document.rules[1].kind {
+      RuleKind::Type(t) => {
+        assert_eq!(
+          t.choices[0].type2,
+          Type2::Value(Value::Text("a".to_string()))
+        );
+        assert!(matches!(
+          t.choices[1].type2,
+          Type2::Tagged { tag: Some(32), .. }
+        ));
+      }
+      kind => panic!("expected type rule, got {:?}", kind),
+    }
+
+    assert!(document.rules[2].is_choice_alternate);
+
+    match &document.rules[3].kind {
+      RuleKind::Group(GroupEntry::Inline {
+        occurrence: None,
+        group,
+      }) => {
+        assert!(matches!(
+          group.choices[0].entries[0],
+          GroupEntry::Inline {
+            occurrence: Some(Occurrence::OneOrMore),
+            ..
+          }
+        ));
+      }
+      kind => panic!("expected group rule, got {:?}", kind),
+    }
+
+    Ok(())
+  }
+}
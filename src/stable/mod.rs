@@ -0,0 +1,239 @@
+#![cfg(feature = "std")]
+
+#[cfg(not(feature = "lsp"))]
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "cbor")]
+use crate::validator::cbor;
+#[cfg(not(feature = "lsp"))]
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(any(feature = "json", feature = "cbor"))]
+use crate::validator::compiled::CompiledCddl;
+#[cfg(not(feature = "lsp"))]
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "json")]
+use crate::validator::json;
+
+use std::fmt;
+
+/// Versioned views of the AST. Each version is frozen once released, so
+/// changes to the internal AST are absorbed by its conversion rather than
+/// breaking downstream code
+pub mod ast {
+  /// First version of the AST view
+  pub mod v1;
+}
+
+/// Error returned by the stable entry points
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+  /// CDDL document is invalid, or can't be used for validation
+  Schema(String),
+  /// Data can't be decoded
+  Decoding(String),
+  /// Data doesn't conform to the CDDL document
+  Validation(Vec<ValidationError>),
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Error::Schema(error) => write!(f, "invalid CDDL document: {}", error),
+      Error::Decoding(error) => write!(f, "error decoding data: {}", error),
+      Error::Validation(errors) => {
+        let mut error_str = String::new();
+        for e in errors.iter() {
+          error_str.push_str(&format!("{}\n", e));
+        }
+        write!(f, "{}", error_str)
+      }
+    }
+  }
+}
+
+impl std::error::Error for Error {}
+
+/// Validation error
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct ValidationError {
+  /// Error message
+  pub reason: String,
+  /// Name of the innermost rule being validated when the error occurred
+  pub rule: String,
+  /// Location in the data where the error occurred, as a JSON pointer for
+  /// JSON and as a CBOR location for CBOR
+  pub location: String,
+  /// Byte range of the offending CBOR data item, if known
+  pub byte_range: Option<(usize, usize)>,
+}
+
+impl fmt::Display for ValidationError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "error validating at location {}: {}",
+      self.location, self.reason
+    )
+  }
+}
+
+/// Parse a CDDL document string into the first version of the AST view
+///
+/// # Example
+///
+/// ```
+/// let document = cddl::stable::parse("id = uint")?;
+/// assert_eq!(document.rules[0].name, "id");
+/// # Ok::<(), cddl::stable::Error>(())
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub fn parse(input: &str) -> Result<ast::v1::Document, Error> {
+  let cddl = crate::cddl_from_str(input, false).map_err(|e| Error::Schema(e.to_string()))?;
+
+  Ok(ast::v1::Document::from(&cddl))
+}
+
+/// Validate a JSON document string against a CDDL document string
+#[cfg(not(feature = "lsp"))]
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "json")]
+pub fn validate_json(cddl: &str, json: &str) -> Result<(), Error> {
+  Schema::new(cddl)?.validate_json(json)
+}
+
+/// Validate a CBOR data item against a CDDL document string
+#[cfg(not(feature = "lsp"))]
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "cbor")]
+pub fn validate_cbor(cddl: &str, cbor: &[u8]) -> Result<(), Error> {
+  Schema::new(cddl)?.validate_cbor(cbor)
+}
+
+/// CDDL document compiled once for validating many data items
+///
+/// # Example
+///
+/// ```
+/// use cddl::stable::{Error, Schema};
+///
+/// let schema = Schema::new("message = { id: uint }")?;
+/// schema.validate_json(r#"{ "id": 1 }"#)?;
+///
+/// match schema.validate_json(r#"{ "id": "a" }"#) {
+///   Err(Error::Validation(errors)) => assert_eq!(errors[0].location, "/id"),
+///   result => panic!("expected validation errors, got {:?}", result),
+/// }
+/// # Ok::<(), Error>(())
+/// ```
+#[cfg(not(feature = "lsp"))]
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(any(feature = "json", feature = "cbor"))]
+pub struct Schema<'a> {
+  compiled: CompiledCddl<'a>,
+}
+
+#[cfg(not(feature = "lsp"))]
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(any(feature = "json", feature = "cbor"))]
+impl<'a> Schema<'a> {
+  /// Compile a CDDL document string
+  pub fn new(input: &'a str) -> Result<Self, Error> {
+    Ok(Schema {
+      compiled: CompiledCddl::compile(input).map_err(|e| Error::Schema(e.to_string()))?,
+    })
+  }
+
+  /// View of the compiled document. Generic rules that were instantiated
+  /// ahead of time are not included
+  pub fn document(&self) -> ast::v1::Document {
+    ast::v1::Document::from(self.compiled.cddl())
+  }
+
+  /// Validate a JSON document string against the schema
+  #[cfg(feature = "json")]
+  pub fn validate_json(&self, json: &str) -> Result<(), Error> {
+    #[cfg(feature = "additional-controls")]
+    let result = self.compiled.validate_json(json, None);
+    #[cfg(not(feature = "additional-controls"))]
+    let result = self.compiled.validate_json(json);
+
+    result.map_err(|e| match e {
+      json::Error::Validation(errors) => Error::Validation(
+        errors
+          .into_iter()
+          .map(|e| ValidationError {
+            reason: e.reason,
+            rule: e.cddl_location,
+            location: e.json_location,
+            byte_range: None,
+          })
+          .collect(),
+      ),
+      json::Error::JSONParsing(_) | json::Error::UTF8Parsing(_) => Error::Decoding(e.to_string()),
+      _ => Error::Schema(e.to_string()),
+    })
+  }
+
+  /// Validate a CBOR data item against the schema
+  #[cfg(feature = "cbor")]
+  pub fn validate_cbor(&self, cbor: &[u8]) -> Result<(), Error> {
+    #[cfg(feature = "additional-controls")]
+    let result = self.compiled.validate_cbor(cbor, None);
+    #[cfg(not(feature = "additional-controls"))]
+    let result = self.compiled.validate_cbor(cbor);
+
+    result.map_err(|e| match e {
+      cbor::Error::Validation(errors) => Error::Validation(
+        errors
+          .into_iter()
+          .map(|e| ValidationError {
+            reason: e.reason,
+            rule: e.cddl_location,
+            location: e.cbor_location,
+            byte_range: e.cbor_range,
+          })
+          .collect(),
+      ),
+      cbor::Error::CBORParsing(_) | cbor::Error::UTF8Parsing(_) => Error::Decoding(e.to_string()),
+      _ => Error::Schema(e.to_string()),
+    })
+  }
+}
+
+#[cfg(test)]
+#[cfg(not(feature = "lsp"))]
+#[cfg(feature = "json")]
+#[cfg(feature = "cbor")]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn validate_stable_entry_points() {
+    let cddl = "message = { id: uint, ? tags: [* tstr] }";
+
+    assert_eq!(validate_json(cddl, r#"{ "id": 1, "tags": ["a"] }"#), Ok(()));
+    assert!(matches!(validate_json(cddl, "{"), Err(Error::Decoding(_))));
+    assert!(matches!(
+      validate_json("message = ", "{}"),
+      Err(Error::Schema(_))
+    ));
+
+    // { "id": 1, "tags": [2] }
+    let cbor = [
+      0xa2, 0x62, 0x69, 0x64, 0x01, 0x64, 0x74, 0x61, 0x67, 0x73, 0x81, 0x02,
+    ];
+    match validate_cbor(cddl, &cbor) {
+      Err(Error::Validation(errors)) => {
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].rule, "message");
+        assert_eq!(errors[0].location, r#"/"tags"/0"#);
+        assert_eq!(errors[0].byte_range, Some((11, 12)));
+      }
+      result => panic!("expected validation errors, got {:?}", result),
+    }
+
+    let schema = Schema::new(cddl).unwrap();
+    assert_eq!(schema.document(), parse(cddl).unwrap());
+  }
+}
@@ -0,0 +1,681 @@
+#![cfg(feature = "templates")]
+
+use crate::{
+  annotations::{Annotation, RuleComments},
+  ast::*,
+  cddl_from_str, generics, parser,
+  token::{lookup_ident, Value},
+  unwrap,
+};
+
+use displaydoc::Display;
+use minijinja::Environment;
+use serde::Serialize;
+
+/// Alias for `Result` with an error of type `template::Error`
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Template rendering error
+#[derive(Debug, Display)]
+pub enum Error {
+  /// {0}
+  Parsing(parser::Error),
+  /// {0}
+  Generics(generics::Error),
+  /// {0}
+  Unwrap(unwrap::Error),
+  /// error rendering template: {0}
+  Template(String),
+}
+
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Error::Generics(error) => Some(error),
+      Error::Unwrap(error) => Some(error),
+      _ => None,
+    }
+  }
+}
+
+/// Model of a CDDL document fed to templates. Generic rules are instantiated
+/// and unwrap operators are resolved, so every rule is self-contained
+///
+/// Templates are rendered with [MiniJinja](https://docs.rs/minijinja), with
+/// the model's `rules` as the only variable. Blocks are trimmed (i.e. the first
+/// newline after a block tag and the whitespace preceding it on its line are
+/// removed) so that templates can be laid out like the code they generate. The
+/// `snake_case` and `pascal_case` filters convert rule and member names to
+/// identifiers
+///
+/// # Example
+///
+/// ```
+/// use cddl::template::Model;
+///
+/// let model = Model::from_cddl(
+///   r#"
+///     ; A user account
+///     user = { id: uint, ? display-name: tstr }
+///   "#,
+/// )
+/// .unwrap();
+///
+/// let sql = model
+///   .render(
+///     r#"
+/// {% for rule in rules %}
+/// -- {{ rule.description }}
+/// CREATE TABLE {{ rule.name | snake_case }} (
+/// {% for member in rule.members %}
+///   {{ member.key | snake_case }} {{ member.types[0].name | upper }}{% if not member.optional %} NOT NULL{% endif %}{% if not loop.last %},{% endif %}
+///
+/// {% endfor %}
+/// );
+/// {% endfor %}
+/// "#,
+///   )
+///   .unwrap();
+///
+/// assert_eq!(
+///   sql.trim(),
+///   "-- A user account\nCREATE TABLE user (\n  id UINT NOT NULL,\n  display_name TSTR\n);"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Model {
+  /// Rules, in order of definition. Rules extended with additional choices
+  /// (i.e. `/=` or `//=`) appear once, with all of their choices
+  pub rules: Vec<RuleModel>,
+}
+
+/// Model of a rule
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleModel {
+  /// Rule name
+  pub name: String,
+  /// `type` for type rules and `group` for group rules
+  pub kind: &'static str,
+  /// Text of the comments attached to the rule, excluding annotations
+  pub description: String,
+  /// Annotations attached to the rule, e.g. `;@example`
+  pub annotations: Vec<AnnotationModel>,
+  /// Definitions of the rule as CDDL text
+  pub definitions: Vec<String>,
+  /// Type choices of a type rule. Empty for group rules
+  pub types: Vec<TypeModel>,
+  /// Members of the rule, i.e. the entries of a group rule, or of the map or
+  /// array a type rule consists of. Only the first group choice is included,
+  /// see `group_choices` for all of them
+  pub members: Vec<MemberModel>,
+  /// Entries of each group choice of the rule
+  pub group_choices: Vec<Vec<MemberModel>>,
+}
+
+/// Model of an annotation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnnotationModel {
+  /// Annotation name, e.g. `example`
+  pub name: String,
+  /// Annotation arguments
+  pub args: String,
+}
+
+/// Model of a group entry
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MemberModel {
+  /// Key of the entry, i.e. the bareword or the literal text or number of a
+  /// value key. `None` for entries without a key and for entries whose key is a
+  /// type (see `key_type`)
+  pub key: Option<String>,
+  /// Type of the key of entries whose key is a type, e.g. `tstr` in
+  /// `* tstr => int`
+  pub key_type: Option<TypeModel>,
+  /// Whether or not the key is followed by a cut (`^` or `:`)
+  pub cut: bool,
+  /// Whether or not the entry may be omitted
+  pub optional: bool,
+  /// Bounds of the number of occurrences of the entry
+  pub occurrence: OccurrenceModel,
+  /// Type choices of the entry's value, or the group referenced by the entry
+  pub types: Vec<TypeModel>,
+  /// Entries of the first group choice of an inline group entry
+  pub members: Vec<MemberModel>,
+  /// Entry as CDDL text
+  pub text: String,
+}
+
+/// Model of the occurrence indicator of a group entry
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OccurrenceModel {
+  /// Minimum number of occurrences
+  pub lower: usize,
+  /// Maximum number of occurrences, or `None` if unbounded
+  pub upper: Option<usize>,
+  /// Occurrence indicator as written, e.g. `?` or `1*3`. Empty for entries
+  /// occurring exactly once
+  pub indicator: String,
+}
+
+/// Model of a type choice
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TypeModel {
+  /// `prelude` for types of the standard prelude, `reference` for references
+  /// to rules, `value` for literal values, `map`, `array`, `tagged` for tagged
+  /// data, `parenthesized`, `choice` for choices from groups (`&`) or `other`
+  pub kind: &'static str,
+  /// Name of the prelude type or referenced rule, including generic arguments
+  pub name: Option<String>,
+  /// Literal value, as a string, number or boolean
+  pub value: Option<serde_json::Value>,
+  /// Tag of tagged data
+  pub tag: Option<usize>,
+  /// Type choices enclosed by tagged data or parentheses
+  pub types: Vec<TypeModel>,
+  /// Members of a map, array or choice from an inline group. Only the first
+  /// group choice is included, see `group_choices` for all of them
+  pub members: Vec<MemberModel>,
+  /// Entries of each group choice of a map, array or choice from an inline
+  /// group
+  pub group_choices: Vec<Vec<MemberModel>>,
+  /// Range or control operator applied to the type, e.g. `..` or `.size`
+  pub operator: Option<String>,
+  /// Argument of the range or control operator, e.g. the upper bound of a
+  /// range
+  pub argument: Option<Box<TypeModel>>,
+  /// Type as CDDL text
+  pub text: String,
+}
+
+impl Model {
+  /// Build the model of a CDDL document string, including the descriptions and
+  /// annotations of its rules
+  pub fn from_cddl(input: &str) -> Result<Self> {
+    let cddl = cddl_from_str(input, false).map_err(Error::Parsing)?;
+    let comments =
+      RuleComments::from_cddl(input).map_err(|e| Error::Parsing(parser::Error::LEXER(e)))?;
+
+    let mut model = Model::from_ast(&cddl)?;
+    for rule in model.rules.iter_mut() {
+      let (annotations, description): (Vec<_>, Vec<_>) = comments
+        .comments(&rule.name)
+        .iter()
+        .map(|c| (Annotation::from_comment(c), c))
+        .partition(|(a, _)| a.is_some());
+
+      rule.description = description
+        .into_iter()
+        .map(|(_, c)| c.trim())
+        .filter(|c| !c.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+      rule.annotations = annotations
+        .into_iter()
+        .filter_map(|(a, _)| a)
+        .map(|a| AnnotationModel {
+          name: a.name,
+          args: a.args,
+        })
+        .collect();
+    }
+
+    Ok(model)
+  }
+
+  /// Build the model of a parsed CDDL document. Descriptions and annotations
+  /// are left empty, since comments preceding rules are not part of the AST
+  pub fn from_ast(cddl: &CDDL) -> Result<Self> {
+    let cddl = cddl
+      .resolve_generics()
+      .map_err(Error::Generics)?
+      .resolve_unwraps()
+      .map_err(Error::Unwrap)?;
+
+    let mut model = Model::default();
+    for rule in cddl.rules.iter() {
+      let name = rule.name();
+      let idx = match model.rules.iter().position(|r| r.name == name) {
+        Some(idx) => idx,
+        None => {
+          model.rules.push(RuleModel {
+            kind: match rule {
+              Rule::Type { .. } => "type",
+              Rule::Group { .. } => "group",
+            },
+            name,
+            description: String::new(),
+            annotations: Vec::new(),
+            definitions: Vec::new(),
+            types: Vec::new(),
+            members: Vec::new(),
+            group_choices: Vec::new(),
+          });
+          model.rules.len() - 1
+        }
+      };
+      let r = &mut model.rules[idx];
+
+      r.definitions.push(rule.to_string().trim().to_string());
+      match rule {
+        Rule::Type { rule, .. } => {
+          let types = type_models(&rule.value);
+          for t in types.iter() {
+            r.group_choices.extend(t.group_choices.iter().cloned());
+          }
+          r.types.extend(types);
+        }
+        Rule::Group { rule, .. } => match &rule.entry {
+          GroupEntry::InlineGroup {
+            occur: None, group, ..
+          } => r.group_choices.extend(group_choices(group)),
+          entry => r.group_choices.push(vec![member_model(entry)]),
+        },
+      }
+      r.members = r.group_choices.first().cloned().unwrap_or_default();
+    }
+
+    Ok(model)
+  }
+
+  /// Render the given template with the model
+  pub fn render(&self, template: &str) -> Result<String> {
+    let mut env = Environment::new();
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
+    env.set_keep_trailing_newline(true);
+    env.add_filter("snake_case", |s: String| case(&s, '_', false));
+    env.add_filter("pascal_case", |s: String| case(&s, '\0', true));
+
+    env
+      .template_from_str(template)
+      .and_then(|t| t.render(self))
+      .map_err(|e| Error::Template(e.to_string()))
+  }
+}
+
+// Convert a CDDL name, whose words are separated by `-`, `_`, `.` or `@`, to an
+// identifier with the given separator (`\0` for none)
+fn case(name: &str, separator: char, capitalize: bool) -> String {
+  let mut ident = String::with_capacity(name.len());
+  for (idx, word) in name
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|w| !w.is_empty())
+    .enumerate()
+  {
+    if idx > 0 && separator != '\0' {
+      ident.push(separator);
+    }
+
+    if capitalize {
+      let mut chars = word.chars();
+      if let Some(first) = chars.next() {
+        ident.extend(first.to_uppercase());
+        ident.push_str(chars.as_str());
+      }
+    } else {
+      ident.push_str(&word.to_lowercase());
+    }
+  }
+
+  ident
+}
+
+fn group_choices(group: &Group) -> Vec<Vec<MemberModel>> {
+  group
+    .group_choices
+    .iter()
+    .map(|gc| {
+      gc.group_entries
+        .iter()
+        .map(|(ge, _)| member_model(ge))
+        .collect()
+    })
+    .collect()
+}
+
+fn member_model(entry: &GroupEntry) -> MemberModel {
+  let (occur, key, key_type, cut, types, members) = match entry {
+    GroupEntry::ValueMemberKey { ge, .. } => {
+      let (key, key_type, cut) = match &ge.member_key {
+        Some(MemberKey::Bareword { ident, .. }) => (Some(ident.to_string()), None, true),
+        Some(MemberKey::Value { value, .. }) => (Some(value_key(value)), None, true),
+        Some(MemberKey::Type1 { t1, is_cut, .. }) => (None, Some(type1_model(t1)), *is_cut),
+        Some(MemberKey::NonMemberKey { .. }) | None => (None, None, false),
+      };
+
+      (
+        ge.occur.as_ref(),
+        key,
+        key_type,
+        cut,
+        type_models(&ge.entry_type),
+        Vec::new(),
+      )
+    }
+    GroupEntry::TypeGroupname { ge, .. } => {
+      let name = match &ge.generic_args {
+        Some(ga) => format!("{}{}", ge.name, ga),
+        None => ge.name.to_string(),
+      };
+
+      (
+        ge.occur.as_ref(),
+        None,
+        None,
+        false,
+        vec![TypeModel {
+          name: Some(name.clone()),
+          ..type_model(named_kind(&ge.name), name)
+        }],
+        Vec::new(),
+      )
+    }
+    GroupEntry::InlineGroup { occur, group, .. } => (
+      occur.as_ref(),
+      None,
+      None,
+      false,
+      Vec::new(),
+      group_choices(group).into_iter().next().unwrap_or_default(),
+    ),
+  };
+
+  let (lower, upper) = match occur.map(|o| &o.occur) {
+    None => (1, Some(1)),
+    Some(Occur::Optional { .. }) => (0, Some(1)),
+    Some(Occur::ZeroOrMore { .. }) => (0, None),
+    Some(Occur::OneOrMore { .. }) => (1, None),
+    Some(Occur::Exact { lower, upper, .. }) => (lower.unwrap_or(0), *upper),
+  };
+
+  MemberModel {
+    key,
+    key_type,
+    cut,
+    optional: lower == 0,
+    occurrence: OccurrenceModel {
+      lower,
+      upper,
+      indicator: occur.map(|o| o.occur.to_string()).unwrap_or_default(),
+    },
+    types,
+    members,
+    text: entry
+      .to_string()
+      .trim()
+      .trim_end_matches(',')
+      .trim_end()
+      .to_string(),
+  }
+}
+
+fn value_key(value: &Value) -> String {
+  match value {
+    Value::TEXT(text) => text.to_string(),
+    value => value.to_string(),
+  }
+}
+
+fn named_kind(ident: &Identifier) -> &'static str {
+  if lookup_ident(&ident.ident).in_standard_prelude().is_some() {
+    "prelude"
+  } else {
+    "reference"
+  }
+}
+
+fn type_models(t: &Type) -> Vec<TypeModel> {
+  t.type_choices
+    .iter()
+    .map(|tc| type1_model(&tc.type1))
+    .collect()
+}
+
+fn type1_model(t1: &Type1) -> TypeModel {
+  let mut model = type2_model(&t1.type2);
+  if let Some(o) = &t1.operator {
+    model.operator = Some(o.operator.to_string());
+    model.argument = Some(Box::new(type2_model(&o.type2)));
+    model.text = t1.to_string().trim().to_string();
+  }
+
+  model
+}
+
+fn type2_model(t2: &Type2) -> TypeModel {
+  let text = t2.to_string().trim().to_string();
+  match t2 {
+    Type2::IntValue { value, .. } => TypeModel {
+      value: Some((*value as i64).into()),
+      ..type_model("value", text)
+    },
+    Type2::UintValue { value, .. } => TypeModel {
+      value: Some((*value as u64).into()),
+      ..type_model("value", text)
+    },
+    Type2::FloatValue { value, .. } => TypeModel {
+      value: Some((*value).into()),
+      ..type_model("value", text)
+    },
+    Type2::TextValue { value, .. } => TypeModel {
+      value: Some(value.to_string().into()),
+      ..type_model("value", text)
+    },
+    Type2::UTF8ByteString { value, .. } => TypeModel {
+      value: Some(String::from_utf8_lossy(value).into_owned().into()),
+      ..type_model("value", text)
+    },
+    Type2::B16ByteString { value, .. } | Type2::B64ByteString { value, .. } => TypeModel {
+      value: Some(String::from_utf8_lossy(value).into_owned().into()),
+      ..type_model("value", text)
+    },
+    Type2::Typename {
+      ident,
+      generic_args,
+      ..
+    } => {
+      // `true`, `false` and `null` are prelude types with a single value
+      let value = match ident.ident.as_ref() {
+        "true" => Some(true.into()),
+        "false" => Some(false.into()),
+        "null" | "nil" => Some(serde_json::Value::Null),
+        _ => None,
+      };
+
+      TypeModel {
+        name: Some(match generic_args {
+          Some(ga) => format!("{}{}", ident, ga),
+          None => ident.to_string(),
+        }),
+        value,
+        ..type_model(named_kind(ident), text)
+      }
+    }
+    Type2::ParenthesizedType { pt, .. } => TypeModel {
+      types: type_models(pt),
+      ..type_model("parenthesized", text)
+    },
+    Type2::Map { group, .. } => group_type_model("map", group, text),
+    Type2::Array { group, .. } => group_type_model("array", group, text),
+    Type2::ChoiceFromInlineGroup { group, .. } => group_type_model("choice", group, text),
+    Type2::ChoiceFromGroup {
+      ident,
+      generic_args,
+      ..
+    } => TypeModel {
+      name: Some(match generic_args {
+        Some(ga) => format!("{}{}", ident, ga),
+        None => ident.to_string(),
+      }),
+      ..type_model("choice", text)
+    },
+    Type2::TaggedData { tag, t, .. } => TypeModel {
+      tag: *tag,
+      types: type_models(t),
+      ..type_model("tagged", text)
+    },
+    _ => type_model("other", text),
+  }
+}
+
+fn group_type_model(kind: &'static str, group: &Group, text: String) -> TypeModel {
+  let group_choices = group_choices(group);
+  TypeModel {
+    members: group_choices.first().cloned().unwrap_or_default(),
+    group_choices,
+    ..type_model(kind, text)
+  }
+}
+
+fn type_model(kind: &'static str, text: String) -> TypeModel {
+  TypeModel {
+    kind,
+    name: None,
+    value: None,
+    tag: None,
+    types: Vec::new(),
+    members: Vec::new(),
+    group_choices: Vec::new(),
+    operator: None,
+    argument: None,
+    text,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use indoc::indoc;
+
+  #[test]
+  fn verify_template_model() -> Result<()> {
+    let model = Model::from_cddl(indoc!(
+      r#"
+        ; A message
+        ;@example { "id": 1 }
+        message = {
+          id: uint,
+          ? "content-type": tstr .size (1..64),
+          * tstr => envelope<bytes>,
+          ~header,
+        }
+        envelope<t> = [tag: #6.24(t), 1*3 uint]
+        header = { version: 1 / 2 }
+        kind = "a" / "b"
+        kind /= true
+        extra = (name: tstr // code: int)
+      "#
+    ))?;
+
+    assert_eq!(
+      model
+        .rules
+        .iter()
+        .map(|r| r.name.as_str())
+        .collect::<Vec<_>>(),
+      vec!["message", "header", "kind", "extra"]
+    );
+
+    let message = &model.rules[0];
+    assert_eq!(message.kind, "type");
+    assert_eq!(message.description, "A message");
+    assert_eq!(
+      message.annotations,
+      vec![AnnotationModel {
+        name: "example".to_string(),
+        args: r#"{ "id": 1 }"#.to_string(),
+      }]
+    );
+    assert_eq!(message.types[0].kind, "map");
+    assert_eq!(message.members.len(), 4);
+
+    let id = &message.members[0];
+    assert_eq!(id.key.as_deref(), Some("id"));
+    assert!(!id.optional);
+    assert_eq!(id.types[0].kind, "prelude");
+    assert_eq!(id.types[0].name.as_deref(), Some("uint"));
+
+    let content_type = &message.members[1];
+    assert_eq!(content_type.key.as_deref(), Some("content-type"));
+    assert!(content_type.optional);
+    assert_eq!(content_type.occurrence.indicator, "?");
+    assert_eq!(content_type.types[0].operator.as_deref(), Some(".size"));
+    assert_eq!(
+      content_type.types[0].argument.as_ref().unwrap().kind,
+      "parenthesized"
+    );
+
+    // Generic rules are instantiated
+    let extension = &message.members[2];
+    assert_eq!(extension.key, None);
+    assert_eq!(
+      extension.key_type.as_ref().unwrap().name.as_deref(),
+      Some("tstr")
+    );
+    assert_eq!(extension.occurrence.upper, None);
+    let envelope = &extension.types[0];
+    assert_eq!(envelope.kind, "array");
+    assert_eq!(envelope.members[0].types[0].tag, Some(24));
+    assert_eq!(
+      envelope.members[0].types[0].types[0].name.as_deref(),
+      Some("bytes")
+    );
+    assert_eq!(envelope.members[1].occurrence.indicator, "1*3");
+    assert_eq!(
+      (
+        envelope.members[1].occurrence.lower,
+        envelope.members[1].occurrence.upper
+      ),
+      (1, Some(3))
+    );
+
+    // Unwrapped rules are inlined
+    assert_eq!(
+      message.members[3].members[0].key.as_deref(),
+      Some("version")
+    );
+
+    let kind = &model.rules[2];
+    assert_eq!(kind.definitions.len(), 2);
+    assert_eq!(
+      kind
+        .types
+        .iter()
+        .map(|t| t.value.clone())
+        .collect::<Vec<_>>(),
+      vec![Some("a".into()), Some("b".into()), Some(true.into())]
+    );
+
+    let extra = &model.rules[3];
+    assert_eq!(extra.kind, "group");
+    assert_eq!(extra.group_choices.len(), 2);
+    assert_eq!(extra.members[0].key.as_deref(), Some("name"));
+
+    let rendered = model.render(indoc!(
+      r#"
+        {% for rule in rules %}
+        {{ rule.name | pascal_case }}: {{ rule.members | map(attribute="key") | reject("none") | join(", ") }}
+        {% endfor %}
+      "#
+    ))?;
+    assert_eq!(
+      rendered,
+      "Message: id, content-type\nHeader: version\nKind: \nExtra: name\n"
+    );
+
+    assert!(matches!(
+      model.render("{% for rule in rules %}"),
+      Err(Error::Template(_))
+    ));
+
+    Ok(())
+  }
+
+  #[test]
+  fn verify_case_conversion() {
+    assert_eq!(case("content-type", '_', false), "content_type");
+    assert_eq!(case("content-type", '\0', true), "ContentType");
+    assert_eq!(case("$$tcp.option", '\0', true), "TcpOption");
+  }
+}
@@ -3,7 +3,7 @@ use std::{convert::TryFrom, fmt};
 #[cfg(feature = "std")]
 use std::borrow::Cow;
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(any(target_arch = "wasm32", feature = "ast-serde"))]
 use serde::{Deserialize, Serialize};
 
 #[cfg(not(feature = "std"))]
@@ -149,6 +149,10 @@ pub enum Token<'a> {
   #[cfg(feature = "additional-controls")]
   /// .feature control operator (rfc 9165)
   FEATURE,
+  /// Control operator defined neither by RFC 8610 nor RFC 9165, implemented by
+  /// a control operator plugin
+  #[cfg(feature = "control-plugins")]
+  CONTROL(&'a str),
 
   /// group to choice enumeration '&'
   GTOCHOICE,
@@ -234,9 +238,12 @@ pub enum Token<'a> {
   CBORANY,
   /// undefined
   UNDEFINED,
-  /// newline (used only for comment formatting when compiled with the "lsp"
-  /// feature)
+  /// newline (used for comment formatting when compiled with the "lsp"
+  /// feature, and as trivia by the token stream of the lexer)
   NEWLINE,
+  /// Whitespace other than newlines (used only as trivia by the token stream of
+  /// the lexer)
+  WHITESPACE(&'a str),
 }
 
 impl<'a> Token<'a> {
@@ -356,7 +363,10 @@ impl<'a> fmt::Display for RangeValue<'a> {
 
 /// Literal value
 // TODO: support hexfloat and exponent
-#[cfg_attr(target_arch = "wasm32", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  any(target_arch = "wasm32", feature = "ast-serde"),
+  derive(Serialize, Deserialize)
+)]
 #[derive(Debug, PartialEq, Clone)]
 pub enum Value<'a> {
   /// Integer value
@@ -366,10 +376,8 @@ pub enum Value<'a> {
   /// Float value
   FLOAT(f64),
   /// Text value
-  #[cfg_attr(target_arch = "wasm32", serde(borrow))]
   TEXT(Cow<'a, str>),
   /// Byte value
-  #[cfg_attr(target_arch = "wasm32", serde(borrow))]
   BYTE(ByteValue<'a>),
 }
 
@@ -396,6 +404,19 @@ impl<'a> fmt::Display for Value<'a> {
   }
 }
 
+impl<'a> Value<'a> {
+  /// Convert into an owned value
+  pub fn into_owned(self) -> Value<'static> {
+    match self {
+      Value::INT(i) => Value::INT(i),
+      Value::UINT(ui) => Value::UINT(ui),
+      Value::FLOAT(float) => Value::FLOAT(float),
+      Value::TEXT(text) => Value::TEXT(Cow::Owned(text.into_owned())),
+      Value::BYTE(bv) => Value::BYTE(bv.into_owned()),
+    }
+  }
+}
+
 impl<'a> From<&'a str> for Value<'a> {
   fn from(value: &'a str) -> Self {
     Value::TEXT(value.into())
@@ -403,7 +424,10 @@ impl<'a> From<&'a str> for Value<'a> {
 }
 
 /// Byte string values
-#[cfg_attr(target_arch = "wasm32", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  any(target_arch = "wasm32", feature = "ast-serde"),
+  derive(Serialize, Deserialize)
+)]
 #[derive(Debug, PartialEq, Clone)]
 pub enum ByteValue<'a> {
   /// Unprefixed byte string value
@@ -414,6 +438,17 @@ pub enum ByteValue<'a> {
   B64(Cow<'a, [u8]>),
 }
 
+impl<'a> ByteValue<'a> {
+  /// Convert into an owned byte string value
+  pub fn into_owned(self) -> ByteValue<'static> {
+    match self {
+      ByteValue::UTF8(b) => ByteValue::UTF8(Cow::Owned(b.into_owned())),
+      ByteValue::B16(b) => ByteValue::B16(Cow::Owned(b.into_owned())),
+      ByteValue::B64(b) => ByteValue::B64(Cow::Owned(b.into_owned())),
+    }
+  }
+}
+
 impl<'a> fmt::Display for ByteValue<'a> {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     match self {
@@ -437,7 +472,10 @@ impl<'a> fmt::Display for ByteValue<'a> {
 }
 
 /// Socket/plug prefix
-#[cfg_attr(target_arch = "wasm32", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  any(target_arch = "wasm32", feature = "ast-serde"),
+  derive(Serialize, Deserialize)
+)]
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum SocketPlug {
   /// Type socket `$`
@@ -501,6 +539,7 @@ impl<'a> fmt::Display for Token<'a> {
       Token::GCHOICEALT => write!(f, "//="),
       Token::COMMA => write!(f, ","),
       Token::COMMENT(c) => write!(f, ";{}", c),
+      Token::WHITESPACE(w) => write!(f, "{}", w),
       Token::COLON => write!(f, ":"),
       Token::CUT => write!(f, "^"),
       Token::EOF => write!(f, ""),
@@ -529,6 +568,8 @@ impl<'a> fmt::Display for Token<'a> {
       Token::ABNFB => write!(f, ".abnfb"),
       #[cfg(feature = "additional-controls")]
       Token::FEATURE => write!(f, ".feature"),
+      #[cfg(feature = "control-plugins")]
+      Token::CONTROL(ctrl) => write!(f, "{}", ctrl),
       Token::AND => write!(f, ".and"),
       Token::LT => write!(f, ".lt"),
       Token::LE => write!(f, ".le"),
@@ -670,6 +711,29 @@ pub fn control_str_from_token(t: &Token) -> Option<&'static str> {
   }
 }
 
+/// Return the name of a control operator from a given token, including the
+/// control operators implemented by control operator plugins. Unlike
+/// `control_str_from_token`, the name may borrow from the token
+///
+/// # Arguments
+///
+/// `t` - Reference to a `Token`
+///
+/// # Example
+///
+/// ```
+/// use cddl::token::{control_name, Token};
+///
+/// assert_eq!(control_name(&Token::SIZE), Some(".size"));
+/// ```
+pub fn control_name<'a>(t: &Token<'a>) -> Option<&'a str> {
+  match t {
+    #[cfg(feature = "control-plugins")]
+    Token::CONTROL(ctrl) => Some(ctrl),
+    _ => control_str_from_token(t),
+  }
+}
+
 /// Returns token in standard prelude from given string
 ///
 /// # Arguments
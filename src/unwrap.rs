@@ -0,0 +1,539 @@
+#![cfg(feature = "std")]
+
+use crate::{
+  ast::*,
+  token::{self, Token},
+};
+
+use std::{borrow::Cow, fmt};
+
+/// Unwrap resolution result
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Unwrap resolution error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+  /// Unwrapped rule isn't defined
+  MissingRule(String),
+  /// Unwrapped rule unwraps itself, directly or indirectly
+  Recursive(String),
+  /// Unwrapped rule isn't a map, array or tag, or its contents can't be
+  /// expressed in the position of the unwrap operator
+  Unsupported(String),
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Error::MissingRule(rule) => write!(f, "no rule named {} is defined", rule),
+      Error::Recursive(rule) => write!(f, "rule {} unwraps itself and cannot be resolved", rule),
+      Error::Unsupported(reason) => write!(f, "{}", reason),
+    }
+  }
+}
+
+impl std::error::Error for Error {}
+
+impl<'a> CDDL<'a> {
+  /// Resolve the unwrap operator, returning a new document in which every
+  /// `~name` is replaced by the contents of the referenced rule, i.e. the group
+  /// of a map or array or the type of tagged data. An unwrapped group is
+  /// inlined as a group entry, and an unwrapped type as a parenthesized type.
+  ///
+  /// The inlined contents keep the spans of the referenced rule's definition,
+  /// whereas the group entry or parenthesized type replacing the unwrap
+  /// operator keeps the span of the unwrap operator. Unwrapped generic rules
+  /// must be instantiated first with [`CDDL::resolve_generics`]
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use cddl::cddl_from_str;
+  ///
+  /// let cddl = cddl_from_str(
+  ///   "header = [~basic-header, field3: bytes]\nbasic-header = [field1: int, field2: text]",
+  ///   true,
+  /// )
+  /// .unwrap();
+  ///
+  /// let resolved = cddl.resolve_unwraps().unwrap();
+  /// assert_eq!(
+  ///   resolved.rules[0].to_string().trim(),
+  ///   "header = [ ( field1: int, field2: text ), field3: bytes ]"
+  /// );
+  /// ```
+  pub fn resolve_unwraps(&self) -> Result<CDDL<'a>> {
+    let mut resolver = Resolver {
+      cddl: self,
+      unwrapping: Vec::new(),
+    };
+
+    let mut rules = Vec::with_capacity(self.rules.len());
+    for rule in self.rules.iter() {
+      let mut rule = rule.clone();
+      match &mut rule {
+        Rule::Type {
+          rule: type_rule,
+          #[cfg(feature = "ast-span")]
+          span,
+          #[cfg(feature = "ast-comments")]
+          comments_after_rule,
+        } => {
+          // A type rule that is an unwrapped group in its entirety (e.g. `a =
+          // ~b`) is resolved as a group rule
+          if let Some(Contents::Group(group)) = resolver.rule_contents(type_rule)? {
+            rule = Rule::Group {
+              rule: Box::new(GroupRule {
+                name: type_rule.name.clone(),
+                generic_params: type_rule.generic_params.clone(),
+                is_group_choice_alternate: false,
+                entry: GroupEntry::InlineGroup {
+                  occur: None,
+                  group,
+                  #[cfg(feature = "ast-span")]
+                  span: type_rule.value.span,
+                  #[cfg(feature = "ast-comments")]
+                  comments_before_group: None,
+                  #[cfg(feature = "ast-comments")]
+                  comments_after_group: None,
+                },
+                #[cfg(feature = "ast-comments")]
+                comments_before_assigng: type_rule.comments_before_assignt.clone(),
+                #[cfg(feature = "ast-comments")]
+                comments_after_assigng: type_rule.comments_after_assignt.clone(),
+              }),
+              #[cfg(feature = "ast-span")]
+              span: *span,
+              #[cfg(feature = "ast-comments")]
+              comments_after_rule: comments_after_rule.clone(),
+            };
+          } else {
+            resolver.type_(&mut type_rule.value)?;
+          }
+        }
+        Rule::Group { rule, .. } => resolver.group_entry(&mut rule.entry)?,
+      }
+
+      rules.push(rule);
+    }
+
+    Ok(CDDL {
+      rules,
+      #[cfg(feature = "ast-comments")]
+      comments: self.comments.clone(),
+    })
+  }
+}
+
+// Contents of an unwrapped rule
+enum Contents<'a> {
+  Group(Group<'a>),
+  Type(Type<'a>),
+}
+
+struct Resolver<'a, 'b> {
+  cddl: &'b CDDL<'a>,
+  // Rules currently being unwrapped, used to detect cycles
+  unwrapping: Vec<Cow<'a, str>>,
+}
+
+impl<'a, 'b> Resolver<'a, 'b> {
+  fn unwrap(
+    &mut self,
+    ident: &Identifier<'a>,
+    generic_args: Option<&GenericArgs<'a>>,
+  ) -> Result<Contents<'a>> {
+    if generic_args.is_some() {
+      return Err(Error::Unsupported(format!(
+        "unwrapped generic rule {} must be instantiated before it can be resolved",
+        ident
+      )));
+    }
+
+    if self.unwrapping.contains(&ident.ident) {
+      return Err(Error::Recursive(ident.ident.to_string()));
+    }
+
+    let definitions = self
+      .cddl
+      .rules
+      .iter()
+      .filter(|r| r.name() == ident.ident)
+      .collect::<Vec<_>>();
+    if definitions.is_empty() {
+      return prelude_contents(ident);
+    }
+
+    self.unwrapping.push(ident.ident.clone());
+
+    // Rules extended with additional choices (i.e. `/=`) are unwrapped as the
+    // union of the contents of each choice
+    let mut contents: Option<Contents<'a>> = None;
+    for definition in definitions {
+      let t = match definition {
+        Rule::Type { rule, .. } => &rule.value,
+        Rule::Group { .. } => {
+          return Err(Error::Unsupported(format!(
+            "group rule {} cannot be unwrapped",
+            ident
+          )))
+        }
+      };
+
+      for tc in t.type_choices.iter() {
+        let choice = match &tc.type1 {
+          Type1 {
+            type2: Type2::Map { group, .. } | Type2::Array { group, .. },
+            operator: None,
+            ..
+          } => {
+            let mut group = group.clone();
+            self.group(&mut group)?;
+            Contents::Group(group)
+          }
+          Type1 {
+            type2: Type2::TaggedData { t, .. },
+            operator: None,
+            ..
+          } => {
+            let mut t = t.clone();
+            self.type_(&mut t)?;
+            Contents::Type(t)
+          }
+          // Rules aliasing another rule unwrap the aliased rule
+          Type1 {
+            type2:
+              Type2::Typename {
+                ident: alias,
+                generic_args,
+                ..
+              },
+            operator: None,
+            ..
+          } => self.unwrap(alias, generic_args.as_ref())?,
+          _ => {
+            return Err(Error::Unsupported(format!(
+              "rule {} is not a map, array or tag and cannot be unwrapped",
+              ident
+            )))
+          }
+        };
+
+        contents = Some(match (contents, choice) {
+          (None, choice) => choice,
+          (Some(Contents::Group(mut g)), Contents::Group(mut choice)) => {
+            g.group_choices.append(&mut choice.group_choices);
+            Contents::Group(g)
+          }
+          (Some(Contents::Type(mut t)), Contents::Type(mut choice)) => {
+            t.type_choices.append(&mut choice.type_choices);
+            Contents::Type(t)
+          }
+          _ => {
+            return Err(Error::Unsupported(format!(
+              "rule {} has both group and type contents and cannot be unwrapped",
+              ident
+            )))
+          }
+        });
+      }
+    }
+
+    self.unwrapping.pop();
+
+    // Every definition of a type rule has at least one type choice
+    Ok(contents.unwrap())
+  }
+
+  // Contents of a type rule whose value is a single unwrapped rule
+  fn rule_contents(&mut self, rule: &TypeRule<'a>) -> Result<Option<Contents<'a>>> {
+    match &rule.value.type_choices[..] {
+      [TypeChoice {
+        type1:
+          Type1 {
+            type2:
+              Type2::Unwrap {
+                ident,
+                generic_args,
+                ..
+              },
+            operator: None,
+            ..
+          },
+        ..
+      }] if !rule.is_type_choice_alternate => self.unwrap(ident, generic_args.as_ref()).map(Some),
+      _ => Ok(None),
+    }
+  }
+
+  fn type_(&mut self, t: &mut Type<'a>) -> Result<()> {
+    for tc in t.type_choices.iter_mut() {
+      self.type1(&mut tc.type1)?;
+    }
+
+    Ok(())
+  }
+
+  fn type1(&mut self, t1: &mut Type1<'a>) -> Result<()> {
+    self.type2(&mut t1.type2)?;
+    if let Some(o) = &mut t1.operator {
+      self.type2(&mut o.type2)?;
+    }
+
+    Ok(())
+  }
+
+  fn type2(&mut self, t2: &mut Type2<'a>) -> Result<()> {
+    match t2 {
+      Type2::Unwrap {
+        ident,
+        generic_args,
+        #[cfg(feature = "ast-span")]
+        span,
+        ..
+      } => match self.unwrap(ident, generic_args.as_ref())? {
+        Contents::Type(pt) => {
+          *t2 = Type2::ParenthesizedType {
+            pt,
+            #[cfg(feature = "ast-span")]
+            span: *span,
+            #[cfg(feature = "ast-comments")]
+            comments_before_type: None,
+            #[cfg(feature = "ast-comments")]
+            comments_after_type: None,
+          }
+        }
+        Contents::Group(_) => {
+          return Err(Error::Unsupported(format!(
+            "unwrapped group {} can only be used as a group entry",
+            ident
+          )))
+        }
+      },
+      Type2::Typename {
+        generic_args: Some(ga),
+        ..
+      }
+      | Type2::ChoiceFromGroup {
+        generic_args: Some(ga),
+        ..
+      } => {
+        for arg in ga.args.iter_mut() {
+          self.type1(&mut arg.arg)?;
+        }
+      }
+      Type2::ParenthesizedType { pt: t, .. } | Type2::TaggedData { t, .. } => self.type_(t)?,
+      Type2::Map { group, .. }
+      | Type2::Array { group, .. }
+      | Type2::ChoiceFromInlineGroup { group, .. } => self.group(group)?,
+      _ => {}
+    }
+
+    Ok(())
+  }
+
+  fn group(&mut self, g: &mut Group<'a>) -> Result<()> {
+    for gc in g.group_choices.iter_mut() {
+      for (ge, _) in gc.group_entries.iter_mut() {
+        self.group_entry(ge)?;
+      }
+    }
+
+    Ok(())
+  }
+
+  fn group_entry(&mut self, entry: &mut GroupEntry<'a>) -> Result<()> {
+    #[cfg(feature = "ast-span")]
+    let span = entry.span();
+
+    match entry {
+      GroupEntry::ValueMemberKey { ge, .. } => {
+        // An unwrapped map or array entry contributes the entries of its group
+        if ge.member_key.is_none() && ge.entry_type.type_choices.len() == 1 {
+          if let Type1 {
+            type2:
+              Type2::Unwrap {
+                ident,
+                generic_args,
+                ..
+              },
+            operator: None,
+            ..
+          } = &ge.entry_type.type_choices[0].type1
+          {
+            let ident = ident.clone();
+            if let Contents::Group(group) = self.unwrap(&ident, generic_args.as_ref())? {
+              *entry = GroupEntry::InlineGroup {
+                occur: ge.occur.clone(),
+                group,
+                #[cfg(feature = "ast-span")]
+                span,
+                #[cfg(feature = "ast-comments")]
+                comments_before_group: None,
+                #[cfg(feature = "ast-comments")]
+                comments_after_group: None,
+              };
+
+              return Ok(());
+            }
+          }
+        }
+
+        match &mut ge.member_key {
+          Some(MemberKey::Type1 { t1, .. }) => self.type1(t1)?,
+          Some(MemberKey::NonMemberKey {
+            non_member_key: NonMemberKey::Type(t),
+            ..
+          }) => self.type_(t)?,
+          Some(MemberKey::NonMemberKey {
+            non_member_key: NonMemberKey::Group(g),
+            ..
+          }) => self.group(g)?,
+          _ => {}
+        }
+
+        self.type_(&mut ge.entry_type)
+      }
+      GroupEntry::TypeGroupname { ge, .. } => {
+        if let Some(ga) = &mut ge.generic_args {
+          for arg in ga.args.iter_mut() {
+            self.type1(&mut arg.arg)?;
+          }
+        }
+
+        Ok(())
+      }
+      GroupEntry::InlineGroup { group, .. } => self.group(group),
+    }
+  }
+}
+
+// Contents of an unwrapped tag type of the standard prelude, e.g. `~tdate`
+fn prelude_contents<'a>(ident: &Identifier<'a>) -> Result<Contents<'a>> {
+  let token = token::lookup_ident(&ident.ident);
+  if ident.socket.is_some() || token.in_standard_prelude().is_none() {
+    return Err(Error::MissingRule(ident.ident.to_string()));
+  }
+
+  match token {
+    // Tags whose contents aren't available as types
+    Token::DECFRAC | Token::BIGFLOAT => Err(Error::Unsupported(format!(
+      "prelude type {} cannot be unwrapped",
+      ident
+    ))),
+    token => match tag_from_token(&token) {
+      Some(Type2::TaggedData { t, .. }) => Ok(Contents::Type(t)),
+      _ => Err(Error::Unsupported(format!(
+        "prelude type {} is not a tag and cannot be unwrapped",
+        ident
+      ))),
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cddl_from_str;
+
+  fn resolve(input: &str) -> Result<String> {
+    let cddl = cddl_from_str(input, true).unwrap();
+    let resolved = cddl.resolve_unwraps()?;
+
+    Ok(
+      resolved
+        .rules
+        .iter()
+        .map(|r| r.to_string().trim().to_string())
+        .collect::<Vec<_>>()
+        .join("\n"),
+    )
+  }
+
+  #[test]
+  fn verify_resolve_unwraps() -> Result<()> {
+    let input = "a = [~b, c: bytes]\nb = [x: int, y: tstr]";
+    assert_eq!(
+      resolve(input)?,
+      resolve("a = [(x: int, y: tstr), c: bytes]\nb = [x: int, y: tstr]")?
+    );
+
+    // Unwrapped tags, aliases, choices and nested unwraps
+    assert_eq!(
+      resolve("a = { id: ~tagged, ? ~base }\ntagged = #6.32(tstr)\nbase = alias\nalias = { x: int } / { y: [~c] }\nc = [tstr]")?
+        .lines()
+        .next()
+        .unwrap(),
+      "a = { id: (tstr), ? ( x: int // y: [ ( tstr ) ] ) }"
+    );
+    assert_eq!(resolve("a = ~tdate")?, "a = (tstr)");
+
+    // A type rule that unwraps a group is resolved as a group rule
+    assert_eq!(
+      resolve("a = ~b\nb = { x: int }")?.lines().next().unwrap(),
+      "a = ( x: int )"
+    );
+
+    assert_eq!(
+      resolve("a = [~b]\nb = [int, ~a]"),
+      Err(Error::Recursive("b".to_string()))
+    );
+    assert_eq!(
+      resolve("a = [~b]"),
+      Err(Error::MissingRule("b".to_string()))
+    );
+    assert!(matches!(
+      resolve("a = [~b]\nb = int"),
+      Err(Error::Unsupported(_))
+    ));
+    assert!(matches!(
+      resolve("a = { c: ~b }\nb = [int]"),
+      Err(Error::Unsupported(_))
+    ));
+
+    Ok(())
+  }
+
+  #[test]
+  #[cfg(feature = "ast-span")]
+  fn verify_resolve_unwraps_spans() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let input = "a = [~b, c: #6.1(~d)]\nb = [x: int]\nd = #6.2(tstr)";
+    let cddl = cddl_from_str(input, true)?;
+    let resolved = cddl.resolve_unwraps()?;
+
+    let entries = match &resolved.rules[0] {
+      Rule::Type { rule, .. } => match &rule.value.type_choices[0].type1.type2 {
+        Type2::Array { group, .. } => group.group_choices[0].group_entries.clone(),
+        t => panic!("expected array, got {:?}", t),
+      },
+      r => panic!("expected type rule, got {:?}", r),
+    };
+
+    // The inlined group entry keeps the span of the unwrapped entry, and its
+    // contents the spans of the definition of b
+    match &entries[0].0 {
+      GroupEntry::InlineGroup { group, span, .. } => {
+        assert_eq!(&input[span.0..span.1], "~b,");
+        let entry_span = group.group_choices[0].group_entries[0].0.span();
+        assert_eq!(&input[entry_span.0..entry_span.1], "x: int");
+      }
+      ge => panic!("expected inline group, got {:?}", ge),
+    }
+
+    match &entries[1].0 {
+      GroupEntry::ValueMemberKey { ge, .. } => match &ge.entry_type.type_choices[0].type1.type2 {
+        Type2::TaggedData { t, .. } => match &t.type_choices[0].type1.type2 {
+          Type2::ParenthesizedType { pt, span, .. } => {
+            assert_eq!(&input[span.0..span.1], "~d");
+            assert_eq!(&input[pt.span.0..pt.span.1], "tstr");
+          }
+          t2 => panic!("expected parenthesized type, got {:?}", t2),
+        },
+        t2 => panic!("expected tagged data, got {:?}", t2),
+      },
+      ge => panic!("expected value member key, got {:?}", ge),
+    }
+
+    Ok(())
+  }
+}
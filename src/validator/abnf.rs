@@ -0,0 +1,728 @@
+#![cfg(feature = "std")]
+
+use displaydoc::Display;
+use std::collections::{BTreeSet, HashMap};
+
+/// Alias for `Result` with an error of type `abnf::Error`
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// ABNF parsing and matching error
+#[derive(Debug, Display, PartialEq)]
+pub enum Error {
+  /// invalid ABNF on line {line}: {reason}
+  Syntax {
+    /// Line of the grammar, starting at 1
+    line: usize,
+    /// Why the line is invalid
+    reason: String,
+  },
+  /// rule {0} is referenced but not defined
+  UndefinedRule(String),
+  /// rule {rule} failed to match at offset {offset}, found {found}
+  Mismatch {
+    /// Rules being matched at the point of failure, outermost first and
+    /// separated by " > "
+    rule: String,
+    /// Offset in the input at which matching failed, in characters for text
+    /// and in bytes for byte strings
+    offset: usize,
+    /// Character or byte found at the offset, or "end of input"
+    found: String,
+  },
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug, Clone)]
+enum Node {
+  Alternation(Vec<Node>),
+  Concatenation(Vec<Node>),
+  Repetition {
+    min: usize,
+    max: Option<usize>,
+    node: Box<Node>,
+  },
+  Rule(String),
+  Literal {
+    value: Vec<u32>,
+    case_sensitive: bool,
+  },
+  Range(u32, u32),
+}
+
+/// Grammar written in ABNF (RFC 5234), including the case-sensitive string
+/// extension of RFC 7405. The core rules of RFC 5234 appendix B are available
+/// to grammars that don't define rules of the same name. Prose values
+/// (`<...>`) can't be matched and are rejected.
+///
+/// ```
+/// use cddl::validator::abnf::Abnf;
+///
+/// let abnf = Abnf::parse("year = 4DIGIT\nmonth = \"0\" %x31-39 / \"1\" %x30-32")?;
+/// assert!(abnf.match_str("year", "2009").is_ok());
+/// assert!(abnf.match_str("month", "13").is_err());
+/// # Ok::<(), cddl::validator::abnf::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Abnf {
+  names: Vec<String>,
+  rules: Vec<Node>,
+  index: HashMap<String, usize>,
+}
+
+const CORE_RULES: &str = r#"
+ALPHA = %x41-5A / %x61-7A
+BIT = "0" / "1"
+CHAR = %x01-7F
+CR = %x0D
+CRLF = CR LF
+CTL = %x00-1F / %x7F
+DIGIT = %x30-39
+DQUOTE = %x22
+HEXDIG = DIGIT / "A" / "B" / "C" / "D" / "E" / "F"
+HTAB = %x09
+LF = %x0A
+LWSP = *(WSP / CRLF WSP)
+OCTET = %x00-FF
+SP = %x20
+VCHAR = %x21-7E
+WSP = SP / HTAB
+"#;
+
+impl Abnf {
+  /// Parse a grammar. Each line starting with a rule name followed by `=` or
+  /// `=/` begins a definition, and any other line continues the previous one,
+  /// so grammars may be indented as a whole
+  pub fn parse(abnf: &str) -> Result<Self> {
+    let mut grammar = Abnf {
+      names: Vec::new(),
+      rules: Vec::new(),
+      index: HashMap::new(),
+    };
+
+    grammar.define_rules(abnf)?;
+
+    let core = grammar.names.len();
+    let mut core_grammar = Abnf {
+      names: Vec::new(),
+      rules: Vec::new(),
+      index: HashMap::new(),
+    };
+    core_grammar.define_rules(CORE_RULES)?;
+    for (name, node) in core_grammar.names.into_iter().zip(core_grammar.rules) {
+      if !grammar.index.contains_key(&name.to_lowercase()) {
+        grammar
+          .index
+          .insert(name.to_lowercase(), grammar.names.len());
+        grammar.names.push(name);
+        grammar.rules.push(node);
+      }
+    }
+
+    for node in grammar.rules[..core].iter() {
+      grammar.check_references(node)?;
+    }
+
+    Ok(grammar)
+  }
+
+  /// Match a text string against a rule. The whole string must match
+  pub fn match_str(&self, rule: &str, target: &str) -> Result<()> {
+    let input = target.chars().map(u32::from).collect::<Vec<_>>();
+    self.match_input(rule, &input, false)
+  }
+
+  /// Match a byte string against a rule. The whole byte string must match
+  pub fn match_bytes(&self, rule: &str, target: &[u8]) -> Result<()> {
+    let input = target.iter().map(|b| u32::from(*b)).collect::<Vec<_>>();
+    self.match_input(rule, &input, true)
+  }
+
+  fn match_input(&self, rule: &str, input: &[u32], bytes: bool) -> Result<()> {
+    let idx = *self
+      .index
+      .get(&rule.to_lowercase())
+      .ok_or_else(|| Error::UndefinedRule(rule.to_string()))?;
+
+    let mut matcher = Matcher {
+      grammar: self,
+      input,
+      memo: HashMap::new(),
+      stack: Vec::new(),
+      furthest: None,
+    };
+    let ends = matcher.rule(idx, 0);
+    if ends.contains(&input.len()) {
+      return Ok(());
+    }
+
+    let (offset, rule) = match matcher.furthest {
+      Some((offset, stack)) if ends.iter().all(|end| *end <= offset) => (offset, stack),
+      _ => (
+        ends.iter().next_back().copied().unwrap_or(0),
+        self.names[idx].clone(),
+      ),
+    };
+    let found = match input.get(offset) {
+      Some(c) if bytes => format!("0x{:02x}", c),
+      Some(c) => format!(
+        "{:?}",
+        char::from_u32(*c).unwrap_or(char::REPLACEMENT_CHARACTER)
+      ),
+      None => "end of input".to_string(),
+    };
+
+    Err(Error::Mismatch {
+      rule,
+      offset,
+      found,
+    })
+  }
+
+  fn define_rules(&mut self, abnf: &str) -> Result<()> {
+    let mut definitions: Vec<(usize, String)> = Vec::new();
+    for (idx, line) in abnf.lines().enumerate() {
+      let line = strip_comment(line);
+      if line.trim().is_empty() {
+        continue;
+      }
+
+      if starts_definition(line) {
+        definitions.push((idx + 1, line.trim().to_string()));
+      } else if let Some((_, definition)) = definitions.last_mut() {
+        definition.push(' ');
+        definition.push_str(line.trim());
+      } else {
+        return Err(Error::Syntax {
+          line: idx + 1,
+          reason: "expected a rule definition".to_string(),
+        });
+      }
+    }
+
+    for (line, definition) in definitions {
+      let eq = definition.find('=').unwrap_or_default();
+      let name = definition[..eq].trim().to_string();
+      let (incremental, elements) = match definition[eq + 1..].strip_prefix('/') {
+        Some(elements) => (true, elements),
+        None => (false, &definition[eq + 1..]),
+      };
+
+      let mut parser = Parser {
+        chars: elements.chars().collect(),
+        pos: 0,
+        line,
+      };
+      let node = parser.alternation()?;
+      parser.skip_whitespace();
+      if let Some(c) = parser.peek() {
+        return Err(parser.error(format!("unexpected {:?}", c)));
+      }
+
+      match self.index.get(&name.to_lowercase()) {
+        Some(idx) if incremental => {
+          let existing = std::mem::replace(&mut self.rules[*idx], Node::Alternation(Vec::new()));
+          self.rules[*idx] = match existing {
+            Node::Alternation(mut alternatives) => {
+              alternatives.push(node);
+              Node::Alternation(alternatives)
+            }
+            existing => Node::Alternation(vec![existing, node]),
+          };
+        }
+        Some(_) => {
+          return Err(Error::Syntax {
+            line,
+            reason: format!("rule {} is already defined", name),
+          })
+        }
+        None if incremental => {
+          return Err(Error::Syntax {
+            line,
+            reason: format!("incremental alternative for undefined rule {}", name),
+          })
+        }
+        None => {
+          self.index.insert(name.to_lowercase(), self.names.len());
+          self.names.push(name);
+          self.rules.push(node);
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  fn check_references(&self, node: &Node) -> Result<()> {
+    match node {
+      Node::Alternation(nodes) | Node::Concatenation(nodes) => {
+        nodes.iter().try_for_each(|n| self.check_references(n))
+      }
+      Node::Repetition { node, .. } => self.check_references(node),
+      Node::Rule(name) if !self.index.contains_key(name) => Err(Error::UndefinedRule(name.clone())),
+      _ => Ok(()),
+    }
+  }
+}
+
+/// Remove a trailing comment, ignoring semicolons in quoted strings and prose
+/// values
+fn strip_comment(line: &str) -> &str {
+  let mut closing = None;
+  for (idx, c) in line.char_indices() {
+    match (closing, c) {
+      (Some(close), c) if c == close => closing = None,
+      (Some(_), _) => {}
+      (None, '"') => closing = Some('"'),
+      (None, '<') => closing = Some('>'),
+      (None, ';') => return &line[..idx],
+      _ => {}
+    }
+  }
+
+  line
+}
+
+/// Whether a line starts with a rule name followed by `=` or `=/`, as opposed
+/// to continuing the previous definition
+fn starts_definition(line: &str) -> bool {
+  let line = line.trim_start();
+  let name_len = line
+    .char_indices()
+    .find(|(_, c)| !(c.is_ascii_alphanumeric() || *c == '-'))
+    .map(|(idx, _)| idx)
+    .unwrap_or(line.len());
+
+  line.starts_with(|c: char| c.is_ascii_alphabetic())
+    && line[name_len..].trim_start().starts_with('=')
+}
+
+struct Parser {
+  chars: Vec<char>,
+  pos: usize,
+  line: usize,
+}
+
+impl Parser {
+  fn peek(&self) -> Option<char> {
+    self.chars.get(self.pos).copied()
+  }
+
+  fn error(&self, reason: String) -> Error {
+    Error::Syntax {
+      line: self.line,
+      reason,
+    }
+  }
+
+  fn skip_whitespace(&mut self) {
+    while let Some(' ') | Some('\t') = self.peek() {
+      self.pos += 1;
+    }
+  }
+
+  fn alternation(&mut self) -> Result<Node> {
+    let mut alternatives = vec![self.concatenation()?];
+    loop {
+      self.skip_whitespace();
+      if self.peek() != Some('/') {
+        break;
+      }
+      self.pos += 1;
+      alternatives.push(self.concatenation()?);
+    }
+
+    Ok(if alternatives.len() == 1 {
+      alternatives.remove(0)
+    } else {
+      Node::Alternation(alternatives)
+    })
+  }
+
+  fn concatenation(&mut self) -> Result<Node> {
+    let mut elements = Vec::new();
+    loop {
+      self.skip_whitespace();
+      match self.peek() {
+        None | Some('/') | Some(')') | Some(']') => break,
+        _ => elements.push(self.repetition()?),
+      }
+    }
+
+    match elements.len() {
+      0 => Err(self.error("expected an element".to_string())),
+      1 => Ok(elements.remove(0)),
+      _ => Ok(Node::Concatenation(elements)),
+    }
+  }
+
+  fn repetition(&mut self) -> Result<Node> {
+    let min = self.number(10)?;
+    let (min, max) = if self.peek() == Some('*') {
+      self.pos += 1;
+      (min.unwrap_or(0), self.number(10)?)
+    } else {
+      match min {
+        Some(n) => (n, Some(n)),
+        None => return self.element(),
+      }
+    };
+    if let Some(max) = max {
+      if max < min {
+        return Err(self.error(format!("repetition {}*{} has max below min", min, max)));
+      }
+    }
+
+    let node = self.element()?;
+    Ok(Node::Repetition {
+      min: min as usize,
+      max: max.map(|max| max as usize),
+      node: Box::new(node),
+    })
+  }
+
+  fn element(&mut self) -> Result<Node> {
+    match self.peek() {
+      Some(c) if c.is_ascii_alphabetic() => {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+          if !(c.is_ascii_alphanumeric() || c == '-') {
+            break;
+          }
+          self.pos += 1;
+        }
+        Ok(Node::Rule(
+          self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .to_lowercase(),
+        ))
+      }
+      Some('(') => {
+        self.pos += 1;
+        let node = self.alternation()?;
+        self.close(')')?;
+        Ok(node)
+      }
+      Some('[') => {
+        self.pos += 1;
+        let node = self.alternation()?;
+        self.close(']')?;
+        Ok(Node::Repetition {
+          min: 0,
+          max: Some(1),
+          node: Box::new(node),
+        })
+      }
+      Some('"') => self.char_val(false),
+      Some('%') => {
+        self.pos += 1;
+        match self.peek().map(|c| c.to_ascii_lowercase()) {
+          Some('s') => {
+            self.pos += 1;
+            self.char_val(true)
+          }
+          Some('i') => {
+            self.pos += 1;
+            self.char_val(false)
+          }
+          Some('x') => self.num_val(16),
+          Some('d') => self.num_val(10),
+          Some('b') => self.num_val(2),
+          Some(c) => Err(self.error(format!("invalid value prefix %{}", c))),
+          None => Err(self.error("unexpected end of rule after %".to_string())),
+        }
+      }
+      Some('<') => Err(self.error("prose values can't be matched".to_string())),
+      Some(c) => Err(self.error(format!("unexpected {:?}", c))),
+      None => Err(self.error("unexpected end of rule".to_string())),
+    }
+  }
+
+  fn close(&mut self, c: char) -> Result<()> {
+    self.skip_whitespace();
+    if self.peek() == Some(c) {
+      self.pos += 1;
+      Ok(())
+    } else {
+      Err(self.error(format!("expected {:?}", c)))
+    }
+  }
+
+  fn char_val(&mut self, case_sensitive: bool) -> Result<Node> {
+    if self.peek() != Some('"') {
+      return Err(self.error("expected a quoted string".to_string()));
+    }
+    self.pos += 1;
+
+    let start = self.pos;
+    while let Some(c) = self.peek() {
+      if c == '"' {
+        let value = self.chars[start..self.pos]
+          .iter()
+          .map(|c| u32::from(*c))
+          .collect();
+        self.pos += 1;
+        return Ok(Node::Literal {
+          value,
+          case_sensitive,
+        });
+      }
+      self.pos += 1;
+    }
+
+    Err(self.error("unterminated quoted string".to_string()))
+  }
+
+  fn num_val(&mut self, radix: u32) -> Result<Node> {
+    self.pos += 1;
+    let first = self.value(radix)?;
+    match self.peek() {
+      Some('-') => {
+        self.pos += 1;
+        let last = self.value(radix)?;
+        if last < first {
+          return Err(self.error(format!("range {}-{} is empty", first, last)));
+        }
+        Ok(Node::Range(first, last))
+      }
+      Some('.') => {
+        let mut value = vec![first];
+        while self.peek() == Some('.') {
+          self.pos += 1;
+          value.push(self.value(radix)?);
+        }
+        Ok(Node::Literal {
+          value,
+          case_sensitive: true,
+        })
+      }
+      _ => Ok(Node::Range(first, first)),
+    }
+  }
+
+  fn value(&mut self, radix: u32) -> Result<u32> {
+    self
+      .number(radix)?
+      .ok_or_else(|| self.error("expected a numeric value".to_string()))
+  }
+
+  fn number(&mut self, radix: u32) -> Result<Option<u32>> {
+    let start = self.pos;
+    while let Some(true) = self.peek().map(|c| c.is_digit(radix)) {
+      self.pos += 1;
+    }
+    if start == self.pos {
+      return Ok(None);
+    }
+
+    let digits = self.chars[start..self.pos].iter().collect::<String>();
+    u32::from_str_radix(&digits, radix)
+      .map(Some)
+      .map_err(|e| self.error(format!("invalid number {}: {}", digits, e)))
+  }
+}
+
+/// Computes the set of input offsets at which each element can end when
+/// started at a given offset, so that alternatives and repetitions never need
+/// to backtrack. Results of rules are memoized per start offset
+struct Matcher<'a> {
+  grammar: &'a Abnf,
+  input: &'a [u32],
+  memo: HashMap<(usize, usize), Option<BTreeSet<usize>>>,
+  stack: Vec<usize>,
+  furthest: Option<(usize, String)>,
+}
+
+impl<'a> Matcher<'a> {
+  fn fail(&mut self, offset: usize) {
+    if matches!(&self.furthest, Some((furthest, _)) if *furthest >= offset) {
+      return;
+    }
+
+    let rule = self
+      .stack
+      .iter()
+      .map(|idx| self.grammar.names[*idx].as_str())
+      .collect::<Vec<_>>()
+      .join(" > ");
+    self.furthest = Some((offset, rule));
+  }
+
+  fn rule(&mut self, idx: usize, start: usize) -> BTreeSet<usize> {
+    match self.memo.get(&(idx, start)) {
+      Some(Some(ends)) => return ends.clone(),
+      // Left recursion never consumes input before recursing, so it can't
+      // contribute further matches
+      Some(None) => return BTreeSet::new(),
+      None => {}
+    }
+
+    self.memo.insert((idx, start), None);
+    self.stack.push(idx);
+    let grammar = self.grammar;
+    let ends = self.ends(&grammar.rules[idx], start);
+    self.stack.pop();
+    self.memo.insert((idx, start), Some(ends.clone()));
+
+    ends
+  }
+
+  fn ends(&mut self, node: &Node, start: usize) -> BTreeSet<usize> {
+    let mut ends = BTreeSet::new();
+
+    match node {
+      Node::Alternation(nodes) => {
+        for node in nodes {
+          ends.extend(self.ends(node, start));
+        }
+      }
+      Node::Concatenation(nodes) => {
+        ends.insert(start);
+        for node in nodes {
+          let mut next = BTreeSet::new();
+          for offset in ends {
+            next.extend(self.ends(node, offset));
+          }
+          ends = next;
+          if ends.is_empty() {
+            break;
+          }
+        }
+      }
+      Node::Repetition { min, max, node } => {
+        if *min == 0 {
+          ends.insert(start);
+        }
+
+        let mut current = BTreeSet::from([start]);
+        let mut count = 0;
+        while !matches!(max, Some(max) if count >= *max) {
+          count += 1;
+          let mut next = BTreeSet::new();
+          for offset in current.iter() {
+            next.extend(self.ends(node, *offset));
+          }
+          if next.is_empty() {
+            break;
+          }
+
+          if count >= *min {
+            let new = next.difference(&ends).copied().collect::<BTreeSet<_>>();
+            ends.extend(new.iter().copied());
+            // Without an upper bound, offsets already reached can't lead
+            // anywhere new
+            if max.is_none() {
+              if new.is_empty() {
+                break;
+              }
+              next = new;
+            }
+          }
+          current = next;
+        }
+      }
+      Node::Rule(name) => {
+        let idx = self.grammar.index[name];
+        ends = self.rule(idx, start);
+      }
+      Node::Literal {
+        value,
+        case_sensitive,
+      } => {
+        let matched = value
+          .iter()
+          .zip(self.input[start..].iter())
+          .take_while(|(expected, found)| {
+            *expected == *found || (!case_sensitive && fold_case(**expected) == fold_case(**found))
+          })
+          .count();
+        if matched == value.len() {
+          ends.insert(start + matched);
+        } else {
+          self.fail(start + matched);
+        }
+      }
+      Node::Range(first, last) => match self.input.get(start) {
+        Some(c) if first <= c && c <= last => {
+          ends.insert(start + 1);
+        }
+        _ => self.fail(start),
+      },
+    }
+
+    ends
+  }
+}
+
+fn fold_case(c: u32) -> u32 {
+  if (u32::from(b'A')..=u32::from(b'Z')).contains(&c) {
+    c + 32
+  } else {
+    c
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn verify_abnf_matching() -> Result<()> {
+    let abnf = Abnf::parse(
+      r#"
+        date = year "-" month   ; comments; ignored
+        year = 4DIGIT
+        month = "0" %x31-39 / %s"1" ("0" / "1" / "2")
+        greeting = "hello" [ SP name ]
+        name = 1*ALPHA
+        greeting =/ %s"Hi"
+        crlf = %d13.10
+      "#,
+    )?;
+
+    abnf.match_str("date", "2009-12")?;
+    abnf.match_str("greeting", "HeLLo world")?;
+    abnf.match_str("greeting", "Hi")?;
+    abnf.match_str("crlf", "\r\n")?;
+    assert!(abnf.match_str("greeting", "hi").is_err());
+    // The whole input has to match, not just a prefix
+    assert!(abnf.match_str("year", "20091").is_err());
+
+    assert_eq!(
+      abnf.match_str("date", "2009-1x").unwrap_err(),
+      Error::Mismatch {
+        rule: "date > month".to_string(),
+        offset: 6,
+        found: "'x'".to_string(),
+      }
+    );
+    assert_eq!(
+      abnf.match_str("date", "209-12").unwrap_err(),
+      Error::Mismatch {
+        rule: "date > year > DIGIT".to_string(),
+        offset: 3,
+        found: "'-'".to_string(),
+      }
+    );
+
+    assert_eq!(
+      Abnf::parse("a = b").unwrap_err(),
+      Error::UndefinedRule("b".to_string())
+    );
+    assert!(Abnf::parse("a = <prose>").is_err());
+
+    let abnf = Abnf::parse("nlsb = %x81-ff *%x80-ff")?;
+    abnf.match_bytes("nlsb", &[0x81, 0xff, 0x80])?;
+    assert_eq!(
+      abnf.match_bytes("nlsb", &[0x81, 0x00]).unwrap_err(),
+      Error::Mismatch {
+        rule: "nlsb".to_string(),
+        offset: 1,
+        found: "0x00".to_string(),
+      }
+    );
+
+    Ok(())
+  }
+}
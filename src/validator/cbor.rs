@@ -1,28 +1,91 @@
-#![cfg(feature = "std")]
 #![cfg(feature = "cbor")]
 #![cfg(not(feature = "lsp"))]
 
-use super::*;
+use super::{
+  compiled::CompiledCddl,
+  deterministic::check_deterministic_encoding,
+  offsets::ItemOffsets,
+  plan::{ArrayPlan, Matcher, PlanOutcome},
+  *,
+};
 use crate::{
   ast::*,
   token::{self, Token},
   visitor::{self, *},
 };
 
-use std::{borrow::Cow, collections::HashMap, convert::TryFrom, fmt};
+use std::fmt;
 
-use chrono::{TimeZone, Utc};
 use ciborium::value::Value;
+
+#[cfg(feature = "std")]
+use chrono::{TimeZone, Utc};
+#[cfg(feature = "std")]
 use serde_json;
+#[cfg(feature = "std")]
+use std::{
+  borrow::Cow,
+  collections::{BTreeMap, BTreeSet},
+  convert::TryFrom,
+  rc::Rc,
+};
 
-#[cfg(feature = "additional-controls")]
-use crate::validator::control::{
-  abnf_from_complex_controller, cat_operation, plus_operation, validate_abnf,
+#[cfg(not(feature = "std"))]
+use alloc::{
+  borrow::Cow,
+  boxed::Box,
+  collections::{BTreeMap, BTreeSet},
+  rc::Rc,
+  string::{String, ToString},
+  vec::Vec,
 };
+use core::cell::RefCell;
+
+#[cfg(feature = "additional-controls")]
+#[cfg(feature = "std")]
+use crate::validator::control::{abnf_from_complex_controller, validate_abnf, validate_abnfb};
+#[cfg(feature = "additional-controls")]
+use crate::validator::control::{cat_operation, plus_operation_with_overflow};
+use crate::validator::control::{OverflowBehavior, RegexEngine};
+#[cfg(feature = "std")]
+use crate::validator::extended_prelude::ExtendedPrelude;
+#[cfg(feature = "control-plugins")]
+use crate::validator::plugin::{ControlPlugins, Encoding};
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+use rayon::prelude::*;
+
+/// Default maximum nesting depth of CBOR embedded in byte strings with the
+/// `.cbor` and `.cborseq` control operators
+pub const DEFAULT_MAX_EMBEDDED_DEPTH: usize = 16;
+
+// Minimum number of elements of an array of homogeneous occurrences for them
+// to be validated across threads
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+const PARALLEL_THRESHOLD: usize = 64;
+
+// Errors, labeled paths, choice explanations and feature uses of validating an
+// array element
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+type ElementOutcome = (
+  Vec<ValidationError>,
+  Vec<LabeledPath>,
+  Vec<ChoiceExplanation>,
+  Vec<FeatureUse>,
+);
 
 /// cbor validation Result
 pub type Result<T> = std::result::Result<(), Error<T>>;
 
+/// Error type of the reader CBOR is decoded from, i.e. `std::io::Error`, or
+/// `ciborium_io::EndOfFile` when decoding from a byte slice in `no_std`
+#[cfg(feature = "std")]
+pub type ReaderError = std::io::Error;
+
+/// Error type of the reader CBOR is decoded from, i.e. `std::io::Error`, or
+/// `ciborium_io::EndOfFile` when decoding from a byte slice in `no_std`
+#[cfg(not(feature = "std"))]
+pub type ReaderError = ciborium_io::EndOfFile;
+
 /// cbor validation error
 #[derive(Debug)]
 pub enum Error<T: std::fmt::Debug> {
@@ -31,11 +94,16 @@ pub enum Error<T: std::fmt::Debug> {
   /// cbor parsing error
   CBORParsing(ciborium::de::Error<T>),
   /// json parsing error. Used only for parsing regex controller strings
+  #[cfg(feature = "std")]
   JSONParsing(serde_json::Error),
   /// CDDL parsing error
-  CDDLParsing(String),
+  CDDLParsing(parser::Error),
   /// UTF8 parsing error,
   UTF8Parsing(std::str::Utf8Error),
+  /// Rule selected as the validation root is not defined
+  MissingRule(String),
+  /// Validation exceeded one of the validation limits
+  LimitExceeded(String),
 }
 
 impl<T: std::fmt::Debug> fmt::Display for Error<T> {
@@ -49,17 +117,73 @@ impl<T: std::fmt::Debug> fmt::Display for Error<T> {
         write!(f, "{}", error_str)
       }
       Error::CBORParsing(error) => write!(f, "error parsing cbor: {}", error),
+      #[cfg(feature = "std")]
       Error::JSONParsing(error) => write!(f, "error parsing json string: {}", error),
       Error::CDDLParsing(error) => write!(f, "error parsing CDDL: {}", error),
       Error::UTF8Parsing(error) => write!(f, "error pasing utf8: {}", error),
+      Error::MissingRule(rule) => write!(f, "no rule named {} is defined", rule),
+      Error::LimitExceeded(limit) => write!(f, "validation limit exceeded: {}", limit),
     }
   }
 }
 
+#[cfg(feature = "std")]
 impl<T: std::fmt::Debug + 'static> std::error::Error for Error<T> {
   fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
     match self {
       Error::CBORParsing(error) => Some(error),
+      Error::CDDLParsing(error) => Some(error),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(feature = "miette")]
+impl<T: std::fmt::Debug + 'static> miette::Diagnostic for Error<T> {
+  fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+    match self {
+      Error::CDDLParsing(e) => e.code(),
+      Error::Validation(_) => Some(Box::new("cddl::validation")),
+      Error::CBORParsing(_) => Some(Box::new("cddl::cbor")),
+      Error::JSONParsing(_) => Some(Box::new("cddl::json")),
+      Error::UTF8Parsing(_) => Some(Box::new("cddl::utf8")),
+      Error::MissingRule(_) => Some(Box::new("cddl::missing_rule")),
+      Error::LimitExceeded(_) => Some(Box::new("cddl::limit_exceeded")),
+    }
+  }
+
+  fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+    match self {
+      Error::Validation(errors) => errors.iter().find_map(|e| e.help()),
+      Error::CDDLParsing(e) => e.help(),
+      Error::MissingRule(_) => Some(Box::new(
+        "the root rule must be the name of a type rule defined in the CDDL document",
+      )),
+      Error::LimitExceeded(_) => Some(Box::new(
+        "raise the validation limits if the document and data are trusted",
+      )),
+      _ => None,
+    }
+  }
+
+  fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+    match self {
+      Error::CDDLParsing(e) => e.source_code(),
+      _ => None,
+    }
+  }
+
+  fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+    match self {
+      Error::Validation(errors) => Some(Box::new(
+        errors
+          .iter()
+          .filter_map(|e| e.labels())
+          .flatten()
+          .collect::<Vec<_>>()
+          .into_iter(),
+      )),
+      Error::CDDLParsing(e) => e.labels(),
       _ => None,
     }
   }
@@ -70,7 +194,7 @@ impl<T: std::fmt::Debug + 'static> std::error::Error for Error<T> {
 pub struct ValidationError {
   /// Error message
   pub reason: String,
-  /// Location in CDDL where error occurred
+  /// Name of the innermost rule being validated when the error occurred
   pub cddl_location: String,
   /// Location in CBOR where error occurred
   pub cbor_location: String,
@@ -82,6 +206,13 @@ pub struct ValidationError {
   pub is_group_to_choice_enum: bool,
   /// Error is associated with a type/group name group entry
   pub type_group_name_entry: Option<String>,
+  /// Span of the CDDL type or group entry being validated when the error
+  /// occurred
+  #[cfg(feature = "ast-span")]
+  pub cddl_span: Option<Span>,
+  /// Byte range of the offending data item within the encoding of the CBOR
+  /// data item, if the encoding was given to the validator
+  pub cbor_range: Option<(usize, usize)>,
 }
 
 impl fmt::Display for ValidationError {
@@ -100,6 +231,14 @@ impl fmt::Display for ValidationError {
       error_str.push_str(&format!(" group entry associated with rule \"{}\"", entry));
     }
 
+    if let Some((start, end)) = self.cbor_range {
+      return write!(
+        f,
+        "{} at cbor location {} (bytes {}..{}): {}",
+        error_str, self.cbor_location, start, end, self.reason
+      );
+    }
+
     write!(
       f,
       "{} at cbor location {}: {}",
@@ -108,12 +247,43 @@ impl fmt::Display for ValidationError {
   }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for ValidationError {
   fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
     None
   }
 }
 
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for ValidationError {
+  fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+    Some(Box::new("cddl::validation"))
+  }
+
+  fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+    if self.is_multi_type_choice || self.is_multi_group_choice || self.is_group_to_choice_enum {
+      return Some(Box::new("the value must match at least one of the choices"));
+    }
+
+    self.type_group_name_entry.as_ref().map(|entry| {
+      Box::new(format!("the entry is defined by rule \"{}\"", entry)) as Box<dyn fmt::Display>
+    })
+  }
+
+  fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+    #[cfg(feature = "ast-span")]
+    if let Some(span) = self.cddl_span {
+      return Some(Box::new(std::iter::once(miette::LabeledSpan::new(
+        Some(self.reason.clone()),
+        span.0,
+        span.1.saturating_sub(span.0),
+      ))));
+    }
+
+    None
+  }
+}
+
 impl<T: std::fmt::Debug> Error<T> {
   fn from_validator(cv: &CBORValidator, reason: String) -> Self {
     Error::Validation(vec![ValidationError {
@@ -122,7 +292,10 @@ impl<T: std::fmt::Debug> Error<T> {
       reason,
       is_multi_type_choice: cv.is_multi_type_choice,
       is_group_to_choice_enum: cv.is_group_to_choice_enum,
-      type_group_name_entry: cv.type_group_name_entry.map(|e| e.to_string()),
+      type_group_name_entry: cv.type_group_name_entry.as_ref().map(|e| e.to_string()),
+      #[cfg(feature = "ast-span")]
+      cddl_span: cv.cddl_span,
+      cbor_range: None,
       is_multi_group_choice: cv.is_multi_group_choice,
     }])
   }
@@ -132,16 +305,20 @@ impl<T: std::fmt::Debug> Error<T> {
 #[derive(Clone)]
 pub struct CBORValidator<'a> {
   cddl: &'a CDDL<'a>,
-  cbor: Value,
+  cbor: Cow<'a, Value>,
   errors: Vec<ValidationError>,
   cddl_location: String,
   cbor_location: String,
+  // Span of the CDDL type or group entry currently being validated
+  #[cfg(feature = "ast-span")]
+  cddl_span: Option<Span>,
   // Occurrence indicator detected in current state of AST evaluation
   occurrence: Option<Occur>,
   // Current group entry index detected in current state of AST evaluation
   group_entry_idx: Option<usize>,
-  // cbor object value hoisted from previous state of AST evaluation
-  object_value: Option<Value>,
+  // Index of the cbor map entry whose value is hoisted from previous state of
+  // AST evaluation
+  object_entry_idx: Option<usize>,
   // Is member key detected in current state of AST evaluation
   is_member_key: bool,
   // Is a cut detected in current state of AST evaluation
@@ -150,7 +327,7 @@ pub struct CBORValidator<'a> {
   cut_value: Option<Type1<'a>>,
   // Validate the generic rule given by str ident in current state of AST
   // evaluation
-  eval_generic_rule: Option<&'a str>,
+  eval_generic_rule: Option<Cow<'a, str>>,
   // Aggregation of generic rules
   generic_rules: Vec<GenericRule<'a>>,
   // Control operator token detected in current state of AST evaluation
@@ -164,7 +341,7 @@ pub struct CBORValidator<'a> {
   is_multi_group_choice: bool,
   // Type/group name entry detected in current state of AST evaluation. Used
   // only for providing more verbose error messages
-  type_group_name_entry: Option<&'a str>,
+  type_group_name_entry: Option<Cow<'a, str>>,
   // Whether or not to advance to the next group entry if member key validation
   // fails as detected during the current state of AST evaluation
   advance_to_next_entry: bool,
@@ -173,36 +350,232 @@ pub struct CBORValidator<'a> {
   entry_counts: Option<Vec<EntryCount>>,
   // Collect map entry keys that have already been validated
   validated_keys: Option<Vec<Value>>,
-  // Collect map entry values that have yet to be validated
-  values_to_validate: Option<Vec<Value>>,
+  // Whether or not a member key with a cut matched a map key, along with its
+  // value, in the current group choice
+  is_cut_matched: bool,
+  // Errors of map values that failed to match an optional member key without
+  // a cut, reported unless another group entry matches their key
+  unmatched_key_errors: Vec<(Value, Vec<ValidationError>)>,
+  // Collect indices of map entries whose values have yet to be validated
+  entries_to_validate: Option<Vec<usize>>,
   // Whether or not the validator is validating a map entry value
   validating_value: bool,
   // Collect valid array indices when entries are type choices
-  valid_array_items: Option<Vec<usize>>,
+  valid_array_items: Option<BTreeSet<usize>>,
   // Collect invalid array item errors where the key is the index of the invalid
   // array item
-  array_errors: Option<HashMap<usize, Vec<ValidationError>>>,
+  array_errors: Option<BTreeMap<usize, Vec<ValidationError>>>,
   is_colon_shortcut_present: bool,
   is_root: bool,
+  // Labels attached to rules via `;@label` annotations
+  rule_labels: Option<&'a RuleLabels>,
+  // Compiled document providing the rule index and precompiled controllers
+  compiled: Option<&'a CompiledCddl<'a>>,
+  // Locations successfully validated against labeled rules
+  labeled_paths: Vec<LabeledPath>,
+  // Whether or not to explain the matches of type choices
+  explain_choices: bool,
+  // Explanations of the type choices matched during validation
+  choice_explanations: Vec<ChoiceExplanation>,
+  // Uses of features by the locations validated so far
+  feature_uses: Vec<FeatureUse>,
+  // Behavior of control operator arithmetic on integer overflow
+  overflow_behavior: OverflowBehavior,
+  // Engine matching the `.regexp` and `.pcre` control operators
+  regex_engine: RegexEngine,
+  // Number of `.cbor` and `.cborseq` byte strings the CBOR data item is
+  // embedded in
+  embedded_depth: usize,
+  // Maximum nesting depth of CBOR embedded in byte strings
+  max_embedded_depth: usize,
+  // Resources used so far, bounded by the validation limits
+  budget: Budget,
+  // Plugins implementing control operators beyond RFC 8610 and RFC 9165
+  #[cfg(feature = "control-plugins")]
+  control_plugins: Option<&'a ControlPlugins>,
+  // Types beyond the standard prelude that the document may reference without
+  // defining them
+  #[cfg(feature = "std")]
+  extended_prelude: Option<&'a ExtendedPrelude>,
+  // Encoding of the CBOR data item, used to report the byte ranges of errors
+  cbor_slice: Option<&'a [u8]>,
+  // Whether or not to check that the encoding is core deterministic
+  deterministic_encoding: bool,
+  // Memo of the subtrees validated so far if memoization is enabled. Shared
+  // with the validators of nested items so identical subtrees are only
+  // validated once
+  memo: Option<Rc<RefCell<Memo>>>,
+  // Rule, and optional generic arguments, to validate against instead of the
+  // first type rule in the document
+  root_rule: Option<(&'a str, Option<GenericArgs<'a>>)>,
   #[cfg(not(target_arch = "wasm32"))]
   #[cfg(feature = "additional-controls")]
   enabled_features: Option<&'a [&'a str]>,
   #[cfg(target_arch = "wasm32")]
   #[cfg(feature = "additional-controls")]
   enabled_features: Option<Box<[JsValue]>>,
+  // Behavior of the `.feature` control operator on matches of features that
+  // aren't enabled
   #[cfg(feature = "additional-controls")]
-  has_feature_errors: bool,
-  #[cfg(feature = "additional-controls")]
-  disabled_features: Option<Vec<String>>,
+  disabled_feature_behavior: DisabledFeatureBehavior,
+}
+
+// Name of a type rule along with the identifier of a subtree of the CBOR data
+// item that is valid against it
+type MemoKey = (String, usize);
+
+// Rules that arrays, maps and tagged items of the CBOR data item have been
+// successfully validated against
+#[derive(Clone, Debug, Default)]
+struct Memo {
+  // Identifiers of the distinct subtrees of the CBOR data item, keyed by their
+  // address. Identical subtrees share an identifier
+  subtree_ids: Arc<BTreeMap<usize, usize>>,
+  // Validated subtrees along with the features they used, relative to their
+  // location
+  validated: BTreeMap<MemoKey, Vec<FeatureUse>>,
+}
+
+impl Memo {
+  // Assign identifiers to the arrays, maps and tagged items of the data item
+  // by hash-consing, i.e. interning each item by its kind and the identifiers
+  // of its children, in a single pass over the data item
+  fn new(cbor: &Value) -> Option<Memo> {
+    fn intern(
+      value: &Value,
+      nodes: &mut BTreeMap<(Vec<u8>, Vec<usize>), usize>,
+      subtree_ids: &mut BTreeMap<usize, usize>,
+    ) -> Option<usize> {
+      // Arrays, maps and tags are distinguished from the encodings of other
+      // data items by initial bytes those encodings can't start with
+      let node = match value {
+        Value::Array(items) => (
+          vec![0x9f],
+          items
+            .iter()
+            .map(|v| intern(v, nodes, subtree_ids))
+            .collect::<Option<_>>()?,
+        ),
+        Value::Map(entries) => {
+          let mut children = Vec::with_capacity(entries.len() * 2);
+          for (k, v) in entries.iter() {
+            children.push(intern(k, nodes, subtree_ids)?);
+            children.push(intern(v, nodes, subtree_ids)?);
+          }
+
+          (vec![0xbf], children)
+        }
+        Value::Tag(tag, v) => {
+          let mut kind = vec![0xdb];
+          kind.extend_from_slice(&tag.to_be_bytes());
+
+          (kind, vec![intern(v, nodes, subtree_ids)?])
+        }
+        _ => {
+          let mut encoding = Vec::new();
+          ciborium::ser::into_writer(value, &mut encoding).ok()?;
+
+          (encoding, Vec::new())
+        }
+      };
+
+      let next_id = nodes.len();
+      let id = *nodes.entry(node).or_insert(next_id);
+      if matches!(value, Value::Array(_) | Value::Map(_) | Value::Tag(..)) {
+        subtree_ids.insert(value as *const Value as usize, id);
+      }
+
+      Some(id)
+    }
+
+    let mut subtree_ids = BTreeMap::new();
+    intern(cbor, &mut BTreeMap::new(), &mut subtree_ids)?;
+
+    Some(Memo {
+      subtree_ids: Arc::new(subtree_ids),
+      validated: BTreeMap::new(),
+    })
+  }
 }
 
 #[derive(Clone, Debug)]
 struct GenericRule<'a> {
-  name: &'a str,
-  params: Vec<&'a str>,
+  name: Cow<'a, str>,
+  params: Vec<Cow<'a, str>>,
   args: Vec<Type1<'a>>,
 }
 
+// Settings of a validator that the validators of the data items nested in the
+// one being validated inherit. Unlike the validator, they can be shared with
+// the threads validating array elements in parallel
+#[derive(Clone)]
+struct ValidatorSettings<'a> {
+  cddl: &'a CDDL<'a>,
+  #[cfg(not(target_arch = "wasm32"))]
+  #[cfg(feature = "additional-controls")]
+  enabled_features: Option<&'a [&'a str]>,
+  #[cfg(target_arch = "wasm32")]
+  #[cfg(feature = "additional-controls")]
+  enabled_features: Option<Box<[JsValue]>>,
+  generic_rules: Vec<GenericRule<'a>>,
+  eval_generic_rule: Option<Cow<'a, str>>,
+  rule_labels: Option<&'a RuleLabels>,
+  compiled: Option<&'a CompiledCddl<'a>>,
+  cddl_location: String,
+  explain_choices: bool,
+  overflow_behavior: OverflowBehavior,
+  regex_engine: RegexEngine,
+  #[cfg(feature = "additional-controls")]
+  disabled_feature_behavior: DisabledFeatureBehavior,
+  embedded_depth: usize,
+  max_embedded_depth: usize,
+  budget: Budget,
+  #[cfg(feature = "control-plugins")]
+  control_plugins: Option<&'a ControlPlugins>,
+  #[cfg(feature = "std")]
+  extended_prelude: Option<&'a ExtendedPrelude>,
+}
+
+impl<'a> ValidatorSettings<'a> {
+  // Validator of the given data item with these settings
+  fn validator<'b>(self, cbor: Cow<'b, Value>) -> Box<CBORValidator<'b>>
+  where
+    'a: 'b,
+  {
+    #[cfg(feature = "additional-controls")]
+    let mut cv = CBORValidator::new(self.cddl, Value::Null, self.enabled_features);
+    #[cfg(not(feature = "additional-controls"))]
+    let mut cv = CBORValidator::new(self.cddl, Value::Null);
+
+    cv.cbor = cbor;
+    cv.generic_rules = self.generic_rules;
+    cv.eval_generic_rule = self.eval_generic_rule;
+    cv.rule_labels = self.rule_labels;
+    cv.compiled = self.compiled;
+    cv.cddl_location = self.cddl_location;
+    cv.explain_choices = self.explain_choices;
+    cv.overflow_behavior = self.overflow_behavior;
+    cv.regex_engine = self.regex_engine;
+    #[cfg(feature = "additional-controls")]
+    {
+      cv.disabled_feature_behavior = self.disabled_feature_behavior;
+    }
+    cv.embedded_depth = self.embedded_depth;
+    cv.max_embedded_depth = self.max_embedded_depth;
+    cv.budget = self.budget;
+    #[cfg(feature = "control-plugins")]
+    {
+      cv.control_plugins = self.control_plugins;
+    }
+    #[cfg(feature = "std")]
+    {
+      cv.extended_prelude = self.extended_prelude;
+    }
+
+    Box::new(cv)
+  }
+}
+
 impl<'a> CBORValidator<'a> {
   #[cfg(not(target_arch = "wasm32"))]
   #[cfg(feature = "additional-controls")]
@@ -210,13 +583,15 @@ impl<'a> CBORValidator<'a> {
   pub fn new(cddl: &'a CDDL<'a>, cbor: Value, enabled_features: Option<&'a [&'a str]>) -> Self {
     CBORValidator {
       cddl,
-      cbor,
+      cbor: Cow::Owned(cbor),
       errors: Vec::default(),
       cddl_location: String::new(),
+      #[cfg(feature = "ast-span")]
+      cddl_span: None,
       cbor_location: String::new(),
       occurrence: None,
       group_entry_idx: None,
-      object_value: None,
+      object_entry_idx: None,
       is_member_key: false,
       is_cut_present: false,
       cut_value: None,
@@ -231,15 +606,35 @@ impl<'a> CBORValidator<'a> {
       is_ctrl_map_equality: false,
       entry_counts: None,
       validated_keys: None,
-      values_to_validate: None,
+      is_cut_matched: false,
+      unmatched_key_errors: Vec::new(),
+      entries_to_validate: None,
       validating_value: false,
       valid_array_items: None,
       array_errors: None,
       is_colon_shortcut_present: false,
       is_root: false,
+      rule_labels: None,
+      compiled: None,
+      labeled_paths: Vec::new(),
+      explain_choices: false,
+      overflow_behavior: OverflowBehavior::default(),
+      regex_engine: RegexEngine::default(),
+      embedded_depth: 0,
+      max_embedded_depth: DEFAULT_MAX_EMBEDDED_DEPTH,
+      budget: Budget::default(),
+      #[cfg(feature = "control-plugins")]
+      control_plugins: None,
+      #[cfg(feature = "std")]
+      extended_prelude: None,
+      cbor_slice: None,
+      deterministic_encoding: false,
+      memo: None,
+      choice_explanations: Vec::new(),
+      feature_uses: Vec::new(),
+      root_rule: None,
       enabled_features,
-      has_feature_errors: false,
-      disabled_features: None,
+      disabled_feature_behavior: DisabledFeatureBehavior::default(),
     }
   }
 
@@ -249,13 +644,15 @@ impl<'a> CBORValidator<'a> {
   pub fn new(cddl: &'a CDDL<'a>, cbor: Value) -> Self {
     CBORValidator {
       cddl,
-      cbor,
+      cbor: Cow::Owned(cbor),
       errors: Vec::default(),
       cddl_location: String::new(),
+      #[cfg(feature = "ast-span")]
+      cddl_span: None,
       cbor_location: String::new(),
       occurrence: None,
       group_entry_idx: None,
-      object_value: None,
+      object_entry_idx: None,
       is_member_key: false,
       is_cut_present: false,
       cut_value: None,
@@ -270,12 +667,33 @@ impl<'a> CBORValidator<'a> {
       is_ctrl_map_equality: false,
       entry_counts: None,
       validated_keys: None,
-      values_to_validate: None,
+      is_cut_matched: false,
+      unmatched_key_errors: Vec::new(),
+      entries_to_validate: None,
       validating_value: false,
       valid_array_items: None,
       array_errors: None,
       is_colon_shortcut_present: false,
       is_root: false,
+      rule_labels: None,
+      compiled: None,
+      labeled_paths: Vec::new(),
+      explain_choices: false,
+      overflow_behavior: OverflowBehavior::default(),
+      regex_engine: RegexEngine::default(),
+      embedded_depth: 0,
+      max_embedded_depth: DEFAULT_MAX_EMBEDDED_DEPTH,
+      budget: Budget::default(),
+      #[cfg(feature = "control-plugins")]
+      control_plugins: None,
+      #[cfg(feature = "std")]
+      extended_prelude: None,
+      cbor_slice: None,
+      deterministic_encoding: false,
+      memo: None,
+      choice_explanations: Vec::new(),
+      feature_uses: Vec::new(),
+      root_rule: None,
     }
   }
 
@@ -285,13 +703,15 @@ impl<'a> CBORValidator<'a> {
   pub fn new(cddl: &'a CDDL<'a>, cbor: Value, enabled_features: Option<Box<[JsValue]>>) -> Self {
     CBORValidator {
       cddl,
-      cbor,
+      cbor: Cow::Owned(cbor),
       errors: Vec::default(),
       cddl_location: String::new(),
+      #[cfg(feature = "ast-span")]
+      cddl_span: None,
       cbor_location: String::new(),
       occurrence: None,
       group_entry_idx: None,
-      object_value: None,
+      object_entry_idx: None,
       is_member_key: false,
       is_cut_present: false,
       cut_value: None,
@@ -306,15 +726,35 @@ impl<'a> CBORValidator<'a> {
       is_ctrl_map_equality: false,
       entry_counts: None,
       validated_keys: None,
-      values_to_validate: None,
+      is_cut_matched: false,
+      unmatched_key_errors: Vec::new(),
+      entries_to_validate: None,
       validating_value: false,
       valid_array_items: None,
       array_errors: None,
       is_colon_shortcut_present: false,
       is_root: false,
+      rule_labels: None,
+      compiled: None,
+      labeled_paths: Vec::new(),
+      explain_choices: false,
+      overflow_behavior: OverflowBehavior::default(),
+      regex_engine: RegexEngine::default(),
+      embedded_depth: 0,
+      max_embedded_depth: DEFAULT_MAX_EMBEDDED_DEPTH,
+      budget: Budget::default(),
+      #[cfg(feature = "control-plugins")]
+      control_plugins: None,
+      #[cfg(feature = "std")]
+      extended_prelude: None,
+      cbor_slice: None,
+      deterministic_encoding: false,
+      memo: None,
+      choice_explanations: Vec::new(),
+      feature_uses: Vec::new(),
+      root_rule: None,
       enabled_features,
-      has_feature_errors: false,
-      disabled_features: None,
+      disabled_feature_behavior: DisabledFeatureBehavior::default(),
     }
   }
 
@@ -324,13 +764,15 @@ impl<'a> CBORValidator<'a> {
   pub fn new(cddl: &'a CDDL<'a>, cbor: Value) -> Self {
     CBORValidator {
       cddl,
-      cbor,
+      cbor: Cow::Owned(cbor),
       errors: Vec::default(),
       cddl_location: String::new(),
+      #[cfg(feature = "ast-span")]
+      cddl_span: None,
       cbor_location: String::new(),
       occurrence: None,
       group_entry_idx: None,
-      object_value: None,
+      object_entry_idx: None,
       is_member_key: false,
       is_cut_present: false,
       cut_value: None,
@@ -345,2631 +787,3740 @@ impl<'a> CBORValidator<'a> {
       is_ctrl_map_equality: false,
       entry_counts: None,
       validated_keys: None,
-      values_to_validate: None,
+      is_cut_matched: false,
+      unmatched_key_errors: Vec::new(),
+      entries_to_validate: None,
       validating_value: false,
       valid_array_items: None,
       array_errors: None,
       is_colon_shortcut_present: false,
       is_root: false,
+      rule_labels: None,
+      compiled: None,
+      labeled_paths: Vec::new(),
+      explain_choices: false,
+      overflow_behavior: OverflowBehavior::default(),
+      regex_engine: RegexEngine::default(),
+      embedded_depth: 0,
+      max_embedded_depth: DEFAULT_MAX_EMBEDDED_DEPTH,
+      budget: Budget::default(),
+      #[cfg(feature = "control-plugins")]
+      control_plugins: None,
+      #[cfg(feature = "std")]
+      extended_prelude: None,
+      cbor_slice: None,
+      deterministic_encoding: false,
+      memo: None,
+      choice_explanations: Vec::new(),
+      feature_uses: Vec::new(),
+      root_rule: None,
     }
   }
-}
 
-impl<'a, T: std::fmt::Debug + 'static> Validator<'a, cbor::Error<T>> for CBORValidator<'a>
-where
-  cbor::Error<T>: From<cbor::Error<std::io::Error>>,
-{
-  fn validate(&mut self) -> std::result::Result<(), cbor::Error<T>> {
-    for r in self.cddl.rules.iter() {
-      // First type rule is root
-      if let Rule::Type { rule, .. } = r {
-        if rule.generic_params.is_none() {
-          self.is_root = true;
-          self.visit_type_rule(rule)?;
-          self.is_root = false;
-          break;
-        }
-      }
+  /// Set the labels attached to rules via `;@label` annotations. Locations in
+  /// the CBOR data that successfully validate against a labeled rule are
+  /// collected during validation
+  pub fn set_rule_labels(&mut self, rule_labels: &'a RuleLabels) {
+    self.rule_labels = Some(rule_labels);
+  }
+
+  /// Validate against the named rule rather than the first type rule in the
+  /// document. Generic rules must be given generic arguments, e.g. the
+  /// arguments parsed from `message<tstr, uint>`
+  pub fn set_root_rule(&mut self, name: &'a str, generic_args: Option<GenericArgs<'a>>) {
+    self.root_rule = Some((name, generic_args));
+  }
+
+  /// Look up rules and precompiled controllers in a compiled document rather
+  /// than the document the validator was created with. The compiled document
+  /// must be the one the validator was created with
+  pub fn set_compiled(&mut self, compiled: &'a CompiledCddl<'a>) {
+    self.compiled = Some(compiled);
+  }
+
+  /// Labeled locations collected during validation
+  pub fn labeled_paths(&self) -> &[LabeledPath] {
+    &self.labeled_paths
+  }
+
+  /// Explain, for each location validated against a type choice, which choice
+  /// matched and why the choices preceding it did not
+  pub fn set_explain_choices(&mut self, explain_choices: bool) {
+    self.explain_choices = explain_choices;
+  }
+
+  /// Explanations of the type choices matched during validation
+  pub fn choice_explanations(&self) -> &[ChoiceExplanation] {
+    &self.choice_explanations
+  }
+
+  /// Set the behavior of the integer arithmetic of control operators such as
+  /// `.plus` on overflow. Defaults to reporting overflow as a validation error
+  pub fn set_overflow_behavior(&mut self, overflow_behavior: OverflowBehavior) {
+    self.overflow_behavior = overflow_behavior;
+  }
+
+  /// Set the behavior of the `.feature` control operator when its target
+  /// matches but the feature isn't among the enabled features. Defaults to
+  /// rejecting the match. All features are enabled if no enabled features were
+  /// given
+  #[cfg(feature = "additional-controls")]
+  pub fn set_disabled_feature_behavior(&mut self, behavior: DisabledFeatureBehavior) {
+    self.disabled_feature_behavior = behavior;
+  }
+
+  /// Uses of features by the locations validated, i.e. matches of the targets
+  /// of `.feature` control operators
+  pub fn feature_uses(&self) -> &[FeatureUse] {
+    &self.feature_uses
+  }
+
+  /// Set the engine matching the `.regexp` and `.pcre` control operators.
+  /// Defaults to the linear-time engine of the `regex` crate
+  pub fn set_regex_engine(&mut self, regex_engine: RegexEngine) {
+    self.regex_engine = regex_engine;
+  }
+
+  /// Set the maximum nesting depth of CBOR embedded in byte strings with the
+  /// `.cbor` and `.cborseq` control operators. Deeper payloads are reported as
+  /// validation errors rather than decoded. Defaults to
+  /// `DEFAULT_MAX_EMBEDDED_DEPTH`
+  pub fn set_max_embedded_depth(&mut self, max_embedded_depth: usize) {
+    self.max_embedded_depth = max_embedded_depth;
+  }
+
+  /// Set the limits on the resources used to validate the data. Validation
+  /// exceeding them fails with `Error::LimitExceeded`. Defaults to
+  /// `ValidationLimits::default()`
+  pub fn set_limits(&mut self, limits: ValidationLimits) {
+    self.budget = Budget::new(limits);
+  }
+
+  /// Memoize the rules that arrays, maps and tagged items of the CBOR data
+  /// item are valid against, so that identical subtrees are only validated
+  /// once. Identical subtrees are found in a pass over the data item before
+  /// validation, which pays off for data items repeating large subtrees.
+  /// Disabled by default
+  pub fn set_memoize(&mut self, memoize: bool) {
+    self.memo = if memoize { Some(Rc::default()) } else { None };
+  }
+
+  /// Set the plugins implementing control operators defined neither by RFC
+  /// 8610 nor RFC 9165. Such control operators are otherwise reported as
+  /// unsupported
+  #[cfg(feature = "control-plugins")]
+  pub fn set_control_plugins(&mut self, control_plugins: &'a ControlPlugins) {
+    self.control_plugins = Some(control_plugins);
+  }
+
+  /// Set the types, beyond those of the standard prelude, that the document may
+  /// reference without defining them. Rules defined by the document and types
+  /// of the standard prelude take precedence
+  #[cfg(feature = "std")]
+  pub fn set_extended_prelude(&mut self, extended_prelude: &'a ExtendedPrelude) {
+    self.extended_prelude = Some(extended_prelude);
+  }
+
+  /// Set the encoding of the CBOR data item the validator was created with.
+  /// Validation errors then give the byte range of the offending data item
+  /// within the encoding (see
+  /// [`byte_range`](super::offsets::byte_range))
+  pub fn set_cbor_slice(&mut self, cbor_slice: &'a [u8]) {
+    self.cbor_slice = Some(cbor_slice);
+  }
+
+  /// In addition to conformance to the CDDL document, check that the given
+  /// encoding of the CBOR data item uses the core deterministic encoding of
+  /// RFC 8949 (see [`check_deterministic_encoding`]). Violations are reported
+  /// as validation errors giving the byte offset of the offending item
+  pub fn set_deterministic_encoding(&mut self, cbor_slice: &'a [u8]) {
+    self.cbor_slice = Some(cbor_slice);
+    self.deterministic_encoding = true;
+  }
+
+  // Validate the target of a `.feature` control operator, recording the use of
+  // the feature if the target matches. Matches of features that aren't enabled
+  // are either rejected or recorded, depending on the disabled feature behavior
+  #[cfg(feature = "additional-controls")]
+  fn validate_feature<T: std::fmt::Debug + 'static>(
+    &mut self,
+    target: &Type2<'a>,
+    controller: &Type2<'a>,
+  ) -> visitor::Result<Error<T>>
+  where
+    cbor::Error<T>: From<cbor::Error<ReaderError>>,
+  {
+    let feature = match text_value_from_type2(self.cddl, controller) {
+      Some(Type2::TextValue { value, .. }) => value.to_string(),
+      Some(Type2::UTF8ByteString { value, .. }) => std::str::from_utf8(value)
+        .map_err(Error::UTF8Parsing)?
+        .to_string(),
+      _ => return <Self as Visitor<Error<T>>>::visit_type2(self, target),
+    };
+
+    let error_count = self.errors.len();
+    <Self as Visitor<Error<T>>>::visit_type2(self, target)?;
+    if self.errors.len() > error_count {
+      return Ok(());
     }
 
-    if !self.errors.is_empty() {
-      return Err(Error::Validation(self.errors.clone()));
+    // As a member key, the target is only used if it matched any entries
+    if self.is_member_key
+      && matches!(*self.cbor, Value::Map(_))
+      && self.object_entry_idx.is_none()
+      && self
+        .entries_to_validate
+        .as_ref()
+        .is_none_or(|values| values.is_empty())
+    {
+      return Ok(());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let enabled = self
+      .enabled_features
+      .is_none_or(|ef| ef.contains(&feature.as_str()));
+    #[cfg(target_arch = "wasm32")]
+    let enabled = self
+      .enabled_features
+      .as_ref()
+      .is_none_or(|ef| ef.contains(&JsValue::from(feature.as_str())));
+
+    if !enabled && self.disabled_feature_behavior == DisabledFeatureBehavior::Reject {
+      self.add_error(format!("feature {} is not enabled", feature));
+      return Ok(());
     }
 
+    self.feature_uses.push(FeatureUse {
+      location: self.cbor_location.clone(),
+      feature,
+      enabled,
+    });
+
     Ok(())
   }
 
-  fn add_error(&mut self, reason: String) {
-    self.errors.push(ValidationError {
-      reason,
+  // Validate the data item against the controller of a control operator
+  // implemented by a plugin
+  #[cfg(feature = "control-plugins")]
+  fn validate_control_plugin(&mut self, target: &Type2, ctrl: &str, controller: &Type2) {
+    let plugin = match self.control_plugins.and_then(|plugins| plugins.get(ctrl)) {
+      Some(plugin) => plugin,
+      None => return,
+    };
+
+    let mut data = Vec::new();
+    if let Err(e) = ciborium::ser::into_writer(&*self.cbor, &mut data) {
+      self.add_error(format!(
+        "error encoding data item for control operator {}: {}",
+        ctrl, e
+      ));
+      return;
+    }
+
+    match plugin.validate(Encoding::CBOR, &data, &controller.to_string()) {
+      Ok(true) => (),
+      Ok(false) => self.add_error(format!(
+        "expected {} {} {}, got {:?}",
+        target, ctrl, controller, self.cbor
+      )),
+      Err(e) => self.add_error(format!("error validating control operator {}: {}", ctrl, e)),
+    }
+  }
+
+  // Settings of this validator inherited by the validators of nested data
+  // items
+  fn settings(&self) -> ValidatorSettings<'a> {
+    ValidatorSettings {
+      cddl: self.cddl,
+      #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
+      enabled_features: self.enabled_features.clone(),
+      #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
+      enabled_features: self.enabled_features,
+      generic_rules: self.generic_rules.clone(),
+      eval_generic_rule: self.eval_generic_rule.clone(),
+      rule_labels: self.rule_labels,
+      compiled: self.compiled,
       cddl_location: self.cddl_location.clone(),
-      cbor_location: self.cbor_location.clone(),
-      is_multi_type_choice: self.is_multi_type_choice,
-      is_multi_group_choice: self.is_multi_group_choice,
-      is_group_to_choice_enum: self.is_group_to_choice_enum,
-      type_group_name_entry: self.type_group_name_entry.map(|e| e.to_string()),
-    });
+      explain_choices: self.explain_choices,
+      overflow_behavior: self.overflow_behavior,
+      regex_engine: self.regex_engine,
+      #[cfg(feature = "additional-controls")]
+      disabled_feature_behavior: self.disabled_feature_behavior,
+      embedded_depth: self.embedded_depth,
+      max_embedded_depth: self.max_embedded_depth,
+      budget: self.budget.clone(),
+      #[cfg(feature = "control-plugins")]
+      control_plugins: self.control_plugins,
+      #[cfg(feature = "std")]
+      extended_prelude: self.extended_prelude,
+    }
   }
-}
 
-impl<'a, T: std::fmt::Debug + 'static> Visitor<'a, Error<T>> for CBORValidator<'a>
-where
-  cbor::Error<T>: From<cbor::Error<std::io::Error>>,
-{
-  fn visit_type_rule(&mut self, tr: &TypeRule<'a>) -> visitor::Result<Error<T>> {
-    if let Some(gp) = &tr.generic_params {
-      if let Some(gr) = self
-        .generic_rules
-        .iter_mut()
-        .find(|r| r.name == tr.name.ident)
+  // Validator for a data item nested in the one being validated, e.g. an array
+  // item or a key or value of a map, inheriting the settings and memo of this
+  // validator. The data item is borrowed rather than copied, so the validator
+  // can't outlive the data item being validated
+  fn nested_validator<'b>(&self, cbor: &'b Value) -> Box<CBORValidator<'b>>
+  where
+    'a: 'b,
+  {
+    let mut cv = self.settings().validator(Cow::Borrowed(cbor));
+    cv.memo = self.memo.clone();
+
+    cv
+  }
+
+  // Validator for a data item nested in the one being validated, e.g. a key or
+  // value of a map or the content of a tag, inheriting the settings and state
+  // of this validator
+  fn child_validator<'b>(&self, cbor: &'b Value) -> Box<CBORValidator<'b>>
+  where
+    'a: 'b,
+  {
+    let mut cv = self.nested_validator(cbor);
+
+    cv.is_multi_type_choice = self.is_multi_type_choice;
+    cv.is_multi_group_choice = self.is_multi_group_choice;
+    cv.type_group_name_entry = self.type_group_name_entry.clone();
+
+    cv
+  }
+
+  // Validate the entries of a map matching a member key with a repeated
+  // occurrence, e.g. `* int => any`. Each key not matched by a preceding entry
+  // is checked against the key type, so that only keys of that type are
+  // accepted and the others are left to the entries that follow or reported
+  // as unexpected
+  fn validate_repeated_map_entries<T: std::fmt::Debug + 'static>(
+    &mut self,
+    entry: &ValueMemberKeyEntry<'a>,
+    key_type: &Type1<'a>,
+    lower: usize,
+    upper: Option<usize>,
+  ) -> visitor::Result<Error<T>>
+  where
+    Error<T>: From<Error<ReaderError>>,
+  {
+    let m = match &*self.cbor {
+      Value::Map(m) => m,
+      _ => return Ok(()),
+    };
+
+    let current_location = self.cbor_location.clone();
+    let mut matched = 0;
+
+    for (k, v) in m.iter() {
+      if upper.is_some_and(|upper| matched >= upper)
+        || self
+          .validated_keys
+          .as_ref()
+          .is_some_and(|keys| keys.contains(k))
       {
-        gr.params = gp.params.iter().map(|p| p.param.ident).collect();
-      } else {
-        self.generic_rules.push(GenericRule {
-          name: tr.name.ident,
-          params: gp.params.iter().map(|p| p.param.ident).collect(),
-          args: vec![],
-        });
+        continue;
       }
-    }
 
-    let error_count = self.errors.len();
+      let mut cv = self.child_validator(k);
+      <CBORValidator as Visitor<Error<T>>>::visit_type1(&mut cv, key_type)?;
+      if !cv.errors.is_empty() {
+        continue;
+      }
+      self.feature_uses.append(&mut cv.feature_uses);
+
+      matched += 1;
+      self
+        .validated_keys
+        .get_or_insert_with(Vec::new)
+        .push(k.clone());
+
+      let mut cv = self.child_validator(v);
+      cv.cbor_location = match k {
+        Value::Text(key) => format!("{}/{:?}", current_location, key),
+        Value::Integer(key) => format!("{}/{}", current_location, i128::from(*key)),
+        _ => format!("{}/{:?}", current_location, k),
+      };
+      cv.validating_value = true;
+      <CBORValidator as Visitor<Error<T>>>::visit_type(&mut cv, &entry.entry_type)?;
+
+      if cv.errors.is_empty() {
+        self.labeled_paths.append(&mut cv.labeled_paths);
+        self.choice_explanations.append(&mut cv.choice_explanations);
+        self.feature_uses.append(&mut cv.feature_uses);
+      }
 
-    for t in type_choice_alternates_from_ident(self.cddl, &tr.name) {
-      let cur_errors = self.errors.len();
-      self.visit_type(t)?;
-      if self.errors.len() == cur_errors {
-        for _ in 0..self.errors.len() - error_count {
-          self.errors.pop();
-        }
+      self.errors.append(&mut cv.errors);
+    }
 
-        return Ok(());
-      }
+    if matched < lower {
+      self.add_error(format!(
+        "expected at least {} map entries with key type {}, got {}",
+        lower, key_type, matched
+      ));
     }
 
+    self.occurrence = None;
+
     Ok(())
   }
 
-  fn visit_group_rule(&mut self, gr: &GroupRule<'a>) -> visitor::Result<Error<T>> {
-    if let Some(gp) = &gr.generic_params {
-      if let Some(gr) = self
-        .generic_rules
-        .iter_mut()
-        .find(|r| r.name == gr.name.ident)
-      {
-        gr.params = gp.params.iter().map(|p| p.param.ident).collect();
-      } else {
-        self.generic_rules.push(GenericRule {
-          name: gr.name.ident,
-          params: gp.params.iter().map(|p| p.param.ident).collect(),
-          args: vec![],
-        });
-      }
+  // Key under which the outcome of validating the CBOR data item against the
+  // type rule is memoized. Only outcomes that don't depend on the state of the
+  // surrounding validation, and whose validation has no side effects beyond
+  // errors and feature uses, are memoized. Invalid items are always
+  // revalidated so that their errors are reported at each location
+  fn memo_key(&self, tr: &TypeRule<'a>) -> Option<MemoKey> {
+    let memo = self.memo.as_ref()?;
+    if tr.generic_params.is_some()
+      || self.eval_generic_rule.is_some()
+      || self.ctrl.is_some()
+      || self.occurrence.is_some()
+      || self.is_member_key
+      || self.is_cut_present
+      || self.is_multi_type_choice
+      || self.is_multi_group_choice
+      || self.is_root
+      || self.object_entry_idx.is_some()
+      || self.entry_counts.is_some()
+      || self.group_entry_idx.is_some()
+      || self.entries_to_validate.is_some()
+      || self.validated_keys.is_some()
+      || self.valid_array_items.is_some()
+      || self.array_errors.is_some()
+      || self.rule_labels.is_some()
+      || self.explain_choices
+    {
+      return None;
     }
 
-    let error_count = self.errors.len();
+    // Data items that aren't part of the CBOR data item the validator was
+    // created with, e.g. embedded CBOR, don't have an identifier
+    let subtree_id = *memo
+      .borrow()
+      .subtree_ids
+      .get(&(&*self.cbor as *const Value as usize))?;
 
-    for ge in group_choice_alternates_from_ident(self.cddl, &gr.name) {
-      let cur_errors = self.errors.len();
-      self.visit_group_entry(ge)?;
-      if self.errors.len() == cur_errors {
-        for _ in 0..self.errors.len() - error_count {
-          self.errors.pop();
-        }
+    Some((tr.name.to_string(), subtree_id))
+  }
 
-        return Ok(());
-      }
+  // Record the outcome of validating against the type choice at the given
+  // index. Choices failing before the first match are collected as rejected
+  // choices, which are moved into the explanation once a choice matches
+  fn explain_choice(
+    &mut self,
+    t: &Type<'a>,
+    idx: usize,
+    error_count: usize,
+    explanation_count: usize,
+    rejected: &mut Option<Vec<RejectedChoice>>,
+  ) {
+    if !self.explain_choices || t.type_choices.len() < 2 {
+      return;
     }
 
-    Ok(())
+    let choice = t.type_choices[idx].type1.to_string();
+    self.record_choice(idx, choice, false, error_count, explanation_count, rejected);
   }
 
-  fn visit_type(&mut self, t: &Type<'a>) -> visitor::Result<Error<T>> {
-    if t.type_choices.len() > 1 {
-      self.is_multi_type_choice = true;
+  // Explain the match of the group choice of the given index as with
+  // `explain_choice`
+  fn explain_group_choice(
+    &mut self,
+    g: &Group<'a>,
+    idx: usize,
+    error_count: usize,
+    explanation_count: usize,
+    rejected: &mut Option<Vec<RejectedChoice>>,
+  ) {
+    if !self.explain_choices || g.group_choices.len() < 2 {
+      return;
     }
 
-    let initial_error_count = self.errors.len();
-    for type_choice in t.type_choices.iter() {
-      // If validating an array whose elements are type choices (i.e. [ 1* tstr
-      // / integer ]), collect all errors and filter after the fact
-      if matches!(self.cbor, Value::Array(_)) {
-        let error_count = self.errors.len();
-
-        self.visit_type_choice(type_choice)?;
+    let choice = g.group_choices[idx].to_string().trim().to_string();
+    self.record_choice(idx, choice, true, error_count, explanation_count, rejected);
+  }
 
-        #[cfg(feature = "additional-controls")]
-        if self.errors.len() == error_count
-          && !self.has_feature_errors
-          && self.disabled_features.is_none()
-        {
-          // Disregard invalid type choice validation errors if one of the
-          // choices validates successfully
-          let type_choice_error_count = self.errors.len() - initial_error_count;
-          if type_choice_error_count > 0 {
-            for _ in 0..type_choice_error_count {
-              self.errors.pop();
-            }
-          }
-        }
-
-        #[cfg(not(feature = "additional-controls"))]
-        if self.errors.len() == error_count {
-          // Disregard invalid type choice validation errors if one of the
-          // choices validates successfully
-          let type_choice_error_count = self.errors.len() - initial_error_count;
-          if type_choice_error_count > 0 {
-            for _ in 0..type_choice_error_count {
-              self.errors.pop();
-            }
-          }
-        }
-
-        continue;
+  fn record_choice(
+    &mut self,
+    idx: usize,
+    choice: String,
+    is_group_choice: bool,
+    error_count: usize,
+    explanation_count: usize,
+    rejected: &mut Option<Vec<RejectedChoice>>,
+  ) {
+    match rejected {
+      Some(choices) if self.errors.len() != error_count => {
+        self.choice_explanations.truncate(explanation_count);
+        choices.push(RejectedChoice {
+          index: idx,
+          choice,
+          reasons: self.errors[error_count..]
+            .iter()
+            .map(|e| e.reason.clone())
+            .collect(),
+        });
       }
+      Some(choices) => {
+        self.choice_explanations.push(ChoiceExplanation {
+          location: self.cbor_location.clone(),
+          rule: self.cddl_location.clone(),
+          index: idx,
+          choice,
+          is_group_choice,
+          rejected: std::mem::take(choices),
+        });
+        *rejected = None;
+      }
+      // Only the first matching choice is explained
+      None => self.choice_explanations.truncate(explanation_count),
+    }
+  }
 
-      let error_count = self.errors.len();
-      self.visit_type_choice(type_choice)?;
+  // Find non-choice alternate rule from a given identifier, using the rule
+  // index of the compiled document if any
+  fn lookup_rule(&self, ident: &Identifier) -> Option<&'a Rule<'a>> {
+    match self.compiled {
+      Some(compiled) => compiled.rule_from_ident(ident),
+      None => rule_from_ident(self.cddl, ident),
+    }
+  }
 
-      #[cfg(feature = "additional-controls")]
-      if self.errors.len() == error_count
-        && !self.has_feature_errors
-        && self.disabled_features.is_none()
-      {
-        // Disregard invalid type choice validation errors if one of the
-        // choices validates successfully
-        let type_choice_error_count = self.errors.len() - initial_error_count;
-        if type_choice_error_count > 0 {
-          for _ in 0..type_choice_error_count {
-            self.errors.pop();
-          }
-        }
+  // Indices of the array items a rule referenced from within an array group
+  // applies to. Returns `None` if no labels are being collected or the current
+  // location is not an array being validated against an array group
+  fn array_item_indices(&self) -> Option<Vec<usize>> {
+    if self.rule_labels.is_none() || self.entry_counts.is_none() {
+      return None;
+    }
 
-        return Ok(());
-      }
+    if let Value::Array(a) = &*self.cbor {
+      return match validate_array_occurrence(
+        self.occurrence.as_ref(),
+        self.entry_counts.as_ref().map(|ec| &ec[..]),
+        a,
+      ) {
+        Ok((true, _)) => Some((0..a.len()).collect()),
+        _ => Some(self.group_entry_idx.into_iter().collect()),
+      };
+    }
 
-      #[cfg(not(feature = "additional-controls"))]
-      if self.errors.len() == error_count {
-        // Disregard invalid type choice validation errors if one of the
-        // choices validates successfully
-        let type_choice_error_count = self.errors.len() - initial_error_count;
-        if type_choice_error_count > 0 {
-          for _ in 0..type_choice_error_count {
-            self.errors.pop();
+    None
+  }
+
+  // Rules referenced from within an array group are validated against the
+  // array items rather than the array itself, so labels are recorded for each
+  // item that validates successfully against the rule
+  fn collect_array_item_labels<T: std::fmt::Debug + 'static>(
+    &mut self,
+    ident: &Identifier<'a>,
+    indices: &[usize],
+    labeled_path_count: usize,
+  ) -> visitor::Result<Error<T>>
+  where
+    cbor::Error<T>: From<cbor::Error<ReaderError>>,
+  {
+    self.labeled_paths.truncate(labeled_path_count);
+
+    if let Value::Array(a) = &*self.cbor {
+      for idx in indices.iter() {
+        if let Some(v) = a.get(*idx) {
+          let mut cv = self.nested_validator(v);
+
+          cv.cbor_location
+            .push_str(&format!("{}/{}", self.cbor_location, idx));
+
+          cv.visit_identifier(ident)?;
+
+          if cv.errors.is_empty() {
+            self.labeled_paths.append(&mut cv.labeled_paths);
+            self.choice_explanations.append(&mut cv.choice_explanations);
+            self.feature_uses.append(&mut cv.feature_uses);
           }
         }
-
-        return Ok(());
       }
     }
 
     Ok(())
   }
 
-  fn visit_group(&mut self, g: &Group<'a>) -> visitor::Result<Error<T>> {
-    if g.group_choices.len() > 1 {
-      self.is_multi_group_choice = true;
+  // Record the labels of the given rule at the current location if it validated
+  // successfully, otherwise discard the labels collected while validating it
+  fn collect_labels(&mut self, ident: &Identifier, error_count: usize, labeled_path_count: usize) {
+    if self.errors.len() != error_count {
+      self.labeled_paths.truncate(labeled_path_count);
+      return;
     }
 
-    // Map equality/inequality validation
-    if self.is_ctrl_map_equality {
-      if let Some(t) = &self.ctrl {
-        if let Value::Map(m) = &self.cbor {
-          let mut entry_counts = Vec::new();
-          for gc in g.group_choices.iter() {
-            let count = entry_counts_from_group_choice(self.cddl, gc);
-            entry_counts.push(count);
+    if let Some(labels) = self.rule_labels.and_then(|l| l.get(&ident.to_string())) {
+      self.labeled_paths.push(LabeledPath {
+        location: self.cbor_location.clone(),
+        rule: ident.to_string(),
+        labels: labels.clone(),
+      });
+    }
+  }
+
+  // Match the items of the array being validated against a plan compiled from
+  // its group. Returns whether the array matched, recording an error for the
+  // offending item if it didn't
+  fn validate_array_plan<T: std::fmt::Debug + 'static>(
+    &mut self,
+    plan: &ArrayPlan<'a, '_>,
+  ) -> std::result::Result<bool, Error<T>>
+  where
+    cbor::Error<T>: From<cbor::Error<ReaderError>>,
+  {
+    let len = match &*self.cbor {
+      Value::Array(a) => a.len(),
+      _ => return Ok(false),
+    };
+
+    // Errors of the item currently being matched, and labels and choice
+    // explanations of each successful match keyed by item and matcher index
+    let mut item_errors = Vec::new();
+    let mut item_labels = BTreeMap::new();
+    let mut current_idx = 0;
+
+    // The items of large arrays matched against a single matcher, e.g.
+    // `[* record]`, are validated across threads up front
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    let mut outcomes = match &*self.cbor {
+      Value::Array(a) if plan.matcher_count() == 1 && self.validates_in_parallel(a) => self
+        .validate_array_items_in_parallel(a, |cv| match plan.matcher(0) {
+          Matcher::Type(t) => <CBORValidator as Visitor<Error<ReaderError>>>::visit_type(cv, t),
+          Matcher::Name(ident) => {
+            <CBORValidator as Visitor<Error<ReaderError>>>::visit_identifier(cv, ident)
           }
-          let len = m.len();
-          if let Token::EQ | Token::NE = t {
-            if !validate_entry_count(&entry_counts, len) {
-              for ec in entry_counts.iter() {
-                if let Some(occur) = &ec.entry_occurrence {
-                  self.add_error(format!(
-                    "expecting array with length per occurrence {}",
-                    occur,
-                  ));
-                } else {
-                  self.add_error(format!(
-                    "expecting array with length {}, got {}",
-                    ec.count, len
-                  ));
-                }
-              }
-              return Ok(());
-            }
+        })?
+        .into_iter()
+        .map(Some)
+        .collect(),
+      _ => Vec::new(),
+    };
+
+    let outcome = plan.run(len, |idx, matcher| -> std::result::Result<bool, Error<T>> {
+      #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+      let outcome = outcomes.get_mut(idx).and_then(Option::take);
+      #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+      let outcome = None;
+
+      let (mut errors, labeled_paths, choice_explanations, feature_uses) = match outcome {
+        Some(outcome) => outcome,
+        None => {
+          let v = match &*self.cbor {
+            Value::Array(a) => &a[idx],
+            _ => return Ok(false),
+          };
+
+          let mut cv = self.nested_validator(v);
+
+          cv.is_multi_type_choice = self.is_multi_type_choice;
+          cv.cbor_location
+            .push_str(&format!("{}/{}", self.cbor_location, idx));
+
+          match plan.matcher(matcher) {
+            Matcher::Type(t) => cv.visit_type(t)?,
+            Matcher::Name(ident) => cv.visit_identifier(ident)?,
           }
+
+          (
+            cv.errors,
+            cv.labeled_paths,
+            cv.choice_explanations,
+            cv.feature_uses,
+          )
         }
+      };
+
+      if idx != current_idx {
+        item_errors.clear();
+        current_idx = idx;
       }
-    }
 
-    self.is_ctrl_map_equality = false;
+      if !errors.is_empty() {
+        item_errors.append(&mut errors);
+        return Ok(false);
+      }
 
-    let initial_error_count = self.errors.len();
-    for group_choice in g.group_choices.iter() {
-      let error_count = self.errors.len();
-      self.visit_group_choice(group_choice)?;
-      if self.errors.len() == error_count {
-        // Disregard invalid group choice validation errors if one of the
-        // choices validates successfully
-        let group_choice_error_count = self.errors.len() - initial_error_count;
-        if group_choice_error_count > 0 {
-          for _ in 0..group_choice_error_count {
-            self.errors.pop();
+      if !labeled_paths.is_empty() || !choice_explanations.is_empty() || !feature_uses.is_empty() {
+        item_labels.insert(
+          (idx, matcher),
+          (labeled_paths, choice_explanations, feature_uses),
+        );
+      }
+
+      Ok(true)
+    })?;
+
+    match outcome {
+      PlanOutcome::Matched(path) => {
+        for (idx, matcher) in path.into_iter().enumerate() {
+          if let Some((mut labels, mut explanations, mut feature_uses)) =
+            item_labels.remove(&(idx, matcher))
+          {
+            self.labeled_paths.append(&mut labels);
+            self.choice_explanations.append(&mut explanations);
+            self.feature_uses.append(&mut feature_uses);
           }
         }
 
+        Ok(true)
+      }
+      PlanOutcome::Unexpected { index, candidates } => {
+        if candidates.is_empty() {
+          <Self as Validator<Error<T>>>::add_error(
+            self,
+            format!(
+              "expected end of array at index {}, got {} items",
+              index, len
+            ),
+          );
+        } else {
+          self.errors.append(&mut item_errors);
+        }
+
+        Ok(false)
+      }
+      PlanOutcome::Incomplete(expected) => {
+        let expected = expected
+          .into_iter()
+          .map(|matcher| plan.matcher(matcher).to_string())
+          .collect::<Vec<_>>();
+
+        <Self as Validator<Error<T>>>::add_error(
+          self,
+          format!("expected type {} at index {}", expected.join(" or "), len),
+        );
+
+        Ok(false)
+      }
+    }
+  }
+
+  fn validate_root_rule<T: std::fmt::Debug + 'static>(
+    &mut self,
+    name: &'a str,
+    generic_args: Option<GenericArgs<'a>>,
+  ) -> visitor::Result<Error<T>>
+  where
+    cbor::Error<T>: From<cbor::Error<ReaderError>>,
+  {
+    let cddl = self.cddl;
+    let rule = match cddl.rules.iter().find(|r| r.name() == name) {
+      Some(rule) => rule,
+      None => return Err(Error::MissingRule(name.to_string())),
+    };
+
+    let tr = match rule {
+      Rule::Type { rule, .. } => rule,
+      Rule::Group { .. } => {
+        <Self as Validator<Error<T>>>::add_error(
+          self,
+          format!(
+            "rule {} is a group rule and cannot be used as the root of a CBOR data item",
+            name
+          ),
+        );
         return Ok(());
       }
+    };
+
+    let param_count = tr.generic_params.as_ref().map_or(0, |gp| gp.params.len());
+    let arg_count = generic_args.as_ref().map_or(0, |ga| ga.args.len());
+    if param_count != arg_count {
+      <Self as Validator<Error<T>>>::add_error(
+        self,
+        format!(
+          "rule {} expects {} generic argument(s), got {}",
+          name, param_count, arg_count
+        ),
+      );
+      return Ok(());
+    }
+
+    self.is_root = true;
+    if let Some(ga) = generic_args {
+      self.visit_type2(&Type2::Typename {
+        ident: tr.name.clone(),
+        generic_args: Some(ga),
+        #[cfg(feature = "ast-span")]
+        span: tr.name.span,
+      })?;
+    } else {
+      self.visit_type_rule(tr)?;
     }
+    self.is_root = false;
+    self.collect_labels(&tr.name, 0, 0);
 
     Ok(())
   }
 
-  fn visit_group_choice(&mut self, gc: &GroupChoice<'a>) -> visitor::Result<Error<T>> {
-    if self.is_group_to_choice_enum {
-      let initial_error_count = self.errors.len();
-      for tc in type_choices_from_group_choice(self.cddl, gc).iter() {
-        let error_count = self.errors.len();
-        self.visit_type_choice(tc)?;
-        if self.errors.len() == error_count {
-          let type_choice_error_count = self.errors.len() - initial_error_count;
-          if type_choice_error_count > 0 {
-            for _ in 0..type_choice_error_count {
-              self.errors.pop();
-            }
+  fn validate_document<T: std::fmt::Debug + 'static>(&mut self) -> cbor::Result<T>
+  where
+    cbor::Error<T>: From<cbor::Error<ReaderError>>,
+  {
+    if let Some(memo) = &self.memo {
+      *memo.borrow_mut() = Memo::new(&self.cbor).unwrap_or_default();
+    }
+
+    if let Some((name, generic_args)) = self.root_rule.take() {
+      self.validate_root_rule(name, generic_args)?;
+    } else {
+      for r in self.cddl.rules.iter() {
+        // First type rule is root
+        if let Rule::Type { rule, .. } = r {
+          if rule.generic_params.is_none() {
+            self.is_root = true;
+            <Self as Visitor<Error<T>>>::visit_type_rule(self, rule)?;
+            self.is_root = false;
+            self.collect_labels(&rule.name, 0, 0);
+            break;
           }
-          return Ok(());
         }
       }
-
-      return Ok(());
     }
 
-    for (idx, ge) in gc.group_entries.iter().enumerate() {
-      self.group_entry_idx = Some(idx);
+    if let (true, Some(cbor_slice)) = (self.deterministic_encoding, self.cbor_slice) {
+      let offsets = ItemOffsets::new(cbor_slice);
+      for violation in check_deterministic_encoding(cbor_slice) {
+        // Bytes following the data item aren't indexed, so violations there
+        // don't have a byte range
+        let cbor_range = offsets
+          .as_ref()
+          .and_then(|offsets| offsets.item_range(violation.offset));
+        self.errors.push(ValidationError {
+          reason: violation.to_string(),
+          cddl_location: String::new(),
+          cbor_location: violation.cbor_location,
+          is_multi_type_choice: false,
+          is_multi_group_choice: false,
+          is_group_to_choice_enum: false,
+          type_group_name_entry: None,
+          #[cfg(feature = "ast-span")]
+          cddl_span: None,
+          cbor_range,
+        });
+      }
+    }
 
-      self.visit_group_entry(&ge.0)?;
+    if !self.errors.is_empty() {
+      return Err(Error::Validation(self.errors.clone()));
     }
 
     Ok(())
   }
 
-  fn visit_range(
-    &mut self,
-    lower: &Type2,
-    upper: &Type2,
-    is_inclusive: bool,
-  ) -> visitor::Result<Error<T>> {
-    if let Value::Array(a) = &self.cbor {
-      match validate_array_occurrence(
-        self.occurrence.as_ref().take(),
-        self.entry_counts.as_ref().map(|ec| &ec[..]),
-        a,
-      ) {
-        Ok((iter_items, allow_empty_array)) => {
-          if iter_items {
-            for (idx, v) in a.iter().enumerate() {
-              if let Some(indices) = &self.valid_array_items {
-                if self.is_multi_type_choice && indices.contains(&idx) {
-                  continue;
-                }
-              }
-
-              #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-              let mut cv = CBORValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-              #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-              let mut cv = CBORValidator::new(self.cddl, v.clone(), self.enabled_features);
-              #[cfg(not(feature = "additional-controls"))]
-              let mut cv = CBORValidator::new(self.cddl, v.clone());
+  // Whether or not to validate the given elements of an array across threads.
+  // Elements validated against type choices are tracked across the choices,
+  // so only homogeneous occurrences are. The worker threads have the default
+  // stack of spawned threads, which only fits the nesting allowed by the
+  // default depth limit, so elements that may nest deeper are validated on
+  // this thread
+  #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+  fn validates_in_parallel(&self, items: &[Value]) -> bool {
+    !self.is_multi_type_choice
+      && items.len() >= PARALLEL_THRESHOLD
+      && self.budget.remaining_depth() <= ValidationLimits::default().max_depth
+  }
 
-              cv.generic_rules = self.generic_rules.clone();
-              cv.eval_generic_rule = self.eval_generic_rule;
-              cv.ctrl = self.ctrl.clone();
-              cv.is_multi_type_choice = self.is_multi_type_choice;
-              cv.cbor_location
-                .push_str(&format!("{}/{}", self.cbor_location, idx));
+  // Validate each element of an array across threads, returning the outcomes
+  // in the order of the elements. The validator itself isn't shared between
+  // threads, so the settings inherited by the validators of the elements are
+  // copied out of it first, and each thread memoizes validated subtrees
+  // separately, starting from the subtrees validated so far
+  #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+  fn validate_array_items_in_parallel<'b, F>(
+    &self,
+    items: &'b [Value],
+    validate: F,
+  ) -> std::result::Result<Vec<ElementOutcome>, Error<ReaderError>>
+  where
+    'a: 'b,
+    F: Fn(&mut CBORValidator<'b>) -> visitor::Result<Error<ReaderError>> + Sync,
+  {
+    let settings = self.settings();
+    let cbor_location = &self.cbor_location;
+    let ctrl = &self.ctrl;
+    let memo = self.memo.as_ref().map(|memo| memo.borrow().clone());
+
+    let outcomes = items
+      .par_iter()
+      .enumerate()
+      .map_init(
+        || memo.clone().map(|memo| Rc::new(RefCell::new(memo))),
+        |memo, (idx, v)| {
+          let mut cv = settings.clone().validator(Cow::Borrowed(v));
+
+          cv.memo = memo.clone();
+          cv.ctrl = ctrl.clone();
+          cv.cbor_location
+            .push_str(&format!("{}/{}", cbor_location, idx));
+
+          validate(&mut cv).map(|_| {
+            (
+              cv.errors,
+              cv.labeled_paths,
+              cv.choice_explanations,
+              cv.feature_uses,
+            )
+          })
+        },
+      )
+      .collect::<Vec<_>>();
 
-              cv.visit_range(lower, upper, is_inclusive)?;
+    // Report the error of the first element that failed rather than that of
+    // whichever thread failed first
+    outcomes.into_iter().collect()
+  }
 
-              if self.is_multi_type_choice && cv.errors.is_empty() {
-                if let Some(indices) = &mut self.valid_array_items {
-                  indices.push(idx);
-                } else {
-                  self.valid_array_items = Some(vec![idx]);
-                }
-                continue;
-              }
+  // Validate the data items embedded in a byte string with the `.cbor` or
+  // `.cborseq` control operator
+  fn validate_embedded<T: std::fmt::Debug + 'static>(
+    &mut self,
+    t2: &Type2<'a>,
+    is_sequence: bool,
+  ) -> visitor::Result<Error<T>>
+  where
+    Error<T>: From<Error<ReaderError>>,
+  {
+    let ctrl = if is_sequence { ".cborseq" } else { ".cbor" };
+
+    if let Value::Bytes(b) = &*self.cbor {
+      if self.embedded_depth >= self.max_embedded_depth {
+        self.add_error(format!(
+          "embedded CBOR exceeds the maximum nesting depth of {}",
+          self.max_embedded_depth
+        ));
 
-              if let Some(errors) = &mut self.array_errors {
-                if let Some(error) = errors.get_mut(&idx) {
-                  error.append(&mut cv.errors);
-                } else {
-                  errors.insert(idx, cv.errors);
-                }
-              } else {
-                let mut errors = HashMap::new();
-                errors.insert(idx, cv.errors);
-                self.array_errors = Some(errors)
-              }
-            }
-          } else if let Some(idx) = self.group_entry_idx.take() {
-            if let Some(v) = a.get(idx) {
-              #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-              let mut cv = CBORValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-              #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-              let mut cv = CBORValidator::new(self.cddl, v.clone(), self.enabled_features);
-              #[cfg(not(feature = "additional-controls"))]
-              let mut cv = CBORValidator::new(self.cddl, v.clone());
-
-              cv.generic_rules = self.generic_rules.clone();
-              cv.eval_generic_rule = self.eval_generic_rule;
-              cv.is_multi_type_choice = self.is_multi_type_choice;
-              cv.ctrl = self.ctrl.clone();
-              cv.cbor_location
-                .push_str(&format!("{}/{}", self.cbor_location, idx));
+        return Ok(());
+      }
 
-              cv.visit_range(lower, upper, is_inclusive)?;
+      // A .cbor byte string holds exactly one data item, whereas the data
+      // items of a .cborseq byte string are validated as the elements of an
+      // array
+      let value = match decode_embedded_cbor(b, is_sequence) {
+        Ok(value) => value,
+        Err(e) => {
+          self.add_error(format!("error decoding embedded CBOR, {}", e));
 
-              self.errors.append(&mut cv.errors);
-            } else if !allow_empty_array {
-              self.add_error(format!("expected array item at index {}", idx));
-            }
-          } else {
-            self.add_error(format!(
-              "expected range lower {} upper {} inclusive {}, got {:?}",
-              lower, upper, is_inclusive, self.cbor
-            ));
-          }
-        }
-        Err(errors) => {
-          for e in errors.into_iter() {
-            self.add_error(e);
-          }
+          return Ok(());
         }
-      }
+      };
 
-      return Ok(());
-    }
+      let mut settings = self.settings();
+      settings.embedded_depth += 1;
 
-    match lower {
-      Type2::IntValue { value: l, .. } => match upper {
-        Type2::IntValue { value: u, .. } => {
-          let error_str = if is_inclusive {
-            format!(
-              "expected integer to be in range {} <= value <= {}, got {:?}",
-              l, u, self.cbor
-            )
-          } else {
-            format!(
-              "expected integer to be in range {} < value < {}, got {:?}",
-              l, u, self.cbor
-            )
-          };
+      let mut cv = settings.validator(Cow::Owned(value));
 
-          match &self.cbor {
-            Value::Integer(i) => {
-              if is_inclusive {
-                if i128::from(*i) < *l as i128 || i128::from(*i) > *u as i128 {
-                  self.add_error(error_str);
-                } else {
-                  return Ok(());
-                }
-              } else if i128::from(*i) <= *l as i128 || i128::from(*i) >= *u as i128 {
-                self.add_error(error_str);
-                return Ok(());
-              } else {
-                return Ok(());
-              }
-            }
-            _ => {
-              self.add_error(error_str);
+      cv.memo = self.memo.clone();
+      cv.is_multi_type_choice = self.is_multi_type_choice;
+      cv.is_multi_group_choice = self.is_multi_group_choice;
+      cv.cbor_location = format!("{}/{}", self.cbor_location, ctrl);
+      cv.type_group_name_entry = self.type_group_name_entry.clone();
+      cv.visit_type2(t2)?;
+
+      if cv.errors.is_empty() {
+        self.labeled_paths.append(&mut cv.labeled_paths);
+        self.choice_explanations.append(&mut cv.choice_explanations);
+        self.feature_uses.append(&mut cv.feature_uses);
+      } else {
+        self.errors.append(&mut cv.errors);
+      }
+    }
+    Ok(())
+  }
+
+  // Validate the data item against a map type
+  fn validate_map<T: std::fmt::Debug + 'static>(
+    &mut self,
+    t2: &Type2<'a>,
+    group: &Group<'a>,
+  ) -> visitor::Result<Error<T>>
+  where
+    Error<T>: From<Error<ReaderError>>,
+  {
+    match &*self.cbor {
+      Value::Map(m) => {
+        if self.is_member_key {
+          let current_location = self.cbor_location.clone();
+
+          for (idx, (k, _)) in m.iter().enumerate() {
+            let mut cv = self.nested_validator(k);
+
+            cv.is_multi_type_choice = self.is_multi_type_choice;
+            cv.is_multi_group_choice = self.is_multi_group_choice;
+            cv.cbor_location.push_str(&self.cbor_location);
+            cv.type_group_name_entry = self.type_group_name_entry.clone();
+            cv.visit_type2(t2)?;
+
+            if cv.errors.is_empty() {
+              self.object_entry_idx = Some(idx);
+              self
+                .validated_keys
+                .get_or_insert_with(Vec::new)
+                .push(k.clone());
+              self.cbor_location = current_location;
               return Ok(());
             }
+
+            if cv.errors.is_empty() {
+              self.labeled_paths.append(&mut cv.labeled_paths);
+              self.choice_explanations.append(&mut cv.choice_explanations);
+              self.feature_uses.append(&mut cv.feature_uses);
+            }
+
+            self.errors.append(&mut cv.errors);
           }
+
+          return Ok(());
         }
-        Type2::UintValue { value: u, .. } => {
-          let error_str = if is_inclusive {
-            format!(
-              "expected integer to be in range {} <= value <= {}, got {:?}",
-              l, u, self.cbor
-            )
-          } else {
-            format!(
-              "expected integer to be in range {} < value < {}, got {:?}",
-              l, u, self.cbor
-            )
-          };
 
-          match &self.cbor {
-            Value::Integer(i) => {
-              if is_inclusive {
-                if i128::from(*i) < *l as i128 || i128::from(*i) > *u as i128 {
-                  self.add_error(error_str);
-                } else {
-                  return Ok(());
-                }
-              } else if i128::from(*i) <= *l as i128 || i128::from(*i) >= *u as i128 {
-                self.add_error(error_str);
-                return Ok(());
-              } else {
-                return Ok(());
-              }
+        self.visit_group(group)?;
+
+        // If extra map entries are detected, return validation error
+        let mut unmatched_key_errors = std::mem::take(&mut self.unmatched_key_errors);
+        if let (None, Value::Map(m)) = (&self.entries_to_validate, &*self.cbor) {
+          let current_location = self.cbor_location.clone();
+          let mut unexpected_keys = Vec::new();
+          for (k, _) in m.iter() {
+            if self
+              .validated_keys
+              .as_ref()
+              .is_some_and(|keys| keys.contains(k))
+            {
+              continue;
             }
-            _ => {
-              self.add_error(error_str);
-              return Ok(());
+
+            // Keys whose value failed to match an optional member key are
+            // reported with the errors of the value
+            if let Some(idx) = unmatched_key_errors.iter().position(|(key, _)| key == k) {
+              self.errors.append(&mut unmatched_key_errors.remove(idx).1);
+              continue;
             }
+
+            let segment = match k {
+              Value::Text(key) => format!("{:?}", key),
+              Value::Integer(key) => i128::from(*key).to_string(),
+              _ => format!("{:?}", k),
+            };
+            unexpected_keys.push((
+              format!("{}/{}", current_location, segment),
+              format!("unexpected key {}", segment),
+            ));
           }
+
+          for (location, reason) in unexpected_keys {
+            self.cbor_location = location;
+            self.add_error(reason);
+          }
+          self.cbor_location = current_location;
         }
-        _ => {
-          self.add_error(format!(
-            "invalid cddl range. upper value must be an integer type. got {}",
-            upper
-          ));
+
+        self.is_cut_present = false;
+        self.cut_value = None;
+        Ok(())
+      }
+      Value::Array(a) => {
+        // Member keys are annotation only in an array context
+        if self.is_member_key {
           return Ok(());
         }
-      },
-      Type2::UintValue { value: l, .. } => match upper {
-        Type2::UintValue { value: u, .. } => {
-          let error_str = if is_inclusive {
-            format!(
-              "expected uint to be in range {} <= value <= {}, got {:?}",
-              l, u, self.cbor
-            )
-          } else {
-            format!(
-              "expected uint to be in range {} < value < {}, got {:?}",
-              l, u, self.cbor
-            )
-          };
 
-          match &self.cbor {
-            Value::Integer(i) => {
-              if is_inclusive {
-                if i128::from(*i) < *l as i128 || i128::from(*i) > *u as i128 {
-                  self.add_error(error_str);
+        match validate_array_occurrence(
+          self.occurrence.as_ref().take(),
+          self.entry_counts.as_ref().map(|ec| &ec[..]),
+          a,
+        ) {
+          Ok((iter_items, allow_empty_array)) => {
+            if iter_items {
+              for (idx, v) in a.iter().enumerate() {
+                if let Some(indices) = &self.valid_array_items {
+                  if self.is_multi_type_choice && indices.contains(&idx) {
+                    continue;
+                  }
+                }
+
+                let mut cv = self.nested_validator(v);
+
+                cv.ctrl = self.ctrl.clone();
+                cv.is_multi_type_choice = self.is_multi_type_choice;
+                cv.cbor_location
+                  .push_str(&format!("{}/{}", self.cbor_location, idx));
+
+                cv.visit_group(group)?;
+
+                if cv.errors.is_empty() {
+                  self.labeled_paths.append(&mut cv.labeled_paths);
+                  self.choice_explanations.append(&mut cv.choice_explanations);
+                  self.feature_uses.append(&mut cv.feature_uses);
+                }
+
+                if self.is_multi_type_choice && cv.errors.is_empty() {
+                  self
+                    .valid_array_items
+                    .get_or_insert_with(BTreeSet::new)
+                    .insert(idx);
+                  continue;
+                }
+
+                if let Some(errors) = &mut self.array_errors {
+                  if let Some(error) = errors.get_mut(&idx) {
+                    error.append(&mut cv.errors);
+                  } else {
+                    errors.insert(idx, cv.errors);
+                  }
                 } else {
-                  return Ok(());
+                  let mut errors = BTreeMap::new();
+                  errors.insert(idx, cv.errors);
+                  self.array_errors = Some(errors)
                 }
-              } else if i128::from(*i) <= *l as i128 || i128::from(*i) >= *u as i128 {
-                self.add_error(error_str);
-                return Ok(());
-              } else {
-                return Ok(());
               }
-            }
-            Value::Text(s) => match self.ctrl {
-              Some(Token::SIZE) => {
-                let len = s.len();
-                let s = s.clone();
-                if is_inclusive {
-                  if s.len() < *l || s.len() > *u {
-                    self.add_error(format!(
-                      "expected \"{}\" string length to be in the range {} <= value <= {}, got {}",
-                      s, l, u, len
-                    ));
-                  }
+            } else if let Some(idx) = self.group_entry_idx.take() {
+              if let Some(v) = a.get(idx) {
+                let mut cv = self.nested_validator(v);
 
-                  return Ok(());
-                } else if s.len() <= *l || s.len() >= *u {
-                  self.add_error(format!(
-                    "expected \"{}\" string length to be in the range {} < value < {}, got {}",
-                    s, l, u, len
-                  ));
-                  return Ok(());
+                cv.ctrl = self.ctrl.clone();
+                cv.is_multi_type_choice = self.is_multi_type_choice;
+                cv.cbor_location
+                  .push_str(&format!("{}/{}", self.cbor_location, idx));
+
+                cv.visit_group(group)?;
+
+                if cv.errors.is_empty() {
+                  self.labeled_paths.append(&mut cv.labeled_paths);
+                  self.choice_explanations.append(&mut cv.choice_explanations);
+                  self.feature_uses.append(&mut cv.feature_uses);
                 }
+
+                self.errors.append(&mut cv.errors);
+              } else if !allow_empty_array {
+                self.add_error(format!("expected map object {} at index {}", group, idx));
               }
-              _ => {
-                self.add_error("string value cannot be validated against a range without the .size control operator".to_string());
-                return Ok(());
-              }
-            },
-            _ => {
-              self.add_error(error_str);
-              return Ok(());
+            } else {
+              self.add_error(format!(
+                "expected map object {}, got {:?}",
+                group, self.cbor
+              ));
+            }
+          }
+          Err(errors) => {
+            for e in errors.into_iter() {
+              self.add_error(e);
             }
           }
         }
-        _ => {
-          self.add_error(format!(
-            "invalid cddl range. upper value must be a uint type. got {}",
-            upper
-          ));
+
+        Ok(())
+      }
+      _ => {
+        self.add_error(format!("expected map object {}, got {:?}", t2, self.cbor));
+        Ok(())
+      }
+    }
+  }
+
+  // Validate the data item against an array type
+  fn validate_array<T: std::fmt::Debug + 'static>(
+    &mut self,
+    t2: &Type2<'a>,
+    group: &Group<'a>,
+  ) -> visitor::Result<Error<T>>
+  where
+    Error<T>: From<Error<ReaderError>>,
+  {
+    match &*self.cbor {
+      Value::Array(a) => {
+        if group.group_choices.len() == 1
+          && group.group_choices[0].group_entries.is_empty()
+          && !a.is_empty()
+          && !matches!(self.ctrl, Some(Token::NE) | Some(Token::DEFAULT))
+        {
+          self.add_error(format!("expected empty array, got {:?}", self.cbor));
           return Ok(());
         }
-      },
-      Type2::FloatValue { value: l, .. } => match upper {
-        Type2::FloatValue { value: u, .. } => {
-          let error_str = if is_inclusive {
-            format!(
-              "expected float to be in range {} <= value <= {}, got {:?}",
-              l, u, self.cbor
-            )
-          } else {
-            format!(
-              "expected float to be in range {} < value < {}, got {:?}",
-              l, u, self.cbor
-            )
-          };
 
-          match &self.cbor {
-            Value::Float(f) => {
-              if is_inclusive {
-                if *f < *l || *f > *u {
-                  self.add_error(error_str);
-                } else {
-                  return Ok(());
-                }
-              } else if *f <= *l || *f >= *u {
-                self.add_error(error_str);
-                return Ok(());
-              } else {
-                return Ok(());
-              }
-            }
-            _ => {
-              self.add_error(error_str);
+        // Occurrences are matched in a single pass over the items when the
+        // group compiles into a plan. The entry-by-entry validation below is
+        // then only relied on for its more detailed diagnostics, falling
+        // back to the plan's own error if it doesn't report any
+        let error_count = self.errors.len();
+        let mut plan_errors = None;
+        if self.ctrl.is_none() {
+          if let Some(plan) = ArrayPlan::compile(self.cddl, group) {
+            if self.validate_array_plan(&plan)? {
               return Ok(());
             }
+
+            plan_errors = Some(self.errors.split_off(error_count));
           }
         }
-        _ => {
-          self.add_error(format!(
-            "invalid cddl range. upper value must be a float type. got {}",
-            upper
-          ));
-          return Ok(());
+
+        let mut entry_counts = Vec::new();
+        for gc in group.group_choices.iter() {
+          let count = entry_counts_from_group_choice(self.cddl, gc);
+          entry_counts.push(count);
         }
-      },
+
+        self.entry_counts = Some(entry_counts);
+        self.visit_group(group)?;
+        self.entry_counts = None;
+
+        if let Some(errors) = &mut self.array_errors {
+          if let Some(indices) = &self.valid_array_items {
+            for idx in indices.iter() {
+              errors.remove(idx);
+            }
+          }
+
+          for error in errors.values_mut() {
+            self.errors.append(error);
+          }
+        }
+
+        self.valid_array_items = None;
+        self.array_errors = None;
+
+        if let Some(mut plan_errors) = plan_errors {
+          if self.errors.len() == error_count {
+            self.errors.append(&mut plan_errors);
+          }
+        }
+
+        Ok(())
+      }
+      Value::Map(m) if self.is_member_key => {
+        let current_location = self.cbor_location.clone();
+
+        let mut entry_counts = Vec::new();
+        for gc in group.group_choices.iter() {
+          let count = entry_counts_from_group_choice(self.cddl, gc);
+          entry_counts.push(count);
+        }
+
+        self.entry_counts = Some(entry_counts);
+
+        for (idx, (k, _)) in m.iter().enumerate() {
+          let mut cv = self.nested_validator(k);
+
+          cv.entry_counts = self.entry_counts.clone();
+          cv.is_multi_type_choice = self.is_multi_type_choice;
+          cv.is_multi_group_choice = self.is_multi_group_choice;
+          cv.cbor_location.push_str(&self.cbor_location);
+          cv.type_group_name_entry = self.type_group_name_entry.clone();
+          cv.visit_type2(t2)?;
+
+          if cv.errors.is_empty() {
+            self.object_entry_idx = Some(idx);
+            self
+              .validated_keys
+              .get_or_insert_with(Vec::new)
+              .push(k.clone());
+            self.cbor_location = current_location;
+            return Ok(());
+          }
+
+          if cv.errors.is_empty() {
+            self.labeled_paths.append(&mut cv.labeled_paths);
+            self.choice_explanations.append(&mut cv.choice_explanations);
+            self.feature_uses.append(&mut cv.feature_uses);
+          }
+
+          self.errors.append(&mut cv.errors);
+        }
+
+        self.entry_counts = None;
+
+        Ok(())
+      }
       _ => {
-        self.add_error(
-          "invalid cddl range. upper and lower values must be either integers or floats"
-            .to_string(),
-        );
+        self.add_error(format!("expected array type, got {:?}", self.cbor));
+        Ok(())
+      }
+    }
+  }
+
+  // Validate the data item against a choice from the entries of a group rule
+  fn validate_choice_from_group<T: std::fmt::Debug + 'static>(
+    &mut self,
+    ident: &Identifier<'a>,
+    generic_args: &Option<GenericArgs<'a>>,
+  ) -> visitor::Result<Error<T>>
+  where
+    Error<T>: From<Error<ReaderError>>,
+  {
+    if let Some(ga) = generic_args {
+      if let Some(rule) = self.lookup_rule(ident) {
+        if let Some(gr) = self
+          .generic_rules
+          .iter_mut()
+          .find(|gr| gr.name == ident.ident)
+        {
+          for arg in ga.args.iter() {
+            gr.args.push((*arg.arg).clone());
+          }
+        } else if let Some(params) = generic_params_from_rule(rule) {
+          self.generic_rules.push(GenericRule {
+            name: ident.ident.clone(),
+            params,
+            args: ga.args.iter().cloned().map(|arg| *arg.arg).collect(),
+          });
+        }
+
+        let mut cv = self.nested_validator(&self.cbor);
+
+        cv.eval_generic_rule = Some(ident.ident.clone());
+        cv.is_group_to_choice_enum = true;
+        cv.is_multi_type_choice = self.is_multi_type_choice;
+        cv.visit_rule(rule)?;
+
+        if cv.errors.is_empty() {
+          self.labeled_paths.append(&mut cv.labeled_paths);
+          self.choice_explanations.append(&mut cv.choice_explanations);
+          self.feature_uses.append(&mut cv.feature_uses);
+        }
+
+        self.errors.append(&mut cv.errors);
 
         return Ok(());
       }
     }
 
+    if group_rule_from_ident(self.cddl, ident).is_none() {
+      self.add_error(format!(
+        "rule {} must be a group rule to turn it into a choice",
+        ident
+      ));
+      return Ok(());
+    }
+
+    self.is_group_to_choice_enum = true;
+    self.visit_identifier(ident)?;
+    self.is_group_to_choice_enum = false;
+
     Ok(())
   }
 
-  fn visit_control_operator(
+  // Validate the data item against a type name, instantiating a generic rule
+  // with its arguments
+  fn validate_typename<T: std::fmt::Debug + 'static>(
     &mut self,
-    target: &Type2<'a>,
-    ctrl: &str,
-    controller: &Type2<'a>,
-  ) -> visitor::Result<Error<T>> {
-    if let Type2::Typename {
-      ident: target_ident,
-      ..
-    } = target
-    {
-      if let Type2::Typename {
-        ident: controller_ident,
-        ..
-      } = controller
-      {
-        if let Some(name) = self.eval_generic_rule {
-          if let Some(gr) = self
-            .generic_rules
-            .iter()
-            .cloned()
-            .find(|gr| gr.name == name)
-          {
-            for (idx, gp) in gr.params.iter().enumerate() {
-              if let Some(arg) = gr.args.get(idx) {
-                if *gp == target_ident.ident {
-                  let t2 = Type2::from(arg.clone());
+    ident: &Identifier<'a>,
+    generic_args: &Option<GenericArgs<'a>>,
+  ) -> visitor::Result<Error<T>>
+  where
+    Error<T>: From<Error<ReaderError>>,
+  {
+    if let Some(ga) = generic_args {
+      if let Some(rule) = self.lookup_rule(ident) {
+        if let Some(gr) = self
+          .generic_rules
+          .iter_mut()
+          .find(|gr| gr.name == ident.ident)
+        {
+          for arg in ga.args.iter() {
+            gr.args.push((*arg.arg).clone());
+          }
+        } else if let Some(params) = generic_params_from_rule(rule) {
+          self.generic_rules.push(GenericRule {
+            name: ident.ident.clone(),
+            params,
+            args: ga.args.iter().cloned().map(|arg| *arg.arg).collect(),
+          });
+        }
 
-                  if *gp == controller_ident.ident {
-                    return self.visit_control_operator(&t2, ctrl, &t2);
-                  }
+        let mut cv = self.nested_validator(&self.cbor);
 
-                  return self.visit_control_operator(&arg.type2, ctrl, controller);
-                }
-              }
-            }
-          }
+        cv.eval_generic_rule = Some(ident.ident.clone());
+        cv.is_multi_type_choice = self.is_multi_type_choice;
+        cv.visit_rule(rule)?;
+
+        if cv.errors.is_empty() {
+          self.labeled_paths.append(&mut cv.labeled_paths);
+          self.choice_explanations.append(&mut cv.choice_explanations);
+          self.feature_uses.append(&mut cv.feature_uses);
         }
+
+        self.errors.append(&mut cv.errors);
+
+        return Ok(());
       }
+    }
+
+    self.visit_identifier(ident)
+  }
 
-      if let Some(name) = self.eval_generic_rule {
+  // Validate the data item against an unwrapped type
+  fn validate_unwrap<T: std::fmt::Debug + 'static>(
+    &mut self,
+    ident: &Identifier<'a>,
+    generic_args: &Option<GenericArgs<'a>>,
+  ) -> visitor::Result<Error<T>>
+  where
+    Error<T>: From<Error<ReaderError>>,
+  {
+    // Per
+    // https://github.com/w3c/did-spec-registries/pull/138#issuecomment-719739215,
+    // strip tag and validate underlying type
+    if let Some(Type2::TaggedData { t, .. }) = tag_from_token(&lookup_ident(&ident.ident)) {
+      return self.visit_type(&t);
+    }
+
+    if let Some(ga) = generic_args {
+      if let Some(rule) = unwrap_rule_from_ident(self.cddl, ident) {
         if let Some(gr) = self
           .generic_rules
-          .iter()
-          .cloned()
-          .find(|gr| gr.name == name)
+          .iter_mut()
+          .find(|gr| gr.name == ident.ident)
         {
-          for (idx, gp) in gr.params.iter().enumerate() {
-            if let Some(arg) = gr.args.get(idx) {
-              if *gp == target_ident.ident {
-                let t2 = Type2::from(arg.clone());
-                return self.visit_control_operator(&t2, ctrl, controller);
-              }
-            }
+          for arg in ga.args.iter() {
+            gr.args.push((*arg.arg).clone());
           }
+        } else if let Some(params) = generic_params_from_rule(rule) {
+          self.generic_rules.push(GenericRule {
+            name: ident.ident.clone(),
+            params,
+            args: ga.args.iter().cloned().map(|arg| *arg.arg).collect(),
+          });
         }
+
+        let mut cv = self.nested_validator(&self.cbor);
+
+        cv.eval_generic_rule = Some(ident.ident.clone());
+        cv.is_multi_type_choice = self.is_multi_type_choice;
+        cv.visit_rule(rule)?;
+
+        if cv.errors.is_empty() {
+          self.labeled_paths.append(&mut cv.labeled_paths);
+          self.choice_explanations.append(&mut cv.choice_explanations);
+          self.feature_uses.append(&mut cv.feature_uses);
+        }
+
+        self.errors.append(&mut cv.errors);
+
+        return Ok(());
       }
     }
 
-    match lookup_control_from_str(ctrl) {
-      t @ Some(Token::EQ) => {
-        match target {
-          Type2::Typename { ident, .. } => {
-            if is_ident_string_data_type(self.cddl, ident)
-              || is_ident_numeric_data_type(self.cddl, ident)
-            {
-              return self.visit_type2(controller);
-            }
+    if let Some(rule) = unwrap_rule_from_ident(self.cddl, ident) {
+      return self.visit_rule(rule);
+    }
+
+    self.add_error(format!(
+      "cannot unwrap identifier {}, rule not found",
+      ident
+    ));
+
+    Ok(())
+  }
+
+  // Validate the data item against tagged data
+  fn validate_tagged_data<T: std::fmt::Debug + 'static>(
+    &mut self,
+    t2: &Type2<'a>,
+    tag: &Option<usize>,
+    tag_type: &Option<Box<Type<'a>>>,
+    t: &Type<'a>,
+  ) -> visitor::Result<Error<T>>
+  where
+    Error<T>: From<Error<ReaderError>>,
+  {
+    match &*self.cbor {
+      Value::Tag(actual_tag, value) => {
+        if let Some(tag) = tag {
+          if *tag as u64 != *actual_tag {
+            self.add_error(format!(
+              "expected tagged data {} with tag {}, got tag {}",
+              t2, tag, actual_tag
+            ));
+            return Ok(());
           }
-          Type2::Array { group, .. } => {
-            if let Value::Array(_) = &self.cbor {
-              let mut entry_counts = Vec::new();
-              for gc in group.group_choices.iter() {
-                let count = entry_counts_from_group_choice(self.cddl, gc);
-                entry_counts.push(count);
-              }
-              self.entry_counts = Some(entry_counts);
-              self.visit_type2(controller)?;
-              self.entry_counts = None;
+        } else if let Some(tag_type) = tag_type {
+          // The tag number is validated as an unsigned integer against the
+          // type, which may be defined by a rule or a generic parameter
+          let tag_number = Value::Integer((*actual_tag).into());
+          let mut cv = self.child_validator(&tag_number);
+          cv.visit_type(tag_type)?;
+          if !cv.errors.is_empty() {
+            self.add_error(format!(
+              "expected tagged data {} with a tag number matching {}, got tag {}",
+              t2, tag_type, actual_tag
+            ));
+            return Ok(());
+          }
+        } else if *actual_tag > 0 {
+          self.add_error(format!(
+            "expected tagged data #6({}), got {:?}",
+            t, self.cbor
+          ));
+          return Ok(());
+        }
+
+        let mut cv = self.child_validator(value);
+        cv.cbor_location.push_str(&self.cbor_location);
+        cv.visit_type(t)?;
+
+        if cv.errors.is_empty() {
+          self.labeled_paths.append(&mut cv.labeled_paths);
+          self.choice_explanations.append(&mut cv.choice_explanations);
+          self.feature_uses.append(&mut cv.feature_uses);
+        }
+
+        self.errors.append(&mut cv.errors);
+        Ok(())
+      }
+      _ => {
+        self.add_error(format!("expected tagged data {}, got {:?}", t2, self.cbor));
+
+        Ok(())
+      }
+    }
+  }
+
+  // Validate the data item against a major type, e.g. `#0` or `#1.3`
+  fn validate_data_major_type<T: std::fmt::Debug + 'static>(
+    &mut self,
+    mt: &u8,
+    constraint: &Option<usize>,
+  ) -> visitor::Result<Error<T>>
+  where
+    Error<T>: From<Error<ReaderError>>,
+  {
+    match &*self.cbor {
+      Value::Integer(i) => {
+        match mt {
+          0u8 => match constraint {
+            Some(c) if i128::from(*i) == *c as i128 && i128::from(*i) >= 0i128 => return Ok(()),
+            Some(c) => {
+              self.add_error(format!(
+                "expected uint data type with constraint {} (#{}.{}), got {:?}",
+                c, mt, c, self.cbor
+              ));
               return Ok(());
             }
-          }
-          Type2::Map { .. } => {
-            if let Value::Map(_) = &self.cbor {
-              self.ctrl = t;
-              self.is_ctrl_map_equality = true;
-              self.visit_type2(controller)?;
-              self.ctrl = None;
-              self.is_ctrl_map_equality = false;
+            _ => {
+              if i128::from(*i).is_negative() {
+                self.add_error(format!(
+                  "expected uint data type (#{}), got {:?}",
+                  mt, self.cbor
+                ));
+                return Ok(());
+              }
+            }
+          },
+          1u8 => match constraint {
+            Some(c) if i128::from(*i) == 0i128 - *c as i128 => return Ok(()),
+            Some(c) => {
+              self.add_error(format!(
+                "expected nint type with constraint {} (#{}.{}), got {:?}",
+                c, mt, c, self.cbor
+              ));
               return Ok(());
             }
-          }
+            _ => {
+              if i128::from(*i) >= 0i128 {
+                self.add_error(format!(
+                  "expected nint data type (#{}), got {:?}",
+                  mt, self.cbor
+                ));
+                return Ok(());
+              }
+            }
+          },
           _ => self.add_error(format!(
-            "target for .eq operator must be a string, numerical, array or map data type, got {}",
-            target
+            "expected major type {} with constraint {:?}, got {:?}",
+            mt, constraint, self.cbor
           )),
         }
+
         Ok(())
       }
-      t @ Some(Token::NE) => {
-        match target {
-          Type2::Typename { ident, .. } => {
-            if is_ident_string_data_type(self.cddl, ident)
-              || is_ident_numeric_data_type(self.cddl, ident)
-            {
-              self.ctrl = t;
-              self.visit_type2(controller)?;
-              self.ctrl = None;
-              return Ok(());
-            }
-          }
-          Type2::Array { .. } => {
-            if let Value::Array(_) = &self.cbor {
-              self.ctrl = t;
-              self.visit_type2(controller)?;
-              self.ctrl = None;
-              return Ok(());
-            }
-          }
-          Type2::Map { .. } => {
-            if let Value::Map(_) = &self.cbor {
-              self.ctrl = t;
-              self.is_ctrl_map_equality = true;
-              self.visit_type2(controller)?;
-              self.ctrl = None;
-              self.is_ctrl_map_equality = false;
-              return Ok(());
-            }
-          }
+      Value::Bytes(b) => {
+        match mt {
+          2u8 => match constraint {
+            Some(c) if *c == b.len() => return Ok(()),
+            Some(c) => self.add_error(format!(
+              "expected byte string type with constraint {} (#{}.{}), got {:?}",
+              c, mt, c, self.cbor
+            )),
+            _ => return Ok(()),
+          },
           _ => self.add_error(format!(
-            "target for .ne operator must be a string, numerical, array or map data type, got {}",
-            target
+            "expected major type {} with constraint {:?}, got {:?}",
+            mt, constraint, self.cbor
           )),
         }
-        Ok(())
-      }
-      t @ Some(Token::LT) | t @ Some(Token::GT) | t @ Some(Token::GE) | t @ Some(Token::LE) => {
-        match target {
-          Type2::Typename { ident, .. } if is_ident_numeric_data_type(self.cddl, ident) => {
-            self.ctrl = t;
-            self.visit_type2(controller)?;
-            self.ctrl = None;
-            Ok(())
-          }
-          _ => {
-            self.add_error(format!(
-              "target for .lt, .gt, .ge or .le operator must be a numerical data type, got {}",
-              target
-            ));
-            Ok(())
-          }
-        }
-      }
-      t @ Some(Token::SIZE) => match target {
-        Type2::Typename { ident, .. }
-          if is_ident_string_data_type(self.cddl, ident)
-            || is_ident_uint_data_type(self.cddl, ident)
-            || is_ident_byte_string_data_type(self.cddl, ident) =>
-        {
-          self.ctrl = t;
-          self.visit_type2(controller)?;
-          self.ctrl = None;
-          Ok(())
-        }
-        _ => {
-          self.add_error(format!(
-            "target for .size must a string or uint data type, got {}",
-            target
-          ));
-          Ok(())
-        }
-      },
-      t @ Some(Token::AND) => {
-        self.ctrl = t;
-        self.visit_type2(target)?;
-        self.visit_type2(controller)?;
-        self.ctrl = None;
-        Ok(())
-      }
-      t @ Some(Token::WITHIN) => {
-        self.ctrl = t;
-        let error_count = self.errors.len();
-        self.visit_type2(target)?;
-        let no_errors = self.errors.len() == error_count;
-        self.visit_type2(controller)?;
-        if no_errors && self.errors.len() > error_count {
-          for _ in 0..self.errors.len() - error_count {
-            self.errors.pop();
-          }
-
-          self.add_error(format!(
-            "expected type {} .within type {}, got {:?}",
-            target, controller, self.cbor,
-          ));
-        }
-
-        self.ctrl = None;
 
         Ok(())
       }
-      t @ Some(Token::DEFAULT) => {
-        self.ctrl = t;
-        let error_count = self.errors.len();
-        self.visit_type2(target)?;
-        if self.errors.len() != error_count {
-          #[cfg(feature = "ast-span")]
-          if let Some(Occur::Optional(_)) = self.occurrence.take() {
-            self.add_error(format!(
-              "expected default value {}, got {:?}",
-              controller, self.cbor
-            ));
-          }
-          #[cfg(not(feature = "ast-span"))]
-          if let Some(Occur::Optional) = self.occurrence.take() {
-            self.add_error(format!(
-              "expected default value {}, got {:?}",
-              controller, self.cbor
-            ));
-          }
+      Value::Text(t) => {
+        match mt {
+          3u8 => match constraint {
+            Some(c) if *c == t.len() => return Ok(()),
+            Some(c) => self.add_error(format!(
+              "expected text string type with constraint {} (#{}.{}), got {:?}",
+              c, mt, c, self.cbor
+            )),
+            _ => return Ok(()),
+          },
+          _ => self.add_error(format!(
+            "expected major type {} with constraint {:?}, got {:?}",
+            mt, constraint, self.cbor
+          )),
         }
-        self.ctrl = None;
+
         Ok(())
       }
-      t @ Some(Token::REGEXP) | t @ Some(Token::PCRE) => {
-        self.ctrl = t;
-        match target {
-          Type2::Typename { ident, .. } if is_ident_string_data_type(self.cddl, ident) => {
-            match self.cbor {
-              Value::Text(_) | Value::Array(_) => self.visit_type2(controller)?,
-              _ => self.add_error(format!(
-                ".regexp/.pcre control can only be matched against CBOR string, got {:?}",
-                self.cbor
-              )),
-            }
-          }
+      Value::Array(a) => {
+        match mt {
+          4u8 => match constraint {
+            Some(c) if *c == a.len() => return Ok(()),
+            Some(c) => self.add_error(format!(
+              "expected array type with constraint {} (#{}.{}), got {:?}",
+              c, mt, c, self.cbor
+            )),
+            _ => return Ok(()),
+          },
           _ => self.add_error(format!(
-            ".regexp/.pcre control can only be matched against string data type, got {}",
-            target
+            "expected major type {} with constraint {:?}, got {:?}",
+            mt, constraint, self.cbor
           )),
         }
-        self.ctrl = None;
 
         Ok(())
       }
-      t @ Some(Token::CBOR) | t @ Some(Token::CBORSEQ) => {
-        self.ctrl = t;
-        match target {
-          Type2::Typename { ident, .. } if is_ident_byte_string_data_type(self.cddl, ident) => {
-            match &self.cbor {
-              Value::Bytes(_) | Value::Array(_) => self.visit_type2(controller)?,
-              _ => self.add_error(format!(
-                "{} control can only be matched against a CBOR byte string, got {:?}",
-                ctrl, self.cbor
-              )),
-            }
-          }
+      Value::Map(m) => {
+        match mt {
+          5u8 => match constraint {
+            Some(c) if *c == m.len() => return Ok(()),
+            Some(c) => self.add_error(format!(
+              "expected map type with constraint {} (#{}.{}), got {:?}",
+              c, mt, c, self.cbor
+            )),
+            _ => return Ok(()),
+          },
           _ => self.add_error(format!(
-            ".cbor control can only be matched against a byte string data type, got {}",
-            target
+            "expected major type {} with constraint {:?}, got {:?}",
+            mt, constraint, self.cbor
           )),
         }
-        self.ctrl = None;
 
         Ok(())
       }
-      t @ Some(Token::BITS) => {
-        self.ctrl = t;
-        match target {
-          Type2::Typename { ident, .. }
-            if is_ident_byte_string_data_type(self.cddl, ident)
-              || is_ident_uint_data_type(self.cddl, ident) =>
-          {
-            match &self.cbor {
-              Value::Bytes(_) | Value::Array(_) => self.visit_type2(controller)?,
-              Value::Integer(i) if i128::from(*i) >= 0i128 => self.visit_type2(controller)?,
-              _ => self.add_error(format!(
-                "{} control can only be matched against a CBOR byte string or uint, got {:?}",
-                ctrl, self.cbor,
-              )),
-            }
-          }
+      Value::Float(_f) => {
+        match mt {
+          7u8 => match constraint {
+            Some(_c) => unimplemented!(),
+            _ => return Ok(()),
+          },
           _ => self.add_error(format!(
-            ".bits control can only be matched against a byte string data type, got {}",
-            target
+            "expected major type {} with constraint {:?}, got {:?}",
+            mt, constraint, self.cbor
           )),
         }
-        self.ctrl = None;
 
         Ok(())
       }
-      #[cfg(feature = "additional-controls")]
-      t @ Some(Token::CAT) => {
-        self.ctrl = t;
-
-        match cat_operation(self.cddl, target, controller, false) {
-          Ok(values) => {
-            let error_count = self.errors.len();
-            for v in values.iter() {
-              let cur_errors = self.errors.len();
-
-              self.visit_type2(v)?;
-
-              if self.errors.len() == cur_errors {
-                for _ in 0..self.errors.len() - error_count {
-                  self.errors.pop();
-                }
-
-                break;
-              }
-            }
-          }
-          Err(e) => self.add_error(e),
+      _ => {
+        if let Some(constraint) = constraint {
+          self.add_error(format!(
+            "expected major type #{}.{}, got {:?}",
+            mt, constraint, self.cbor
+          ));
+        } else {
+          self.add_error(format!("expected major type #{}, got {:?}", mt, self.cbor));
         }
 
-        self.ctrl = None;
-
         Ok(())
       }
-      #[cfg(feature = "additional-controls")]
-      t @ Some(Token::DET) => {
-        self.ctrl = t;
-
-        match cat_operation(self.cddl, target, controller, true) {
-          Ok(values) => {
-            let error_count = self.errors.len();
-
-            for v in values.iter() {
-              let cur_errors = self.errors.len();
-              self.visit_type2(v)?;
-
-              if self.errors.len() == cur_errors {
-                for _ in 0..self.errors.len() - error_count {
-                  self.errors.pop();
-                }
+    }
+  }
 
-                break;
-              }
-            }
-          }
-          Err(e) => self.add_error(e),
+  // Validate the data item against a type of the standard prelude, or a
+  // value the identifier is bound to, the identifier not naming a rule
+  fn validate_prelude_type<T: std::fmt::Debug + 'static>(
+    &mut self,
+    ident: &Identifier<'a>,
+  ) -> visitor::Result<Error<T>>
+  where
+    Error<T>: From<Error<ReaderError>>,
+  {
+    match &*self.cbor {
+      Value::Null if is_ident_null_data_type(self.cddl, ident) => Ok(()),
+      Value::Bytes(_) if is_ident_byte_string_data_type(self.cddl, ident) => Ok(()),
+      Value::Bool(b) => {
+        if is_ident_bool_data_type(self.cddl, ident) {
+          return Ok(());
         }
 
-        self.ctrl = None;
+        if ident_matches_bool_value(self.cddl, ident, *b) {
+          return Ok(());
+        }
 
+        self.add_error(format!("expected type {}, got {:?}", ident, self.cbor));
         Ok(())
       }
-      #[cfg(feature = "additional-controls")]
-      t @ Some(Token::PLUS) => {
-        self.ctrl = t;
-
-        match plus_operation(self.cddl, target, controller) {
-          Ok(values) => {
-            let error_count = self.errors.len();
-            for v in values.iter() {
-              let cur_errors = self.errors.len();
-
-              self.visit_type2(v)?;
-
-              self.visit_type2(v)?;
-              if self.errors.len() == cur_errors {
-                for _ in 0..self.errors.len() - error_count {
-                  self.errors.pop();
-                }
-
-                break;
-              }
-            }
+      Value::Integer(i) => {
+        if is_ident_uint_data_type(self.cddl, ident) {
+          if i128::from(*i).is_negative() {
+            self.add_error(format!("expected type {}, got {:?}", ident, self.cbor));
           }
 
-          Err(e) => self.add_error(e),
-        }
-
-        self.ctrl = None;
+          Ok(())
+        } else if is_ident_nint_data_type(self.cddl, ident) {
+          if !i128::from(*i).is_negative() {
+            self.add_error(format!("expected type {}, got {:?}", ident, self.cbor));
+          }
 
-        Ok(())
-      }
-      #[cfg(feature = "additional-controls")]
-      t @ Some(Token::ABNF) => {
-        self.ctrl = t;
+          Ok(())
+        } else if is_ident_integer_data_type(self.cddl, ident) {
+          Ok(())
+        } else if is_ident_time_data_type(self.cddl, ident) {
+          #[cfg(feature = "std")]
+          if let chrono::LocalResult::None =
+            Utc.timestamp_millis_opt((i128::from(*i) * 1000) as i64)
+          {
+            let i = *i;
+            self.add_error(format!(
+              "expected time data type, invalid UNIX timestamp {:?}",
+              i,
+            ));
+          }
 
-        match target {
-          Type2::Typename { ident, .. } if is_ident_string_data_type(self.cddl, ident) => {
-            match self.cbor {
-              Value::Text(_) | Value::Array(_) => {
-                if let Type2::ParenthesizedType { pt, .. } = controller {
-                  match abnf_from_complex_controller(self.cddl, pt) {
-                    Ok(values) => {
-                      let error_count = self.errors.len();
-                      for v in values.iter() {
-                        let cur_errors = self.errors.len();
+          Ok(())
+        } else {
+          self.add_error(format!("expected type {}, got {:?}", ident, self.cbor));
+          Ok(())
+        }
+      }
+      // Format checks of time, URI, base64 URL and tdate data types require
+      // `std`
+      #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+      Value::Float(f) => {
+        if is_ident_float_data_type(self.cddl, ident) {
+          Ok(())
+        } else if is_ident_time_data_type(self.cddl, ident) {
+          #[cfg(feature = "std")]
+          if let chrono::LocalResult::None = Utc.timestamp_millis_opt((*f * 1000f64) as i64) {
+            let f = *f;
+            self.add_error(format!(
+              "expected time data type, invalid UNIX timestamp {:?}",
+              f,
+            ));
+          }
 
-                        self.visit_type2(v)?;
+          Ok(())
+        } else {
+          self.add_error(format!("expected type {}, got {:?}", ident, self.cbor));
+          Ok(())
+        }
+      }
+      // Format checks of time, URI, base64 URL and tdate data types require
+      // `std`
+      #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+      Value::Text(s) => {
+        if is_ident_uri_data_type(self.cddl, ident) {
+          #[cfg(feature = "std")]
+          if let Err(e) = uriparse::URI::try_from(&**s) {
+            self.add_error(format!("expected URI data type, decoding error: {}", e));
+          }
+        } else if is_ident_b64url_data_type(self.cddl, ident) {
+          #[cfg(feature = "std")]
+          if let Err(e) = base64_url::decode(s) {
+            self.add_error(format!(
+              "expected base64 URL data type, decoding error: {}",
+              e
+            ));
+          }
+        } else if is_ident_tdate_data_type(self.cddl, ident) {
+          #[cfg(feature = "std")]
+          if let Err(e) = chrono::DateTime::parse_from_rfc3339(s) {
+            self.add_error(format!("expected tdate data type, decoding error: {}", e));
+          }
+        } else if is_ident_string_data_type(self.cddl, ident) {
+          return Ok(());
+        } else {
+          self.add_error(format!("expected type {}, got {:?}", ident, self.cbor));
+        }
 
-                        if self.errors.len() == cur_errors {
-                          for _ in 0..self.errors.len() - error_count {
-                            self.errors.pop();
-                          }
+        Ok(())
+      }
+      Value::Array(a) => {
+        // Member keys are annotation only in an array context
+        if self.is_member_key {
+          return Ok(());
+        }
 
-                          break;
-                        }
-                      }
-                    }
-                    Err(e) => self.add_error(e),
+        match validate_array_occurrence(
+          self.occurrence.as_ref().take(),
+          self.entry_counts.as_ref().map(|ec| &ec[..]),
+          a,
+        ) {
+          Ok((iter_items, allow_empty_array)) => {
+            if iter_items {
+              #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+              if self.validates_in_parallel(a) {
+                let outcomes = self.validate_array_items_in_parallel(a, |cv| {
+                  <CBORValidator as Visitor<Error<ReaderError>>>::visit_identifier(cv, ident)
+                })?;
+                let errors = self.array_errors.get_or_insert_with(BTreeMap::new);
+                for (
+                  idx,
+                  (mut item_errors, mut labeled_paths, mut choice_explanations, mut feature_uses),
+                ) in outcomes.into_iter().enumerate()
+                {
+                  if item_errors.is_empty() {
+                    self.labeled_paths.append(&mut labeled_paths);
+                    self.choice_explanations.append(&mut choice_explanations);
+                    self.feature_uses.append(&mut feature_uses);
                   }
-                } else {
-                  self.visit_type2(controller)?
+
+                  errors.entry(idx).or_default().append(&mut item_errors);
                 }
+
+                return Ok(());
               }
-              _ => self.add_error(format!(
-                ".abnf control can only be matched against a cbor string, got {:?}",
-                self.cbor,
-              )),
-            }
-          }
-          _ => self.add_error(format!(
-            ".abnf can only be matched against string data type, got {}",
-            target,
-          )),
-        }
 
-        self.ctrl = None;
+              for (idx, v) in a.iter().enumerate() {
+                if let Some(indices) = &self.valid_array_items {
+                  if self.is_multi_type_choice && indices.contains(&idx) {
+                    continue;
+                  }
+                }
 
-        Ok(())
-      }
-      #[cfg(feature = "additional-controls")]
-      t @ Some(Token::ABNFB) => {
-        self.ctrl = t;
+                let mut cv = self.nested_validator(v);
 
-        match target {
-          Type2::Typename { ident, .. } if is_ident_byte_string_data_type(self.cddl, ident) => {
-            match self.cbor {
-              Value::Bytes(_) | Value::Array(_) => {
-                if let Type2::ParenthesizedType { pt, .. } = controller {
-                  match abnf_from_complex_controller(self.cddl, pt) {
-                    Ok(values) => {
-                      let error_count = self.errors.len();
-                      for v in values.iter() {
-                        let cur_errors = self.errors.len();
+                cv.ctrl = self.ctrl.clone();
+                cv.is_multi_type_choice = self.is_multi_type_choice;
+                cv.cbor_location
+                  .push_str(&format!("{}/{}", self.cbor_location, idx));
 
-                        self.visit_type2(v)?;
+                cv.visit_identifier(ident)?;
 
-                        if self.errors.len() == cur_errors {
-                          for _ in 0..self.errors.len() - error_count {
-                            self.errors.pop();
-                          }
+                if cv.errors.is_empty() {
+                  self.labeled_paths.append(&mut cv.labeled_paths);
+                  self.choice_explanations.append(&mut cv.choice_explanations);
+                  self.feature_uses.append(&mut cv.feature_uses);
+                }
 
-                          break;
-                        }
-                      }
-                    }
-                    Err(e) => self.add_error(e),
+                if self.is_multi_type_choice && cv.errors.is_empty() {
+                  self
+                    .valid_array_items
+                    .get_or_insert_with(BTreeSet::new)
+                    .insert(idx);
+                  continue;
+                }
+
+                if let Some(errors) = &mut self.array_errors {
+                  if let Some(error) = errors.get_mut(&idx) {
+                    error.append(&mut cv.errors);
+                  } else {
+                    errors.insert(idx, cv.errors);
                   }
                 } else {
-                  self.visit_type2(controller)?
+                  let mut errors = BTreeMap::new();
+                  errors.insert(idx, cv.errors);
+                  self.array_errors = Some(errors)
                 }
               }
-              _ => self.add_error(format!(
-                ".abnfb control can only be matched against cbor bytes, got {:?}",
-                self.cbor,
-              )),
-            }
-          }
-          _ => self.add_error(format!(
-            ".abnfb can only be matched against byte string target data type, got {}",
-            target,
-          )),
-        }
+            } else if let Some(idx) = self.group_entry_idx.take() {
+              if let Some(v) = a.get(idx) {
+                let mut cv = self.nested_validator(v);
 
-        self.ctrl = None;
+                cv.is_multi_type_choice = self.is_multi_type_choice;
+                cv.ctrl = self.ctrl.clone();
+                cv.cbor_location
+                  .push_str(&format!("{}/{}", self.cbor_location, idx));
 
-        Ok(())
-      }
-      #[cfg(feature = "additional-controls")]
-      #[cfg(not(target_arch = "wasm32"))]
-      t @ Some(Token::FEATURE) => {
-        self.ctrl = t;
+                cv.visit_identifier(ident)?;
 
-        if let Some(ef) = self.enabled_features {
-          let tv = text_value_from_type2(self.cddl, controller);
-          if let Some(Type2::TextValue { value, .. }) = tv {
-            if ef.contains(&&**value) {
-              let err_count = self.errors.len();
-              self.visit_type2(target)?;
-              if self.errors.len() > err_count {
-                self.has_feature_errors = true;
-              }
-              self.ctrl = None;
-            } else {
-              self
-                .disabled_features
-                .get_or_insert(vec![value.to_string()])
-                .push(value.to_string());
-            }
-          } else if let Some(Type2::UTF8ByteString { value, .. }) = tv {
-            let value = std::str::from_utf8(value).map_err(Error::UTF8Parsing)?;
-            if ef.contains(&value) {
-              let err_count = self.errors.len();
-              self.visit_type2(target)?;
-              if self.errors.len() > err_count {
-                self.has_feature_errors = true;
+                if cv.errors.is_empty() {
+                  self.labeled_paths.append(&mut cv.labeled_paths);
+                  self.choice_explanations.append(&mut cv.choice_explanations);
+                  self.feature_uses.append(&mut cv.feature_uses);
+                }
+
+                self.errors.append(&mut cv.errors);
+              } else if !allow_empty_array {
+                self.add_error(format!("expected type {} at index {}", ident, idx));
               }
-              self.ctrl = None;
             } else {
-              self
-                .disabled_features
-                .get_or_insert(vec![value.to_string()])
-                .push(value.to_string());
+              self.add_error(format!("expected type {}, got {:?}", ident, self.cbor));
             }
           }
-        }
-
-        self.ctrl = None;
-
-        Ok(())
-      }
-      #[cfg(feature = "additional-controls")]
-      #[cfg(target_arch = "wasm32")]
-      t @ Some(Token::FEATURE) => {
-        self.ctrl = t;
-
-        if let Some(ef) = &self.enabled_features {
-          let tv = text_value_from_type2(self.cddl, controller);
-          if let Some(Type2::TextValue { value, .. }) = tv {
-            if ef.contains(&JsValue::from(value.as_ref())) {
-              let err_count = self.errors.len();
-              self.visit_type2(target)?;
-              if self.errors.len() > err_count {
-                self.has_feature_errors = true;
-              }
-              self.ctrl = None;
-            } else {
-              self
-                .disabled_features
-                .get_or_insert(vec![value.to_string()])
-                .push(value.to_string());
-            }
-          } else if let Some(Type2::UTF8ByteString { value, .. }) = tv {
-            let value = std::str::from_utf8(value).map_err(Error::UTF8Parsing)?;
-            if ef.contains(&JsValue::from(value)) {
-              let err_count = self.errors.len();
-              self.visit_type2(target)?;
-              if self.errors.len() > err_count {
-                self.has_feature_errors = true;
-              }
-              self.ctrl = None;
-            } else {
-              self
-                .disabled_features
-                .get_or_insert(vec![value.to_string()])
-                .push(value.to_string());
+          Err(errors) => {
+            for e in errors.into_iter() {
+              self.add_error(e);
             }
           }
         }
 
-        self.ctrl = None;
-
-        Ok(())
-      }
-      _ => {
-        self.add_error(format!("unsupported control operator {}", ctrl));
         Ok(())
       }
-    }
-  }
-
-  fn visit_type2(&mut self, t2: &Type2<'a>) -> visitor::Result<Error<T>> {
-    if matches!(self.ctrl, Some(Token::CBOR)) {
-      if let Value::Bytes(b) = &self.cbor {
-        let value = ciborium::de::from_reader(&b[..]);
-        match value {
-          Ok(value) => {
-            let current_location = self.cbor_location.clone();
-
-            #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-            let mut cv = CBORValidator::new(self.cddl, value, self.enabled_features.clone());
-            #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-            let mut cv = CBORValidator::new(self.cddl, value, self.enabled_features);
-
-            #[cfg(not(feature = "additional-controls"))]
-            let mut cv = CBORValidator::new(self.cddl, value);
-
-            cv.generic_rules = self.generic_rules.clone();
-            cv.eval_generic_rule = self.eval_generic_rule;
-            cv.is_multi_type_choice = self.is_multi_type_choice;
-            cv.is_multi_group_choice = self.is_multi_group_choice;
-            cv.cbor_location.push_str(&self.cbor_location);
-            cv.type_group_name_entry = self.type_group_name_entry;
-            cv.visit_type2(t2)?;
-
-            if cv.errors.is_empty() {
-              self.cbor_location = current_location;
-              return Ok(());
+      Value::Map(m) => {
+        if let Some(occur) = &self.occurrence {
+          #[cfg(feature = "ast-span")]
+          if let Occur::ZeroOrMore(_) | Occur::OneOrMore(_) = occur {
+            if let Occur::OneOrMore(_) = occur {
+              if m.is_empty() {
+                self.add_error(format!(
+                  "map cannot be empty, one or more entries with key type {} required",
+                  ident
+                ));
+                return Ok(());
+              }
             }
 
-            self.errors.append(&mut cv.errors);
-          }
-          Err(e) => {
-            self.add_error(format!("error decoding embedded CBOR, {}", e));
-          }
-        }
-      }
-
-      return Ok(());
-    } else if matches!(self.ctrl, Some(Token::CBORSEQ)) {
-      if let Value::Bytes(b) = &self.cbor {
-        let value = ciborium::de::from_reader(&b[..]);
-        match value {
-          Ok(Value::Array(_)) => {
-            let current_location = self.cbor_location.clone();
-
-            #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-            let mut cv = CBORValidator::new(
-              self.cddl,
-              value.unwrap_or(Value::Null),
-              self.enabled_features.clone(),
-            );
-            #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-            let mut cv = CBORValidator::new(
-              self.cddl,
-              value.unwrap_or(Value::Null),
-              self.enabled_features,
-            );
-
-            #[cfg(not(feature = "additional-controls"))]
-            let mut cv = CBORValidator::new(self.cddl, value.unwrap_or(Value::Null));
+            if is_ident_string_data_type(self.cddl, ident) {
+              let mut errors = Vec::new();
+              let entries_to_validate = m
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, (k, _))| {
+                  if let Some(keys) = &self.validated_keys {
+                    if !keys.contains(k) {
+                      if matches!(k, Value::Text(_)) {
+                        Some(idx)
+                      } else {
+                        errors.push(format!("key of type {} required, got {:?}", ident, k));
+                        None
+                      }
+                    } else {
+                      None
+                    }
+                  } else if matches!(k, Value::Text(_)) {
+                    Some(idx)
+                  } else {
+                    errors.push(format!("key of type {} required, got {:?}", ident, k));
+                    None
+                  }
+                })
+                .collect::<Vec<_>>();
 
-            cv.generic_rules = self.generic_rules.clone();
-            cv.eval_generic_rule = self.eval_generic_rule;
-            cv.is_multi_type_choice = self.is_multi_type_choice;
-            cv.is_multi_group_choice = self.is_multi_group_choice;
-            cv.cbor_location.push_str(&self.cbor_location);
-            cv.type_group_name_entry = self.type_group_name_entry;
-            cv.visit_type2(t2)?;
+              self.entries_to_validate = Some(entries_to_validate);
+              for e in errors.into_iter() {
+                self.add_error(e);
+              }
 
-            if cv.errors.is_empty() {
-              self.cbor_location = current_location;
               return Ok(());
             }
 
-            self.errors.append(&mut cv.errors);
-          }
-          Err(e) => {
-            self.add_error(format!("error decoding embedded CBOR, {}", e));
-          }
-          Ok(v) => self.add_error(format!(
-            "embedded CBOR must be a CBOR sequence, got {:?}",
-            v
-          )),
-        }
-      }
-
-      return Ok(());
-    }
-
-    match t2 {
-      Type2::TextValue { value, .. } => self.visit_value(&token::Value::TEXT(value.clone())),
-      Type2::Map { group, .. } => match &self.cbor {
-        Value::Map(m) => {
-          if self.is_member_key {
-            let current_location = self.cbor_location.clone();
-
-            for (k, v) in m.iter() {
-              #[cfg(feature = "additional-controls")]
-              #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-              let mut cv = CBORValidator::new(self.cddl, k.clone(), self.enabled_features.clone());
-              #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-              let mut cv = CBORValidator::new(self.cddl, k.clone(), self.enabled_features);
-              #[cfg(not(feature = "additional-controls"))]
-              let mut cv = CBORValidator::new(self.cddl, k.clone());
-
-              cv.generic_rules = self.generic_rules.clone();
-              cv.eval_generic_rule = self.eval_generic_rule;
-              cv.is_multi_type_choice = self.is_multi_type_choice;
-              cv.is_multi_group_choice = self.is_multi_group_choice;
-              cv.cbor_location.push_str(&self.cbor_location);
-              cv.type_group_name_entry = self.type_group_name_entry;
-              cv.visit_type2(t2)?;
+            if is_ident_integer_data_type(self.cddl, ident) {
+              let mut errors = Vec::new();
+              let entries_to_validate = m
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, (k, _))| {
+                  if let Some(keys) = &self.validated_keys {
+                    if !keys.contains(k) {
+                      if matches!(k, Value::Integer(_)) {
+                        Some(idx)
+                      } else {
+                        errors.push(format!("key of type {} required, got {:?}", ident, k));
+                        None
+                      }
+                    } else {
+                      None
+                    }
+                  } else if matches!(k, Value::Integer(_)) {
+                    Some(idx)
+                  } else {
+                    errors.push(format!("key of type {} required, got {:?}", ident, k));
+                    None
+                  }
+                })
+                .collect::<Vec<_>>();
 
-              if cv.errors.is_empty() {
-                self.object_value = Some(v.clone());
-                self
-                  .validated_keys
-                  .get_or_insert(vec![k.clone()])
-                  .push(k.clone());
-                self.cbor_location = current_location;
-                return Ok(());
+              self.entries_to_validate = Some(entries_to_validate);
+              for e in errors.into_iter() {
+                self.add_error(e);
               }
 
-              self.errors.append(&mut cv.errors);
+              return Ok(());
             }
 
-            return Ok(());
-          }
-
-          #[allow(clippy::needless_collect)]
-          let m = m.iter().map(|entry| entry.0.clone()).collect::<Vec<_>>();
-
-          self.visit_group(group)?;
+            if is_ident_bool_data_type(self.cddl, ident) {
+              let mut errors = Vec::new();
+              let entries_to_validate = m
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, (k, _))| {
+                  if let Some(keys) = &self.validated_keys {
+                    if !keys.contains(k) {
+                      if matches!(k, Value::Bool(_)) {
+                        Some(idx)
+                      } else {
+                        errors.push(format!("key of type {} required, got {:?}", ident, k));
+                        None
+                      }
+                    } else {
+                      None
+                    }
+                  } else if matches!(k, Value::Bool(_)) {
+                    Some(idx)
+                  } else {
+                    errors.push(format!("key of type {} required, got {:?}", ident, k));
+                    None
+                  }
+                })
+                .collect::<Vec<_>>();
 
-          // If extra map entries are detected, return validation error
-          if self.values_to_validate.is_none() {
-            for k in m.into_iter() {
-              if let Some(keys) = &self.validated_keys {
-                if !keys.contains(&k) {
-                  self.add_error(format!("unexpected key {:?}", k));
-                }
+              self.entries_to_validate = Some(entries_to_validate);
+              for e in errors.into_iter() {
+                self.add_error(e);
               }
-            }
-          }
-
-          self.is_cut_present = false;
-          self.cut_value = None;
-          Ok(())
-        }
-        Value::Array(a) => {
-          // Member keys are annotation only in an array context
-          if self.is_member_key {
-            return Ok(());
-          }
 
-          match validate_array_occurrence(
-            self.occurrence.as_ref().take(),
-            self.entry_counts.as_ref().map(|ec| &ec[..]),
-            a,
-          ) {
-            Ok((iter_items, allow_empty_array)) => {
-              if iter_items {
-                for (idx, v) in a.iter().enumerate() {
-                  if let Some(indices) = &self.valid_array_items {
-                    if self.is_multi_type_choice && indices.contains(&idx) {
-                      continue;
-                    }
-                  }
+              return Ok(());
+            }
 
-                  #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-                  let mut cv =
-                    CBORValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-                  #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-                  let mut cv = CBORValidator::new(self.cddl, v.clone(), self.enabled_features);
-                  #[cfg(not(feature = "additional-controls"))]
-                  let mut cv = CBORValidator::new(self.cddl, v.clone());
-
-                  cv.generic_rules = self.generic_rules.clone();
-                  cv.eval_generic_rule = self.eval_generic_rule;
-                  cv.ctrl = self.ctrl.clone();
-                  cv.is_multi_type_choice = self.is_multi_type_choice;
-                  cv.cbor_location
-                    .push_str(&format!("{}/{}", self.cbor_location, idx));
-
-                  cv.visit_group(group)?;
-
-                  if self.is_multi_type_choice && cv.errors.is_empty() {
-                    if let Some(indices) = &mut self.valid_array_items {
-                      indices.push(idx);
+            if is_ident_byte_string_data_type(self.cddl, ident) {
+              let mut errors = Vec::new();
+              let entries_to_validate = m
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, (k, _))| {
+                  if let Some(keys) = &self.validated_keys {
+                    if !keys.contains(k) {
+                      if matches!(k, Value::Bytes(_)) {
+                        Some(idx)
+                      } else {
+                        errors.push(format!("key of type {} required, got {:?}", ident, k));
+                        None
+                      }
                     } else {
-                      self.valid_array_items = Some(vec![idx]);
+                      None
                     }
-                    continue;
+                  } else if matches!(k, Value::Bytes(_)) {
+                    Some(idx)
+                  } else {
+                    errors.push(format!("key of type {} required, got {:?}", ident, k));
+                    None
                   }
+                })
+                .collect::<Vec<_>>();
+
+              self.entries_to_validate = Some(entries_to_validate);
+              for e in errors.into_iter() {
+                self.add_error(e);
+              }
+
+              return Ok(());
+            }
 
-                  if let Some(errors) = &mut self.array_errors {
-                    if let Some(error) = errors.get_mut(&idx) {
-                      error.append(&mut cv.errors);
+            if is_ident_null_data_type(self.cddl, ident) {
+              let mut errors = Vec::new();
+              let entries_to_validate = m
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, (k, _))| {
+                  if let Some(keys) = &self.validated_keys {
+                    if !keys.contains(k) {
+                      if matches!(k, Value::Null) {
+                        Some(idx)
+                      } else {
+                        errors.push(format!("key of type {} required, got {:?}", ident, k));
+                        None
+                      }
                     } else {
-                      errors.insert(idx, cv.errors);
+                      None
                     }
+                  } else if matches!(k, Value::Null) {
+                    Some(idx)
                   } else {
-                    let mut errors = HashMap::new();
-                    errors.insert(idx, cv.errors);
-                    self.array_errors = Some(errors)
+                    errors.push(format!("key of type {} required, got {:?}", ident, k));
+                    None
                   }
-                }
-              } else if let Some(idx) = self.group_entry_idx.take() {
-                if let Some(v) = a.get(idx) {
-                  #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-                  let mut cv =
-                    CBORValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-                  #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-                  let mut cv = CBORValidator::new(self.cddl, v.clone(), self.enabled_features);
-                  #[cfg(not(feature = "additional-controls"))]
-                  let mut cv = CBORValidator::new(self.cddl, v.clone());
-
-                  cv.generic_rules = self.generic_rules.clone();
-                  cv.eval_generic_rule = self.eval_generic_rule;
-                  cv.ctrl = self.ctrl.clone();
-                  cv.is_multi_type_choice = self.is_multi_type_choice;
-                  cv.cbor_location
-                    .push_str(&format!("{}/{}", self.cbor_location, idx));
-
-                  cv.visit_group(group)?;
-
-                  self.errors.append(&mut cv.errors);
-                } else if !allow_empty_array {
-                  self.add_error(format!("expected map object {} at index {}", group, idx));
-                }
-              } else {
-                self.add_error(format!(
-                  "expected map object {}, got {:?}",
-                  group, self.cbor
-                ));
-              }
-            }
-            Err(errors) => {
+                })
+                .collect::<Vec<_>>();
+
+              self.entries_to_validate = Some(entries_to_validate);
               for e in errors.into_iter() {
                 self.add_error(e);
               }
-            }
-          }
-
-          Ok(())
-        }
-        _ => {
-          self.add_error(format!("expected map object {}, got {:?}", t2, self.cbor));
-          Ok(())
-        }
-      },
-      Type2::Array { group, .. } => match &self.cbor {
-        Value::Array(a) => {
-          if group.group_choices.len() == 1
-            && group.group_choices[0].group_entries.is_empty()
-            && !a.is_empty()
-            && !matches!(self.ctrl, Some(Token::NE) | Some(Token::DEFAULT))
-          {
-            self.add_error(format!("expected empty array, got {:?}", self.cbor));
-            return Ok(());
-          }
 
-          let mut entry_counts = Vec::new();
-          for gc in group.group_choices.iter() {
-            let count = entry_counts_from_group_choice(self.cddl, gc);
-            entry_counts.push(count);
-          }
+              return Ok(());
+            }
 
-          self.entry_counts = Some(entry_counts);
-          self.visit_group(group)?;
-          self.entry_counts = None;
+            if is_ident_float_data_type(self.cddl, ident) {
+              let mut errors = Vec::new();
+              let entries_to_validate = m
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, (k, _))| {
+                  if let Some(keys) = &self.validated_keys {
+                    if !keys.contains(k) {
+                      if matches!(k, Value::Float(_)) {
+                        Some(idx)
+                      } else {
+                        errors.push(format!("key of type {} required, got {:?}", ident, k));
+                        None
+                      }
+                    } else {
+                      None
+                    }
+                  } else if matches!(k, Value::Float(_)) {
+                    Some(idx)
+                  } else {
+                    errors.push(format!("key of type {} required, got {:?}", ident, k));
+                    None
+                  }
+                })
+                .collect::<Vec<_>>();
 
-          if let Some(errors) = &mut self.array_errors {
-            if let Some(indices) = &self.valid_array_items {
-              for idx in indices.iter() {
-                errors.remove(idx);
+              self.entries_to_validate = Some(entries_to_validate);
+              for e in errors.into_iter() {
+                self.add_error(e);
               }
-            }
 
-            for error in errors.values_mut() {
-              self.errors.append(error);
+              return Ok(());
             }
           }
 
-          self.valid_array_items = None;
-          self.array_errors = None;
-
-          Ok(())
-        }
-        Value::Map(m) if self.is_member_key => {
-          let current_location = self.cbor_location.clone();
-
-          let mut entry_counts = Vec::new();
-          for gc in group.group_choices.iter() {
-            let count = entry_counts_from_group_choice(self.cddl, gc);
-            entry_counts.push(count);
-          }
-
-          self.entry_counts = Some(entry_counts);
+          #[cfg(not(feature = "ast-span"))]
+          if let Occur::ZeroOrMore | Occur::OneOrMore = occur {
+            if let Occur::OneOrMore = occur {
+              if m.is_empty() {
+                self.add_error(format!(
+                  "map cannot be empty, one or more entries with key type {} required",
+                  ident
+                ));
+                return Ok(());
+              }
+            }
 
-          for (k, v) in m.iter() {
-            #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-            let mut cv = CBORValidator::new(self.cddl, k.clone(), self.enabled_features.clone());
-            #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-            let mut cv = CBORValidator::new(self.cddl, k.clone(), self.enabled_features);
-            #[cfg(not(feature = "additional-controls"))]
-            let mut cv = CBORValidator::new(self.cddl, k.clone());
+            if is_ident_string_data_type(self.cddl, ident) {
+              let mut errors = Vec::new();
+              let entries_to_validate = m
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, (k, _))| {
+                  if let Some(keys) = &self.validated_keys {
+                    if !keys.contains(k) {
+                      if matches!(k, Value::Text(_)) {
+                        Some(idx)
+                      } else {
+                        errors.push(format!("key of type {} required, got {:?}", ident, k));
+                        None
+                      }
+                    } else {
+                      None
+                    }
+                  } else if matches!(k, Value::Text(_)) {
+                    Some(idx)
+                  } else {
+                    errors.push(format!("key of type {} required, got {:?}", ident, k));
+                    None
+                  }
+                })
+                .collect::<Vec<_>>();
 
-            cv.generic_rules = self.generic_rules.clone();
-            cv.entry_counts = self.entry_counts.clone();
-            cv.eval_generic_rule = self.eval_generic_rule;
-            cv.is_multi_type_choice = self.is_multi_type_choice;
-            cv.is_multi_group_choice = self.is_multi_group_choice;
-            cv.cbor_location.push_str(&self.cbor_location);
-            cv.type_group_name_entry = self.type_group_name_entry;
-            cv.visit_type2(t2)?;
+              self.entries_to_validate = Some(entries_to_validate);
+              for e in errors.into_iter() {
+                self.add_error(e);
+              }
 
-            if cv.errors.is_empty() {
-              self.object_value = Some(v.clone());
-              self
-                .validated_keys
-                .get_or_insert(vec![k.clone()])
-                .push(k.clone());
-              self.cbor_location = current_location;
               return Ok(());
             }
 
-            self.errors.append(&mut cv.errors);
-          }
-
-          self.entry_counts = None;
+            if is_ident_integer_data_type(self.cddl, ident) {
+              let mut errors = Vec::new();
+              let entries_to_validate = m
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, (k, _))| {
+                  if let Some(keys) = &self.validated_keys {
+                    if !keys.contains(k) {
+                      if matches!(k, Value::Integer(_)) {
+                        Some(idx)
+                      } else {
+                        errors.push(format!("key of type {} required, got {:?}", ident, k));
+                        None
+                      }
+                    } else {
+                      None
+                    }
+                  } else if matches!(k, Value::Integer(_)) {
+                    Some(idx)
+                  } else {
+                    errors.push(format!("key of type {} required, got {:?}", ident, k));
+                    None
+                  }
+                })
+                .collect::<Vec<_>>();
 
-          Ok(())
-        }
-        _ => {
-          self.add_error(format!("expected array type, got {:?}", self.cbor));
-          Ok(())
-        }
-      },
-      Type2::ChoiceFromGroup {
-        ident,
-        generic_args,
-        ..
-      } => {
-        if let Some(ga) = generic_args {
-          if let Some(rule) = rule_from_ident(self.cddl, ident) {
-            if let Some(gr) = self
-              .generic_rules
-              .iter_mut()
-              .find(|gr| gr.name == ident.ident)
-            {
-              for arg in ga.args.iter() {
-                gr.args.push((*arg.arg).clone());
+              self.entries_to_validate = Some(entries_to_validate);
+              for e in errors.into_iter() {
+                self.add_error(e);
               }
-            } else if let Some(params) = generic_params_from_rule(rule) {
-              self.generic_rules.push(GenericRule {
-                name: ident.ident,
-                params,
-                args: ga.args.iter().cloned().map(|arg| *arg.arg).collect(),
-              });
-            }
-
-            #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-            let mut cv =
-              CBORValidator::new(self.cddl, self.cbor.clone(), self.enabled_features.clone());
-            #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-            let mut cv = CBORValidator::new(self.cddl, self.cbor.clone(), self.enabled_features);
-            #[cfg(not(feature = "additional-controls"))]
-            let mut cv = CBORValidator::new(self.cddl, self.cbor.clone());
 
-            cv.generic_rules = self.generic_rules.clone();
-            cv.eval_generic_rule = Some(ident.ident);
-            cv.is_group_to_choice_enum = true;
-            cv.is_multi_type_choice = self.is_multi_type_choice;
-            cv.visit_rule(rule)?;
+              return Ok(());
+            }
 
-            self.errors.append(&mut cv.errors);
+            if is_ident_bool_data_type(self.cddl, ident) {
+              let mut errors = Vec::new();
+              let entries_to_validate = m
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, (k, _))| {
+                  if let Some(keys) = &self.validated_keys {
+                    if !keys.contains(k) {
+                      if matches!(k, Value::Bool(_)) {
+                        Some(idx)
+                      } else {
+                        errors.push(format!("key of type {} required, got {:?}", ident, k));
+                        None
+                      }
+                    } else {
+                      None
+                    }
+                  } else if matches!(k, Value::Bool(_)) {
+                    Some(idx)
+                  } else {
+                    errors.push(format!("key of type {} required, got {:?}", ident, k));
+                    None
+                  }
+                })
+                .collect::<Vec<_>>();
 
-            return Ok(());
-          }
-        }
+              self.entries_to_validate = Some(entries_to_validate);
+              for e in errors.into_iter() {
+                self.add_error(e);
+              }
 
-        if group_rule_from_ident(self.cddl, ident).is_none() {
-          self.add_error(format!(
-            "rule {} must be a group rule to turn it into a choice",
-            ident
-          ));
-          return Ok(());
-        }
+              return Ok(());
+            }
 
-        self.is_group_to_choice_enum = true;
-        self.visit_identifier(ident)?;
-        self.is_group_to_choice_enum = false;
+            if is_ident_byte_string_data_type(self.cddl, ident) {
+              let mut errors = Vec::new();
+              let entries_to_validate = m
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, (k, _))| {
+                  if let Some(keys) = &self.validated_keys {
+                    if !keys.contains(k) {
+                      if matches!(k, Value::Bytes(_)) {
+                        Some(idx)
+                      } else {
+                        errors.push(format!("key of type {} required, got {:?}", ident, k));
+                        None
+                      }
+                    } else {
+                      None
+                    }
+                  } else if matches!(k, Value::Bytes(_)) {
+                    Some(idx)
+                  } else {
+                    errors.push(format!("key of type {} required, got {:?}", ident, k));
+                    None
+                  }
+                })
+                .collect::<Vec<_>>();
 
-        Ok(())
-      }
-      Type2::ChoiceFromInlineGroup { group, .. } => {
-        self.is_group_to_choice_enum = true;
-        self.visit_group(group)?;
-        self.is_group_to_choice_enum = false;
-        Ok(())
-      }
-      Type2::Typename {
-        ident,
-        generic_args,
-        ..
-      } => {
-        if let Some(ga) = generic_args {
-          if let Some(rule) = rule_from_ident(self.cddl, ident) {
-            if let Some(gr) = self
-              .generic_rules
-              .iter_mut()
-              .find(|gr| gr.name == ident.ident)
-            {
-              for arg in ga.args.iter() {
-                gr.args.push((*arg.arg).clone());
+              self.entries_to_validate = Some(entries_to_validate);
+              for e in errors.into_iter() {
+                self.add_error(e);
               }
-            } else if let Some(params) = generic_params_from_rule(rule) {
-              self.generic_rules.push(GenericRule {
-                name: ident.ident,
-                params,
-                args: ga.args.iter().cloned().map(|arg| *arg.arg).collect(),
-              });
-            }
-
-            #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-            let mut cv =
-              CBORValidator::new(self.cddl, self.cbor.clone(), self.enabled_features.clone());
-            #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-            let mut cv = CBORValidator::new(self.cddl, self.cbor.clone(), self.enabled_features);
-            #[cfg(not(feature = "additional-controls"))]
-            let mut cv = CBORValidator::new(self.cddl, self.cbor.clone());
-
-            cv.generic_rules = self.generic_rules.clone();
-            cv.eval_generic_rule = Some(ident.ident);
-            cv.is_multi_type_choice = self.is_multi_type_choice;
-            cv.visit_rule(rule)?;
 
-            self.errors.append(&mut cv.errors);
+              return Ok(());
+            }
 
-            return Ok(());
-          }
-        }
+            if is_ident_null_data_type(self.cddl, ident) {
+              let mut errors = Vec::new();
+              let entries_to_validate = m
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, (k, _))| {
+                  if let Some(keys) = &self.validated_keys {
+                    if !keys.contains(k) {
+                      if matches!(k, Value::Null) {
+                        Some(idx)
+                      } else {
+                        errors.push(format!("key of type {} required, got {:?}", ident, k));
+                        None
+                      }
+                    } else {
+                      None
+                    }
+                  } else if matches!(k, Value::Null) {
+                    Some(idx)
+                  } else {
+                    errors.push(format!("key of type {} required, got {:?}", ident, k));
+                    None
+                  }
+                })
+                .collect::<Vec<_>>();
 
-        self.visit_identifier(ident)
-      }
-      Type2::IntValue { value, .. } => self.visit_value(&token::Value::INT(*value)),
-      Type2::UintValue { value, .. } => self.visit_value(&token::Value::UINT(*value)),
-      Type2::FloatValue { value, .. } => self.visit_value(&token::Value::FLOAT(*value)),
-      Type2::ParenthesizedType { pt, .. } => self.visit_type(pt),
-      Type2::Unwrap {
-        ident,
-        generic_args,
-        ..
-      } => {
-        // Per
-        // https://github.com/w3c/did-spec-registries/pull/138#issuecomment-719739215,
-        // strip tag and validate underlying type
-        if let Some(Type2::TaggedData { t, .. }) = tag_from_token(&lookup_ident(ident.ident)) {
-          return self.visit_type(&t);
-        }
-
-        if let Some(ga) = generic_args {
-          if let Some(rule) = unwrap_rule_from_ident(self.cddl, ident) {
-            if let Some(gr) = self
-              .generic_rules
-              .iter_mut()
-              .find(|gr| gr.name == ident.ident)
-            {
-              for arg in ga.args.iter() {
-                gr.args.push((*arg.arg).clone());
+              self.entries_to_validate = Some(entries_to_validate);
+              for e in errors.into_iter() {
+                self.add_error(e);
               }
-            } else if let Some(params) = generic_params_from_rule(rule) {
-              self.generic_rules.push(GenericRule {
-                name: ident.ident,
-                params,
-                args: ga.args.iter().cloned().map(|arg| *arg.arg).collect(),
-              });
-            }
-
-            #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-            let mut cv =
-              CBORValidator::new(self.cddl, self.cbor.clone(), self.enabled_features.clone());
-            #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-            let mut cv = CBORValidator::new(self.cddl, self.cbor.clone(), self.enabled_features);
-            #[cfg(not(feature = "additional-controls"))]
-            let mut cv = CBORValidator::new(self.cddl, self.cbor.clone());
-
-            cv.generic_rules = self.generic_rules.clone();
-            cv.eval_generic_rule = Some(ident.ident);
-            cv.is_multi_type_choice = self.is_multi_type_choice;
-            cv.visit_rule(rule)?;
-
-            self.errors.append(&mut cv.errors);
 
-            return Ok(());
-          }
-        }
+              return Ok(());
+            }
 
-        if let Some(rule) = unwrap_rule_from_ident(self.cddl, ident) {
-          return self.visit_rule(rule);
-        }
+            if is_ident_float_data_type(self.cddl, ident) {
+              let mut errors = Vec::new();
+              let entries_to_validate = m
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, (k, _))| {
+                  if let Some(keys) = &self.validated_keys {
+                    if !keys.contains(k) {
+                      if matches!(k, Value::Float(_)) {
+                        Some(idx)
+                      } else {
+                        errors.push(format!("key of type {} required, got {:?}", ident, k));
+                        None
+                      }
+                    } else {
+                      None
+                    }
+                  } else if matches!(k, Value::Float(_)) {
+                    Some(idx)
+                  } else {
+                    errors.push(format!("key of type {} required, got {:?}", ident, k));
+                    None
+                  }
+                })
+                .collect::<Vec<_>>();
 
-        self.add_error(format!(
-          "cannot unwrap identifier {}, rule not found",
-          ident
-        ));
+              self.entries_to_validate = Some(entries_to_validate);
+              for e in errors.into_iter() {
+                self.add_error(e);
+              }
 
-        Ok(())
-      }
-      Type2::TaggedData { tag, t, .. } => match &self.cbor {
-        Value::Tag(actual_tag, value) => {
-          if let Some(tag) = tag {
-            if *tag as u64 != *actual_tag {
-              self.add_error(format!(
-                "expected tagged data #6.{}({}), got {:?}",
-                tag, t, self.cbor
-              ));
               return Ok(());
             }
-          } else if *actual_tag > 0 {
-            self.add_error(format!(
-              "expected tagged data #6({}), got {:?}",
-              t, self.cbor
-            ));
-            return Ok(());
           }
+        }
 
-          #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-          let mut cv = CBORValidator::new(
-            self.cddl,
-            value.as_ref().clone(),
-            self.enabled_features.clone(),
-          );
-          #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-          let mut cv = CBORValidator::new(self.cddl, value.as_ref().clone(), self.enabled_features);
-          #[cfg(not(feature = "additional-controls"))]
-          let mut cv = CBORValidator::new(self.cddl, value.as_ref().clone());
+        if is_ident_string_data_type(self.cddl, ident) && !self.validating_value {
+          if let Some((idx, (k, v))) = m
+            .iter()
+            .enumerate()
+            .find(|(_, (k, _))| matches!(k, Value::Text(_)))
+          {
+            self
+              .validated_keys
+              .get_or_insert_with(Vec::new)
+              .push(k.clone());
+            self.object_entry_idx = Some(idx);
+            self.cbor_location.push_str(&format!("/{:?}", v));
+          } else {
+            self.add_error(format!("map requires entry key of type {}", ident));
+          }
 
-          cv.generic_rules = self.generic_rules.clone();
-          cv.eval_generic_rule = self.eval_generic_rule;
-          cv.is_multi_type_choice = self.is_multi_type_choice;
-          cv.is_multi_group_choice = self.is_multi_group_choice;
-          cv.cbor_location.push_str(&self.cbor_location);
-          cv.type_group_name_entry = self.type_group_name_entry;
-          cv.visit_type(t)?;
+          return Ok(());
+        }
 
-          self.errors.append(&mut cv.errors);
-          Ok(())
+        if is_ident_integer_data_type(self.cddl, ident) && !self.validating_value {
+          if let Some((idx, (k, v))) = m
+            .iter()
+            .enumerate()
+            .find(|(_, (k, _))| matches!(k, Value::Integer(_)))
+          {
+            self
+              .validated_keys
+              .get_or_insert_with(Vec::new)
+              .push(k.clone());
+            self.object_entry_idx = Some(idx);
+            self.cbor_location.push_str(&format!("/{:?}", v));
+          } else {
+            self.add_error(format!("map requires entry key of type {}", ident));
+          }
+          return Ok(());
         }
-        _ => {
-          if let Some(tag) = tag {
-            self.add_error(format!(
-              "expected tagged data #6.{}({}), got {:?}",
-              tag, t, self.cbor
-            ));
+
+        if is_ident_bool_data_type(self.cddl, ident) && !self.validating_value {
+          if let Some((idx, (k, v))) = m
+            .iter()
+            .enumerate()
+            .find(|(_, (k, _))| matches!(k, Value::Bool(_)))
+          {
+            self
+              .validated_keys
+              .get_or_insert_with(Vec::new)
+              .push(k.clone());
+            self.object_entry_idx = Some(idx);
+            self.cbor_location.push_str(&format!("/{:?}", v));
           } else {
-            self.add_error(format!(
-              "expected tagged data #6({}), got {:?}",
-              t, self.cbor
-            ));
+            self.add_error(format!("map requires entry key of type {}", ident));
           }
+          return Ok(());
+        }
 
-          Ok(())
+        if is_ident_null_data_type(self.cddl, ident) && !self.validating_value {
+          if let Some((idx, (k, v))) = m
+            .iter()
+            .enumerate()
+            .find(|(_, (k, _))| matches!(k, Value::Null))
+          {
+            self
+              .validated_keys
+              .get_or_insert_with(Vec::new)
+              .push(k.clone());
+            self.object_entry_idx = Some(idx);
+            self.cbor_location.push_str(&format!("/{:?}", v));
+          } else {
+            self.add_error(format!("map requires entry key of type {}", ident));
+          }
+          return Ok(());
         }
-      },
-      Type2::DataMajorType { mt, constraint, .. } => match &self.cbor {
-        Value::Integer(i) => {
-          match mt {
-            0u8 => match constraint {
-              Some(c) if i128::from(*i) == *c as i128 && i128::from(*i) >= 0i128 => return Ok(()),
-              Some(c) => {
-                self.add_error(format!(
-                  "expected uint data type with constraint {} (#{}.{}), got {:?}",
-                  c, mt, c, self.cbor
-                ));
-                return Ok(());
-              }
-              _ => {
-                if i128::from(*i).is_negative() {
-                  self.add_error(format!(
-                    "expected uint data type (#{}), got {:?}",
-                    mt, self.cbor
-                  ));
-                  return Ok(());
-                }
-              }
-            },
-            1u8 => match constraint {
-              Some(c) if i128::from(*i) == 0i128 - *c as i128 => return Ok(()),
-              Some(c) => {
-                self.add_error(format!(
-                  "expected nint type with constraint {} (#{}.{}), got {:?}",
-                  c, mt, c, self.cbor
-                ));
-                return Ok(());
-              }
-              _ => {
-                if i128::from(*i) >= 0i128 {
-                  self.add_error(format!(
-                    "expected nint data type (#{}), got {:?}",
-                    mt, self.cbor
-                  ));
-                  return Ok(());
-                }
-              }
-            },
-            _ => self.add_error(format!(
-              "expected major type {} with constraint {:?}, got {:?}",
-              mt, constraint, self.cbor
-            )),
-          }
 
-          Ok(())
-        }
-        Value::Bytes(b) => {
-          match mt {
-            2u8 => match constraint {
-              Some(c) if *c == b.len() => return Ok(()),
-              Some(c) => self.add_error(format!(
-                "expected byte string type with constraint {} (#{}.{}), got {:?}",
-                c, mt, c, self.cbor
-              )),
-              _ => return Ok(()),
-            },
-            _ => self.add_error(format!(
-              "expected major type {} with constraint {:?}, got {:?}",
-              mt, constraint, self.cbor
-            )),
+        if is_ident_byte_string_data_type(self.cddl, ident) && !self.validating_value {
+          if let Some((idx, (k, v))) = m
+            .iter()
+            .enumerate()
+            .find(|(_, (k, _))| matches!(k, Value::Bytes(_)))
+          {
+            self
+              .validated_keys
+              .get_or_insert_with(Vec::new)
+              .push(k.clone());
+            self.object_entry_idx = Some(idx);
+            self.cbor_location.push_str(&format!("/{:?}", v));
+          } else {
+            self.add_error(format!("map requires entry key of type {}", ident));
           }
-
-          Ok(())
+          return Ok(());
         }
-        Value::Text(t) => {
-          match mt {
-            3u8 => match constraint {
-              Some(c) if *c == t.len() => return Ok(()),
-              Some(c) => self.add_error(format!(
-                "expected text string type with constraint {} (#{}.{}), got {:?}",
-                c, mt, c, self.cbor
-              )),
-              _ => return Ok(()),
-            },
-            _ => self.add_error(format!(
-              "expected major type {} with constraint {:?}, got {:?}",
-              mt, constraint, self.cbor
-            )),
-          }
 
-          Ok(())
-        }
-        Value::Array(a) => {
-          match mt {
-            4u8 => match constraint {
-              Some(c) if *c == a.len() => return Ok(()),
-              Some(c) => self.add_error(format!(
-                "expected array type with constraint {} (#{}.{}), got {:?}",
-                c, mt, c, self.cbor
-              )),
-              _ => return Ok(()),
-            },
-            _ => self.add_error(format!(
-              "expected major type {} with constraint {:?}, got {:?}",
-              mt, constraint, self.cbor
-            )),
+        if is_ident_float_data_type(self.cddl, ident) && !self.validating_value {
+          if let Some((idx, (k, v))) = m
+            .iter()
+            .enumerate()
+            .find(|(_, (k, _))| matches!(k, Value::Null))
+          {
+            self
+              .validated_keys
+              .get_or_insert_with(Vec::new)
+              .push(k.clone());
+            self.object_entry_idx = Some(idx);
+            self.cbor_location.push_str(&format!("/{:?}", v));
+          } else {
+            self.add_error(format!("map requires entry key of type {}", ident));
           }
-
-          Ok(())
+          return Ok(());
         }
-        Value::Map(m) => {
-          match mt {
-            5u8 => match constraint {
-              Some(c) if *c == m.len() => return Ok(()),
-              Some(c) => self.add_error(format!(
-                "expected map type with constraint {} (#{}.{}), got {:?}",
-                c, mt, c, self.cbor
-              )),
-              _ => return Ok(()),
-            },
-            _ => self.add_error(format!(
-              "expected major type {} with constraint {:?}, got {:?}",
-              mt, constraint, self.cbor
-            )),
-          }
 
-          Ok(())
+        if token::lookup_ident(&ident.ident)
+          .in_standard_prelude()
+          .is_some()
+        {
+          self.add_error(format!(
+            "expecting object value of type {}, got object",
+            ident.ident
+          ));
+          return Ok(());
         }
-        Value::Float(_f) => {
-          match mt {
-            7u8 => match constraint {
-              Some(_c) => unimplemented!(),
-              _ => return Ok(()),
-            },
-            _ => self.add_error(format!(
-              "expected major type {} with constraint {:?}, got {:?}",
-              mt, constraint, self.cbor
-            )),
-          }
 
-          Ok(())
+        self.visit_value(&token::Value::TEXT(ident.ident.clone()))
+      }
+      _ => {
+        if let Some(cut_value) = self.cut_value.take() {
+          self.add_error(format!(
+            "cut present for member key {}. expected type {}, got {:?}",
+            cut_value, ident, self.cbor
+          ));
+        } else {
+          self.add_error(format!("expected type {}, got {:?}", ident, self.cbor));
         }
-        _ => {
-          if let Some(constraint) = constraint {
-            self.add_error(format!(
-              "expected major type #{}.{}, got {:?}",
-              mt, constraint, self.cbor
-            ));
-          } else {
-            self.add_error(format!("expected major type #{}, got {:?}", mt, self.cbor));
-          }
+        Ok(())
+      }
+    }
+  }
+}
 
-          Ok(())
+impl<'a, T: std::fmt::Debug + 'static> Validator<'a, cbor::Error<T>> for CBORValidator<'a>
+where
+  cbor::Error<T>: From<cbor::Error<ReaderError>>,
+{
+  fn validate(&mut self) -> std::result::Result<(), cbor::Error<T>> {
+    let result = self.validate_document();
+
+    match (result, self.cbor_slice) {
+      (Err(Error::Validation(mut errors)), Some(cbor_slice)) => {
+        if let Some(offsets) = ItemOffsets::new(cbor_slice) {
+          for error in errors.iter_mut().filter(|e| e.cbor_range.is_none()) {
+            error.cbor_range = Some(offsets.byte_range(&error.cbor_location));
+          }
         }
-      },
-      #[cfg(feature = "ast-span")]
-      Type2::Any(_) => Ok(()),
-      #[cfg(not(feature = "ast-span"))]
-      Type2::Any => Ok(()),
-      _ => {
-        self.add_error(format!(
-          "unsupported data type for validating cbor, got {}",
-          t2
-        ));
-        Ok(())
+
+        Err(Error::Validation(errors))
       }
+      (result, _) => result,
     }
   }
 
-  fn visit_identifier(&mut self, ident: &Identifier<'a>) -> visitor::Result<Error<T>> {
-    if let Some(name) = self.eval_generic_rule {
+  fn add_error(&mut self, reason: String) {
+    self.errors.push(ValidationError {
+      reason,
+      cddl_location: self.cddl_location.clone(),
+      cbor_location: self.cbor_location.clone(),
+      is_multi_type_choice: self.is_multi_type_choice,
+      is_multi_group_choice: self.is_multi_group_choice,
+      is_group_to_choice_enum: self.is_group_to_choice_enum,
+      type_group_name_entry: self.type_group_name_entry.as_ref().map(|e| e.to_string()),
+      #[cfg(feature = "ast-span")]
+      cddl_span: self.cddl_span,
+      cbor_range: None,
+    });
+  }
+}
+
+impl<'a, T: std::fmt::Debug + 'static> Visitor<'a, Error<T>> for CBORValidator<'a>
+where
+  cbor::Error<T>: From<cbor::Error<ReaderError>>,
+{
+  fn visit_type_rule(&mut self, tr: &TypeRule<'a>) -> visitor::Result<Error<T>> {
+    if let Some(gp) = &tr.generic_params {
       if let Some(gr) = self
         .generic_rules
-        .iter()
-        .cloned()
-        .find(|gr| gr.name == name)
+        .iter_mut()
+        .find(|r| r.name == tr.name.ident)
       {
-        for (idx, gp) in gr.params.iter().enumerate() {
-          if *gp == ident.ident {
-            if let Some(arg) = gr.args.get(idx) {
-              return self.visit_type1(arg);
-            }
-          }
-        }
+        gr.params = gp.params.iter().map(|p| p.param.ident.clone()).collect();
+      } else {
+        self.generic_rules.push(GenericRule {
+          name: tr.name.ident.clone(),
+          params: gp.params.iter().map(|p| p.param.ident.clone()).collect(),
+          args: vec![],
+        });
       }
     }
 
-    // self.is_colon_shortcut_present is only true when the ident is part of a
-    // member key
-    if !self.is_colon_shortcut_present {
-      if let Some(r) = rule_from_ident(self.cddl, ident) {
-        return self.visit_rule(r);
+    let memo_key = self.memo_key(tr);
+    if let (Some(key), Some(memo)) = (&memo_key, &self.memo) {
+      if let Some(feature_uses) = memo.borrow().validated.get(key) {
+        for feature_use in feature_uses {
+          self.feature_uses.push(FeatureUse {
+            location: format!("{}{}", self.cbor_location, feature_use.location),
+            ..feature_use.clone()
+          });
+        }
+
+        return Ok(());
       }
     }
 
-    if is_ident_any_type(self.cddl, ident) {
-      return Ok(());
-    }
+    self.budget.enter_rule().map_err(Error::LimitExceeded)?;
 
-    match &self.cbor {
-      Value::Null if is_ident_null_data_type(self.cddl, ident) => Ok(()),
-      Value::Bytes(_) if is_ident_byte_string_data_type(self.cddl, ident) => Ok(()),
-      Value::Bool(b) => {
-        if is_ident_bool_data_type(self.cddl, ident) {
-          return Ok(());
-        }
+    // Errors are attributed to the innermost rule being validated
+    let cddl_location = std::mem::replace(&mut self.cddl_location, tr.name.to_string());
+    let error_count = self.errors.len();
+    let feature_use_count = self.feature_uses.len();
 
-        if ident_matches_bool_value(self.cddl, ident, *b) {
-          return Ok(());
+    for t in type_choice_alternates_from_ident(self.cddl, &tr.name) {
+      let cur_errors = self.errors.len();
+      self.visit_type(t)?;
+      if self.errors.len() == cur_errors {
+        for _ in 0..self.errors.len() - error_count {
+          self.errors.pop();
         }
 
-        self.add_error(format!("expected type {}, got {:?}", ident, self.cbor));
-        Ok(())
+        break;
       }
-      Value::Integer(i) => {
-        if is_ident_uint_data_type(self.cddl, ident) {
-          if i128::from(*i).is_negative() {
-            self.add_error(format!("expected type {}, got {:?}", ident, self.cbor));
-          }
+    }
 
-          Ok(())
-        } else if is_ident_integer_data_type(self.cddl, ident) {
-          Ok(())
-        } else if is_ident_time_data_type(self.cddl, ident) {
-          if let chrono::LocalResult::None =
-            Utc.timestamp_millis_opt((i128::from(*i) * 1000) as i64)
-          {
-            let i = *i;
-            self.add_error(format!(
-              "expected time data type, invalid UNIX timestamp {:?}",
-              i,
-            ));
-          }
+    self.cddl_location = cddl_location;
+    self.budget.exit_rule();
 
-          Ok(())
-        } else {
-          self.add_error(format!("expected type {}, got {:?}", ident, self.cbor));
-          Ok(())
-        }
+    if let (Some(key), Some(memo)) = (memo_key, &self.memo) {
+      if self.errors.len() == error_count {
+        let feature_uses = self.feature_uses[feature_use_count..]
+          .iter()
+          .map(|feature_use| FeatureUse {
+            location: feature_use
+              .location
+              .strip_prefix(self.cbor_location.as_str())
+              .unwrap_or(&feature_use.location)
+              .to_string(),
+            ..feature_use.clone()
+          })
+          .collect();
+        memo.borrow_mut().validated.insert(key, feature_uses);
       }
-      Value::Float(f) => {
-        if is_ident_float_data_type(self.cddl, ident) {
-          Ok(())
-        } else if is_ident_time_data_type(self.cddl, ident) {
-          if let chrono::LocalResult::None = Utc.timestamp_millis_opt((*f * 1000f64) as i64) {
-            let f = *f;
-            self.add_error(format!(
-              "expected time data type, invalid UNIX timestamp {:?}",
-              f,
-            ));
-          }
+    }
 
-          Ok(())
-        } else {
-          self.add_error(format!("expected type {}, got {:?}", ident, self.cbor));
-          Ok(())
+    Ok(())
+  }
+
+  fn visit_group_rule(&mut self, gr: &GroupRule<'a>) -> visitor::Result<Error<T>> {
+    if let Some(gp) = &gr.generic_params {
+      if let Some(gr) = self
+        .generic_rules
+        .iter_mut()
+        .find(|r| r.name == gr.name.ident)
+      {
+        gr.params = gp.params.iter().map(|p| p.param.ident.clone()).collect();
+      } else {
+        self.generic_rules.push(GenericRule {
+          name: gr.name.ident.clone(),
+          params: gp.params.iter().map(|p| p.param.ident.clone()).collect(),
+          args: vec![],
+        });
+      }
+    }
+
+    self.budget.enter_rule().map_err(Error::LimitExceeded)?;
+
+    // Errors are attributed to the innermost rule being validated
+    let cddl_location = std::mem::replace(&mut self.cddl_location, gr.name.to_string());
+    let error_count = self.errors.len();
+
+    for ge in group_choice_alternates_from_ident(self.cddl, &gr.name) {
+      let cur_errors = self.errors.len();
+      self.visit_group_entry(ge)?;
+      if self.errors.len() == cur_errors {
+        for _ in 0..self.errors.len() - error_count {
+          self.errors.pop();
         }
+
+        break;
       }
-      Value::Text(s) => {
-        if is_ident_uri_data_type(self.cddl, ident) {
-          if let Err(e) = uriparse::URI::try_from(&**s) {
-            self.add_error(format!("expected URI data type, decoding error: {}", e));
-          }
-        } else if is_ident_b64url_data_type(self.cddl, ident) {
-          if let Err(e) = base64_url::decode(s) {
-            self.add_error(format!(
-              "expected base64 URL data type, decoding error: {}",
-              e
-            ));
-          }
-        } else if is_ident_tdate_data_type(self.cddl, ident) {
-          if let Err(e) = chrono::DateTime::parse_from_rfc3339(s) {
-            self.add_error(format!("expected tdate data type, decoding error: {}", e));
+    }
+
+    self.cddl_location = cddl_location;
+    self.budget.exit_rule();
+
+    Ok(())
+  }
+
+  fn visit_type(&mut self, t: &Type<'a>) -> visitor::Result<Error<T>> {
+    if t.type_choices.len() > 1 {
+      self.is_multi_type_choice = true;
+    }
+
+    let initial_error_count = self.errors.len();
+    let mut rejected = Some(Vec::new());
+    for (idx, type_choice) in t.type_choices.iter().enumerate() {
+      self.budget.step().map_err(Error::LimitExceeded)?;
+
+      // If validating an array whose elements are type choices (i.e. [ 1* tstr
+      // / integer ]), collect all errors and filter after the fact
+      if matches!(*self.cbor, Value::Array(_)) {
+        let error_count = self.errors.len();
+        let explanation_count = self.choice_explanations.len();
+        let feature_use_count = self.feature_uses.len();
+
+        self.visit_type_choice(type_choice)?;
+        self.explain_choice(t, idx, error_count, explanation_count, &mut rejected);
+        if self.errors.len() > error_count {
+          self.feature_uses.truncate(feature_use_count);
+        }
+
+        if self.errors.len() == error_count {
+          // Disregard invalid type choice validation errors if one of the
+          // choices validates successfully
+          let type_choice_error_count = self.errors.len() - initial_error_count;
+          if type_choice_error_count > 0 {
+            for _ in 0..type_choice_error_count {
+              self.errors.pop();
+            }
           }
-        } else if is_ident_string_data_type(self.cddl, ident) {
-          return Ok(());
-        } else {
-          self.add_error(format!("expected type {}, got {:?}", ident, self.cbor));
         }
 
-        Ok(())
+        continue;
       }
-      Value::Array(a) => {
-        // Member keys are annotation only in an array context
-        if self.is_member_key {
-          return Ok(());
-        }
 
-        match validate_array_occurrence(
-          self.occurrence.as_ref().take(),
-          self.entry_counts.as_ref().map(|ec| &ec[..]),
-          a,
-        ) {
-          Ok((iter_items, allow_empty_array)) => {
-            if iter_items {
-              for (idx, v) in a.iter().enumerate() {
-                if let Some(indices) = &self.valid_array_items {
-                  if self.is_multi_type_choice && indices.contains(&idx) {
-                    continue;
-                  }
-                }
+      let error_count = self.errors.len();
+      let explanation_count = self.choice_explanations.len();
+      let feature_use_count = self.feature_uses.len();
+      self.visit_type_choice(type_choice)?;
+      self.explain_choice(t, idx, error_count, explanation_count, &mut rejected);
+      if self.errors.len() > error_count {
+        self.feature_uses.truncate(feature_use_count);
+      }
 
-                #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-                let mut cv =
-                  CBORValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-                #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-                let mut cv = CBORValidator::new(self.cddl, v.clone(), self.enabled_features);
-                #[cfg(not(feature = "additional-controls"))]
-                let mut cv = CBORValidator::new(self.cddl, v.clone());
+      if self.errors.len() == error_count {
+        // Disregard invalid type choice validation errors if one of the
+        // choices validates successfully
+        let type_choice_error_count = self.errors.len() - initial_error_count;
+        if type_choice_error_count > 0 {
+          for _ in 0..type_choice_error_count {
+            self.errors.pop();
+          }
+        }
 
-                cv.generic_rules = self.generic_rules.clone();
-                cv.ctrl = self.ctrl.clone();
-                cv.eval_generic_rule = self.eval_generic_rule;
-                cv.is_multi_type_choice = self.is_multi_type_choice;
-                cv.cbor_location
-                  .push_str(&format!("{}/{}", self.cbor_location, idx));
+        return Ok(());
+      }
+    }
 
-                cv.visit_identifier(ident)?;
+    Ok(())
+  }
 
-                if self.is_multi_type_choice && cv.errors.is_empty() {
-                  if let Some(indices) = &mut self.valid_array_items {
-                    indices.push(idx);
-                  } else {
-                    self.valid_array_items = Some(vec![idx]);
-                  }
-                  continue;
-                }
+  fn visit_group(&mut self, g: &Group<'a>) -> visitor::Result<Error<T>> {
+    if g.group_choices.len() > 1 {
+      self.is_multi_group_choice = true;
+    }
 
-                if let Some(errors) = &mut self.array_errors {
-                  if let Some(error) = errors.get_mut(&idx) {
-                    error.append(&mut cv.errors);
-                  } else {
-                    errors.insert(idx, cv.errors);
-                  }
+    // Map equality/inequality validation
+    if self.is_ctrl_map_equality {
+      if let Some(t) = &self.ctrl {
+        if let Value::Map(m) = &*self.cbor {
+          let mut entry_counts = Vec::new();
+          for gc in g.group_choices.iter() {
+            let count = entry_counts_from_group_choice(self.cddl, gc);
+            entry_counts.push(count);
+          }
+          let len = m.len();
+          if let Token::EQ | Token::NE = t {
+            if !validate_entry_count(&entry_counts, len) {
+              for ec in entry_counts.iter() {
+                if let Some(occur) = &ec.entry_occurrence {
+                  self.add_error(format!(
+                    "expecting array with length per occurrence {}",
+                    occur,
+                  ));
                 } else {
-                  let mut errors = HashMap::new();
-                  errors.insert(idx, cv.errors);
-                  self.array_errors = Some(errors)
+                  self.add_error(format!(
+                    "expecting array with length {}, got {}",
+                    ec.count, len
+                  ));
                 }
               }
-            } else if let Some(idx) = self.group_entry_idx.take() {
-              if let Some(v) = a.get(idx) {
-                #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-                let mut cv =
-                  CBORValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-                #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-                let mut cv = CBORValidator::new(self.cddl, v.clone(), self.enabled_features);
-                #[cfg(not(feature = "additional-controls"))]
-                let mut cv = CBORValidator::new(self.cddl, v.clone());
-
-                cv.generic_rules = self.generic_rules.clone();
-                cv.eval_generic_rule = self.eval_generic_rule;
-                cv.is_multi_type_choice = self.is_multi_type_choice;
-                cv.ctrl = self.ctrl.clone();
-                cv.cbor_location
-                  .push_str(&format!("{}/{}", self.cbor_location, idx));
-
-                cv.visit_identifier(ident)?;
-
-                self.errors.append(&mut cv.errors);
-              } else if !allow_empty_array {
-                self.add_error(format!("expected type {} at index {}", ident, idx));
-              }
-            } else {
-              self.add_error(format!("expected type {}, got {:?}", ident, self.cbor));
+              return Ok(());
             }
           }
-          Err(errors) => {
-            for e in errors.into_iter() {
-              self.add_error(e);
-            }
+        }
+      }
+    }
+
+    self.is_ctrl_map_equality = false;
+
+    let initial_error_count = self.errors.len();
+    let initial_validated_keys = self.validated_keys.clone();
+    let is_cut_matched = self.is_cut_matched;
+    // Keys matched by any of the group choices, none of which validated
+    let mut matched_keys = initial_validated_keys.clone();
+    let mut rejected = Some(Vec::new());
+    for (idx, group_choice) in g.group_choices.iter().enumerate() {
+      let error_count = self.errors.len();
+      let explanation_count = self.choice_explanations.len();
+      let feature_use_count = self.feature_uses.len();
+      self.is_cut_matched = false;
+      self.visit_group_choice(group_choice)?;
+      self.explain_group_choice(g, idx, error_count, explanation_count, &mut rejected);
+      if self.errors.len() == error_count {
+        // Disregard invalid group choice validation errors if one of the
+        // choices validates successfully
+        let group_choice_error_count = self.errors.len() - initial_error_count;
+        if group_choice_error_count > 0 {
+          for _ in 0..group_choice_error_count {
+            self.errors.pop();
           }
         }
 
-        Ok(())
+        self.is_cut_matched |= is_cut_matched;
+        return Ok(());
       }
-      Value::Map(m) => {
-        if let Some(occur) = &self.occurrence {
-          #[cfg(feature = "ast-span")]
-          if let Occur::ZeroOrMore(_) | Occur::OneOrMore(_) = occur {
-            if let Occur::OneOrMore(_) = occur {
-              if m.is_empty() {
-                self.add_error(format!(
-                  "map cannot be empty, one or more entries with key type {} required",
-                  ident
-                ));
-                return Ok(());
-              }
-            }
 
-            if is_ident_string_data_type(self.cddl, ident) {
-              let mut errors = Vec::new();
-              let values_to_validate = m
-                .iter()
-                .filter_map(|(k, v)| {
-                  if let Some(keys) = &self.validated_keys {
-                    if !keys.contains(k) {
-                      if matches!(k, Value::Text(_)) {
-                        Some(v.clone())
-                      } else {
-                        errors.push(format!("key of type {} required, got {:?}", ident, k));
-                        None
-                      }
-                    } else {
-                      None
-                    }
-                  } else if matches!(k, Value::Text(_)) {
-                    Some(v.clone())
-                  } else {
-                    errors.push(format!("key of type {} required, got {:?}", ident, k));
-                    None
-                  }
-                })
-                .collect::<Vec<_>>();
+      self.feature_uses.truncate(feature_use_count);
 
-              self.values_to_validate = Some(values_to_validate);
-              for e in errors.into_iter() {
-                self.add_error(e);
-              }
+      // Keys matched by a failed group choice aren't matched by the choices
+      // that follow
+      if let Some(keys) = self
+        .validated_keys
+        .replace(initial_validated_keys.clone().unwrap_or_default())
+      {
+        matched_keys.get_or_insert_with(Vec::new).extend(keys);
+      }
 
-              return Ok(());
-            }
+      // Once a member key with a cut matched, the remaining group choices
+      // aren't evaluated and only the errors of the committed choice are
+      // reported
+      if self.is_cut_matched {
+        self.errors.drain(initial_error_count..error_count);
+        break;
+      }
+    }
 
-            if is_ident_integer_data_type(self.cddl, ident) {
-              let mut errors = Vec::new();
-              let values_to_validate = m
-                .iter()
-                .filter_map(|(k, v)| {
-                  if let Some(keys) = &self.validated_keys {
-                    if !keys.contains(k) {
-                      if matches!(k, Value::Integer(_)) {
-                        Some(v.clone())
-                      } else {
-                        errors.push(format!("key of type {} required, got {:?}", ident, k));
-                        None
-                      }
-                    } else {
-                      None
-                    }
-                  } else if matches!(k, Value::Integer(_)) {
-                    Some(v.clone())
-                  } else {
-                    errors.push(format!("key of type {} required, got {:?}", ident, k));
-                    None
-                  }
-                })
-                .collect::<Vec<_>>();
+    self.validated_keys = matched_keys;
+    self.is_cut_matched = is_cut_matched;
 
-              self.values_to_validate = Some(values_to_validate);
-              for e in errors.into_iter() {
-                self.add_error(e);
-              }
+    Ok(())
+  }
 
-              return Ok(());
-            }
+  fn visit_group_entry(&mut self, entry: &GroupEntry<'a>) -> visitor::Result<Error<T>> {
+    #[cfg(feature = "ast-span")]
+    let cddl_span = self.cddl_span.replace(entry.span());
 
-            if is_ident_bool_data_type(self.cddl, ident) {
-              let mut errors = Vec::new();
-              let values_to_validate = m
-                .iter()
-                .filter_map(|(k, v)| {
-                  if let Some(keys) = &self.validated_keys {
-                    if !keys.contains(k) {
-                      if matches!(k, Value::Bool(_)) {
-                        Some(v.clone())
-                      } else {
-                        errors.push(format!("key of type {} required, got {:?}", ident, k));
-                        None
-                      }
-                    } else {
-                      None
-                    }
-                  } else if matches!(k, Value::Bool(_)) {
-                    Some(v.clone())
-                  } else {
-                    errors.push(format!("key of type {} required, got {:?}", ident, k));
-                    None
-                  }
-                })
-                .collect::<Vec<_>>();
+    let result = walk_group_entry(self, entry);
 
-              self.values_to_validate = Some(values_to_validate);
-              for e in errors.into_iter() {
-                self.add_error(e);
-              }
+    #[cfg(feature = "ast-span")]
+    {
+      self.cddl_span = cddl_span;
+    }
 
-              return Ok(());
-            }
+    result
+  }
 
-            if is_ident_byte_string_data_type(self.cddl, ident) {
-              let mut errors = Vec::new();
-              let values_to_validate = m
-                .iter()
-                .filter_map(|(k, v)| {
-                  if let Some(keys) = &self.validated_keys {
-                    if !keys.contains(k) {
-                      if matches!(k, Value::Bytes(_)) {
-                        Some(v.clone())
-                      } else {
-                        errors.push(format!("key of type {} required, got {:?}", ident, k));
-                        None
-                      }
-                    } else {
-                      None
-                    }
-                  } else if matches!(k, Value::Bytes(_)) {
-                    Some(v.clone())
-                  } else {
-                    errors.push(format!("key of type {} required, got {:?}", ident, k));
-                    None
+  fn visit_group_choice(&mut self, gc: &GroupChoice<'a>) -> visitor::Result<Error<T>> {
+    if self.is_group_to_choice_enum {
+      let initial_error_count = self.errors.len();
+      for tc in type_choices_from_group_choice(self.cddl, gc).iter() {
+        let error_count = self.errors.len();
+        self.visit_type_choice(tc)?;
+        if self.errors.len() == error_count {
+          let type_choice_error_count = self.errors.len() - initial_error_count;
+          if type_choice_error_count > 0 {
+            for _ in 0..type_choice_error_count {
+              self.errors.pop();
+            }
+          }
+          return Ok(());
+        }
+      }
+
+      return Ok(());
+    }
+
+    let error_count = self.errors.len();
+    for (idx, ge) in gc.group_entries.iter().enumerate() {
+      self.group_entry_idx = Some(idx);
+
+      let is_cut_matched = self.is_cut_matched;
+      let entry_error_count = self.errors.len();
+      self.is_cut_matched = false;
+      self.visit_group_entry(&ge.0)?;
+      // The group choice is committed to by a cut only if none of the group
+      // entries preceding it failed
+      self.is_cut_matched =
+        is_cut_matched || (self.is_cut_matched && entry_error_count == error_count);
+    }
+
+    Ok(())
+  }
+
+  fn visit_range(
+    &mut self,
+    lower: &Type2,
+    upper: &Type2,
+    is_inclusive: bool,
+  ) -> visitor::Result<Error<T>> {
+    if let Value::Array(a) = &*self.cbor {
+      match validate_array_occurrence(
+        self.occurrence.as_ref().take(),
+        self.entry_counts.as_ref().map(|ec| &ec[..]),
+        a,
+      ) {
+        Ok((iter_items, allow_empty_array)) => {
+          if iter_items {
+            for (idx, v) in a.iter().enumerate() {
+              if let Some(indices) = &self.valid_array_items {
+                if self.is_multi_type_choice && indices.contains(&idx) {
+                  continue;
+                }
+              }
+
+              let mut cv = self.nested_validator(v);
+
+              cv.ctrl = self.ctrl.clone();
+              cv.is_multi_type_choice = self.is_multi_type_choice;
+              cv.cbor_location
+                .push_str(&format!("{}/{}", self.cbor_location, idx));
+
+              cv.visit_range(lower, upper, is_inclusive)?;
+
+              if cv.errors.is_empty() {
+                self.labeled_paths.append(&mut cv.labeled_paths);
+                self.choice_explanations.append(&mut cv.choice_explanations);
+                self.feature_uses.append(&mut cv.feature_uses);
+              }
+
+              if self.is_multi_type_choice && cv.errors.is_empty() {
+                self
+                  .valid_array_items
+                  .get_or_insert_with(BTreeSet::new)
+                  .insert(idx);
+                continue;
+              }
+
+              if let Some(errors) = &mut self.array_errors {
+                if let Some(error) = errors.get_mut(&idx) {
+                  error.append(&mut cv.errors);
+                } else {
+                  errors.insert(idx, cv.errors);
+                }
+              } else {
+                let mut errors = BTreeMap::new();
+                errors.insert(idx, cv.errors);
+                self.array_errors = Some(errors)
+              }
+            }
+          } else if let Some(idx) = self.group_entry_idx.take() {
+            if let Some(v) = a.get(idx) {
+              let mut cv = self.nested_validator(v);
+
+              cv.is_multi_type_choice = self.is_multi_type_choice;
+              cv.ctrl = self.ctrl.clone();
+              cv.cbor_location
+                .push_str(&format!("{}/{}", self.cbor_location, idx));
+
+              cv.visit_range(lower, upper, is_inclusive)?;
+
+              if cv.errors.is_empty() {
+                self.labeled_paths.append(&mut cv.labeled_paths);
+                self.choice_explanations.append(&mut cv.choice_explanations);
+                self.feature_uses.append(&mut cv.feature_uses);
+              }
+
+              self.errors.append(&mut cv.errors);
+            } else if !allow_empty_array {
+              self.add_error(format!("expected array item at index {}", idx));
+            }
+          } else {
+            self.add_error(format!(
+              "expected range lower {} upper {} inclusive {}, got {:?}",
+              lower, upper, is_inclusive, self.cbor
+            ));
+          }
+        }
+        Err(errors) => {
+          for e in errors.into_iter() {
+            self.add_error(e);
+          }
+        }
+      }
+
+      return Ok(());
+    }
+
+    match lower {
+      Type2::IntValue { value: l, .. } => match upper {
+        Type2::IntValue { value: u, .. } => {
+          let error_str = if is_inclusive {
+            format!(
+              "expected integer to be in range {} <= value <= {}, got {:?}",
+              l, u, self.cbor
+            )
+          } else {
+            format!(
+              "expected integer to be in range {} < value < {}, got {:?}",
+              l, u, self.cbor
+            )
+          };
+
+          match &*self.cbor {
+            Value::Integer(i) => {
+              if is_inclusive {
+                if i128::from(*i) < *l as i128 || i128::from(*i) > *u as i128 {
+                  self.add_error(error_str);
+                } else {
+                  return Ok(());
+                }
+              } else if i128::from(*i) <= *l as i128 || i128::from(*i) >= *u as i128 {
+                self.add_error(error_str);
+                return Ok(());
+              } else {
+                return Ok(());
+              }
+            }
+            _ => {
+              self.add_error(error_str);
+              return Ok(());
+            }
+          }
+        }
+        Type2::UintValue { value: u, .. } => {
+          let error_str = if is_inclusive {
+            format!(
+              "expected integer to be in range {} <= value <= {}, got {:?}",
+              l, u, self.cbor
+            )
+          } else {
+            format!(
+              "expected integer to be in range {} < value < {}, got {:?}",
+              l, u, self.cbor
+            )
+          };
+
+          match &*self.cbor {
+            Value::Integer(i) => {
+              if is_inclusive {
+                if i128::from(*i) < *l as i128 || i128::from(*i) > *u as i128 {
+                  self.add_error(error_str);
+                } else {
+                  return Ok(());
+                }
+              } else if i128::from(*i) <= *l as i128 || i128::from(*i) >= *u as i128 {
+                self.add_error(error_str);
+                return Ok(());
+              } else {
+                return Ok(());
+              }
+            }
+            _ => {
+              self.add_error(error_str);
+              return Ok(());
+            }
+          }
+        }
+        _ => {
+          self.add_error(format!(
+            "invalid cddl range. upper value must be an integer type. got {}",
+            upper
+          ));
+          return Ok(());
+        }
+      },
+      Type2::UintValue { value: l, .. } => match upper {
+        Type2::UintValue { value: u, .. } => {
+          let error_str = if is_inclusive {
+            format!(
+              "expected uint to be in range {} <= value <= {}, got {:?}",
+              l, u, self.cbor
+            )
+          } else {
+            format!(
+              "expected uint to be in range {} < value < {}, got {:?}",
+              l, u, self.cbor
+            )
+          };
+
+          match &*self.cbor {
+            Value::Integer(i) => {
+              if is_inclusive {
+                if i128::from(*i) < *l as i128 || i128::from(*i) > *u as i128 {
+                  self.add_error(error_str);
+                } else {
+                  return Ok(());
+                }
+              } else if i128::from(*i) <= *l as i128 || i128::from(*i) >= *u as i128 {
+                self.add_error(error_str);
+                return Ok(());
+              } else {
+                return Ok(());
+              }
+            }
+            Value::Text(s) => match self.ctrl {
+              Some(Token::SIZE) => {
+                let len = s.len();
+                let s = s.clone();
+                if is_inclusive {
+                  if s.len() < *l || s.len() > *u {
+                    self.add_error(format!(
+                      "expected \"{}\" string length to be in the range {} <= value <= {}, got {}",
+                      s, l, u, len
+                    ));
+                  }
+
+                  return Ok(());
+                } else if s.len() <= *l || s.len() >= *u {
+                  self.add_error(format!(
+                    "expected \"{}\" string length to be in the range {} < value < {}, got {}",
+                    s, l, u, len
+                  ));
+                  return Ok(());
+                }
+              }
+              _ => {
+                self.add_error("string value cannot be validated against a range without the .size control operator".to_string());
+                return Ok(());
+              }
+            },
+            _ => {
+              self.add_error(error_str);
+              return Ok(());
+            }
+          }
+        }
+        _ => {
+          self.add_error(format!(
+            "invalid cddl range. upper value must be a uint type. got {}",
+            upper
+          ));
+          return Ok(());
+        }
+      },
+      Type2::FloatValue { value: l, .. } => match upper {
+        Type2::FloatValue { value: u, .. } => {
+          let error_str = if is_inclusive {
+            format!(
+              "expected float to be in range {} <= value <= {}, got {:?}",
+              l, u, self.cbor
+            )
+          } else {
+            format!(
+              "expected float to be in range {} < value < {}, got {:?}",
+              l, u, self.cbor
+            )
+          };
+
+          match &*self.cbor {
+            Value::Float(f) => {
+              if is_inclusive {
+                if *f < *l || *f > *u {
+                  self.add_error(error_str);
+                } else {
+                  return Ok(());
+                }
+              } else if *f <= *l || *f >= *u {
+                self.add_error(error_str);
+                return Ok(());
+              } else {
+                return Ok(());
+              }
+            }
+            _ => {
+              self.add_error(error_str);
+              return Ok(());
+            }
+          }
+        }
+        _ => {
+          self.add_error(format!(
+            "invalid cddl range. upper value must be a float type. got {}",
+            upper
+          ));
+          return Ok(());
+        }
+      },
+      _ => {
+        self.add_error(
+          "invalid cddl range. upper and lower values must be either integers or floats"
+            .to_string(),
+        );
+
+        return Ok(());
+      }
+    }
+
+    Ok(())
+  }
+
+  fn visit_control_operator(
+    &mut self,
+    target: &Type2<'a>,
+    ctrl: &str,
+    controller: &Type2<'a>,
+  ) -> visitor::Result<Error<T>> {
+    if let Type2::Typename {
+      ident: target_ident,
+      ..
+    } = target
+    {
+      if let Type2::Typename {
+        ident: controller_ident,
+        ..
+      } = controller
+      {
+        if let Some(name) = &self.eval_generic_rule {
+          if let Some(gr) = self
+            .generic_rules
+            .iter()
+            .cloned()
+            .find(|gr| gr.name == *name)
+          {
+            for (idx, gp) in gr.params.iter().enumerate() {
+              if let Some(arg) = gr.args.get(idx) {
+                if *gp == target_ident.ident {
+                  let t2 = Type2::from(arg.clone());
+
+                  if *gp == controller_ident.ident {
+                    return self.visit_control_operator(&t2, ctrl, &t2);
                   }
-                })
-                .collect::<Vec<_>>();
 
-              self.values_to_validate = Some(values_to_validate);
-              for e in errors.into_iter() {
-                self.add_error(e);
-              }
+                  return self.visit_control_operator(&arg.type2, ctrl, controller);
+                }
+              }
+            }
+          }
+        }
+      }
+
+      if let Some(name) = &self.eval_generic_rule {
+        if let Some(gr) = self
+          .generic_rules
+          .iter()
+          .cloned()
+          .find(|gr| gr.name == *name)
+        {
+          for (idx, gp) in gr.params.iter().enumerate() {
+            if let Some(arg) = gr.args.get(idx) {
+              if *gp == target_ident.ident {
+                let t2 = Type2::from(arg.clone());
+                return self.visit_control_operator(&t2, ctrl, controller);
+              }
+            }
+          }
+        }
+      }
+    }
+
+    match lookup_control_from_str(ctrl) {
+      t @ Some(Token::EQ) => {
+        match target {
+          Type2::Typename { ident, .. } => {
+            if is_ident_string_data_type(self.cddl, ident)
+              || is_ident_numeric_data_type(self.cddl, ident)
+            {
+              return self.visit_type2(controller);
+            }
+          }
+          Type2::Array { group, .. } => {
+            if let Value::Array(_) = &*self.cbor {
+              let mut entry_counts = Vec::new();
+              for gc in group.group_choices.iter() {
+                let count = entry_counts_from_group_choice(self.cddl, gc);
+                entry_counts.push(count);
+              }
+              self.entry_counts = Some(entry_counts);
+              self.visit_type2(controller)?;
+              self.entry_counts = None;
+              return Ok(());
+            }
+          }
+          Type2::Map { .. } => {
+            if let Value::Map(_) = &*self.cbor {
+              self.ctrl = t;
+              self.is_ctrl_map_equality = true;
+              self.visit_type2(controller)?;
+              self.ctrl = None;
+              self.is_ctrl_map_equality = false;
+              return Ok(());
+            }
+          }
+          _ => self.add_error(format!(
+            "target for .eq operator must be a string, numerical, array or map data type, got {}",
+            target
+          )),
+        }
+        Ok(())
+      }
+      t @ Some(Token::NE) => {
+        match target {
+          Type2::Typename { ident, .. } => {
+            if is_ident_string_data_type(self.cddl, ident)
+              || is_ident_numeric_data_type(self.cddl, ident)
+            {
+              self.ctrl = t;
+              self.visit_type2(controller)?;
+              self.ctrl = None;
+              return Ok(());
+            }
+          }
+          Type2::Array { .. } => {
+            if let Value::Array(_) = &*self.cbor {
+              self.ctrl = t;
+              self.visit_type2(controller)?;
+              self.ctrl = None;
+              return Ok(());
+            }
+          }
+          Type2::Map { .. } => {
+            if let Value::Map(_) = &*self.cbor {
+              self.ctrl = t;
+              self.is_ctrl_map_equality = true;
+              self.visit_type2(controller)?;
+              self.ctrl = None;
+              self.is_ctrl_map_equality = false;
+              return Ok(());
+            }
+          }
+          _ => self.add_error(format!(
+            "target for .ne operator must be a string, numerical, array or map data type, got {}",
+            target
+          )),
+        }
+        Ok(())
+      }
+      t @ Some(Token::LT) | t @ Some(Token::GT) | t @ Some(Token::GE) | t @ Some(Token::LE) => {
+        match target {
+          Type2::Typename { ident, .. } if is_ident_numeric_data_type(self.cddl, ident) => {
+            self.ctrl = t;
+            self.visit_type2(controller)?;
+            self.ctrl = None;
+            Ok(())
+          }
+          _ => {
+            self.add_error(format!(
+              "target for .lt, .gt, .ge or .le operator must be a numerical data type, got {}",
+              target
+            ));
+            Ok(())
+          }
+        }
+      }
+      t @ Some(Token::SIZE) => match target {
+        Type2::Typename { ident, .. }
+          if is_ident_string_data_type(self.cddl, ident)
+            || is_ident_uint_data_type(self.cddl, ident)
+            || is_ident_byte_string_data_type(self.cddl, ident) =>
+        {
+          self.ctrl = t;
+          self.visit_type2(controller)?;
+          self.ctrl = None;
+          Ok(())
+        }
+        _ => {
+          self.add_error(format!(
+            "target for .size must a string or uint data type, got {}",
+            target
+          ));
+          Ok(())
+        }
+      },
+      t @ Some(Token::AND) => {
+        self.ctrl = t;
+        self.visit_type2(target)?;
+        self.visit_type2(controller)?;
+        self.ctrl = None;
+        Ok(())
+      }
+      t @ Some(Token::WITHIN) => {
+        self.ctrl = t;
+        let error_count = self.errors.len();
+        self.visit_type2(target)?;
+        let no_errors = self.errors.len() == error_count;
+        self.visit_type2(controller)?;
+        if no_errors && self.errors.len() > error_count {
+          for _ in 0..self.errors.len() - error_count {
+            self.errors.pop();
+          }
+
+          self.add_error(format!(
+            "expected type {} .within type {}, got {:?}",
+            target, controller, self.cbor,
+          ));
+        }
+
+        self.ctrl = None;
+
+        Ok(())
+      }
+      t @ Some(Token::DEFAULT) => {
+        self.ctrl = t;
+        let error_count = self.errors.len();
+        self.visit_type2(target)?;
+        if self.errors.len() != error_count {
+          #[cfg(feature = "ast-span")]
+          if let Some(Occur::Optional(_)) = self.occurrence.take() {
+            self.add_error(format!(
+              "expected default value {}, got {:?}",
+              controller, self.cbor
+            ));
+          }
+          #[cfg(not(feature = "ast-span"))]
+          if let Some(Occur::Optional) = self.occurrence.take() {
+            self.add_error(format!(
+              "expected default value {}, got {:?}",
+              controller, self.cbor
+            ));
+          }
+        }
+        self.ctrl = None;
+        Ok(())
+      }
+      #[cfg(feature = "std")]
+      t @ Some(Token::REGEXP) | t @ Some(Token::PCRE) => {
+        self.ctrl = t;
+        match target {
+          Type2::Typename { ident, .. } if is_ident_string_data_type(self.cddl, ident) => {
+            match *self.cbor {
+              Value::Text(_) | Value::Array(_) => self.visit_type2(controller)?,
+              _ => self.add_error(format!(
+                ".regexp/.pcre control can only be matched against CBOR string, got {:?}",
+                self.cbor
+              )),
+            }
+          }
+          _ => self.add_error(format!(
+            ".regexp/.pcre control can only be matched against string data type, got {}",
+            target
+          )),
+        }
+        self.ctrl = None;
+
+        Ok(())
+      }
+      t @ Some(Token::CBOR) | t @ Some(Token::CBORSEQ) => {
+        self.ctrl = t;
+        match target {
+          Type2::Typename { ident, .. } if is_ident_byte_string_data_type(self.cddl, ident) => {
+            match &*self.cbor {
+              Value::Bytes(_) => self.visit_type2(controller)?,
+              _ => self.add_error(format!(
+                "{} control can only be matched against a CBOR byte string, got {:?}",
+                ctrl, self.cbor
+              )),
+            }
+          }
+          _ => self.add_error(format!(
+            "{} control can only be matched against a byte string data type, got {}",
+            ctrl, target
+          )),
+        }
+        self.ctrl = None;
+
+        Ok(())
+      }
+      t @ Some(Token::BITS) => {
+        self.ctrl = t;
+        match target {
+          Type2::Typename { ident, .. }
+            if is_ident_byte_string_data_type(self.cddl, ident)
+              || is_ident_uint_data_type(self.cddl, ident) =>
+          {
+            match &*self.cbor {
+              Value::Bytes(_) | Value::Array(_) => self.visit_type2(controller)?,
+              // No bits are set in 0, so there are none to match
+              Value::Integer(i) if i128::from(*i) == 0i128 => (),
+              Value::Integer(i) if i128::from(*i) > 0i128 => self.visit_type2(controller)?,
+              _ => self.add_error(format!(
+                "{} control can only be matched against a CBOR byte string or uint, got {:?}",
+                ctrl, self.cbor,
+              )),
+            }
+          }
+          _ => self.add_error(format!(
+            ".bits control can only be matched against a byte string data type, got {}",
+            target
+          )),
+        }
+        self.ctrl = None;
+
+        Ok(())
+      }
+      #[cfg(feature = "additional-controls")]
+      t @ Some(Token::CAT) => {
+        self.ctrl = t;
 
-              return Ok(());
-            }
+        match cat_operation(self.cddl, target, controller, false) {
+          Ok(values) => {
+            let error_count = self.errors.len();
+            for v in values.iter() {
+              let cur_errors = self.errors.len();
 
-            if is_ident_null_data_type(self.cddl, ident) {
-              let mut errors = Vec::new();
-              let values_to_validate = m
-                .iter()
-                .filter_map(|(k, v)| {
-                  if let Some(keys) = &self.validated_keys {
-                    if !keys.contains(k) {
-                      if matches!(k, Value::Null) {
-                        Some(v.clone())
-                      } else {
-                        errors.push(format!("key of type {} required, got {:?}", ident, k));
-                        None
-                      }
-                    } else {
-                      None
-                    }
-                  } else if matches!(k, Value::Null) {
-                    Some(v.clone())
-                  } else {
-                    errors.push(format!("key of type {} required, got {:?}", ident, k));
-                    None
-                  }
-                })
-                .collect::<Vec<_>>();
+              self.visit_type2(v)?;
 
-              self.values_to_validate = Some(values_to_validate);
-              for e in errors.into_iter() {
-                self.add_error(e);
-              }
+              if self.errors.len() == cur_errors {
+                for _ in 0..self.errors.len() - error_count {
+                  self.errors.pop();
+                }
 
-              return Ok(());
+                break;
+              }
             }
+          }
+          Err(e) => self.add_error(e),
+        }
 
-            if is_ident_float_data_type(self.cddl, ident) {
-              let mut errors = Vec::new();
-              let values_to_validate = m
-                .iter()
-                .filter_map(|(k, v)| {
-                  if let Some(keys) = &self.validated_keys {
-                    if !keys.contains(k) {
-                      if matches!(k, Value::Float(_)) {
-                        Some(v.clone())
-                      } else {
-                        errors.push(format!("key of type {} required, got {:?}", ident, k));
-                        None
-                      }
-                    } else {
-                      None
-                    }
-                  } else if matches!(k, Value::Float(_)) {
-                    Some(v.clone())
-                  } else {
-                    errors.push(format!("key of type {} required, got {:?}", ident, k));
-                    None
-                  }
-                })
-                .collect::<Vec<_>>();
+        self.ctrl = None;
 
-              self.values_to_validate = Some(values_to_validate);
-              for e in errors.into_iter() {
-                self.add_error(e);
-              }
+        Ok(())
+      }
+      #[cfg(feature = "additional-controls")]
+      t @ Some(Token::DET) => {
+        self.ctrl = t;
 
-              return Ok(());
-            }
-          }
+        match cat_operation(self.cddl, target, controller, true) {
+          Ok(values) => {
+            let error_count = self.errors.len();
 
-          #[cfg(not(feature = "ast-span"))]
-          if let Occur::ZeroOrMore | Occur::OneOrMore = occur {
-            if let Occur::OneOrMore = occur {
-              if m.is_empty() {
-                self.add_error(format!(
-                  "map cannot be empty, one or more entries with key type {} required",
-                  ident
-                ));
-                return Ok(());
-              }
-            }
+            for v in values.iter() {
+              let cur_errors = self.errors.len();
+              self.visit_type2(v)?;
 
-            if is_ident_string_data_type(self.cddl, ident) {
-              let mut errors = Vec::new();
-              let values_to_validate = m
-                .iter()
-                .filter_map(|(k, v)| {
-                  if let Some(keys) = &self.validated_keys {
-                    if !keys.contains(k) {
-                      if matches!(k, Value::Text(_)) {
-                        Some(v.clone())
-                      } else {
-                        errors.push(format!("key of type {} required, got {:?}", ident, k));
-                        None
-                      }
-                    } else {
-                      None
-                    }
-                  } else if matches!(k, Value::Text(_)) {
-                    Some(v.clone())
-                  } else {
-                    errors.push(format!("key of type {} required, got {:?}", ident, k));
-                    None
-                  }
-                })
-                .collect::<Vec<_>>();
+              if self.errors.len() == cur_errors {
+                for _ in 0..self.errors.len() - error_count {
+                  self.errors.pop();
+                }
 
-              self.values_to_validate = Some(values_to_validate);
-              for e in errors.into_iter() {
-                self.add_error(e);
+                break;
               }
-
-              return Ok(());
             }
+          }
+          Err(e) => self.add_error(e),
+        }
 
-            if is_ident_integer_data_type(self.cddl, ident) {
-              let mut errors = Vec::new();
-              let values_to_validate = m
-                .iter()
-                .filter_map(|(k, v)| {
-                  if let Some(keys) = &self.validated_keys {
-                    if !keys.contains(k) {
-                      if matches!(k, Value::Integer(_)) {
-                        Some(v.clone())
-                      } else {
-                        errors.push(format!("key of type {} required, got {:?}", ident, k));
-                        None
-                      }
-                    } else {
-                      None
-                    }
-                  } else if matches!(k, Value::Integer(_)) {
-                    Some(v.clone())
-                  } else {
-                    errors.push(format!("key of type {} required, got {:?}", ident, k));
-                    None
-                  }
-                })
-                .collect::<Vec<_>>();
+        self.ctrl = None;
 
-              self.values_to_validate = Some(values_to_validate);
-              for e in errors.into_iter() {
-                self.add_error(e);
-              }
+        Ok(())
+      }
+      #[cfg(feature = "additional-controls")]
+      t @ Some(Token::PLUS) => {
+        self.ctrl = t;
 
-              return Ok(());
-            }
+        match plus_operation_with_overflow(self.cddl, target, controller, self.overflow_behavior) {
+          Ok(values) => {
+            let error_count = self.errors.len();
+            for v in values.iter() {
+              let cur_errors = self.errors.len();
 
-            if is_ident_bool_data_type(self.cddl, ident) {
-              let mut errors = Vec::new();
-              let values_to_validate = m
-                .iter()
-                .filter_map(|(k, v)| {
-                  if let Some(keys) = &self.validated_keys {
-                    if !keys.contains(k) {
-                      if matches!(k, Value::Bool(_)) {
-                        Some(v.clone())
-                      } else {
-                        errors.push(format!("key of type {} required, got {:?}", ident, k));
-                        None
-                      }
-                    } else {
-                      None
-                    }
-                  } else if matches!(k, Value::Bool(_)) {
-                    Some(v.clone())
-                  } else {
-                    errors.push(format!("key of type {} required, got {:?}", ident, k));
-                    None
-                  }
-                })
-                .collect::<Vec<_>>();
+              self.visit_type2(v)?;
 
-              self.values_to_validate = Some(values_to_validate);
-              for e in errors.into_iter() {
-                self.add_error(e);
-              }
+              self.visit_type2(v)?;
+              if self.errors.len() == cur_errors {
+                for _ in 0..self.errors.len() - error_count {
+                  self.errors.pop();
+                }
 
-              return Ok(());
+                break;
+              }
             }
+          }
 
-            if is_ident_byte_string_data_type(self.cddl, ident) {
-              let mut errors = Vec::new();
-              let values_to_validate = m
-                .iter()
-                .filter_map(|(k, v)| {
-                  if let Some(keys) = &self.validated_keys {
-                    if !keys.contains(k) {
-                      if matches!(k, Value::Bytes(_)) {
-                        Some(v.clone())
-                      } else {
-                        errors.push(format!("key of type {} required, got {:?}", ident, k));
-                        None
-                      }
-                    } else {
-                      None
-                    }
-                  } else if matches!(k, Value::Bytes(_)) {
-                    Some(v.clone())
-                  } else {
-                    errors.push(format!("key of type {} required, got {:?}", ident, k));
-                    None
-                  }
-                })
-                .collect::<Vec<_>>();
+          Err(e) => self.add_error(e),
+        }
 
-              self.values_to_validate = Some(values_to_validate);
-              for e in errors.into_iter() {
-                self.add_error(e);
-              }
+        self.ctrl = None;
 
-              return Ok(());
-            }
+        Ok(())
+      }
+      #[cfg(feature = "additional-controls")]
+      #[cfg(feature = "std")]
+      t @ Some(Token::ABNF) => {
+        self.ctrl = t;
 
-            if is_ident_null_data_type(self.cddl, ident) {
-              let mut errors = Vec::new();
-              let values_to_validate = m
-                .iter()
-                .filter_map(|(k, v)| {
-                  if let Some(keys) = &self.validated_keys {
-                    if !keys.contains(k) {
-                      if matches!(k, Value::Null) {
-                        Some(v.clone())
-                      } else {
-                        errors.push(format!("key of type {} required, got {:?}", ident, k));
-                        None
+        match target {
+          Type2::Typename { ident, .. } if is_ident_string_data_type(self.cddl, ident) => {
+            match *self.cbor {
+              Value::Text(_) | Value::Array(_) => {
+                if let Type2::ParenthesizedType { pt, .. } = controller {
+                  match abnf_from_complex_controller(self.cddl, pt) {
+                    Ok(values) => {
+                      let error_count = self.errors.len();
+                      for v in values.iter() {
+                        let cur_errors = self.errors.len();
+
+                        self.visit_type2(v)?;
+
+                        if self.errors.len() == cur_errors {
+                          for _ in 0..self.errors.len() - error_count {
+                            self.errors.pop();
+                          }
+
+                          break;
+                        }
                       }
-                    } else {
-                      None
                     }
-                  } else if matches!(k, Value::Null) {
-                    Some(v.clone())
-                  } else {
-                    errors.push(format!("key of type {} required, got {:?}", ident, k));
-                    None
+                    Err(e) => self.add_error(e),
                   }
-                })
-                .collect::<Vec<_>>();
-
-              self.values_to_validate = Some(values_to_validate);
-              for e in errors.into_iter() {
-                self.add_error(e);
+                } else {
+                  self.visit_type2(controller)?
+                }
               }
-
-              return Ok(());
+              _ => self.add_error(format!(
+                ".abnf control can only be matched against a cbor string, got {:?}",
+                self.cbor,
+              )),
             }
+          }
+          _ => self.add_error(format!(
+            ".abnf can only be matched against string data type, got {}",
+            target,
+          )),
+        }
 
-            if is_ident_float_data_type(self.cddl, ident) {
-              let mut errors = Vec::new();
-              let values_to_validate = m
-                .iter()
-                .filter_map(|(k, v)| {
-                  if let Some(keys) = &self.validated_keys {
-                    if !keys.contains(k) {
-                      if matches!(k, Value::Float(_)) {
-                        Some(v.clone())
-                      } else {
-                        errors.push(format!("key of type {} required, got {:?}", ident, k));
-                        None
+        self.ctrl = None;
+
+        Ok(())
+      }
+      #[cfg(feature = "additional-controls")]
+      #[cfg(feature = "std")]
+      t @ Some(Token::ABNFB) => {
+        self.ctrl = t;
+
+        match target {
+          Type2::Typename { ident, .. } if is_ident_byte_string_data_type(self.cddl, ident) => {
+            match *self.cbor {
+              Value::Bytes(_) | Value::Array(_) => {
+                if let Type2::ParenthesizedType { pt, .. } = controller {
+                  match abnf_from_complex_controller(self.cddl, pt) {
+                    Ok(values) => {
+                      let error_count = self.errors.len();
+                      for v in values.iter() {
+                        let cur_errors = self.errors.len();
+
+                        self.visit_type2(v)?;
+
+                        if self.errors.len() == cur_errors {
+                          for _ in 0..self.errors.len() - error_count {
+                            self.errors.pop();
+                          }
+
+                          break;
+                        }
                       }
-                    } else {
-                      None
                     }
-                  } else if matches!(k, Value::Float(_)) {
-                    Some(v.clone())
-                  } else {
-                    errors.push(format!("key of type {} required, got {:?}", ident, k));
-                    None
+                    Err(e) => self.add_error(e),
                   }
-                })
-                .collect::<Vec<_>>();
-
-              self.values_to_validate = Some(values_to_validate);
-              for e in errors.into_iter() {
-                self.add_error(e);
+                } else {
+                  self.visit_type2(controller)?
+                }
               }
-
-              return Ok(());
+              _ => self.add_error(format!(
+                ".abnfb control can only be matched against cbor bytes, got {:?}",
+                self.cbor,
+              )),
             }
           }
+          _ => self.add_error(format!(
+            ".abnfb can only be matched against byte string target data type, got {}",
+            target,
+          )),
         }
 
-        if is_ident_string_data_type(self.cddl, ident) && !self.validating_value {
-          if let Some((k, v)) = m.iter().find(|(k, _)| matches!(k, Value::Text(_))) {
-            self
-              .validated_keys
-              .get_or_insert(vec![k.clone()])
-              .push(k.clone());
-            self.object_value = Some(v.clone());
-            self.cbor_location.push_str(&format!("/{:?}", v));
-          } else {
-            self.add_error(format!("map requires entry key of type {}", ident));
-          }
+        self.ctrl = None;
 
-          return Ok(());
-        }
+        Ok(())
+      }
+      #[cfg(feature = "additional-controls")]
+      t @ Some(Token::FEATURE) => {
+        self.ctrl = t;
+        self.validate_feature(target, controller)?;
+        self.ctrl = None;
 
-        if is_ident_integer_data_type(self.cddl, ident) && !self.validating_value {
-          if let Some((k, v)) = m.iter().find(|(k, _)| matches!(k, Value::Integer(_))) {
-            self
-              .validated_keys
-              .get_or_insert(vec![k.clone()])
-              .push(k.clone());
-            self.object_value = Some(v.clone());
-            self.cbor_location.push_str(&format!("/{:?}", v));
-          } else {
-            self.add_error(format!("map requires entry key of type {}", ident));
-          }
-          return Ok(());
+        Ok(())
+      }
+      #[cfg(feature = "control-plugins")]
+      _ if self
+        .control_plugins
+        .map_or(false, |plugins| plugins.get(ctrl).is_some()) =>
+      {
+        let error_count = self.errors.len();
+        self.visit_type2(target)?;
+        if self.errors.len() == error_count {
+          self.validate_control_plugin(target, ctrl, controller);
         }
+        Ok(())
+      }
+      _ => {
+        self.add_error(format!("unsupported control operator {}", ctrl));
+        Ok(())
+      }
+    }
+  }
 
-        if is_ident_bool_data_type(self.cddl, ident) && !self.validating_value {
-          if let Some((k, v)) = m.iter().find(|(k, _)| matches!(k, Value::Bool(_))) {
-            self
-              .validated_keys
-              .get_or_insert(vec![k.clone()])
-              .push(k.clone());
-            self.object_value = Some(v.clone());
-            self.cbor_location.push_str(&format!("/{:?}", v));
-          } else {
-            self.add_error(format!("map requires entry key of type {}", ident));
-          }
-          return Ok(());
-        }
+  fn visit_type1(&mut self, t1: &Type1<'a>) -> visitor::Result<Error<T>> {
+    #[cfg(feature = "ast-span")]
+    let cddl_span = self.cddl_span.replace(t1.span);
 
-        if is_ident_null_data_type(self.cddl, ident) && !self.validating_value {
-          if let Some((k, v)) = m.iter().find(|(k, _)| matches!(k, Value::Null)) {
-            self
-              .validated_keys
-              .get_or_insert(vec![k.clone()])
-              .push(k.clone());
-            self.object_value = Some(v.clone());
-            self.cbor_location.push_str(&format!("/{:?}", v));
-          } else {
-            self.add_error(format!("map requires entry key of type {}", ident));
-          }
-          return Ok(());
-        }
+    let result = walk_type1(self, t1);
 
-        if is_ident_byte_string_data_type(self.cddl, ident) && !self.validating_value {
-          if let Some((k, v)) = m.iter().find(|(k, _)| matches!(k, Value::Bytes(_))) {
-            self
-              .validated_keys
-              .get_or_insert(vec![k.clone()])
-              .push(k.clone());
-            self.object_value = Some(v.clone());
-            self.cbor_location.push_str(&format!("/{:?}", v));
-          } else {
-            self.add_error(format!("map requires entry key of type {}", ident));
-          }
-          return Ok(());
-        }
+    #[cfg(feature = "ast-span")]
+    {
+      self.cddl_span = cddl_span;
+    }
 
-        if is_ident_float_data_type(self.cddl, ident) && !self.validating_value {
-          if let Some((k, v)) = m.iter().find(|(k, _)| matches!(k, Value::Null)) {
-            self
-              .validated_keys
-              .get_or_insert(vec![k.clone()])
-              .push(k.clone());
-            self.object_value = Some(v.clone());
-            self.cbor_location.push_str(&format!("/{:?}", v));
-          } else {
-            self.add_error(format!("map requires entry key of type {}", ident));
+    result
+  }
+
+  fn visit_type2(&mut self, t2: &Type2<'a>) -> visitor::Result<Error<T>> {
+    if let Some(ctrl @ (Token::CBOR | Token::CBORSEQ)) = &self.ctrl {
+      let is_sequence = matches!(ctrl, Token::CBORSEQ);
+      return self.validate_embedded(t2, is_sequence);
+    }
+
+    match t2 {
+      Type2::TextValue { value, .. } => self.visit_value(&token::Value::TEXT(value.clone())),
+      Type2::Map { group, .. } => self.validate_map(t2, group),
+      Type2::Array { group, .. } => self.validate_array(t2, group),
+      Type2::ChoiceFromGroup {
+        ident,
+        generic_args,
+        ..
+      } => self.validate_choice_from_group(ident, generic_args),
+      Type2::ChoiceFromInlineGroup { group, .. } => {
+        self.is_group_to_choice_enum = true;
+        self.visit_group(group)?;
+        self.is_group_to_choice_enum = false;
+        Ok(())
+      }
+      Type2::Typename {
+        ident,
+        generic_args,
+        ..
+      } => self.validate_typename(ident, generic_args),
+      Type2::IntValue { value, .. } => self.visit_value(&token::Value::INT(*value)),
+      Type2::UintValue { value, .. } => self.visit_value(&token::Value::UINT(*value)),
+      Type2::FloatValue { value, .. } => self.visit_value(&token::Value::FLOAT(*value)),
+      Type2::ParenthesizedType { pt, .. } => self.visit_type(pt),
+      Type2::Unwrap {
+        ident,
+        generic_args,
+        ..
+      } => self.validate_unwrap(ident, generic_args),
+      Type2::TaggedData {
+        tag, tag_type, t, ..
+      } => self.validate_tagged_data(t2, tag, tag_type, t),
+      Type2::DataMajorType { mt, constraint, .. } => self.validate_data_major_type(mt, constraint),
+      #[cfg(feature = "ast-span")]
+      Type2::Any(_) => Ok(()),
+      #[cfg(not(feature = "ast-span"))]
+      Type2::Any => Ok(()),
+      _ => {
+        self.add_error(format!(
+          "unsupported data type for validating cbor, got {}",
+          t2
+        ));
+        Ok(())
+      }
+    }
+  }
+
+  fn visit_identifier(&mut self, ident: &Identifier<'a>) -> visitor::Result<Error<T>> {
+    if let Some(name) = &self.eval_generic_rule {
+      if let Some(gr) = self
+        .generic_rules
+        .iter()
+        .cloned()
+        .find(|gr| gr.name == *name)
+      {
+        for (idx, gp) in gr.params.iter().enumerate() {
+          if *gp == ident.ident {
+            if let Some(arg) = gr.args.get(idx) {
+              return self.visit_type1(arg);
+            }
           }
-          return Ok(());
         }
+      }
+    }
 
-        if token::lookup_ident(ident.ident)
-          .in_standard_prelude()
-          .is_some()
-        {
-          self.add_error(format!(
-            "expecting object value of type {}, got object",
-            ident.ident
-          ));
-          return Ok(());
+    // self.is_colon_shortcut_present is only true when the ident is part of a
+    // member key
+    if !self.is_colon_shortcut_present {
+      if let Some(r) = self.lookup_rule(ident) {
+        let error_count = self.errors.len();
+        let labeled_path_count = self.labeled_paths.len();
+        let item_indices = self.array_item_indices();
+        self.visit_rule(r)?;
+
+        if let Some(indices) = item_indices {
+          self.collect_array_item_labels(ident, &indices, labeled_path_count)?;
+        } else {
+          self.collect_labels(ident, error_count, labeled_path_count);
         }
 
-        self.visit_value(&token::Value::TEXT(ident.ident.into()))
+        return Ok(());
       }
-      _ => {
-        if let Some(cut_value) = self.cut_value.take() {
-          self.add_error(format!(
-            "cut present for member key {}. expected type {}, got {:?}",
-            cut_value, ident, self.cbor
-          ));
-        } else {
-          self.add_error(format!("expected type {}, got {:?}", ident, self.cbor));
+    }
+
+    // Arrays and maps validated against the entries of a group are left to the
+    // validation of the individual entries
+    #[cfg(feature = "std")]
+    if let Some(extended_prelude) = self.extended_prelude {
+      let is_group_entry = matches!(*self.cbor, Value::Array(_) | Value::Map(_))
+        && (self.is_member_key || self.occurrence.is_some() || self.group_entry_idx.is_some());
+      if !self.is_colon_shortcut_present
+        && !is_group_entry
+        && lookup_ident(&ident.ident).in_standard_prelude().is_none()
+      {
+        if let Some(valid) = extended_prelude.validate_cbor(&ident.ident, &self.cbor) {
+          if !valid {
+            self.add_error(format!("expected type {}, got {:?}", ident, self.cbor));
+          }
+
+          return Ok(());
         }
-        Ok(())
       }
     }
+
+    if is_ident_any_type(self.cddl, ident) {
+      return Ok(());
+    }
+
+    self.validate_prelude_type(ident)
   }
 
   fn visit_value_member_key_entry(
@@ -2982,6 +4533,27 @@ where
 
     let current_location = self.cbor_location.clone();
 
+    if let (Some(MemberKey::Type1 { t1, .. }), Value::Map(_), Some(occur)) =
+      (&entry.member_key, &*self.cbor, &self.occurrence)
+    {
+      let bounds = match occur {
+        #[cfg(feature = "ast-span")]
+        Occur::ZeroOrMore(_) => Some((0, None)),
+        #[cfg(not(feature = "ast-span"))]
+        Occur::ZeroOrMore => Some((0, None)),
+        #[cfg(feature = "ast-span")]
+        Occur::OneOrMore(_) => Some((1, None)),
+        #[cfg(not(feature = "ast-span"))]
+        Occur::OneOrMore => Some((1, None)),
+        Occur::Exact { lower, upper, .. } => Some((lower.unwrap_or(0), *upper)),
+        _ => None,
+      };
+
+      if let Some((lower, upper)) = bounds {
+        return self.validate_repeated_map_entries(entry, t1, lower, upper);
+      }
+    }
+
     if let Some(mk) = &entry.member_key {
       let error_count = self.errors.len();
       self.is_member_key = true;
@@ -2995,26 +4567,25 @@ where
       }
     }
 
-    if let Some(values) = &self.values_to_validate {
-      for v in values.iter() {
-        #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-        let mut cv = CBORValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-        #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-        let mut cv = CBORValidator::new(self.cddl, v.clone(), self.enabled_features);
-        #[cfg(not(feature = "additional-controls"))]
-        let mut cv = CBORValidator::new(self.cddl, v.clone());
+    if let (Some(indices), Value::Map(m)) = (&self.entries_to_validate, &*self.cbor) {
+      for (_, v) in indices.iter().filter_map(|idx| m.get(*idx)) {
+        let mut cv = self.nested_validator(v);
 
-        cv.generic_rules = self.generic_rules.clone();
-        cv.eval_generic_rule = self.eval_generic_rule;
         cv.is_multi_type_choice = self.is_multi_type_choice;
         cv.is_multi_group_choice = self.is_multi_group_choice;
         cv.cbor_location.push_str(&self.cbor_location);
-        cv.type_group_name_entry = self.type_group_name_entry;
+        cv.type_group_name_entry = self.type_group_name_entry.clone();
         cv.validating_value = true;
         cv.visit_type(&entry.entry_type)?;
 
         self.cbor_location = current_location.clone();
 
+        if cv.errors.is_empty() {
+          self.labeled_paths.append(&mut cv.labeled_paths);
+          self.choice_explanations.append(&mut cv.choice_explanations);
+          self.feature_uses.append(&mut cv.feature_uses);
+        }
+
         self.errors.append(&mut cv.errors);
         if entry.occur.is_some() {
           self.occurrence = None;
@@ -3024,24 +4595,45 @@ where
       return Ok(());
     }
 
-    if let Some(v) = self.object_value.take() {
-      #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-      let mut cv = CBORValidator::new(self.cddl, v, self.enabled_features.clone());
-      #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-      let mut cv = CBORValidator::new(self.cddl, v, self.enabled_features);
-      #[cfg(not(feature = "additional-controls"))]
-      let mut cv = CBORValidator::new(self.cddl, v);
+    let object_value = match (self.object_entry_idx.take(), &*self.cbor) {
+      (Some(idx), Value::Map(m)) => m.get(idx).map(|(_, v)| v),
+      _ => None,
+    };
+
+    if let Some(v) = object_value {
+      let mut cv = self.nested_validator(v);
 
-      cv.generic_rules = self.generic_rules.clone();
-      cv.eval_generic_rule = self.eval_generic_rule;
       cv.is_multi_type_choice = self.is_multi_type_choice;
       cv.is_multi_group_choice = self.is_multi_group_choice;
       cv.cbor_location.push_str(&self.cbor_location);
-      cv.type_group_name_entry = self.type_group_name_entry;
+      cv.type_group_name_entry = self.type_group_name_entry.clone();
       cv.visit_type(&entry.entry_type)?;
 
       self.cbor_location = current_location;
 
+      let has_cut = entry.member_key.as_ref().is_some_and(member_key_has_cut);
+      // The key matched, so a cut commits to the group choice whether or not
+      // its value validates
+      self.is_cut_matched |= has_cut;
+      if cv.errors.is_empty() {
+        self.labeled_paths.append(&mut cv.labeled_paths);
+        self.choice_explanations.append(&mut cv.choice_explanations);
+        self.feature_uses.append(&mut cv.feature_uses);
+      } else if !has_cut
+        && matches!(
+          entry.occur.as_ref().map(|o| &o.occur),
+          Some(Occur::Optional { .. }) | Some(Occur::ZeroOrMore { .. })
+        )
+      {
+        // Without a cut, a key whose value doesn't match an optional entry is
+        // left to the group entries that follow
+        if let Some(key) = self.validated_keys.as_mut().and_then(|keys| keys.pop()) {
+          self
+            .unmatched_key_errors
+            .push((key, std::mem::take(&mut cv.errors)));
+        }
+      }
+
       self.errors.append(&mut cv.errors);
       if entry.occur.is_some() {
         self.occurrence = None;
@@ -3059,10 +4651,10 @@ where
     &mut self,
     entry: &TypeGroupnameEntry<'a>,
   ) -> visitor::Result<Error<T>> {
-    self.type_group_name_entry = Some(entry.name.ident);
+    self.type_group_name_entry = Some(entry.name.ident.clone());
 
     if let Some(ga) = &entry.generic_args {
-      if let Some(rule) = rule_from_ident(self.cddl, &entry.name) {
+      if let Some(rule) = self.lookup_rule(&entry.name) {
         if let Some(gr) = self
           .generic_rules
           .iter_mut()
@@ -3073,25 +4665,35 @@ where
           }
         } else if let Some(params) = generic_params_from_rule(rule) {
           self.generic_rules.push(GenericRule {
-            name: entry.name.ident,
+            name: entry.name.ident.clone(),
             params,
             args: ga.args.iter().cloned().map(|arg| *arg.arg).collect(),
           });
         }
 
-        #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-        let mut cv =
-          CBORValidator::new(self.cddl, self.cbor.clone(), self.enabled_features.clone());
-        #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-        let mut cv = CBORValidator::new(self.cddl, self.cbor.clone(), self.enabled_features);
-        #[cfg(not(feature = "additional-controls"))]
-        let mut cv = CBORValidator::new(self.cddl, self.cbor.clone());
+        let mut cv = self.nested_validator(&self.cbor);
 
-        cv.generic_rules = self.generic_rules.clone();
-        cv.eval_generic_rule = Some(entry.name.ident);
+        cv.eval_generic_rule = Some(entry.name.ident.clone());
         cv.is_multi_type_choice = self.is_multi_type_choice;
+        cv.validated_keys = self.validated_keys.take();
         cv.visit_rule(rule)?;
 
+        // Keys matched by the generic group are expected by the map
+        self.validated_keys = cv.validated_keys.take();
+        if self.entries_to_validate.is_none() {
+          self.entries_to_validate = cv.entries_to_validate.take();
+        }
+        self.is_cut_matched |= cv.is_cut_matched;
+        self
+          .unmatched_key_errors
+          .append(&mut cv.unmatched_key_errors);
+
+        if cv.errors.is_empty() {
+          self.labeled_paths.append(&mut cv.labeled_paths);
+          self.choice_explanations.append(&mut cv.choice_explanations);
+          self.feature_uses.append(&mut cv.feature_uses);
+        }
+
         self.errors.append(&mut cv.errors);
 
         return Ok(());
@@ -3123,7 +4725,7 @@ where
   }
 
   fn visit_value(&mut self, value: &token::Value<'a>) -> visitor::Result<Error<T>> {
-    let error: Option<String> = match &self.cbor {
+    let error: Option<String> = match &*self.cbor {
       Value::Integer(i) => match value {
         token::Value::INT(v) => match &self.ctrl {
           Some(Token::NE) | Some(Token::DEFAULT) if i128::from(*i) != *v as i128 => None,
@@ -3168,7 +4770,13 @@ where
           Some(Token::LE) if i128::from(*i) <= *v as i128 => None,
           Some(Token::GT) if i128::from(*i) > *v as i128 => None,
           Some(Token::GE) if i128::from(*i) >= *v as i128 => None,
-          Some(Token::SIZE) if i128::from(*i) < 256i128.pow(*v as u32) => None,
+          Some(Token::SIZE)
+            if 256i128
+              .checked_pow(*v as u32)
+              .is_none_or(|max| i128::from(*i) < max) =>
+          {
+            None
+          }
           Some(Token::BITS) => {
             if let Some(sv) = 1u32.checked_shl(*v as u32) {
               if (i128::from(*i) & sv as i128) != 0 {
@@ -3263,27 +4871,31 @@ where
               Some(format!("expected {} .ne to \"{}\"", value, s))
             }
           }
+          #[cfg(feature = "std")]
           Some(Token::REGEXP) | Some(Token::PCRE) => {
-            let re = regex::Regex::new(
-              &format_regex(
-                // Text strings must be JSON escaped per
-                // https://datatracker.ietf.org/doc/html/rfc8610#section-3.1
-                serde_json::from_str::<serde_json::Value>(&format!("\"{}\"", t))
-                  .map_err(Error::JSONParsing)?
-                  .as_str()
-                  .ok_or_else(|| Error::from_validator(self, "malformed regex".to_string()))?,
-              )
-              .ok_or_else(|| Error::from_validator(self, "malformed regex".to_string()))?,
-            )
-            .map_err(|e| Error::from_validator(self, e.to_string()))?;
-
-            if re.is_match(s) {
-              None
-            } else {
-              Some(format!("expected \"{}\" to match regex \"{}\"", s, t))
+            let uncompiled_re;
+            let re = match self.compiled.and_then(|c| c.regex(self.regex_engine, t)) {
+              Some(re) => re,
+              None => {
+                uncompiled_re = self
+                  .regex_engine
+                  .compile(t)
+                  .map_err(|e| Error::from_validator(self, e))?;
+                &uncompiled_re
+              }
+            };
+
+            match re.is_match(s) {
+              Ok(true) => None,
+              Ok(false) => Some(format!("expected \"{}\" to match regex \"{}\"", s, t)),
+              Err(e) => Some(format!(
+                "error matching \"{}\" against regex \"{}\": {}",
+                s, t, e
+              )),
             }
           }
           #[cfg(feature = "additional-controls")]
+          #[cfg(feature = "std")]
           Some(Token::ABNF) => validate_abnf(t, s)
             .err()
             .map(|e| format!("\"{}\" is not valid against abnf: {}", s, e)),
@@ -3384,16 +4996,13 @@ where
         },
         #[cfg(feature = "additional-controls")]
         token::Value::TEXT(t) => match &self.ctrl {
-          Some(Token::ABNFB) => {
-            validate_abnf(t, std::str::from_utf8(b).map_err(Error::UTF8Parsing)?)
-              .err()
-              .map(|e| {
-                format!(
-                  "cbor bytes \"{:?}\" are not valid against abnf {}: {}",
-                  b, t, e
-                )
-              })
-          }
+          #[cfg(feature = "std")]
+          Some(Token::ABNFB) => validate_abnfb(t, b).err().map(|e| {
+            format!(
+              "cbor bytes \"{:?}\" are not valid against abnf {}: {}",
+              b, t, e
+            )
+          }),
           _ => Some(format!(
             "expected value {} {}, got {:?}",
             self.ctrl.clone().unwrap(),
@@ -3423,16 +5032,8 @@ where
                   }
                 }
 
-                #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-                let mut cv =
-                  CBORValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-                #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-                let mut cv = CBORValidator::new(self.cddl, v.clone(), self.enabled_features);
-                #[cfg(not(feature = "additional-controls"))]
-                let mut cv = CBORValidator::new(self.cddl, v.clone());
+                let mut cv = self.nested_validator(v);
 
-                cv.generic_rules = self.generic_rules.clone();
-                cv.eval_generic_rule = self.eval_generic_rule;
                 cv.is_multi_type_choice = self.is_multi_type_choice;
                 cv.ctrl = self.ctrl.clone();
                 cv.cbor_location
@@ -3440,12 +5041,17 @@ where
 
                 cv.visit_value(value)?;
 
+                if cv.errors.is_empty() {
+                  self.labeled_paths.append(&mut cv.labeled_paths);
+                  self.choice_explanations.append(&mut cv.choice_explanations);
+                  self.feature_uses.append(&mut cv.feature_uses);
+                }
+
                 if self.is_multi_type_choice && cv.errors.is_empty() {
-                  if let Some(indices) = &mut self.valid_array_items {
-                    indices.push(idx);
-                  } else {
-                    self.valid_array_items = Some(vec![idx]);
-                  }
+                  self
+                    .valid_array_items
+                    .get_or_insert_with(BTreeSet::new)
+                    .insert(idx);
                   continue;
                 }
 
@@ -3456,23 +5062,15 @@ where
                     errors.insert(idx, cv.errors);
                   }
                 } else {
-                  let mut errors = HashMap::new();
+                  let mut errors = BTreeMap::new();
                   errors.insert(idx, cv.errors);
                   self.array_errors = Some(errors)
                 }
               }
             } else if let Some(idx) = self.group_entry_idx.take() {
               if let Some(v) = a.get(idx) {
-                #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-                let mut cv =
-                  CBORValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-                #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-                let mut cv = CBORValidator::new(self.cddl, v.clone(), self.enabled_features);
-                #[cfg(not(feature = "additional-controls"))]
-                let mut cv = CBORValidator::new(self.cddl, v.clone());
-
-                cv.generic_rules = self.generic_rules.clone();
-                cv.eval_generic_rule = self.eval_generic_rule;
+                let mut cv = self.nested_validator(v);
+
                 cv.ctrl = self.ctrl.clone();
                 cv.is_multi_type_choice = self.is_multi_type_choice;
                 cv.cbor_location
@@ -3480,6 +5078,12 @@ where
 
                 cv.visit_value(value)?;
 
+                if cv.errors.is_empty() {
+                  self.labeled_paths.append(&mut cv.labeled_paths);
+                  self.choice_explanations.append(&mut cv.choice_explanations);
+                  self.feature_uses.append(&mut cv.feature_uses);
+                }
+
                 self.errors.append(&mut cv.errors);
               } else if !allow_empty_array {
                 self.add_error(format!("expected value {} at index {}", value, idx));
@@ -3508,15 +5112,17 @@ where
 
         // Retrieve the value from key unless optional/zero or more, in which
         // case advance to next group entry
-        let k = token_value_into_cbor_value(value.clone());
 
         #[cfg(feature = "ast-span")]
-        if let Some(v) = o
+        if let Some(idx) = o
           .iter()
-          .find_map(|entry| if entry.0 == k { Some(&entry.1) } else { None })
+          .position(|(k, _)| token_value_matches_cbor_value(value, k))
         {
-          self.validated_keys.get_or_insert(vec![k.clone()]).push(k);
-          self.object_value = Some(v.clone());
+          self
+            .validated_keys
+            .get_or_insert_with(Vec::new)
+            .push(o[idx].0.clone());
+          self.object_entry_idx = Some(idx);
           self.cbor_location.push_str(&format!("/{}", value));
 
           None
@@ -3532,12 +5138,15 @@ where
         }
 
         #[cfg(not(feature = "ast-span"))]
-        if let Some(v) = o
+        if let Some(idx) = o
           .iter()
-          .find_map(|entry| if entry.0 == k { Some(&entry.1) } else { None })
+          .position(|(k, _)| token_value_matches_cbor_value(value, k))
         {
-          self.validated_keys.get_or_insert(vec![k.clone()]).push(k);
-          self.object_value = Some(v.clone());
+          self
+            .validated_keys
+            .get_or_insert_with(Vec::new)
+            .push(o[idx].0.clone());
+          self.object_entry_idx = Some(idx);
           self.cbor_location.push_str(&format!("/{}", value));
 
           None
@@ -3567,6 +5176,29 @@ where
   }
 }
 
+// Decode the CBOR embedded in a byte string, either a single data item or, for
+// a CBOR sequence, its zero or more data items as an array
+fn decode_embedded_cbor(mut bytes: &[u8], is_sequence: bool) -> std::result::Result<Value, String> {
+  let mut items = Vec::new();
+  while !bytes.is_empty() || (!is_sequence && items.is_empty()) {
+    let item: Value = ciborium::de::from_reader(&mut bytes).map_err(|e| e.to_string())?;
+    if !is_sequence && !bytes.is_empty() {
+      return Err(format!(
+        "{} trailing bytes after the embedded data item",
+        bytes.len()
+      ));
+    }
+
+    items.push(item);
+  }
+
+  if is_sequence {
+    Ok(Value::Array(items))
+  } else {
+    Ok(items.remove(0))
+  }
+}
+
 /// Converts a CDDL value type to ciborium::value::Value
 pub fn token_value_into_cbor_value(value: token::Value) -> ciborium::value::Value {
   match value {
@@ -3582,6 +5214,22 @@ pub fn token_value_into_cbor_value(value: token::Value) -> ciborium::value::Valu
   }
 }
 
+// Whether the CBOR value equals the given token value, compared without
+// converting the token value into an owned CBOR value
+fn token_value_matches_cbor_value(value: &token::Value, cbor: &Value) -> bool {
+  match (value, cbor) {
+    (token::Value::UINT(u), Value::Integer(i)) => i128::from(*i) == *u as i128,
+    (token::Value::INT(n), Value::Integer(i)) => i128::from(*i) == *n as i128,
+    (token::Value::FLOAT(f), Value::Float(cf)) => f == cf,
+    (token::Value::TEXT(t), Value::Text(ct)) => t == ct,
+    (
+      token::Value::BYTE(ByteValue::UTF8(b) | ByteValue::B16(b) | ByteValue::B64(b)),
+      Value::Bytes(cb),
+    ) => b.as_ref() == cb.as_slice(),
+    _ => false,
+  }
+}
+
 #[cfg(test)]
 #[cfg(not(target_arch = "wasm32"))]
 mod tests {
@@ -3645,6 +5293,15 @@ mod tests {
     let mut cv = CBORValidator::new(&cddl, cbor, None);
     cv.validate()?;
 
+    // Byte strings need not be valid UTF-8
+    let cbor = ciborium::value::Value::Bytes(vec![0x60, 0x86, 0x48, 0x01]);
+    let mut cv = CBORValidator::new(&cddl, cbor, None);
+    cv.validate()?;
+
+    let cbor = ciborium::value::Value::Bytes(vec![0x60, 0x86]);
+    let mut cv = CBORValidator::new(&cddl, cbor, None);
+    assert!(cv.validate().is_err());
+
     Ok(())
   }
 
@@ -3672,4 +5329,782 @@ mod tests {
 
     Ok(())
   }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_root_rule() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    use ciborium::value::Value;
+
+    let cddl = indoc!(
+      r#"
+        person = { name: tstr }
+        point = [x: int, y: int]
+        wrapped<t> = #6.24(t)
+      "#
+    );
+
+    let cddl = cddl_from_str(cddl, true)?;
+
+    let validate = |cbor: Value,
+                    rule: &'static str,
+                    args: Option<&'static str>|
+     -> cbor::Result<std::io::Error> {
+      let generic_args = args.map(|args| {
+        crate::parser::Parser::new(args, Box::new(crate::lexer_from_str(args).iter()))
+          .and_then(|mut p| p.parse_genericargs())
+          .unwrap()
+      });
+
+      let mut cv = CBORValidator::new(&cddl, cbor, None);
+      cv.set_root_rule(rule, generic_args);
+      cv.validate()
+    };
+
+    let point = Value::Array(vec![Value::Integer(1.into()), Value::Integer(2.into())]);
+
+    validate(point.clone(), "point", None)?;
+    assert!(validate(point.clone(), "person", None).is_err());
+
+    let tagged = Value::Tag(24, Box::new(point));
+    validate(tagged.clone(), "wrapped", Some("<point>"))?;
+    assert!(validate(tagged, "wrapped", Some("<tstr>")).is_err());
+
+    assert!(matches!(
+      validate(Value::Null, "missing", None),
+      Err(Error::MissingRule(rule)) if rule == "missing"
+    ));
+
+    Ok(())
+  }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_deterministic_encoding() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = cddl_from_str("transaction = { 0: [* uint], 1: uint, ? 2: tstr }", true)?;
+
+    let validate = |cbor_slice: &[u8]| -> cbor::Result<std::io::Error> {
+      let cbor: Value = ciborium::de::from_reader(cbor_slice).unwrap();
+      let mut cv = CBORValidator::new(&cddl, cbor, None);
+      cv.set_deterministic_encoding(cbor_slice);
+      cv.validate()
+    };
+
+    // { 0: [1, 2], 1: 500 }
+    validate(&[0xa2, 0x00, 0x82, 0x01, 0x02, 0x01, 0x19, 0x01, 0xf4])?;
+
+    // { 1: 500, 0: [_ 1, 2] }, which conforms to the CDDL document
+    let cbor_slice = [0xa2, 0x01, 0x19, 0x01, 0xf4, 0x00, 0x9f, 0x01, 0x02, 0xff];
+    let mut cv = CBORValidator::new(&cddl, ciborium::de::from_reader(&cbor_slice[..])?, None);
+    cv.validate()?;
+
+    match validate(&cbor_slice) {
+      Err(Error::Validation(errors)) => assert_eq!(
+        errors
+          .iter()
+          .map(|e| (e.cbor_location.as_str(), e.reason.as_str()))
+          .collect::<Vec<_>>(),
+        vec![
+          (
+            "/0",
+            "map key is not sorted in the bytewise lexicographic order of its encoding at byte offset 5"
+          ),
+          ("/0", "indefinite-length item at byte offset 6"),
+        ]
+      ),
+      result => panic!("expected encoding violations, got {:?}", result),
+    }
+
+    // Violations are reported along with schema validation errors
+    // { 0: [1], 1: "a" }
+    match validate(&[0xa2, 0x00, 0x81, 0x18, 0x01, 0x01, 0x61, 0x61]) {
+      Err(Error::Validation(errors)) => {
+        assert_eq!(errors.len(), 2);
+        assert!(errors[1].to_string().contains("argument 1 is not encoded"));
+      }
+      result => panic!("expected validation errors, got {:?}", result),
+    }
+
+    Ok(())
+  }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_memoized_subtrees() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let memoized = |cv: &CBORValidator| cv.memo.as_ref().map_or(0, |m| m.borrow().validated.len());
+
+    let cddl = cddl_from_str(
+      indoc!(
+        r#"
+          options = [* option]
+          option = { name: tstr, ? value: uint }
+        "#
+      ),
+      true,
+    )?;
+
+    let option = |value: Value| {
+      Value::Map(vec![
+        (
+          Value::Text("name".to_string()),
+          Value::Text("a".to_string()),
+        ),
+        (Value::Text("value".to_string()), value),
+      ])
+    };
+    let valid = option(Value::Integer(1.into()));
+    let invalid = option(Value::Integer((-1).into()));
+
+    // Identical subtrees share an identifier
+    let memo = Memo::new(&Value::Array(vec![
+      valid.clone(),
+      valid.clone(),
+      invalid.clone(),
+    ]))
+    .unwrap();
+    assert_eq!(memo.subtree_ids.len(), 4);
+    assert_eq!(memo.subtree_ids.values().collect::<BTreeSet<_>>().len(), 3);
+
+    let mut cv = CBORValidator::new(
+      &cddl,
+      Value::Array(vec![valid.clone(), valid.clone(), valid.clone()]),
+      None,
+    );
+    cv.set_memoize(true);
+    <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv)?;
+    assert_eq!(memoized(&cv), 1);
+
+    // Memoization is opt-in
+    let mut cv = CBORValidator::new(&cddl, Value::Array(vec![valid.clone()]), None);
+    <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv)?;
+    assert!(cv.memo.is_none());
+
+    // Invalid subtrees are revalidated to report their errors at each location
+    let mut cv = CBORValidator::new(
+      &cddl,
+      Value::Array(vec![valid, invalid.clone(), invalid]),
+      None,
+    );
+    cv.set_memoize(true);
+    match <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv) {
+      Err(Error::Validation(errors)) => assert_eq!(
+        errors
+          .iter()
+          .map(|e| e.cbor_location.as_str())
+          .collect::<Vec<_>>(),
+        vec![r#"/1/"value""#, r#"/2/"value""#]
+      ),
+      result => panic!("expected validation errors, got {:?}", result),
+    }
+
+    // Features used by memoized subtrees are reported at each location
+    let cddl = cddl_from_str(
+      indoc!(
+        r#"
+          root = [* item]
+          item = { ? x: ext }
+          ext = tstr .feature "ext"
+        "#
+      ),
+      true,
+    )?;
+    let item = Value::Map(vec![(
+      Value::Text("x".to_string()),
+      Value::Text("a".to_string()),
+    )]);
+    let mut cv = CBORValidator::new(
+      &cddl,
+      Value::Array(vec![item.clone(), item.clone(), item]),
+      None,
+    );
+    cv.set_memoize(true);
+    <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv)?;
+    assert_eq!(memoized(&cv), 1);
+    assert_eq!(
+      cv.feature_uses()
+        .iter()
+        .map(|f| f.location.as_str())
+        .collect::<Vec<_>>(),
+      vec![r#"/0/"x""#, r#"/1/"x""#, r#"/2/"x""#]
+    );
+
+    Ok(())
+  }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_byte_ranges() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = cddl_from_str("message = { header: { id: uint }, body: [* tstr] }", true)?;
+
+    // { "header": { "id": 1 }, "body": ["a", 2] }
+    let cbor_slice = [
+      0xa2, 0x66, 0x68, 0x65, 0x61, 0x64, 0x65, 0x72, 0xa1, 0x62, 0x69, 0x64, 0x01, 0x64, 0x62,
+      0x6f, 0x64, 0x79, 0x82, 0x61, 0x61, 0x02,
+    ];
+
+    let mut cv = CBORValidator::new(&cddl, ciborium::de::from_reader(&cbor_slice[..])?, None);
+    cv.set_cbor_slice(&cbor_slice);
+    match cv.validate() {
+      Err(Error::Validation(errors)) => {
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].cbor_location, r#"/"body"/1"#);
+        assert_eq!(errors[0].cbor_range, Some((21, 22)));
+        assert!(errors[0].to_string().contains("(bytes 21..22)"));
+      }
+      result => panic!("expected validation errors, got {:?}", result),
+    }
+
+    // Without the encoding, errors don't have byte ranges
+    let mut cv = CBORValidator::new(&cddl, ciborium::de::from_reader(&cbor_slice[..])?, None);
+    match <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv) {
+      Err(Error::Validation(errors)) => assert_eq!(errors[0].cbor_range, None),
+      result => panic!("expected validation errors, got {:?}", result),
+    }
+
+    Ok(())
+  }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_embedded_cbor() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = cddl_from_str(
+      indoc!(
+        r#"
+          envelope = { payload: bstr .cbor inner, ? log: bstr .cborseq [* uint], ? wrapped: #6.24(bstr .cbor inner) }
+          inner = { id: uint }
+        "#
+      ),
+      true,
+    )?;
+
+    let encode = |value: &Value| -> std::result::Result<Vec<u8>, Box<dyn std::error::Error>> {
+      let mut bytes = Vec::new();
+      ciborium::ser::into_writer(value, &mut bytes)?;
+      Ok(bytes)
+    };
+    let inner = |id: Value| Value::Map(vec![(Value::Text("id".into()), id)]);
+    let envelope = |entries: Vec<(&str, Value)>| {
+      Value::Map(
+        entries
+          .into_iter()
+          .map(|(k, v)| (Value::Text(k.into()), v))
+          .collect(),
+      )
+    };
+
+    let valid = encode(&inner(Value::Integer(1.into())))?;
+    let mut cv = CBORValidator::new(
+      &cddl,
+      envelope(vec![
+        ("payload", Value::Bytes(valid.clone())),
+        ("log", Value::Bytes(vec![0x01, 0x02, 0x18, 0x2a])),
+        (
+          "wrapped",
+          Value::Tag(24, Box::new(Value::Bytes(valid.clone()))),
+        ),
+      ]),
+      None,
+    );
+    <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv)?;
+
+    // Errors within the embedded data item are located beneath the control
+    let invalid = encode(&inner(Value::Text("1".into())))?;
+    let mut cv = CBORValidator::new(
+      &cddl,
+      envelope(vec![("payload", Value::Bytes(invalid))]),
+      None,
+    );
+    match <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv) {
+      Err(Error::Validation(errors)) => {
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].cbor_location, r#"/"payload"/.cbor/"id""#);
+      }
+      result => panic!("expected validation errors, got {:?}", result),
+    }
+
+    // Items of a CBOR sequence are located by their index
+    let mut cv = CBORValidator::new(
+      &cddl,
+      envelope(vec![
+        ("payload", Value::Bytes(valid.clone())),
+        ("log", Value::Bytes(vec![0x01, 0x61, 0x61])),
+      ]),
+      None,
+    );
+    match <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv) {
+      Err(Error::Validation(errors)) => {
+        assert_eq!(errors[0].cbor_location, r#"/"log"/.cborseq/1"#);
+      }
+      result => panic!("expected validation errors, got {:?}", result),
+    }
+
+    // A .cbor byte string holds exactly one data item
+    let mut trailing = valid.clone();
+    trailing.push(0x01);
+    let mut cv = CBORValidator::new(
+      &cddl,
+      envelope(vec![("payload", Value::Bytes(trailing))]),
+      None,
+    );
+    match <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv) {
+      Err(Error::Validation(errors)) => assert!(errors[0].reason.contains("trailing bytes")),
+      result => panic!("expected validation errors, got {:?}", result),
+    }
+
+    Ok(())
+  }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_embedded_cbor_depth() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = cddl_from_str("nested = uint / bstr .cbor nested", true)?;
+
+    let mut value = Value::Integer(1.into());
+    for _ in 0..3 {
+      let mut bytes = Vec::new();
+      ciborium::ser::into_writer(&value, &mut bytes)?;
+      value = Value::Bytes(bytes);
+    }
+
+    let mut cv = CBORValidator::new(&cddl, value.clone(), None);
+    <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv)?;
+
+    let mut cv = CBORValidator::new(&cddl, value, None);
+    cv.set_max_embedded_depth(2);
+    match <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv) {
+      Err(Error::Validation(errors)) => assert!(errors
+        .iter()
+        .any(|e| e.reason.contains("maximum nesting depth of 2"))),
+      result => panic!("expected validation errors, got {:?}", result),
+    }
+
+    Ok(())
+  }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_cut() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let locations = |cddl: &str, cbor: Value| -> Vec<String> {
+      let cddl = cddl_from_str(cddl, true).unwrap();
+      let mut cv = CBORValidator::new(&cddl, cbor, None);
+      match <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv) {
+        Ok(()) => Vec::new(),
+        Err(Error::Validation(errors)) => errors.into_iter().map(|e| e.cbor_location).collect(),
+        r => panic!("unexpected result {:?}", r),
+      }
+    };
+    let map = |entries: Vec<(&str, Value)>| {
+      Value::Map(
+        entries
+          .into_iter()
+          .map(|(k, v)| (Value::Text(k.into()), v))
+          .collect(),
+      )
+    };
+
+    assert!(locations(
+      r#"m = { ? "a" => uint, * tstr => tstr }"#,
+      map(vec![("a", Value::Text("x".into()))])
+    )
+    .is_empty());
+    assert_eq!(
+      locations(
+        r#"m = { ? "a" ^ => uint, * tstr => tstr }"#,
+        map(vec![("a", Value::Text("x".into()))])
+      ),
+      vec![r#"/"a""#]
+    );
+    assert_eq!(
+      locations(
+        r#"m = { type: "x", val: uint // type: "y", val: tstr }"#,
+        map(vec![
+          ("type", Value::Text("x".into())),
+          ("val", Value::Text("s".into()))
+        ])
+      ),
+      vec![r#"/"val""#]
+    );
+    assert_eq!(
+      locations(
+        r#"m = { "x" ^ => int // "x" => tstr }"#,
+        map(vec![("x", Value::Text("s".into()))])
+      ),
+      vec![r#"/"x""#]
+    );
+    assert_eq!(
+      locations(
+        r#"m = { "x" ^ => int, "y" => uint // "x" => tstr }"#,
+        map(vec![("x", Value::Text("s".into()))])
+      ),
+      vec![r#"/"x""#, ""]
+    );
+    assert_eq!(
+      locations(
+        "m = { ? a: uint, ? b: uint }",
+        Value::Map(vec![
+          (Value::Text("c".into()), Value::Integer(1.into())),
+          (Value::Integer(1.into()), Value::Integer(1.into()))
+        ])
+      ),
+      vec![r#"/"c""#, "/1"]
+    );
+
+    Ok(())
+  }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_int_keyed_maps() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = cddl_from_str(
+      indoc!(
+        r#"
+          header = { ? 1 => tstr, ? 2 => int }
+          private = { ? 1 => tstr, * nint => any }
+          claims = { ? 1 => tstr, * label => any }
+          reserved = { ? 1 => tstr, 1*2 (10..20) => uint }
+          label = int / tstr
+        "#
+      ),
+      true,
+    )?;
+    let locations = |rule: &str, entries: Vec<(i64, Value)>| -> Vec<String> {
+      let cbor = Value::Map(
+        entries
+          .into_iter()
+          .map(|(k, v)| (Value::Integer(k.into()), v))
+          .collect(),
+      );
+      let mut cv = CBORValidator::new(&cddl, cbor, None);
+      cv.set_root_rule(rule, None);
+      match <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv) {
+        Ok(()) => Vec::new(),
+        Err(Error::Validation(errors)) => errors.into_iter().map(|e| e.cbor_location).collect(),
+        r => panic!("unexpected result {:?}", r),
+      }
+    };
+    let text = || Value::Text("x".into());
+    let int = |i: i64| Value::Integer(i.into());
+
+    assert_eq!(
+      locations("header", vec![(1, text()), (3, int(0))]),
+      vec!["/3"]
+    );
+    assert!(locations("private", vec![(1, text()), (-5, int(0))]).is_empty());
+    assert_eq!(locations("private", vec![(5, int(0))]), vec!["/5"]);
+    assert!(locations("claims", vec![(1, text()), (7, text())]).is_empty());
+    assert!(locations("reserved", vec![(15, int(1))]).is_empty());
+    assert_eq!(locations("reserved", vec![(15, text())]), vec!["/15"]);
+    assert_eq!(
+      locations("reserved", vec![(10, int(1)), (11, int(1)), (12, int(1))]),
+      vec!["/12"]
+    );
+    assert_eq!(locations("reserved", vec![(1, text())]), vec![""]);
+
+    Ok(())
+  }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_tag_number_types() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = cddl_from_str(
+      indoc!(
+        r#"
+          private = #6.<private-tags>(tstr)
+          signed = tagged<bstr, 18>
+          reference = #6.42(uint)
+          tagged<t, n> = #6.<n>(t)
+          private-tags = 1000..1999 / 80000
+        "#
+      ),
+      true,
+    )?;
+    let errors = |rule: &str, tag: u64, cbor: Value| -> Vec<String> {
+      let mut cv = CBORValidator::new(&cddl, Value::Tag(tag, Box::new(cbor)), None);
+      cv.set_root_rule(rule, None);
+      match <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv) {
+        Ok(()) => Vec::new(),
+        Err(Error::Validation(errors)) => errors.into_iter().map(|e| e.reason).collect(),
+        r => panic!("unexpected result {:?}", r),
+      }
+    };
+    let text = || Value::Text("x".into());
+    let bytes = || Value::Bytes(vec![0x01]);
+
+    assert!(errors("private", 1500, text()).is_empty());
+    assert!(errors("private", 80000, text()).is_empty());
+    assert_eq!(
+      errors("private", 2000, text()),
+      vec!["expected tagged data #6.<private-tags>(tstr) with a tag number matching private-tags, got tag 2000"]
+    );
+    assert!(errors("signed", 18, bytes()).is_empty());
+    assert_eq!(
+      errors("signed", 17, bytes()),
+      vec!["expected tagged data #6.<n>(t) with a tag number matching n, got tag 17"]
+    );
+    assert_eq!(
+      errors("reference", 43, Value::Integer(1.into())),
+      vec!["expected tagged data #6.42(uint) with tag 42, got tag 43"]
+    );
+
+    Ok(())
+  }
+
+  #[cfg(all(feature = "parallel", feature = "additional-controls"))]
+  #[test]
+  fn validate_array_items_in_parallel() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = cddl_from_str(
+      indoc!(
+        r#"
+          ledger = [* record]
+          record = { id: uint, amount: int }
+        "#
+      ),
+      true,
+    )?;
+
+    let record = |id: u64, amount: Value| {
+      Value::Map(vec![
+        (Value::Text("id".into()), Value::Integer(id.into())),
+        (Value::Text("amount".into()), amount),
+      ])
+    };
+    let mut records = (0..200)
+      .map(|id| record(id, Value::Integer((-(id as i64)).into())))
+      .collect::<Vec<_>>();
+
+    let mut cv = CBORValidator::new(&cddl, Value::Array(records.clone()), None);
+    <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv)?;
+
+    for idx in [151, 7, 150] {
+      records[idx] = record(idx as u64, Value::Text("lots".into()));
+    }
+    let mut cv = CBORValidator::new(&cddl, Value::Array(records), None);
+    match <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv) {
+      Err(Error::Validation(errors)) => assert_eq!(
+        errors
+          .iter()
+          .map(|e| e.cbor_location.as_str())
+          .collect::<Vec<_>>(),
+        vec![r#"/7/"amount""#, r#"/150/"amount""#, r#"/151/"amount""#]
+      ),
+      result => panic!("expected validation errors, got {:?}", result),
+    }
+
+    Ok(())
+  }
+
+  #[cfg(all(feature = "parallel", feature = "additional-controls"))]
+  #[test]
+  fn validate_deeply_nested_array_items_beyond_the_default_limits() {
+    // Elements nesting deeper than the default limits allow don't fit the
+    // stacks of the worker threads, so they're validated on the thread
+    // validating the array, sized here for the raised limits
+    let is_valid = std::thread::Builder::new()
+      .stack_size(64 << 20)
+      .spawn(|| {
+        let cddl = cddl_from_str("a = [* a]", true).unwrap();
+
+        let mut item = Value::Array(Vec::new());
+        for _ in 0..1000 {
+          item = Value::Array(vec![item]);
+        }
+
+        let mut cv = CBORValidator::new(&cddl, Value::Array(vec![item; PARALLEL_THRESHOLD]), None);
+        cv.set_limits(ValidationLimits {
+          max_depth: 2000,
+          ..Default::default()
+        });
+        <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv).is_ok()
+      })
+      .unwrap()
+      .join()
+      .unwrap();
+
+    assert!(is_valid);
+  }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_deeply_nested_arrays() {
+    // Validation within the default limits fits the default stack of spawned
+    // threads, even in unoptimized builds
+    let limit_exceeded = std::thread::Builder::new()
+      .stack_size(2 << 20)
+      .spawn(|| {
+        let cddl = cddl_from_str("a = [* a]", true).unwrap();
+
+        let mut value = Value::Array(Vec::new());
+        for _ in 0..5000 {
+          value = Value::Array(vec![value]);
+        }
+
+        let mut cv = CBORValidator::new(&cddl, value, None);
+        matches!(
+          <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv),
+          Err(Error::LimitExceeded(_))
+        )
+      })
+      .unwrap()
+      .join()
+      .unwrap();
+
+    assert!(limit_exceeded);
+  }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_trees_at_the_default_depth_limit() {
+    // Each level of the tree references both child and node
+    let depth = (ValidationLimits::default().max_depth - 2) / 2;
+
+    let results = std::thread::Builder::new()
+      .stack_size(2 << 20)
+      .spawn(move || {
+        let cddl = cddl_from_str("root = node\nnode = { ? c: child }\nchild = node", true).unwrap();
+
+        [depth, depth + 1].map(|depth| {
+          let mut value = Value::Map(Vec::new());
+          for _ in 0..depth {
+            value = Value::Map(vec![(Value::Text("c".into()), value)]);
+          }
+
+          let mut cv = CBORValidator::new(&cddl, value, None);
+          <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv)
+        })
+      })
+      .unwrap()
+      .join()
+      .unwrap();
+
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(Error::LimitExceeded(_))));
+  }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_limits() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = cddl_from_str("nested = { ? child: nested }", true)?;
+
+    let mut value = Value::Map(Vec::new());
+    for _ in 0..20 {
+      value = Value::Map(vec![(Value::Text("child".into()), value)]);
+    }
+
+    let validate = |limits: ValidationLimits| {
+      let mut cv = CBORValidator::new(&cddl, value.clone(), None);
+      cv.set_limits(limits);
+      <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv)
+    };
+
+    validate(ValidationLimits::default())?;
+    assert!(matches!(
+      validate(ValidationLimits {
+        max_depth: 8,
+        ..Default::default()
+      }),
+      Err(Error::LimitExceeded(_))
+    ));
+    assert!(matches!(
+      validate(ValidationLimits {
+        max_steps: 8,
+        ..Default::default()
+      }),
+      Err(Error::LimitExceeded(_))
+    ));
+
+    Ok(())
+  }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_nested_map_entries() {
+    let errors = |cddl: &str, cbor: Value| -> Vec<(String, String)> {
+      let cddl = cddl_from_str(cddl, true).unwrap();
+      let mut cv = CBORValidator::new(&cddl, cbor, None);
+      match <CBORValidator as Validator<Error<std::io::Error>>>::validate(&mut cv) {
+        Ok(()) => Vec::new(),
+        Err(Error::Validation(errors)) => errors
+          .into_iter()
+          .map(|e| (e.cbor_location, e.reason))
+          .collect(),
+        r => panic!("unexpected result {:?}", r),
+      }
+    };
+
+    let cddl = indoc!(
+      r#"
+        ledger = { accounts: [* account], ? 1 => { * int => tstr } }
+        account = { id: tstr, "balance" => uint, -2 => bool }
+      "#
+    );
+    let account = |id: &str, balance: Value| {
+      Value::Map(vec![
+        (Value::Text("id".into()), Value::Text(id.into())),
+        (Value::Text("balance".into()), balance),
+        (Value::Integer((-2).into()), Value::Bool(true)),
+      ])
+    };
+    let ledger = |accounts: Vec<Value>, metadata: Vec<(Value, Value)>| {
+      Value::Map(vec![
+        (Value::Text("accounts".into()), Value::Array(accounts)),
+        (Value::Integer(1.into()), Value::Map(metadata)),
+      ])
+    };
+
+    assert_eq!(
+      errors(
+        cddl,
+        ledger(
+          vec![account("a", Value::Integer(1.into()))],
+          vec![(Value::Integer(7.into()), Value::Text("x".into()))]
+        )
+      ),
+      vec![]
+    );
+    assert_eq!(
+      errors(
+        cddl,
+        ledger(
+          vec![
+            account("a", Value::Integer(1.into())),
+            account("b", Value::Text("x".into()))
+          ],
+          vec![(Value::Integer(7.into()), Value::Integer(8.into()))]
+        )
+      ),
+      vec![
+        (
+          r#"/"accounts"/1/"balance""#.to_string(),
+          r#"expected type uint, got Text("x")"#.to_string()
+        ),
+        (
+          "/1/7".to_string(),
+          "expected type tstr, got Integer(Integer(8))".to_string()
+        ),
+      ]
+    );
+
+    let mut extra = account("a", Value::Integer(1.into()));
+    if let Value::Map(m) = &mut extra {
+      m.push((Value::Integer(3.into()), Value::Null));
+    }
+    assert_eq!(
+      errors(cddl, ledger(vec![extra], vec![])),
+      vec![(
+        r#"/"accounts"/0/3"#.to_string(),
+        "unexpected key 3".to_string()
+      )]
+    );
+
+    let mut extra = account("a", Value::Integer(1.into()));
+    if let Value::Map(m) = &mut extra {
+      m.push((Value::Text("@context2".to_string()), Value::Null));
+    }
+    assert_eq!(
+      errors(cddl, ledger(vec![extra], vec![])),
+      vec![(
+        r#"/"accounts"/0/"@context2""#.to_string(),
+        r#"unexpected key "@context2""#.to_string()
+      )]
+    );
+  }
 }
@@ -0,0 +1,453 @@
+#![cfg(not(feature = "lsp"))]
+
+use super::*;
+use crate::{
+  ast::{Identifier, Rule, Type2, CDDL},
+  visitor::{self, Visitor},
+};
+
+#[cfg(feature = "std")]
+use crate::generics::{self, generic_bounds_from_str};
+#[cfg(feature = "std")]
+use std::{borrow::Cow, fmt};
+
+#[cfg(not(feature = "std"))]
+use alloc::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use core::fmt;
+
+/// CDDL compilation error
+#[derive(Debug)]
+pub enum Error {
+  /// CDDL parsing error
+  CDDLParsing(parser::Error),
+  /// Generic rule resolution error
+  #[cfg(feature = "std")]
+  Generics(generics::Error),
+  /// Controller of a control operator can never be satisfied, e.g. a malformed
+  /// `.regexp` pattern
+  InvalidController {
+    /// Control operator
+    ctrl: String,
+    /// Controller
+    controller: String,
+    /// Reason the controller is invalid
+    reason: String,
+  },
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Error::CDDLParsing(error) => write!(f, "error parsing CDDL: {}", error),
+      #[cfg(feature = "std")]
+      Error::Generics(error) => write!(f, "error resolving generic rules: {}", error),
+      Error::InvalidController {
+        ctrl,
+        controller,
+        reason,
+      } => write!(
+        f,
+        "invalid controller {} of control operator {}: {}",
+        controller, ctrl, reason
+      ),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Error::CDDLParsing(error) => Some(error),
+      Error::Generics(error) => Some(error),
+      _ => None,
+    }
+  }
+}
+
+/// CDDL document prepared once for the validation of many data items
+///
+/// Compilation parses the document, instantiates generic rules (enforcing any
+/// `;@bound` annotations), indexes rules by name and checks and compiles the
+/// regular expressions of `.regexp` and `.pcre` controls up front. Validating
+/// a data item against a compiled document skips this work, and a compiled
+/// document is `Send + Sync` so it can be shared across threads
+///
+/// # Example
+///
+/// ```
+/// use cddl::validator::compiled::CompiledCddl;
+///
+/// let compiled = CompiledCddl::compile(r#"person = { name: tstr, age: uint }"#).unwrap();
+///
+/// #[cfg(feature = "additional-controls")]
+/// assert!(compiled
+///   .validate_json(r#"{ "name": "Alice", "age": 42 }"#, None)
+///   .is_ok());
+/// #[cfg(not(feature = "additional-controls"))]
+/// assert!(compiled
+///   .validate_json(r#"{ "name": "Alice", "age": 42 }"#)
+///   .is_ok());
+/// ```
+#[derive(Debug)]
+pub struct CompiledCddl<'a> {
+  cddl: CDDL<'a>,
+  // Indices of the non-choice alternate rules defining each name
+  rules: BTreeMap<Cow<'a, str>, Vec<usize>>,
+  // Engine the regular expressions are compiled with
+  regex_engine: RegexEngine,
+  // Compiled regular expressions keyed by their text controller
+  #[cfg(feature = "std")]
+  regexes: BTreeMap<String, Regex>,
+}
+
+impl<'a> CompiledCddl<'a> {
+  /// Compile a CDDL document string. Generic rules are instantiated
+  /// ahead of time unless they are recursive, in which case they are resolved
+  /// during validation as with an uncompiled document
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn compile(input: &'a str) -> std::result::Result<Self, Error> {
+    #[cfg(feature = "std")]
+    return Self::compile_with_regex_engine(input, RegexEngine::default());
+
+    #[cfg(not(feature = "std"))]
+    Self::from_cddl(cddl_from_str(input).map_err(Error::CDDLParsing)?)
+  }
+
+  /// Compile a CDDL document string as with [`CompiledCddl::compile`],
+  /// compiling the regular expressions of `.regexp` and `.pcre` controls with
+  /// the given engine. Validators of the compiled document match the controls
+  /// with the same engine
+  #[cfg(not(target_arch = "wasm32"))]
+  #[cfg(feature = "std")]
+  pub fn compile_with_regex_engine(
+    input: &'a str,
+    regex_engine: RegexEngine,
+  ) -> std::result::Result<Self, Error> {
+    let cddl = cddl_from_str(input, false).map_err(Error::CDDLParsing)?;
+
+    Self::from_cddl_with_regex_engine(resolve_generics(input, cddl)?, regex_engine)
+  }
+
+  /// Compile a CDDL document parsed from the given document string, e.g. with
+  /// a parser whose errors are reported separately. Generic rules are
+  /// instantiated ahead of time as with [`CompiledCddl::compile`]
+  #[cfg(feature = "std")]
+  pub fn from_parsed(input: &'a str, cddl: CDDL<'a>) -> std::result::Result<Self, Error> {
+    Self::from_cddl(resolve_generics(input, cddl)?)
+  }
+
+  /// Compile a parsed CDDL document. Generic rules are left to be resolved
+  /// during validation
+  pub fn from_cddl(cddl: CDDL<'a>) -> std::result::Result<Self, Error> {
+    Self::from_cddl_with_regex_engine(cddl, RegexEngine::default())
+  }
+
+  /// Compile a parsed CDDL document as with [`CompiledCddl::from_cddl`],
+  /// compiling the regular expressions of `.regexp` and `.pcre` controls with
+  /// the given engine
+  pub fn from_cddl_with_regex_engine(
+    cddl: CDDL<'a>,
+    regex_engine: RegexEngine,
+  ) -> std::result::Result<Self, Error> {
+    Self::compile_parsed(cddl, regex_engine, None)
+  }
+
+  // Compile an edited CDDL document with the engine of its previous
+  // compilation, reusing the regular expressions of the controllers that are
+  // unchanged
+  #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+  pub(crate) fn recompile(
+    cddl: CDDL<'a>,
+    previous: &CompiledCddl,
+  ) -> std::result::Result<Self, Error> {
+    Self::compile_parsed(cddl, previous.regex_engine, Some(previous))
+  }
+
+  #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+  fn compile_parsed(
+    cddl: CDDL<'a>,
+    regex_engine: RegexEngine,
+    previous: Option<&CompiledCddl>,
+  ) -> std::result::Result<Self, Error> {
+    let mut rules: BTreeMap<Cow<'a, str>, Vec<usize>> = BTreeMap::new();
+    for (idx, rule) in cddl.rules.iter().enumerate() {
+      let (name, is_alternate) = match rule {
+        Rule::Type { rule, .. } => (&rule.name, rule.is_type_choice_alternate),
+        Rule::Group { rule, .. } => (&rule.name, rule.is_group_choice_alternate),
+      };
+
+      if !is_alternate {
+        rules.entry(name.ident.clone()).or_default().push(idx);
+      }
+    }
+
+    let mut controls = Controls {
+      cddl: &cddl,
+      regex_engine,
+      #[cfg(feature = "std")]
+      regexes: BTreeMap::new(),
+      #[cfg(feature = "std")]
+      previous: previous.map(|previous| &previous.regexes),
+    };
+    for rule in cddl.rules.iter() {
+      controls.visit_rule(rule)?;
+    }
+    #[cfg(feature = "std")]
+    let regexes = controls.regexes;
+
+    Ok(CompiledCddl {
+      cddl,
+      rules,
+      regex_engine,
+      #[cfg(feature = "std")]
+      regexes,
+    })
+  }
+
+  /// Compiled CDDL document. Generic rules that were instantiated ahead of
+  /// time are not included
+  pub fn cddl(&self) -> &CDDL<'a> {
+    &self.cddl
+  }
+
+  /// Validate CBOR slice against the compiled document
+  #[cfg(not(target_arch = "wasm32"))]
+  #[cfg(feature = "cbor")]
+  pub fn validate_cbor(
+    &self,
+    cbor_slice: &[u8],
+    #[cfg(feature = "additional-controls")] enabled_features: Option<&[&str]>,
+  ) -> cbor::Result<ReaderError> {
+    let cbor: ciborium::value::Value =
+      ciborium::de::from_reader(cbor_slice).map_err(cbor::Error::CBORParsing)?;
+
+    #[cfg(feature = "additional-controls")]
+    let mut cv = CBORValidator::new(&self.cddl, cbor, enabled_features);
+    #[cfg(not(feature = "additional-controls"))]
+    let mut cv = CBORValidator::new(&self.cddl, cbor);
+
+    cv.set_cbor_slice(cbor_slice);
+    cv.set_compiled(self);
+    cv.set_regex_engine(self.regex_engine);
+    cv.validate()
+  }
+
+  /// Validate JSON string against the compiled document
+  #[cfg(not(target_arch = "wasm32"))]
+  #[cfg(feature = "json")]
+  pub fn validate_json(
+    &self,
+    json: &str,
+    #[cfg(feature = "additional-controls")] enabled_features: Option<&[&str]>,
+  ) -> json::Result {
+    let json = serde_json::from_str::<serde_json::Value>(json).map_err(json::Error::JSONParsing)?;
+
+    #[cfg(feature = "additional-controls")]
+    let mut jv = JSONValidator::new(&self.cddl, json, enabled_features);
+    #[cfg(not(feature = "additional-controls"))]
+    let mut jv = JSONValidator::new(&self.cddl, json);
+
+    jv.set_compiled(self);
+    jv.set_regex_engine(self.regex_engine);
+    jv.validate()
+  }
+
+  /// Find non-choice alternate rule from a given identifier using the rule
+  /// index of the compiled document
+  pub fn rule_from_ident(&self, ident: &Identifier) -> Option<&Rule<'a>> {
+    self
+      .rules
+      .get(ident.ident.as_ref())?
+      .iter()
+      .map(|idx| &self.cddl.rules[*idx])
+      .find(|rule| match rule {
+        Rule::Type { rule, .. } => rule.name.socket == ident.socket,
+        Rule::Group { rule, .. } => rule.name.socket == ident.socket,
+      })
+  }
+
+  // Regular expression of a `.regexp` or `.pcre` text controller, if compiled
+  // with the given engine
+  #[cfg(feature = "std")]
+  pub(crate) fn regex(&self, regex_engine: RegexEngine, controller: &str) -> Option<&Regex> {
+    if regex_engine != self.regex_engine {
+      return None;
+    }
+
+    self.regexes.get(controller)
+  }
+}
+
+// Instantiate generic rules ahead of time, enforcing any `;@bound` annotations
+// of the document string. Recursive generic rules are left to be resolved
+// during validation
+#[cfg(feature = "std")]
+fn resolve_generics<'a>(input: &'a str, cddl: CDDL<'a>) -> std::result::Result<CDDL<'a>, Error> {
+  let bounds = generic_bounds_from_str(input).map_err(Error::CDDLParsing)?;
+
+  match cddl.resolve_generics_with_bounds(&bounds) {
+    Ok(resolved) => Ok(resolved),
+    Err(generics::Error::Recursive(_)) | Err(generics::Error::Unsupported(_)) => Ok(cddl),
+    Err(e) => Err(Error::Generics(e)),
+  }
+}
+
+// Checks and compiles the controllers of control operators
+struct Controls<'a, 'b> {
+  cddl: &'b CDDL<'a>,
+  regex_engine: RegexEngine,
+  #[cfg(feature = "std")]
+  regexes: BTreeMap<String, Regex>,
+  // Regular expressions of a previous compilation of the document
+  #[cfg(feature = "std")]
+  previous: Option<&'b BTreeMap<String, Regex>>,
+}
+
+impl<'a, 'b> Visitor<'a, Error> for Controls<'a, 'b> {
+  #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+  fn visit_control_operator(
+    &mut self,
+    target: &Type2<'a>,
+    ctrl: &str,
+    controller: &Type2<'a>,
+  ) -> visitor::Result<Error> {
+    #[cfg(feature = "std")]
+    if ctrl == ".regexp" || ctrl == ".pcre" {
+      if let Some(Type2::TextValue { value, .. }) = text_value_from_type2(self.cddl, controller) {
+        if !self.regexes.contains_key(value.as_ref()) {
+          let re = match self
+            .previous
+            .and_then(|regexes| regexes.get(value.as_ref()))
+          {
+            Some(re) => re.clone(),
+            None => {
+              self
+                .regex_engine
+                .compile(value)
+                .map_err(|reason| Error::InvalidController {
+                  ctrl: ctrl.to_string(),
+                  controller: controller.to_string(),
+                  reason,
+                })?
+            }
+          };
+
+          self.regexes.insert(value.to_string(), re);
+        }
+      }
+    }
+
+    visitor::walk_control_operator(self, target, controller)
+  }
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "std")]
+mod tests {
+  use super::*;
+
+  fn assert_send_sync<T: Send + Sync>() {}
+
+  #[test]
+  fn validate_compiled_cddl() {
+    assert_send_sync::<CompiledCddl>();
+
+    let compiled = CompiledCddl::compile(
+      r#"
+        message = envelope<tstr>
+        envelope<t> = { id: uint, body: t, ? tag: tag }
+        tag = tstr .regexp "[a-z]+"
+      "#,
+    )
+    .unwrap();
+    assert_eq!(compiled.cddl().rules.len(), 2);
+    assert!(compiled.regex(RegexEngine::Linear, "[a-z]+").is_some());
+
+    let valid = [
+      r#"{ "id": 1, "body": "hello" }"#,
+      r#"{ "id": 2, "body": "world", "tag": "greeting" }"#,
+    ];
+    let invalid = [
+      r#"{ "id": 1, "body": 2 }"#,
+      r#"{ "id": 2, "body": "world", "tag": "G" }"#,
+    ];
+
+    std::thread::scope(|s| {
+      for json in valid.iter() {
+        let compiled = &compiled;
+        s.spawn(move || {
+          #[cfg(feature = "additional-controls")]
+          assert!(compiled.validate_json(json, None).is_ok());
+          #[cfg(not(feature = "additional-controls"))]
+          assert!(compiled.validate_json(json).is_ok());
+        });
+      }
+    });
+
+    for json in invalid.iter() {
+      #[cfg(feature = "additional-controls")]
+      assert!(compiled.validate_json(json, None).is_err());
+      #[cfg(not(feature = "additional-controls"))]
+      assert!(compiled.validate_json(json).is_err());
+    }
+
+    assert_eq!(
+      CompiledCddl::compile(r#"a = tstr .regexp "[a-z""#)
+        .unwrap_err()
+        .to_string()
+        .lines()
+        .next(),
+      Some(r#"invalid controller "[a-z" of control operator .regexp: regex parse error:"#)
+    );
+  }
+
+  #[test]
+  #[cfg(feature = "additional-controls")]
+  fn validate_regex_engines() {
+    let validate = |compiled: &CompiledCddl, text: &str| {
+      let json = serde_json::to_string(text).unwrap();
+      let mut cbor = Vec::new();
+      ciborium::ser::into_writer(&ciborium::value::Value::Text(text.to_string()), &mut cbor)
+        .unwrap();
+
+      let json_valid = compiled.validate_json(&json, None).is_ok();
+      let cbor_valid = compiled.validate_cbor(&cbor, None).is_ok();
+      assert_eq!(json_valid, cbor_valid, "{}", text);
+
+      json_valid
+    };
+
+    let compiled = CompiledCddl::compile(r#"a = tstr .regexp "[a-z]+\\.[0-9]{2}""#).unwrap();
+    assert!(validate(&compiled, "abc.12"));
+    assert!(!validate(&compiled, "abc-12"));
+    assert!(!validate(&compiled, "ABC.12"));
+
+    // Lookaround assertions aren't supported by the linear-time engine
+    let lookahead = r#"a = tstr .pcre "^(?!admin)[a-z]+$""#;
+    assert!(matches!(
+      CompiledCddl::compile(lookahead),
+      Err(Error::InvalidController { .. })
+    ));
+
+    #[cfg(feature = "pcre")]
+    {
+      let compiled = CompiledCddl::compile_with_regex_engine(lookahead, RegexEngine::Pcre).unwrap();
+      assert!(validate(&compiled, "user"));
+      assert!(!validate(&compiled, "administrator"));
+
+      let mut jv = JSONValidator::new(&compiled.cddl, serde_json::json!("admin"), None);
+      jv.set_regex_engine(RegexEngine::Pcre);
+      assert!(jv.validate().is_err());
+      let mut cv = CBORValidator::new(
+        &compiled.cddl,
+        ciborium::value::Value::Text("user".to_string()),
+        None,
+      );
+      cv.set_regex_engine(RegexEngine::Pcre);
+      assert!(cv.validate().is_ok());
+    }
+  }
+}
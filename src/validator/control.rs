@@ -2,13 +2,24 @@
 #![cfg(not(feature = "lsp"))]
 
 use crate::ast::{Identifier, Operator, RangeCtlOp, Rule, Type2, CDDL};
+use std::convert::TryFrom;
 
 #[cfg(feature = "additional-controls")]
-use crate::{ast::Type, token::lookup_control_from_str, validator::ByteValue, Token};
+#[cfg(feature = "std")]
+use crate::validator::abnf::Abnf;
 #[cfg(feature = "additional-controls")]
-use itertools::Itertools;
+#[cfg(feature = "std")]
+use crate::{ast::Type, token::lookup_control_from_str};
+#[cfg(feature = "additional-controls")]
+use crate::{validator::ByteValue, Token};
 #[cfg(feature = "additional-controls")]
-use pest_meta;
+use itertools::Itertools;
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+  string::{String, ToString},
+  vec::Vec,
+};
 
 /// Retrieve all text strings and byte string literals from a given rule
 /// identifier. Used for proposed .cat control operator.
@@ -598,25 +609,104 @@ fn dedent_bytes(source: &[u8], is_utf8_byte_string: bool) -> Result<Vec<u8>, Str
   )
 }
 
+/// Behavior of the integer arithmetic of control operators such as `.plus` when
+/// the result doesn't fit the integer type of the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowBehavior {
+  /// Report the overflow as an error
+  #[default]
+  Checked,
+  /// Clamp the result to the bounds of the integer type
+  Saturating,
+  /// Wrap the result around the bounds of the integer type
+  Wrapping,
+}
+
+/// Behavior of the `.feature` control operator when its target matches but the
+/// feature isn't among the features enabled for validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisabledFeatureBehavior {
+  /// Report the match as an error, so only the branches of the document
+  /// guarded by enabled features can match
+  #[default]
+  Reject,
+  /// Accept the match, reporting the use of the disabled feature
+  Warn,
+}
+
+impl OverflowBehavior {
+  // Unsigned integer result of a sum computed without loss of precision
+  fn uint(self, target: &Type2, controller: &Type2, sum: i128) -> Result<usize, String> {
+    if let Ok(sum) = usize::try_from(sum) {
+      return Ok(sum);
+    }
+
+    match self {
+      OverflowBehavior::Checked => Err(format!(
+        "overflow in .plus operation, {} + {} is out of the range of uint",
+        target, controller
+      )),
+      OverflowBehavior::Saturating if sum < 0 => Ok(usize::MIN),
+      OverflowBehavior::Saturating => Ok(usize::MAX),
+      OverflowBehavior::Wrapping => Ok(sum as usize),
+    }
+  }
+
+  // Signed integer result of a sum computed without loss of precision
+  fn int(self, target: &Type2, controller: &Type2, sum: i128) -> Result<isize, String> {
+    if let Ok(sum) = isize::try_from(sum) {
+      return Ok(sum);
+    }
+
+    match self {
+      OverflowBehavior::Checked => Err(format!(
+        "overflow in .plus operation, {} + {} is out of the range of int",
+        target, controller
+      )),
+      OverflowBehavior::Saturating if sum < 0 => Ok(isize::MIN),
+      OverflowBehavior::Saturating => Ok(isize::MAX),
+      OverflowBehavior::Wrapping => Ok(sum as isize),
+    }
+  }
+}
+
 /// Numeric addition of target and controller. The Vec return type is to
-/// accomodate more than one type choice in the controller
+/// accomodate more than one type choice in the controller. Integer overflow is
+/// reported as an error
 pub fn plus_operation<'a>(
   cddl: &CDDL,
   target: &Type2,
   controller: &Type2,
+) -> Result<Vec<Type2<'a>>, String> {
+  plus_operation_with_overflow(cddl, target, controller, OverflowBehavior::default())
+}
+
+/// Numeric addition of target and controller with the given behavior on
+/// integer overflow. Float controllers of integer targets are truncated
+pub fn plus_operation_with_overflow<'a>(
+  cddl: &CDDL,
+  target: &Type2,
+  controller: &Type2,
+  overflow: OverflowBehavior,
 ) -> Result<Vec<Type2<'a>>, String> {
   let mut values = Vec::new();
   match target {
     Type2::UintValue { value, .. } => match controller {
-      Type2::UintValue {
-        value: controller, ..
-      } => values.push((value + controller).into()),
-      Type2::IntValue {
-        value: controller, ..
-      } => values.push(((*value as isize + controller) as usize).into()),
-      Type2::FloatValue {
-        value: controller, ..
-      } => values.push(((*value as isize + *controller as isize) as usize).into()),
+      Type2::UintValue { value: c, .. } => values.push(
+        overflow
+          .uint(target, controller, *value as i128 + *c as i128)?
+          .into(),
+      ),
+      Type2::IntValue { value: c, .. } => values.push(
+        overflow
+          .uint(target, controller, *value as i128 + *c as i128)?
+          .into(),
+      ),
+      Type2::FloatValue { value: c, .. } => values.push(
+        overflow
+          .uint(target, controller, *value as i128 + *c as i128)?
+          .into(),
+      ),
       Type2::Typename { ident, .. } => {
         let nv = numeric_values_from_ident(cddl, ident);
         if nv.is_empty() {
@@ -626,23 +716,38 @@ pub fn plus_operation<'a>(
           ));
         }
         for controller in nv.iter() {
-          println!("controller: {}", controller);
-          values.append(&mut plus_operation(cddl, target, controller)?)
+          values.append(&mut plus_operation_with_overflow(
+            cddl, target, controller, overflow,
+          )?)
         }
       }
       Type2::ParenthesizedType { pt: controller, .. } => {
         for controller in controller.type_choices.iter() {
           match &controller.type1.operator {
             Some(Operator {
-              operator: RangeCtlOp::CtlOp { ctrl: ".plus", .. },
+              operator: RangeCtlOp::CtlOp { ctrl, .. },
               type2: nested_controller,
               ..
-            }) => {
-              for v in plus_operation(cddl, &controller.type1.type2, nested_controller)?.iter() {
-                values.append(&mut plus_operation(cddl, target, v)?);
+            }) if ctrl == ".plus" => {
+              for v in plus_operation_with_overflow(
+                cddl,
+                &controller.type1.type2,
+                nested_controller,
+                overflow,
+              )?
+              .iter()
+              {
+                values.append(&mut plus_operation_with_overflow(
+                  cddl, target, v, overflow,
+                )?);
               }
             }
-            None => values.append(&mut plus_operation(cddl, target, &controller.type1.type2)?),
+            None => values.append(&mut plus_operation_with_overflow(
+              cddl,
+              target,
+              &controller.type1.type2,
+              overflow,
+            )?),
             _ => return Err("nested operator must be .plus".to_string()),
           }
         }
@@ -650,15 +755,21 @@ pub fn plus_operation<'a>(
       _ => return Err("invalid controller used for .plus operation".to_string()),
     },
     Type2::IntValue { value, .. } => match controller {
-      Type2::IntValue {
-        value: controller, ..
-      } => values.push((value + controller).into()),
-      Type2::UintValue {
-        value: controller, ..
-      } => values.push((value + *controller as isize).into()),
-      Type2::FloatValue {
-        value: controller, ..
-      } => values.push((value + *controller as isize).into()),
+      Type2::IntValue { value: c, .. } => values.push(
+        overflow
+          .int(target, controller, *value as i128 + *c as i128)?
+          .into(),
+      ),
+      Type2::UintValue { value: c, .. } => values.push(
+        overflow
+          .int(target, controller, *value as i128 + *c as i128)?
+          .into(),
+      ),
+      Type2::FloatValue { value: c, .. } => values.push(
+        overflow
+          .int(target, controller, *value as i128 + *c as i128)?
+          .into(),
+      ),
       Type2::Typename { ident, .. } => {
         let nv = numeric_values_from_ident(cddl, ident);
         if nv.is_empty() {
@@ -668,23 +779,38 @@ pub fn plus_operation<'a>(
           ));
         }
         for controller in nv.iter() {
-          println!("controller: {}", controller);
-          values.append(&mut plus_operation(cddl, target, controller)?)
+          values.append(&mut plus_operation_with_overflow(
+            cddl, target, controller, overflow,
+          )?)
         }
       }
       Type2::ParenthesizedType { pt: controller, .. } => {
         for controller in controller.type_choices.iter() {
           match &controller.type1.operator {
             Some(Operator {
-              operator: RangeCtlOp::CtlOp { ctrl: ".plus", .. },
+              operator: RangeCtlOp::CtlOp { ctrl, .. },
               type2: nested_controller,
               ..
-            }) => {
-              for v in plus_operation(cddl, &controller.type1.type2, nested_controller)?.iter() {
-                values.append(&mut plus_operation(cddl, target, v)?);
+            }) if ctrl == ".plus" => {
+              for v in plus_operation_with_overflow(
+                cddl,
+                &controller.type1.type2,
+                nested_controller,
+                overflow,
+              )?
+              .iter()
+              {
+                values.append(&mut plus_operation_with_overflow(
+                  cddl, target, v, overflow,
+                )?);
               }
             }
-            None => values.append(&mut plus_operation(cddl, target, &controller.type1.type2)?),
+            None => values.append(&mut plus_operation_with_overflow(
+              cddl,
+              target,
+              &controller.type1.type2,
+              overflow,
+            )?),
             _ => return Err("nested operator must be .plus".to_string()),
           }
         }
@@ -707,23 +833,38 @@ pub fn plus_operation<'a>(
           ));
         }
         for controller in nv.iter() {
-          println!("controller: {}", controller);
-          values.append(&mut plus_operation(cddl, target, controller)?)
+          values.append(&mut plus_operation_with_overflow(
+            cddl, target, controller, overflow,
+          )?)
         }
       }
       Type2::ParenthesizedType { pt: controller, .. } => {
         for controller in controller.type_choices.iter() {
           match &controller.type1.operator {
             Some(Operator {
-              operator: RangeCtlOp::CtlOp { ctrl: ".plus", .. },
+              operator: RangeCtlOp::CtlOp { ctrl, .. },
               type2: nested_controller,
               ..
-            }) => {
-              for v in plus_operation(cddl, &controller.type1.type2, nested_controller)?.iter() {
-                values.append(&mut plus_operation(cddl, target, v)?);
+            }) if ctrl == ".plus" => {
+              for v in plus_operation_with_overflow(
+                cddl,
+                &controller.type1.type2,
+                nested_controller,
+                overflow,
+              )?
+              .iter()
+              {
+                values.append(&mut plus_operation_with_overflow(
+                  cddl, target, v, overflow,
+                )?);
               }
             }
-            None => values.append(&mut plus_operation(cddl, target, &controller.type1.type2)?),
+            None => values.append(&mut plus_operation_with_overflow(
+              cddl,
+              target,
+              &controller.type1.type2,
+              overflow,
+            )?),
             _ => return Err("nested operator must be .plus".to_string()),
           }
         }
@@ -734,7 +875,9 @@ pub fn plus_operation<'a>(
       // Only grab the first type choice value from the target per
       // https://github.com/cbor-wg/cddl-control/issues/2#issuecomment-729253368
       if let Some(value) = numeric_values_from_ident(cddl, ident).first() {
-        values.append(&mut plus_operation(cddl, value, controller)?);
+        values.append(&mut plus_operation_with_overflow(
+          cddl, value, controller, overflow,
+        )?);
       } else {
         return Err("invalid controller used for .plus operation".to_string());
       }
@@ -745,15 +888,25 @@ pub fn plus_operation<'a>(
       if let Some(tc) = target.type_choices.first() {
         match &tc.type1.operator {
           Some(Operator {
-            operator: RangeCtlOp::CtlOp { ctrl: ".plus", .. },
+            operator: RangeCtlOp::CtlOp { ctrl, .. },
             type2: nested_controller,
             ..
-          }) => {
-            for v in plus_operation(cddl, &tc.type1.type2, nested_controller)?.iter() {
-              values.append(&mut plus_operation(cddl, v, controller)?);
+          }) if ctrl == ".plus" => {
+            for v in
+              plus_operation_with_overflow(cddl, &tc.type1.type2, nested_controller, overflow)?
+                .iter()
+            {
+              values.append(&mut plus_operation_with_overflow(
+                cddl, v, controller, overflow,
+              )?);
             }
           }
-          None => values.append(&mut plus_operation(cddl, &tc.type1.type2, controller)?),
+          None => values.append(&mut plus_operation_with_overflow(
+            cddl,
+            &tc.type1.type2,
+            controller,
+            overflow,
+          )?),
           _ => return Err("nested operator must be .plus".to_string()),
         }
       } else {
@@ -771,43 +924,49 @@ pub fn plus_operation<'a>(
   Ok(values)
 }
 
+/// Split the controller of an .abnf/.abnfb control operator into the name of
+/// the rule to match, given on its first line, and the ABNF grammar that
+/// follows
 #[cfg(feature = "additional-controls")]
-pub fn validate_abnf(abnf: &str, target: &str) -> Result<(), String> {
-  if let Some(idx) = abnf.find('\n') {
-    let (rule, abnf) = abnf.split_at(idx);
-
-    let rules = abnf_to_pest::parse_abnf(abnf).map_err(|e| e.to_string())?;
-    let mut w = Vec::new();
-    abnf_to_pest::render_rules_to_pest(rules)
-      .render(0, &mut w)
-      .unwrap();
-    let pest = String::from_utf8(w).unwrap();
+#[cfg(feature = "std")]
+fn abnf_rule(abnf: &str) -> Result<(&str, Abnf), String> {
+  let (rule, abnf) = abnf
+    .split_once('\n')
+    .ok_or_else(|| "abnf controller must name a rule on its first line".to_string())?;
 
-    let pairs = pest_meta::parser::parse(pest_meta::parser::Rule::grammar_rules, &pest)
-      .map_err(|e| e.to_string())?;
-
-    let ast = pest_meta::parser::consume_rules(pairs).unwrap();
-
-    let vm = pest_vm::Vm::new(pest_meta::optimizer::optimize(ast));
+  Ok((rule.trim(), Abnf::parse(abnf).map_err(|e| e.to_string())?))
+}
 
-    let rule = rule.replace('-', "_");
-    let _ = vm.parse(&rule, target).map_err(|e| e.to_string())?;
-  }
+/// Match a text string against the rule named on the first line of an .abnf
+/// controller
+#[cfg(feature = "additional-controls")]
+#[cfg(feature = "std")]
+pub fn validate_abnf(abnf: &str, target: &str) -> Result<(), String> {
+  let (rule, abnf) = abnf_rule(abnf)?;
+  abnf.match_str(rule, target).map_err(|e| e.to_string())
+}
 
-  Ok(())
+/// Match a byte string against the rule named on the first line of an .abnfb
+/// controller
+#[cfg(feature = "additional-controls")]
+#[cfg(feature = "std")]
+pub fn validate_abnfb(abnf: &str, target: &[u8]) -> Result<(), String> {
+  let (rule, abnf) = abnf_rule(abnf)?;
+  abnf.match_bytes(rule, target).map_err(|e| e.to_string())
 }
 
 /// If the controller for an .abnf/.abnfb control operator is a parenthesized
 /// type with a nested .cat/.det, it needs to be parsed beforehand. The Vec
 /// return type is to accomodate more than one type choice in the controller.
 #[cfg(feature = "additional-controls")]
+#[cfg(feature = "std")]
 pub fn abnf_from_complex_controller<'a>(
   cddl: &'a CDDL,
   controller: &Type,
 ) -> Result<Vec<Type2<'a>>, String> {
   if let Some(tc) = controller.type_choices.first() {
     if let Some(operator) = &tc.type1.operator {
-      if let RangeCtlOp::CtlOp { ctrl, .. } = operator.operator {
+      if let RangeCtlOp::CtlOp { ctrl, .. } = &operator.operator {
         match lookup_control_from_str(ctrl) {
           Some(Token::CAT) => return cat_operation(cddl, &tc.type1.type2, &operator.type2, false),
           Some(Token::DET) => return cat_operation(cddl, &tc.type1.type2, &operator.type2, true),
@@ -820,6 +979,73 @@ pub fn abnf_from_complex_controller<'a>(
   Err("invalid controller".to_string())
 }
 
+/// Regular expression engine used to match the `.regexp` and `.pcre` control
+/// operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RegexEngine {
+  /// Linear-time engine of the `regex` crate, supporting the I-Regexp subset
+  /// defined in RFC 9485 along with the syntax of the `regex` crate. Lookaround
+  /// assertions and backreferences are rejected
+  #[default]
+  Linear,
+  /// Backtracking engine of the `fancy-regex` crate, additionally supporting
+  /// PCRE features such as lookaround assertions and backreferences. Matching
+  /// can take exponential time on malicious patterns
+  #[cfg(feature = "pcre")]
+  Pcre,
+}
+
+/// Regular expression compiled with a [`RegexEngine`]
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub enum Regex {
+  /// Regular expression compiled with [`RegexEngine::Linear`]
+  Linear(regex::Regex),
+  /// Regular expression compiled with [`RegexEngine::Pcre`]
+  #[cfg(feature = "pcre")]
+  Pcre(fancy_regex::Regex),
+}
+
+#[cfg(feature = "std")]
+impl RegexEngine {
+  /// Compile the text controller of a `.regexp` or `.pcre` control operator.
+  /// Text strings are JSON escaped per
+  /// https://datatracker.ietf.org/doc/html/rfc8610#section-3.1
+  pub fn compile(self, controller: &str) -> Result<Regex, String> {
+    let pattern = serde_json::from_str::<serde_json::Value>(&format!("\"{}\"", controller))
+      .map_err(|e| e.to_string())?;
+    let pattern = pattern
+      .as_str()
+      .ok_or_else(|| "malformed regex".to_string())?;
+
+    match self {
+      RegexEngine::Linear => {
+        let pattern = super::format_regex(pattern).ok_or_else(|| "malformed regex".to_string())?;
+        regex::Regex::new(&pattern)
+          .map(Regex::Linear)
+          .map_err(|e| e.to_string())
+      }
+      #[cfg(feature = "pcre")]
+      RegexEngine::Pcre => fancy_regex::Regex::new(pattern)
+        .map(Regex::Pcre)
+        .map_err(|e| e.to_string()),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl Regex {
+  /// Whether the regular expression matches the text. Fails if the
+  /// backtracking engine exceeds its backtrack limit
+  pub fn is_match(&self, text: &str) -> Result<bool, String> {
+    match self {
+      Regex::Linear(re) => Ok(re.is_match(text)),
+      #[cfg(feature = "pcre")]
+      Regex::Pcre(re) => re.is_match(text).map_err(|e| e.to_string()),
+    }
+  }
+}
+
 #[cfg(test)]
 #[cfg(not(target_arch = "wasm32"))]
 mod tests {
@@ -905,6 +1131,70 @@ mod tests {
     Ok(())
   }
 
+  #[test]
+  fn test_plus_overflow() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl_str = indoc!(
+      r#"
+        max = 18446744073709551615
+        min = -9223372036854775808
+      "#
+    );
+
+    let cddl = cddl_from_str(cddl_str, true)?;
+
+    let max = Type2::Typename {
+      ident: "max".into(),
+      generic_args: None,
+      #[cfg(feature = "ast-span")]
+      span: Span::default(),
+    };
+    let min = Type2::Typename {
+      ident: "min".into(),
+      generic_args: None,
+      #[cfg(feature = "ast-span")]
+      span: Span::default(),
+    };
+
+    assert_eq!(
+      plus_operation(&cddl, &max, &Type2::from(1usize)).unwrap_err(),
+      "overflow in .plus operation, 18446744073709551615 + 1 is out of the range of uint"
+    );
+    assert_eq!(
+      plus_operation_with_overflow(
+        &cddl,
+        &max,
+        &Type2::from(1usize),
+        OverflowBehavior::Saturating
+      )?,
+      vec![Type2::from(usize::MAX)]
+    );
+    assert_eq!(
+      plus_operation_with_overflow(
+        &cddl,
+        &max,
+        &Type2::from(2usize),
+        OverflowBehavior::Wrapping
+      )?,
+      vec![Type2::from(1usize)]
+    );
+    assert!(plus_operation(&cddl, &min, &Type2::from(-1isize)).is_err());
+    assert_eq!(
+      plus_operation_with_overflow(
+        &cddl,
+        &min,
+        &Type2::from(-1isize),
+        OverflowBehavior::Saturating
+      )?,
+      vec![Type2::from(isize::MIN)]
+    );
+    assert_eq!(
+      plus_operation(&cddl, &Type2::from(1usize), &Type2::from(-1isize))?,
+      vec![Type2::from(0usize)]
+    );
+
+    Ok(())
+  }
+
   #[cfg(feature = "additional-controls")]
   #[test]
   fn test_abnf() -> std::result::Result<(), Box<dyn std::error::Error>> {
@@ -936,6 +1226,17 @@ mod tests {
     );
 
     validate_abnf(abnf_str, "2009")?;
+    assert_eq!(
+      validate_abnf(abnf_str, "20091").unwrap_err(),
+      "rule date-fullyear failed to match at offset 4, found '1'"
+    );
+
+    let abnf_str = abnf_str.replacen("date-fullyear\n", "date-time\n", 1);
+    validate_abnf(&abnf_str, "1985-04-12T23:20:50.52Z")?;
+    assert_eq!(
+      validate_abnf(&abnf_str, "1985-04-12T23:2x:50Z").unwrap_err(),
+      "rule date-time > full-time > partial-time > time-minute > DIGIT failed to match at offset 15, found 'x'"
+    );
 
     Ok(())
   }
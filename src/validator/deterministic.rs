@@ -0,0 +1,579 @@
+use std::{convert::TryFrom, fmt};
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+  format,
+  string::{String, ToString},
+  vec::Vec,
+};
+
+/// Violation of the core deterministic encoding requirements of
+/// [RFC 8949](https://www.rfc-editor.org/rfc/rfc8949#section-4.2.1)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncodingViolation {
+  /// Offset of the initial byte of the offending data item
+  pub offset: usize,
+  /// Location in CBOR of the offending data item
+  pub cbor_location: String,
+  /// Requirement the encoding violates
+  pub reason: String,
+}
+
+impl fmt::Display for EncodingViolation {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} at byte offset {}", self.reason, self.offset)
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncodingViolation {}
+
+/// Check that an encoded CBOR data item uses the core deterministic encoding
+/// of RFC 8949, returning every violation found:
+///
+/// - Integers, lengths and tags are encoded in the shortest form
+/// - Floating-point values are encoded in the shortest form that preserves
+///   their value, with NaN encoded as the half-precision quiet NaN `0xf97e00`
+/// - Bignums are only used for values that don't fit in an integer, and are
+///   encoded without leading zero bytes
+/// - Indefinite-length items are not used
+/// - Map keys are sorted in the bytewise lexicographic order of their encoding,
+///   without duplicates
+///
+/// Checking stops at the first malformed item or at an item nested more than
+/// [`MAX_DEPTH`] levels deep, and bytes following the data item are reported
+///
+/// # Example
+///
+/// ```
+/// use cddl::validator::deterministic::check_deterministic_encoding;
+///
+/// // { "b": 1, "a": 2 }
+/// let violations = check_deterministic_encoding(&[0xa2, 0x61, 0x62, 0x01, 0x61, 0x61, 0x02]);
+/// assert_eq!(violations.len(), 1);
+/// assert_eq!(violations[0].offset, 4);
+/// ```
+pub fn check_deterministic_encoding(cbor_slice: &[u8]) -> Vec<EncodingViolation> {
+  let mut checker = Checker {
+    input: cbor_slice,
+    offset: 0,
+    depth: 0,
+    cbor_location: String::new(),
+    violations: Vec::new(),
+  };
+
+  if checker.item().is_ok() && checker.offset < cbor_slice.len() {
+    checker.violation(
+      checker.offset,
+      format!(
+        "{} trailing bytes follow the data item",
+        cbor_slice.len() - checker.offset
+      ),
+    );
+  }
+
+  checker.violations
+}
+
+/// Maximum nesting depth of arrays, maps and tags checked by
+/// [`check_deterministic_encoding`]
+pub const MAX_DEPTH: usize = 256;
+
+// Raised when the input is malformed. The violation is recorded before the
+// check is aborted
+struct Malformed;
+
+struct Checker<'a> {
+  input: &'a [u8],
+  offset: usize,
+  depth: usize,
+  cbor_location: String,
+  violations: Vec<EncodingViolation>,
+}
+
+impl<'a> Checker<'a> {
+  fn violation(&mut self, offset: usize, reason: String) {
+    self.violations.push(EncodingViolation {
+      offset,
+      cbor_location: self.cbor_location.clone(),
+      reason,
+    });
+  }
+
+  fn bytes(&mut self, len: usize) -> Result<&'a [u8], Malformed> {
+    let input = self.input;
+    match self.offset.checked_add(len) {
+      Some(end) if end <= input.len() => {
+        self.offset = end;
+        Ok(&input[end - len..end])
+      }
+      _ => {
+        self.violation(self.offset, "unexpected end of input".to_string());
+        Err(Malformed)
+      }
+    }
+  }
+
+  // Argument of the item at the given offset. Returns `None` for the
+  // indefinite length
+  fn argument(&mut self, start: usize, info: u8) -> Result<Option<u64>, Malformed> {
+    let value = match info {
+      0..=23 => return Ok(Some(info as u64)),
+      24..=27 => self
+        .bytes(1 << (info - 24))?
+        .iter()
+        .fold(0, |value, b| (value << 8) | *b as u64),
+      31 => return Ok(None),
+      _ => {
+        self.violation(start, format!("reserved additional information {}", info));
+        return Err(Malformed);
+      }
+    };
+
+    let shortest = match value {
+      0..=23 => value as u8,
+      24..=0xff => 24,
+      0x100..=0xffff => 25,
+      0x1_0000..=0xffff_ffff => 26,
+      _ => 27,
+    };
+    if info != shortest {
+      self.violation(
+        start,
+        format!("argument {} is not encoded in the shortest form", value),
+      );
+    }
+
+    Ok(Some(value))
+  }
+
+  fn item(&mut self) -> Result<(), Malformed> {
+    if self.depth == MAX_DEPTH {
+      self.violation(
+        self.offset,
+        format!("data item is nested more than {} levels deep", MAX_DEPTH),
+      );
+      return Err(Malformed);
+    }
+
+    self.depth += 1;
+    let result = self.nested_item();
+    self.depth -= 1;
+
+    result
+  }
+
+  fn nested_item(&mut self) -> Result<(), Malformed> {
+    let start = self.offset;
+    let initial = self.bytes(1)?[0];
+    let (major, info) = (initial >> 5, initial & 0x1f);
+
+    if major == 7 {
+      return self.simple_or_float(start, info);
+    }
+
+    let argument = match self.argument(start, info)? {
+      Some(argument) => argument,
+      None if (2..=5).contains(&major) => {
+        self.violation(start, "indefinite-length item".to_string());
+        return self.indefinite(major);
+      }
+      None => {
+        self.violation(start, format!("reserved additional information {}", info));
+        return Err(Malformed);
+      }
+    };
+
+    match major {
+      2 | 3 => self
+        .bytes(usize::try_from(argument).unwrap_or(usize::MAX))
+        .map(|_| ()),
+      4 => {
+        for idx in 0..argument {
+          self.nested(&idx.to_string(), |c| c.item())?;
+        }
+
+        Ok(())
+      }
+      5 => self.map(Some(argument)),
+      6 if argument == 2 || argument == 3 => self.bignum(start),
+      6 => self.item(),
+      _ => Ok(()),
+    }
+  }
+
+  fn simple_or_float(&mut self, start: usize, info: u8) -> Result<(), Malformed> {
+    match info {
+      24 => {
+        let value = self.bytes(1)?[0];
+        if value < 32 {
+          self.violation(
+            start,
+            format!("simple value {} is not encoded in the shortest form", value),
+          );
+        }
+      }
+      25 => {
+        let bytes = self.bytes(2)?;
+        let bits = u16::from_be_bytes([bytes[0], bytes[1]]);
+        if bits & 0x7c00 == 0x7c00 && bits & 0x3ff != 0 && bits != 0x7e00 {
+          self.violation(
+            start,
+            format!(
+              "NaN 0x{:04x} is not encoded as the canonical NaN 0x7e00",
+              bits
+            ),
+          );
+        }
+      }
+      26 => {
+        let bytes = self.bytes(4)?;
+        let value = f32::from_bits(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        if value.is_nan() || fits_f16(value) {
+          self.violation(
+            start,
+            format!("float {} is not encoded in the shortest form", value),
+          );
+        }
+      }
+      27 => {
+        let bytes = self.bytes(8)?;
+        let mut be = [0; 8];
+        be.copy_from_slice(bytes);
+        let value = f64::from_be_bytes(be);
+        if value.is_nan() || (value as f32) as f64 == value {
+          self.violation(
+            start,
+            format!("float {} is not encoded in the shortest form", value),
+          );
+        }
+      }
+      28..=30 => {
+        self.violation(start, format!("reserved additional information {}", info));
+        return Err(Malformed);
+      }
+      31 => {
+        self.violation(start, "unexpected break".to_string());
+        return Err(Malformed);
+      }
+      _ => {}
+    }
+
+    Ok(())
+  }
+
+  // Content of a bignum tagged at the given offset, which should only be used
+  // for values that don't fit in an integer
+  fn bignum(&mut self, start: usize) -> Result<(), Malformed> {
+    let content = self.offset;
+    self.item()?;
+
+    let initial = self.input[content];
+    let (major, info) = (initial >> 5, initial & 0x1f);
+    let header = match info {
+      0..=23 => 1,
+      24..=27 => 1 + (1 << (info - 24)),
+      _ => return Ok(()),
+    };
+    if major != 2 {
+      return Ok(());
+    }
+
+    let bytes = &self.input[content + header..self.offset];
+    let significant = bytes.iter().skip_while(|b| **b == 0).count();
+    if significant <= 8 {
+      self.violation(
+        start,
+        "bignum is not encoded as an integer although it fits in one".to_string(),
+      );
+    } else if significant < bytes.len() {
+      self.violation(
+        start,
+        "bignum is encoded with leading zero bytes".to_string(),
+      );
+    }
+
+    Ok(())
+  }
+
+  // Items of an indefinite-length item, which has already been reported
+  fn indefinite(&mut self, major: u8) -> Result<(), Malformed> {
+    if major == 5 {
+      return self.map(None);
+    }
+
+    let mut idx = 0;
+    while !self.is_break()? {
+      if major == 4 {
+        self.nested(&idx.to_string(), |c| c.item())?;
+      } else {
+        self.item()?;
+      }
+      idx += 1;
+    }
+
+    Ok(())
+  }
+
+  fn is_break(&mut self) -> Result<bool, Malformed> {
+    match self.input.get(self.offset) {
+      Some(0xff) => {
+        self.offset += 1;
+        Ok(true)
+      }
+      Some(_) => Ok(false),
+      None => {
+        self.violation(self.offset, "unexpected end of input".to_string());
+        Err(Malformed)
+      }
+    }
+  }
+
+  fn map(&mut self, len: Option<u64>) -> Result<(), Malformed> {
+    let mut previous_key: Option<&'a [u8]> = None;
+    let mut idx = 0;
+    loop {
+      match len {
+        Some(len) if idx == len => break,
+        None if self.is_break()? => break,
+        _ => {}
+      }
+
+      let start = self.offset;
+      self.item()?;
+      let key = &self.input[start..self.offset];
+      let location = key_location(key).unwrap_or_else(|| idx.to_string());
+
+      match previous_key {
+        Some(previous) if previous == key => {
+          self.nested(&location, |c| {
+            c.violation(start, "duplicate map key".to_string())
+          });
+        }
+        Some(previous) if previous > key => {
+          self.nested(&location, |c| {
+            c.violation(
+              start,
+              "map key is not sorted in the bytewise lexicographic order of its encoding"
+                .to_string(),
+            )
+          });
+        }
+        _ => {}
+      }
+      previous_key = Some(key);
+
+      self.nested(&location, |c| c.item())?;
+      idx += 1;
+    }
+
+    Ok(())
+  }
+
+  fn nested<T>(&mut self, segment: &str, f: impl FnOnce(&mut Self) -> T) -> T {
+    let len = self.cbor_location.len();
+    self.cbor_location.push('/');
+    self.cbor_location.push_str(segment);
+    let result = f(self);
+    self.cbor_location.truncate(len);
+
+    result
+  }
+}
+
+// Location segment of a text string or small integer map key, formatted as by
+// the CBOR validator
+fn key_location(key: &[u8]) -> Option<String> {
+  let (major, info) = (key.first()? >> 5, key.first()? & 0x1f);
+  match major {
+    0 if info < 24 => Some(info.to_string()),
+    3 if info < 24 && key.len() == 1 + info as usize => std::str::from_utf8(&key[1..])
+      .ok()
+      .map(|s| format!("{:?}", s)),
+    _ => None,
+  }
+}
+
+// Whether a single-precision value can be represented exactly in half
+// precision
+fn fits_f16(value: f32) -> bool {
+  if value.is_infinite() || value == 0.0 {
+    return true;
+  }
+
+  let bits = value.to_bits();
+  let exponent = ((bits >> 23) & 0xff) as i32 - 127;
+  let significand = (bits & 0x7f_ffff) | 0x80_0000;
+  match exponent {
+    // Normal half-precision values have 10 bits of mantissa
+    -14..=15 => significand & 0x1fff == 0,
+    // Subnormal half-precision values are multiples of 2^-24
+    -24..=-15 => significand & ((1 << (13 + (-14 - exponent))) - 1) == 0,
+    _ => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn reasons(cbor_slice: &[u8]) -> Vec<(usize, String, String)> {
+    check_deterministic_encoding(cbor_slice)
+      .into_iter()
+      .map(|v| (v.offset, v.cbor_location, v.reason))
+      .collect()
+  }
+
+  #[test]
+  fn verify_deterministic_encoding() {
+    // [1, -500, h'01', "a", 1.5, { 1: true, "a": null }, 0("x")]
+    let deterministic = [
+      0x87, 0x01, 0x39, 0x01, 0xf3, 0x41, 0x01, 0x61, 0x61, 0xf9, 0x3e, 0x00, 0xa2, 0x01, 0xf5,
+      0x61, 0x61, 0xf6, 0xc0, 0x61, 0x78,
+    ];
+    assert!(check_deterministic_encoding(&deterministic).is_empty());
+
+    // Integers, lengths and tags
+    assert_eq!(
+      reasons(&[0x82, 0x18, 0x01, 0x59, 0x00, 0x01, 0x00]),
+      vec![
+        (
+          1,
+          "/0".to_string(),
+          "argument 1 is not encoded in the shortest form".to_string()
+        ),
+        (
+          3,
+          "/1".to_string(),
+          "argument 1 is not encoded in the shortest form".to_string()
+        ),
+      ]
+    );
+    assert_eq!(reasons(&[0xd8, 0x00, 0x01]).len(), 1);
+
+    // Floats
+    assert!(reasons(&[0xfa, 0x3f, 0xc0, 0x00, 0x00])[0]
+      .2
+      .contains("float 1.5 is not encoded in the shortest form"));
+    assert_eq!(
+      reasons(&[0xfb, 0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]).len(),
+      1
+    );
+    assert!(reasons(&[0xfa, 0x3f, 0x8c, 0xcc, 0xcd]).is_empty());
+    assert!(reasons(&[0xfb, 0x3f, 0xf1, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a]).is_empty());
+    assert_eq!(reasons(&[0xfa, 0x7f, 0xc0, 0x00, 0x00]).len(), 1);
+    assert!(fits_f16(65504.0) && fits_f16(5.960_464_5e-8) && !fits_f16(65505.0));
+
+    // Indefinite-length items
+    assert_eq!(
+      reasons(&[0x9f, 0x01, 0x7f, 0x61, 0x61, 0xff, 0xff]),
+      vec![
+        (0, "".to_string(), "indefinite-length item".to_string()),
+        (2, "/1".to_string(), "indefinite-length item".to_string()),
+      ]
+    );
+
+    // Map keys
+    assert_eq!(
+      reasons(&[0xa3, 0x61, 0x62, 0x01, 0x61, 0x61, 0x02, 0x61, 0x61, 0x03]),
+      vec![
+        (
+          4,
+          "/\"a\"".to_string(),
+          "map key is not sorted in the bytewise lexicographic order of its encoding".to_string()
+        ),
+        (7, "/\"a\"".to_string(), "duplicate map key".to_string()),
+      ]
+    );
+    // Keys are compared bytewise rather than by the length of their encoding
+    assert!(reasons(&[0xa2, 0x18, 0x64, 0xf6, 0x20, 0xf6]).is_empty());
+    assert_eq!(reasons(&[0xa2, 0x20, 0xf6, 0x18, 0x64, 0xf6]).len(), 1);
+
+    // Malformed input
+    assert_eq!(
+      reasons(&[0x82, 0x01]),
+      vec![(2, "/1".to_string(), "unexpected end of input".to_string())]
+    );
+    assert_eq!(
+      reasons(&[0x01, 0x02]),
+      vec![(
+        1,
+        "".to_string(),
+        "1 trailing bytes follow the data item".to_string()
+      )]
+    );
+  }
+
+  #[test]
+  fn verify_bignums() {
+    // 2(h'0100') and 3(h'ffffffffffffffff') fit in integers
+    assert_eq!(
+      reasons(&[0x81, 0xc2, 0x42, 0x01, 0x00]),
+      vec![(
+        1,
+        "/0".to_string(),
+        "bignum is not encoded as an integer although it fits in one".to_string()
+      )]
+    );
+    assert_eq!(
+      reasons(&[0xc3, 0x48, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).len(),
+      1
+    );
+
+    // 2(h'010000000000000000') only fits in a bignum, without leading zeros
+    let mut bignum = vec![0xc2, 0x49, 0x01];
+    bignum.extend_from_slice(&[0x00; 8]);
+    assert!(reasons(&bignum).is_empty());
+    bignum[1] = 0x4a;
+    bignum.insert(2, 0x00);
+    assert_eq!(
+      reasons(&bignum),
+      vec![(
+        0,
+        "".to_string(),
+        "bignum is encoded with leading zero bytes".to_string()
+      )]
+    );
+  }
+
+  #[test]
+  fn verify_nan_payloads() {
+    assert!(reasons(&[0xf9, 0x7e, 0x00]).is_empty());
+    assert!(reasons(&[0xf9, 0x7c, 0x00]).is_empty());
+    assert_eq!(
+      reasons(&[0xf9, 0x7e, 0x01]),
+      vec![(
+        0,
+        "".to_string(),
+        "NaN 0x7e01 is not encoded as the canonical NaN 0x7e00".to_string()
+      )]
+    );
+    assert_eq!(reasons(&[0xf9, 0xfe, 0x00]).len(), 1);
+  }
+
+  #[test]
+  fn verify_deeply_nested_items() {
+    // 2 MB of nested single-item arrays
+    let violations = reasons(&vec![0x81; 2 << 20]);
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].0, MAX_DEPTH);
+    assert_eq!(violations[0].1, "/0".repeat(MAX_DEPTH));
+    assert_eq!(
+      violations[0].2,
+      format!("data item is nested more than {} levels deep", MAX_DEPTH)
+    );
+
+    let mut nested_tags = vec![0xc0; MAX_DEPTH - 1];
+    nested_tags.push(0x00);
+    assert!(reasons(&nested_tags).is_empty());
+  }
+
+  #[test]
+  fn verify_escaped_key_location() {
+    // { "a\"b": 1, "a\"b": 2 }
+    let violations = reasons(&[
+      0xa2, 0x63, 0x61, 0x22, 0x62, 0x01, 0x63, 0x61, 0x22, 0x62, 0x02,
+    ]);
+    assert_eq!(violations[0].1, format!("/{:?}", "a\"b"));
+  }
+}
@@ -0,0 +1,295 @@
+#![cfg(feature = "std")]
+
+use std::collections::HashMap;
+
+/// Predicate a JSON value of an extended prelude type must satisfy
+#[cfg(feature = "json")]
+pub type JsonPredicate = Box<dyn Fn(&serde_json::Value) -> bool + Send + Sync>;
+
+/// Predicate a CBOR data item of an extended prelude type must satisfy
+#[cfg(feature = "cbor")]
+pub type CborPredicate = Box<dyn Fn(&ciborium::value::Value) -> bool + Send + Sync>;
+
+#[derive(Default)]
+struct Predicates {
+  #[cfg(feature = "json")]
+  json: Option<JsonPredicate>,
+  #[cfg(feature = "cbor")]
+  cbor: Option<CborPredicate>,
+}
+
+/// Type names, beyond those of the standard prelude of RFC 8610, that documents
+/// may reference without defining them, each validated by a predicate. Such
+/// names are defined by RFCs and drafts extending the prelude, e.g. the object
+/// identifiers of RFC 9090.
+///
+/// Rules defined by the document and the types of the standard prelude take
+/// precedence over the names registered here. A name registered for only one
+/// of JSON and CBOR is unknown to the validator of the other.
+///
+/// ```
+/// use cddl::validator::{extended_prelude::ExtendedPrelude, json::JSONValidator};
+///
+/// let mut prelude = ExtendedPrelude::default();
+/// prelude.register_json("eui48", |v| {
+///   v.as_str().is_some_and(|s| {
+///     s.split(':').count() == 6
+///       && s.split(':').all(|o| o.len() == 2 && u8::from_str_radix(o, 16).is_ok())
+///   })
+/// });
+///
+/// let cddl = cddl::parser::cddl_from_str_with_extended_prelude(
+///   "device = { mac: eui48 }",
+///   &prelude.names(),
+///   true,
+/// )
+/// .unwrap();
+/// let json = serde_json::json!({ "mac": "00:1a:2b:3c:4d:5e" });
+///
+/// # #[cfg(feature = "additional-controls")]
+/// let mut jv = JSONValidator::new(&cddl, json, None);
+/// # #[cfg(not(feature = "additional-controls"))]
+/// # let mut jv = JSONValidator::new(&cddl, json);
+/// jv.set_extended_prelude(&prelude);
+/// # use cddl::validator::Validator;
+/// assert!(jv.validate().is_ok());
+/// ```
+#[derive(Default)]
+pub struct ExtendedPrelude {
+  types: HashMap<String, Predicates>,
+}
+
+impl ExtendedPrelude {
+  /// Types defined by RFC 9090: `oid`, `roid` and `pen`, i.e. absolute and
+  /// relative object identifiers and relative object identifiers anchored at
+  /// the private enterprise numbers arc. They are only defined for CBOR
+  #[cfg(all(feature = "rfc9090", feature = "cbor"))]
+  pub fn rfc9090() -> Self {
+    use ciborium::value::Value;
+
+    let mut prelude = ExtendedPrelude::default();
+    for (name, tag) in [("oid", 111), ("roid", 110), ("pen", 112)] {
+      prelude.register_cbor(name, move |v| match v {
+        Value::Tag(t, v) if *t == tag => {
+          matches!(&**v, Value::Bytes(b) if is_valid_oid_encoding(b))
+        }
+        _ => false,
+      });
+    }
+
+    prelude
+  }
+
+  /// Register a type name whose JSON values must satisfy the given predicate
+  #[cfg(feature = "json")]
+  pub fn register_json<F>(&mut self, name: &str, predicate: F) -> &mut Self
+  where
+    F: Fn(&serde_json::Value) -> bool + Send + Sync + 'static,
+  {
+    self.types.entry(name.to_string()).or_default().json = Some(Box::new(predicate));
+    self
+  }
+
+  /// Register a type name whose CBOR data items must satisfy the given
+  /// predicate
+  #[cfg(feature = "cbor")]
+  pub fn register_cbor<F>(&mut self, name: &str, predicate: F) -> &mut Self
+  where
+    F: Fn(&ciborium::value::Value) -> bool + Send + Sync + 'static,
+  {
+    self.types.entry(name.to_string()).or_default().cbor = Some(Box::new(predicate));
+    self
+  }
+
+  /// Add the types registered with another extended prelude, replacing the
+  /// predicates of names registered with both
+  pub fn extend(&mut self, other: ExtendedPrelude) -> &mut Self {
+    for (name, predicates) in other.types {
+      let entry = self.types.entry(name).or_default();
+      #[cfg(feature = "json")]
+      if predicates.json.is_some() {
+        entry.json = predicates.json;
+      }
+      #[cfg(feature = "cbor")]
+      if predicates.cbor.is_some() {
+        entry.cbor = predicates.cbor;
+      }
+    }
+
+    self
+  }
+
+  /// Whether or not the type name has been registered
+  pub fn contains(&self, name: &str) -> bool {
+    self.types.contains_key(name)
+  }
+
+  /// Names of the registered types in sorted order, for documents referencing
+  /// them to be parsed with `parser::cddl_from_str_with_extended_prelude`
+  pub fn names(&self) -> Vec<&str> {
+    let mut names = self.types.keys().map(String::as_str).collect::<Vec<_>>();
+    names.sort_unstable();
+    names
+  }
+
+  /// Whether or not the JSON value is a value of the type, or `None` if the
+  /// type has no predicate for JSON
+  #[cfg(feature = "json")]
+  pub fn validate_json(&self, name: &str, value: &serde_json::Value) -> Option<bool> {
+    self
+      .types
+      .get(name)
+      .and_then(|p| p.json.as_ref())
+      .map(|predicate| predicate(value))
+  }
+
+  /// Whether or not the CBOR data item is a value of the type, or `None` if the
+  /// type has no predicate for CBOR
+  #[cfg(feature = "cbor")]
+  pub fn validate_cbor(&self, name: &str, value: &ciborium::value::Value) -> Option<bool> {
+    self
+      .types
+      .get(name)
+      .and_then(|p| p.cbor.as_ref())
+      .map(|predicate| predicate(value))
+  }
+}
+
+impl std::fmt::Debug for ExtendedPrelude {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("ExtendedPrelude")
+      .field("types", &self.names())
+      .finish()
+  }
+}
+
+// Whether or not the bytes are the BER encoding of the subidentifiers of an
+// object identifier, without tag and length, as required by RFC 9090: at least
+// one subidentifier, each in the minimal number of bytes and the last byte of
+// each without the continuation bit set
+#[cfg(all(feature = "rfc9090", feature = "cbor"))]
+fn is_valid_oid_encoding(bytes: &[u8]) -> bool {
+  let mut subidentifier_start = true;
+  for b in bytes {
+    if subidentifier_start && *b == 0x80 {
+      return false;
+    }
+
+    subidentifier_start = b & 0x80 == 0;
+  }
+
+  !bytes.is_empty() && subidentifier_start
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "json", feature = "cbor"))]
+mod tests {
+  #![allow(unused_imports)]
+
+  use super::*;
+  use crate::{
+    parser::{cddl_from_str, cddl_from_str_with_extended_prelude},
+    validator::{cbor::CBORValidator, json::JSONValidator, Validator},
+  };
+  use ciborium::value::Value;
+
+  #[test]
+  fn validate_extended_prelude_types() {
+    let is_ulid = |s: &str| s.len() == 26 && s.bytes().all(|b| b.is_ascii_alphanumeric());
+    let mut prelude = ExtendedPrelude::default();
+    prelude
+      .register_json("ulid", move |v| v.as_str().is_some_and(is_ulid))
+      .register_cbor("ulid", move |v| v.as_text().is_some_and(is_ulid));
+
+    let input = "reading = { id: ulid, ? source: ulid / tstr }";
+    assert!(cddl_from_str(input, false).is_err());
+    let cddl = cddl_from_str_with_extended_prelude(input, &prelude.names(), true).unwrap();
+
+    let validate_json = |json: serde_json::Value, prelude: Option<&ExtendedPrelude>| {
+      #[cfg(feature = "additional-controls")]
+      let mut jv = JSONValidator::new(&cddl, json, None);
+      #[cfg(not(feature = "additional-controls"))]
+      let mut jv = JSONValidator::new(&cddl, json);
+      if let Some(prelude) = prelude {
+        jv.set_extended_prelude(prelude);
+      }
+      jv.validate()
+    };
+
+    let id = "01ARZ3NDEKTSV4RRFFQ69G5FAV";
+    assert!(validate_json(serde_json::json!({ "id": id }), Some(&prelude)).is_ok());
+    assert!(validate_json(
+      serde_json::json!({ "id": id, "source": "x" }),
+      Some(&prelude)
+    )
+    .is_ok());
+    assert!(validate_json(serde_json::json!({ "id": "x" }), Some(&prelude)).is_err());
+    assert!(validate_json(serde_json::json!({ "id": id }), None).is_err());
+
+    let validate_cbor = |cbor: Value| {
+      #[cfg(feature = "additional-controls")]
+      let mut cv = CBORValidator::new(&cddl, cbor, None);
+      #[cfg(not(feature = "additional-controls"))]
+      let mut cv = CBORValidator::new(&cddl, cbor);
+      cv.set_extended_prelude(&prelude);
+      <CBORValidator as Validator<crate::validator::cbor::Error<std::io::Error>>>::validate(&mut cv)
+    };
+
+    let map = |id: &str| Value::Map(vec![(Value::Text("id".into()), Value::Text(id.into()))]);
+    assert!(validate_cbor(map(id)).is_ok());
+    assert!(validate_cbor(map("x")).is_err());
+  }
+
+  #[test]
+  fn validate_extended_prelude_precedence() {
+    let cddl = cddl_from_str("entries = [* count]\ncount = tstr", true).unwrap();
+
+    let mut prelude = ExtendedPrelude::default();
+    prelude
+      .register_json("count", |_| false)
+      .register_json("tstr", |_| false);
+
+    #[cfg(feature = "additional-controls")]
+    let mut jv = JSONValidator::new(&cddl, serde_json::json!(["a", "b"]), None);
+    #[cfg(not(feature = "additional-controls"))]
+    let mut jv = JSONValidator::new(&cddl, serde_json::json!(["a", "b"]));
+    jv.set_extended_prelude(&prelude);
+    assert!(jv.validate().is_ok());
+  }
+
+  #[cfg(feature = "rfc9090")]
+  #[test]
+  fn validate_rfc9090_types() {
+    let prelude = ExtendedPrelude::rfc9090();
+    let cddl =
+      cddl_from_str_with_extended_prelude("ids = [* oid / roid / pen]", &prelude.names(), true)
+        .unwrap();
+
+    let validate = |cbor: Value| {
+      #[cfg(feature = "additional-controls")]
+      let mut cv = CBORValidator::new(&cddl, cbor, None);
+      #[cfg(not(feature = "additional-controls"))]
+      let mut cv = CBORValidator::new(&cddl, cbor);
+      cv.set_extended_prelude(&prelude);
+      <CBORValidator as Validator<crate::validator::cbor::Error<std::io::Error>>>::validate(&mut cv)
+    };
+
+    // 1.3.6.1.4.1, 1.2 and 4 respectively
+    let oid = Value::Tag(
+      111,
+      Box::new(Value::Bytes(vec![0x2b, 0x06, 0x01, 0x04, 0x01])),
+    );
+    let roid = Value::Tag(110, Box::new(Value::Bytes(vec![0x01, 0x02])));
+    let pen = Value::Tag(112, Box::new(Value::Bytes(vec![0x04])));
+    assert!(validate(Value::Array(vec![oid, roid, pen])).is_ok());
+
+    // Non-minimal and truncated subidentifiers
+    for bytes in [vec![0x80, 0x01], vec![0x2b, 0x86], vec![]] {
+      let oid = Value::Tag(111, Box::new(Value::Bytes(bytes)));
+      assert!(validate(Value::Array(vec![oid])).is_err());
+    }
+
+    let untagged = Value::Bytes(vec![0x2b, 0x06]);
+    assert!(validate(Value::Array(vec![untagged])).is_err());
+  }
+}
@@ -2,7 +2,7 @@
 #![cfg(feature = "json")]
 #![cfg(not(feature = "lsp"))]
 
-use super::*;
+use super::{compiled::CompiledCddl, *};
 use crate::{
   ast::*,
   token::{self, Token},
@@ -14,8 +14,14 @@ use std::{borrow::Cow, collections::HashMap, convert::TryFrom, fmt};
 use chrono::{TimeZone, Utc};
 use serde_json::Value;
 
+use super::extended_prelude::ExtendedPrelude;
+#[cfg(feature = "control-plugins")]
+use super::plugin::{ControlPlugins, Encoding};
 #[cfg(feature = "additional-controls")]
-use control::{abnf_from_complex_controller, cat_operation, plus_operation, validate_abnf};
+use control::{
+  abnf_from_complex_controller, cat_operation, plus_operation_with_overflow, validate_abnf,
+};
+use control::{OverflowBehavior, RegexEngine};
 
 /// JSON validation Result
 pub type Result = std::result::Result<(), Error>;
@@ -28,11 +34,15 @@ pub enum Error {
   /// JSON parsing error
   JSONParsing(serde_json::Error),
   /// CDDL parsing error
-  CDDLParsing(String),
+  CDDLParsing(parser::Error),
   /// UTF8 parsing error,
   UTF8Parsing(std::str::Utf8Error),
   /// Disabled feature
   DisabledFeature(String),
+  /// Rule selected as the validation root is not defined
+  MissingRule(String),
+  /// Validation exceeded one of the validation limits
+  LimitExceeded(String),
 }
 
 impl fmt::Display for Error {
@@ -49,6 +59,8 @@ impl fmt::Display for Error {
       Error::CDDLParsing(error) => write!(f, "error parsing CDDL: {}", error),
       Error::UTF8Parsing(error) => write!(f, "error pasing utf8: {}", error),
       Error::DisabledFeature(feature) => write!(f, "feature {} is not enabled", feature),
+      Error::MissingRule(rule) => write!(f, "no rule named {} is defined", rule),
+      Error::LimitExceeded(limit) => write!(f, "validation limit exceeded: {}", limit),
     }
   }
 }
@@ -57,6 +69,62 @@ impl std::error::Error for Error {
   fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
     match self {
       Error::JSONParsing(error) => Some(error),
+      Error::CDDLParsing(error) => Some(error),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for Error {
+  fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+    match self {
+      Error::CDDLParsing(e) => e.code(),
+      Error::Validation(_) => Some(Box::new("cddl::validation")),
+      Error::JSONParsing(_) => Some(Box::new("cddl::json")),
+      Error::UTF8Parsing(_) => Some(Box::new("cddl::utf8")),
+      Error::DisabledFeature(_) => Some(Box::new("cddl::feature")),
+      Error::MissingRule(_) => Some(Box::new("cddl::missing_rule")),
+      Error::LimitExceeded(_) => Some(Box::new("cddl::limit_exceeded")),
+    }
+  }
+
+  fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+    match self {
+      Error::Validation(errors) => errors.iter().find_map(|e| e.help()),
+      Error::CDDLParsing(e) => e.help(),
+      Error::DisabledFeature(feature) => Some(Box::new(format!(
+        "enable the \"{}\" feature when constructing the validator",
+        feature
+      ))),
+      Error::MissingRule(_) => Some(Box::new(
+        "the root rule must be the name of a type rule defined in the CDDL document",
+      )),
+      Error::LimitExceeded(_) => Some(Box::new(
+        "raise the validation limits if the document and data are trusted",
+      )),
+      _ => None,
+    }
+  }
+
+  fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+    match self {
+      Error::CDDLParsing(e) => e.source_code(),
+      _ => None,
+    }
+  }
+
+  fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+    match self {
+      Error::Validation(errors) => Some(Box::new(
+        errors
+          .iter()
+          .filter_map(|e| e.labels())
+          .flatten()
+          .collect::<Vec<_>>()
+          .into_iter(),
+      )),
+      Error::CDDLParsing(e) => e.labels(),
       _ => None,
     }
   }
@@ -70,7 +138,9 @@ impl Error {
       reason,
       is_multi_type_choice: jv.is_multi_type_choice,
       is_group_to_choice_enum: jv.is_group_to_choice_enum,
-      type_group_name_entry: jv.type_group_name_entry.map(|e| e.to_string()),
+      type_group_name_entry: jv.type_group_name_entry.as_ref().map(|e| e.to_string()),
+      #[cfg(feature = "ast-span")]
+      cddl_span: jv.cddl_span,
       is_multi_group_choice: jv.is_multi_group_choice,
     }])
   }
@@ -81,7 +151,7 @@ impl Error {
 pub struct ValidationError {
   /// Error message
   pub reason: String,
-  /// Location in CDDL where error occurred
+  /// Name of the innermost rule being validated when the error occurred
   pub cddl_location: String,
   /// Location in JSON (in JSONPointer notation) where error occurred
   pub json_location: String,
@@ -93,6 +163,10 @@ pub struct ValidationError {
   pub is_group_to_choice_enum: bool,
   /// Error is associated with a type/group name group entry
   pub type_group_name_entry: Option<String>,
+  /// Span of the CDDL type or group entry being validated when the error
+  /// occurred
+  #[cfg(feature = "ast-span")]
+  pub cddl_span: Option<Span>,
 }
 
 impl fmt::Display for ValidationError {
@@ -133,6 +207,36 @@ impl std::error::Error for ValidationError {
   }
 }
 
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for ValidationError {
+  fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+    Some(Box::new("cddl::validation"))
+  }
+
+  fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+    if self.is_multi_type_choice || self.is_multi_group_choice || self.is_group_to_choice_enum {
+      return Some(Box::new("the value must match at least one of the choices"));
+    }
+
+    self.type_group_name_entry.as_ref().map(|entry| {
+      Box::new(format!("the entry is defined by rule \"{}\"", entry)) as Box<dyn fmt::Display>
+    })
+  }
+
+  fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+    #[cfg(feature = "ast-span")]
+    if let Some(span) = self.cddl_span {
+      return Some(Box::new(std::iter::once(miette::LabeledSpan::new(
+        Some(self.reason.clone()),
+        span.0,
+        span.1.saturating_sub(span.0),
+      ))));
+    }
+
+    None
+  }
+}
+
 impl ValidationError {
   fn from_validator(jv: &JSONValidator, reason: String) -> Self {
     ValidationError {
@@ -141,7 +245,9 @@ impl ValidationError {
       reason,
       is_multi_type_choice: jv.is_multi_type_choice,
       is_group_to_choice_enum: jv.is_group_to_choice_enum,
-      type_group_name_entry: jv.type_group_name_entry.map(|e| e.to_string()),
+      type_group_name_entry: jv.type_group_name_entry.as_ref().map(|e| e.to_string()),
+      #[cfg(feature = "ast-span")]
+      cddl_span: jv.cddl_span,
       is_multi_group_choice: jv.is_multi_group_choice,
     }
   }
@@ -155,6 +261,9 @@ pub struct JSONValidator<'a> {
   errors: Vec<ValidationError>,
   cddl_location: String,
   json_location: String,
+  // Span of the CDDL type or group entry currently being validated
+  #[cfg(feature = "ast-span")]
+  cddl_span: Option<Span>,
   // Occurrence indicator detected in current state of AST evaluation
   occurrence: Option<Occur>,
   // Current group entry index detected in current state of AST evaluation
@@ -169,7 +278,7 @@ pub struct JSONValidator<'a> {
   cut_value: Option<Cow<'a, str>>,
   // Validate the generic rule given by str ident in current state of AST
   // evaluation
-  eval_generic_rule: Option<&'a str>,
+  eval_generic_rule: Option<Cow<'a, str>>,
   // Aggregation of generic rules
   generic_rules: Vec<GenericRule<'a>>,
   // Control operator token detected in current state of AST evaluation
@@ -183,7 +292,7 @@ pub struct JSONValidator<'a> {
   is_multi_group_choice: bool,
   // Type/group name entry detected in current state of AST evaluation. Used
   // only for providing more verbose error messages
-  type_group_name_entry: Option<&'a str>,
+  type_group_name_entry: Option<Cow<'a, str>>,
   // Whether or not to advance to the next group entry if member key validation
   // fails as detected during the current state of AST evaluation
   advance_to_next_entry: bool,
@@ -191,6 +300,12 @@ pub struct JSONValidator<'a> {
   entry_counts: Option<Vec<EntryCount>>,
   // Collect map entry keys that have already been validated
   validated_keys: Option<Vec<String>>,
+  // Whether or not a member key with a cut matched an object key, along with
+  // its value, in the current group choice
+  is_cut_matched: bool,
+  // Errors of object values that failed to match an optional member key
+  // without a cut, reported unless another group entry matches their key
+  unmatched_key_errors: Vec<(String, Vec<ValidationError>)>,
   // Collect map entry values that have yet to be validated
   values_to_validate: Option<Vec<Value>>,
   // Collect valid array indices when entries are type choices
@@ -200,22 +315,49 @@ pub struct JSONValidator<'a> {
   array_errors: Option<HashMap<usize, Vec<ValidationError>>>,
   is_colon_shortcut_present: bool,
   is_root: bool,
+  // Labels attached to rules via `;@label` annotations
+  rule_labels: Option<&'a RuleLabels>,
+  // Compiled document providing the rule index and precompiled controllers
+  compiled: Option<&'a CompiledCddl<'a>>,
+  // Locations successfully validated against labeled rules
+  labeled_paths: Vec<LabeledPath>,
+  // Whether or not to explain the matches of type choices
+  explain_choices: bool,
+  // Explanations of the type choices matched during validation
+  choice_explanations: Vec<ChoiceExplanation>,
+  // Uses of features by the locations validated so far
+  feature_uses: Vec<FeatureUse>,
+  // Behavior of control operator arithmetic on integer overflow
+  overflow_behavior: OverflowBehavior,
+  // Engine matching the `.regexp` and `.pcre` control operators
+  regex_engine: RegexEngine,
+  // Resources used so far, bounded by the validation limits
+  budget: Budget,
+  // Plugins implementing control operators beyond RFC 8610 and RFC 9165
+  #[cfg(feature = "control-plugins")]
+  control_plugins: Option<&'a ControlPlugins>,
+  // Types beyond the standard prelude that the document may reference without
+  // defining them
+  extended_prelude: Option<&'a ExtendedPrelude>,
+  // Rule, and optional generic arguments, to validate against instead of the
+  // first type rule in the document
+  root_rule: Option<(&'a str, Option<GenericArgs<'a>>)>,
   #[cfg(not(target_arch = "wasm32"))]
   #[cfg(feature = "additional-controls")]
   enabled_features: Option<&'a [&'a str]>,
   #[cfg(target_arch = "wasm32")]
   #[cfg(feature = "additional-controls")]
   enabled_features: Option<Box<[JsValue]>>,
+  // Behavior of the `.feature` control operator on matches of features that
+  // aren't enabled
   #[cfg(feature = "additional-controls")]
-  has_feature_errors: bool,
-  #[cfg(feature = "additional-controls")]
-  disabled_features: Option<Vec<String>>,
+  disabled_feature_behavior: DisabledFeatureBehavior,
 }
 
 #[derive(Clone, Debug)]
 struct GenericRule<'a> {
-  name: &'a str,
-  params: Vec<&'a str>,
+  name: Cow<'a, str>,
+  params: Vec<Cow<'a, str>>,
   args: Vec<Type1<'a>>,
 }
 
@@ -229,6 +371,8 @@ impl<'a> JSONValidator<'a> {
       json,
       errors: Vec::default(),
       cddl_location: String::new(),
+      #[cfg(feature = "ast-span")]
+      cddl_span: None,
       json_location: String::new(),
       occurrence: None,
       group_entry_idx: None,
@@ -247,14 +391,28 @@ impl<'a> JSONValidator<'a> {
       is_ctrl_map_equality: false,
       entry_counts: None,
       validated_keys: None,
+      is_cut_matched: false,
+      unmatched_key_errors: Vec::new(),
       values_to_validate: None,
       valid_array_items: None,
       array_errors: None,
       is_colon_shortcut_present: false,
       is_root: false,
+      rule_labels: None,
+      compiled: None,
+      labeled_paths: Vec::new(),
+      explain_choices: false,
+      overflow_behavior: OverflowBehavior::default(),
+      regex_engine: RegexEngine::default(),
+      budget: Budget::default(),
+      #[cfg(feature = "control-plugins")]
+      control_plugins: None,
+      extended_prelude: None,
+      choice_explanations: Vec::new(),
+      feature_uses: Vec::new(),
+      root_rule: None,
       enabled_features,
-      has_feature_errors: false,
-      disabled_features: None,
+      disabled_feature_behavior: DisabledFeatureBehavior::default(),
     }
   }
 
@@ -267,6 +425,8 @@ impl<'a> JSONValidator<'a> {
       json,
       errors: Vec::default(),
       cddl_location: String::new(),
+      #[cfg(feature = "ast-span")]
+      cddl_span: None,
       json_location: String::new(),
       occurrence: None,
       group_entry_idx: None,
@@ -285,11 +445,26 @@ impl<'a> JSONValidator<'a> {
       is_ctrl_map_equality: false,
       entry_counts: None,
       validated_keys: None,
+      is_cut_matched: false,
+      unmatched_key_errors: Vec::new(),
       values_to_validate: None,
       valid_array_items: None,
       array_errors: None,
       is_colon_shortcut_present: false,
       is_root: false,
+      rule_labels: None,
+      compiled: None,
+      labeled_paths: Vec::new(),
+      explain_choices: false,
+      overflow_behavior: OverflowBehavior::default(),
+      regex_engine: RegexEngine::default(),
+      budget: Budget::default(),
+      #[cfg(feature = "control-plugins")]
+      control_plugins: None,
+      extended_prelude: None,
+      choice_explanations: Vec::new(),
+      feature_uses: Vec::new(),
+      root_rule: None,
     }
   }
 
@@ -302,6 +477,8 @@ impl<'a> JSONValidator<'a> {
       json,
       errors: Vec::default(),
       cddl_location: String::new(),
+      #[cfg(feature = "ast-span")]
+      cddl_span: None,
       json_location: String::new(),
       occurrence: None,
       group_entry_idx: None,
@@ -320,14 +497,28 @@ impl<'a> JSONValidator<'a> {
       is_ctrl_map_equality: false,
       entry_counts: None,
       validated_keys: None,
+      is_cut_matched: false,
+      unmatched_key_errors: Vec::new(),
       values_to_validate: None,
       valid_array_items: None,
       array_errors: None,
       is_colon_shortcut_present: false,
       is_root: false,
+      rule_labels: None,
+      compiled: None,
+      labeled_paths: Vec::new(),
+      explain_choices: false,
+      overflow_behavior: OverflowBehavior::default(),
+      regex_engine: RegexEngine::default(),
+      budget: Budget::default(),
+      #[cfg(feature = "control-plugins")]
+      control_plugins: None,
+      extended_prelude: None,
+      choice_explanations: Vec::new(),
+      feature_uses: Vec::new(),
+      root_rule: None,
       enabled_features,
-      has_feature_errors: false,
-      disabled_features: None,
+      disabled_feature_behavior: DisabledFeatureBehavior::default(),
     }
   }
 
@@ -340,6 +531,8 @@ impl<'a> JSONValidator<'a> {
       json,
       errors: Vec::default(),
       cddl_location: String::new(),
+      #[cfg(feature = "ast-span")]
+      cddl_span: None,
       json_location: String::new(),
       occurrence: None,
       group_entry_idx: None,
@@ -358,11 +551,26 @@ impl<'a> JSONValidator<'a> {
       is_ctrl_map_equality: false,
       entry_counts: None,
       validated_keys: None,
+      is_cut_matched: false,
+      unmatched_key_errors: Vec::new(),
       values_to_validate: None,
       valid_array_items: None,
       array_errors: None,
       is_colon_shortcut_present: false,
       is_root: false,
+      rule_labels: None,
+      compiled: None,
+      labeled_paths: Vec::new(),
+      explain_choices: false,
+      overflow_behavior: OverflowBehavior::default(),
+      regex_engine: RegexEngine::default(),
+      budget: Budget::default(),
+      #[cfg(feature = "control-plugins")]
+      control_plugins: None,
+      extended_prelude: None,
+      choice_explanations: Vec::new(),
+      feature_uses: Vec::new(),
+      root_rule: None,
     }
   }
 
@@ -387,15 +595,8 @@ impl<'a> JSONValidator<'a> {
                 }
               }
 
-              #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-              let mut jv = JSONValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-              #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-              let mut jv = JSONValidator::new(self.cddl, v.clone(), self.enabled_features);
-              #[cfg(not(feature = "additional-controls"))]
-              let mut jv = JSONValidator::new(self.cddl, v.clone());
+              let mut jv = self.nested_validator(v.clone());
 
-              jv.generic_rules = self.generic_rules.clone();
-              jv.eval_generic_rule = self.eval_generic_rule;
               jv.is_multi_type_choice = self.is_multi_type_choice;
               jv.ctrl = self.ctrl.clone();
               jv.json_location
@@ -403,6 +604,12 @@ impl<'a> JSONValidator<'a> {
 
               jv.visit_value(value)?;
 
+              if jv.errors.is_empty() {
+                self.labeled_paths.append(&mut jv.labeled_paths);
+                self.choice_explanations.append(&mut jv.choice_explanations);
+                self.feature_uses.append(&mut jv.feature_uses);
+              }
+
               if self.is_multi_type_choice && jv.errors.is_empty() {
                 if let Some(indices) = &mut self.valid_array_items {
                   indices.push(idx);
@@ -426,15 +633,8 @@ impl<'a> JSONValidator<'a> {
             }
           } else if let Some(idx) = self.group_entry_idx.take() {
             if let Some(v) = a.get(idx) {
-              #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-              let mut jv = JSONValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-              #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-              let mut jv = JSONValidator::new(self.cddl, v.clone(), self.enabled_features);
-              #[cfg(not(feature = "additional-controls"))]
-              let mut jv = JSONValidator::new(self.cddl, v.clone());
-
-              jv.generic_rules = self.generic_rules.clone();
-              jv.eval_generic_rule = self.eval_generic_rule;
+              let mut jv = self.nested_validator(v.clone());
+
               jv.is_multi_type_choice = self.is_multi_type_choice;
               jv.ctrl = self.ctrl.clone();
               jv.json_location
@@ -442,6 +642,12 @@ impl<'a> JSONValidator<'a> {
 
               jv.visit_value(value)?;
 
+              if jv.errors.is_empty() {
+                self.labeled_paths.append(&mut jv.labeled_paths);
+                self.choice_explanations.append(&mut jv.choice_explanations);
+                self.feature_uses.append(&mut jv.feature_uses);
+              }
+
               self.errors.append(&mut jv.errors);
             } else if !allow_empty_array {
               self.add_error(format!("expected value {} at index {}", value, idx));
@@ -479,7 +685,7 @@ impl<'a> JSONValidator<'a> {
         if let Some(v) = o.get(t.as_ref()) {
           self
             .validated_keys
-            .get_or_insert(vec![t.to_string()])
+            .get_or_insert_with(Vec::new)
             .push(t.to_string());
           self.object_value = Some(v.clone());
           self.json_location.push_str(&format!("/{}", t));
@@ -502,7 +708,7 @@ impl<'a> JSONValidator<'a> {
         if let Some(v) = o.get(t.as_ref()) {
           self
             .validated_keys
-            .get_or_insert(vec![t.to_string()])
+            .get_or_insert_with(Vec::new)
             .push(t.to_string());
           self.object_value = Some(v.clone());
           self.json_location.push_str(&format!("/{}", t));
@@ -526,1697 +732,2267 @@ impl<'a> JSONValidator<'a> {
 
     Ok(())
   }
-}
 
-impl<'a> Validator<'a, Error> for JSONValidator<'a> {
-  /// Validate
-  fn validate(&mut self) -> std::result::Result<(), Error> {
-    for r in self.cddl.rules.iter() {
-      // First type rule is root
-      if let Rule::Type { rule, .. } = r {
-        if rule.generic_params.is_none() {
-          self.is_root = true;
-          self.visit_type_rule(rule)?;
-          self.is_root = false;
-          break;
-        }
-      }
-    }
+  /// Set the labels attached to rules via `;@label` annotations. Locations in
+  /// the JSON document that successfully validate against a labeled rule are
+  /// collected during validation
+  pub fn set_rule_labels(&mut self, rule_labels: &'a RuleLabels) {
+    self.rule_labels = Some(rule_labels);
+  }
 
-    if !self.errors.is_empty() {
-      return Err(Error::Validation(self.errors.clone()));
-    }
+  /// Validate against the named rule rather than the first type rule in the
+  /// document. Generic rules must be given generic arguments, e.g. the
+  /// arguments parsed from `message<tstr, uint>`
+  pub fn set_root_rule(&mut self, name: &'a str, generic_args: Option<GenericArgs<'a>>) {
+    self.root_rule = Some((name, generic_args));
+  }
 
-    Ok(())
+  /// Look up rules and precompiled controllers in a compiled document rather
+  /// than the document the validator was created with. The compiled document
+  /// must be the one the validator was created with
+  pub fn set_compiled(&mut self, compiled: &'a CompiledCddl<'a>) {
+    self.compiled = Some(compiled);
   }
 
-  fn add_error(&mut self, reason: String) {
-    self.errors.push(ValidationError {
-      reason,
-      cddl_location: self.cddl_location.clone(),
-      json_location: self.json_location.clone(),
-      is_multi_type_choice: self.is_multi_type_choice,
-      is_multi_group_choice: self.is_multi_group_choice,
-      is_group_to_choice_enum: self.is_group_to_choice_enum,
-      type_group_name_entry: self.type_group_name_entry.map(|e| e.to_string()),
-    });
+  /// Labeled locations collected during validation
+  pub fn labeled_paths(&self) -> &[LabeledPath] {
+    &self.labeled_paths
   }
-}
 
-impl<'a> Visitor<'a, Error> for JSONValidator<'a> {
-  fn visit_type_rule(&mut self, tr: &TypeRule<'a>) -> visitor::Result<Error> {
-    if let Some(gp) = &tr.generic_params {
-      if let Some(gr) = self
-        .generic_rules
-        .iter_mut()
-        .find(|r| r.name == tr.name.ident)
-      {
-        gr.params = gp.params.iter().map(|p| p.param.ident).collect();
-      } else {
-        self.generic_rules.push(GenericRule {
-          name: tr.name.ident,
-          params: gp.params.iter().map(|p| p.param.ident).collect(),
-          args: vec![],
-        });
-      }
-    }
+  /// Explain, for each location validated against a type choice, which choice
+  /// matched and why the choices preceding it did not
+  pub fn set_explain_choices(&mut self, explain_choices: bool) {
+    self.explain_choices = explain_choices;
+  }
+
+  /// Explanations of the type choices matched during validation
+  pub fn choice_explanations(&self) -> &[ChoiceExplanation] {
+    &self.choice_explanations
+  }
+
+  /// Set the behavior of the integer arithmetic of control operators such as
+  /// `.plus` on overflow. Defaults to reporting overflow as a validation error
+  pub fn set_overflow_behavior(&mut self, overflow_behavior: OverflowBehavior) {
+    self.overflow_behavior = overflow_behavior;
+  }
+
+  /// Set the behavior of the `.feature` control operator when its target
+  /// matches but the feature isn't among the enabled features. Defaults to
+  /// rejecting the match. All features are enabled if no enabled features were
+  /// given
+  #[cfg(feature = "additional-controls")]
+  pub fn set_disabled_feature_behavior(&mut self, behavior: DisabledFeatureBehavior) {
+    self.disabled_feature_behavior = behavior;
+  }
+
+  /// Uses of features by the locations validated, i.e. matches of the targets
+  /// of `.feature` control operators
+  pub fn feature_uses(&self) -> &[FeatureUse] {
+    &self.feature_uses
+  }
+
+  /// Set the engine matching the `.regexp` and `.pcre` control operators.
+  /// Defaults to the linear-time engine of the `regex` crate
+  pub fn set_regex_engine(&mut self, regex_engine: RegexEngine) {
+    self.regex_engine = regex_engine;
+  }
+
+  /// Set the limits on the resources used to validate the data. Validation
+  /// exceeding them fails with `Error::LimitExceeded`. Defaults to
+  /// `ValidationLimits::default()`
+  pub fn set_limits(&mut self, limits: ValidationLimits) {
+    self.budget = Budget::new(limits);
+  }
+
+  /// Set the plugins implementing control operators defined neither by RFC
+  /// 8610 nor RFC 9165. Such control operators are otherwise reported as
+  /// unsupported
+  #[cfg(feature = "control-plugins")]
+  pub fn set_control_plugins(&mut self, control_plugins: &'a ControlPlugins) {
+    self.control_plugins = Some(control_plugins);
+  }
+
+  /// Set the types, beyond those of the standard prelude, that the document may
+  /// reference without defining them. Rules defined by the document and types
+  /// of the standard prelude take precedence
+  pub fn set_extended_prelude(&mut self, extended_prelude: &'a ExtendedPrelude) {
+    self.extended_prelude = Some(extended_prelude);
+  }
+
+  // Validate the target of a `.feature` control operator, recording the use of
+  // the feature if the target matches. Matches of features that aren't enabled
+  // are either rejected or recorded, depending on the disabled feature behavior
+  #[cfg(feature = "additional-controls")]
+  fn validate_feature(
+    &mut self,
+    target: &Type2<'a>,
+    controller: &Type2<'a>,
+  ) -> visitor::Result<Error> {
+    let feature = match text_value_from_type2(self.cddl, controller) {
+      Some(Type2::TextValue { value, .. }) => value.to_string(),
+      Some(Type2::UTF8ByteString { value, .. }) => std::str::from_utf8(value)
+        .map_err(Error::UTF8Parsing)?
+        .to_string(),
+      _ => return self.visit_type2(target),
+    };
 
     let error_count = self.errors.len();
+    self.visit_type2(target)?;
+    if self.errors.len() > error_count {
+      return Ok(());
+    }
 
-    for t in type_choice_alternates_from_ident(self.cddl, &tr.name) {
-      let cur_errors = self.errors.len();
-      self.visit_type(t)?;
-      if self.errors.len() == cur_errors {
-        for _ in 0..self.errors.len() - error_count {
-          self.errors.pop();
-        }
+    // As a member key, the target is only used if it matched any entries
+    if self.is_member_key
+      && matches!(self.json, Value::Object(_))
+      && self.object_value.is_none()
+      && self
+        .values_to_validate
+        .as_ref()
+        .is_none_or(|values| values.is_empty())
+    {
+      return Ok(());
+    }
 
-        return Ok(());
-      }
+    #[cfg(not(target_arch = "wasm32"))]
+    let enabled = self
+      .enabled_features
+      .is_none_or(|ef| ef.contains(&feature.as_str()));
+    #[cfg(target_arch = "wasm32")]
+    let enabled = self
+      .enabled_features
+      .as_ref()
+      .is_none_or(|ef| ef.contains(&JsValue::from(feature.as_str())));
+
+    if !enabled && self.disabled_feature_behavior == DisabledFeatureBehavior::Reject {
+      self.add_error(format!("feature {} is not enabled", feature));
+      return Ok(());
     }
 
+    self.feature_uses.push(FeatureUse {
+      location: self.json_location.clone(),
+      feature,
+      enabled,
+    });
+
     Ok(())
   }
 
-  fn visit_group_rule(&mut self, gr: &GroupRule<'a>) -> visitor::Result<Error> {
-    if let Some(gp) = &gr.generic_params {
-      if let Some(gr) = self
-        .generic_rules
-        .iter_mut()
-        .find(|r| r.name == gr.name.ident)
-      {
-        gr.params = gp.params.iter().map(|p| p.param.ident).collect();
-      } else {
-        self.generic_rules.push(GenericRule {
-          name: gr.name.ident,
-          params: gp.params.iter().map(|p| p.param.ident).collect(),
-          args: vec![],
-        });
-      }
+  // Validate the data item against the controller of a control operator
+  // implemented by a plugin
+  #[cfg(feature = "control-plugins")]
+  fn validate_control_plugin(&mut self, target: &Type2, ctrl: &str, controller: &Type2) {
+    let plugin = match self.control_plugins.and_then(|plugins| plugins.get(ctrl)) {
+      Some(plugin) => plugin,
+      None => return,
+    };
+
+    let data = self.json.to_string();
+
+    match plugin.validate(Encoding::JSON, data.as_bytes(), &controller.to_string()) {
+      Ok(true) => (),
+      Ok(false) => self.add_error(format!(
+        "expected {} {} {}, got {}",
+        target, ctrl, controller, self.json
+      )),
+      Err(e) => self.add_error(format!("error validating control operator {}: {}", ctrl, e)),
     }
+  }
 
-    let error_count = self.errors.len();
+  // Record the outcome of validating against the type choice at the given
+  // index. Choices failing before the first match are collected as rejected
+  // choices, which are moved into the explanation once a choice matches
+  fn explain_choice(
+    &mut self,
+    t: &Type<'a>,
+    idx: usize,
+    error_count: usize,
+    explanation_count: usize,
+    rejected: &mut Option<Vec<RejectedChoice>>,
+  ) {
+    if !self.explain_choices || t.type_choices.len() < 2 {
+      return;
+    }
 
-    for ge in group_choice_alternates_from_ident(self.cddl, &gr.name) {
-      let cur_errors = self.errors.len();
-      self.visit_group_entry(ge)?;
-      if self.errors.len() == cur_errors {
-        for _ in 0..self.errors.len() - error_count {
-          self.errors.pop();
-        }
+    let choice = t.type_choices[idx].type1.to_string();
+    self.record_choice(idx, choice, false, error_count, explanation_count, rejected);
+  }
 
-        return Ok(());
-      }
+  // Explain the match of the group choice of the given index as with
+  // `explain_choice`
+  fn explain_group_choice(
+    &mut self,
+    g: &Group<'a>,
+    idx: usize,
+    error_count: usize,
+    explanation_count: usize,
+    rejected: &mut Option<Vec<RejectedChoice>>,
+  ) {
+    if !self.explain_choices || g.group_choices.len() < 2 {
+      return;
     }
 
-    Ok(())
+    let choice = g.group_choices[idx].to_string().trim().to_string();
+    self.record_choice(idx, choice, true, error_count, explanation_count, rejected);
   }
 
-  fn visit_type(&mut self, t: &Type<'a>) -> visitor::Result<Error> {
-    if t.type_choices.len() > 1 {
-      self.is_multi_type_choice = true;
+  fn record_choice(
+    &mut self,
+    idx: usize,
+    choice: String,
+    is_group_choice: bool,
+    error_count: usize,
+    explanation_count: usize,
+    rejected: &mut Option<Vec<RejectedChoice>>,
+  ) {
+    match rejected {
+      Some(choices) if self.errors.len() != error_count => {
+        self.choice_explanations.truncate(explanation_count);
+        choices.push(RejectedChoice {
+          index: idx,
+          choice,
+          reasons: self.errors[error_count..]
+            .iter()
+            .map(|e| e.reason.clone())
+            .collect(),
+        });
+      }
+      Some(choices) => {
+        self.choice_explanations.push(ChoiceExplanation {
+          location: self.json_location.clone(),
+          rule: self.cddl_location.clone(),
+          index: idx,
+          choice,
+          is_group_choice,
+          rejected: std::mem::take(choices),
+        });
+        *rejected = None;
+      }
+      // Only the first matching choice is explained
+      None => self.choice_explanations.truncate(explanation_count),
     }
+  }
 
-    let initial_error_count = self.errors.len();
-    for type_choice in t.type_choices.iter() {
-      // If validating an array whose elements are type choices (i.e. [ 1* tstr
-      // / integer ]), collect all errors and filter after the fact
-      if matches!(self.json, Value::Array(_)) {
-        let error_count = self.errors.len();
+  // Find non-choice alternate rule from a given identifier, using the rule
+  // index of the compiled document if any
+  fn lookup_rule(&self, ident: &Identifier) -> Option<&'a Rule<'a>> {
+    match self.compiled {
+      Some(compiled) => compiled.rule_from_ident(ident),
+      None => rule_from_ident(self.cddl, ident),
+    }
+  }
 
-        self.visit_type_choice(type_choice)?;
+  // Indices of the array items a rule referenced from within an array group
+  // applies to. Returns `None` if no labels are being collected or the current
+  // location is not an array being validated against an array group
+  fn array_item_indices(&self) -> Option<Vec<usize>> {
+    if self.rule_labels.is_none() || self.entry_counts.is_none() {
+      return None;
+    }
 
-        #[cfg(feature = "additional-controls")]
-        if self.errors.len() == error_count
-          && !self.has_feature_errors
-          && self.disabled_features.is_none()
-        {
-          // Disregard invalid type choice validation errors if one of the
-          // choices validates successfully
-          let type_choice_error_count = self.errors.len() - initial_error_count;
-          if type_choice_error_count > 0 {
-            for _ in 0..type_choice_error_count {
-              self.errors.pop();
-            }
-          }
-        }
+    if let Value::Array(a) = &self.json {
+      return match validate_array_occurrence(
+        self.occurrence.as_ref(),
+        self.entry_counts.as_ref().map(|ec| &ec[..]),
+        a,
+      ) {
+        Ok((true, _)) => Some((0..a.len()).collect()),
+        _ => Some(self.group_entry_idx.into_iter().collect()),
+      };
+    }
 
-        #[cfg(not(feature = "additional-controls"))]
-        if self.errors.len() == error_count {
-          // Disregard invalid type choice validation errors if one of the
-          // choices validates successfully
-          let type_choice_error_count = self.errors.len() - initial_error_count;
-          if type_choice_error_count > 0 {
-            for _ in 0..type_choice_error_count {
-              self.errors.pop();
-            }
-          }
-        }
+    None
+  }
 
-        continue;
-      }
+  // Rules referenced from within an array group are validated against the
+  // array items rather than the array itself, so labels are recorded for each
+  // item that validates successfully against the rule
+  fn collect_array_item_labels(
+    &mut self,
+    ident: &Identifier<'a>,
+    indices: &[usize],
+    labeled_path_count: usize,
+  ) -> visitor::Result<Error> {
+    self.labeled_paths.truncate(labeled_path_count);
 
-      let error_count = self.errors.len();
-      self.visit_type_choice(type_choice)?;
+    if let Value::Array(a) = &self.json {
+      for idx in indices.iter() {
+        if let Some(v) = a.get(*idx) {
+          let mut jv = self.nested_validator(v.clone());
 
-      #[cfg(feature = "additional-controls")]
-      if self.errors.len() == error_count
-        && !self.has_feature_errors
-        && self.disabled_features.is_none()
-      {
-        // Disregard invalid type choice validation errors if one of the
-        // choices validates successfully
-        let type_choice_error_count = self.errors.len() - initial_error_count;
-        if type_choice_error_count > 0 {
-          for _ in 0..type_choice_error_count {
-            self.errors.pop();
-          }
-        }
+          jv.json_location
+            .push_str(&format!("{}/{}", self.json_location, idx));
 
-        return Ok(());
-      }
+          jv.visit_identifier(ident)?;
 
-      #[cfg(not(feature = "additional-controls"))]
-      if self.errors.len() == error_count {
-        // Disregard invalid type choice validation errors if one of the
-        // choices validates successfully
-        let type_choice_error_count = self.errors.len() - initial_error_count;
-        if type_choice_error_count > 0 {
-          for _ in 0..type_choice_error_count {
-            self.errors.pop();
+          if jv.errors.is_empty() {
+            self.labeled_paths.append(&mut jv.labeled_paths);
+            self.choice_explanations.append(&mut jv.choice_explanations);
+            self.feature_uses.append(&mut jv.feature_uses);
           }
         }
-
-        return Ok(());
       }
     }
 
     Ok(())
   }
 
-  fn visit_group(&mut self, g: &Group<'a>) -> visitor::Result<Error> {
-    if g.group_choices.len() > 1 {
-      self.is_multi_group_choice = true;
+  // Record the labels of the given rule at the current location if it validated
+  // successfully, otherwise discard the labels collected while validating it
+  fn collect_labels(&mut self, ident: &Identifier, error_count: usize, labeled_path_count: usize) {
+    if self.errors.len() != error_count {
+      self.labeled_paths.truncate(labeled_path_count);
+      return;
     }
 
-    // Map equality/inequality validation
-    if self.is_ctrl_map_equality {
-      if let Some(t) = &self.ctrl {
-        if let Value::Object(o) = &self.json {
-          let mut entry_counts = Vec::new();
-          for gc in g.group_choices.iter() {
-            let count = entry_counts_from_group_choice(self.cddl, gc);
-            entry_counts.push(count);
-          }
-          let len = o.len();
-          if let Token::EQ = t {
-            if !validate_entry_count(&entry_counts, len) {
-              for ec in entry_counts.iter() {
-                if let Some(occur) = &ec.entry_occurrence {
-                  self.add_error(format!(
-                    "map equality error. expected object with number of entries per occurrence {}",
-                    occur,
-                  ));
-                } else {
-                  self.add_error(format!(
-                    "map equality error, expected object with length {}, got {}",
-                    ec.count, len
-                  ));
-                }
-              }
-              return Ok(());
-            }
-          } else if let Token::NE | Token::DEFAULT = t {
-            if !validate_entry_count(&entry_counts, len) {
-              for ec in entry_counts.iter() {
-                if let Some(occur) = &ec.entry_occurrence {
-                  self.add_error(format!(
-                    "map inequality error. expected object with number of entries not per occurrence {}",
-                    occur,
-                  ));
-                } else {
-                  self.add_error(format!(
-                    "map inequality error, expected object not with length {}, got {}",
-                    ec.count, len
-                  ));
-                }
-              }
-              return Ok(());
-            }
-          }
-        }
-      }
+    if let Some(labels) = self.rule_labels.and_then(|l| l.get(&ident.to_string())) {
+      self.labeled_paths.push(LabeledPath {
+        location: self.json_location.clone(),
+        rule: ident.to_string(),
+        labels: labels.clone(),
+      });
     }
+  }
+}
 
-    self.is_ctrl_map_equality = false;
+impl<'a> JSONValidator<'a> {
+  // Validator for a value nested in the one being validated, e.g. an array
+  // item, or for the same value against another rule, inheriting the settings
+  // of this validator
+  fn nested_validator(&self, json: Value) -> Box<JSONValidator<'a>> {
+    #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
+    let mut jv = JSONValidator::new(self.cddl, json, self.enabled_features.clone());
+    #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
+    let mut jv = JSONValidator::new(self.cddl, json, self.enabled_features);
+    #[cfg(not(feature = "additional-controls"))]
+    let mut jv = JSONValidator::new(self.cddl, json);
+
+    jv.generic_rules = self.generic_rules.clone();
+    jv.rule_labels = self.rule_labels;
+    jv.cddl_location = self.cddl_location.clone();
+    jv.explain_choices = self.explain_choices;
+    jv.overflow_behavior = self.overflow_behavior;
+    jv.regex_engine = self.regex_engine;
+    jv.budget = self.budget.clone();
+    #[cfg(feature = "additional-controls")]
+    {
+      jv.disabled_feature_behavior = self.disabled_feature_behavior;
+    }
+    #[cfg(feature = "control-plugins")]
+    {
+      jv.control_plugins = self.control_plugins;
+    }
+    jv.extended_prelude = self.extended_prelude;
+    jv.compiled = self.compiled;
+    jv.eval_generic_rule = self.eval_generic_rule.clone();
 
-    let initial_error_count = self.errors.len();
-    for group_choice in g.group_choices.iter() {
-      let error_count = self.errors.len();
-      self.visit_group_choice(group_choice)?;
-      if self.errors.len() == error_count {
-        // Disregard invalid group choice validation errors if one of the
-        // choices validates successfully
-        let group_choice_error_count = self.errors.len() - initial_error_count;
-        if group_choice_error_count > 0 {
-          for _ in 0..group_choice_error_count {
-            self.errors.pop();
-          }
-        }
+    Box::new(jv)
+  }
 
-        return Ok(());
+  // Validator for a key or value of the object being validated, inheriting
+  // the settings and state of this validator
+  fn child_validator(&self, json: Value) -> Box<JSONValidator<'a>> {
+    let mut jv = self.nested_validator(json);
+
+    jv.is_multi_type_choice = self.is_multi_type_choice;
+    jv.is_multi_group_choice = self.is_multi_group_choice;
+    jv.type_group_name_entry = self.type_group_name_entry.clone();
+
+    jv
+  }
+
+  // Validate the entries of an object matching a member key with a repeated
+  // occurrence, e.g. `* tstr .regexp "^x-" => any`. Each key not matched by a
+  // preceding entry is checked against the key type, so that only keys of
+  // that type are accepted and the others are left to the entries that follow
+  // or reported as unexpected
+  fn validate_repeated_object_entries(
+    &mut self,
+    entry: &ValueMemberKeyEntry<'a>,
+    key_type: &Type1<'a>,
+    o: &[(String, Value)],
+    lower: usize,
+    upper: Option<usize>,
+  ) -> visitor::Result<Error> {
+    let current_location = self.json_location.clone();
+    let mut matched = 0;
+
+    for (k, v) in o.iter() {
+      if upper.is_some_and(|upper| matched >= upper)
+        || self
+          .validated_keys
+          .as_ref()
+          .is_some_and(|keys| keys.contains(k))
+      {
+        continue;
+      }
+
+      let mut jv = self.child_validator(Value::String(k.clone()));
+      jv.visit_type1(key_type)?;
+      if !jv.errors.is_empty() {
+        continue;
+      }
+      self.feature_uses.append(&mut jv.feature_uses);
+
+      matched += 1;
+      self
+        .validated_keys
+        .get_or_insert_with(Vec::new)
+        .push(k.clone());
+
+      let mut jv = self.child_validator(v.clone());
+      jv.json_location = format!("{}/{}", current_location, k);
+      jv.visit_type(&entry.entry_type)?;
+
+      if jv.errors.is_empty() {
+        self.labeled_paths.append(&mut jv.labeled_paths);
+        self.choice_explanations.append(&mut jv.choice_explanations);
+        self.feature_uses.append(&mut jv.feature_uses);
       }
+
+      self.errors.append(&mut jv.errors);
+    }
+
+    if matched < lower {
+      self.add_error(format!(
+        "expected at least {} object entries with key type {}, got {}",
+        lower, key_type, matched
+      ));
     }
 
+    self.occurrence = None;
+
     Ok(())
   }
 
-  fn visit_group_choice(&mut self, gc: &GroupChoice<'a>) -> visitor::Result<Error> {
-    if self.is_group_to_choice_enum {
-      let initial_error_count = self.errors.len();
-      for tc in type_choices_from_group_choice(self.cddl, gc).iter() {
-        let error_count = self.errors.len();
-        self.visit_type_choice(tc)?;
-        if self.errors.len() == error_count {
-          let type_choice_error_count = self.errors.len() - initial_error_count;
-          if type_choice_error_count > 0 {
-            for _ in 0..type_choice_error_count {
-              self.errors.pop();
-            }
-          }
-          return Ok(());
-        }
+  fn validate_root_rule(
+    &mut self,
+    name: &'a str,
+    generic_args: Option<GenericArgs<'a>>,
+  ) -> visitor::Result<Error> {
+    let cddl = self.cddl;
+    let rule = match cddl.rules.iter().find(|r| r.name() == name) {
+      Some(rule) => rule,
+      None => return Err(Error::MissingRule(name.to_string())),
+    };
+
+    let tr = match rule {
+      Rule::Type { rule, .. } => rule,
+      Rule::Group { .. } => {
+        self.add_error(format!(
+          "rule {} is a group rule and cannot be used as the root of a JSON document",
+          name
+        ));
+        return Ok(());
       }
+    };
 
+    let param_count = tr.generic_params.as_ref().map_or(0, |gp| gp.params.len());
+    let arg_count = generic_args.as_ref().map_or(0, |ga| ga.args.len());
+    if param_count != arg_count {
+      self.add_error(format!(
+        "rule {} expects {} generic argument(s), got {}",
+        name, param_count, arg_count
+      ));
       return Ok(());
     }
 
-    for (idx, ge) in gc.group_entries.iter().enumerate() {
-      self.group_entry_idx = Some(idx);
-
-      self.visit_group_entry(&ge.0)?;
+    self.is_root = true;
+    if let Some(ga) = generic_args {
+      self.visit_type2(&Type2::Typename {
+        ident: tr.name.clone(),
+        generic_args: Some(ga),
+        #[cfg(feature = "ast-span")]
+        span: tr.name.span,
+      })?;
+    } else {
+      self.visit_type_rule(tr)?;
     }
+    self.is_root = false;
+    self.collect_labels(&tr.name, 0, 0);
 
     Ok(())
   }
 
-  fn visit_range(
-    &mut self,
-    lower: &Type2,
-    upper: &Type2,
-    is_inclusive: bool,
-  ) -> visitor::Result<Error> {
-    if let Value::Array(a) = &self.json {
-      match validate_array_occurrence(
-        self.occurrence.as_ref().take(),
-        self.entry_counts.as_ref().map(|ec| &ec[..]),
-        a,
-      ) {
-        Ok((iter_items, allow_empty_array)) => {
-          if iter_items {
-            for (idx, v) in a.iter().enumerate() {
-              if let Some(indices) = &self.valid_array_items {
-                if self.is_multi_type_choice && indices.contains(&idx) {
-                  continue;
+  // Validate the value against a map type
+  fn validate_map(&mut self, t2: &Type2<'a>, group: &Group<'a>) -> visitor::Result<Error> {
+    match &self.json {
+      Value::Object(o) => {
+        #[allow(clippy::needless_collect)]
+        let o = o.keys().cloned().collect::<Vec<_>>();
+
+        self.visit_group(group)?;
+
+        let mut unmatched_key_errors = std::mem::take(&mut self.unmatched_key_errors);
+        if self.values_to_validate.is_none() {
+          let current_location = self.json_location.clone();
+          for k in o.into_iter() {
+            if self
+              .validated_keys
+              .as_ref()
+              .is_some_and(|keys| keys.contains(&k))
+            {
+              continue;
+            }
+
+            // Keys whose value failed to match an optional member key are
+            // reported with the errors of the value
+            if let Some(idx) = unmatched_key_errors.iter().position(|(key, _)| *key == k) {
+              self.errors.append(&mut unmatched_key_errors.remove(idx).1);
+              continue;
+            }
+
+            self.json_location = format!("{}/{}", current_location, k);
+            self.add_error(format!("unexpected key {:?}", k));
+          }
+          self.json_location = current_location;
+        }
+
+        self.is_cut_present = false;
+        self.cut_value = None;
+        Ok(())
+      }
+      Value::Array(a) => {
+        // Member keys are annotation only in an array context
+        if self.is_member_key {
+          return Ok(());
+        }
+
+        match validate_array_occurrence(
+          self.occurrence.as_ref().take(),
+          self.entry_counts.as_ref().map(|ec| &ec[..]),
+          a,
+        ) {
+          Ok((iter_items, allow_empty_array)) => {
+            if iter_items {
+              for (idx, v) in a.iter().enumerate() {
+                if let Some(indices) = &self.valid_array_items {
+                  if self.is_multi_type_choice && indices.contains(&idx) {
+                    continue;
+                  }
                 }
-              }
 
-              #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-              let mut jv = JSONValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-              #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-              let mut jv = JSONValidator::new(self.cddl, v.clone(), self.enabled_features);
-              #[cfg(not(feature = "additional-controls"))]
-              let mut jv = JSONValidator::new(self.cddl, v.clone());
+                let mut jv = self.nested_validator(v.clone());
 
-              jv.generic_rules = self.generic_rules.clone();
-              jv.eval_generic_rule = self.eval_generic_rule;
-              jv.ctrl = self.ctrl.clone();
-              jv.is_multi_type_choice = self.is_multi_type_choice;
-              jv.json_location
-                .push_str(&format!("{}/{}", self.json_location, idx));
+                jv.ctrl = self.ctrl.clone();
+                jv.is_multi_type_choice = self.is_multi_type_choice;
+                jv.json_location
+                  .push_str(&format!("{}/{}", self.json_location, idx));
 
-              jv.visit_range(lower, upper, is_inclusive)?;
+                jv.visit_group(group)?;
 
-              if self.is_multi_type_choice && jv.errors.is_empty() {
-                if let Some(indices) = &mut self.valid_array_items {
-                  indices.push(idx);
-                } else {
-                  self.valid_array_items = Some(vec![idx]);
+                if jv.errors.is_empty() {
+                  self.labeled_paths.append(&mut jv.labeled_paths);
+                  self.choice_explanations.append(&mut jv.choice_explanations);
+                  self.feature_uses.append(&mut jv.feature_uses);
                 }
-                continue;
-              }
 
-              if let Some(errors) = &mut self.array_errors {
-                if let Some(error) = errors.get_mut(&idx) {
-                  error.append(&mut jv.errors);
+                if self.is_multi_type_choice && jv.errors.is_empty() {
+                  if let Some(indices) = &mut self.valid_array_items {
+                    indices.push(idx);
+                  } else {
+                    self.valid_array_items = Some(vec![idx]);
+                  }
+                  continue;
+                }
+
+                if let Some(errors) = &mut self.array_errors {
+                  if let Some(error) = errors.get_mut(&idx) {
+                    error.append(&mut jv.errors);
+                  } else {
+                    errors.insert(idx, jv.errors);
+                  }
                 } else {
+                  let mut errors = HashMap::new();
                   errors.insert(idx, jv.errors);
+                  self.array_errors = Some(errors)
                 }
-              } else {
-                let mut errors = HashMap::new();
-                errors.insert(idx, jv.errors);
-                self.array_errors = Some(errors)
               }
-            }
-          } else if let Some(idx) = self.group_entry_idx.take() {
-            if let Some(v) = a.get(idx) {
-              #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-              let mut jv = JSONValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-              #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-              let mut jv = JSONValidator::new(self.cddl, v.clone(), self.enabled_features);
-              #[cfg(not(feature = "additional-controls"))]
-              let mut jv = JSONValidator::new(self.cddl, v.clone());
-
-              jv.generic_rules = self.generic_rules.clone();
-              jv.eval_generic_rule = self.eval_generic_rule;
-              jv.ctrl = self.ctrl.clone();
-              jv.is_multi_type_choice = self.is_multi_type_choice;
-              jv.json_location
-                .push_str(&format!("{}/{}", self.json_location, idx));
+            } else if let Some(idx) = self.group_entry_idx.take() {
+              if let Some(v) = a.get(idx) {
+                let mut jv = self.nested_validator(v.clone());
 
-              jv.visit_range(lower, upper, is_inclusive)?;
+                jv.ctrl = self.ctrl.clone();
+                jv.is_multi_type_choice = self.is_multi_type_choice;
+                jv.json_location
+                  .push_str(&format!("{}/{}", self.json_location, idx));
 
-              self.errors.append(&mut jv.errors);
-            } else if !allow_empty_array {
-              self.add_error(format!("expected array item at index {}", idx));
+                jv.visit_group(group)?;
+
+                if jv.errors.is_empty() {
+                  self.labeled_paths.append(&mut jv.labeled_paths);
+                  self.choice_explanations.append(&mut jv.choice_explanations);
+                  self.feature_uses.append(&mut jv.feature_uses);
+                }
+
+                self.errors.append(&mut jv.errors);
+              } else if !allow_empty_array {
+                self.add_error(format!("expected map object {} at index {}", group, idx));
+              }
+            } else {
+              self.add_error(format!("expected map object {}, got {}", group, self.json));
             }
-          } else {
-            self.add_error(format!(
-              "expected range lower {} upper {} inclusive {}, got {}",
-              lower, upper, is_inclusive, self.json
-            ));
           }
-        }
-        Err(errors) => {
-          for e in errors.into_iter() {
-            self.add_error(e);
+          Err(errors) => {
+            for e in errors.into_iter() {
+              self.add_error(e);
+            }
           }
         }
-      }
 
-      return Ok(());
+        Ok(())
+      }
+      _ => {
+        self.add_error(format!("expected map object {}, got {}", t2, self.json));
+        Ok(())
+      }
     }
+  }
 
-    match lower {
-      Type2::IntValue { value: l, .. } => match upper {
-        Type2::IntValue { value: u, .. } => {
-          let error_str = if is_inclusive {
-            format!(
-              "expected integer to be in range {} <= value <= {}, got {}",
-              l, u, self.json
-            )
-          } else {
-            format!(
-              "expected integer to be in range {} < value < {}, got {}",
-              l, u, self.json
-            )
-          };
+  // Validate the value against an array type
+  fn validate_array(&mut self, group: &Group<'a>) -> visitor::Result<Error> {
+    match &self.json {
+      Value::Array(a) => {
+        if group.group_choices.len() == 1
+          && group.group_choices[0].group_entries.is_empty()
+          && !a.is_empty()
+          && !matches!(self.ctrl, Some(Token::NE) | Some(Token::DEFAULT))
+        {
+          self.add_error(format!("expected empty array, got {}", self.json));
+          return Ok(());
+        }
 
-          match &self.json {
-            Value::Number(n) => {
-              if let Some(i) = n.as_i64() {
-                if is_inclusive {
-                  if i < *l as i64 || i > *u as i64 {
-                    self.add_error(error_str);
-                  } else {
-                    return Ok(());
-                  }
-                } else if i <= *l as i64 || i >= *u as i64 {
-                  self.add_error(error_str);
-                  return Ok(());
-                } else {
-                  return Ok(());
-                }
-              } else {
-                self.add_error(error_str);
-                return Ok(());
-              }
-            }
-            _ => {
-              self.add_error(error_str);
-              return Ok(());
-            }
-          }
+        let mut entry_counts = Vec::new();
+        for gc in group.group_choices.iter() {
+          let count = entry_counts_from_group_choice(self.cddl, gc);
+          entry_counts.push(count);
         }
-        Type2::UintValue { value: u, .. } => {
-          let error_str = if is_inclusive {
-            format!(
-              "expected integer to be in range {} <= value <= {}, got {}",
-              l, u, self.json
-            )
-          } else {
-            format!(
-              "expected integer to be in range {} < value < {}, got {}",
-              l, u, self.json
-            )
-          };
 
-          match &self.json {
-            Value::Number(n) => {
-              if let Some(i) = n.as_i64() {
-                if is_inclusive {
-                  if i < *l as i64 || i > *u as i64 {
-                    self.add_error(error_str);
-                  } else {
-                    return Ok(());
-                  }
-                } else if i <= *l as i64 || i >= *u as i64 {
-                  self.add_error(error_str);
-                  return Ok(());
-                } else {
-                  return Ok(());
-                }
-              } else {
-                self.add_error(error_str);
-                return Ok(());
-              }
-            }
-            _ => {
-              self.add_error(error_str);
-              return Ok(());
+        self.entry_counts = Some(entry_counts);
+        self.visit_group(group)?;
+        self.entry_counts = None;
+
+        if let Some(errors) = &mut self.array_errors {
+          if let Some(indices) = &self.valid_array_items {
+            for idx in indices.iter() {
+              errors.remove(idx);
             }
           }
+
+          for error in errors.values_mut() {
+            self.errors.append(error);
+          }
         }
-        _ => {
-          self.add_error(format!(
-            "invalid cddl range. upper value must be an integer type. got {}",
-            upper
-          ));
-          return Ok(());
-        }
-      },
-      Type2::UintValue { value: l, .. } => match upper {
-        Type2::UintValue { value: u, .. } => {
-          let error_str = if is_inclusive {
-            format!(
-              "expected uint to be in range {} <= value <= {}, got {}",
-              l, u, self.json
-            )
-          } else {
-            format!(
-              "expected uint to be in range {} < value < {}, got {}",
-              l, u, self.json
-            )
-          };
 
-          match &self.json {
-            Value::Number(n) => {
-              if let Some(i) = n.as_u64() {
-                if is_inclusive {
-                  if i < *l as u64 || i > *u as u64 {
-                    self.add_error(error_str);
-                  } else {
-                    return Ok(());
-                  }
-                } else if i <= *l as u64 || i >= *u as u64 {
-                  self.add_error(error_str);
-                  return Ok(());
-                } else {
-                  return Ok(());
-                }
-              } else {
-                self.add_error(error_str);
-                return Ok(());
-              }
-            }
-            Value::String(s) => match self.ctrl {
-              Some(Token::SIZE) => {
-                let len = s.len();
-                let s = s.clone();
-                if is_inclusive {
-                  if s.len() < *l || s.len() > *u {
-                    self.add_error(format!(
-                      "expected \"{}\" string length to be in the range {} <= value <= {}, got {}",
-                      s, l, u, len
-                    ));
-                  }
+        self.valid_array_items = None;
+        self.array_errors = None;
 
-                  return Ok(());
-                } else if s.len() <= *l || s.len() >= *u {
-                  self.add_error(format!(
-                    "expected \"{}\" string length to be in the range {} < value < {}, got {}",
-                    s, l, u, len
-                  ));
-                  return Ok(());
-                }
-              }
-              _ => {
-                self.add_error("string value cannot be validated against a range without the .size control operator".to_string());
-                return Ok(());
-              }
-            },
-            _ => {
-              self.add_error(error_str);
-              return Ok(());
-            }
+        Ok(())
+      }
+      _ => {
+        self.add_error(format!("expected array type, got {}", self.json));
+        Ok(())
+      }
+    }
+  }
+
+  // Validate the value against a choice from the entries of a group rule
+  fn validate_choice_from_group(
+    &mut self,
+    ident: &Identifier<'a>,
+    generic_args: &Option<GenericArgs<'a>>,
+  ) -> visitor::Result<Error> {
+    if let Some(ga) = generic_args {
+      if let Some(rule) = self.lookup_rule(ident) {
+        if let Some(gr) = self
+          .generic_rules
+          .iter_mut()
+          .find(|gr| gr.name == ident.ident)
+        {
+          for arg in ga.args.iter() {
+            gr.args.push((*arg.arg).clone());
           }
+        } else if let Some(params) = generic_params_from_rule(rule) {
+          self.generic_rules.push(GenericRule {
+            name: ident.ident.clone(),
+            params,
+            args: ga.args.iter().cloned().map(|arg| *arg.arg).collect(),
+          });
         }
-        _ => {
-          self.add_error(format!(
-            "invalid cddl range. upper value must be a uint type. got {}",
-            upper
-          ));
-          return Ok(());
+
+        let mut jv = self.nested_validator(self.json.clone());
+
+        jv.eval_generic_rule = Some(ident.ident.clone());
+        jv.is_group_to_choice_enum = true;
+        jv.is_multi_type_choice = self.is_multi_type_choice;
+        jv.visit_rule(rule)?;
+
+        if jv.errors.is_empty() {
+          self.labeled_paths.append(&mut jv.labeled_paths);
+          self.choice_explanations.append(&mut jv.choice_explanations);
+          self.feature_uses.append(&mut jv.feature_uses);
         }
-      },
-      Type2::FloatValue { value: l, .. } => match upper {
-        Type2::FloatValue { value: u, .. } => {
-          let error_str = if is_inclusive {
-            format!(
-              "expected float to be in range {} <= value <= {}, got {}",
-              l, u, self.json
-            )
-          } else {
-            format!(
-              "expected float to be in range {} < value < {}, got {}",
-              l, u, self.json
-            )
-          };
 
-          match &self.json {
-            Value::Number(n) => {
-              if let Some(f) = n.as_f64() {
-                if is_inclusive {
-                  if f < *l as f64 || f > *u as f64 {
-                    self.add_error(error_str);
-                  } else {
-                    return Ok(());
-                  }
-                } else if f <= *l as f64 || f >= *u as f64 {
-                  self.add_error(error_str);
-                  return Ok(());
-                } else {
-                  return Ok(());
-                }
-              } else {
-                self.add_error(error_str);
-                return Ok(());
-              }
-            }
-            _ => {
-              self.add_error(error_str);
-              return Ok(());
-            }
+        self.errors.append(&mut jv.errors);
+
+        return Ok(());
+      }
+    }
+
+    if group_rule_from_ident(self.cddl, ident).is_none() {
+      self.add_error(format!(
+        "rule {} must be a group rule to turn it into a choice",
+        ident
+      ));
+      return Ok(());
+    }
+
+    self.is_group_to_choice_enum = true;
+    self.visit_identifier(ident)?;
+    self.is_group_to_choice_enum = false;
+
+    Ok(())
+  }
+
+  // Validate the value against a type name, instantiating a generic rule
+  // with its arguments
+  fn validate_typename(
+    &mut self,
+    ident: &Identifier<'a>,
+    generic_args: &Option<GenericArgs<'a>>,
+  ) -> visitor::Result<Error> {
+    if let Some(ga) = generic_args {
+      if let Some(rule) = self.lookup_rule(ident) {
+        if let Some(gr) = self
+          .generic_rules
+          .iter_mut()
+          .find(|gr| gr.name == ident.ident)
+        {
+          for arg in ga.args.iter() {
+            gr.args.push((*arg.arg).clone());
           }
+        } else if let Some(params) = generic_params_from_rule(rule) {
+          self.generic_rules.push(GenericRule {
+            name: ident.ident.clone(),
+            params,
+            args: ga.args.iter().cloned().map(|arg| *arg.arg).collect(),
+          });
         }
-        _ => {
-          self.add_error(format!(
-            "invalid cddl range. upper value must be a float type. got {}",
-            upper
-          ));
-          return Ok(());
+
+        let mut jv = self.nested_validator(self.json.clone());
+
+        jv.eval_generic_rule = Some(ident.ident.clone());
+        jv.is_multi_type_choice = self.is_multi_type_choice;
+        jv.visit_rule(rule)?;
+
+        if jv.errors.is_empty() {
+          self.labeled_paths.append(&mut jv.labeled_paths);
+          self.choice_explanations.append(&mut jv.choice_explanations);
+          self.feature_uses.append(&mut jv.feature_uses);
         }
-      },
-      _ => {
-        self.add_error(
-          "invalid cddl range. upper and lower values must be either integers or floats"
-            .to_string(),
-        );
+
+        self.errors.append(&mut jv.errors);
 
         return Ok(());
       }
     }
 
-    Ok(())
+    self.visit_identifier(ident)
   }
 
-  fn visit_control_operator(
+  // Validate the value against an unwrapped type
+  fn validate_unwrap(
     &mut self,
-    target: &Type2<'a>,
-    ctrl: &str,
-    controller: &Type2<'a>,
+    ident: &Identifier<'a>,
+    generic_args: &Option<GenericArgs<'a>>,
   ) -> visitor::Result<Error> {
-    if let Type2::Typename {
-      ident: target_ident,
-      ..
-    } = target
-    {
-      if let Type2::Typename {
-        ident: controller_ident,
-        ..
-      } = controller
-      {
-        if let Some(name) = self.eval_generic_rule {
-          if let Some(gr) = self
-            .generic_rules
-            .iter()
-            .cloned()
-            .find(|gr| gr.name == name)
-          {
-            for (idx, gp) in gr.params.iter().enumerate() {
-              if let Some(arg) = gr.args.get(idx) {
-                if *gp == target_ident.ident {
-                  let t2 = Type2::from(arg.clone());
-
-                  if *gp == controller_ident.ident {
-                    return self.visit_control_operator(&t2, ctrl, &t2);
-                  }
-
-                  return self.visit_control_operator(&arg.type2, ctrl, controller);
-                }
-              }
-            }
-          }
-        }
-      }
+    // Per
+    // https://github.com/w3c/did-spec-registries/pull/138#issuecomment-719739215,
+    // strip tag and validate underlying type
+    if let Some(Type2::TaggedData { t, .. }) = tag_from_token(&lookup_ident(&ident.ident)) {
+      return self.visit_type(&t);
+    }
 
-      if let Some(name) = self.eval_generic_rule {
+    if let Some(ga) = generic_args {
+      if let Some(rule) = unwrap_rule_from_ident(self.cddl, ident) {
         if let Some(gr) = self
           .generic_rules
-          .iter()
-          .cloned()
-          .find(|gr| gr.name == name)
+          .iter_mut()
+          .find(|gr| gr.name == ident.ident)
         {
-          for (idx, gp) in gr.params.iter().enumerate() {
-            if let Some(arg) = gr.args.get(idx) {
-              if *gp == target_ident.ident {
-                let t2 = Type2::from(arg.clone());
-                return self.visit_control_operator(&t2, ctrl, controller);
-              }
-            }
+          for arg in ga.args.iter() {
+            gr.args.push((*arg.arg).clone());
           }
+        } else if let Some(params) = generic_params_from_rule(rule) {
+          self.generic_rules.push(GenericRule {
+            name: ident.ident.clone(),
+            params,
+            args: ga.args.iter().cloned().map(|arg| *arg.arg).collect(),
+          });
+        }
+
+        let mut jv = self.nested_validator(self.json.clone());
+
+        jv.eval_generic_rule = Some(ident.ident.clone());
+        jv.is_multi_type_choice = self.is_multi_type_choice;
+        jv.visit_rule(rule)?;
+
+        if jv.errors.is_empty() {
+          self.labeled_paths.append(&mut jv.labeled_paths);
+          self.choice_explanations.append(&mut jv.choice_explanations);
+          self.feature_uses.append(&mut jv.feature_uses);
         }
+
+        self.errors.append(&mut jv.errors);
+
+        return Ok(());
       }
     }
 
-    match lookup_control_from_str(ctrl) {
-      t @ Some(Token::EQ) => match target {
-        Type2::Typename { ident, .. } => {
-          if is_ident_string_data_type(self.cddl, ident)
-            || is_ident_numeric_data_type(self.cddl, ident)
-          {
-            return self.visit_type2(controller);
-          }
-        }
-        Type2::Array { group, .. } => {
-          if let Value::Array(_) = &self.json {
-            let mut entry_counts = Vec::new();
-            for gc in group.group_choices.iter() {
-              let count = entry_counts_from_group_choice(self.cddl, gc);
-              entry_counts.push(count);
-            }
-            self.entry_counts = Some(entry_counts);
-            self.visit_type2(controller)?;
-            self.entry_counts = None;
-            return Ok(());
-          }
-        }
-        Type2::Map { .. } => {
-          if let Value::Object(_) = &self.json {
-            self.ctrl = t;
-            self.is_ctrl_map_equality = true;
-            self.visit_type2(controller)?;
-            self.ctrl = None;
-            self.is_ctrl_map_equality = false;
-            return Ok(());
-          }
+    if let Some(rule) = unwrap_rule_from_ident(self.cddl, ident) {
+      return self.visit_rule(rule);
+    }
+
+    self.add_error(format!(
+      "cannot unwrap identifier {}, rule not found",
+      ident
+    ));
+
+    Ok(())
+  }
+
+  // Validate the value against a type of the standard prelude, or a value
+  // the identifier is bound to, the identifier not naming a rule
+  fn validate_prelude_type(&mut self, ident: &Identifier<'a>) -> visitor::Result<Error> {
+    match &self.json {
+      Value::Null if is_ident_null_data_type(self.cddl, ident) => Ok(()),
+      Value::Bool(b) => {
+        if is_ident_bool_data_type(self.cddl, ident) {
+          return Ok(());
         }
-        _ => self.add_error(format!(
-          "target for .eq operator must be a string, numerical, array or map data type, got {}",
-          target
-        )),
-      },
-      t @ Some(Token::NE) => match target {
-        Type2::Typename { ident, .. } => {
-          if is_ident_string_data_type(self.cddl, ident)
-            || is_ident_numeric_data_type(self.cddl, ident)
-          {
-            self.ctrl = t;
-            self.visit_type2(controller)?;
-            self.ctrl = None;
-            return Ok(());
-          }
+
+        if ident_matches_bool_value(self.cddl, ident, *b) {
+          return Ok(());
         }
-        Type2::Array { .. } => {
-          if let Value::Array(_) = &self.json {
-            self.ctrl = t;
-            self.visit_type2(controller)?;
-            self.ctrl = None;
-            return Ok(());
+
+        self.add_error(format!("expected type {}, got {}", ident, self.json));
+        Ok(())
+      }
+      Value::Number(n) => {
+        if is_ident_uint_data_type(self.cddl, ident) && n.is_u64() {
+          return Ok(());
+        } else if is_ident_nint_data_type(self.cddl, ident) {
+          if let Some(n) = n.as_i64() {
+            if n.is_negative() {
+              return Ok(());
+            }
           }
-        }
-        Type2::Map { .. } => {
-          if let Value::Object(_) = &self.json {
-            self.ctrl = t;
-            self.is_ctrl_map_equality = true;
-            self.visit_type2(controller)?;
-            self.ctrl = None;
-            self.is_ctrl_map_equality = false;
+        } else if is_ident_time_data_type(self.cddl, ident) {
+          if let Some(n) = n.as_i64() {
+            if let chrono::LocalResult::None = Utc.timestamp_millis_opt(n * 1000) {
+              self.add_error(format!(
+                "expected time data type, invalid UNIX timestamp {}",
+                n,
+              ));
+            }
+
             return Ok(());
+          } else if let Some(n) = n.as_f64() {
+            // truncates fractional milliseconds when validating
+            if let chrono::LocalResult::None = Utc.timestamp_millis_opt((n * 1000f64) as i64) {
+              self.add_error(format!(
+                "expected time data type, invalid UNIX timestamp {}",
+                n,
+              ));
+            }
           }
+        } else if (is_ident_integer_data_type(self.cddl, ident) && n.is_i64())
+          || (is_ident_float_data_type(self.cddl, ident) && n.is_f64())
+        {
+          return Ok(());
         }
-        _ => self.add_error(format!(
-          "target for .ne operator must be a string, numerical, array or map data type, got {}",
-          target
-        )),
-      },
-      t @ Some(Token::LT) | t @ Some(Token::GT) | t @ Some(Token::GE) | t @ Some(Token::LE) => {
-        match target {
-          Type2::Typename { ident, .. } if is_ident_numeric_data_type(self.cddl, ident) => {
-            self.ctrl = t;
-            self.visit_type2(controller)?;
-            self.ctrl = None;
+
+        self.add_error(format!("expected type {}, got {}", ident, self.json));
+        Ok(())
+      }
+      Value::String(s) => {
+        if is_ident_uri_data_type(self.cddl, ident) {
+          if let Err(e) = uriparse::URI::try_from(&**s) {
+            self.add_error(format!("expected URI data type, decoding error: {}", e));
           }
-          _ => {
+        } else if is_ident_b64url_data_type(self.cddl, ident) {
+          if let Err(e) = base64_url::decode(s) {
             self.add_error(format!(
-              "target for .lt, .gt, .ge or .le operator must be a numerical data type, got {}",
-              target
+              "expected base64 URL data type, decoding error: {}",
+              e
             ));
           }
+        } else if is_ident_tdate_data_type(self.cddl, ident) {
+          if let Err(e) = chrono::DateTime::parse_from_rfc3339(s) {
+            self.add_error(format!("expected tdate data type, decoding error: {}", e));
+          }
+        } else if is_ident_string_data_type(self.cddl, ident) {
+          return Ok(());
+        } else {
+          self.add_error(format!("expected type {}, got {}", ident, self.json));
         }
+
+        Ok(())
       }
-      t @ Some(Token::SIZE) => match target {
-        Type2::Typename { ident, .. }
-          if is_ident_string_data_type(self.cddl, ident)
-            || is_ident_uint_data_type(self.cddl, ident) =>
-        {
-          self.ctrl = t;
-          self.visit_type2(controller)?;
-          self.ctrl = None;
-        }
-        _ => {
-          self.add_error(format!(
-            "target for .size must a string or uint data type, got {}",
-            target
-          ));
+      Value::Array(a) => {
+        // Member keys are annotation only in an array context
+        if self.is_member_key {
+          return Ok(());
         }
-      },
-      t @ Some(Token::AND) => {
-        self.ctrl = t;
-        self.visit_type2(target)?;
-        self.visit_type2(controller)?;
-        self.ctrl = None;
-      }
-      t @ Some(Token::WITHIN) => {
-        self.ctrl = t;
-        let error_count = self.errors.len();
-        self.visit_type2(target)?;
-        let no_errors = self.errors.len() == error_count;
-        self.visit_type2(controller)?;
-        if no_errors && self.errors.len() > error_count {
-          for _ in 0..self.errors.len() - error_count {
-            self.errors.pop();
-          }
 
-          self.add_error(format!(
-            "expected type {} .within type {}, got {}",
-            target, controller, self.json,
-          ));
-        }
+        match validate_array_occurrence(
+          self.occurrence.as_ref().take(),
+          self.entry_counts.as_ref().map(|ec| &ec[..]),
+          a,
+        ) {
+          Ok((iter_items, allow_empty_array)) => {
+            if iter_items {
+              for (idx, v) in a.iter().enumerate() {
+                if let Some(indices) = &self.valid_array_items {
+                  if self.is_multi_type_choice && indices.contains(&idx) {
+                    continue;
+                  }
+                }
 
-        self.ctrl = None;
+                let mut jv = self.nested_validator(v.clone());
+
+                jv.is_multi_type_choice = self.is_multi_type_choice;
+                jv.ctrl = self.ctrl.clone();
+                jv.json_location
+                  .push_str(&format!("{}/{}", self.json_location, idx));
+
+                jv.visit_identifier(ident)?;
+
+                if jv.errors.is_empty() {
+                  self.labeled_paths.append(&mut jv.labeled_paths);
+                  self.choice_explanations.append(&mut jv.choice_explanations);
+                  self.feature_uses.append(&mut jv.feature_uses);
+                }
+
+                if self.is_multi_type_choice && jv.errors.is_empty() {
+                  if let Some(indices) = &mut self.valid_array_items {
+                    indices.push(idx);
+                  } else {
+                    self.valid_array_items = Some(vec![idx]);
+                  }
+                  continue;
+                }
+
+                if let Some(errors) = &mut self.array_errors {
+                  if let Some(error) = errors.get_mut(&idx) {
+                    error.append(&mut jv.errors);
+                  } else {
+                    errors.insert(idx, jv.errors);
+                  }
+                } else {
+                  let mut errors = HashMap::new();
+                  errors.insert(idx, jv.errors);
+                  self.array_errors = Some(errors)
+                }
+              }
+            } else if let Some(idx) = self.group_entry_idx.take() {
+              if let Some(v) = a.get(idx) {
+                let mut jv = self.nested_validator(v.clone());
+
+                jv.ctrl = self.ctrl.clone();
+                jv.is_multi_type_choice = self.is_multi_type_choice;
+                jv.json_location
+                  .push_str(&format!("{}/{}", self.json_location, idx));
+
+                jv.visit_identifier(ident)?;
+
+                if jv.errors.is_empty() {
+                  self.labeled_paths.append(&mut jv.labeled_paths);
+                  self.choice_explanations.append(&mut jv.choice_explanations);
+                  self.feature_uses.append(&mut jv.feature_uses);
+                }
+
+                self.errors.append(&mut jv.errors);
+              } else if !allow_empty_array {
+                self.add_error(format!("expected type {} at index {}", ident, idx));
+              }
+            } else {
+              self.add_error(format!("expected type {}, got {}", ident, self.json));
+            }
+          }
+          Err(errors) => {
+            for e in errors.into_iter() {
+              self.add_error(e);
+            }
+          }
+        }
+
+        Ok(())
       }
-      t @ Some(Token::DEFAULT) => {
-        self.ctrl = t;
-        let error_count = self.errors.len();
-        self.visit_type2(target)?;
-        if self.errors.len() != error_count {
+      Value::Object(o) => {
+        if let Some(occur) = &self.occurrence {
           #[cfg(feature = "ast-span")]
-          if let Some(Occur::Optional(_)) = self.occurrence.take() {
-            self.add_error(format!(
-              "expected default value {}, got {}",
-              controller, self.json
-            ));
+          if let Occur::ZeroOrMore(_) | Occur::OneOrMore(_) = occur {
+            if let Occur::OneOrMore(_) = occur {
+              if o.is_empty() {
+                self.add_error(format!(
+                  "object cannot be empty, one or more entries with key type {} required",
+                  ident
+                ));
+                return Ok(());
+              }
+            }
+
+            if is_ident_string_data_type(self.cddl, ident) {
+              let values_to_validate = o
+                .iter()
+                .filter_map(|(k, v)| match &self.validated_keys {
+                  Some(keys) if !keys.contains(k) => Some(v.clone()),
+                  Some(_) => None,
+                  None => Some(v.clone()),
+                })
+                .collect::<Vec<_>>();
+
+              self.values_to_validate = Some(values_to_validate);
+
+              return Ok(());
+            }
           }
+
           #[cfg(not(feature = "ast-span"))]
-          if let Some(Occur::Optional) = self.occurrence.take() {
-            self.add_error(format!(
-              "expected default value {}, got {}",
-              controller, self.json
-            ));
+          if let Occur::ZeroOrMore | Occur::OneOrMore = occur {
+            if let Occur::OneOrMore = occur {
+              if o.is_empty() {
+                self.add_error(format!(
+                  "object cannot be empty, one or more entries with key type {} required",
+                  ident
+                ));
+                return Ok(());
+              }
+            }
+
+            if is_ident_string_data_type(self.cddl, ident) {
+              let values_to_validate = o
+                .iter()
+                .filter_map(|(k, v)| match &self.validated_keys {
+                  Some(keys) if !keys.contains(k) => Some(v.clone()),
+                  Some(_) => None,
+                  None => Some(v.clone()),
+                })
+                .collect::<Vec<_>>();
+
+              self.values_to_validate = Some(values_to_validate);
+
+              return Ok(());
+            }
           }
         }
-        self.ctrl = None;
+
+        if token::lookup_ident(&ident.ident)
+          .in_standard_prelude()
+          .is_some()
+        {
+          self.add_error(format!(
+            "expecting object value of type {}, got object",
+            ident.ident
+          ));
+          return Ok(());
+        }
+
+        self.visit_value(&token::Value::TEXT(ident.ident.clone()))
       }
-      t @ Some(Token::REGEXP) | t @ Some(Token::PCRE) => {
-        self.ctrl = t;
-        match target {
-          Type2::Typename { ident, .. } if is_ident_string_data_type(self.cddl, ident) => {
-            match self.json {
-              Value::String(_) | Value::Array(_) => self.visit_type2(controller)?,
-              _ => self.add_error(format!(
-                ".regexp/.pcre control can only be matched against JSON string, got {}",
-                self.json
-              )),
-            }
-          }
-          _ => self.add_error(format!(
-            ".regexp/.pcre control can only be matched against string data type, got {}",
-            target
-          )),
+      _ => {
+        if let Some(cut_value) = self.cut_value.take() {
+          self.add_error(format!(
+            "cut present for member key {}. expected type {}, got {}",
+            cut_value, ident, self.json
+          ));
+        } else {
+          self.add_error(format!("expected type {}, got {}", ident, self.json));
         }
-        self.ctrl = None;
+        Ok(())
       }
-      #[cfg(feature = "additional-controls")]
-      t @ Some(Token::CAT) => {
-        self.ctrl = t;
-
-        match cat_operation(self.cddl, target, controller, false) {
-          Ok(values) => {
-            let error_count = self.errors.len();
+    }
+  }
+}
 
-            for v in values.iter() {
-              let cur_errors = self.errors.len();
+impl<'a> Validator<'a, Error> for JSONValidator<'a> {
+  /// Validate
+  fn validate(&mut self) -> std::result::Result<(), Error> {
+    if let Some((name, generic_args)) = self.root_rule.take() {
+      self.validate_root_rule(name, generic_args)?;
 
-              self.visit_type2(v)?;
+      if !self.errors.is_empty() {
+        return Err(Error::Validation(self.errors.clone()));
+      }
 
-              if self.errors.len() == cur_errors {
-                for _ in 0..self.errors.len() - error_count {
-                  self.errors.pop();
-                }
+      return Ok(());
+    }
 
-                break;
-              }
-            }
-          }
-          Err(e) => self.add_error(e),
+    for r in self.cddl.rules.iter() {
+      // First type rule is root
+      if let Rule::Type { rule, .. } = r {
+        if rule.generic_params.is_none() {
+          self.is_root = true;
+          self.visit_type_rule(rule)?;
+          self.is_root = false;
+          self.collect_labels(&rule.name, 0, 0);
+          break;
         }
+      }
+    }
 
-        self.ctrl = None;
+    if !self.errors.is_empty() {
+      return Err(Error::Validation(self.errors.clone()));
+    }
+
+    Ok(())
+  }
+
+  fn add_error(&mut self, reason: String) {
+    self.errors.push(ValidationError {
+      reason,
+      cddl_location: self.cddl_location.clone(),
+      json_location: self.json_location.clone(),
+      is_multi_type_choice: self.is_multi_type_choice,
+      is_multi_group_choice: self.is_multi_group_choice,
+      is_group_to_choice_enum: self.is_group_to_choice_enum,
+      type_group_name_entry: self.type_group_name_entry.as_ref().map(|e| e.to_string()),
+      #[cfg(feature = "ast-span")]
+      cddl_span: self.cddl_span,
+    });
+  }
+}
+
+impl<'a> Visitor<'a, Error> for JSONValidator<'a> {
+  fn visit_type_rule(&mut self, tr: &TypeRule<'a>) -> visitor::Result<Error> {
+    if let Some(gp) = &tr.generic_params {
+      if let Some(gr) = self
+        .generic_rules
+        .iter_mut()
+        .find(|r| r.name == tr.name.ident)
+      {
+        gr.params = gp.params.iter().map(|p| p.param.ident.clone()).collect();
+      } else {
+        self.generic_rules.push(GenericRule {
+          name: tr.name.ident.clone(),
+          params: gp.params.iter().map(|p| p.param.ident.clone()).collect(),
+          args: vec![],
+        });
       }
-      #[cfg(feature = "additional-controls")]
-      t @ Some(Token::DET) => {
-        self.ctrl = t;
+    }
 
-        match cat_operation(self.cddl, target, controller, true) {
-          Ok(values) => {
-            let error_count = self.errors.len();
+    self.budget.enter_rule().map_err(Error::LimitExceeded)?;
 
-            for v in values.iter() {
-              let cur_errors = self.errors.len();
+    // Errors are attributed to the innermost rule being validated
+    let cddl_location = std::mem::replace(&mut self.cddl_location, tr.name.to_string());
+    let error_count = self.errors.len();
 
-              self.visit_type2(v)?;
+    for t in type_choice_alternates_from_ident(self.cddl, &tr.name) {
+      let cur_errors = self.errors.len();
+      self.visit_type(t)?;
+      if self.errors.len() == cur_errors {
+        for _ in 0..self.errors.len() - error_count {
+          self.errors.pop();
+        }
 
-              if self.errors.len() == cur_errors {
-                for _ in 0..self.errors.len() - error_count {
-                  self.errors.pop();
+        break;
+      }
+    }
+
+    self.cddl_location = cddl_location;
+    self.budget.exit_rule();
+
+    Ok(())
+  }
+
+  fn visit_group_rule(&mut self, gr: &GroupRule<'a>) -> visitor::Result<Error> {
+    if let Some(gp) = &gr.generic_params {
+      if let Some(gr) = self
+        .generic_rules
+        .iter_mut()
+        .find(|r| r.name == gr.name.ident)
+      {
+        gr.params = gp.params.iter().map(|p| p.param.ident.clone()).collect();
+      } else {
+        self.generic_rules.push(GenericRule {
+          name: gr.name.ident.clone(),
+          params: gp.params.iter().map(|p| p.param.ident.clone()).collect(),
+          args: vec![],
+        });
+      }
+    }
+
+    self.budget.enter_rule().map_err(Error::LimitExceeded)?;
+
+    // Errors are attributed to the innermost rule being validated
+    let cddl_location = std::mem::replace(&mut self.cddl_location, gr.name.to_string());
+    let error_count = self.errors.len();
+
+    for ge in group_choice_alternates_from_ident(self.cddl, &gr.name) {
+      let cur_errors = self.errors.len();
+      self.visit_group_entry(ge)?;
+      if self.errors.len() == cur_errors {
+        for _ in 0..self.errors.len() - error_count {
+          self.errors.pop();
+        }
+
+        break;
+      }
+    }
+
+    self.cddl_location = cddl_location;
+    self.budget.exit_rule();
+
+    Ok(())
+  }
+
+  fn visit_type(&mut self, t: &Type<'a>) -> visitor::Result<Error> {
+    if t.type_choices.len() > 1 {
+      self.is_multi_type_choice = true;
+    }
+
+    let initial_error_count = self.errors.len();
+    let mut rejected = Some(Vec::new());
+    for (idx, type_choice) in t.type_choices.iter().enumerate() {
+      self.budget.step().map_err(Error::LimitExceeded)?;
+
+      // If validating an array whose elements are type choices (i.e. [ 1* tstr
+      // / integer ]), collect all errors and filter after the fact
+      if matches!(self.json, Value::Array(_)) {
+        let error_count = self.errors.len();
+        let explanation_count = self.choice_explanations.len();
+        let feature_use_count = self.feature_uses.len();
+
+        self.visit_type_choice(type_choice)?;
+        self.explain_choice(t, idx, error_count, explanation_count, &mut rejected);
+        if self.errors.len() > error_count {
+          self.feature_uses.truncate(feature_use_count);
+        }
+
+        if self.errors.len() == error_count {
+          // Disregard invalid type choice validation errors if one of the
+          // choices validates successfully
+          let type_choice_error_count = self.errors.len() - initial_error_count;
+          if type_choice_error_count > 0 {
+            for _ in 0..type_choice_error_count {
+              self.errors.pop();
+            }
+          }
+        }
+
+        continue;
+      }
+
+      let error_count = self.errors.len();
+      let explanation_count = self.choice_explanations.len();
+      let feature_use_count = self.feature_uses.len();
+      self.visit_type_choice(type_choice)?;
+      self.explain_choice(t, idx, error_count, explanation_count, &mut rejected);
+      if self.errors.len() > error_count {
+        self.feature_uses.truncate(feature_use_count);
+      }
+
+      if self.errors.len() == error_count {
+        // Disregard invalid type choice validation errors if one of the
+        // choices validates successfully
+        let type_choice_error_count = self.errors.len() - initial_error_count;
+        if type_choice_error_count > 0 {
+          for _ in 0..type_choice_error_count {
+            self.errors.pop();
+          }
+        }
+
+        return Ok(());
+      }
+    }
+
+    Ok(())
+  }
+
+  fn visit_group(&mut self, g: &Group<'a>) -> visitor::Result<Error> {
+    if g.group_choices.len() > 1 {
+      self.is_multi_group_choice = true;
+    }
+
+    // Map equality/inequality validation
+    if self.is_ctrl_map_equality {
+      if let Some(t) = &self.ctrl {
+        if let Value::Object(o) = &self.json {
+          let mut entry_counts = Vec::new();
+          for gc in g.group_choices.iter() {
+            let count = entry_counts_from_group_choice(self.cddl, gc);
+            entry_counts.push(count);
+          }
+          let len = o.len();
+          if let Token::EQ = t {
+            if !validate_entry_count(&entry_counts, len) {
+              for ec in entry_counts.iter() {
+                if let Some(occur) = &ec.entry_occurrence {
+                  self.add_error(format!(
+                    "map equality error. expected object with number of entries per occurrence {}",
+                    occur,
+                  ));
+                } else {
+                  self.add_error(format!(
+                    "map equality error, expected object with length {}, got {}",
+                    ec.count, len
+                  ));
+                }
+              }
+              return Ok(());
+            }
+          } else if let Token::NE | Token::DEFAULT = t {
+            if !validate_entry_count(&entry_counts, len) {
+              for ec in entry_counts.iter() {
+                if let Some(occur) = &ec.entry_occurrence {
+                  self.add_error(format!(
+                    "map inequality error. expected object with number of entries not per occurrence {}",
+                    occur,
+                  ));
+                } else {
+                  self.add_error(format!(
+                    "map inequality error, expected object not with length {}, got {}",
+                    ec.count, len
+                  ));
+                }
+              }
+              return Ok(());
+            }
+          }
+        }
+      }
+    }
+
+    self.is_ctrl_map_equality = false;
+
+    let initial_error_count = self.errors.len();
+    let initial_validated_keys = self.validated_keys.clone();
+    let is_cut_matched = self.is_cut_matched;
+    // Keys matched by any of the group choices, none of which validated
+    let mut matched_keys = initial_validated_keys.clone();
+    let mut rejected = Some(Vec::new());
+    for (idx, group_choice) in g.group_choices.iter().enumerate() {
+      let error_count = self.errors.len();
+      let explanation_count = self.choice_explanations.len();
+      let feature_use_count = self.feature_uses.len();
+      self.is_cut_matched = false;
+      self.visit_group_choice(group_choice)?;
+      self.explain_group_choice(g, idx, error_count, explanation_count, &mut rejected);
+      if self.errors.len() == error_count {
+        // Disregard invalid group choice validation errors if one of the
+        // choices validates successfully
+        let group_choice_error_count = self.errors.len() - initial_error_count;
+        if group_choice_error_count > 0 {
+          for _ in 0..group_choice_error_count {
+            self.errors.pop();
+          }
+        }
+
+        self.is_cut_matched |= is_cut_matched;
+        return Ok(());
+      }
+
+      self.feature_uses.truncate(feature_use_count);
+
+      // Keys matched by a failed group choice aren't matched by the choices
+      // that follow
+      if let Some(keys) = self
+        .validated_keys
+        .replace(initial_validated_keys.clone().unwrap_or_default())
+      {
+        matched_keys.get_or_insert_with(Vec::new).extend(keys);
+      }
+
+      // Once a member key with a cut matched, the remaining group choices
+      // aren't evaluated and only the errors of the committed choice are
+      // reported
+      if self.is_cut_matched {
+        self.errors.drain(initial_error_count..error_count);
+        break;
+      }
+    }
+
+    self.validated_keys = matched_keys;
+    self.is_cut_matched = is_cut_matched;
+
+    Ok(())
+  }
+
+  fn visit_group_entry(&mut self, entry: &GroupEntry<'a>) -> visitor::Result<Error> {
+    #[cfg(feature = "ast-span")]
+    let cddl_span = self.cddl_span.replace(entry.span());
+
+    let result = walk_group_entry(self, entry);
+
+    #[cfg(feature = "ast-span")]
+    {
+      self.cddl_span = cddl_span;
+    }
+
+    result
+  }
+
+  fn visit_group_choice(&mut self, gc: &GroupChoice<'a>) -> visitor::Result<Error> {
+    if self.is_group_to_choice_enum {
+      let initial_error_count = self.errors.len();
+      for tc in type_choices_from_group_choice(self.cddl, gc).iter() {
+        let error_count = self.errors.len();
+        self.visit_type_choice(tc)?;
+        if self.errors.len() == error_count {
+          let type_choice_error_count = self.errors.len() - initial_error_count;
+          if type_choice_error_count > 0 {
+            for _ in 0..type_choice_error_count {
+              self.errors.pop();
+            }
+          }
+          return Ok(());
+        }
+      }
+
+      return Ok(());
+    }
+
+    let error_count = self.errors.len();
+    for (idx, ge) in gc.group_entries.iter().enumerate() {
+      self.group_entry_idx = Some(idx);
+
+      let is_cut_matched = self.is_cut_matched;
+      let entry_error_count = self.errors.len();
+      self.is_cut_matched = false;
+      self.visit_group_entry(&ge.0)?;
+      // The group choice is committed to by a cut only if none of the group
+      // entries preceding it failed
+      self.is_cut_matched =
+        is_cut_matched || (self.is_cut_matched && entry_error_count == error_count);
+    }
+
+    Ok(())
+  }
+
+  fn visit_range(
+    &mut self,
+    lower: &Type2,
+    upper: &Type2,
+    is_inclusive: bool,
+  ) -> visitor::Result<Error> {
+    if let Value::Array(a) = &self.json {
+      match validate_array_occurrence(
+        self.occurrence.as_ref().take(),
+        self.entry_counts.as_ref().map(|ec| &ec[..]),
+        a,
+      ) {
+        Ok((iter_items, allow_empty_array)) => {
+          if iter_items {
+            for (idx, v) in a.iter().enumerate() {
+              if let Some(indices) = &self.valid_array_items {
+                if self.is_multi_type_choice && indices.contains(&idx) {
+                  continue;
+                }
+              }
+
+              let mut jv = self.nested_validator(v.clone());
+
+              jv.ctrl = self.ctrl.clone();
+              jv.is_multi_type_choice = self.is_multi_type_choice;
+              jv.json_location
+                .push_str(&format!("{}/{}", self.json_location, idx));
+
+              jv.visit_range(lower, upper, is_inclusive)?;
+
+              if jv.errors.is_empty() {
+                self.labeled_paths.append(&mut jv.labeled_paths);
+                self.choice_explanations.append(&mut jv.choice_explanations);
+                self.feature_uses.append(&mut jv.feature_uses);
+              }
+
+              if self.is_multi_type_choice && jv.errors.is_empty() {
+                if let Some(indices) = &mut self.valid_array_items {
+                  indices.push(idx);
+                } else {
+                  self.valid_array_items = Some(vec![idx]);
+                }
+                continue;
+              }
+
+              if let Some(errors) = &mut self.array_errors {
+                if let Some(error) = errors.get_mut(&idx) {
+                  error.append(&mut jv.errors);
+                } else {
+                  errors.insert(idx, jv.errors);
+                }
+              } else {
+                let mut errors = HashMap::new();
+                errors.insert(idx, jv.errors);
+                self.array_errors = Some(errors)
+              }
+            }
+          } else if let Some(idx) = self.group_entry_idx.take() {
+            if let Some(v) = a.get(idx) {
+              let mut jv = self.nested_validator(v.clone());
+
+              jv.ctrl = self.ctrl.clone();
+              jv.is_multi_type_choice = self.is_multi_type_choice;
+              jv.json_location
+                .push_str(&format!("{}/{}", self.json_location, idx));
+
+              jv.visit_range(lower, upper, is_inclusive)?;
+
+              if jv.errors.is_empty() {
+                self.labeled_paths.append(&mut jv.labeled_paths);
+                self.choice_explanations.append(&mut jv.choice_explanations);
+                self.feature_uses.append(&mut jv.feature_uses);
+              }
+
+              self.errors.append(&mut jv.errors);
+            } else if !allow_empty_array {
+              self.add_error(format!("expected array item at index {}", idx));
+            }
+          } else {
+            self.add_error(format!(
+              "expected range lower {} upper {} inclusive {}, got {}",
+              lower, upper, is_inclusive, self.json
+            ));
+          }
+        }
+        Err(errors) => {
+          for e in errors.into_iter() {
+            self.add_error(e);
+          }
+        }
+      }
+
+      return Ok(());
+    }
+
+    match lower {
+      Type2::IntValue { value: l, .. } => match upper {
+        Type2::IntValue { value: u, .. } => {
+          let error_str = if is_inclusive {
+            format!(
+              "expected integer to be in range {} <= value <= {}, got {}",
+              l, u, self.json
+            )
+          } else {
+            format!(
+              "expected integer to be in range {} < value < {}, got {}",
+              l, u, self.json
+            )
+          };
+
+          match &self.json {
+            Value::Number(n) => {
+              if let Some(i) = n
+                .as_i64()
+                .map(i128::from)
+                .or_else(|| n.as_u64().map(i128::from))
+              {
+                if is_inclusive {
+                  if i < *l as i128 || i > *u as i128 {
+                    self.add_error(error_str);
+                  } else {
+                    return Ok(());
+                  }
+                } else if i <= *l as i128 || i >= *u as i128 {
+                  self.add_error(error_str);
+                  return Ok(());
+                } else {
+                  return Ok(());
+                }
+              } else {
+                self.add_error(error_str);
+                return Ok(());
+              }
+            }
+            _ => {
+              self.add_error(error_str);
+              return Ok(());
+            }
+          }
+        }
+        Type2::UintValue { value: u, .. } => {
+          let error_str = if is_inclusive {
+            format!(
+              "expected integer to be in range {} <= value <= {}, got {}",
+              l, u, self.json
+            )
+          } else {
+            format!(
+              "expected integer to be in range {} < value < {}, got {}",
+              l, u, self.json
+            )
+          };
+
+          match &self.json {
+            Value::Number(n) => {
+              if let Some(i) = n
+                .as_i64()
+                .map(i128::from)
+                .or_else(|| n.as_u64().map(i128::from))
+              {
+                if is_inclusive {
+                  if i < *l as i128 || i > *u as i128 {
+                    self.add_error(error_str);
+                  } else {
+                    return Ok(());
+                  }
+                } else if i <= *l as i128 || i >= *u as i128 {
+                  self.add_error(error_str);
+                  return Ok(());
+                } else {
+                  return Ok(());
+                }
+              } else {
+                self.add_error(error_str);
+                return Ok(());
+              }
+            }
+            _ => {
+              self.add_error(error_str);
+              return Ok(());
+            }
+          }
+        }
+        _ => {
+          self.add_error(format!(
+            "invalid cddl range. upper value must be an integer type. got {}",
+            upper
+          ));
+          return Ok(());
+        }
+      },
+      Type2::UintValue { value: l, .. } => match upper {
+        Type2::UintValue { value: u, .. } => {
+          let error_str = if is_inclusive {
+            format!(
+              "expected uint to be in range {} <= value <= {}, got {}",
+              l, u, self.json
+            )
+          } else {
+            format!(
+              "expected uint to be in range {} < value < {}, got {}",
+              l, u, self.json
+            )
+          };
+
+          match &self.json {
+            Value::Number(n) => {
+              if let Some(i) = n.as_u64() {
+                if is_inclusive {
+                  if i < *l as u64 || i > *u as u64 {
+                    self.add_error(error_str);
+                  } else {
+                    return Ok(());
+                  }
+                } else if i <= *l as u64 || i >= *u as u64 {
+                  self.add_error(error_str);
+                  return Ok(());
+                } else {
+                  return Ok(());
+                }
+              } else {
+                self.add_error(error_str);
+                return Ok(());
+              }
+            }
+            Value::String(s) => match self.ctrl {
+              Some(Token::SIZE) => {
+                let len = s.len();
+                let s = s.clone();
+                if is_inclusive {
+                  if s.len() < *l || s.len() > *u {
+                    self.add_error(format!(
+                      "expected \"{}\" string length to be in the range {} <= value <= {}, got {}",
+                      s, l, u, len
+                    ));
+                  }
+
+                  return Ok(());
+                } else if s.len() <= *l || s.len() >= *u {
+                  self.add_error(format!(
+                    "expected \"{}\" string length to be in the range {} < value < {}, got {}",
+                    s, l, u, len
+                  ));
+                  return Ok(());
                 }
-
-                break;
               }
+              _ => {
+                self.add_error("string value cannot be validated against a range without the .size control operator".to_string());
+                return Ok(());
+              }
+            },
+            _ => {
+              self.add_error(error_str);
+              return Ok(());
             }
           }
-          Err(e) => self.add_error(e),
         }
+        _ => {
+          self.add_error(format!(
+            "invalid cddl range. upper value must be a uint type. got {}",
+            upper
+          ));
+          return Ok(());
+        }
+      },
+      Type2::FloatValue { value: l, .. } => match upper {
+        Type2::FloatValue { value: u, .. } => {
+          let error_str = if is_inclusive {
+            format!(
+              "expected float to be in range {} <= value <= {}, got {}",
+              l, u, self.json
+            )
+          } else {
+            format!(
+              "expected float to be in range {} < value < {}, got {}",
+              l, u, self.json
+            )
+          };
 
-        self.ctrl = None;
-      }
-      #[cfg(feature = "additional-controls")]
-      t @ Some(Token::PLUS) => {
-        self.ctrl = t;
-
-        match plus_operation(self.cddl, target, controller) {
-          Ok(values) => {
-            let error_count = self.errors.len();
-            for v in values.iter() {
-              let cur_errors = self.errors.len();
-
-              self.visit_type2(v)?;
-              if self.errors.len() == cur_errors {
-                for _ in 0..self.errors.len() - error_count {
-                  self.errors.pop();
+          match &self.json {
+            Value::Number(n) => {
+              if let Some(f) = n.as_f64() {
+                if is_inclusive {
+                  if f < *l as f64 || f > *u as f64 {
+                    self.add_error(error_str);
+                  } else {
+                    return Ok(());
+                  }
+                } else if f <= *l as f64 || f >= *u as f64 {
+                  self.add_error(error_str);
+                  return Ok(());
+                } else {
+                  return Ok(());
                 }
-
-                break;
+              } else {
+                self.add_error(error_str);
+                return Ok(());
               }
             }
+            _ => {
+              self.add_error(error_str);
+              return Ok(());
+            }
           }
-
-          Err(e) => self.add_error(e),
         }
+        _ => {
+          self.add_error(format!(
+            "invalid cddl range. upper value must be a float type. got {}",
+            upper
+          ));
+          return Ok(());
+        }
+      },
+      _ => {
+        self.add_error(
+          "invalid cddl range. upper and lower values must be either integers or floats"
+            .to_string(),
+        );
 
-        self.ctrl = None;
+        return Ok(());
       }
-      #[cfg(feature = "additional-controls")]
-      t @ Some(Token::ABNF) => {
-        self.ctrl = t;
-
-        match target {
-          Type2::Typename { ident, .. } if is_ident_string_data_type(self.cddl, ident) => {
-            match self.json {
-              Value::String(_) | Value::Array(_) => {
-                if let Type2::ParenthesizedType { pt, .. } = controller {
-                  match abnf_from_complex_controller(self.cddl, pt) {
-                    Ok(values) => {
-                      let error_count = self.errors.len();
-                      for v in values.iter() {
-                        let cur_errors = self.errors.len();
+    }
 
-                        self.visit_type2(v)?;
+    Ok(())
+  }
 
-                        if self.errors.len() == cur_errors {
-                          for _ in 0..self.errors.len() - error_count {
-                            self.errors.pop();
-                          }
+  fn visit_control_operator(
+    &mut self,
+    target: &Type2<'a>,
+    ctrl: &str,
+    controller: &Type2<'a>,
+  ) -> visitor::Result<Error> {
+    if let Type2::Typename {
+      ident: target_ident,
+      ..
+    } = target
+    {
+      if let Type2::Typename {
+        ident: controller_ident,
+        ..
+      } = controller
+      {
+        if let Some(name) = &self.eval_generic_rule {
+          if let Some(gr) = self
+            .generic_rules
+            .iter()
+            .cloned()
+            .find(|gr| gr.name == *name)
+          {
+            for (idx, gp) in gr.params.iter().enumerate() {
+              if let Some(arg) = gr.args.get(idx) {
+                if *gp == target_ident.ident {
+                  let t2 = Type2::from(arg.clone());
 
-                          break;
-                        }
-                      }
-                    }
-                    Err(e) => self.add_error(e),
+                  if *gp == controller_ident.ident {
+                    return self.visit_control_operator(&t2, ctrl, &t2);
                   }
-                } else {
-                  self.visit_type2(controller)?
+
+                  return self.visit_control_operator(&arg.type2, ctrl, controller);
                 }
               }
-              _ => self.add_error(format!(
-                ".abnf control can only be matched against a JSON string, got {}",
-                self.json,
-              )),
             }
           }
-          _ => self.add_error(format!(
-            ".abnf can only be matched against string data type, got {}",
-            target,
-          )),
         }
-
-        self.ctrl = None;
       }
-      #[cfg(feature = "additional-controls")]
-      #[cfg(not(target_arch = "wasm32"))]
-      t @ Some(Token::FEATURE) => {
-        self.ctrl = t;
 
-        if let Some(ef) = self.enabled_features {
-          let tv = text_value_from_type2(self.cddl, controller);
-          if let Some(Type2::TextValue { value, .. }) = tv {
-            if ef.contains(&&**value) {
-              let err_count = self.errors.len();
-              self.visit_type2(target)?;
-              if self.errors.len() > err_count {
-                self.has_feature_errors = true;
-              }
-              self.ctrl = None;
-            } else {
-              self
-                .disabled_features
-                .get_or_insert(vec![value.to_string()])
-                .push(value.to_string());
-            }
-          } else if let Some(Type2::UTF8ByteString { value, .. }) = tv {
-            let value = std::str::from_utf8(value).map_err(Error::UTF8Parsing)?;
-            if ef.contains(&value) {
-              let err_count = self.errors.len();
-              self.visit_type2(target)?;
-              if self.errors.len() > err_count {
-                self.has_feature_errors = true;
+      if let Some(name) = &self.eval_generic_rule {
+        if let Some(gr) = self
+          .generic_rules
+          .iter()
+          .cloned()
+          .find(|gr| gr.name == *name)
+        {
+          for (idx, gp) in gr.params.iter().enumerate() {
+            if let Some(arg) = gr.args.get(idx) {
+              if *gp == target_ident.ident {
+                let t2 = Type2::from(arg.clone());
+                return self.visit_control_operator(&t2, ctrl, controller);
               }
-              self.ctrl = None;
-            } else {
-              self
-                .disabled_features
-                .get_or_insert(vec![value.to_string()])
-                .push(value.to_string());
             }
           }
         }
-
-        self.ctrl = None;
       }
-      #[cfg(feature = "additional-controls")]
-      #[cfg(target_arch = "wasm32")]
-      t @ Some(Token::FEATURE) => {
-        self.ctrl = t;
+    }
 
-        if let Some(ef) = &self.enabled_features {
-          let tv = text_value_from_type2(self.cddl, controller);
-          if let Some(Type2::TextValue { value, .. }) = tv {
-            if ef.contains(&JsValue::from(value.as_ref())) {
-              let err_count = self.errors.len();
-              self.visit_type2(target)?;
-              if self.errors.len() > err_count {
-                self.has_feature_errors = true;
-              }
-              self.ctrl = None;
-            } else {
-              self
-                .disabled_features
-                .get_or_insert(vec![value.to_string()])
-                .push(value.to_string());
-            }
-          } else if let Some(Type2::UTF8ByteString { value, .. }) = tv {
-            let value = std::str::from_utf8(value).map_err(Error::UTF8Parsing)?;
-            if ef.contains(&JsValue::from(value)) {
-              let err_count = self.errors.len();
-              self.visit_type2(target)?;
-              if self.errors.len() > err_count {
-                self.has_feature_errors = true;
-              }
-              self.ctrl = None;
-            } else {
-              self
-                .disabled_features
-                .get_or_insert(vec![value.to_string()])
-                .push(value.to_string());
-            }
+    match lookup_control_from_str(ctrl) {
+      t @ Some(Token::EQ) => match target {
+        Type2::Typename { ident, .. } => {
+          if is_ident_string_data_type(self.cddl, ident)
+            || is_ident_numeric_data_type(self.cddl, ident)
+          {
+            return self.visit_type2(controller);
           }
         }
-
-        self.ctrl = None;
-      }
-      _ => {
-        self.add_error(format!("unsupported control operator {}", ctrl));
-      }
-    }
-
-    Ok(())
-  }
-
-  fn visit_type2(&mut self, t2: &Type2<'a>) -> visitor::Result<Error> {
-    match t2 {
-      Type2::TextValue { value, .. } => self.visit_value(&token::Value::TEXT(value.clone())),
-      Type2::Map { group, .. } => match &self.json {
-        Value::Object(o) => {
-          #[allow(clippy::needless_collect)]
-          let o = o.keys().cloned().collect::<Vec<_>>();
-
-          self.visit_group(group)?;
-
-          if self.values_to_validate.is_none() {
-            for k in o.into_iter() {
-              if let Some(keys) = &self.validated_keys {
-                if !keys.contains(&k) {
-                  self.add_error(format!("unexpected key {:?}", k));
-                }
-              }
+        Type2::Array { group, .. } => {
+          if let Value::Array(_) = &self.json {
+            let mut entry_counts = Vec::new();
+            for gc in group.group_choices.iter() {
+              let count = entry_counts_from_group_choice(self.cddl, gc);
+              entry_counts.push(count);
             }
+            self.entry_counts = Some(entry_counts);
+            self.visit_type2(controller)?;
+            self.entry_counts = None;
+            return Ok(());
           }
-
-          self.is_cut_present = false;
-          self.cut_value = None;
-          Ok(())
         }
-        Value::Array(a) => {
-          // Member keys are annotation only in an array context
-          if self.is_member_key {
+        Type2::Map { .. } => {
+          if let Value::Object(_) = &self.json {
+            self.ctrl = t;
+            self.is_ctrl_map_equality = true;
+            self.visit_type2(controller)?;
+            self.ctrl = None;
+            self.is_ctrl_map_equality = false;
             return Ok(());
           }
-
-          match validate_array_occurrence(
-            self.occurrence.as_ref().take(),
-            self.entry_counts.as_ref().map(|ec| &ec[..]),
-            a,
-          ) {
-            Ok((iter_items, allow_empty_array)) => {
-              if iter_items {
-                for (idx, v) in a.iter().enumerate() {
-                  if let Some(indices) = &self.valid_array_items {
-                    if self.is_multi_type_choice && indices.contains(&idx) {
-                      continue;
-                    }
-                  }
-
-                  #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-                  let mut jv =
-                    JSONValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-                  #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-                  let mut jv = JSONValidator::new(self.cddl, v.clone(), self.enabled_features);
-                  #[cfg(not(feature = "additional-controls"))]
-                  let mut jv = JSONValidator::new(self.cddl, v.clone());
-
-                  jv.generic_rules = self.generic_rules.clone();
-                  jv.eval_generic_rule = self.eval_generic_rule;
-                  jv.ctrl = self.ctrl.clone();
-                  jv.is_multi_type_choice = self.is_multi_type_choice;
-                  jv.json_location
-                    .push_str(&format!("{}/{}", self.json_location, idx));
-
-                  jv.visit_group(group)?;
-
-                  if self.is_multi_type_choice && jv.errors.is_empty() {
-                    if let Some(indices) = &mut self.valid_array_items {
-                      indices.push(idx);
-                    } else {
-                      self.valid_array_items = Some(vec![idx]);
-                    }
-                    continue;
-                  }
-
-                  if let Some(errors) = &mut self.array_errors {
-                    if let Some(error) = errors.get_mut(&idx) {
-                      error.append(&mut jv.errors);
-                    } else {
-                      errors.insert(idx, jv.errors);
-                    }
-                  } else {
-                    let mut errors = HashMap::new();
-                    errors.insert(idx, jv.errors);
-                    self.array_errors = Some(errors)
-                  }
-                }
-              } else if let Some(idx) = self.group_entry_idx.take() {
-                if let Some(v) = a.get(idx) {
-                  #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-                  let mut jv =
-                    JSONValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-                  #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-                  let mut jv = JSONValidator::new(self.cddl, v.clone(), self.enabled_features);
-                  #[cfg(not(feature = "additional-controls"))]
-                  let mut jv = JSONValidator::new(self.cddl, v.clone());
-
-                  jv.generic_rules = self.generic_rules.clone();
-                  jv.eval_generic_rule = self.eval_generic_rule;
-                  jv.ctrl = self.ctrl.clone();
-                  jv.is_multi_type_choice = self.is_multi_type_choice;
-                  jv.json_location
-                    .push_str(&format!("{}/{}", self.json_location, idx));
-
-                  jv.visit_group(group)?;
-
-                  self.errors.append(&mut jv.errors);
-                } else if !allow_empty_array {
-                  self.add_error(format!("expected map object {} at index {}", group, idx));
-                }
-              } else {
-                self.add_error(format!("expected map object {}, got {}", group, self.json));
-              }
-            }
-            Err(errors) => {
-              for e in errors.into_iter() {
-                self.add_error(e);
-              }
-            }
+        }
+        _ => self.add_error(format!(
+          "target for .eq operator must be a string, numerical, array or map data type, got {}",
+          target
+        )),
+      },
+      t @ Some(Token::NE) => match target {
+        Type2::Typename { ident, .. } => {
+          if is_ident_string_data_type(self.cddl, ident)
+            || is_ident_numeric_data_type(self.cddl, ident)
+          {
+            self.ctrl = t;
+            self.visit_type2(controller)?;
+            self.ctrl = None;
+            return Ok(());
           }
-
-          Ok(())
         }
-        _ => {
-          self.add_error(format!("expected map object {}, got {}", t2, self.json));
-          Ok(())
+        Type2::Array { .. } => {
+          if let Value::Array(_) = &self.json {
+            self.ctrl = t;
+            self.visit_type2(controller)?;
+            self.ctrl = None;
+            return Ok(());
+          }
         }
-      },
-      Type2::Array { group, .. } => match &self.json {
-        Value::Array(a) => {
-          if group.group_choices.len() == 1
-            && group.group_choices[0].group_entries.is_empty()
-            && !a.is_empty()
-            && !matches!(self.ctrl, Some(Token::NE) | Some(Token::DEFAULT))
-          {
-            self.add_error(format!("expected empty array, got {}", self.json));
+        Type2::Map { .. } => {
+          if let Value::Object(_) = &self.json {
+            self.ctrl = t;
+            self.is_ctrl_map_equality = true;
+            self.visit_type2(controller)?;
+            self.ctrl = None;
+            self.is_ctrl_map_equality = false;
             return Ok(());
           }
-
-          let mut entry_counts = Vec::new();
-          for gc in group.group_choices.iter() {
-            let count = entry_counts_from_group_choice(self.cddl, gc);
-            entry_counts.push(count);
+        }
+        _ => self.add_error(format!(
+          "target for .ne operator must be a string, numerical, array or map data type, got {}",
+          target
+        )),
+      },
+      t @ Some(Token::LT) | t @ Some(Token::GT) | t @ Some(Token::GE) | t @ Some(Token::LE) => {
+        match target {
+          Type2::Typename { ident, .. } if is_ident_numeric_data_type(self.cddl, ident) => {
+            self.ctrl = t;
+            self.visit_type2(controller)?;
+            self.ctrl = None;
           }
-
-          self.entry_counts = Some(entry_counts);
-          self.visit_group(group)?;
-          self.entry_counts = None;
-
-          if let Some(errors) = &mut self.array_errors {
-            if let Some(indices) = &self.valid_array_items {
-              for idx in indices.iter() {
-                errors.remove(idx);
-              }
-            }
-
-            for error in errors.values_mut() {
-              self.errors.append(error);
-            }
+          _ => {
+            self.add_error(format!(
+              "target for .lt, .gt, .ge or .le operator must be a numerical data type, got {}",
+              target
+            ));
           }
-
-          self.valid_array_items = None;
-          self.array_errors = None;
-
-          Ok(())
+        }
+      }
+      t @ Some(Token::SIZE) => match target {
+        Type2::Typename { ident, .. }
+          if is_ident_string_data_type(self.cddl, ident)
+            || is_ident_uint_data_type(self.cddl, ident) =>
+        {
+          self.ctrl = t;
+          self.visit_type2(controller)?;
+          self.ctrl = None;
         }
         _ => {
-          self.add_error(format!("expected array type, got {}", self.json));
-          Ok(())
+          self.add_error(format!(
+            "target for .size must a string or uint data type, got {}",
+            target
+          ));
         }
       },
-      Type2::ChoiceFromGroup {
-        ident,
-        generic_args,
-        ..
-      } => {
-        if let Some(ga) = generic_args {
-          if let Some(rule) = rule_from_ident(self.cddl, ident) {
-            if let Some(gr) = self
-              .generic_rules
-              .iter_mut()
-              .find(|gr| gr.name == ident.ident)
-            {
-              for arg in ga.args.iter() {
-                gr.args.push((*arg.arg).clone());
-              }
-            } else if let Some(params) = generic_params_from_rule(rule) {
-              self.generic_rules.push(GenericRule {
-                name: ident.ident,
-                params,
-                args: ga.args.iter().cloned().map(|arg| *arg.arg).collect(),
-              });
-            }
-
-            #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-            let mut jv =
-              JSONValidator::new(self.cddl, self.json.clone(), self.enabled_features.clone());
-            #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-            let mut jv = JSONValidator::new(self.cddl, self.json.clone(), self.enabled_features);
-            #[cfg(not(feature = "additional-controls"))]
-            let mut jv = JSONValidator::new(self.cddl, self.json.clone());
-
-            jv.generic_rules = self.generic_rules.clone();
-            jv.eval_generic_rule = Some(ident.ident);
-            jv.is_group_to_choice_enum = true;
-            jv.is_multi_type_choice = self.is_multi_type_choice;
-            jv.visit_rule(rule)?;
-
-            self.errors.append(&mut jv.errors);
-
-            return Ok(());
+      t @ Some(Token::AND) => {
+        self.ctrl = t;
+        self.visit_type2(target)?;
+        self.visit_type2(controller)?;
+        self.ctrl = None;
+      }
+      t @ Some(Token::WITHIN) => {
+        self.ctrl = t;
+        let error_count = self.errors.len();
+        self.visit_type2(target)?;
+        let no_errors = self.errors.len() == error_count;
+        self.visit_type2(controller)?;
+        if no_errors && self.errors.len() > error_count {
+          for _ in 0..self.errors.len() - error_count {
+            self.errors.pop();
           }
-        }
 
-        if group_rule_from_ident(self.cddl, ident).is_none() {
           self.add_error(format!(
-            "rule {} must be a group rule to turn it into a choice",
-            ident
+            "expected type {} .within type {}, got {}",
+            target, controller, self.json,
           ));
-          return Ok(());
         }
 
-        self.is_group_to_choice_enum = true;
-        self.visit_identifier(ident)?;
-        self.is_group_to_choice_enum = false;
-
-        Ok(())
-      }
-      Type2::ChoiceFromInlineGroup { group, .. } => {
-        self.is_group_to_choice_enum = true;
-        self.visit_group(group)?;
-        self.is_group_to_choice_enum = false;
-        Ok(())
+        self.ctrl = None;
       }
-      Type2::Typename {
-        ident,
-        generic_args,
-        ..
-      } => {
-        if let Some(ga) = generic_args {
-          if let Some(rule) = rule_from_ident(self.cddl, ident) {
-            if let Some(gr) = self
-              .generic_rules
-              .iter_mut()
-              .find(|gr| gr.name == ident.ident)
-            {
-              for arg in ga.args.iter() {
-                gr.args.push((*arg.arg).clone());
-              }
-            } else if let Some(params) = generic_params_from_rule(rule) {
-              self.generic_rules.push(GenericRule {
-                name: ident.ident,
-                params,
-                args: ga.args.iter().cloned().map(|arg| *arg.arg).collect(),
-              });
-            }
-
-            #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-            let mut jv =
-              JSONValidator::new(self.cddl, self.json.clone(), self.enabled_features.clone());
-            #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-            let mut jv = JSONValidator::new(self.cddl, self.json.clone(), self.enabled_features);
-            #[cfg(not(feature = "additional-controls"))]
-            let mut jv = JSONValidator::new(self.cddl, self.json.clone());
-
-            jv.generic_rules = self.generic_rules.clone();
-            jv.eval_generic_rule = Some(ident.ident);
-            jv.is_multi_type_choice = self.is_multi_type_choice;
-            jv.visit_rule(rule)?;
-
-            self.errors.append(&mut jv.errors);
-
-            return Ok(());
+      t @ Some(Token::DEFAULT) => {
+        self.ctrl = t;
+        let error_count = self.errors.len();
+        self.visit_type2(target)?;
+        if self.errors.len() != error_count {
+          #[cfg(feature = "ast-span")]
+          if let Some(Occur::Optional(_)) = self.occurrence.take() {
+            self.add_error(format!(
+              "expected default value {}, got {}",
+              controller, self.json
+            ));
           }
-        }
-
-        self.visit_identifier(ident)
-      }
-      Type2::IntValue { value, .. } => self.visit_value(&token::Value::INT(*value)),
-      Type2::UintValue { value, .. } => self.visit_value(&token::Value::UINT(*value)),
-      Type2::FloatValue { value, .. } => self.visit_value(&token::Value::FLOAT(*value)),
-      Type2::ParenthesizedType { pt, .. } => self.visit_type(pt),
-      Type2::Unwrap {
-        ident,
-        generic_args,
-        ..
-      } => {
-        // Per
-        // https://github.com/w3c/did-spec-registries/pull/138#issuecomment-719739215,
-        // strip tag and validate underlying type
-        if let Some(Type2::TaggedData { t, .. }) = tag_from_token(&lookup_ident(ident.ident)) {
-          return self.visit_type(&t);
-        }
-
-        if let Some(ga) = generic_args {
-          if let Some(rule) = unwrap_rule_from_ident(self.cddl, ident) {
-            if let Some(gr) = self
-              .generic_rules
-              .iter_mut()
-              .find(|gr| gr.name == ident.ident)
-            {
-              for arg in ga.args.iter() {
-                gr.args.push((*arg.arg).clone());
-              }
-            } else if let Some(params) = generic_params_from_rule(rule) {
-              self.generic_rules.push(GenericRule {
-                name: ident.ident,
-                params,
-                args: ga.args.iter().cloned().map(|arg| *arg.arg).collect(),
-              });
-            }
-
-            #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-            let mut jv =
-              JSONValidator::new(self.cddl, self.json.clone(), self.enabled_features.clone());
-            #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-            let mut jv = JSONValidator::new(self.cddl, self.json.clone(), self.enabled_features);
-            #[cfg(not(feature = "additional-controls"))]
-            let mut jv = JSONValidator::new(self.cddl, self.json.clone());
-
-            jv.generic_rules = self.generic_rules.clone();
-            jv.eval_generic_rule = Some(ident.ident);
-            jv.is_multi_type_choice = self.is_multi_type_choice;
-            jv.visit_rule(rule)?;
-
-            self.errors.append(&mut jv.errors);
-
-            return Ok(());
+          #[cfg(not(feature = "ast-span"))]
+          if let Some(Occur::Optional) = self.occurrence.take() {
+            self.add_error(format!(
+              "expected default value {}, got {}",
+              controller, self.json
+            ));
           }
         }
-
-        if let Some(rule) = unwrap_rule_from_ident(self.cddl, ident) {
-          return self.visit_rule(rule);
-        }
-
-        self.add_error(format!(
-          "cannot unwrap identifier {}, rule not found",
-          ident
-        ));
-
-        Ok(())
-      }
-      #[cfg(feature = "ast-span")]
-      Type2::Any(_) => Ok(()),
-      #[cfg(not(feature = "ast-span"))]
-      Type2::Any => Ok(()),
-      _ => {
-        self.add_error(format!(
-          "unsupported data type for validating JSON, got {}",
-          t2
-        ));
-        Ok(())
+        self.ctrl = None;
       }
-    }
-  }
-
-  fn visit_identifier(&mut self, ident: &Identifier<'a>) -> visitor::Result<Error> {
-    if let Some(name) = self.eval_generic_rule {
-      if let Some(gr) = self
-        .generic_rules
-        .iter()
-        .cloned()
-        .find(|gr| gr.name == name)
-      {
-        for (idx, gp) in gr.params.iter().enumerate() {
-          if *gp == ident.ident {
-            if let Some(arg) = gr.args.get(idx) {
-              return self.visit_type1(arg);
+      t @ Some(Token::REGEXP) | t @ Some(Token::PCRE) => {
+        self.ctrl = t;
+        match target {
+          Type2::Typename { ident, .. } if is_ident_string_data_type(self.cddl, ident) => {
+            match self.json {
+              Value::String(_) | Value::Array(_) => self.visit_type2(controller)?,
+              _ => self.add_error(format!(
+                ".regexp/.pcre control can only be matched against JSON string, got {}",
+                self.json
+              )),
             }
           }
+          _ => self.add_error(format!(
+            ".regexp/.pcre control can only be matched against string data type, got {}",
+            target
+          )),
         }
+        self.ctrl = None;
       }
-    }
-
-    // self.is_colon_shortcut_present is only true when the ident is part of a
-    // member key
-    if !self.is_colon_shortcut_present {
-      if let Some(r) = rule_from_ident(self.cddl, ident) {
-        return self.visit_rule(r);
-      }
-    }
+      #[cfg(feature = "additional-controls")]
+      t @ Some(Token::CAT) => {
+        self.ctrl = t;
 
-    if is_ident_any_type(self.cddl, ident) {
-      return Ok(());
-    }
+        match cat_operation(self.cddl, target, controller, false) {
+          Ok(values) => {
+            let error_count = self.errors.len();
 
-    match &self.json {
-      Value::Null if is_ident_null_data_type(self.cddl, ident) => Ok(()),
-      Value::Bool(b) => {
-        if is_ident_bool_data_type(self.cddl, ident) {
-          return Ok(());
-        }
+            for v in values.iter() {
+              let cur_errors = self.errors.len();
 
-        if ident_matches_bool_value(self.cddl, ident, *b) {
-          return Ok(());
-        }
+              self.visit_type2(v)?;
 
-        self.add_error(format!("expected type {}, got {}", ident, self.json));
-        Ok(())
-      }
-      Value::Number(n) => {
-        if is_ident_uint_data_type(self.cddl, ident) && n.is_u64() {
-          return Ok(());
-        } else if is_ident_nint_data_type(self.cddl, ident) {
-          if let Some(n) = n.as_i64() {
-            if n.is_negative() {
-              return Ok(());
-            }
-          }
-        } else if is_ident_time_data_type(self.cddl, ident) {
-          if let Some(n) = n.as_i64() {
-            if let chrono::LocalResult::None = Utc.timestamp_millis_opt(n * 1000) {
-              self.add_error(format!(
-                "expected time data type, invalid UNIX timestamp {}",
-                n,
-              ));
-            }
+              if self.errors.len() == cur_errors {
+                for _ in 0..self.errors.len() - error_count {
+                  self.errors.pop();
+                }
 
-            return Ok(());
-          } else if let Some(n) = n.as_f64() {
-            // truncates fractional milliseconds when validating
-            if let chrono::LocalResult::None = Utc.timestamp_millis_opt((n * 1000f64) as i64) {
-              self.add_error(format!(
-                "expected time data type, invalid UNIX timestamp {}",
-                n,
-              ));
+                break;
+              }
             }
           }
-        } else if (is_ident_integer_data_type(self.cddl, ident) && n.is_i64())
-          || (is_ident_float_data_type(self.cddl, ident) && n.is_f64())
-        {
-          return Ok(());
+          Err(e) => self.add_error(e),
         }
 
-        self.add_error(format!("expected type {}, got {}", ident, self.json));
-        Ok(())
+        self.ctrl = None;
       }
-      Value::String(s) => {
-        if is_ident_uri_data_type(self.cddl, ident) {
-          if let Err(e) = uriparse::URI::try_from(&**s) {
-            self.add_error(format!("expected URI data type, decoding error: {}", e));
-          }
-        } else if is_ident_b64url_data_type(self.cddl, ident) {
-          if let Err(e) = base64_url::decode(s) {
-            self.add_error(format!(
-              "expected base64 URL data type, decoding error: {}",
-              e
-            ));
-          }
-        } else if is_ident_tdate_data_type(self.cddl, ident) {
-          if let Err(e) = chrono::DateTime::parse_from_rfc3339(s) {
-            self.add_error(format!("expected tdate data type, decoding error: {}", e));
+      #[cfg(feature = "additional-controls")]
+      t @ Some(Token::DET) => {
+        self.ctrl = t;
+
+        match cat_operation(self.cddl, target, controller, true) {
+          Ok(values) => {
+            let error_count = self.errors.len();
+
+            for v in values.iter() {
+              let cur_errors = self.errors.len();
+
+              self.visit_type2(v)?;
+
+              if self.errors.len() == cur_errors {
+                for _ in 0..self.errors.len() - error_count {
+                  self.errors.pop();
+                }
+
+                break;
+              }
+            }
           }
-        } else if is_ident_string_data_type(self.cddl, ident) {
-          return Ok(());
-        } else {
-          self.add_error(format!("expected type {}, got {}", ident, self.json));
+          Err(e) => self.add_error(e),
         }
 
-        Ok(())
+        self.ctrl = None;
       }
-      Value::Array(a) => {
-        // Member keys are annotation only in an array context
-        if self.is_member_key {
-          return Ok(());
-        }
+      #[cfg(feature = "additional-controls")]
+      t @ Some(Token::PLUS) => {
+        self.ctrl = t;
 
-        match validate_array_occurrence(
-          self.occurrence.as_ref().take(),
-          self.entry_counts.as_ref().map(|ec| &ec[..]),
-          a,
-        ) {
-          Ok((iter_items, allow_empty_array)) => {
-            if iter_items {
-              for (idx, v) in a.iter().enumerate() {
-                if let Some(indices) = &self.valid_array_items {
-                  if self.is_multi_type_choice && indices.contains(&idx) {
-                    continue;
-                  }
+        match plus_operation_with_overflow(self.cddl, target, controller, self.overflow_behavior) {
+          Ok(values) => {
+            let error_count = self.errors.len();
+            for v in values.iter() {
+              let cur_errors = self.errors.len();
+
+              self.visit_type2(v)?;
+              if self.errors.len() == cur_errors {
+                for _ in 0..self.errors.len() - error_count {
+                  self.errors.pop();
                 }
 
-                #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-                let mut jv =
-                  JSONValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-                #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-                let mut jv = JSONValidator::new(self.cddl, v.clone(), self.enabled_features);
-                #[cfg(not(feature = "additional-controls"))]
-                let mut jv = JSONValidator::new(self.cddl, v.clone());
+                break;
+              }
+            }
+          }
 
-                jv.generic_rules = self.generic_rules.clone();
-                jv.eval_generic_rule = self.eval_generic_rule;
-                jv.is_multi_type_choice = self.is_multi_type_choice;
-                jv.ctrl = self.ctrl.clone();
-                jv.json_location
-                  .push_str(&format!("{}/{}", self.json_location, idx));
+          Err(e) => self.add_error(e),
+        }
 
-                jv.visit_identifier(ident)?;
+        self.ctrl = None;
+      }
+      #[cfg(feature = "additional-controls")]
+      t @ Some(Token::ABNF) => {
+        self.ctrl = t;
 
-                if self.is_multi_type_choice && jv.errors.is_empty() {
-                  if let Some(indices) = &mut self.valid_array_items {
-                    indices.push(idx);
-                  } else {
-                    self.valid_array_items = Some(vec![idx]);
-                  }
-                  continue;
-                }
+        match target {
+          Type2::Typename { ident, .. } if is_ident_string_data_type(self.cddl, ident) => {
+            match self.json {
+              Value::String(_) | Value::Array(_) => {
+                if let Type2::ParenthesizedType { pt, .. } = controller {
+                  match abnf_from_complex_controller(self.cddl, pt) {
+                    Ok(values) => {
+                      let error_count = self.errors.len();
+                      for v in values.iter() {
+                        let cur_errors = self.errors.len();
 
-                if let Some(errors) = &mut self.array_errors {
-                  if let Some(error) = errors.get_mut(&idx) {
-                    error.append(&mut jv.errors);
-                  } else {
-                    errors.insert(idx, jv.errors);
+                        self.visit_type2(v)?;
+
+                        if self.errors.len() == cur_errors {
+                          for _ in 0..self.errors.len() - error_count {
+                            self.errors.pop();
+                          }
+
+                          break;
+                        }
+                      }
+                    }
+                    Err(e) => self.add_error(e),
                   }
                 } else {
-                  let mut errors = HashMap::new();
-                  errors.insert(idx, jv.errors);
-                  self.array_errors = Some(errors)
+                  self.visit_type2(controller)?
                 }
               }
-            } else if let Some(idx) = self.group_entry_idx.take() {
-              if let Some(v) = a.get(idx) {
-                #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-                let mut jv =
-                  JSONValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-                #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-                let mut jv = JSONValidator::new(self.cddl, v.clone(), self.enabled_features);
-                #[cfg(not(feature = "additional-controls"))]
-                let mut jv = JSONValidator::new(self.cddl, v.clone());
-
-                jv.generic_rules = self.generic_rules.clone();
-                jv.eval_generic_rule = self.eval_generic_rule;
-                jv.ctrl = self.ctrl.clone();
-                jv.is_multi_type_choice = self.is_multi_type_choice;
-                jv.json_location
-                  .push_str(&format!("{}/{}", self.json_location, idx));
-
-                jv.visit_identifier(ident)?;
-
-                self.errors.append(&mut jv.errors);
-              } else if !allow_empty_array {
-                self.add_error(format!("expected type {} at index {}", ident, idx));
-              }
-            } else {
-              self.add_error(format!("expected type {}, got {}", ident, self.json));
-            }
-          }
-          Err(errors) => {
-            for e in errors.into_iter() {
-              self.add_error(e);
+              _ => self.add_error(format!(
+                ".abnf control can only be matched against a JSON string, got {}",
+                self.json,
+              )),
             }
           }
+          _ => self.add_error(format!(
+            ".abnf can only be matched against string data type, got {}",
+            target,
+          )),
         }
 
-        Ok(())
+        self.ctrl = None;
       }
-      Value::Object(o) => {
-        if let Some(occur) = &self.occurrence {
-          #[cfg(feature = "ast-span")]
-          if let Occur::ZeroOrMore(_) | Occur::OneOrMore(_) = occur {
-            if let Occur::OneOrMore(_) = occur {
-              if o.is_empty() {
-                self.add_error(format!(
-                  "object cannot be empty, one or more entries with key type {} required",
-                  ident
-                ));
-                return Ok(());
-              }
-            }
+      #[cfg(feature = "additional-controls")]
+      t @ Some(Token::FEATURE) => {
+        self.ctrl = t;
+        self.validate_feature(target, controller)?;
+        self.ctrl = None;
+      }
+      #[cfg(feature = "control-plugins")]
+      _ if self
+        .control_plugins
+        .map_or(false, |plugins| plugins.get(ctrl).is_some()) =>
+      {
+        let error_count = self.errors.len();
+        self.visit_type2(target)?;
+        if self.errors.len() == error_count {
+          self.validate_control_plugin(target, ctrl, controller);
+        }
+      }
+      _ => {
+        self.add_error(format!("unsupported control operator {}", ctrl));
+      }
+    }
 
-            if is_ident_string_data_type(self.cddl, ident) {
-              let values_to_validate = o
-                .iter()
-                .filter_map(|(k, v)| match &self.validated_keys {
-                  Some(keys) if !keys.contains(k) => Some(v.clone()),
-                  Some(_) => None,
-                  None => Some(v.clone()),
-                })
-                .collect::<Vec<_>>();
+    Ok(())
+  }
 
-              self.values_to_validate = Some(values_to_validate);
+  fn visit_type1(&mut self, t1: &Type1<'a>) -> visitor::Result<Error> {
+    #[cfg(feature = "ast-span")]
+    let cddl_span = self.cddl_span.replace(t1.span);
+
+    let result = walk_type1(self, t1);
+
+    #[cfg(feature = "ast-span")]
+    {
+      self.cddl_span = cddl_span;
+    }
+
+    result
+  }
+
+  fn visit_type2(&mut self, t2: &Type2<'a>) -> visitor::Result<Error> {
+    match t2 {
+      Type2::TextValue { value, .. } => self.visit_value(&token::Value::TEXT(value.clone())),
+      Type2::Map { group, .. } => self.validate_map(t2, group),
+      Type2::Array { group, .. } => self.validate_array(group),
+      Type2::ChoiceFromGroup {
+        ident,
+        generic_args,
+        ..
+      } => self.validate_choice_from_group(ident, generic_args),
+      Type2::ChoiceFromInlineGroup { group, .. } => {
+        self.is_group_to_choice_enum = true;
+        self.visit_group(group)?;
+        self.is_group_to_choice_enum = false;
+        Ok(())
+      }
+      Type2::Typename {
+        ident,
+        generic_args,
+        ..
+      } => self.validate_typename(ident, generic_args),
+      Type2::IntValue { value, .. } => self.visit_value(&token::Value::INT(*value)),
+      Type2::UintValue { value, .. } => self.visit_value(&token::Value::UINT(*value)),
+      Type2::FloatValue { value, .. } => self.visit_value(&token::Value::FLOAT(*value)),
+      Type2::ParenthesizedType { pt, .. } => self.visit_type(pt),
+      Type2::Unwrap {
+        ident,
+        generic_args,
+        ..
+      } => self.validate_unwrap(ident, generic_args),
+      #[cfg(feature = "ast-span")]
+      Type2::Any(_) => Ok(()),
+      #[cfg(not(feature = "ast-span"))]
+      Type2::Any => Ok(()),
+      _ => {
+        self.add_error(format!(
+          "unsupported data type for validating JSON, got {}",
+          t2
+        ));
+        Ok(())
+      }
+    }
+  }
 
-              return Ok(());
+  fn visit_identifier(&mut self, ident: &Identifier<'a>) -> visitor::Result<Error> {
+    if let Some(name) = &self.eval_generic_rule {
+      if let Some(gr) = self
+        .generic_rules
+        .iter()
+        .cloned()
+        .find(|gr| gr.name == *name)
+      {
+        for (idx, gp) in gr.params.iter().enumerate() {
+          if *gp == ident.ident {
+            if let Some(arg) = gr.args.get(idx) {
+              return self.visit_type1(arg);
             }
           }
+        }
+      }
+    }
 
-          #[cfg(not(feature = "ast-span"))]
-          if let Occur::ZeroOrMore | Occur::OneOrMore = occur {
-            if let Occur::OneOrMore = occur {
-              if o.is_empty() {
-                self.add_error(format!(
-                  "object cannot be empty, one or more entries with key type {} required",
-                  ident
-                ));
-                return Ok(());
-              }
-            }
+    // self.is_colon_shortcut_present is only true when the ident is part of a
+    // member key
+    if !self.is_colon_shortcut_present {
+      if let Some(r) = self.lookup_rule(ident) {
+        let error_count = self.errors.len();
+        let labeled_path_count = self.labeled_paths.len();
+        let item_indices = self.array_item_indices();
+        self.visit_rule(r)?;
 
-            if is_ident_string_data_type(self.cddl, ident) {
-              let values_to_validate = o
-                .iter()
-                .filter_map(|(k, v)| match &self.validated_keys {
-                  Some(keys) if !keys.contains(k) => Some(v.clone()),
-                  Some(_) => None,
-                  None => Some(v.clone()),
-                })
-                .collect::<Vec<_>>();
+        if let Some(indices) = item_indices {
+          self.collect_array_item_labels(ident, &indices, labeled_path_count)?;
+        } else {
+          self.collect_labels(ident, error_count, labeled_path_count);
+        }
 
-              self.values_to_validate = Some(values_to_validate);
+        return Ok(());
+      }
+    }
 
-              return Ok(());
-            }
+    // Arrays and maps validated against the entries of a group are left to the
+    // validation of the individual entries
+    if let Some(extended_prelude) = self.extended_prelude {
+      let is_group_entry = matches!(self.json, Value::Array(_) | Value::Object(_))
+        && (self.is_member_key || self.occurrence.is_some() || self.group_entry_idx.is_some());
+      if !self.is_colon_shortcut_present
+        && !is_group_entry
+        && lookup_ident(&ident.ident).in_standard_prelude().is_none()
+      {
+        if let Some(valid) = extended_prelude.validate_json(&ident.ident, &self.json) {
+          if !valid {
+            self.add_error(format!("expected type {}, got {}", ident, self.json));
           }
-        }
 
-        if token::lookup_ident(ident.ident)
-          .in_standard_prelude()
-          .is_some()
-        {
-          self.add_error(format!(
-            "expecting object value of type {}, got object",
-            ident.ident
-          ));
           return Ok(());
         }
-
-        self.visit_value(&token::Value::TEXT(ident.ident.into()))
-      }
-      _ => {
-        if let Some(cut_value) = self.cut_value.take() {
-          self.add_error(format!(
-            "cut present for member key {}. expected type {}, got {}",
-            cut_value, ident, self.json
-          ));
-        } else {
-          self.add_error(format!("expected type {}, got {}", ident, self.json));
-        }
-        Ok(())
       }
     }
+
+    if is_ident_any_type(self.cddl, ident) {
+      return Ok(());
+    }
+
+    self.validate_prelude_type(ident)
   }
 
   fn visit_value_member_key_entry(
@@ -2229,6 +3005,31 @@ impl<'a> Visitor<'a, Error> for JSONValidator<'a> {
 
     let current_location = self.json_location.clone();
 
+    if let (Some(MemberKey::Type1 { t1, .. }), Value::Object(o), Some(occur)) =
+      (&entry.member_key, &self.json, &self.occurrence)
+    {
+      let bounds = match occur {
+        #[cfg(feature = "ast-span")]
+        Occur::ZeroOrMore(_) => Some((0, None)),
+        #[cfg(not(feature = "ast-span"))]
+        Occur::ZeroOrMore => Some((0, None)),
+        #[cfg(feature = "ast-span")]
+        Occur::OneOrMore(_) => Some((1, None)),
+        #[cfg(not(feature = "ast-span"))]
+        Occur::OneOrMore => Some((1, None)),
+        Occur::Exact { lower, upper, .. } => Some((lower.unwrap_or(0), *upper)),
+        _ => None,
+      };
+
+      if let Some((lower, upper)) = bounds {
+        let o = o
+          .iter()
+          .map(|(k, v)| (k.clone(), v.clone()))
+          .collect::<Vec<_>>();
+        return self.validate_repeated_object_entries(entry, t1, &o, lower, upper);
+      }
+    }
+
     if let Some(mk) = &entry.member_key {
       let error_count = self.errors.len();
       self.is_member_key = true;
@@ -2244,23 +3045,19 @@ impl<'a> Visitor<'a, Error> for JSONValidator<'a> {
 
     if let Some(values) = &self.values_to_validate {
       for v in values.iter() {
-        #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-        let mut jv = JSONValidator::new(self.cddl, v.clone(), self.enabled_features.clone());
-        #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-        let mut jv = JSONValidator::new(self.cddl, v.clone(), self.enabled_features);
-        #[cfg(not(feature = "additional-controls"))]
-        let mut jv = JSONValidator::new(self.cddl, v.clone());
-
-        jv.generic_rules = self.generic_rules.clone();
-        jv.eval_generic_rule = self.eval_generic_rule;
-        jv.is_multi_type_choice = self.is_multi_type_choice;
-        jv.is_multi_group_choice = self.is_multi_group_choice;
+        let mut jv = self.child_validator(v.clone());
+
         jv.json_location.push_str(&self.json_location);
-        jv.type_group_name_entry = self.type_group_name_entry;
         jv.visit_type(&entry.entry_type)?;
 
         self.json_location = current_location.clone();
 
+        if jv.errors.is_empty() {
+          self.labeled_paths.append(&mut jv.labeled_paths);
+          self.choice_explanations.append(&mut jv.choice_explanations);
+          self.feature_uses.append(&mut jv.feature_uses);
+        }
+
         self.errors.append(&mut jv.errors);
         if entry.occur.is_some() {
           self.occurrence = None;
@@ -2271,23 +3068,36 @@ impl<'a> Visitor<'a, Error> for JSONValidator<'a> {
     }
 
     if let Some(v) = self.object_value.take() {
-      #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-      let mut jv = JSONValidator::new(self.cddl, v, self.enabled_features.clone());
-      #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-      let mut jv = JSONValidator::new(self.cddl, v, self.enabled_features);
-      #[cfg(not(feature = "additional-controls"))]
-      let mut jv = JSONValidator::new(self.cddl, v);
+      let mut jv = self.child_validator(v);
 
-      jv.generic_rules = self.generic_rules.clone();
-      jv.eval_generic_rule = self.eval_generic_rule;
-      jv.is_multi_type_choice = self.is_multi_type_choice;
-      jv.is_multi_group_choice = self.is_multi_group_choice;
       jv.json_location.push_str(&self.json_location);
-      jv.type_group_name_entry = self.type_group_name_entry;
       jv.visit_type(&entry.entry_type)?;
 
       self.json_location = current_location;
 
+      let has_cut = entry.member_key.as_ref().is_some_and(member_key_has_cut);
+      // The key matched, so a cut commits to the group choice whether or not
+      // its value validates
+      self.is_cut_matched |= has_cut;
+      if jv.errors.is_empty() {
+        self.labeled_paths.append(&mut jv.labeled_paths);
+        self.choice_explanations.append(&mut jv.choice_explanations);
+        self.feature_uses.append(&mut jv.feature_uses);
+      } else if !has_cut
+        && matches!(
+          entry.occur.as_ref().map(|o| &o.occur),
+          Some(Occur::Optional { .. }) | Some(Occur::ZeroOrMore { .. })
+        )
+      {
+        // Without a cut, a key whose value doesn't match an optional entry is
+        // left to the group entries that follow
+        if let Some(key) = self.validated_keys.as_mut().and_then(|keys| keys.pop()) {
+          self
+            .unmatched_key_errors
+            .push((key, std::mem::take(&mut jv.errors)));
+        }
+      }
+
       self.errors.append(&mut jv.errors);
       if entry.occur.is_some() {
         self.occurrence = None;
@@ -2305,10 +3115,10 @@ impl<'a> Visitor<'a, Error> for JSONValidator<'a> {
     &mut self,
     entry: &TypeGroupnameEntry<'a>,
   ) -> visitor::Result<Error> {
-    self.type_group_name_entry = Some(entry.name.ident);
+    self.type_group_name_entry = Some(entry.name.ident.clone());
 
     if let Some(ga) = &entry.generic_args {
-      if let Some(rule) = rule_from_ident(self.cddl, &entry.name) {
+      if let Some(rule) = self.lookup_rule(&entry.name) {
         if let Some(gr) = self
           .generic_rules
           .iter_mut()
@@ -2319,25 +3129,35 @@ impl<'a> Visitor<'a, Error> for JSONValidator<'a> {
           }
         } else if let Some(params) = generic_params_from_rule(rule) {
           self.generic_rules.push(GenericRule {
-            name: entry.name.ident,
+            name: entry.name.ident.clone(),
             params,
             args: ga.args.iter().cloned().map(|arg| *arg.arg).collect(),
           });
         }
 
-        #[cfg(all(feature = "additional-controls", target_arch = "wasm32"))]
-        let mut jv =
-          JSONValidator::new(self.cddl, self.json.clone(), self.enabled_features.clone());
-        #[cfg(all(feature = "additional-controls", not(target_arch = "wasm32")))]
-        let mut jv = JSONValidator::new(self.cddl, self.json.clone(), self.enabled_features);
-        #[cfg(not(feature = "additional-controls"))]
-        let mut jv = JSONValidator::new(self.cddl, self.json.clone());
+        let mut jv = self.nested_validator(self.json.clone());
 
-        jv.generic_rules = self.generic_rules.clone();
-        jv.eval_generic_rule = Some(entry.name.ident);
+        jv.eval_generic_rule = Some(entry.name.ident.clone());
         jv.is_multi_type_choice = self.is_multi_type_choice;
+        jv.validated_keys = self.validated_keys.take();
         jv.visit_rule(rule)?;
 
+        // Keys matched by the generic group are expected by the object
+        self.validated_keys = jv.validated_keys.take();
+        if self.values_to_validate.is_none() {
+          self.values_to_validate = jv.values_to_validate.take();
+        }
+        self.is_cut_matched |= jv.is_cut_matched;
+        self
+          .unmatched_key_errors
+          .append(&mut jv.unmatched_key_errors);
+
+        if jv.errors.is_empty() {
+          self.labeled_paths.append(&mut jv.labeled_paths);
+          self.choice_explanations.append(&mut jv.choice_explanations);
+          self.feature_uses.append(&mut jv.feature_uses);
+        }
+
         self.errors.append(&mut jv.errors);
 
         return Ok(());
@@ -2431,7 +3251,7 @@ impl<'a> Visitor<'a, Error> for JSONValidator<'a> {
             Some(Token::LE) if i <= *v as u64 => None,
             Some(Token::GT) if i > *v as u64 => None,
             Some(Token::GE) if i >= *v as u64 => None,
-            Some(Token::SIZE) if i < 256u64.pow(*v as u32) => None,
+            Some(Token::SIZE) if 256u64.checked_pow(*v as u32).is_none_or(|max| i < max) => None,
             #[cfg(feature = "additional-controls")]
             Some(Token::PLUS) => {
               if i == *v as u64 {
@@ -2530,23 +3350,25 @@ impl<'a> Visitor<'a, Error> for JSONValidator<'a> {
             }
           }
           Some(Token::REGEXP) | Some(Token::PCRE) => {
-            let re = regex::Regex::new(
-              &format_regex(
-                // Text strings must be JSON escaped per
-                // https://datatracker.ietf.org/doc/html/rfc8610#section-3.1
-                serde_json::from_str::<Value>(&format!("\"{}\"", t))
-                  .map_err(Error::JSONParsing)?
-                  .as_str()
-                  .ok_or_else(|| Error::from_validator(self, "malformed regex".to_string()))?,
-              )
-              .ok_or_else(|| Error::from_validator(self, "malformed regex".to_string()))?,
-            )
-            .map_err(|e| Error::from_validator(self, e.to_string()))?;
-
-            if re.is_match(s) {
-              None
-            } else {
-              Some(format!("expected \"{}\" to match regex \"{}\"", s, t))
+            let uncompiled_re;
+            let re = match self.compiled.and_then(|c| c.regex(self.regex_engine, t)) {
+              Some(re) => re,
+              None => {
+                uncompiled_re = self
+                  .regex_engine
+                  .compile(t)
+                  .map_err(|e| Error::from_validator(self, e))?;
+                &uncompiled_re
+              }
+            };
+
+            match re.is_match(s) {
+              Ok(true) => None,
+              Ok(false) => Some(format!("expected \"{}\" to match regex \"{}\"", s, t)),
+              Err(e) => Some(format!(
+                "error matching \"{}\" against regex \"{}\": {}",
+                s, t, e
+              )),
             }
           }
           #[cfg(feature = "additional-controls")]
@@ -2675,4 +3497,446 @@ mod tests {
 
     Ok(())
   }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_feature_profiles() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = indoc!(
+      r#"
+        message = {
+          id: uint,
+          ? trace: tstr .feature "tracing",
+          * (tstr .feature "extensions") => any,
+        }
+      "#
+    );
+
+    let cddl = cddl_from_str(cddl, true).map_err(json::Error::CDDLParsing)?;
+    let base = serde_json::json!({ "id": 1 });
+    let extended = serde_json::json!({ "id": 1, "trace": "abc", "x-debug": true });
+
+    let mut jv = JSONValidator::new(&cddl, base.clone(), Some(&[]));
+    jv.validate()?;
+    assert!(jv.feature_uses().is_empty());
+
+    let mut jv = JSONValidator::new(&cddl, extended.clone(), Some(&[]));
+    assert!(jv.validate().is_err());
+
+    let mut jv = JSONValidator::new(&cddl, extended.clone(), Some(&["tracing", "extensions"]));
+    jv.validate()?;
+    let mut uses = jv
+      .feature_uses()
+      .iter()
+      .map(|u| (u.feature.as_str(), u.enabled))
+      .collect::<Vec<_>>();
+    uses.sort_unstable();
+    uses.dedup();
+    assert_eq!(uses, [("extensions", true), ("tracing", true)]);
+
+    let mut jv = JSONValidator::new(&cddl, extended, Some(&["tracing"]));
+    jv.set_disabled_feature_behavior(DisabledFeatureBehavior::Warn);
+    jv.validate()?;
+    assert!(jv
+      .feature_uses()
+      .iter()
+      .any(|u| u.feature == "extensions" && !u.enabled));
+    assert!(jv
+      .feature_uses()
+      .iter()
+      .any(|u| u.feature == "tracing" && u.enabled && u.location == "/trace"));
+
+    // All features are enabled if no enabled features are given
+    let mut jv = JSONValidator::new(&cddl, serde_json::json!({ "id": 1, "trace": 2 }), None);
+    assert!(jv.validate().is_err());
+
+    Ok(())
+  }
+
+  #[test]
+  fn validate_rule_labels() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = indoc!(
+      r#"
+        ;@label personal
+        person = {
+          name: tstr,
+          ssn: ssn,
+          ? contacts: [* contact],
+        }
+
+        contact = tstr / ssn ;@label pii
+        ssn = tstr .regexp "[0-9]{3}-[0-9]{2}-[0-9]{4}" ;@label pii, secret
+      "#
+    );
+
+    let json = r#"{
+      "name": "Joe",
+      "ssn": "123-45-6789",
+      "contacts": ["Jane"]
+    }"#;
+
+    #[cfg(feature = "additional-controls")]
+    let labeled_paths = crate::validator::validate_json_with_labels(cddl, json, None)?;
+    #[cfg(not(feature = "additional-controls"))]
+    let labeled_paths = crate::validator::validate_json_with_labels(cddl, json)?;
+
+    let locations = labeled_paths
+      .iter()
+      .map(|lp| (lp.location.as_str(), lp.rule.as_str()))
+      .collect::<Vec<_>>();
+
+    assert!(locations.contains(&("", "person")));
+    assert!(locations.contains(&("/ssn", "ssn")));
+    assert!(locations.contains(&("/contacts/0", "contact")));
+    assert!(!locations.contains(&("/contacts/0", "ssn")));
+    assert_eq!(
+      labeled_paths
+        .iter()
+        .find(|lp| lp.rule == "ssn")
+        .unwrap()
+        .labels,
+      vec!["pii".to_string(), "secret".to_string()]
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn validate_error_rule() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = indoc!(
+      r#"
+        person = { name: tstr, address: address }
+        address = { street: tstr, zip: uint }
+      "#
+    );
+
+    let cddl = cddl_from_str(cddl, true)?;
+    let json = serde_json::from_str::<Value>(
+      r#"{ "name": "Joe", "address": { "street": "Main", "zip": "x" } }"#,
+    )?;
+
+    #[cfg(feature = "additional-controls")]
+    let mut jv = JSONValidator::new(&cddl, json, None);
+    #[cfg(not(feature = "additional-controls"))]
+    let mut jv = JSONValidator::new(&cddl, json);
+
+    match jv.validate() {
+      Err(Error::Validation(errors)) => {
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].cddl_location, "address");
+        assert_eq!(errors[0].json_location, "/address/zip");
+      }
+      r => panic!("unexpected result {:?}", r),
+    }
+
+    Ok(())
+  }
+
+  #[test]
+  fn explain_choices() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = indoc!(
+      r#"
+        person = { name: tstr, id: uint / tstr .regexp "[a-z]+" / any }
+      "#
+    );
+
+    let json = r#"{ "name": "Joe", "id": "X-1" }"#;
+
+    #[cfg(feature = "additional-controls")]
+    let explanations = crate::validator::explain_json_from_str(cddl, json, None)?;
+    #[cfg(not(feature = "additional-controls"))]
+    let explanations = crate::validator::explain_json_from_str(cddl, json)?;
+
+    assert_eq!(explanations.len(), 1);
+    assert_eq!(explanations[0].location, "/id");
+    assert_eq!(explanations[0].index, 2);
+    assert_eq!(explanations[0].choice, "any");
+    assert_eq!(
+      explanations[0]
+        .rejected
+        .iter()
+        .map(|r| r.choice.as_str())
+        .collect::<Vec<_>>(),
+      vec!["uint", r#"tstr .regexp "[a-z]+""#]
+    );
+    assert!(explanations[0]
+      .rejected
+      .iter()
+      .all(|r| !r.reasons.is_empty()));
+
+    Ok(())
+  }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_plus_overflow() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = indoc!(
+      r#"
+        big = 18446744073709551615 .plus 1
+        id = -1..18446744073709551615
+      "#
+    );
+    let cddl = cddl_from_str(cddl, true).map_err(json::Error::CDDLParsing)?;
+    let json = serde_json::from_str::<serde_json::Value>("18446744073709551615")?;
+
+    let mut jv = JSONValidator::new(&cddl, json.clone(), None);
+    assert!(jv.validate().is_err());
+
+    let mut jv = JSONValidator::new(&cddl, json.clone(), None);
+    jv.set_overflow_behavior(OverflowBehavior::Saturating);
+    assert!(jv.validate().is_ok());
+
+    let mut jv = JSONValidator::new(&cddl, json.clone(), None);
+    jv.set_overflow_behavior(OverflowBehavior::Wrapping);
+    assert!(jv.validate().is_err());
+
+    let mut jv = JSONValidator::new(&cddl, json, None);
+    jv.set_root_rule("id", None);
+    assert!(jv.validate().is_ok());
+
+    Ok(())
+  }
+
+  #[test]
+  fn validate_root_rule() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = indoc!(
+      r#"
+        person = { name: tstr }
+        address = { street: tstr, ? zip: uint }
+        message<t, v> = { type: t, value: v }
+      "#
+    );
+
+    let cddl = cddl_from_str(cddl, true)?;
+
+    let validate = |json: &str, rule: &'static str, args: Option<&'static str>| {
+      let json = serde_json::from_str::<serde_json::Value>(json).unwrap();
+      let generic_args = args.map(|args| {
+        crate::parser::Parser::new(args, Box::new(crate::lexer_from_str(args).iter()))
+          .and_then(|mut p| p.parse_genericargs())
+          .unwrap()
+      });
+
+      #[cfg(feature = "additional-controls")]
+      let mut jv = JSONValidator::new(&cddl, json, None);
+      #[cfg(not(feature = "additional-controls"))]
+      let mut jv = JSONValidator::new(&cddl, json);
+
+      jv.set_root_rule(rule, generic_args);
+      jv.validate()
+    };
+
+    validate(r#"{ "street": "Main", "zip": 1 }"#, "address", None)?;
+    assert!(validate(r#"{ "name": "Joe" }"#, "address", None).is_err());
+
+    validate(
+      r#"{ "type": "ping", "value": 1 }"#,
+      "message",
+      Some("<tstr, uint>"),
+    )?;
+    assert!(validate(
+      r#"{ "type": "ping", "value": 1 }"#,
+      "message",
+      Some("<tstr, tstr>")
+    )
+    .is_err());
+    assert!(validate(r#"{ "type": "ping", "value": 1 }"#, "message", None).is_err());
+
+    assert!(matches!(
+      validate(r#"{ "name": "Joe" }"#, "missing", None),
+      Err(Error::MissingRule(rule)) if rule == "missing"
+    ));
+
+    Ok(())
+  }
+
+  #[cfg(all(feature = "miette", feature = "ast-span"))]
+  #[test]
+  fn validate_diagnostic() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    use miette::Diagnostic;
+
+    let cddl_str = indoc!(
+      r#"
+        person = { name: tstr, age: uint }
+      "#
+    );
+    let json = r#"{ "name": "Joe", "age": "ten" }"#;
+
+    let cddl = cddl_from_str(cddl_str, true)?;
+    let json = serde_json::from_str::<serde_json::Value>(json).map_err(json::Error::JSONParsing)?;
+
+    #[cfg(feature = "additional-controls")]
+    let mut jv = JSONValidator::new(&cddl, json, None);
+    #[cfg(not(feature = "additional-controls"))]
+    let mut jv = JSONValidator::new(&cddl, json);
+
+    let e = jv.validate().unwrap_err();
+    assert_eq!(e.code().unwrap().to_string(), "cddl::validation");
+
+    let labels = e.labels().unwrap().collect::<Vec<_>>();
+    assert_eq!(labels.len(), 1);
+    assert_eq!(
+      &cddl_str[labels[0].offset()..labels[0].offset() + labels[0].len()],
+      "uint"
+    );
+
+    Ok(())
+  }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_cut() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let locations = |cddl: &str, json: serde_json::Value| -> Vec<(String, String)> {
+      let cddl = cddl_from_str(cddl, true).unwrap();
+      let mut jv = JSONValidator::new(&cddl, json, None);
+      match jv.validate() {
+        Ok(()) => Vec::new(),
+        Err(Error::Validation(errors)) => errors
+          .into_iter()
+          .map(|e| (e.json_location, e.reason))
+          .collect(),
+        r => panic!("unexpected result {:?}", r),
+      }
+    };
+
+    // Without a cut, a key whose value doesn't match an optional entry is left
+    // to the entries that follow
+    assert!(locations(
+      r#"m = { ? "a" => uint, * tstr => tstr }"#,
+      serde_json::json!({ "a": "x" })
+    )
+    .is_empty());
+    assert_eq!(
+      locations(
+        r#"m = { ? "a" ^ => uint, * tstr => tstr }"#,
+        serde_json::json!({ "a": "x" })
+      ),
+      vec![(
+        "/a".to_string(),
+        r#"expected type uint, got "x""#.to_string()
+      )]
+    );
+
+    // Only the group choice committed to by a cut reports errors
+    assert_eq!(
+      locations(
+        r#"m = { type: "x", val: uint // type: "y", val: tstr }"#,
+        serde_json::json!({ "type": "x", "val": "s" })
+      ),
+      vec![(
+        "/val".to_string(),
+        r#"expected type uint, got "s""#.to_string()
+      )]
+    );
+
+    // A matched key with a cut commits to its group choice even if the value
+    // doesn't validate, so the group choices that follow aren't tried
+    assert_eq!(
+      locations(
+        r#"m = { "x" ^ => int // "x" => tstr }"#,
+        serde_json::json!({ "x": "s" })
+      ),
+      vec![(
+        "/x".to_string(),
+        r#"expected type int, got "s""#.to_string()
+      )]
+    );
+    assert_eq!(
+      locations(
+        r#"m = { "x" ^ => int, "y" => uint // "x" => tstr }"#,
+        serde_json::json!({ "x": "s" })
+      ),
+      vec![
+        (
+          "/x".to_string(),
+          r#"expected type int, got "s""#.to_string()
+        ),
+        ("".to_string(), r#"object missing key: "y""#.to_string())
+      ]
+    );
+
+    // Unexpected keys are located at the key, including keys only matched by
+    // a group choice that failed
+    assert_eq!(
+      locations(
+        "m = { ? a: uint, ? b: uint }",
+        serde_json::json!({ "c": 1 })
+      ),
+      vec![("/c".to_string(), r#"unexpected key "c""#.to_string())]
+    );
+    assert_eq!(
+      locations(
+        r#"m = { "a" => uint // "b" => tstr }"#,
+        serde_json::json!({ "a": "x", "b": "y" })
+      ),
+      vec![("/a".to_string(), r#"unexpected key "a""#.to_string())]
+    );
+
+    Ok(())
+  }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_limits() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = cddl_from_str("nested = { ? child: nested }", true)?;
+
+    let mut json = serde_json::json!({});
+    for _ in 0..20 {
+      json = serde_json::json!({ "child": json });
+    }
+
+    let validate = |limits: ValidationLimits| {
+      let mut jv = JSONValidator::new(&cddl, json.clone(), None);
+      jv.set_limits(limits);
+      jv.validate()
+    };
+
+    validate(ValidationLimits::default())?;
+    assert!(matches!(
+      validate(ValidationLimits {
+        max_depth: 8,
+        ..Default::default()
+      }),
+      Err(Error::LimitExceeded(_))
+    ));
+    assert!(matches!(
+      validate(ValidationLimits {
+        max_steps: 8,
+        ..Default::default()
+      }),
+      Err(Error::LimitExceeded(_))
+    ));
+
+    Ok(())
+  }
+
+  #[cfg(feature = "additional-controls")]
+  #[test]
+  fn validate_trees_at_the_default_depth_limit() {
+    // Validation within the default limits fits the default stack of spawned
+    // threads, even in unoptimized builds. Each level of the tree references
+    // both child and node
+    let depth = (ValidationLimits::default().max_depth - 2) / 2;
+
+    let results = std::thread::Builder::new()
+      .stack_size(2 << 20)
+      .spawn(move || {
+        let cddl = cddl_from_str("root = node\nnode = { ? c: child }\nchild = node", true).unwrap();
+
+        [depth, depth + 1].map(|depth| {
+          let mut json = serde_json::json!({});
+          for _ in 0..depth {
+            json = serde_json::json!({ "c": json });
+          }
+
+          let mut jv = JSONValidator::new(&cddl, json, None);
+          jv.validate()
+        })
+      })
+      .unwrap()
+      .join()
+      .unwrap();
+
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(Error::LimitExceeded(_))));
+  }
 }
@@ -1,22 +1,64 @@
 #![cfg(not(feature = "lsp"))]
 
+/// ABNF grammars matched by the `.abnf` and `.abnfb` control operators
+#[cfg(feature = "std")]
+pub mod abnf;
 /// CBOR validation implementation
 pub mod cbor;
+/// Validation against CDDL documents compiled ahead of time
+pub mod compiled;
+/// Deterministic CBOR encoding checks
+#[cfg(feature = "cbor")]
+pub mod deterministic;
+/// Types beyond the standard prelude validated by predicates
+#[cfg(feature = "std")]
+pub mod extended_prelude;
 /// JSON validation implementation
+#[cfg(feature = "json")]
 pub mod json;
+/// Byte ranges of CBOR data items
+#[cfg(feature = "cbor")]
+pub mod offsets;
 
-mod control;
+pub(crate) mod control;
+#[cfg(feature = "std")]
+pub use control::Regex;
+pub use control::{DisabledFeatureBehavior, OverflowBehavior, RegexEngine};
+#[cfg(feature = "cbor")]
+mod plan;
+/// Control operators implemented by WebAssembly plugins
+#[cfg(feature = "control-plugins")]
+pub mod plugin;
+/// WebAssembly bindings for reusable schemas
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
 
 use crate::{
+  annotations::RuleComments,
   ast::{
-    GroupChoice, GroupEntry, GroupRule, Identifier, Occur, Rule, Type, Type2, TypeChoice, TypeRule,
-    CDDL,
+    GroupChoice, GroupEntry, GroupRule, Identifier, MemberKey, Occur, Rule, Type, Type2,
+    TypeChoice, TypeRule, CDDL,
   },
   token::*,
   visitor::Visitor,
 };
 
-use std::error::Error;
+#[cfg(feature = "std")]
+use std::{borrow::Cow, collections::BTreeMap, error::Error, fmt, sync::Arc};
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+  borrow::Cow,
+  collections::BTreeMap,
+  string::{String, ToString},
+  sync::Arc,
+  vec::Vec,
+};
+use core::sync::atomic::{AtomicUsize, Ordering};
+// `std::error::Error` isn't available in `no_std`, so validation errors are
+// only required to be `Debug`
+#[cfg(not(feature = "std"))]
+use core::fmt::{self, Debug as Error};
 
 #[cfg(feature = "cbor")]
 use cbor::CBORValidator;
@@ -38,7 +80,10 @@ use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
 #[cfg(not(target_arch = "wasm32"))]
-use crate::cddl_from_str;
+use crate::{cddl_from_str, parser};
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "cbor")]
+use cbor::ReaderError;
 
 #[cfg(target_arch = "wasm32")]
 #[derive(Serialize)]
@@ -47,11 +92,16 @@ struct ParserError {
   msg: ErrorMsg,
 }
 
-trait Validator<'a, E: Error>: Visitor<'a, E> {
+/// Validation of a data item against a CDDL AST
+pub trait Validator<'a, E: Error>: Visitor<'a, E> {
+  /// Validate the data item against the root rule
   fn validate(&mut self) -> std::result::Result<(), E>;
+  /// Record a validation error
   fn add_error(&mut self, reason: String);
 }
 
+#[cfg(feature = "json")]
+#[cfg(feature = "cbor")]
 impl CDDL<'_> {
   /// Validate the given document against the CDDL definition
   pub fn validate(
@@ -71,14 +121,17 @@ impl CDDL<'_> {
       #[cfg(feature = "additional-controls")]
       let mut jv = JSONValidator::new(self, json, enabled_features);
       #[cfg(not(feature = "additional-controls"))]
-      let mut jv = JSONValidator::new(&cddl, json);
+      let mut jv = JSONValidator::new(self, json);
 
       return jv.validate().map_err(|e| e.into());
     }
 
     let cbor: ciborium::value::Value = ciborium::de::from_reader(document)?;
 
+    #[cfg(feature = "additional-controls")]
     let mut cv = CBORValidator::new(self, cbor, enabled_features);
+    #[cfg(not(feature = "additional-controls"))]
+    let mut cv = CBORValidator::new(self, cbor);
     cv.validate().map_err(|e| e.into())
   }
 }
@@ -91,7 +144,7 @@ pub fn validate_json_from_str(
   json: &str,
   #[cfg(feature = "additional-controls")] enabled_features: Option<&[&str]>,
 ) -> json::Result {
-  let cddl = cddl_from_str(cddl, true).map_err(json::Error::CDDLParsing)?;
+  let cddl = cddl_from_str(cddl, false).map_err(json::Error::CDDLParsing)?;
   let json = serde_json::from_str::<serde_json::Value>(json).map_err(json::Error::JSONParsing)?;
 
   #[cfg(feature = "additional-controls")]
@@ -184,6 +237,328 @@ pub fn validate_json_from_str(cddl: &str, json: &str) -> std::result::Result<JsV
     .map(|_| JsValue::default())
 }
 
+/// Labels (e.g. `pii` or `secret`) attached to rules via `;@label` annotations,
+/// keyed by rule name
+pub type RuleLabels = BTreeMap<String, Vec<String>>;
+
+/// Location in a validated document carrying the labels of the rule it was
+/// validated against
+#[derive(Clone, Debug, PartialEq)]
+pub struct LabeledPath {
+  /// Location in the document (in JSONPointer notation for JSON)
+  pub location: String,
+  /// Name of the labeled rule
+  pub rule: String,
+  /// Labels attached to the rule
+  pub labels: Vec<String>,
+}
+
+/// Explanation of the type or group choice a location in a validated document
+/// matched, listing the earlier choices that failed to match and why. Useful to
+/// find out which branch of an over-permissive choice (e.g. one ending in
+/// `any`) accepted a value
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChoiceExplanation {
+  /// Location in the document (in JSONPointer notation for JSON)
+  pub location: String,
+  /// Name of the innermost rule containing the type choice, empty if the
+  /// choice isn't contained in a rule
+  pub rule: String,
+  /// Index of the first choice that matched
+  pub index: usize,
+  /// First choice that matched
+  pub choice: String,
+  /// Whether the choice is a group choice rather than a type choice
+  pub is_group_choice: bool,
+  /// Choices preceding the matched choice
+  pub rejected: Vec<RejectedChoice>,
+}
+
+/// Type or group choice that failed to match
+#[derive(Clone, Debug, PartialEq)]
+pub struct RejectedChoice {
+  /// Index of the choice
+  pub index: usize,
+  /// Choice
+  pub choice: String,
+  /// Reasons the choice failed to match
+  pub reasons: Vec<String>,
+}
+
+impl fmt::Display for ChoiceExplanation {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "{} matched {}choice {} ({})",
+      if self.location.is_empty() {
+        "root"
+      } else {
+        &self.location
+      },
+      if self.is_group_choice { "group " } else { "" },
+      self.index,
+      self.choice
+    )?;
+
+    for rejected in self.rejected.iter() {
+      write!(
+        f,
+        "\n  choice {} ({}) failed: {}",
+        rejected.index,
+        rejected.choice,
+        rejected.reasons.join("; ")
+      )?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Use of a feature by a location in a validated document, i.e. a match of the
+/// target of a `.feature` control operator
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeatureUse {
+  /// Location in the document (in JSONPointer notation for JSON)
+  pub location: String,
+  /// Name of the feature
+  pub feature: String,
+  /// Whether the feature is enabled. Uses of disabled features are only
+  /// reported if they're warned about rather than rejected
+  pub enabled: bool,
+}
+
+impl fmt::Display for FeatureUse {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "{} uses {}feature {}",
+      if self.location.is_empty() {
+        "root"
+      } else {
+        &self.location
+      },
+      if self.enabled { "" } else { "disabled " },
+      self.feature
+    )
+  }
+}
+
+/// Limits on the resources used to validate a document, protecting services
+/// that validate untrusted data against untrusted schemas from overflowing the
+/// stack or validating indefinitely
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidationLimits {
+  /// Maximum depth of nested rule references, e.g. of the recursion of
+  /// `tree = { ? child: tree }`. Defaults to 64, which fits the default 2 MiB
+  /// stack of spawned threads for both JSON and CBOR validation, even in
+  /// unoptimized builds. Raising it may require a larger stack
+  pub max_depth: usize,
+  /// Maximum number of types a document is validated against, counting each
+  /// choice tried. Defaults to no limit
+  pub max_steps: usize,
+}
+
+impl Default for ValidationLimits {
+  fn default() -> Self {
+    ValidationLimits {
+      max_depth: 64,
+      max_steps: usize::MAX,
+    }
+  }
+}
+
+// Resources used so far by a validator and the validators of nested data items
+// it creates, which share its count of steps
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Budget {
+  limits: ValidationLimits,
+  depth: usize,
+  steps: Arc<AtomicUsize>,
+}
+
+impl Budget {
+  pub(crate) fn new(limits: ValidationLimits) -> Self {
+    Budget {
+      limits,
+      ..Default::default()
+    }
+  }
+
+  // Descend into a referenced rule, failing if the maximum depth is exceeded
+  pub(crate) fn enter_rule(&mut self) -> Result<(), String> {
+    if self.depth >= self.limits.max_depth {
+      return Err(format!(
+        "rule references nested more than {} levels deep",
+        self.limits.max_depth
+      ));
+    }
+
+    self.depth += 1;
+
+    Ok(())
+  }
+
+  pub(crate) fn exit_rule(&mut self) {
+    self.depth -= 1;
+  }
+
+  // Number of further nested rule references allowed
+  #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+  pub(crate) fn remaining_depth(&self) -> usize {
+    self.limits.max_depth.saturating_sub(self.depth)
+  }
+
+  // Count a step of validation, failing if the maximum number of steps is
+  // exceeded
+  pub(crate) fn step(&self) -> Result<(), String> {
+    if self.steps.fetch_add(1, Ordering::Relaxed) >= self.limits.max_steps {
+      return Err(format!(
+        "validation exceeded the limit of {} steps",
+        self.limits.max_steps
+      ));
+    }
+
+    Ok(())
+  }
+}
+
+/// Collect the labels attached to rules via `;@label` annotations in a given
+/// CDDL document string. Multiple labels are separated by commas and may be
+/// spread across multiple annotations
+///
+/// ```cddl
+/// ;@label pii
+/// person = { name: tstr, ssn: ssn }
+/// ssn = tstr ;@label pii, secret
+/// ```
+pub fn rule_labels_from_str(cddl: &str) -> std::result::Result<RuleLabels, parser::Error> {
+  let rule_comments = RuleComments::from_cddl(cddl).map_err(parser::Error::LEXER)?;
+
+  let mut rule_labels = RuleLabels::new();
+  for (rule, _) in rule_comments.iter() {
+    let labels = rule_comments
+      .annotations(rule)
+      .iter()
+      .filter(|a| a.name == "label")
+      .flat_map(|a| a.values().into_iter().map(String::from).collect::<Vec<_>>())
+      .collect::<Vec<_>>();
+
+    if !labels.is_empty() {
+      rule_labels.insert(rule.to_string(), labels);
+    }
+  }
+
+  Ok(rule_labels)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "json")]
+/// Validate JSON string from a given CDDL document string and return the
+/// locations in the JSON document carrying labels attached to rules via
+/// `;@label` annotations
+pub fn validate_json_with_labels(
+  cddl: &str,
+  json: &str,
+  #[cfg(feature = "additional-controls")] enabled_features: Option<&[&str]>,
+) -> std::result::Result<Vec<LabeledPath>, json::Error> {
+  let rule_labels = rule_labels_from_str(cddl).map_err(json::Error::CDDLParsing)?;
+  let cddl = cddl_from_str(cddl, false).map_err(json::Error::CDDLParsing)?;
+  let json = serde_json::from_str::<serde_json::Value>(json).map_err(json::Error::JSONParsing)?;
+
+  #[cfg(feature = "additional-controls")]
+  let mut jv = JSONValidator::new(&cddl, json, enabled_features);
+  #[cfg(not(feature = "additional-controls"))]
+  let mut jv = JSONValidator::new(&cddl, json);
+
+  jv.set_rule_labels(&rule_labels);
+  jv.validate()?;
+
+  Ok(jv.labeled_paths().to_vec())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "cbor")]
+/// Validate CBOR slice from a given CDDL document string and return the
+/// locations in the CBOR data carrying labels attached to rules via `;@label`
+/// annotations
+pub fn validate_cbor_with_labels(
+  cddl: &str,
+  cbor_slice: &[u8],
+  #[cfg(feature = "additional-controls")] enabled_features: Option<&[&str]>,
+) -> std::result::Result<Vec<LabeledPath>, cbor::Error<ReaderError>> {
+  let rule_labels = rule_labels_from_str(cddl).map_err(cbor::Error::CDDLParsing)?;
+  #[cfg(feature = "std")]
+  let cddl = cddl_from_str(cddl, false).map_err(cbor::Error::CDDLParsing)?;
+  #[cfg(not(feature = "std"))]
+  let cddl = cddl_from_str(cddl).map_err(cbor::Error::CDDLParsing)?;
+  let cbor: ciborium::value::Value =
+    ciborium::de::from_reader(cbor_slice).map_err(cbor::Error::CBORParsing)?;
+
+  #[cfg(feature = "additional-controls")]
+  let mut cv = CBORValidator::new(&cddl, cbor, enabled_features);
+  #[cfg(not(feature = "additional-controls"))]
+  let mut cv = CBORValidator::new(&cddl, cbor);
+
+  cv.set_cbor_slice(cbor_slice);
+  cv.set_rule_labels(&rule_labels);
+  cv.validate()?;
+
+  Ok(cv.labeled_paths().to_vec())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "json")]
+/// Validate JSON string from a given CDDL document string and explain, for
+/// each location validated against a type choice, which choice matched and why
+/// the choices preceding it did not
+pub fn explain_json_from_str(
+  cddl: &str,
+  json: &str,
+  #[cfg(feature = "additional-controls")] enabled_features: Option<&[&str]>,
+) -> std::result::Result<Vec<ChoiceExplanation>, json::Error> {
+  let cddl = cddl_from_str(cddl, false).map_err(json::Error::CDDLParsing)?;
+  let json = serde_json::from_str::<serde_json::Value>(json).map_err(json::Error::JSONParsing)?;
+
+  #[cfg(feature = "additional-controls")]
+  let mut jv = JSONValidator::new(&cddl, json, enabled_features);
+  #[cfg(not(feature = "additional-controls"))]
+  let mut jv = JSONValidator::new(&cddl, json);
+
+  jv.set_explain_choices(true);
+  jv.validate()?;
+
+  Ok(jv.choice_explanations().to_vec())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(feature = "cbor")]
+/// Validate CBOR slice from a given CDDL document string and explain, for each
+/// location validated against a type choice, which choice matched and why the
+/// choices preceding it did not
+pub fn explain_cbor_from_slice(
+  cddl: &str,
+  cbor_slice: &[u8],
+  #[cfg(feature = "additional-controls")] enabled_features: Option<&[&str]>,
+) -> std::result::Result<Vec<ChoiceExplanation>, cbor::Error<ReaderError>> {
+  #[cfg(feature = "std")]
+  let cddl = cddl_from_str(cddl, false).map_err(cbor::Error::CDDLParsing)?;
+  #[cfg(not(feature = "std"))]
+  let cddl = cddl_from_str(cddl).map_err(cbor::Error::CDDLParsing)?;
+  let cbor: ciborium::value::Value =
+    ciborium::de::from_reader(cbor_slice).map_err(cbor::Error::CBORParsing)?;
+
+  #[cfg(feature = "additional-controls")]
+  let mut cv = CBORValidator::new(&cddl, cbor, enabled_features);
+  #[cfg(not(feature = "additional-controls"))]
+  let mut cv = CBORValidator::new(&cddl, cbor);
+
+  cv.set_cbor_slice(cbor_slice);
+  cv.set_explain_choices(true);
+  cv.validate()?;
+
+  Ok(cv.choice_explanations().to_vec())
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(feature = "cbor")]
 #[cfg(feature = "additional-controls")]
@@ -192,13 +567,17 @@ pub fn validate_cbor_from_slice(
   cddl: &str,
   cbor_slice: &[u8],
   enabled_features: Option<&[&str]>,
-) -> cbor::Result<std::io::Error> {
-  let cddl = cddl_from_str(cddl, true).map_err(cbor::Error::CDDLParsing)?;
+) -> cbor::Result<ReaderError> {
+  #[cfg(feature = "std")]
+  let cddl = cddl_from_str(cddl, false).map_err(cbor::Error::CDDLParsing)?;
+  #[cfg(not(feature = "std"))]
+  let cddl = cddl_from_str(cddl).map_err(cbor::Error::CDDLParsing)?;
 
   let cbor: ciborium::value::Value =
     ciborium::de::from_reader(cbor_slice).map_err(cbor::Error::CBORParsing)?;
 
   let mut cv = CBORValidator::new(&cddl, cbor, enabled_features);
+  cv.set_cbor_slice(cbor_slice);
   cv.validate()
 }
 
@@ -206,13 +585,16 @@ pub fn validate_cbor_from_slice(
 #[cfg(feature = "cbor")]
 #[cfg(not(feature = "additional-controls"))]
 /// Validate CBOR slice from a given CDDL document string
-pub fn validate_cbor_from_slice(cddl: &str, cbor_slice: &[u8]) -> cbor::Result<std::io::Error> {
-  let mut lexer = lexer_from_str(cddl);
-  let cddl = cddl_from_str(&mut lexer, cddl, true).map_err(cbor::Error::CDDLParsing)?;
+pub fn validate_cbor_from_slice(cddl: &str, cbor_slice: &[u8]) -> cbor::Result<ReaderError> {
+  #[cfg(feature = "std")]
+  let cddl = cddl_from_str(cddl, false).map_err(cbor::Error::CDDLParsing)?;
+  #[cfg(not(feature = "std"))]
+  let cddl = cddl_from_str(cddl).map_err(cbor::Error::CDDLParsing)?;
   let cbor: ciborium::value::Value =
     ciborium::de::from_reader(cbor_slice).map_err(cbor::Error::CBORParsing)?;
 
   let mut cv = CBORValidator::new(&cddl, cbor);
+  cv.set_cbor_slice(cbor_slice);
   cv.validate()
 }
 
@@ -254,6 +636,7 @@ pub fn validate_cbor_from_slice(
     ciborium::de::from_reader(cbor_slice).map_err(|e| JsValue::from(e.to_string()))?;
 
   let mut cv = CBORValidator::new(&c, cbor, enabled_features);
+  cv.set_cbor_slice(cbor_slice);
   cv.validate()
     .map_err(|e| JsValue::from(e.to_string()))
     .map(|_| JsValue::default())
@@ -296,6 +679,7 @@ pub fn validate_cbor_from_slice(
     ciborium::de::from_reader(cbor_slice).map_err(|e| JsValue::from(e.to_string()))?;
 
   let mut cv = CBORValidator::new(&c, cbor);
+  cv.set_cbor_slice(cbor_slice);
   cv.validate()
     .map_err(|e| JsValue::from(e.to_string()))
     .map(|_| JsValue::default())
@@ -440,16 +824,16 @@ pub fn type_rule_from_ident<'a>(cddl: &'a CDDL, ident: &Identifier) -> Option<&'
 }
 
 /// Retrieve the list of generic parameters for a given rule
-pub fn generic_params_from_rule<'a>(rule: &Rule<'a>) -> Option<Vec<&'a str>> {
+pub fn generic_params_from_rule<'a>(rule: &Rule<'a>) -> Option<Vec<Cow<'a, str>>> {
   match rule {
     Rule::Type { rule, .. } => rule
       .generic_params
       .as_ref()
-      .map(|gp| gp.params.iter().map(|gp| gp.param.ident).collect()),
+      .map(|gp| gp.params.iter().map(|gp| gp.param.ident.clone()).collect()),
     Rule::Group { rule, .. } => rule
       .generic_params
       .as_ref()
-      .map(|gp| gp.params.iter().map(|gp| gp.param.ident).collect()),
+      .map(|gp| gp.params.iter().map(|gp| gp.param.ident.clone()).collect()),
   }
 }
 
@@ -519,7 +903,7 @@ pub fn type_choices_from_group_choice<'a>(
 
 /// Is the given identifier associated with a null data type
 pub fn is_ident_null_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
-  if let Token::NULL | Token::NIL = lookup_ident(ident.ident) {
+  if let Token::NULL | Token::NIL = lookup_ident(&ident.ident) {
     return true;
   }
 
@@ -537,7 +921,7 @@ pub fn is_ident_null_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
 
 /// Is the given identifier associated with a boolean data type
 pub fn is_ident_bool_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
-  if let Token::BOOL = lookup_ident(ident.ident) {
+  if let Token::BOOL = lookup_ident(&ident.ident) {
     return true;
   }
 
@@ -555,13 +939,13 @@ pub fn is_ident_bool_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
 
 /// Does the given boolean identifier match the boolean value
 pub fn ident_matches_bool_value(cddl: &CDDL, ident: &Identifier, value: bool) -> bool {
-  if let Token::TRUE = lookup_ident(ident.ident) {
+  if let Token::TRUE = lookup_ident(&ident.ident) {
     if value {
       return true;
     }
   }
 
-  if let Token::FALSE = lookup_ident(ident.ident) {
+  if let Token::FALSE = lookup_ident(&ident.ident) {
     if !value {
       return true;
     }
@@ -581,7 +965,7 @@ pub fn ident_matches_bool_value(cddl: &CDDL, ident: &Identifier, value: bool) ->
 
 /// Is the given identifier associated with a URI data type
 pub fn is_ident_uri_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
-  if let Token::URI = lookup_ident(ident.ident) {
+  if let Token::URI = lookup_ident(&ident.ident) {
     return true;
   }
 
@@ -599,7 +983,7 @@ pub fn is_ident_uri_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
 
 /// Is the given identifier associated with a b64url data type
 pub fn is_ident_b64url_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
-  if let Token::B64URL = lookup_ident(ident.ident) {
+  if let Token::B64URL = lookup_ident(&ident.ident) {
     return true;
   }
 
@@ -617,7 +1001,7 @@ pub fn is_ident_b64url_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
 
 /// Is the given identifier associated with a tdate data type
 pub fn is_ident_tdate_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
-  if let Token::TDATE = lookup_ident(ident.ident) {
+  if let Token::TDATE = lookup_ident(&ident.ident) {
     return true;
   }
 
@@ -635,7 +1019,7 @@ pub fn is_ident_tdate_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
 
 /// Is the given identifier associated with a time data type
 pub fn is_ident_time_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
-  if let Token::TIME = lookup_ident(ident.ident) {
+  if let Token::TIME = lookup_ident(&ident.ident) {
     return true;
   }
 
@@ -664,7 +1048,7 @@ pub fn is_ident_numeric_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
   | Token::FLOAT64
   | Token::FLOAT1632
   | Token::FLOAT3264
-  | Token::UNSIGNED = lookup_ident(ident.ident)
+  | Token::UNSIGNED = lookup_ident(&ident.ident)
   {
     return true;
   }
@@ -683,7 +1067,7 @@ pub fn is_ident_numeric_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
 
 /// Is the given identifier associated with a uint data type
 pub fn is_ident_uint_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
-  if let Token::UINT = lookup_ident(ident.ident) {
+  if let Token::UINT = lookup_ident(&ident.ident) {
     return true;
   }
 
@@ -701,7 +1085,7 @@ pub fn is_ident_uint_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
 
 /// Is the given identifier associated with a nint data type
 pub fn is_ident_nint_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
-  if let Token::NINT = lookup_ident(ident.ident) {
+  if let Token::NINT = lookup_ident(&ident.ident) {
     return true;
   }
 
@@ -720,7 +1104,7 @@ pub fn is_ident_nint_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
 /// Is the given identifier associated with an integer data type
 pub fn is_ident_integer_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
   if let Token::INT | Token::INTEGER | Token::NINT | Token::UINT | Token::NUMBER | Token::UNSIGNED =
-    lookup_ident(ident.ident)
+    lookup_ident(&ident.ident)
   {
     return true;
   }
@@ -740,11 +1124,12 @@ pub fn is_ident_integer_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
 /// Is the given identifier associated with a float data type
 pub fn is_ident_float_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
   if let Token::FLOAT
+  | Token::NUMBER
   | Token::FLOAT16
   | Token::FLOAT1632
   | Token::FLOAT32
   | Token::FLOAT3264
-  | Token::FLOAT64 = lookup_ident(ident.ident)
+  | Token::FLOAT64 = lookup_ident(&ident.ident)
   {
     return true;
   }
@@ -763,7 +1148,7 @@ pub fn is_ident_float_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
 
 /// Is the given identifier associated with a string data type
 pub fn is_ident_string_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
-  if let Token::TEXT | Token::TSTR = lookup_ident(ident.ident) {
+  if let Token::TEXT | Token::TSTR = lookup_ident(&ident.ident) {
     return true;
   }
 
@@ -781,7 +1166,7 @@ pub fn is_ident_string_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
 
 /// Is the given identifier associated with the any type
 pub fn is_ident_any_type(cddl: &CDDL, ident: &Identifier) -> bool {
-  if let Token::ANY = lookup_ident(ident.ident) {
+  if let Token::ANY = lookup_ident(&ident.ident) {
     return true;
   }
 
@@ -799,7 +1184,7 @@ pub fn is_ident_any_type(cddl: &CDDL, ident: &Identifier) -> bool {
 
 /// Is the given identifier associated with a byte string data type
 pub fn is_ident_byte_string_data_type(cddl: &CDDL, ident: &Identifier) -> bool {
-  if let Token::BSTR | Token::BYTES = lookup_ident(ident.ident) {
+  if let Token::BSTR | Token::BYTES = lookup_ident(&ident.ident) {
     return true;
   }
 
@@ -930,6 +1315,17 @@ pub fn validate_array_occurrence<'de, T: Deserialize<'de>>(
   Ok((iter_items, allow_empty_array))
 }
 
+/// Whether or not a member key has a cut, given explicitly with `^` or implied
+/// by the colon shortcut. Once a key of a map matches a member key with a cut,
+/// the map entry must match the value of the group entry
+pub fn member_key_has_cut(member_key: &MemberKey) -> bool {
+  match member_key {
+    MemberKey::Type1 { is_cut, .. } => *is_cut,
+    MemberKey::Bareword { .. } | MemberKey::Value { .. } => true,
+    _ => false,
+  }
+}
+
 /// Retrieve number of group entries from a group choice. This is currently only
 /// used for determining map equality/inequality and for validating the number
 /// of entries in arrays, but may be useful in other contexts. The occurrence is
@@ -1024,6 +1420,7 @@ pub struct EntryCount {
 
 /// Regex needs to be formatted in a certain way so it can be parsed. See
 /// <https://github.com/anweiss/cddl/issues/67>
+#[cfg(feature = "std")]
 pub fn format_regex(input: &str) -> Option<String> {
   let mut formatted_regex = String::from(input);
   let mut unescape = Vec::new();
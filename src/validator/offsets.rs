@@ -0,0 +1,350 @@
+use std::convert::TryFrom;
+
+#[cfg(not(feature = "std"))]
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+/// Byte range of the data item at the given CBOR location, as reported by the
+/// CBOR validator, within the encoding of a CBOR data item. Locations are
+/// resolved as far as the encoding allows, so the range of the innermost item
+/// containing a location that doesn't exist in the encoding is returned.
+/// Returns `None` if the encoding is malformed. Use [`ItemOffsets`] to resolve
+/// many locations within the same encoding
+///
+/// # Example
+///
+/// ```
+/// use cddl::validator::offsets::byte_range;
+///
+/// // { "a": [1, 500] }
+/// let cbor = [0xa1, 0x61, 0x61, 0x82, 0x01, 0x19, 0x01, 0xf4];
+/// assert_eq!(byte_range(&cbor, r#"/"a"/1"#), Some((5, 8)));
+/// assert_eq!(byte_range(&cbor, ""), Some((0, 8)));
+/// ```
+pub fn byte_range(cbor_slice: &[u8], cbor_location: &str) -> Option<(usize, usize)> {
+  ItemOffsets::new(cbor_slice).map(|offsets| offsets.byte_range(cbor_location))
+}
+
+/// Byte ranges of the data items within the encoding of a CBOR data item,
+/// indexed in a single pass over the encoding
+#[derive(Clone, Debug)]
+pub struct ItemOffsets {
+  // Data items in the order of their encoding
+  items: Vec<Item>,
+}
+
+#[derive(Clone, Debug)]
+struct Item {
+  start: usize,
+  end: usize,
+  children: Children,
+}
+
+#[derive(Clone, Debug)]
+enum Children {
+  None,
+  Tagged(usize),
+  Array(Vec<usize>),
+  // Values indexed by the location segment of their key. Keys without a
+  // segment, i.e. other than text strings and integers, are skipped
+  Map {
+    values: BTreeMap<String, usize>,
+    key: Option<usize>,
+  },
+}
+
+// Container whose items are being indexed. `remaining` is `None` for
+// indefinite-length items
+struct Frame {
+  item: usize,
+  remaining: Option<u64>,
+}
+
+impl ItemOffsets {
+  /// Index the encoding of a CBOR data item. Bytes following the data item are
+  /// ignored. Returns `None` if the encoding is malformed
+  pub fn new(cbor_slice: &[u8]) -> Option<ItemOffsets> {
+    let mut items: Vec<Item> = Vec::new();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut offset = 0;
+
+    loop {
+      while let Some(frame) = stack.last() {
+        match frame.remaining {
+          Some(0) => {}
+          None if *cbor_slice.get(offset)? == 0xff => offset += 1,
+          _ => break,
+        }
+        items[frame.item].end = offset;
+        stack.pop();
+      }
+      if stack.is_empty() && !items.is_empty() {
+        return Some(ItemOffsets { items });
+      }
+
+      let idx = items.len();
+      if let Some(frame) = stack.last_mut() {
+        if let Some(remaining) = frame.remaining.as_mut() {
+          *remaining -= 1;
+        }
+
+        let parent = frame.item;
+        let key = match &items[parent].children {
+          Children::Map { key: Some(key), .. } => {
+            key_segment(cbor_slice, items[*key].start, items[*key].end)
+          }
+          _ => None,
+        };
+        match &mut items[parent].children {
+          Children::Tagged(child) => *child = idx,
+          Children::Array(children) => children.push(idx),
+          Children::Map {
+            values,
+            key: pending,
+          } => match pending.take() {
+            Some(_) => {
+              if let Some(key) = key {
+                values.entry(key).or_insert(idx);
+              }
+            }
+            None => *pending = Some(idx),
+          },
+          Children::None => {}
+        }
+      }
+
+      let header = Header::at(cbor_slice, offset)?;
+      let (children, remaining) = match (header.major, header.argument) {
+        (2, Some(len)) | (3, Some(len)) => {
+          let end = header.end.checked_add(usize::try_from(len).ok()?)?;
+          if end > cbor_slice.len() {
+            return None;
+          }
+
+          items.push(Item {
+            start: offset,
+            end,
+            children: Children::None,
+          });
+          offset = end;
+          continue;
+        }
+        (0, Some(_)) | (1, Some(_)) | (7, Some(_)) => {
+          items.push(Item {
+            start: offset,
+            end: header.end,
+            children: Children::None,
+          });
+          offset = header.end;
+          continue;
+        }
+        (4, len) => (Children::Array(Vec::new()), len),
+        (5, len) => (
+          Children::Map {
+            values: BTreeMap::new(),
+            key: None,
+          },
+          match len {
+            Some(len) => Some(len.checked_mul(2)?),
+            None => None,
+          },
+        ),
+        // Chunks of indefinite-length strings aren't addressed by locations
+        (2, None) | (3, None) => (Children::None, None),
+        (6, Some(_)) => (Children::Tagged(idx + 1), Some(1)),
+        _ => return None,
+      };
+
+      items.push(Item {
+        start: offset,
+        end: 0,
+        children,
+      });
+      stack.push(Frame {
+        item: idx,
+        remaining,
+      });
+      offset = header.end;
+    }
+  }
+
+  /// Byte range of the data item at the given CBOR location, resolved as
+  /// described for [`byte_range`]
+  pub fn byte_range(&self, cbor_location: &str) -> (usize, usize) {
+    let mut item = &self.items[0];
+    let mut location = cbor_location;
+    loop {
+      let rest = match location.strip_prefix('/') {
+        Some(rest) if !rest.is_empty() => rest,
+        _ => return (item.start, item.end),
+      };
+
+      let child = match &item.children {
+        // Tags don't contribute a segment to the location
+        Children::Tagged(child) => Some((*child, location)),
+        Children::Array(children) => {
+          let (segment, rest) = split_segment(rest);
+          segment
+            .parse::<usize>()
+            .ok()
+            .and_then(|idx| children.get(idx))
+            .map(|child| (*child, rest))
+        }
+        Children::Map { values, .. } => {
+          let (segment, rest) = split_segment(rest);
+          values.get(segment).map(|child| (*child, rest))
+        }
+        Children::None => None,
+      };
+
+      match child {
+        Some((child, child_location)) => {
+          item = &self.items[child];
+          location = child_location;
+        }
+        None => return (item.start, item.end),
+      }
+    }
+  }
+
+  /// Byte range of the data item starting at the given offset, if any
+  pub fn item_range(&self, start: usize) -> Option<(usize, usize)> {
+    self
+      .items
+      .binary_search_by_key(&start, |item| item.start)
+      .ok()
+      .map(|idx| (self.items[idx].start, self.items[idx].end))
+  }
+}
+
+// First segment of a location and the rest of the location. Text string keys
+// are formatted with their escapes, so they may contain slashes
+fn split_segment(location: &str) -> (&str, &str) {
+  let end = if location.starts_with('"') {
+    let mut escaped = false;
+    location
+      .char_indices()
+      .skip(1)
+      .find(|(_, c)| match (escaped, c) {
+        (false, '"') => true,
+        (false, '\\') => {
+          escaped = true;
+          false
+        }
+        _ => {
+          escaped = false;
+          false
+        }
+      })
+      .map(|(idx, _)| idx + 1)
+  } else {
+    location.find('/')
+  };
+
+  location.split_at(end.unwrap_or(location.len()))
+}
+
+// Initial byte and argument of a data item. The argument is `None` for
+// indefinite-length items
+struct Header {
+  major: u8,
+  argument: Option<u64>,
+  end: usize,
+}
+
+impl Header {
+  fn at(input: &[u8], start: usize) -> Option<Header> {
+    let initial = *input.get(start)?;
+    let (major, info) = (initial >> 5, initial & 0x1f);
+
+    let (argument, end) = match info {
+      0..=23 => (Some(info as u64), start + 1),
+      24..=27 => {
+        let len = 1 << (info - 24);
+        let bytes = input.get(start + 1..start + 1 + len)?;
+        (
+          Some(bytes.iter().fold(0, |value, b| (value << 8) | *b as u64)),
+          start + 1 + len,
+        )
+      }
+      31 => (None, start + 1),
+      _ => return None,
+    };
+
+    Some(Header {
+      major,
+      argument,
+      end,
+    })
+  }
+}
+
+// Location segment of a map key, formatted as by the CBOR validator, i.e. text
+// strings as escaped in double quotes and integers in decimal
+fn key_segment(input: &[u8], start: usize, end: usize) -> Option<String> {
+  let header = Header::at(input, start)?;
+  match (header.major, header.argument) {
+    (0, Some(value)) => Some(format!("{}", value)),
+    (1, Some(value)) => Some(format!("{}", -1 - value as i128)),
+    (3, Some(_)) => std::str::from_utf8(&input[header.end..end])
+      .ok()
+      .map(|s| format!("{:?}", s)),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn verify_byte_range() {
+    // { "a": [1, { -2: 6("x") }], "b/c": h'0102' }
+    let cbor = [
+      0xa2, 0x61, 0x61, 0x82, 0x01, 0xa1, 0x21, 0xc6, 0x61, 0x78, 0x63, 0x62, 0x2f, 0x63, 0x42,
+      0x01, 0x02,
+    ];
+
+    assert_eq!(byte_range(&cbor, ""), Some((0, 17)));
+    assert_eq!(byte_range(&cbor, r#"/"a""#), Some((3, 10)));
+    assert_eq!(byte_range(&cbor, r#"/"a"/0"#), Some((4, 5)));
+    assert_eq!(byte_range(&cbor, r#"/"a"/1/-2"#), Some((7, 10)));
+    assert_eq!(byte_range(&cbor, r#"/"b/c""#), Some((14, 17)));
+
+    // Locations missing from the encoding resolve to the innermost item
+    assert_eq!(byte_range(&cbor, r#"/"a"/2"#), Some((3, 10)));
+    assert_eq!(byte_range(&cbor, r#"/"z"/0"#), Some((0, 17)));
+
+    // [_ 1, [2]]
+    let cbor = [0x9f, 0x01, 0x81, 0x02, 0xff];
+    assert_eq!(byte_range(&cbor, "/1/0"), Some((3, 4)));
+    assert_eq!(byte_range(&cbor, "/2"), Some((0, 5)));
+
+    assert_eq!(byte_range(&[0x82, 0x01], ""), None);
+  }
+
+  #[test]
+  fn verify_item_offsets() {
+    // { "a\"/": [1, 2], "b": 3 }
+    let cbor = [
+      0xa2, 0x63, 0x61, 0x22, 0x2f, 0x82, 0x01, 0x02, 0x61, 0x62, 0x03,
+    ];
+    let offsets = ItemOffsets::new(&cbor).unwrap();
+    assert_eq!(offsets.byte_range(&format!("/{:?}/1", "a\"/")), (7, 8));
+    assert_eq!(offsets.byte_range(r#"/"b""#), (10, 11));
+    assert_eq!(offsets.item_range(5), Some((5, 8)));
+    assert_eq!(offsets.item_range(6), Some((6, 7)));
+    assert_eq!(offsets.item_range(2), None);
+
+    // Deeply nested items are indexed without recursion
+    let mut cbor = vec![0x81; 1 << 20];
+    cbor.push(0x00);
+    let offsets = ItemOffsets::new(&cbor).unwrap();
+    assert_eq!(
+      offsets.byte_range(&"/0".repeat(1 << 20)),
+      (1 << 20, (1 << 20) + 1)
+    );
+    assert_eq!(offsets.byte_range("/0/1"), (1, (1 << 20) + 1));
+  }
+}
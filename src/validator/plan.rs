@@ -0,0 +1,464 @@
+use crate::ast::{
+  Group, GroupChoice, GroupEntry, Identifier, Occur, Occurrence, Type, Type2, CDDL,
+};
+
+use super::group_rule_from_ident;
+
+use std::fmt;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+// Upper bound on the number of states in a plan. Groups whose occurrence
+// indicators expand beyond it, e.g. `[1000*2000 int]`, aren't compiled
+const MAX_STATES: usize = 1024;
+
+// Upper bound on the nesting of group rules inlined into a plan. Guards against
+// recursive group rules
+const MAX_DEPTH: usize = 16;
+
+// Marks the absence of a previous item state
+const NO_STATE: usize = usize::MAX;
+
+/// What a single array item is matched against
+#[derive(Debug)]
+pub enum Matcher<'a, 'b> {
+  /// Entry type of a value group entry. Member keys are annotation only in an
+  /// array context
+  Type(&'b Type<'a>),
+  /// Type name of a type/group name entry
+  Name(&'b Identifier<'a>),
+}
+
+impl<'a, 'b> Matcher<'a, 'b> {
+  fn is_same(&self, other: &Matcher<'a, 'b>) -> bool {
+    match (self, other) {
+      (Matcher::Type(t), Matcher::Type(other)) => std::ptr::eq(*t, *other),
+      (Matcher::Name(ident), Matcher::Name(other)) => std::ptr::eq(*ident, *other),
+      _ => false,
+    }
+  }
+}
+
+impl<'a, 'b> fmt::Display for Matcher<'a, 'b> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Matcher::Type(t) => write!(f, "{}", t),
+      Matcher::Name(ident) => write!(f, "{}", ident),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+  // Consume an item that satisfies the matcher, then continue at `next`
+  Item { matcher: usize, next: usize },
+  // Continue at either state without consuming an item. The first state is
+  // preferred when recording which matcher an item was matched against
+  Split(usize, usize),
+  Accept,
+}
+
+/// Outcome of matching the items of an array against a plan
+#[derive(Debug, PartialEq)]
+pub enum PlanOutcome {
+  /// Every item matched. Holds the index of the matcher each item was matched
+  /// against
+  Matched(Vec<usize>),
+  /// The item at `index` didn't satisfy any of the candidate matchers. No
+  /// candidates means the group doesn't allow any more items
+  Unexpected {
+    /// Index of the item
+    index: usize,
+    /// Indices of the matchers the item was checked against
+    candidates: Vec<usize>,
+  },
+  /// The array ended while the group still expected an item satisfying one of
+  /// the given matchers
+  Incomplete(Vec<usize>),
+}
+
+/// Array group compiled into a nondeterministic finite automaton over the
+/// array items. Group choices become alternations and occurrence indicators
+/// become loops, so the automaton is simulated in a single pass over the items
+/// and each item is checked at most once per matcher rather than re-tried for
+/// every way the preceding items could have been grouped
+#[derive(Debug)]
+pub struct ArrayPlan<'a, 'b> {
+  matchers: Vec<Matcher<'a, 'b>>,
+  states: Vec<State>,
+  start: usize,
+}
+
+impl<'a, 'b> ArrayPlan<'a, 'b> {
+  /// Compile the group of an array. Returns `None` for groups that have no
+  /// plan, i.e. those with generic arguments, unwrapped types or recursive
+  /// group rules, which are left to the entry-by-entry validation
+  pub fn compile(cddl: &'a CDDL<'a>, group: &'b Group<'a>) -> Option<Self> {
+    let mut plan = ArrayPlan {
+      matchers: Vec::new(),
+      states: vec![State::Accept],
+      start: 0,
+    };
+
+    plan.start = plan.group(cddl, group, 0, 0)?;
+
+    Some(plan)
+  }
+
+  /// Item matcher with the given index
+  pub fn matcher(&self, idx: usize) -> &Matcher<'a, 'b> {
+    &self.matchers[idx]
+  }
+
+  /// Number of item matchers
+  #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+  pub fn matcher_count(&self) -> usize {
+    self.matchers.len()
+  }
+
+  /// Match `len` items, where `matches` reports whether the item at the given
+  /// index satisfies the matcher at the given index
+  pub fn run<E>(
+    &self,
+    len: usize,
+    mut matches: impl FnMut(usize, usize) -> std::result::Result<bool, E>,
+  ) -> std::result::Result<PlanOutcome, E> {
+    // States reachable before each item, each paired with the item state that
+    // consumed the previous item
+    let mut trail = Vec::with_capacity(len);
+    let mut current = self.closure(&[(self.start, NO_STATE)]);
+    let mut memo = vec![None; self.matchers.len()];
+
+    for idx in 0..len {
+      memo.iter_mut().for_each(|m| *m = None);
+
+      let mut candidates = Vec::new();
+      let mut targets = Vec::new();
+      for &(state, _) in current.iter() {
+        if let State::Item { matcher, next } = self.states[state] {
+          let matched = match memo[matcher] {
+            Some(matched) => matched,
+            None => {
+              let matched = matches(idx, matcher)?;
+              memo[matcher] = Some(matched);
+              candidates.push(matcher);
+              matched
+            }
+          };
+
+          if matched {
+            targets.push((next, state));
+          }
+        }
+      }
+
+      if targets.is_empty() {
+        return Ok(PlanOutcome::Unexpected {
+          index: idx,
+          candidates,
+        });
+      }
+
+      trail.push(current);
+      current = self.closure(&targets);
+    }
+
+    let mut state = match current
+      .iter()
+      .find(|(state, _)| matches!(self.states[*state], State::Accept))
+    {
+      Some((_, from)) => *from,
+      None => {
+        let mut expected = Vec::new();
+        for &(state, _) in current.iter() {
+          if let State::Item { matcher, .. } = self.states[state] {
+            if !expected.contains(&matcher) {
+              expected.push(matcher);
+            }
+          }
+        }
+
+        return Ok(PlanOutcome::Incomplete(expected));
+      }
+    };
+
+    // Walk back from the accepting state to find the matcher each item was
+    // matched against
+    let mut path = vec![0; len];
+    for idx in (0..len).rev() {
+      if let State::Item { matcher, .. } = self.states[state] {
+        path[idx] = matcher;
+      }
+
+      state = trail[idx]
+        .iter()
+        .find(|(s, _)| *s == state)
+        .map_or(NO_STATE, |(_, from)| *from);
+    }
+
+    Ok(PlanOutcome::Matched(path))
+  }
+
+  // States reachable from the given states without consuming an item, each
+  // paired with the item state that consumed the previous item
+  fn closure(&self, states: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut visited = vec![false; self.states.len()];
+    let mut closure = Vec::new();
+    let mut stack = states.iter().rev().cloned().collect::<Vec<_>>();
+
+    while let Some((state, from)) = stack.pop() {
+      if visited[state] {
+        continue;
+      }
+      visited[state] = true;
+
+      match self.states[state] {
+        State::Split(first, second) => {
+          stack.push((second, from));
+          stack.push((first, from));
+        }
+        _ => closure.push((state, from)),
+      }
+    }
+
+    closure
+  }
+
+  fn push(&mut self, state: State) -> Option<usize> {
+    if self.states.len() >= MAX_STATES {
+      return None;
+    }
+
+    self.states.push(state);
+
+    Some(self.states.len() - 1)
+  }
+
+  // Copies of a node unrolled from an occurrence indicator share their
+  // matchers, so an item is checked against each entry only once
+  fn item(&mut self, matcher: Matcher<'a, 'b>, next: usize) -> Option<usize> {
+    let matcher = match self.matchers.iter().position(|m| m.is_same(&matcher)) {
+      Some(idx) => idx,
+      None => {
+        self.matchers.push(matcher);
+        self.matchers.len() - 1
+      }
+    };
+
+    self.push(State::Item { matcher, next })
+  }
+
+  // Each of the following compiles an AST node such that matching continues at
+  // `next` once the node is matched, and returns the state matching starts at
+
+  fn group(
+    &mut self,
+    cddl: &'a CDDL<'a>,
+    group: &'b Group<'a>,
+    next: usize,
+    depth: usize,
+  ) -> Option<usize> {
+    let mut starts = Vec::with_capacity(group.group_choices.len());
+    for gc in group.group_choices.iter() {
+      starts.push(self.group_choice(cddl, gc, next, depth)?);
+    }
+
+    let mut start = starts.pop()?;
+    while let Some(choice) = starts.pop() {
+      start = self.push(State::Split(choice, start))?;
+    }
+
+    Some(start)
+  }
+
+  fn group_choice(
+    &mut self,
+    cddl: &'a CDDL<'a>,
+    gc: &'b GroupChoice<'a>,
+    next: usize,
+    depth: usize,
+  ) -> Option<usize> {
+    let mut start = next;
+    for (ge, _) in gc.group_entries.iter().rev() {
+      start = self.group_entry(cddl, ge, start, depth)?;
+    }
+
+    Some(start)
+  }
+
+  fn group_entry(
+    &mut self,
+    cddl: &'a CDDL<'a>,
+    ge: &'b GroupEntry<'a>,
+    next: usize,
+    depth: usize,
+  ) -> Option<usize> {
+    match ge {
+      GroupEntry::ValueMemberKey { ge, .. } => {
+        if ge
+          .entry_type
+          .type_choices
+          .iter()
+          .any(|tc| match &tc.type1.type2 {
+            Type2::Unwrap { .. } => true,
+            Type2::Typename { ident, .. } => group_rule_from_ident(cddl, ident).is_some(),
+            _ => false,
+          })
+        {
+          return None;
+        }
+
+        self.repeat(ge.occur.as_ref(), next, |plan, next| {
+          plan.item(Matcher::Type(&ge.entry_type), next)
+        })
+      }
+      GroupEntry::TypeGroupname { ge, .. } => {
+        if ge.generic_args.is_some() {
+          return None;
+        }
+
+        match group_rule_from_ident(cddl, &ge.name) {
+          Some(gr) => {
+            if gr.generic_params.is_some() || depth >= MAX_DEPTH {
+              return None;
+            }
+
+            self.repeat(ge.occur.as_ref(), next, |plan, next| {
+              plan.group_entry(cddl, &gr.entry, next, depth + 1)
+            })
+          }
+          None => self.repeat(ge.occur.as_ref(), next, |plan, next| {
+            plan.item(Matcher::Name(&ge.name), next)
+          }),
+        }
+      }
+      GroupEntry::InlineGroup { occur, group, .. } => {
+        self.repeat(occur.as_ref(), next, |plan, next| {
+          plan.group(cddl, group, next, depth)
+        })
+      }
+    }
+  }
+
+  // Repeat the node compiled by `node` per the occurrence indicator. Bounded
+  // occurrences are unrolled into required and optional copies of the node,
+  // and unbounded ones loop back to a split between another copy and `next`
+  fn repeat(
+    &mut self,
+    occur: Option<&Occurrence>,
+    next: usize,
+    mut node: impl FnMut(&mut Self, usize) -> Option<usize>,
+  ) -> Option<usize> {
+    let (lower, upper) = match occur.map(|o| &o.occur) {
+      None => (1, Some(1)),
+      #[cfg(feature = "ast-span")]
+      Some(Occur::Optional(_)) => (0, Some(1)),
+      #[cfg(not(feature = "ast-span"))]
+      Some(Occur::Optional) => (0, Some(1)),
+      #[cfg(feature = "ast-span")]
+      Some(Occur::ZeroOrMore(_)) => (0, None),
+      #[cfg(not(feature = "ast-span"))]
+      Some(Occur::ZeroOrMore) => (0, None),
+      #[cfg(feature = "ast-span")]
+      Some(Occur::OneOrMore(_)) => (1, None),
+      #[cfg(not(feature = "ast-span"))]
+      Some(Occur::OneOrMore) => (1, None),
+      Some(Occur::Exact { lower, upper, .. }) => (lower.unwrap_or(0), *upper),
+    };
+
+    let mut start = match upper {
+      Some(upper) if upper < lower => return None,
+      Some(upper) => {
+        let mut start = next;
+        for _ in lower..upper {
+          let copy = node(self, start)?;
+          start = self.push(State::Split(copy, next))?;
+        }
+
+        start
+      }
+      None => {
+        let split = self.push(State::Split(next, next))?;
+        let copy = node(self, split)?;
+        self.states[split] = State::Split(copy, next);
+
+        split
+      }
+    };
+
+    for _ in 0..lower {
+      start = node(self, start)?;
+    }
+
+    Some(start)
+  }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+  use super::*;
+  use crate::cddl_from_str;
+
+  #[test]
+  fn match_array_groups() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let cddl = cddl_from_str(
+      r#"
+        a = [* (key: int, value: tstr), ? bool]
+        b = [1*2 item, + tail]
+        item = (int // tstr)
+        tail = float
+      "#,
+      true,
+    )?;
+
+    // Items are given as the name of the single type they satisfy
+    let run = |rule: usize, items: &[&str]| {
+      let group = match &cddl.rules[rule] {
+        crate::ast::Rule::Type { rule, .. } => match &rule.value.type_choices[0].type1.type2 {
+          Type2::Array { group, .. } => group,
+          _ => unreachable!(),
+        },
+        _ => unreachable!(),
+      };
+
+      let plan = ArrayPlan::compile(&cddl, group).unwrap();
+      plan
+        .run(items.len(), |idx, matcher| {
+          Ok::<_, ()>(plan.matcher(matcher).to_string() == items[idx])
+        })
+        .unwrap()
+    };
+
+    assert!(matches!(run(0, &[]), PlanOutcome::Matched(_)));
+    assert!(matches!(
+      run(0, &["int", "tstr", "int", "tstr", "bool"]),
+      PlanOutcome::Matched(_)
+    ));
+    assert_eq!(
+      run(0, &["int", "tstr", "bool", "int"]),
+      PlanOutcome::Unexpected {
+        index: 3,
+        candidates: vec![]
+      }
+    );
+    assert!(matches!(
+      run(0, &["int", "int"]),
+      PlanOutcome::Unexpected { index: 1, .. }
+    ));
+    assert!(matches!(run(0, &["int"]), PlanOutcome::Incomplete(_)));
+
+    assert!(matches!(
+      run(1, &["tstr", "int", "tail", "tail"]),
+      PlanOutcome::Matched(_)
+    ));
+    assert!(matches!(
+      run(1, &["tstr", "int", "tstr", "tail"]),
+      PlanOutcome::Unexpected { index: 2, .. }
+    ));
+    assert!(matches!(run(1, &["int"]), PlanOutcome::Incomplete(_)));
+
+    Ok(())
+  }
+}
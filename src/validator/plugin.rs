@@ -0,0 +1,381 @@
+#![cfg(feature = "control-plugins")]
+
+use std::{collections::HashMap, convert::TryFrom, fs, path::Path};
+
+use displaydoc::Display;
+use wasmi::{
+  core::ValType, Config, Engine, ExternType, FuncType, Linker, Module, Store, StoreLimits,
+  StoreLimitsBuilder,
+};
+
+/// Version of the interface between the validator and control operator
+/// plugins, returned by a plugin's `cddl_abi_version` export
+pub const ABI_VERSION: i32 = 1;
+
+/// Default amount of fuel, i.e. roughly the number of instructions, a plugin
+/// may consume per invocation
+pub const DEFAULT_FUEL: u64 = 10_000_000;
+
+/// Default maximum size in bytes of a plugin's linear memory
+pub const DEFAULT_MEMORY_LIMIT: usize = 16 * 1024 * 1024;
+
+/// Alias for `Result` with an error of type `plugin::Error`
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Control operator plugin error
+#[derive(Debug, Display)]
+pub enum Error {
+  /// error reading control operator plugin: {0}
+  Io(std::io::Error),
+  /// error running control operator plugin: {0}
+  Wasm(wasmi::Error),
+  /// control operator plugin does not export {0} with the expected type
+  MissingExport(&'static str),
+  /// control operator plugin implements interface version {0}, expected version 1
+  AbiVersion(i32),
+  /// control operator plugin allocated out of bounds memory at {0}
+  Memory(i32),
+  /// control operator plugin returned {0}, expected 0 (invalid) or 1 (valid)
+  Verdict(i32),
+}
+
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Error::Io(error) => Some(error),
+      Error::Wasm(error) => Some(error),
+      _ => None,
+    }
+  }
+}
+
+impl From<wasmi::Error> for Error {
+  fn from(error: wasmi::Error) -> Self {
+    Error::Wasm(error)
+  }
+}
+
+/// Encoding of the data item handed to a control operator plugin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+  /// Data item encoded as CBOR
+  CBOR = 0,
+  /// Data item serialized as JSON text
+  JSON = 1,
+}
+
+/// Control operator implemented by a WebAssembly module
+///
+/// Plugins implement interface version 1 by exporting:
+///
+/// - `memory`, the linear memory the validator writes its inputs to
+/// - `cddl_abi_version: () -> i32`, returning `1`
+/// - `cddl_alloc: (len: i32) -> i32`, returning the offset of `len` bytes of
+///   memory available to the validator
+/// - `cddl_control: (encoding: i32, data: i32, data_len: i32, controller: i32,
+///   controller_len: i32) -> i32`, returning `1` if the data item satisfies
+///   the controller and `0` otherwise. `encoding` is `0` for CBOR and `1` for
+///   JSON, and `controller` is the controller of the control operator as CDDL
+///   text
+///
+/// Plugins may not import anything. Each invocation runs in a fresh instance
+/// of the module, bounded by an amount of fuel and a memory limit
+///
+/// # Example
+///
+/// ```
+/// use cddl::validator::plugin::{ControlPlugin, Encoding};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// // Accepts data items no longer than the controller
+/// let plugin = ControlPlugin::from_bytes(&wat::parse_str(
+///   r#"
+///     (module
+///       (memory (export "memory") 1)
+///       (global $next (mut i32) (i32.const 0))
+///       (func (export "cddl_abi_version") (result i32) i32.const 1)
+///       (func (export "cddl_alloc") (param $len i32) (result i32)
+///         global.get $next
+///         global.get $next
+///         local.get $len
+///         i32.add
+///         global.set $next)
+///       (func (export "cddl_control") (param i32 i32 i32 i32 i32) (result i32)
+///         local.get 2
+///         local.get 4
+///         i32.le_u))
+///   "#,
+/// )?)?;
+///
+/// assert!(plugin.validate(Encoding::JSON, br#""abc""#, r#""xyz""#)?);
+/// assert!(!plugin.validate(Encoding::JSON, br#""abcdef""#, r#""xyz""#)?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ControlPlugin {
+  engine: Engine,
+  module: Module,
+  fuel: u64,
+  memory_limit: usize,
+}
+
+impl ControlPlugin {
+  /// Load a plugin from the bytes of a WebAssembly module
+  pub fn from_bytes(wasm: &[u8]) -> Result<Self> {
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config);
+    let module = Module::new(&engine, wasm)?;
+
+    let i32_func = |params: &[ValType]| FuncType::new(params.to_vec(), [ValType::I32]);
+    let exports: [(&'static str, Option<FuncType>); 4] = [
+      ("memory", None),
+      ("cddl_abi_version", Some(i32_func(&[]))),
+      ("cddl_alloc", Some(i32_func(&[ValType::I32]))),
+      ("cddl_control", Some(i32_func(&[ValType::I32; 5]))),
+    ];
+    for (name, func_type) in exports.iter() {
+      let is_expected = match (module.get_export(name), func_type) {
+        (Some(ExternType::Memory(_)), None) => true,
+        (Some(ExternType::Func(ft)), Some(func_type)) => ft == *func_type,
+        _ => false,
+      };
+      if !is_expected {
+        return Err(Error::MissingExport(name));
+      }
+    }
+
+    let plugin = ControlPlugin {
+      engine,
+      module,
+      fuel: DEFAULT_FUEL,
+      memory_limit: DEFAULT_MEMORY_LIMIT,
+    };
+
+    let (mut store, instance) = plugin.instantiate()?;
+    let version = instance
+      .get_typed_func::<(), i32>(&store, "cddl_abi_version")?
+      .call(&mut store, ())?;
+    if version != ABI_VERSION {
+      return Err(Error::AbiVersion(version));
+    }
+
+    Ok(plugin)
+  }
+
+  /// Load a plugin from a WebAssembly module file
+  pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+    Self::from_bytes(&fs::read(path).map_err(Error::Io)?)
+  }
+
+  /// Set the amount of fuel, i.e. roughly the number of instructions, the
+  /// plugin may consume per invocation. Defaults to `DEFAULT_FUEL`
+  pub fn set_fuel(&mut self, fuel: u64) {
+    self.fuel = fuel;
+  }
+
+  /// Set the maximum size in bytes of the plugin's linear memory. Defaults to
+  /// `DEFAULT_MEMORY_LIMIT`
+  pub fn set_memory_limit(&mut self, memory_limit: usize) {
+    self.memory_limit = memory_limit;
+  }
+
+  /// Whether or not the encoded data item satisfies the controller, given as
+  /// CDDL text
+  pub fn validate(&self, encoding: Encoding, data: &[u8], controller: &str) -> Result<bool> {
+    let (mut store, instance) = self.instantiate()?;
+
+    let memory = instance
+      .get_memory(&store, "memory")
+      .ok_or(Error::MissingExport("memory"))?;
+    let alloc = instance.get_typed_func::<i32, i32>(&store, "cddl_alloc")?;
+    let control =
+      instance.get_typed_func::<(i32, i32, i32, i32, i32), i32>(&store, "cddl_control")?;
+
+    let write = |store: &mut Store<StoreLimits>, bytes: &[u8]| -> Result<(i32, i32)> {
+      let len = i32::try_from(bytes.len()).map_err(|_| Error::Memory(i32::MAX))?;
+      let ptr = alloc.call(&mut *store, len)?;
+      memory
+        .write(&mut *store, ptr as u32 as usize, bytes)
+        .map_err(|_| Error::Memory(ptr))?;
+      Ok((ptr, len))
+    };
+    let (data_ptr, data_len) = write(&mut store, data)?;
+    let (controller_ptr, controller_len) = write(&mut store, controller.as_bytes())?;
+
+    match control.call(
+      &mut store,
+      (
+        encoding as i32,
+        data_ptr,
+        data_len,
+        controller_ptr,
+        controller_len,
+      ),
+    )? {
+      0 => Ok(false),
+      1 => Ok(true),
+      verdict => Err(Error::Verdict(verdict)),
+    }
+  }
+
+  fn instantiate(&self) -> Result<(Store<StoreLimits>, wasmi::Instance)> {
+    let limits = StoreLimitsBuilder::new()
+      .memory_size(self.memory_limit)
+      .instances(1)
+      .build();
+    let mut store = Store::new(&self.engine, limits);
+    store.limiter(|limits| limits);
+    store.set_fuel(self.fuel).map_err(wasmi::Error::from)?;
+
+    let instance = Linker::new(&self.engine)
+      .instantiate(&mut store, &self.module)?
+      .start(&mut store)?;
+
+    Ok((store, instance))
+  }
+}
+
+/// Control operator plugins by the name of the control operator they implement
+///
+/// # Example
+///
+/// ```no_run
+/// use cddl::validator::{json::JSONValidator, plugin::ControlPlugins};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut plugins = ControlPlugins::default();
+/// plugins.load(".iban", "iban.wasm")?;
+///
+/// let cddl = cddl::parser::cddl_from_str("account = tstr .iban \"DE\"", true)?;
+/// let json = serde_json::from_str(r#""DE89370400440532013000""#)?;
+///
+/// let mut jv = JSONValidator::new(&cddl, json, None);
+/// jv.set_control_plugins(&plugins);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct ControlPlugins {
+  plugins: HashMap<String, ControlPlugin>,
+}
+
+impl ControlPlugins {
+  /// Register a plugin implementing the given control operator, with or
+  /// without its leading `.`
+  pub fn insert(&mut self, ctrl: &str, plugin: ControlPlugin) {
+    self.plugins.insert(normalize(ctrl), plugin);
+  }
+
+  /// Load a plugin implementing the given control operator from a WebAssembly
+  /// module file
+  pub fn load<P: AsRef<Path>>(&mut self, ctrl: &str, path: P) -> Result<()> {
+    self.insert(ctrl, ControlPlugin::from_file(path)?);
+
+    Ok(())
+  }
+
+  /// Plugin implementing the given control operator, if any
+  pub fn get(&self, ctrl: &str) -> Option<&ControlPlugin> {
+    self.plugins.get(&normalize(ctrl))
+  }
+}
+
+fn normalize(ctrl: &str) -> String {
+  if ctrl.starts_with('.') {
+    ctrl.to_string()
+  } else {
+    format!(".{}", ctrl)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    cddl_from_str,
+    validator::{cbor::CBORValidator, json::JSONValidator, Validator},
+  };
+
+  // Accepts data items no longer than the controller
+  const MAX_LEN_PLUGIN: &str = r#"
+    (module
+      (memory (export "memory") 1)
+      (global $next (mut i32) (i32.const 0))
+      (func (export "cddl_abi_version") (result i32) i32.const 1)
+      (func (export "cddl_alloc") (param $len i32) (result i32)
+        global.get $next
+        global.get $next
+        local.get $len
+        i32.add
+        global.set $next)
+      (func (export "cddl_control") (param i32 i32 i32 i32 i32) (result i32)
+        local.get 2
+        local.get 4
+        i32.le_u))
+  "#;
+
+  fn plugins() -> ControlPlugins {
+    let mut plugins = ControlPlugins::default();
+    plugins.insert(
+      "max-len",
+      ControlPlugin::from_bytes(&wat::parse_str(MAX_LEN_PLUGIN).unwrap()).unwrap(),
+    );
+    plugins
+  }
+
+  #[test]
+  fn verify_plugin_interface() {
+    assert!(matches!(
+      ControlPlugin::from_bytes(
+        &wat::parse_str(r#"(module (memory (export "memory") 1))"#).unwrap()
+      ),
+      Err(Error::MissingExport("cddl_abi_version"))
+    ));
+
+    let version_2 = MAX_LEN_PLUGIN.replace("(result i32) i32.const 1", "(result i32) i32.const 2");
+    assert!(matches!(
+      ControlPlugin::from_bytes(&wat::parse_str(version_2).unwrap()),
+      Err(Error::AbiVersion(2))
+    ));
+
+    let endless = MAX_LEN_PLUGIN.replace("local.get 2", "(loop br 0) local.get 2");
+    let plugin = ControlPlugin::from_bytes(&wat::parse_str(endless).unwrap()).unwrap();
+    assert!(matches!(
+      plugin.validate(Encoding::JSON, b"1", "1"),
+      Err(Error::Wasm(_))
+    ));
+  }
+
+  #[test]
+  fn validate_control_plugin() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let plugins = plugins();
+    let cddl = cddl_from_str(r#"name = tstr .max-len "xyz""#, true)?;
+
+    let mut jv = JSONValidator::new(&cddl, serde_json::json!("abc"), None);
+    jv.set_control_plugins(&plugins);
+    jv.validate()?;
+
+    let mut jv = JSONValidator::new(&cddl, serde_json::json!("abcdef"), None);
+    jv.set_control_plugins(&plugins);
+    assert!(jv.validate().is_err());
+
+    let mut jv = JSONValidator::new(&cddl, serde_json::json!(1), None);
+    jv.set_control_plugins(&plugins);
+    assert!(jv.validate().is_err());
+
+    let mut cv = CBORValidator::new(&cddl, ciborium::value::Value::Text("abcdef".into()), None);
+    cv.set_control_plugins(&plugins);
+    assert!(cv.validate().is_err());
+
+    let mut cv = CBORValidator::new(&cddl, ciborium::value::Value::Text("abc".into()), None);
+    cv.set_control_plugins(&plugins);
+    cv.validate()?;
+
+    let mut jv = JSONValidator::new(&cddl, serde_json::json!("abc"), None);
+    assert!(jv.validate().is_err());
+
+    Ok(())
+  }
+}
@@ -0,0 +1,241 @@
+#![cfg(target_arch = "wasm32")]
+#![cfg(feature = "json")]
+#![cfg(feature = "cbor")]
+
+use super::{compiled::CompiledCddl, *};
+use crate::{ast::Span, lexer::lexer_from_str};
+
+/// CDDL document parsed and compiled once, which JavaScript callers can use to
+/// validate many JSON documents and CBOR data items without reparsing the
+/// document
+///
+/// # Example
+///
+/// ```typescript
+/// import * as wasm from 'cddl';
+///
+/// const schema = new wasm.Schema(text);
+/// try {
+///   schema.validate_json(json, "person");
+/// } catch (errors) {
+///   for (const e of errors) {
+///     console.error(`${e.location} (${e.rule}): ${e.reason}`);
+///   }
+/// }
+/// ```
+#[wasm_bindgen]
+pub struct Schema {
+  // Borrows from `input`, so it is declared (and therefore dropped) first
+  compiled: CompiledCddl<'static>,
+  // Source text of the document, which is never mutated and whose heap
+  // allocation doesn't move while the schema is alive
+  #[allow(dead_code)]
+  input: Box<str>,
+}
+
+// Validation error reported to JavaScript callers
+#[derive(Serialize)]
+struct ValidationErrorObject {
+  // Reason the data is invalid
+  reason: String,
+  // Location in the data (in JSONPointer notation for JSON), empty at the root
+  location: String,
+  // Name of the innermost rule being validated, if any
+  rule: Option<String>,
+  // Span of the CDDL type or group entry being validated
+  span: Option<Span>,
+  // Human readable error message
+  message: String,
+}
+
+impl ValidationErrorObject {
+  // Error that isn't associated with a location in the data, e.g. a data
+  // parsing error
+  fn from_reason(reason: String) -> Self {
+    ValidationErrorObject {
+      message: reason.clone(),
+      reason,
+      location: String::new(),
+      rule: None,
+      span: None,
+    }
+  }
+}
+
+impl From<&json::ValidationError> for ValidationErrorObject {
+  fn from(e: &json::ValidationError) -> Self {
+    ValidationErrorObject {
+      reason: e.reason.clone(),
+      location: e.json_location.clone(),
+      rule: Some(e.cddl_location.clone()).filter(|r| !r.is_empty()),
+      #[cfg(feature = "ast-span")]
+      span: e.cddl_span,
+      #[cfg(not(feature = "ast-span"))]
+      span: None,
+      message: e.to_string(),
+    }
+  }
+}
+
+impl From<&cbor::ValidationError> for ValidationErrorObject {
+  fn from(e: &cbor::ValidationError) -> Self {
+    ValidationErrorObject {
+      reason: e.reason.clone(),
+      location: e.cbor_location.clone(),
+      rule: Some(e.cddl_location.clone()).filter(|r| !r.is_empty()),
+      #[cfg(feature = "ast-span")]
+      span: e.cddl_span,
+      #[cfg(not(feature = "ast-span"))]
+      span: None,
+      message: e.to_string(),
+    }
+  }
+}
+
+// Array of validation error objects thrown to JavaScript callers
+fn validation_errors(errors: Vec<ValidationErrorObject>) -> JsValue {
+  JsValue::from_serde(&errors).unwrap_or_else(|e| JsValue::from(e.to_string()))
+}
+
+#[wasm_bindgen]
+impl Schema {
+  /// Parse and compile a CDDL document string. Throws an array of parser
+  /// errors if the document is invalid
+  #[wasm_bindgen(constructor)]
+  pub fn new(cddl: &str) -> std::result::Result<Schema, JsValue> {
+    let input: Box<str> = cddl.into();
+    // SAFETY: the compiled document only borrows from the heap allocation of
+    // `input`, which is owned by the schema, never mutated and dropped after
+    // the compiled document
+    let source: &'static str = unsafe { &*(input.as_ref() as *const str) };
+
+    let mut p = Parser::new(source, Box::new(lexer_from_str(source).iter()))
+      .map_err(|e| JsValue::from(e.to_string()))?;
+    let c = match p.parse_cddl() {
+      Ok(c) if p.errors.is_empty() => c,
+      Ok(_) | Err(parser::Error::INCREMENTAL) if !p.errors.is_empty() => {
+        return Err(
+          JsValue::from_serde(
+            &p.errors
+              .iter()
+              .filter_map(|e| {
+                if let parser::Error::PARSER { position, msg } = e {
+                  Some(ParserError {
+                    position: *position,
+                    msg: msg.clone(),
+                  })
+                } else {
+                  None
+                }
+              })
+              .collect::<Vec<ParserError>>(),
+          )
+          .map_err(|e| JsValue::from(e.to_string()))?,
+        );
+      }
+      Ok(_) => return Err(JsValue::from(parser::Error::INCREMENTAL.to_string())),
+      Err(e) => return Err(JsValue::from(e.to_string())),
+    };
+
+    let compiled =
+      CompiledCddl::from_parsed(source, c).map_err(|e| JsValue::from(e.to_string()))?;
+
+    Ok(Schema { compiled, input })
+  }
+
+  /// Names of the rules defined by the document, which may be selected as the
+  /// root rule when validating
+  pub fn rule_names(&self) -> Box<[JsValue]> {
+    self
+      .compiled
+      .cddl()
+      .rules
+      .iter()
+      .map(|r| JsValue::from(r.name()))
+      .collect()
+  }
+
+  /// Validate a JSON string against the named rule, or the first type rule in
+  /// the document if no rule is given. Throws an array of error objects with
+  /// `reason`, `location`, `rule`, `span` and `message` properties if the
+  /// JSON is invalid. Enabled features only apply if the crate is built with
+  /// the `additional-controls` feature
+  pub fn validate_json(
+    &self,
+    json: &str,
+    root_rule: Option<String>,
+    enabled_features: Option<Box<[JsValue]>>,
+  ) -> std::result::Result<JsValue, JsValue> {
+    let json = serde_json::from_str::<serde_json::Value>(json).map_err(|e| {
+      validation_errors(vec![ValidationErrorObject::from_reason(format!(
+        "error parsing JSON: {}",
+        e
+      ))])
+    })?;
+
+    #[cfg(feature = "additional-controls")]
+    let mut jv = JSONValidator::new(self.compiled.cddl(), json, enabled_features);
+    #[cfg(not(feature = "additional-controls"))]
+    let mut jv = {
+      let _ = enabled_features;
+      JSONValidator::new(self.compiled.cddl(), json)
+    };
+
+    jv.set_compiled(&self.compiled);
+    if let Some(rule) = &root_rule {
+      jv.set_root_rule(rule, None);
+    }
+
+    match jv.validate() {
+      Ok(()) => Ok(JsValue::default()),
+      Err(json::Error::Validation(errors)) => Err(validation_errors(
+        errors.iter().map(ValidationErrorObject::from).collect(),
+      )),
+      Err(e) => Err(validation_errors(vec![ValidationErrorObject::from_reason(
+        e.to_string(),
+      )])),
+    }
+  }
+
+  /// Validate a CBOR data item against the named rule, or the first type rule
+  /// in the document if no rule is given. Throws an array of error objects as
+  /// with `validate_json`
+  pub fn validate_cbor(
+    &self,
+    cbor_slice: &[u8],
+    root_rule: Option<String>,
+    enabled_features: Option<Box<[JsValue]>>,
+  ) -> std::result::Result<JsValue, JsValue> {
+    let cbor: ciborium::value::Value = ciborium::de::from_reader(cbor_slice).map_err(|e| {
+      validation_errors(vec![ValidationErrorObject::from_reason(format!(
+        "error parsing CBOR: {}",
+        e
+      ))])
+    })?;
+
+    #[cfg(feature = "additional-controls")]
+    let mut cv = CBORValidator::new(self.compiled.cddl(), cbor, enabled_features);
+    #[cfg(not(feature = "additional-controls"))]
+    let mut cv = {
+      let _ = enabled_features;
+      CBORValidator::new(self.compiled.cddl(), cbor)
+    };
+
+    cv.set_compiled(&self.compiled);
+    cv.set_cbor_slice(cbor_slice);
+    if let Some(rule) = &root_rule {
+      cv.set_root_rule(rule, None);
+    }
+
+    let result: cbor::Result<cbor::ReaderError> = cv.validate();
+    match result {
+      Ok(()) => Ok(JsValue::default()),
+      Err(cbor::Error::Validation(errors)) => Err(validation_errors(
+        errors.iter().map(ValidationErrorObject::from).collect(),
+      )),
+      Err(e) => Err(validation_errors(vec![ValidationErrorObject::from_reason(
+        e.to_string(),
+      )])),
+    }
+  }
+}
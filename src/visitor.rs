@@ -1,7 +1,11 @@
-#![cfg(feature = "std")]
-
 use crate::{ast::*, token::ByteValue, token::Value};
+
+#[cfg(feature = "std")]
 use std::error::Error;
+// `std::error::Error` isn't available in `no_std`, so visitor errors are only
+// required to be `Debug`
+#[cfg(not(feature = "std"))]
+use core::fmt::Debug as Error;
 
 /// Visitor result
 pub type Result<T> = std::result::Result<(), T>;
@@ -262,7 +266,13 @@ where
       visitor.visit_identifier(ident)
     }
     Type2::ChoiceFromInlineGroup { group, .. } => visitor.visit_group(group),
-    Type2::TaggedData { t, .. } => visitor.visit_type(t),
+    Type2::TaggedData { tag_type, t, .. } => {
+      if let Some(tag_type) = tag_type {
+        visitor.visit_type(tag_type)?;
+      }
+
+      visitor.visit_type(t)
+    }
     Type2::Typename { ident, .. } => visitor.visit_identifier(ident),
     Type2::Unwrap {
       generic_args,
@@ -179,6 +179,68 @@ fn validate_cbor_homogenous_array() {
   validate_cbor_from_slice(cddl_input, cbor::ARRAY_123, None).unwrap();
 }
 
+#[test]
+fn validate_cbor_array_group_choices() {
+  let items = |n: usize| {
+    let items = (0..n)
+      .map(|i| {
+        if i % 2 == 0 {
+          ciborium::value::Value::Integer((i as u64).into())
+        } else {
+          ciborium::value::Value::Text(i.to_string())
+        }
+      })
+      .collect::<Vec<_>>();
+
+    let mut cbor_bytes = Vec::new();
+    ciborium::ser::into_writer(&ciborium::value::Value::Array(items), &mut cbor_bytes).unwrap();
+    cbor_bytes
+  };
+
+  let cddl_input = r#"thing = [* (int // tstr)]"#;
+  validate_cbor_from_slice(cddl_input, &items(100_000), None).unwrap();
+  validate_cbor_from_slice(cddl_input, cbor::ARRAY_EMPTY, None).unwrap();
+
+  let cddl_input = r#"thing = [+ item]  item = (int // tstr)"#;
+  validate_cbor_from_slice(cddl_input, &items(7), None).unwrap();
+  validate_cbor_from_slice(cddl_input, cbor::ARRAY_EMPTY, None).unwrap_err();
+
+  let cddl_input = r#"thing = [* (key: int, value: tstr)]"#;
+  validate_cbor_from_slice(cddl_input, &items(8), None).unwrap();
+  validate_cbor_from_slice(cddl_input, &items(7), None).unwrap_err();
+
+  let cddl_input = r#"thing = [1*2 (key: int, value: tstr), ? int]"#;
+  validate_cbor_from_slice(cddl_input, &items(5), None).unwrap();
+  validate_cbor_from_slice(cddl_input, &items(6), None).unwrap_err();
+}
+
+#[test]
+fn explain_cbor_array_choices() {
+  let input = ciborium::value::Value::Array(vec![
+    ciborium::value::Value::Integer(1.into()),
+    ciborium::value::Value::Text("a".into()),
+    ciborium::value::Value::Bool(true),
+  ]);
+  let mut cbor_bytes = Vec::new();
+  ciborium::ser::into_writer(&input, &mut cbor_bytes).unwrap();
+
+  let explanations = cddl::validator::explain_cbor_from_slice(
+    r#"thing = [* item]  item = int / tstr / any"#,
+    &cbor_bytes,
+    None,
+  )
+  .unwrap();
+
+  assert_eq!(
+    explanations
+      .iter()
+      .map(|e| (e.location.as_str(), e.index, e.rejected.len()))
+      .collect::<Vec<_>>(),
+    vec![("/0", 0, 0), ("/1", 1, 1), ("/2", 2, 2)]
+  );
+  assert_eq!(explanations[2].choice, "any");
+}
+
 #[test]
 #[ignore] // FIXME: broken
 fn validate_cbor_array_groups() {
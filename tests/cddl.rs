@@ -34,3 +34,45 @@ fn verify_json_validation() -> json::Result {
     None,
   )
 }
+
+#[test]
+#[cfg(feature = "ast-serde")]
+fn verify_ast_serde_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+  use cddl::{ast::CDDL, validator::Validator};
+
+  for file in fs::read_dir("tests/fixtures/cddl/")? {
+    let path = file?.path();
+    if path.extension().unwrap() != "cddl" {
+      continue;
+    }
+
+    let file_content = fs::read_to_string(&path)?;
+    let cddl = parser::cddl_from_str(&file_content, true)?;
+
+    let serialized = serde_json::to_string(&cddl)?;
+    let deserialized: CDDL<'static> = serde_json::from_str(&serialized)?;
+
+    assert_eq!(deserialized, cddl, "{:?}", path);
+    assert_eq!(deserialized.to_string(), cddl.to_string(), "{:?}", path);
+  }
+
+  // Validating against the deserialized document behaves like validating
+  // against the freshly parsed one
+  let file_content = fs::read_to_string("tests/fixtures/cddl/reputon.cddl")?;
+  let cddl = parser::cddl_from_str(&file_content, true)?;
+  let deserialized: CDDL = serde_json::from_str(&serde_json::to_string(&cddl)?)?;
+
+  let valid = serde_json::from_str(&fs::read_to_string("tests/fixtures/json/reputon.json")?)?;
+  let invalid = serde_json::json!({ "application": 1, "reputons": [{ "rating": "a" }] });
+  for (json, is_valid) in [(valid, true), (invalid, false)].iter() {
+    let validate = |cddl| {
+      let mut jv = json::JSONValidator::new(cddl, json.clone(), None);
+      jv.validate().map_err(|e| e.to_string())
+    };
+
+    assert_eq!(validate(&cddl).is_ok(), *is_valid);
+    assert_eq!(validate(&deserialized), validate(&cddl));
+  }
+
+  Ok(())
+}